@@ -2,13 +2,17 @@
 //!
 //! ZERO dependencies. Pure Rust. CBMC-friendly.
 //!
-//! KEY DESIGN DECISION — u32 mirror types (PERC-761):
-//! Functions use u32 inputs / u64 intermediates. Production code uses u64/u128.
-//! This is valid because the arithmetic properties (conservation, monotonicity,
+//! KEY DESIGN DECISION — u32 instantiation (PERC-761):
+//! The functions under proof are NOT copies. `percolator-stake/src/lp_math.rs`
+//! is included by path and its `lp_math!` macro expanded with u32 inputs / u64
+//! intermediates; production expands the same macro at u64/u128. Narrowing the
+//! width is valid because the arithmetic properties (conservation, monotonicity,
 //! bounds) are SCALE-INVARIANT: if `a/b ≤ 1` holds for all u32 inputs, the
 //! algebraically identical formula holds for all u64 inputs (the inequality
-//! structure depends only on the ratio, not the magnitude). The mirror lets CBMC
+//! structure depends only on the ratio, not the magnitude). The narrow width lets CBMC
 //! model-check in <60s per proof vs minutes/hours for u64 bitvectors.
+//! `tests/proptest_kani_mirror.rs` in the main crate cross-checks this
+//! instantiation (and the model helpers) against the real u64 functions.
 //!
 //! SCALE-INVARIANCE ARGUMENT (informal): For anti-inflation — `back ≤ deposit` —
 //! the proof obligation is:
@@ -37,98 +41,38 @@
 //! Run one:   cargo kani --harness proof_deposit_withdraw_no_inflation_inductive
 
 // ═══════════════════════════════════════════════════════════════
-// LP Math (u32/u64 mirror of percolator-stake/src/math.rs)
-// Arithmetic is IDENTICAL — just narrower types for CBMC tractability.
+// LP Math — percolator-stake/src/lp_math.rs expanded at u32/u64/i64.
+// The SAME source math.rs expands at u64/u128/i128, so the proofs below
+// run on production code; there is no hand-copied mirror to drift.
 // ═══════════════════════════════════════════════════════════════
 
-/// N7 (CONSOLIDATED-PLAN §2.2): u32 mirror of `math::VIRTUAL_SHARES` /
-/// `math::VIRTUAL_ASSETS` — MUST stay numerically identical (value `1`) to the
-/// production constants in `percolator-stake/src/math.rs`, not just
-/// scale-mirrored, since the offset participates in the scale-invariance
-/// argument (module doc above) the same way for both widths.
-const VIRTUAL_SHARES: u64 = 1;
-const VIRTUAL_ASSETS: u64 = 1;
-
-/// LP tokens for deposit. First depositor: 1:1. Subsequent: pro-rata (floor),
-/// with the N7 virtual-offset applied to both sides of the ratio — mirrors
-/// `math::calc_lp_for_deposit` exactly (percolator-stake/src/math.rs).
-/// C9 fix: returns None when orphaned value exists (supply=0, value>0) or
-/// when pool is valueless but LP exists (supply>0, value=0).
-pub fn calc_lp_for_deposit(supply: u32, pool_value: u32, deposit: u32) -> Option<u32> {
-    if supply == 0 && pool_value == 0 {
-        Some(deposit) // True first depositor — 1:1 (offset cancels: VIRTUAL_SHARES == VIRTUAL_ASSETS)
-    } else if supply == 0 || pool_value == 0 {
-        None // Orphaned value or valueless LP — block deposits
-    } else {
-        let lp = (deposit as u64)
-            .checked_mul((supply as u64).checked_add(VIRTUAL_SHARES)?)?
-            .checked_div((pool_value as u64).checked_add(VIRTUAL_ASSETS)?)?;
-        // Mirror production overflow guard (production checks > u64::MAX)
-        if lp > u32::MAX as u64 {
-            None
-        } else {
-            Some(lp as u32)
-        }
-    }
-}
+#[path = "../../src/lp_math.rs"]
+#[macro_use]
+mod lp_math;
 
-/// Collateral for LP burn. floor(lp * (pool_value + VIRTUAL_ASSETS) / (supply +
-/// VIRTUAL_SHARES)) — N7 offset, mirrors `math::calc_collateral_for_withdraw`.
-pub fn calc_collateral_for_withdraw(supply: u32, pool_value: u32, lp: u32) -> Option<u32> {
-    if supply == 0 {
-        return None;
-    }
-    let col = (lp as u64)
-        .checked_mul((pool_value as u64).checked_add(VIRTUAL_ASSETS)?)?
-        .checked_div((supply as u64).checked_add(VIRTUAL_SHARES)?)?;
-    // Mirror production overflow guard (production checks > u64::MAX)
-    if col > u32::MAX as u64 {
-        None
-    } else {
-        Some(col as u32)
-    }
-}
+lp_math!(u32, u64, i64);
 
-/// Pool value = deposited - withdrawn.
-/// Mirrors StakePool::total_pool_value() after C4 fix.
-pub fn pool_value(deposited: u32, withdrawn: u32) -> Option<u32> {
-    deposited.checked_sub(withdrawn)
-}
+// ═══════════════════════════════════════════════════════════════
+// Model helpers — proof-side shorthands composed ONLY from the generated
+// functions above (or pure predicates over their results).
+// ═══════════════════════════════════════════════════════════════
 
-/// Full pool value with flush tracking and insurance returns.
-/// Mirrors StakePool::total_pool_value(): deposited - withdrawn - flushed + returned.
+/// Mode-0 pool value with no accrued fees and no realized junior loss:
+/// `total_pool_value(deposited, withdrawn, flushed, returned, 0, 0)`.
 pub fn pool_value_with_flush(
     deposited: u32,
     withdrawn: u32,
     flushed: u32,
     returned: u32,
 ) -> Option<u32> {
-    deposited
-        .checked_sub(withdrawn)?
-        .checked_sub(flushed)?
-        .checked_add(returned)
+    total_pool_value(deposited, withdrawn, flushed, returned, 0, 0)
 }
 
-/// Flush available = deposited - withdrawn - flushed (saturating).
+/// Flush capacity with nothing returned: `total_pool_value` floored at 0.
+/// (Production removed its own `flush_available()` in #200; FlushToInsurance
+/// caps against `total_pool_value()` directly.)
 pub fn flush_available(deposited: u32, withdrawn: u32, flushed: u32) -> u32 {
-    deposited.saturating_sub(withdrawn).saturating_sub(flushed)
-}
-
-/// Cooldown check: current_slot >= deposit_slot + cooldown_slots
-pub fn cooldown_elapsed(current_slot: u32, deposit_slot: u32, cooldown_slots: u32) -> bool {
-    current_slot >= deposit_slot.saturating_add(cooldown_slots)
-}
-
-/// Deposit cap check: returns true if deposit would exceed cap.
-/// Cap of 0 = uncapped.
-pub fn exceeds_cap(total_deposited: u32, new_deposit: u32, cap: u32) -> bool {
-    if cap == 0 {
-        return false;
-    }
-    match total_deposited.checked_add(new_deposit) {
-        Some(total) => total > cap,
-        None => true, // overflow = definitely exceeds
-    }
+    total_pool_value(deposited, withdrawn, flushed, 0, 0, 0).unwrap_or(0)
 }
 
 /// Pool invariant: supply == 0 iff pool_value == 0.
@@ -142,169 +86,6 @@ pub fn pool_inv(supply: u32, pv: u32) -> bool {
     (supply == 0) == (pv == 0)
 }
 
-// ═══════════════════════════════════════════════════════════════
-// Tranche math (u32/u64 mirror of percolator-stake/src/math.rs tranche
-// helpers and StakePool::{total_pool_value, effective_junior_balance,
-// senior_balance}). Same arithmetic, narrower types for CBMC tractability.
-//
-// The senior/junior deposit + withdraw helpers delegate to the GLOBAL calc_*
-// over their own SUB-pool (supply, balance) — exactly as production does — so
-// they inherit the C9 orphaned-value guard. There is no separate "bootstrap"
-// branch: a true first sub-pool depositor has sub_balance == 0 (→ 1:1), while
-// orphaned sub-pool value (sub_lp == 0, sub_balance > 0) is rejected.
-// ═══════════════════════════════════════════════════════════════
-
-/// Mirror of calc_senior_lp_for_deposit / calc_junior_lp_for_deposit
-/// (both delegate to calc_lp_for_deposit over the sub-pool supply/balance).
-pub fn calc_subpool_lp_for_deposit(sub_lp: u32, sub_balance: u32, deposit: u32) -> Option<u32> {
-    calc_lp_for_deposit(sub_lp, sub_balance, deposit)
-}
-
-/// Mirror of calc_senior/junior_collateral_for_withdraw (delegate to global).
-pub fn calc_subpool_collateral_for_withdraw(sub_lp: u32, sub_balance: u32, lp: u32) -> Option<u32> {
-    calc_collateral_for_withdraw(sub_lp, sub_balance, lp)
-}
-
-/// Mirror of distribute_loss: the junior tranche absorbs loss first, capped at
-/// the combined balance. Returns (junior_loss, senior_loss).
-pub fn distribute_loss(junior_balance: u32, senior_balance: u32, loss: u32) -> (u32, u32) {
-    let total = (junior_balance as u64).saturating_add(senior_balance as u64);
-    let capped = (loss as u64).min(total);
-    if capped <= junior_balance as u64 {
-        (capped as u32, 0)
-    } else {
-        let senior_loss = capped - junior_balance as u64;
-        (junior_balance, senior_loss as u32)
-    }
-}
-
-/// Mirror of distribute_fees (SIMPLE path). Weighted split: junior weight =
-/// junior_balance * mult_bps, senior weight = senior_balance * 10_000; junior_fee
-/// = floor(total_fee * junior_weight / total_weight), senior_fee = remainder.
-///
-/// Production's u128 overflow fallback (when total_fee * junior_weight exceeds
-/// u128) is unreachable at this mirror's bounded scale, so it is not modelled —
-/// the conservation/bounds invariants proven here are the same ones that fallback
-/// must preserve. Returns (junior_fee, senior_fee), summing to <= total_fee.
-pub fn distribute_fees(
-    junior_balance: u32,
-    senior_balance: u32,
-    junior_fee_mult_bps: u32,
-    total_fee: u32,
-) -> (u32, u32) {
-    if total_fee == 0 {
-        return (0, 0);
-    }
-    let jb = junior_balance as u64;
-    let sb = senior_balance as u64;
-    if jb + sb == 0 {
-        return (0, 0);
-    }
-    let junior_weight = jb * junior_fee_mult_bps as u64;
-    let senior_weight = sb * 10_000;
-    let total_weight = junior_weight + senior_weight;
-    if total_weight == 0 {
-        return (0, 0);
-    }
-    let junior_fee = ((total_fee as u64) * junior_weight / total_weight).min(total_fee as u64);
-    let senior_fee = (total_fee as u64).saturating_sub(junior_fee);
-    (junior_fee as u32, senior_fee as u32)
-}
-
-/// Mirror of StakePool::total_pool_value() for pool_mode 0 (insurance LP):
-/// deposited - withdrawn - flushed + returned.
-pub fn total_pool_value_mode0(deposited: u32, withdrawn: u32, flushed: u32, returned: u32) -> Option<u32> {
-    deposited
-        .checked_sub(withdrawn)?
-        .checked_sub(flushed)?
-        .checked_add(returned)
-}
-
-/// Mirror of StakePool::effective_junior_balance(): junior tranche balance after
-/// absorbing outstanding insurance loss (net_loss = flushed - returned) against
-/// the GROSS (pre-loss) balances.
-pub fn effective_junior_balance(
-    deposited: u32,
-    withdrawn: u32,
-    flushed: u32,
-    returned: u32,
-    junior_balance: u32,
-) -> u32 {
-    let jb = junior_balance;
-    let net_loss = flushed.saturating_sub(returned);
-    if net_loss == 0 {
-        return jb;
-    }
-    let gross_pool = deposited.saturating_sub(withdrawn);
-    let gross_senior = gross_pool.saturating_sub(jb);
-    let (junior_loss, _) = distribute_loss(jb, gross_senior, net_loss);
-    jb.saturating_sub(junior_loss)
-}
-
-/// Mirror of StakePool::senior_balance(): total_pool_value - effective_junior_balance.
-pub fn senior_balance(
-    deposited: u32,
-    withdrawn: u32,
-    flushed: u32,
-    returned: u32,
-    junior_balance: u32,
-) -> Option<u32> {
-    let pv = total_pool_value_mode0(deposited, withdrawn, flushed, returned)?;
-    pv.checked_sub(effective_junior_balance(deposited, withdrawn, flushed, returned, junior_balance))
-}
-
-/// Mirror of StakePool::total_pool_value() AFTER the issue-#161 fix: the value a
-/// later insurance return can re-credit is reduced by `realized_junior_loss` —
-/// the loss the junior tranche permanently FORFEITED when its last LP exited
-/// during an outstanding loss. Those recovered tokens become dead (unclaimable)
-/// value so the refund cannot windfall to the protected senior tranche.
-pub fn total_pool_value_mode0_rl(
-    deposited: u32,
-    withdrawn: u32,
-    flushed: u32,
-    returned: u32,
-    realized_junior_loss: u32,
-) -> Option<u32> {
-    total_pool_value_mode0(deposited, withdrawn, flushed, returned)?
-        .checked_sub(realized_junior_loss)
-}
-
-/// Mirror of StakePool::senior_balance() after the #161 fix (RL-aware pool value).
-pub fn senior_balance_rl(
-    deposited: u32,
-    withdrawn: u32,
-    flushed: u32,
-    returned: u32,
-    junior_balance: u32,
-    realized_junior_loss: u32,
-) -> Option<u32> {
-    let pv = total_pool_value_mode0_rl(deposited, withdrawn, flushed, returned, realized_junior_loss)?;
-    pv.checked_sub(effective_junior_balance(deposited, withdrawn, flushed, returned, junior_balance))
-}
-
-/// Mirror of StakePool::total_pool_value() AFTER the issue-#169 i128 fix: the mode-1
-/// (fee-inclusive, RL-aware) value summed in a wide SIGNED intermediate. In mode 1 a
-/// withdrawer's payout includes their fee share, so `withdrawn` can exceed `deposited`;
-/// the pre-fix left-to-right `deposited.checked_sub(withdrawn)` underflowed to None and
-/// bricked the pool. Summing signed makes order irrelevant — we fail closed only when the
-/// FINAL value is genuinely out of range (negative = insolvent, or > u32::MAX here).
-pub fn total_pool_value_mode1_rl_i128(
-    deposited: u32,
-    withdrawn: u32,
-    flushed: u32,
-    returned: u32,
-    fees: u32,
-    realized_junior_loss: u32,
-) -> Option<u32> {
-    let v = deposited as i64 - withdrawn as i64 - flushed as i64 + returned as i64 + fees as i64
-        - realized_junior_loss as i64;
-    if v < 0 || v > u32::MAX as i64 {
-        None
-    } else {
-        Some(v as u32)
-    }
-}
-
 // ═══════════════════════════════════════════════════════════════
 // KANI PROOFS — 54 harnesses (52 bounded + 2 INDUCTIVE §14)
 // PERC-783: kani::cover!() added to all symbolic proofs to guard
@@ -1611,7 +1392,7 @@ mod proofs {
     fn proof_distribute_fees_conservation() {
         let jb: u32 = kani::any();
         let sb: u32 = kani::any();
-        let mult: u32 = kani::any();
+        let mult: u16 = kani::any();
         let fee: u32 = kani::any();
         kani::assume(jb <= 0xFFFF && sb <= 0xFFFF && fee <= 0xFFFF);
         kani::assume(mult > 0 && mult <= 50_000); // production caps junior_fee_mult_bps
@@ -1619,10 +1400,16 @@ mod proofs {
         let (jf, sf) = distribute_fees(jb, sb, mult, fee);
         assert!(jf <= fee, "junior fee bounded by total");
         assert!(sf <= fee, "senior fee bounded by total");
-        assert!(jf as u64 + sf as u64 <= fee as u64, "fees never exceed total");
+        assert!(
+            jf as u64 + sf as u64 <= fee as u64,
+            "fees never exceed total"
+        );
         if fee > 0 && (jb > 0 || sb > 0) {
             kani::cover!(jf + sf == fee, "COVER: fee-conservation path is reachable");
-            assert!(jf as u64 + sf as u64 == fee as u64, "fee fully conserved when distributable");
+            assert!(
+                jf as u64 + sf as u64 == fee as u64,
+                "fee fully conserved when distributable"
+            );
         }
     }
 
@@ -1633,7 +1420,7 @@ mod proofs {
     #[kani::proof]
     fn proof_distribute_fees_no_senior_all_to_junior() {
         let jb: u32 = kani::any();
-        let mult: u32 = kani::any();
+        let mult: u16 = kani::any();
         let fee: u32 = kani::any();
         kani::assume(jb > 0 && jb <= 0xFFFF);
         kani::assume(fee > 0 && fee <= 0xFFFF);
@@ -1641,7 +1428,10 @@ mod proofs {
 
         let (jf, sf) = distribute_fees(jb, 0, mult, fee);
         kani::cover!(jf == fee, "COVER: all-fees-to-junior path is reachable");
-        assert!(jf == fee && sf == 0, "no senior => junior captures all fees");
+        assert!(
+            jf == fee && sf == 0,
+            "no senior => junior captures all fees"
+        );
     }
 
     /// C9 for a tranche sub-pool (the bootstrap-bypass fix): a deposit into a
@@ -1655,7 +1445,7 @@ mod proofs {
         kani::assume(dep > 0 && dep <= 0xFFFF);
 
         assert!(
-            calc_subpool_lp_for_deposit(0, bal, dep).is_none(),
+            calc_senior_lp_for_deposit(0, bal, dep).is_none(),
             "orphaned sub-pool value must block deposits (C9)"
         );
     }
@@ -1666,10 +1456,10 @@ mod proofs {
         let dep: u32 = kani::any();
         kani::assume(dep > 0 && dep <= 0xFFFF);
         kani::cover!(
-            calc_subpool_lp_for_deposit(0, 0, dep) == Some(dep),
+            calc_senior_lp_for_deposit(0, 0, dep) == Some(dep),
             "COVER: first-subpool-depositor 1:1 path is reachable"
         );
-        assert_eq!(calc_subpool_lp_for_deposit(0, 0, dep), Some(dep));
+        assert_eq!(calc_senior_lp_for_deposit(0, 0, dep), Some(dep));
     }
 
     /// Sub-pool deposit→withdraw round-trip cannot profit (senior and junior both
@@ -1684,15 +1474,18 @@ mod proofs {
         kani::assume(sub_bal > 0 && sub_bal <= 0xFFFF);
         kani::assume(dep > 0 && dep <= 0xFFFF);
 
-        let lp = match calc_subpool_lp_for_deposit(sub_lp, sub_bal, dep) {
+        let lp = match calc_senior_lp_for_deposit(sub_lp, sub_bal, dep) {
             Some(l) if l > 0 => l,
             _ => return,
         };
-        let back = match calc_subpool_collateral_for_withdraw(sub_lp + lp, sub_bal + dep, lp) {
+        let back = match calc_senior_collateral_for_withdraw(sub_lp + lp, sub_bal + dep, lp) {
             Some(v) => v,
             None => return,
         };
-        kani::cover!(back <= dep, "COVER: subpool round-trip no-profit path is reachable");
+        kani::cover!(
+            back <= dep,
+            "COVER: subpool round-trip no-profit path is reachable"
+        );
         assert!(back <= dep, "sub-pool deposit-then-withdraw cannot profit");
     }
 
@@ -1707,9 +1500,11 @@ mod proofs {
         let flush: u32 = kani::any();
         let ret: u32 = kani::any();
         let jb: u32 = kani::any();
-        kani::assume(dep <= 0xFFFF && wd <= 0xFFFF && flush <= 0xFFFF && ret <= 0xFFFF && jb <= 0xFFFF);
+        kani::assume(
+            dep <= 0xFFFF && wd <= 0xFFFF && flush <= 0xFFFF && ret <= 0xFFFF && jb <= 0xFFFF,
+        );
 
-        let pv = match total_pool_value_mode0(dep, wd, flush, ret) {
+        let pv = match total_pool_value(dep, wd, flush, ret, 0, 0) {
             Some(v) => v,
             None => return, // inconsistent accounting — not a reachable pool state
         };
@@ -1723,10 +1518,16 @@ mod proofs {
         kani::assume(jb <= gross_pool);
 
         let ejb = effective_junior_balance(dep, wd, flush, ret, jb);
-        kani::cover!(ejb <= pv, "COVER: effective_junior <= pool_value path is reachable");
-        assert!(ejb <= pv, "effective junior balance never exceeds pool value");
+        kani::cover!(
+            ejb <= pv,
+            "COVER: effective_junior <= pool_value path is reachable"
+        );
+        assert!(
+            ejb <= pv,
+            "effective junior balance never exceeds pool value"
+        );
         assert!(
-            senior_balance(dep, wd, flush, ret, jb).is_some(),
+            senior_balance(dep, wd, flush, ret, 0, 0, jb).is_some(),
             "senior_balance never underflows under the pool invariants"
         );
     }
@@ -1740,21 +1541,29 @@ mod proofs {
         let flush: u32 = kani::any();
         let ret: u32 = kani::any();
         let jb: u32 = kani::any();
-        kani::assume(dep <= 0xFFFF && wd <= 0xFFFF && flush <= 0xFFFF && ret <= 0xFFFF && jb <= 0xFFFF);
+        kani::assume(
+            dep <= 0xFFFF && wd <= 0xFFFF && flush <= 0xFFFF && ret <= 0xFFFF && jb <= 0xFFFF,
+        );
         kani::assume(ret <= flush);
         kani::assume(jb <= dep.saturating_sub(wd));
 
-        let pv = match total_pool_value_mode0(dep, wd, flush, ret) {
+        let pv = match total_pool_value(dep, wd, flush, ret, 0, 0) {
             Some(v) => v,
             None => return,
         };
         let ejb = effective_junior_balance(dep, wd, flush, ret, jb);
-        let sb = match senior_balance(dep, wd, flush, ret, jb) {
+        let sb = match senior_balance(dep, wd, flush, ret, 0, 0, jb) {
             Some(v) => v,
             None => return,
         };
-        kani::cover!(sb + ejb == pv, "COVER: tranche-decomposition path is reachable");
-        assert!(sb as u64 + ejb as u64 == pv as u64, "senior + effective_junior == pool value");
+        kani::cover!(
+            sb + ejb == pv,
+            "COVER: tranche-decomposition path is reachable"
+        );
+        assert!(
+            sb as u64 + ejb as u64 == pv as u64,
+            "senior + effective_junior == pool value"
+        );
     }
 
     /// ISSUE #169 — mode-1 pool value never falsely bricks; insolvency still fails closed.
@@ -1775,19 +1584,33 @@ mod proofs {
         let ret: u32 = kani::any();
         let fees: u32 = kani::any();
         let rl: u32 = kani::any();
-        kani::assume(dep <= 0xFFFF && wd <= 0xFFFF && flush <= 0xFFFF && ret <= 0xFFFF && fees <= 0xFFFF && rl <= 0xFFFF);
+        kani::assume(
+            dep <= 0xFFFF
+                && wd <= 0xFFFF
+                && flush <= 0xFFFF
+                && ret <= 0xFFFF
+                && fees <= 0xFFFF
+                && rl <= 0xFFFF,
+        );
 
-        let true_value = dep as i64 - wd as i64 - flush as i64 + ret as i64 + fees as i64 - rl as i64;
-        let got = total_pool_value_mode1_rl_i128(dep, wd, flush, ret, fees, rl);
+        let true_value =
+            dep as i64 - wd as i64 - flush as i64 + ret as i64 + fees as i64 - rl as i64;
+        let got = total_pool_value(dep, wd, flush, ret, fees, rl);
 
         if (0..=u32::MAX as i64).contains(&true_value) {
-            kani::cover!(wd > dep, "COVER: the fee-withdrawal (withdrawn>deposited) path is reachable");
+            kani::cover!(
+                wd > dep,
+                "COVER: the fee-withdrawal (withdrawn>deposited) path is reachable"
+            );
             assert!(
                 got == Some(true_value as u32),
                 "#169: returns the true value even when withdrawn>deposited — no false brick"
             );
         } else if true_value < 0 {
-            assert!(got.is_none(), "#169: genuine insolvency still fails closed (None)");
+            assert!(
+                got.is_none(),
+                "#169: genuine insolvency still fails closed (None)"
+            );
         }
     }
 
@@ -1827,7 +1650,7 @@ mod proofs {
         let withdrawn = junior_payout; // junior fully exits
         let returned_after_book = l; // total_returned += L
         let rl = l; // realized_junior_loss += L
-        // After booking, junior_balance == 0.
+                    // After booking, junior_balance == 0.
 
         // --- later permissionless ReturnInsurance of `r` ---
         // bounded by the still-outstanding loss (flushed - returned_after_book).
@@ -1840,12 +1663,15 @@ mod proofs {
         };
 
         // Senior balance with junior gone (junior_balance == 0) and RL booked.
-        let senior = match senior_balance_rl(deposited, withdrawn, nl, returned_final, 0, rl) {
+        let senior = match senior_balance(deposited, withdrawn, nl, returned_final, 0, rl, 0) {
             Some(v) => v,
             None => return, // inconsistent accounting — not a reachable state
         };
 
-        kani::cover!(senior == sp, "COVER: senior recovers exactly to principal (full senior-share return)");
+        kani::cover!(
+            senior == sp,
+            "COVER: senior recovers exactly to principal (full senior-share return)"
+        );
         kani::cover!(r > 0, "COVER: a non-trivial return is reachable");
         assert!(
             senior <= sp,
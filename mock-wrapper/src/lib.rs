@@ -0,0 +1,43 @@
+//! Mock Percolator wrapper
+//!
+//! A small stand-in for `percolator-prog`, built from this repo and loaded into
+//! LiteSVM at the wrapper's program id, so the stake program's CPI surface can
+//! be tested without the external `.so`. It implements only the instructions
+//! percolator-stake issues or its tests need:
+//!
+//!   0  - InitMarket:                 seed marketauth and asset 0..N authorities
+//!                                    to the signer (219-byte wire, as v17)
+//!   9  - TopUpInsurance:             signer_ata → wrapper vault, asset 0
+//!  19  - ResolveMarket:              marketauth; Live → Resolved
+//!  32  - UpdateAuthority:            rotate marketauth (current + new sign)
+//!  51  - UpdateBackingFeePolicy:     insurance_authority of domain / 2
+//!  55  - UpdateTradeFeePolicy:       asset 0 insurance_authority
+//!  57  - WithdrawInsuranceAsset:     insurance_operator; wrapper vault → dest
+//!  65  - UpdateAssetAuthority:       per-asset admin / insurance / operator
+//!  86  - UpdateFeeSplit:             marketauth
+//!  88  - UpdateMaintenanceFeePerSlot: marketauth
+//!
+//! Wires and account shapes are the v17 ones `percolator_stake::cpi` sends.
+//! Every gated instruction checks Live mode before the authority, and fails
+//! with the wrapper's own codes: `Unauthorized` = 8, `EngineLockActive` = 21.
+//! Anything else the real wrapper checks (oracles, margin, the canonical vault
+//! ATA) is out of scope; failures outside those two codes use plain
+//! `ProgramError`s.
+//!
+//! FAULT INJECTION: a test can write `state::Faults` into a market to make one
+//! tag fail with a chosen code, or make tags 9/57 move fewer tokens than they
+//! book — the wrapper misbehaviour the stake program's vault-delta checks
+//! exist for. See `state::set_faults`.
+//!
+//! Build: `cargo build-sbf --manifest-path mock-wrapper/Cargo.toml`.
+
+pub mod processor;
+pub mod state;
+
+#[cfg(not(feature = "no-entrypoint"))]
+mod entrypoint;
+
+/// Wrapper `PercolatorError::Unauthorized`.
+pub const ERR_UNAUTHORIZED: u32 = 8;
+/// Wrapper `PercolatorError::EngineLockActive`: the market is not Live.
+pub const ERR_ENGINE_LOCK_ACTIVE: u32 = 21;
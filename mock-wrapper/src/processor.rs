@@ -0,0 +1,783 @@
+//! Instruction handlers. Each one decodes the exact v17 wire (trailing bytes
+//! are `InvalidInstructionData`), loads the market, applies any injected
+//! fault for its tag, then checks Live mode before authority.
+
+use std::cell::RefMut;
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    state::{self, *},
+    ERR_ENGINE_LOCK_ACTIVE, ERR_UNAUTHORIZED,
+};
+
+const TOKEN_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+/// SPL Token `Transfer`.
+const TOKEN_IX_TRANSFER: u8 = 3;
+/// InitMarket payload length after the tag (the 219-byte v17 wire).
+const INIT_MARKET_PAYLOAD_LEN: usize = 218;
+/// InitMarket payload offset of `trade_fee_base_bps`: max_portfolio_assets(2)
+/// + 3 u64 + 2 u128 + 3 u64.
+const INIT_MARKET_TRADE_FEE_OFF: usize = 82;
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (&tag, rest) = data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    match tag {
+        0 => process_init_market(program_id, accounts, rest),
+        9 => process_top_up_insurance(program_id, accounts, rest),
+        19 => process_resolve_market(program_id, accounts, rest),
+        32 => process_update_authority(program_id, accounts, rest),
+        51 => process_update_backing_fee_policy(program_id, accounts, rest),
+        55 => process_update_trade_fee_policy(program_id, accounts, rest),
+        57 => process_withdraw_insurance_asset(program_id, accounts, rest),
+        65 => process_update_asset_authority(program_id, accounts, rest),
+        86 => process_update_fee_split(program_id, accounts, rest),
+        88 => process_update_maintenance_fee_per_slot(program_id, accounts, rest),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+// ── decoding ────────────────────────────────────────────────────────────────
+
+fn take<const N: usize>(rest: &mut &[u8]) -> Result<[u8; N], ProgramError> {
+    if rest.len() < N {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let (head, tail) = rest.split_at(N);
+    *rest = tail;
+    Ok(head.try_into().unwrap())
+}
+
+fn finish(rest: &[u8]) -> ProgramResult {
+    if rest.is_empty() {
+        Ok(())
+    } else {
+        Err(ProgramError::InvalidInstructionData)
+    }
+}
+
+fn custom(code: u32) -> ProgramError {
+    ProgramError::Custom(code)
+}
+
+// ── shared checks ───────────────────────────────────────────────────────────
+
+/// Writable, owned, initialized market data, after `tag`'s injected fault.
+fn market_data<'b, 'a>(
+    program_id: &Pubkey,
+    market: &'b AccountInfo<'a>,
+    tag: u8,
+) -> Result<RefMut<'b, &'a mut [u8]>, ProgramError> {
+    if market.owner != program_id {
+        return Err(ProgramError::IllegalOwner);
+    }
+    if !market.is_writable {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let data = market.try_borrow_mut_data()?;
+    if !state::is_initialized(&data) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let faults = state::faults(&data);
+    if faults.fail_tag == tag && faults.fail_code != 0 {
+        msg!(
+            "mock wrapper: injected failure {} for tag {}",
+            faults.fail_code,
+            tag
+        );
+        return Err(custom(faults.fail_code));
+    }
+    Ok(data)
+}
+
+fn require_live(data: &[u8]) -> ProgramResult {
+    if data[OFF_MODE] != MODE_LIVE {
+        return Err(custom(ERR_ENGINE_LOCK_ACTIVE));
+    }
+    Ok(())
+}
+
+/// `signer` signed and is `expected` (a zeroed authority authorizes nobody).
+fn require_authority(signer: &AccountInfo, expected: &[u8; 32]) -> ProgramResult {
+    if !signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *expected == [0u8; 32] || signer.key.to_bytes() != *expected {
+        return Err(custom(ERR_UNAUTHORIZED));
+    }
+    Ok(())
+}
+
+fn require_asset(data: &[u8], asset_index: u16) -> ProgramResult {
+    if asset_index >= read_u16(data, OFF_ASSET_COUNT) {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// A token account of the market's mint owned by the vault authority PDA.
+fn require_wrapper_vault(
+    program_id: &Pubkey,
+    market: &AccountInfo,
+    data: &[u8],
+    vault: &AccountInfo,
+) -> Result<(Pubkey, u8), ProgramError> {
+    let (vault_auth, bump) = state::vault_authority(program_id, market.key);
+    let vault_data = vault.try_borrow_data()?;
+    if *vault.owner != TOKEN_PROGRAM_ID
+        || vault_data.len() < 64
+        || vault_data[0..32] != data[OFF_MINT..OFF_MINT + 32]
+        || vault_data[32..64] != vault_auth.to_bytes()
+    {
+        msg!("mock wrapper: vault is not this market's insurance vault");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok((vault_auth, bump))
+}
+
+fn token_transfer_ix(
+    source: &Pubkey,
+    dest: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(9);
+    data.push(TOKEN_IX_TRANSFER);
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new(*dest, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data,
+    }
+}
+
+// ── 0: InitMarket ───────────────────────────────────────────────────────────
+// Accounts: [admin(signer), market(w), mint]
+
+fn process_init_market(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    rest: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let market = next_account_info(accounts_iter)?;
+    let mint = next_account_info(accounts_iter)?;
+    if rest.len() != INIT_MARKET_PAYLOAD_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if market.owner != program_id || !market.is_writable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let asset_count = u16::from_le_bytes([rest[0], rest[1]]);
+    let trade_fee_base_bps = u64::from_le_bytes(
+        rest[INIT_MARKET_TRADE_FEE_OFF..INIT_MARKET_TRADE_FEE_OFF + 8]
+            .try_into()
+            .unwrap(),
+    );
+    let maintenance_fee_per_slot =
+        u128::from_le_bytes(rest[INIT_MARKET_PAYLOAD_LEN - 16..].try_into().unwrap());
+
+    let mut data = market.try_borrow_mut_data()?;
+    if data[..HEADER_LEN].iter().any(|&b| b != 0) {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    match state::capacity(data.len()) {
+        Some(capacity) if asset_count >= 1 && asset_count <= capacity => {}
+        _ => return Err(ProgramError::InvalidAccountData),
+    }
+    state::init_market(
+        &mut data,
+        &admin.key.to_bytes(),
+        &mint.key.to_bytes(),
+        asset_count,
+        trade_fee_base_bps,
+        maintenance_fee_per_slot,
+    );
+    Ok(())
+}
+
+// ── 9: TopUpInsurance ───────────────────────────────────────────────────────
+// Accounts: [signer, market(w), signer_ata(w), vault(w), token_program]
+// Data: amount(u128). Gate: asset 0's insurance_authority.
+
+fn process_top_up_insurance(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut rest: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let signer = next_account_info(accounts_iter)?;
+    let market = next_account_info(accounts_iter)?;
+    let signer_ata = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let amount = u128::from_le_bytes(take::<16>(&mut rest)?);
+    finish(rest)?;
+    let amount = u64::try_from(amount).map_err(|_| ProgramError::InvalidArgument)?;
+    if *token_program.key != TOKEN_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let short = {
+        let mut data = market_data(program_id, market, 9)?;
+        require_live(&data)?;
+        require_authority(signer, &asset_authority(&data, 0, ASSET_AUTH_INSURANCE))?;
+        require_wrapper_vault(program_id, market, &data, vault)?;
+        let off = slot_off(0) + SLOT_INSURANCE_BALANCE;
+        let balance = read_u128(&data, off)
+            .checked_add(amount as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        write(&mut data, off, &balance.to_le_bytes());
+        state::faults(&data).short_transfer
+    };
+
+    invoke(
+        &token_transfer_ix(
+            signer_ata.key,
+            vault.key,
+            signer.key,
+            amount.saturating_sub(short),
+        ),
+        &[signer_ata.clone(), vault.clone(), signer.clone()],
+    )
+}
+
+// ── 19: ResolveMarket ───────────────────────────────────────────────────────
+// Accounts: [marketauth(signer), market(w)]
+
+fn process_resolve_market(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    rest: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let market = next_account_info(accounts_iter)?;
+    finish(rest)?;
+    let mut data = market_data(program_id, market, 19)?;
+    require_live(&data)?;
+    require_authority(authority, &read_pubkey(&data, OFF_MARKETAUTH))?;
+    data[OFF_MODE] = MODE_RESOLVED;
+    Ok(())
+}
+
+// ── 32: UpdateAuthority ─────────────────────────────────────────────────────
+// Accounts: [current marketauth(signer), new(signer), market(w)]
+// Data: new_authority(32)
+
+fn process_update_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut rest: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let current = next_account_info(accounts_iter)?;
+    let new_authority = next_account_info(accounts_iter)?;
+    let market = next_account_info(accounts_iter)?;
+    let new_pubkey = take::<32>(&mut rest)?;
+    finish(rest)?;
+    let mut data = market_data(program_id, market, 32)?;
+    require_authority(current, &read_pubkey(&data, OFF_MARKETAUTH))?;
+    if new_authority.key.to_bytes() != new_pubkey {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if !new_authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    write(&mut data, OFF_MARKETAUTH, &new_pubkey);
+    Ok(())
+}
+
+// ── 51: UpdateBackingFeePolicy ──────────────────────────────────────────────
+// Accounts: [insurance_authority(signer), market(w)]
+// Data: domain(u16) fee_bps(u16) insurance_share_bps(u16). Asset = domain / 2.
+
+fn process_update_backing_fee_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut rest: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let market = next_account_info(accounts_iter)?;
+    let domain = u16::from_le_bytes(take::<2>(&mut rest)?);
+    let fee_bps = u16::from_le_bytes(take::<2>(&mut rest)?);
+    let insurance_share_bps = u16::from_le_bytes(take::<2>(&mut rest)?);
+    finish(rest)?;
+    let mut data = market_data(program_id, market, 51)?;
+    let asset_index = domain / 2;
+    require_asset(&data, asset_index)?;
+    require_live(&data)?;
+    require_authority(
+        authority,
+        &asset_authority(&data, asset_index, ASSET_AUTH_INSURANCE),
+    )?;
+    if fee_bps > 10_000 || insurance_share_bps > 10_000 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let slot = slot_off(asset_index);
+    let side = 2 * (domain % 2) as usize;
+    write(
+        &mut data,
+        slot + SLOT_BACKING_FEE_BPS + side,
+        &fee_bps.to_le_bytes(),
+    );
+    write(
+        &mut data,
+        slot + SLOT_BACKING_INSURANCE_SHARE_BPS + side,
+        &insurance_share_bps.to_le_bytes(),
+    );
+    Ok(())
+}
+
+// ── 55: UpdateTradeFeePolicy ────────────────────────────────────────────────
+// Accounts: [asset 0 insurance_authority(signer), market(w)]
+// Data: trade_fee_base_bps(u64)
+
+fn process_update_trade_fee_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut rest: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let market = next_account_info(accounts_iter)?;
+    let trade_fee_base_bps = u64::from_le_bytes(take::<8>(&mut rest)?);
+    finish(rest)?;
+    let mut data = market_data(program_id, market, 55)?;
+    require_live(&data)?;
+    require_authority(authority, &asset_authority(&data, 0, ASSET_AUTH_INSURANCE))?;
+    if trade_fee_base_bps > 10_000 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    write(
+        &mut data,
+        OFF_TRADE_FEE_BASE_BPS,
+        &trade_fee_base_bps.to_le_bytes(),
+    );
+    Ok(())
+}
+
+// ── 57: WithdrawInsuranceAsset ──────────────────────────────────────────────
+// Accounts: [operator(signer), market(w), dest(w), vault(w), vault_authority,
+//            token_program]
+// Data: asset_index(u16) amount(u128). Gate: the asset's insurance_operator.
+
+fn process_withdraw_insurance_asset(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut rest: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let operator = next_account_info(accounts_iter)?;
+    let market = next_account_info(accounts_iter)?;
+    let dest = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let vault_authority = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let asset_index = u16::from_le_bytes(take::<2>(&mut rest)?);
+    let amount = u128::from_le_bytes(take::<16>(&mut rest)?);
+    finish(rest)?;
+    let amount = u64::try_from(amount).map_err(|_| ProgramError::InvalidArgument)?;
+    if *token_program.key != TOKEN_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let (bump, short) = {
+        let mut data = market_data(program_id, market, 57)?;
+        require_asset(&data, asset_index)?;
+        require_live(&data)?;
+        require_authority(
+            operator,
+            &asset_authority(&data, asset_index, ASSET_AUTH_INSURANCE_OPERATOR),
+        )?;
+        let (expected_authority, bump) = require_wrapper_vault(program_id, market, &data, vault)?;
+        if *vault_authority.key != expected_authority {
+            return Err(ProgramError::InvalidSeeds);
+        }
+        let off = slot_off(asset_index) + SLOT_INSURANCE_BALANCE;
+        let balance = read_u128(&data, off)
+            .checked_sub(amount as u128)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        write(&mut data, off, &balance.to_le_bytes());
+        (bump, state::faults(&data).short_transfer)
+    };
+
+    invoke_signed(
+        &token_transfer_ix(
+            vault.key,
+            dest.key,
+            vault_authority.key,
+            amount.saturating_sub(short),
+        ),
+        &[vault.clone(), dest.clone(), vault_authority.clone()],
+        &[&[b"vault", market.key.as_ref(), &[bump]]],
+    )
+}
+
+// ── 65: UpdateAssetAuthority ────────────────────────────────────────────────
+// Accounts: [current(signer), new(signer unless burning), market(w)]
+// Data: asset_index(u16) kind(u8) new_pubkey(32)
+//
+// The current holder of `kind`, or the asset's `asset_admin`, may move it, so
+// an unburned asset_admin can always take an authority back. New authorities
+// must co-sign; `[0; 32]` burns `asset_admin` and needs no co-signer. No other
+// kind can be burned.
+
+fn process_update_asset_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut rest: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let current = next_account_info(accounts_iter)?;
+    let new_authority = next_account_info(accounts_iter)?;
+    let market = next_account_info(accounts_iter)?;
+    let asset_index = u16::from_le_bytes(take::<2>(&mut rest)?);
+    let [kind] = take::<1>(&mut rest)?;
+    let new_pubkey = take::<32>(&mut rest)?;
+    finish(rest)?;
+    let mut data = market_data(program_id, market, 65)?;
+    require_live(&data)?;
+    require_asset(&data, asset_index)?;
+    let off = authority_off(asset_index, kind)?;
+    if new_pubkey == [0u8; 32] && kind != ASSET_AUTH_ADMIN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let holder = read_pubkey(&data, off);
+    let asset_admin = asset_authority(&data, asset_index, ASSET_AUTH_ADMIN);
+    if require_authority(current, &holder).is_err() {
+        require_authority(current, &asset_admin)?;
+    }
+    if new_pubkey != [0u8; 32] {
+        if new_authority.key.to_bytes() != new_pubkey {
+            return Err(ProgramError::InvalidArgument);
+        }
+        if !new_authority.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+    }
+    write(&mut data, off, &new_pubkey);
+    Ok(())
+}
+
+// ── 86: UpdateFeeSplit ──────────────────────────────────────────────────────
+// Accounts: [marketauth(signer), market(w)]
+// Data: creator, lp, insurance share bps (u16 each)
+
+fn process_update_fee_split(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut rest: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let market = next_account_info(accounts_iter)?;
+    let creator = u16::from_le_bytes(take::<2>(&mut rest)?);
+    let lp = u16::from_le_bytes(take::<2>(&mut rest)?);
+    let insurance = u16::from_le_bytes(take::<2>(&mut rest)?);
+    finish(rest)?;
+    let mut data = market_data(program_id, market, 86)?;
+    require_live(&data)?;
+    require_authority(authority, &read_pubkey(&data, OFF_MARKETAUTH))?;
+    if creator as u32 + lp as u32 + insurance as u32 != FEE_SHARE_TOTAL_BPS as u32
+        || creator > MAX_CREATOR_SHARE_BPS
+        || lp < MIN_LP_SHARE_BPS
+        || insurance < MIN_INSURANCE_SHARE_BPS
+    {
+        return Err(ProgramError::InvalidArgument);
+    }
+    write(&mut data, OFF_CREATOR_SHARE_BPS, &creator.to_le_bytes());
+    write(&mut data, OFF_LP_SHARE_BPS, &lp.to_le_bytes());
+    write(&mut data, OFF_INSURANCE_SHARE_BPS, &insurance.to_le_bytes());
+    Ok(())
+}
+
+// ── 88: UpdateMaintenanceFeePerSlot ─────────────────────────────────────────
+// Accounts: [marketauth(signer), market(w)]
+// Data: maintenance_fee_per_slot(u128)
+
+fn process_update_maintenance_fee_per_slot(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut rest: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let authority = next_account_info(accounts_iter)?;
+    let market = next_account_info(accounts_iter)?;
+    let fee = take::<16>(&mut rest)?;
+    finish(rest)?;
+    let mut data = market_data(program_id, market, 88)?;
+    require_live(&data)?;
+    require_authority(authority, &read_pubkey(&data, OFF_MARKETAUTH))?;
+    write(&mut data, OFF_MAINTENANCE_FEE_PER_SLOT, &fee);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_with(
+        program_id: &Pubkey,
+        metas: &[(Pubkey, bool, bool, Pubkey)],
+        datas: &mut [Vec<u8>],
+        ix: &[u8],
+    ) -> ProgramResult {
+        let mut lamports = vec![1_000_000u64; metas.len()];
+        let accounts: Vec<AccountInfo> = metas
+            .iter()
+            .zip(lamports.iter_mut().zip(datas.iter_mut()))
+            .map(|((key, signer, writable, owner), (l, d))| {
+                AccountInfo::new(key, *signer, *writable, l, d, owner, false, 0)
+            })
+            .collect();
+        process(program_id, &accounts, ix)
+    }
+
+    fn ix(tag: u8, payload: &[u8]) -> Vec<u8> {
+        let mut data = vec![tag];
+        data.extend_from_slice(payload);
+        data
+    }
+
+    /// InitMarket seeds every authority to the signer; a gated tag then
+    /// rejects strangers with 8, and once Resolved rejects everyone with 21
+    /// before looking at the signer.
+    #[test]
+    fn init_then_mode_before_authority() {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let admin = Pubkey::new_from_array([1u8; 32]);
+        let stranger = Pubkey::new_from_array([2u8; 32]);
+        let market = Pubkey::new_from_array([3u8; 32]);
+        let mint = Pubkey::new_from_array([4u8; 32]);
+        let other = Pubkey::default();
+
+        let mut payload = vec![0u8; INIT_MARKET_PAYLOAD_LEN];
+        payload[0..2].copy_from_slice(&2u16.to_le_bytes());
+        payload[INIT_MARKET_TRADE_FEE_OFF..INIT_MARKET_TRADE_FEE_OFF + 8]
+            .copy_from_slice(&30u64.to_le_bytes());
+        let init_metas = [
+            (admin, true, false, other),
+            (market, false, true, program_id),
+            (mint, false, false, other),
+        ];
+        let mut datas = vec![vec![], vec![0u8; market_len(2)], vec![]];
+        run_with(&program_id, &init_metas, &mut datas, &ix(0, &payload)).unwrap();
+        assert_eq!(
+            run_with(&program_id, &init_metas, &mut datas, &ix(0, &payload)),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+        let mut market_data = datas.swap_remove(1);
+        assert_eq!(read_u64(&market_data, OFF_TRADE_FEE_BASE_BPS), 30);
+        assert_eq!(
+            asset_authority(&market_data, 1, ASSET_AUTH_INSURANCE_OPERATOR),
+            admin.to_bytes()
+        );
+
+        let call = |signer: Pubkey, data: &mut Vec<u8>, ix: &[u8]| {
+            let mut datas = vec![vec![], std::mem::take(data)];
+            let r = run_with(
+                &program_id,
+                &[
+                    (signer, true, false, other),
+                    (market, false, true, program_id),
+                ],
+                &mut datas,
+                ix,
+            );
+            *data = datas.swap_remove(1);
+            r
+        };
+        let trade_fee = ix(55, &50u64.to_le_bytes());
+        assert_eq!(
+            call(stranger, &mut market_data, &trade_fee),
+            Err(custom(ERR_UNAUTHORIZED))
+        );
+        call(admin, &mut market_data, &trade_fee).unwrap();
+        assert_eq!(read_u64(&market_data, OFF_TRADE_FEE_BASE_BPS), 50);
+        // Trailing bytes are a different wire, not a tolerated one.
+        assert_eq!(
+            call(admin, &mut market_data, &ix(55, &[0u8; 9])),
+            Err(ProgramError::InvalidInstructionData)
+        );
+
+        call(admin, &mut market_data, &ix(19, &[])).unwrap();
+        assert_eq!(market_data[OFF_MODE], MODE_RESOLVED);
+        assert_eq!(
+            call(stranger, &mut market_data, &trade_fee),
+            Err(custom(ERR_ENGINE_LOCK_ACTIVE))
+        );
+    }
+
+    /// Tag 65: the holder or the asset admin may move an authority, a new
+    /// holder must co-sign, and a burned admin cannot be used again.
+    #[test]
+    fn asset_authority_rules_and_fault_injection() {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let admin = Pubkey::new_from_array([1u8; 32]);
+        let pool = Pubkey::new_from_array([5u8; 32]);
+        let market = Pubkey::new_from_array([3u8; 32]);
+        let other = Pubkey::default();
+        let mut market_data = new_market_data(1, &admin, &Pubkey::new_from_array([4u8; 32]));
+
+        let call = |data: &mut Vec<u8>,
+                    current: Pubkey,
+                    new: Pubkey,
+                    new_signs: bool,
+                    kind: u8,
+                    to: Pubkey| {
+            let mut payload = 0u16.to_le_bytes().to_vec();
+            payload.push(kind);
+            payload.extend_from_slice(to.as_ref());
+            let mut datas = vec![vec![], vec![], std::mem::take(data)];
+            let r = run_with(
+                &program_id,
+                &[
+                    (current, true, false, other),
+                    (new, new_signs, false, other),
+                    (market, false, true, program_id),
+                ],
+                &mut datas,
+                &ix(65, &payload),
+            );
+            *data = datas.swap_remove(2);
+            r
+        };
+
+        assert_eq!(
+            call(
+                &mut market_data,
+                admin,
+                pool,
+                false,
+                ASSET_AUTH_INSURANCE,
+                pool
+            ),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        call(
+            &mut market_data,
+            admin,
+            pool,
+            true,
+            ASSET_AUTH_INSURANCE,
+            pool,
+        )
+        .unwrap();
+        // The holder can move it on, and the asset admin can take it back.
+        call(
+            &mut market_data,
+            pool,
+            admin,
+            true,
+            ASSET_AUTH_INSURANCE,
+            admin,
+        )
+        .unwrap();
+        call(
+            &mut market_data,
+            admin,
+            pool,
+            true,
+            ASSET_AUTH_INSURANCE,
+            pool,
+        )
+        .unwrap();
+        // Once the admin is burned, only holders can move their authority.
+        call(
+            &mut market_data,
+            admin,
+            other,
+            false,
+            ASSET_AUTH_ADMIN,
+            other,
+        )
+        .unwrap();
+        assert_eq!(
+            call(
+                &mut market_data,
+                admin,
+                admin,
+                true,
+                ASSET_AUTH_INSURANCE,
+                admin
+            ),
+            Err(custom(ERR_UNAUTHORIZED))
+        );
+        for (kind, to) in [(3, pool), (ASSET_AUTH_INSURANCE, other)] {
+            assert_eq!(
+                call(&mut market_data, pool, to, true, kind, to),
+                Err(ProgramError::InvalidInstructionData)
+            );
+        }
+
+        set_faults(
+            &mut market_data,
+            Faults {
+                fail_tag: 65,
+                fail_code: ERR_ENGINE_LOCK_ACTIVE,
+                ..Faults::default()
+            },
+        );
+        assert_eq!(
+            call(
+                &mut market_data,
+                admin,
+                pool,
+                true,
+                ASSET_AUTH_INSURANCE,
+                pool
+            ),
+            Err(custom(ERR_ENGINE_LOCK_ACTIVE))
+        );
+    }
+
+    #[test]
+    fn fee_split_bounds() {
+        let program_id = Pubkey::new_from_array([7u8; 32]);
+        let admin = Pubkey::new_from_array([1u8; 32]);
+        let market = Pubkey::new_from_array([3u8; 32]);
+        let mut market_data = new_market_data(1, &admin, &Pubkey::new_from_array([4u8; 32]));
+        let mut call = |shares: [u16; 3]| {
+            let payload: Vec<u8> = shares.iter().flat_map(|s| s.to_le_bytes()).collect();
+            let mut datas = vec![vec![], std::mem::take(&mut market_data)];
+            let r = run_with(
+                &program_id,
+                &[
+                    (admin, true, false, Pubkey::default()),
+                    (market, false, true, program_id),
+                ],
+                &mut datas,
+                &ix(86, &payload),
+            );
+            market_data = datas.swap_remove(1);
+            r
+        };
+        for bad in [[1600, 4800, 1601], [3700, 3200, 1100], [1000, 3100, 3900]] {
+            assert_eq!(call(bad), Err(ProgramError::InvalidArgument));
+        }
+        call([3600, 3200, 1200]).unwrap();
+        assert_eq!(fee_split(&market_data), (3600, 3200, 1200));
+    }
+}
@@ -0,0 +1,262 @@
+//! Mock market account layout.
+//!
+//! The stake program observes the total length, the header and one asset-slot
+//! field: its market probe requires a v17 length (`market_len`) and a non-zero
+//! 16-byte header, and `AssertPoolInvariants` reads the asset's insurance
+//! balance. Those follow the real wrapper. So do the config offsets the stake
+//! e2e suites read back (fee split, maintenance fee, trade fee), so one set of
+//! readers works against both programs. Every other offset is the mock's own
+//! choice and says nothing about the real wrapper's layout.
+//!
+//! ```text
+//! [0..16)             header: MAGIC(8) + VERSION(u32) + pad
+//! [16..592)           config: marketauth, mint, fees
+//! [592..1350)         group: mode, asset count, injected faults
+//! [1350 + i*1797..)   asset slot i: authorities, insurance, backing fees
+//! ```
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+pub const HEADER_LEN: usize = 16;
+/// `MARKET_GROUP_OFF`: header + the 576-byte wrapper config.
+pub const GROUP_OFF: usize = HEADER_LEN + 576;
+pub const GROUP_LEN: usize = 758;
+pub const ASSET_SLOTS_OFF: usize = GROUP_OFF + GROUP_LEN;
+pub const ASSET_SLOT_LEN: usize = 1797;
+
+pub const MAGIC: [u8; 8] = *b"PRCLMOCK";
+pub const VERSION: u32 = 17;
+
+// ── config (offsets mirrored from the real v17 WrapperConfigV16 are marked) ──
+pub const OFF_MARKETAUTH: usize = HEADER_LEN;
+pub const OFF_MINT: usize = HEADER_LEN + 32;
+/// u128; real v17 offset.
+pub const OFF_MAINTENANCE_FEE_PER_SLOT: usize = HEADER_LEN + 96;
+/// u64; real v17 offset.
+pub const OFF_TRADE_FEE_BASE_BPS: usize = HEADER_LEN + 128;
+/// u16 x3; real v17 offsets.
+pub const OFF_CREATOR_SHARE_BPS: usize = HEADER_LEN + 560;
+pub const OFF_LP_SHARE_BPS: usize = HEADER_LEN + 562;
+pub const OFF_INSURANCE_SHARE_BPS: usize = HEADER_LEN + 564;
+
+// ── group ──
+/// u8: `MODE_LIVE` or `MODE_RESOLVED`.
+pub const OFF_MODE: usize = GROUP_OFF;
+/// u16: InitMarket's `max_portfolio_assets`.
+pub const OFF_ASSET_COUNT: usize = GROUP_OFF + 2;
+/// u64: tokens tags 9 and 57 hold back from every transfer they book.
+pub const OFF_FAULT_SHORT_TRANSFER: usize = GROUP_OFF + 8;
+/// u8: the tag `OFF_FAULT_CODE` applies to (0 = none).
+pub const OFF_FAULT_TAG: usize = GROUP_OFF + 16;
+/// u32: custom error that tag fails with.
+pub const OFF_FAULT_CODE: usize = GROUP_OFF + 20;
+
+// ── asset slot (relative to `slot_off`) ──
+pub const SLOT_ASSET_ADMIN: usize = 0;
+pub const SLOT_INSURANCE_AUTHORITY: usize = 32;
+pub const SLOT_INSURANCE_OPERATOR: usize = 64;
+/// u128: tokens flushed in and not yet withdrawn; real v17 offset.
+pub const SLOT_INSURANCE_BALANCE: usize = 96;
+/// u16 x2, indexed by domain side (0 = long, 1 = short).
+pub const SLOT_BACKING_FEE_BPS: usize = 112;
+/// u16 x2, indexed by domain side.
+pub const SLOT_BACKING_INSURANCE_SHARE_BPS: usize = 116;
+
+pub const MODE_LIVE: u8 = 0;
+pub const MODE_RESOLVED: u8 = 1;
+
+/// `UpdateAssetAuthority` kinds.
+pub const ASSET_AUTH_ADMIN: u8 = 0;
+pub const ASSET_AUTH_INSURANCE: u8 = 1;
+pub const ASSET_AUTH_INSURANCE_OPERATOR: u8 = 2;
+
+/// InitMarket fee split; the three shares always sum to `FEE_SHARE_TOTAL_BPS`.
+pub const DEFAULT_CREATOR_SHARE_BPS: u16 = 1600;
+pub const DEFAULT_LP_SHARE_BPS: u16 = 4800;
+pub const DEFAULT_INSURANCE_SHARE_BPS: u16 = 1600;
+pub const FEE_SHARE_TOTAL_BPS: u16 = 8000;
+pub const MAX_CREATOR_SHARE_BPS: u16 = 3600;
+pub const MIN_LP_SHARE_BPS: u16 = 3200;
+pub const MIN_INSURANCE_SHARE_BPS: u16 = 1200;
+
+/// Market account length for `capacity` assets.
+pub const fn market_len(capacity: u16) -> usize {
+    ASSET_SLOTS_OFF + capacity as usize * ASSET_SLOT_LEN
+}
+
+/// Asset capacity of a market account of `len` bytes, if it is a market length.
+pub fn capacity(len: usize) -> Option<u16> {
+    let slots = len.checked_sub(ASSET_SLOTS_OFF)?;
+    if slots == 0 || slots % ASSET_SLOT_LEN != 0 {
+        return None;
+    }
+    u16::try_from(slots / ASSET_SLOT_LEN).ok()
+}
+
+pub const fn slot_off(asset_index: u16) -> usize {
+    ASSET_SLOTS_OFF + asset_index as usize * ASSET_SLOT_LEN
+}
+
+/// Offset of the `kind` authority of `asset_index`.
+pub fn authority_off(asset_index: u16, kind: u8) -> Result<usize, ProgramError> {
+    let field = match kind {
+        ASSET_AUTH_ADMIN => SLOT_ASSET_ADMIN,
+        ASSET_AUTH_INSURANCE => SLOT_INSURANCE_AUTHORITY,
+        ASSET_AUTH_INSURANCE_OPERATOR => SLOT_INSURANCE_OPERATOR,
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+    Ok(slot_off(asset_index) + field)
+}
+
+/// The wrapper vault authority PDA: `[b"vault", market]`.
+pub fn vault_authority(program_id: &Pubkey, market: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"vault", market.as_ref()], program_id)
+}
+
+pub fn is_initialized(data: &[u8]) -> bool {
+    capacity(data.len()).is_some() && data[..8] == MAGIC
+}
+
+pub fn read_pubkey(data: &[u8], off: usize) -> [u8; 32] {
+    data[off..off + 32].try_into().unwrap()
+}
+
+pub fn read_u16(data: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes(data[off..off + 2].try_into().unwrap())
+}
+
+pub fn read_u32(data: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes(data[off..off + 4].try_into().unwrap())
+}
+
+pub fn read_u64(data: &[u8], off: usize) -> u64 {
+    u64::from_le_bytes(data[off..off + 8].try_into().unwrap())
+}
+
+pub fn read_u128(data: &[u8], off: usize) -> u128 {
+    u128::from_le_bytes(data[off..off + 16].try_into().unwrap())
+}
+
+pub fn write(data: &mut [u8], off: usize, bytes: &[u8]) {
+    data[off..off + bytes.len()].copy_from_slice(bytes);
+}
+
+/// What InitMarket writes: every authority of every asset and `marketauth`
+/// set to `admin`, Live, default fee split.
+pub fn init_market(
+    data: &mut [u8],
+    admin: &[u8; 32],
+    mint: &[u8; 32],
+    asset_count: u16,
+    trade_fee_base_bps: u64,
+    maintenance_fee_per_slot: u128,
+) {
+    write(data, 0, &MAGIC);
+    write(data, 8, &VERSION.to_le_bytes());
+    write(data, OFF_MARKETAUTH, admin);
+    write(data, OFF_MINT, mint);
+    write(
+        data,
+        OFF_MAINTENANCE_FEE_PER_SLOT,
+        &maintenance_fee_per_slot.to_le_bytes(),
+    );
+    write(
+        data,
+        OFF_TRADE_FEE_BASE_BPS,
+        &trade_fee_base_bps.to_le_bytes(),
+    );
+    write(
+        data,
+        OFF_CREATOR_SHARE_BPS,
+        &DEFAULT_CREATOR_SHARE_BPS.to_le_bytes(),
+    );
+    write(data, OFF_LP_SHARE_BPS, &DEFAULT_LP_SHARE_BPS.to_le_bytes());
+    write(
+        data,
+        OFF_INSURANCE_SHARE_BPS,
+        &DEFAULT_INSURANCE_SHARE_BPS.to_le_bytes(),
+    );
+    data[OFF_MODE] = MODE_LIVE;
+    write(data, OFF_ASSET_COUNT, &asset_count.to_le_bytes());
+    for asset in 0..asset_count {
+        for kind in [
+            ASSET_AUTH_ADMIN,
+            ASSET_AUTH_INSURANCE,
+            ASSET_AUTH_INSURANCE_OPERATOR,
+        ] {
+            write(data, authority_off(asset, kind).unwrap(), admin);
+        }
+    }
+}
+
+/// A ready-made Live market for `svm.set_account`, as if InitMarket had run
+/// with `capacity` assets and zero fees.
+pub fn new_market_data(capacity: u16, admin: &Pubkey, mint: &Pubkey) -> Vec<u8> {
+    let mut data = vec![0u8; market_len(capacity)];
+    init_market(
+        &mut data,
+        &admin.to_bytes(),
+        &mint.to_bytes(),
+        capacity,
+        0,
+        0,
+    );
+    data
+}
+
+/// Misbehaviour a test injects into a market.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Faults {
+    /// Tags 9 and 57 transfer this many tokens fewer than they book.
+    pub short_transfer: u64,
+    /// Tag that fails with `fail_code` before doing anything (0 = none).
+    pub fail_tag: u8,
+    pub fail_code: u32,
+}
+
+pub fn faults(data: &[u8]) -> Faults {
+    Faults {
+        short_transfer: read_u64(data, OFF_FAULT_SHORT_TRANSFER),
+        fail_tag: data[OFF_FAULT_TAG],
+        fail_code: read_u32(data, OFF_FAULT_CODE),
+    }
+}
+
+pub fn set_faults(data: &mut [u8], faults: Faults) {
+    write(
+        data,
+        OFF_FAULT_SHORT_TRANSFER,
+        &faults.short_transfer.to_le_bytes(),
+    );
+    data[OFF_FAULT_TAG] = faults.fail_tag;
+    write(data, OFF_FAULT_CODE, &faults.fail_code.to_le_bytes());
+}
+
+/// Asset `asset_index`'s insurance balance.
+pub fn insurance_balance(data: &[u8], asset_index: u16) -> u128 {
+    read_u128(data, slot_off(asset_index) + SLOT_INSURANCE_BALANCE)
+}
+
+/// The current `kind` authority of `asset_index`.
+pub fn asset_authority(data: &[u8], asset_index: u16, kind: u8) -> [u8; 32] {
+    read_pubkey(data, authority_off(asset_index, kind).unwrap())
+}
+
+/// (creator, lp, insurance) share bps.
+pub fn fee_split(data: &[u8]) -> (u16, u16, u16) {
+    (
+        read_u16(data, OFF_CREATOR_SHARE_BPS),
+        read_u16(data, OFF_LP_SHARE_BPS),
+        read_u16(data, OFF_INSURANCE_SHARE_BPS),
+    )
+}
+
+/// (fee_bps, insurance_share_bps) of backing-fee `domain`.
+pub fn backing_fee_policy(data: &[u8], domain: u16) -> (u16, u16) {
+    let slot = slot_off(domain / 2);
+    let side = 2 * (domain % 2) as usize;
+    (
+        read_u16(data, slot + SLOT_BACKING_FEE_BPS + side),
+        read_u16(data, slot + SLOT_BACKING_INSURANCE_SHARE_BPS + side),
+    )
+}
@@ -0,0 +1,591 @@
+//! Pool accounting transitions — the ledger half of the processor handlers.
+//!
+//! Each function here takes a `StakePool` and plain numbers only: no accounts,
+//! no CPIs, no logging. The handlers validate accounts, move tokens, and then
+//! book the movement through these functions, so the glue that previously
+//! lived inline (and where the #198 / HWM-freeze / recover-cap bugs were) can
+//! be model-checked on its own. Harnesses live in `mod proofs` below and start
+//! from an arbitrary pool satisfying [`pool_invariant`]:
+//!
+//!   cargo kani --lib
+
+use solana_program::program_error::ProgramError;
+
+use crate::error::StakeError;
+use crate::state::{self, StakePool};
+
+/// The ledger invariant every handler preserves:
+///
+/// - `total_pool_value()` is representable (the pool is solvent);
+/// - `realized_junior_loss + total_recovered_from_wrapper` fits within both
+///   `total_flushed` (nothing comes back from the wrapper that was not sent)
+///   and `total_returned` (both are booked into it);
+/// - junior's part of the deficit write-off is within the write-off;
+/// - `hwm_floor_bps <= 10_000`.
+pub fn pool_invariant(pool: &StakePool) -> bool {
+    let settled = match pool
+        .realized_junior_loss()
+        .checked_add(pool.total_recovered_from_wrapper)
+    {
+        Some(v) => v,
+        None => return false,
+    };
+    pool.total_pool_value().is_some()
+        && settled <= pool.total_flushed
+        && settled <= pool.total_returned
+        && pool.deficit_junior_written_off <= pool.deficit_written_off
+        && pool.hwm_floor_bps() <= 10_000
+}
+
+/// The invariants `AssertPoolInvariants` holds the pool's books to, against
+/// the on-chain balances it read:
+///
+/// - [`pool_invariant`];
+/// - `vault_balance >= total_pool_value()` — only an un-accrued positive
+///   surplus (fees not yet crystallized, donations) is allowed;
+/// - `lp_mint_supply <= total_lp_supply - MINIMUM_LIQUIDITY` — the dead
+///   shares are booked but never minted, and a holder burning their own LP
+///   outside the program only makes the supply smaller;
+/// - `effective_junior_balance() <= total_pool_value()`;
+/// - `wrapper_insurance_balance >= wrapper_recoverable()`, when the pool's
+///   asset slot was read from its market.
+pub fn check_pool_invariants(
+    pool: &StakePool,
+    vault_balance: u64,
+    lp_mint_supply: u64,
+    wrapper_insurance_balance: Option<u128>,
+) -> Result<(), StakeError> {
+    if !pool_invariant(pool) {
+        return Err(StakeError::InvariantLedgerInconsistent);
+    }
+    let pool_value = pool
+        .total_pool_value()
+        .ok_or(StakeError::InvariantLedgerInconsistent)?;
+    if vault_balance < pool_value {
+        return Err(StakeError::InvariantVaultShortfall);
+    }
+    if lp_mint_supply
+        > pool
+            .total_lp_supply
+            .saturating_sub(state::MINIMUM_LIQUIDITY)
+    {
+        return Err(StakeError::InvariantLpSupplyMismatch);
+    }
+    if pool.effective_junior_balance() > pool_value {
+        return Err(StakeError::InvariantJuniorExceedsPoolValue);
+    }
+    if wrapper_insurance_balance.is_some_and(|balance| balance < pool.wrapper_recoverable() as u128)
+    {
+        return Err(StakeError::InvariantWrapperShortfall);
+    }
+    Ok(())
+}
+
+/// Crystallize any un-accrued vault surplus into pool share price. Returns the
+/// fee delta booked (0 when there is nothing to accrue).
+///
+/// `vault_balance` MUST be the verified vault token-account balance read BEFORE
+/// any deposit transfer in the calling instruction — otherwise the deposit's own
+/// collateral would be mis-credited as fees. No-op when there is no surplus or
+/// no LP holders, preserving the first-depositor bootstrap / anti-brick guard.
+pub fn accrue_fees(pool: &mut StakePool, vault_balance: u64) -> Result<u64, ProgramError> {
+    // total_pool_value() = deposited - withdrawn - flushed + returned + fees_earned (mode 1)
+    // — the authoritative expected balance; any excess is un-accrued fee revenue.
+    let pool_value = pool.total_pool_value().ok_or(StakeError::Overflow)?;
+
+    // Only accrue when there are active LP holders. Accruing at total_lp_supply == 0
+    // would set total_fees_earned > 0 at zero supply, tripping calc_lp_for_deposit's
+    // orphaned-value guard and permanently bricking the first deposit (an attacker can
+    // donate 1 token to the vault pre-first-deposit to trigger it).
+    if vault_balance <= pool_value || pool.total_lp_supply == 0 {
+        return Ok(0);
+    }
+    let fee_delta = vault_balance - pool_value;
+
+    // Snapshot pre-fee tranche balances BEFORE incrementing total_fees_earned.
+    // senior_balance() derives from total_pool_value() which includes
+    // total_fees_earned, so reading it post-increment would inflate the senior
+    // weight in distribute_fees and systematically shortchange the junior tranche.
+    let distribute_to_junior = pool.tranche_enabled() && pool.junior_total_lp() > 0;
+    let (snapshot_junior_bal, snapshot_senior_bal) = if distribute_to_junior {
+        (
+            pool.junior_balance(),
+            pool.senior_balance().ok_or(StakeError::Overflow)?,
+        )
+    } else {
+        (0, 0)
+    };
+
+    pool.total_fees_earned = pool
+        .total_fees_earned
+        .checked_add(fee_delta)
+        .ok_or(StakeError::Overflow)?;
+
+    // PERC-303: distribute the fee delta between junior/senior sub-pools using the
+    // junior fee multiplier. Senior implicitly receives the remainder since
+    // senior_balance = total_pool_value() - junior_balance and total_fees_earned
+    // was already incremented by the full fee_delta above.
+    if distribute_to_junior {
+        let (junior_fee, _) = crate::math::distribute_fees(
+            snapshot_junior_bal,
+            snapshot_senior_bal,
+            pool.junior_fee_mult_bps(),
+            fee_delta,
+        );
+        pool.set_junior_balance(
+            pool.junior_balance()
+                .checked_add(junior_fee)
+                .ok_or(StakeError::Overflow)?,
+        );
+        // M-2: guard — reject accrual if it would push junior_balance above pool value.
+        let pv = pool.total_pool_value().ok_or(StakeError::Overflow)?;
+        if pool.junior_balance() > pv {
+            return Err(StakeError::Overflow.into());
+        }
+    }
+    Ok(fee_delta)
+}
+
+/// N7 (CONSOLIDATED-PLAN §2.2): applies the `state::MINIMUM_LIQUIDITY`
+/// dead-share lock at the pool's TRUE genesis deposit. Shared by `process_deposit` (senior/global bootstrap) and
+/// `process_deposit_junior` (junior bootstrap) — both mutate the SAME
+/// `pool.total_lp_supply` counter, so whichever instruction is called FIRST on a
+/// freshly-initialized pool is the genesis deposit, regardless of which entry
+/// point it comes through.
+///
+/// `total_lp_supply_before` MUST be read BEFORE the caller applies this
+/// deposit's own `total_lp_supply` increment. `lp_to_mint` is the FULL LP
+/// amount computed by the pro-rata/bootstrap math (at genesis this comes from
+/// the `total_lp_supply == 0 && total_pool_value == 0` branch of
+/// `calc_lp_for_deposit`, so `lp_to_mint == deposit_amount` exactly). The
+/// caller must still add the FULL `lp_to_mint` to `pool.total_lp_supply`
+/// unchanged — only the amount actually SPL-minted to the depositor's ATA is
+/// reduced here. The `MINIMUM_LIQUIDITY` difference is never minted to any
+/// account, so it becomes permanently unredeemable "dead" supply (the
+/// Uniswap-V2-style anti-inflation floor — see the constant's doc comment).
+///
+/// Returns `DepositBelowMinimumLiquidity` if the genesis deposit is too small
+/// to carve out the dead-share floor (rather than silently underflowing or
+/// minting 0 real LP while still transferring in collateral).
+pub fn apply_minimum_liquidity_lock(
+    total_lp_supply_before: u64,
+    lp_to_mint: u64,
+) -> Result<u64, ProgramError> {
+    if total_lp_supply_before != 0 {
+        // Not the pool's genesis deposit — mint the full computed amount, as before.
+        return Ok(lp_to_mint);
+    }
+    let mint_amount = lp_to_mint
+        .checked_sub(state::MINIMUM_LIQUIDITY)
+        .ok_or(StakeError::DepositBelowMinimumLiquidity)?;
+    // Reject an exact-equal genesis deposit too: it would carve the dead-share
+    // floor out fully and mint 0 real LP to the depositor while still
+    // transferring their collateral in — same S-4 class of bug as ZeroSharesMinted.
+    if mint_amount == 0 {
+        return Err(StakeError::DepositBelowMinimumLiquidity.into());
+    }
+    Ok(mint_amount)
+}
+
+/// Collateral a flush may move: exactly `total_pool_value()`.
+///
+/// #198: the previous inline chain `((D - W) - F) + R` (u64, left-to-right)
+/// UNDERFLOWED at `(D - W) - F` whenever `D - W < F` and failed the flush with
+/// Overflow even though the tokens were in the vault. It ALSO over-counted by
+/// omitting `- realized_junior_loss`: the #161 last-junior-exit booking raises
+/// total_returned by the forfeited loss WITHOUT a token movement. The vault
+/// physically holds exactly total_pool_value() (fees are 0 because flush is
+/// mode-0 only), which returns None only on genuine insolvency.
+pub fn flush_capacity(pool: &StakePool) -> Result<u64, ProgramError> {
+    Ok(pool.total_pool_value().ok_or(StakeError::Overflow)?)
+}
+
+/// Book a completed flush of `amount` to the wrapper insurance fund.
+pub fn book_flush(pool: &mut StakePool, amount: u64) -> Result<(), ProgramError> {
+    if amount > flush_capacity(pool)? {
+        return Err(StakeError::InsufficientVaultBalance.into());
+    }
+    pool.total_flushed = pool
+        .total_flushed
+        .checked_add(amount)
+        .ok_or(StakeError::Overflow)?;
+
+    // PERC-313 HWM: a flush is a realized insurance LOSS — pool TVL drops by `amount`.
+    // The high-water-mark withdrawal floor must track that loss, or LPs get frozen out
+    // of a pool that just lost money. `refresh_hwm` only RAISES the mark within an epoch
+    // and is never called here, so the mark must be lowered explicitly. Lower it by
+    // exactly the flushed amount (the realized loss) so the floor recomputes against the
+    // loss-adjusted peak (peak − Σ losses). This preserves anti-drain protection:
+    // WITHDRAWALS never lower the mark (only refresh_hwm's raise and this flush do), so a
+    // withdrawal-driven drain is still floored; only a real loss lowers the floor, and only
+    // by the loss amount — no free withdrawal headroom is created (TVL dropped by the same
+    // `amount`). saturating_sub is panic-free; if a stale/zero mark is below `amount` it
+    // floors at 0 (floor 0 = no restriction), and the next withdraw's refresh_hwm re-bases
+    // the mark to live TVL. Left ungated on hwm_enabled(): the mark is only ever READ in the
+    // hwm_enabled branch, and a lowered mark is strictly more permissive, so tracking the
+    // loss unconditionally keeps "mark = peak − losses" true and avoids a stale-high mark
+    // re-freezing if HWM is toggled on mid-epoch after a flush. ReturnInsurance is left
+    // untouched: recovery rides the existing same-epoch refresh_hwm raise (clamped to TVL).
+    pool.set_epoch_high_water_tvl(pool.epoch_high_water_tvl().saturating_sub(amount));
+    Ok(())
+}
+
+/// Book `amount` recovered from the wrapper insurance fund into the vault.
+///
+/// Capped at `wrapper_recoverable()`, which is measured against
+/// `total_recovered_from_wrapper`, NOT `total_returned` — see its doc comment
+/// for the two live defects (#262 / #270) that came from getting this wrong.
+pub fn book_recovery(pool: &mut StakePool, amount: u64) -> Result<(), ProgramError> {
+    if amount > pool.wrapper_recoverable() {
+        return Err(StakeError::InsufficientVaultBalance.into());
+    }
+    // CONSERVATION: the recovered tokens are back in the vault.
+    pool.total_returned = pool
+        .total_returned
+        .checked_add(amount)
+        .ok_or(StakeError::Overflow)?;
+    // H-1 re-review fix: this is the ONLY site that increments
+    // `total_recovered_from_wrapper`, and the handler calls it only after the
+    // tag-57 WithdrawInsuranceAsset CPI has succeeded, so this counter tracks
+    // real wrapper-side recovery only. `process_return_insurance` (admin's own
+    // wallet -> pool.vault, no wrapper CPI) and the #161 last-junior-exit
+    // phantom write-off (realized_junior_loss, zero token movement) must NEVER
+    // touch this counter — see its doc comment on `StakePool` and the H-1 gates
+    // in `process_admin_resolve_market` / `process_set_market_resolved`.
+    pool.total_recovered_from_wrapper = pool
+        .total_recovered_from_wrapper
+        .checked_add(amount)
+        .ok_or(StakeError::Overflow)?;
+    Ok(())
+}
+
+/// Deficit mode: write the pool value down to `vault_balance` when the vault
+/// holds less than `total_pool_value()`, junior first, and enter deficit mode.
+/// Returns the shortfall written off (0 when the vault covers the books).
+///
+/// The shortfall is booked into `deficit_written_off`, which
+/// `total_pool_value()` excludes, and the junior tranche gives up
+/// `min(shortfall, effective_junior_balance())` of its balance — so senior
+/// only loses what junior could not cover. Every later exit is then priced
+/// against what the vault really holds, instead of the first withdrawers
+/// being paid at the overstated value and the last ones holding the loss.
+///
+/// A ledger `total_pool_value()` cannot represent is written down too, from
+/// its signed value (`StakePool::ledger_value`). Past `u64::MAX` it is an
+/// ordinary shortfall. A negative ledger has paid out more than it booked, so
+/// the vault's tokens are unbooked: they are booked into `total_fees_earned`
+/// the way `accrue_fees` books a surplus, and whatever junior then claims
+/// beyond the vault is the shortfall.
+pub fn write_down_deficit(pool: &mut StakePool, vault_balance: u64) -> Result<u64, ProgramError> {
+    let ledger = pool.ledger_value();
+    if ledger < 0 {
+        let junior_excess = pool
+            .effective_junior_balance()
+            .saturating_sub(vault_balance);
+        let unbooked = u64::try_from(vault_balance as i128 - ledger)
+            .ok()
+            .and_then(|v| v.checked_add(junior_excess))
+            .ok_or(StakeError::Overflow)?;
+        pool.total_fees_earned = pool
+            .total_fees_earned
+            .checked_add(unbooked)
+            .ok_or(StakeError::Overflow)?;
+    }
+    let ledger = pool.ledger_value();
+    if ledger <= vault_balance as i128 {
+        return Ok(0);
+    }
+    let shortfall =
+        u64::try_from(ledger - vault_balance as i128).map_err(|_| StakeError::Overflow)?;
+    let junior_share = shortfall.min(pool.effective_junior_balance());
+    let written_off = pool
+        .deficit_written_off
+        .checked_add(shortfall)
+        .ok_or(StakeError::Overflow)?;
+    // `total_pool_value()` excludes `dead_value()`, which must stay representable.
+    pool.realized_junior_loss()
+        .checked_add(written_off)
+        .ok_or(StakeError::Overflow)?;
+    pool.set_junior_balance(
+        pool.junior_balance()
+            .checked_sub(junior_share)
+            .ok_or(StakeError::Overflow)?,
+    );
+    pool.deficit_junior_written_off = pool
+        .deficit_junior_written_off
+        .checked_add(junior_share)
+        .ok_or(StakeError::Overflow)?;
+    pool.deficit_written_off = written_off;
+    pool.deficit_mode = 1;
+    Ok(shortfall)
+}
+
+/// Deficit mode's way out: a vault holding more than `total_pool_value()`
+/// while a write-off is outstanding restores the write-off before anything
+/// is booked as fees. Returns the amount restored (0 outside deficit mode).
+///
+/// Recovery runs the write-down in reverse — senior's part first, then
+/// junior's — so first-loss capital is made whole last. Once all of
+/// `deficit_written_off` is back the vault covers the original books and
+/// the pool leaves deficit mode; any surplus left over is fees.
+pub fn restore_deficit(pool: &mut StakePool, vault_balance: u64) -> Result<u64, ProgramError> {
+    if !pool.in_deficit() {
+        return Ok(0);
+    }
+    let pool_value = pool.total_pool_value().ok_or(StakeError::Overflow)?;
+    let restored = vault_balance
+        .saturating_sub(pool_value)
+        .min(pool.deficit_written_off);
+    let senior_part = pool
+        .deficit_written_off
+        .saturating_sub(pool.deficit_junior_written_off);
+    let junior_part = restored.saturating_sub(senior_part);
+    pool.set_junior_balance(
+        pool.junior_balance()
+            .checked_add(junior_part)
+            .ok_or(StakeError::Overflow)?,
+    );
+    pool.deficit_junior_written_off -= junior_part;
+    pool.deficit_written_off -= restored;
+    if pool.deficit_written_off == 0 {
+        pool.deficit_mode = 0;
+    }
+    Ok(restored)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Kani harnesses — u64 is tractable here: the transitions are
+// additions and comparisons, with no wide multiplication.
+// ═══════════════════════════════════════════════════════════════
+
+#[cfg(kani)]
+mod proofs {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    /// An arbitrary mode-0 pool (no tranches) satisfying `pool_invariant`.
+    fn any_pool() -> StakePool {
+        let mut pool = StakePool::zeroed();
+        pool.total_deposited = kani::any();
+        pool.total_withdrawn = kani::any();
+        pool.total_flushed = kani::any();
+        pool.total_returned = kani::any();
+        pool.total_fees_earned = kani::any();
+        pool.total_lp_supply = kani::any();
+        pool.total_recovered_from_wrapper = kani::any();
+        pool.set_realized_junior_loss(kani::any());
+        pool.set_epoch_high_water_tvl(kani::any());
+        pool.set_hwm_last_epoch(kani::any());
+        pool.set_hwm_floor_bps(kani::any());
+        kani::assume(pool_invariant(&pool));
+        pool
+    }
+
+    /// A flush moves exactly `amount` of value out of the pool and into the
+    /// wrapper-recoverable balance, and preserves the invariant.
+    #[kani::proof]
+    fn proof_flush_conserves_value() {
+        let mut pool = any_pool();
+        let amount: u64 = kani::any();
+        let pv = pool.total_pool_value().unwrap();
+        let recoverable = pool.wrapper_recoverable();
+
+        if book_flush(&mut pool, amount).is_ok() {
+            kani::cover!(amount > 0, "COVER: a non-zero flush books");
+            assert_eq!(pool.total_pool_value(), Some(pv - amount));
+            assert_eq!(pool.wrapper_recoverable(), recoverable + amount);
+            assert!(pool_invariant(&pool));
+        } else {
+            assert!(amount > pv || pool.total_flushed.checked_add(amount).is_none());
+        }
+    }
+
+    /// A recovery moves exactly `amount` of value back into the pool, out of
+    /// the wrapper-recoverable balance, and preserves the invariant.
+    #[kani::proof]
+    fn proof_recovery_conserves_value() {
+        let mut pool = any_pool();
+        let amount: u64 = kani::any();
+        let pv = pool.total_pool_value().unwrap();
+        let recoverable = pool.wrapper_recoverable();
+
+        if book_recovery(&mut pool, amount).is_ok() {
+            kani::cover!(amount > 0, "COVER: a non-zero recovery books");
+            assert!(amount <= recoverable);
+            assert_eq!(pool.wrapper_recoverable(), recoverable - amount);
+            // Exact whenever the new value is representable at all.
+            assert_eq!(
+                pool.total_pool_value().map(u128::from),
+                Some(pv as u128 + amount as u128).filter(|v| *v <= u64::MAX as u128)
+            );
+            assert!(pool.total_pool_value().is_none() || pool_invariant(&pool));
+        } else {
+            assert!(amount > recoverable || pool.total_returned.checked_add(amount).is_none());
+        }
+    }
+
+    /// `wrapper_recoverable` always converges: recovering the whole outstanding
+    /// balance succeeds whenever the vault can account for it, lands exactly
+    /// on zero (the H-1 resolve gate opens), and nothing more can be drawn.
+    #[kani::proof]
+    fn proof_wrapper_recoverable_converges_to_zero() {
+        let mut pool = any_pool();
+        let outstanding = pool.wrapper_recoverable();
+        kani::assume(outstanding > 0);
+        kani::assume(pool.total_returned.checked_add(outstanding).is_some());
+
+        assert!(book_recovery(&mut pool, outstanding).is_ok());
+        kani::cover!(
+            pool.realized_junior_loss() > 0,
+            "COVER: converges with a #161 write-off on the books"
+        );
+        assert_eq!(pool.wrapper_recoverable(), 0);
+        assert!(pool.wrapper_fully_recovered());
+        let extra: u64 = kani::any();
+        kani::assume(extra > 0);
+        assert!(book_recovery(&mut pool, extra).is_err());
+    }
+
+    /// A flush never freezes withdrawals through the HWM floor: the mark
+    /// drops with TVL, so a pool at its high-water mark before the flush can
+    /// still withdraw (at least down to the floor) after it, whatever
+    /// `refresh_hwm` does next in the same or a later epoch.
+    #[kani::proof]
+    fn proof_flush_never_freezes_hwm() {
+        let mut pool = any_pool();
+        let amount: u64 = kani::any();
+        let epoch: u64 = kani::any();
+        let tvl = pool.total_pool_value().unwrap();
+        let mark = pool.epoch_high_water_tvl();
+        kani::assume(mark <= tvl);
+
+        if book_flush(&mut pool, amount).is_err() {
+            return;
+        }
+        let tvl_after = pool.total_pool_value().unwrap();
+        let mark_after = pool.refresh_hwm(epoch, tvl_after);
+        kani::cover!(
+            amount > 0 && pool.hwm_floor_bps() > 0,
+            "COVER: a real flush under an active floor"
+        );
+        assert!(crate::math::hwm_withdrawal_allowed(
+            tvl_after,
+            mark_after,
+            pool.hwm_floor_bps()
+        ));
+    }
+
+    /// Fee accrual books exactly the vault surplus: afterwards the pool value
+    /// equals the vault balance, and nothing is booked at zero LP supply.
+    #[kani::proof]
+    fn proof_accrue_fees_conserves_value() {
+        let mut pool = any_pool();
+        let vault_balance: u64 = kani::any();
+        let pv = pool.total_pool_value().unwrap();
+
+        let booked = match accrue_fees(&mut pool, vault_balance) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        if pool.total_lp_supply == 0 || vault_balance <= pv {
+            assert_eq!(booked, 0);
+            assert_eq!(pool.total_pool_value(), Some(pv));
+        } else {
+            kani::cover!(booked > 0, "COVER: a surplus is accrued");
+            assert_eq!(booked, vault_balance - pv);
+            assert_eq!(pool.total_pool_value(), Some(vault_balance));
+            assert!(pool_invariant(&pool));
+        }
+    }
+
+    /// A deficit write-down lands the pool value exactly on the vault balance,
+    /// takes the shortfall from junior first, and preserves the invariant.
+    #[kani::proof]
+    fn proof_deficit_write_down_is_junior_first() {
+        let mut pool = any_pool();
+        pool.set_tranche_enabled(true);
+        pool.set_junior_balance(kani::any());
+        // Possibly already in deficit mode from an earlier write-down.
+        pool.deficit_written_off = kani::any();
+        kani::assume(pool_invariant(&pool));
+        let vault_balance: u64 = kani::any();
+        let pv = pool.total_pool_value().unwrap();
+        let junior = pool.effective_junior_balance();
+        let senior = pool.senior_balance();
+        kani::assume(senior.is_some());
+        let senior = senior.unwrap();
+
+        let shortfall = match write_down_deficit(&mut pool, vault_balance) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        if vault_balance >= pv {
+            assert_eq!(shortfall, 0);
+            return;
+        }
+        kani::cover!(shortfall > junior, "COVER: the deficit reaches senior");
+        let junior_share = shortfall.min(junior);
+        assert_eq!(shortfall, pv - vault_balance);
+        assert_eq!(pool.total_pool_value(), Some(vault_balance));
+        assert_eq!(pool.effective_junior_balance(), junior - junior_share);
+        assert_eq!(
+            pool.senior_balance(),
+            Some(senior - (shortfall - junior_share))
+        );
+        assert!(pool.in_deficit());
+        assert!(pool_invariant(&pool));
+    }
+
+    /// Restoring a write-off lifts the pool value by exactly the amount
+    /// restored, makes senior whole before junior, and leaves deficit mode
+    /// only once nothing is left written off.
+    #[kani::proof]
+    fn proof_deficit_restore_is_senior_first() {
+        let mut pool = any_pool();
+        pool.set_tranche_enabled(true);
+        pool.set_junior_balance(kani::any());
+        pool.deficit_written_off = kani::any();
+        pool.deficit_junior_written_off = kani::any();
+        pool.deficit_mode = 1;
+        kani::assume(pool_invariant(&pool));
+        let vault_balance: u64 = kani::any();
+        let pv = pool.total_pool_value().unwrap();
+        let written_off = pool.deficit_written_off;
+        let senior_loss = written_off - pool.deficit_junior_written_off;
+        let junior = pool.junior_balance();
+
+        let restored = match restore_deficit(&mut pool, vault_balance) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+        kani::cover!(restored > senior_loss, "COVER: recovery reaches junior");
+        assert_eq!(restored, vault_balance.saturating_sub(pv).min(written_off));
+        assert_eq!(pool.total_pool_value(), Some(pv + restored));
+        assert_eq!(
+            pool.junior_balance(),
+            junior + restored.saturating_sub(senior_loss)
+        );
+        assert_eq!(pool.in_deficit(), restored < written_off);
+        assert!(pool_invariant(&pool));
+    }
+
+    /// The genesis lock withholds exactly `MINIMUM_LIQUIDITY` and never mints
+    /// zero; later deposits pass through unchanged.
+    #[kani::proof]
+    fn proof_minimum_liquidity_lock() {
+        let supply_before: u64 = kani::any();
+        let lp_to_mint: u64 = kani::any();
+        match apply_minimum_liquidity_lock(supply_before, lp_to_mint) {
+            Ok(minted) if supply_before == 0 => {
+                kani::cover!(true, "COVER: genesis deposit mints");
+                assert!(minted > 0);
+                assert_eq!(minted + state::MINIMUM_LIQUIDITY, lp_to_mint);
+            }
+            Ok(minted) => assert_eq!(minted, lp_to_mint),
+            Err(_) => {
+                assert_eq!(supply_before, 0);
+                assert!(lp_to_mint <= state::MINIMUM_LIQUIDITY);
+            }
+        }
+    }
+}
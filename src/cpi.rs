@@ -21,10 +21,11 @@
 //! with kind byte = 2 (AUTHORITY_INSURANCE) and a 34-byte payload. The v17 auth
 //! overhaul replaced per-field authority mutation with a per-ASSET handler (tag 65
 //! `UpdateAssetAuthority`). The new wire is:
-//!   [tag=65u8][asset_index: u16 LE = 0x00 0x00][kind: u8 = 1][pubkey: 32 bytes]
+//!   [tag=65u8][asset_index: u16 LE][kind: u8 = 1][pubkey: 32 bytes]
 //!   = 36 bytes total.  THREE changes from the v16 wire: (1) tag 32→65, (2) kind
 //!   value FLIPPED 2→1 (ASSET_AUTH_INSURANCE=1, not AUTHORITY_INSURANCE=2), (3)
-//!   NEW 2-byte asset_index prefix (always 0 for the asset-0 insurance profile).
+//!   NEW 2-byte asset_index prefix — the pool's `wrapper_asset_index`, recorded at
+//!   InitPool (0 for single-asset markets).
 //! The 3-account shape is UNCHANGED from tag 32:
 //!   [0] current authority (signer)
 //!   [1] new authority (signer when new_pubkey != 0; no-op slot when burning to 0)
@@ -38,15 +39,30 @@
 //! owning program (us) that `invoke_signed`s the PDA as the new authority while
 //! the admin co-signs as the current authority. This is NOT a redundant proxy:
 //! the human admin literally cannot perform this bind directly.
+//!
+//! WIRE VERSIONING: each helper takes its instruction data from a `WrapperAbi`
+//! — one implementation per wrapper revision (v16, v17), chosen per pool by
+//! `StakePool::wrapper_abi_version`. The wires described in this file are v17's
+//! unless a comment says otherwise.
+//!
+//! MARKET PROBE: before building its instruction, every helper runs
+//! `probe_market` on the market account it is about to hand the wrapper, so a
+//! market of the wrong program or revision fails with a stake error instead of
+//! a wrapper `InvalidAccountData` revert.
 #![allow(clippy::too_many_arguments)]
 
 use solana_program::{
     account_info::AccountInfo,
     entrypoint::ProgramResult,
     instruction::{AccountMeta, Instruction},
+    msg,
     program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
 };
 
+use crate::{error::StakeError, state::StakePool};
+
 // Wrapper instruction tags (from percolator-prog/src/v16_program.rs ix::Instruction).
 const TAG_TOP_UP_INSURANCE: u8 = 9;
 /// UpdateAuthority (tag 32) — rotates the single market-level `cfg.marketauth`
@@ -63,28 +79,378 @@ const TAG_UPDATE_AUTHORITY: u8 = 32;
 /// `cfg.marketauth`. Per-asset authorities (including insurance_authority for
 /// asset 0) now go through tag 65 `UpdateAssetAuthority`.
 const TAG_UPDATE_ASSET_AUTHORITY: u8 = 65;
-/// asset_index for the asset-0 insurance profile (always 0 in the stake use-case).
-/// Encoded as u16 LE = [0x00, 0x00] in the 36-byte tag-65 wire.
-const ASSET_INDEX_ZERO: u16 = 0;
 /// UpdateAssetAuthority kind selector for insurance_authority.
 /// Source: v16_program.rs ASSET_AUTH_INSURANCE = 1.
 /// NOTE: this is DIFFERENT from the v16 AUTHORITY_INSURANCE=2 that tag 32 used.
 /// The footgun here is that both look like small integers but are defined in
 /// different constant families and must NOT be swapped.
-const ASSET_AUTH_INSURANCE: u8 = 1;
+pub const ASSET_AUTH_INSURANCE: u8 = 1;
 /// UpdateAssetAuthority kind selector for insurance_operator.
 /// Source: v16_program.rs ASSET_AUTH_INSURANCE_OPERATOR = 2.
 /// Must be moved (cannot burn to zero) to a key the admin does not control.
 /// In the secure-bind sequence we move it to the vault_auth PDA so the admin
 /// cannot drain via the local_authorized path in WithdrawInsuranceAsset (tag 57).
-const ASSET_AUTH_INSURANCE_OPERATOR: u8 = 2;
+pub const ASSET_AUTH_INSURANCE_OPERATOR: u8 = 2;
 /// UpdateAssetAuthority kind selector for asset_admin.
 /// Source: v16_program.rs ASSET_AUTH_ADMIN = 0.
 /// This is the ONLY authority that can be burned to zero (new_pubkey = [0;32]).
 /// Burning asset_admin irrevocably removes the admin's ability to rotate any of
 /// the asset's authorities (insurance, operator, backing, oracle) back to admin
 /// control. This is the final step of the secure-bind sequence.
-const ASSET_AUTH_ADMIN: u8 = 0;
+pub const ASSET_AUTH_ADMIN: u8 = 0;
+
+/// v16 `UpdateAuthority` (tag 32) kind selector for the market's insurance
+/// authority. v16 only; v17 moved it to tag 65 as `ASSET_AUTH_INSURANCE` = 1.
+const V16_AUTHORITY_INSURANCE: u8 = 2;
+
+// ═══════════════════════════════════════════════════════════════
+// WrapperAbi — one wire encoder per wrapper revision
+// ═══════════════════════════════════════════════════════════════
+// Every `cpi_*` helper below takes its instruction data from the pool's
+// `WrapperAbi`, picked by `StakePool::wrapper_abi_version`. The default
+// methods are the wires v16 and v17 share; an implementation overrides only
+// what its revision changed. Supporting the next wrapper revision means adding
+// an implementation and a version number, not editing an encoder that live
+// pools depend on.
+//
+// A version with no implementation fails every CPI with
+// `WrapperAbiUnsupported` instead of sending a wire the wrapper cannot decode.
+// That is what enforces "never run against a pre-v16 wrapper": the v12 tag-9
+// wire (u64 amount) is simply not an ABI this program knows.
+
+/// `StakePool::wrapper_abi_version` of a pool bound to a v16 wrapper.
+pub const WRAPPER_ABI_V16: u8 = 16;
+/// `StakePool::wrapper_abi_version` of a pool bound to a v17 wrapper: the
+/// `InitPool` default.
+pub const WRAPPER_ABI_V17: u8 = 17;
+
+/// Instruction-data encoders for one wrapper revision. Account lists are not
+/// part of it: every CPI's account shape is the same in v16 and v17.
+pub trait WrapperAbi {
+    /// The `StakePool::wrapper_abi_version` this ABI answers to.
+    fn version(&self) -> u8;
+
+    /// Number of asset slots in a market account of `market_len` bytes, or
+    /// None when no market of this revision has that length.
+    fn market_asset_capacity(&self, market_len: usize) -> Option<u16>;
+
+    /// The insurance balance of `asset_index` in a market account's data.
+    /// Call `probe_market_asset` on the account first.
+    fn asset_insurance_balance(
+        &self,
+        market_data: &[u8],
+        asset_index: u16,
+    ) -> Result<u128, ProgramError>;
+
+    /// Tag 9 `TopUpInsurance`: tag(1) + amount(16, u128 LE) = 17 bytes.
+    fn top_up_insurance(&self, amount: u64) -> Vec<u8> {
+        let mut data = Vec::with_capacity(17);
+        data.push(TAG_TOP_UP_INSURANCE);
+        data.extend_from_slice(&(amount as u128).to_le_bytes());
+        data
+    }
+
+    /// Tag 32 `UpdateAuthority` for `cfg.marketauth`:
+    /// tag(1) + new_pubkey(32) = 33 bytes.
+    fn update_authority(&self, new_authority: &[u8; 32]) -> Vec<u8> {
+        let mut data = Vec::with_capacity(33);
+        data.push(TAG_UPDATE_AUTHORITY);
+        data.extend_from_slice(new_authority);
+        data
+    }
+
+    /// Moves the `kind` (`ASSET_AUTH_*`) authority of `asset_index` to
+    /// `new_pubkey`; `[0; 32]` burns `ASSET_AUTH_ADMIN`.
+    fn update_asset_authority(
+        &self,
+        asset_index: u16,
+        kind: u8,
+        new_pubkey: &[u8; 32],
+    ) -> Result<Vec<u8>, ProgramError>;
+
+    /// Moves `amount` of `asset_index`'s insurance fund to the pool vault.
+    fn withdraw_insurance_asset(
+        &self,
+        asset_index: u16,
+        amount: u64,
+    ) -> Result<Vec<u8>, ProgramError>;
+
+    /// Tag 19 `ResolveMarket`: the bare tag, 1 byte.
+    fn resolve_market(&self) -> Vec<u8> {
+        vec![TAG_RESOLVE_MARKET]
+    }
+
+    /// Tag 86 `UpdateFeeSplit`: tag(1) + three u16 LE shares = 7 bytes.
+    fn update_fee_split(
+        &self,
+        creator_share_bps: u16,
+        lp_share_bps: u16,
+        insurance_share_bps: u16,
+    ) -> Vec<u8> {
+        let mut data = Vec::with_capacity(7);
+        data.push(TAG_UPDATE_FEE_SPLIT);
+        data.extend_from_slice(&creator_share_bps.to_le_bytes());
+        data.extend_from_slice(&lp_share_bps.to_le_bytes());
+        data.extend_from_slice(&insurance_share_bps.to_le_bytes());
+        data
+    }
+
+    /// Tag 88 `UpdateMaintenanceFeePerSlot`: tag(1) + u128 LE = 17 bytes.
+    fn update_maintenance_fee_per_slot(&self, maintenance_fee_per_slot: u128) -> Vec<u8> {
+        let mut data = Vec::with_capacity(17);
+        data.push(TAG_UPDATE_MAINTENANCE_FEE_PER_SLOT);
+        data.extend_from_slice(&maintenance_fee_per_slot.to_le_bytes()); // 16 bytes
+        data
+    }
+
+    /// Tag 51 `UpdateBackingFeePolicy`: tag(1) + three u16 LE = 7 bytes.
+    fn update_backing_fee_policy(
+        &self,
+        domain: u16,
+        fee_bps: u16,
+        insurance_share_bps: u16,
+    ) -> Vec<u8> {
+        let mut data = Vec::with_capacity(7);
+        data.push(TAG_UPDATE_BACKING_FEE_POLICY);
+        data.extend_from_slice(&domain.to_le_bytes());
+        data.extend_from_slice(&fee_bps.to_le_bytes());
+        data.extend_from_slice(&insurance_share_bps.to_le_bytes());
+        data
+    }
+
+    /// Tag 55 `UpdateTradeFeePolicy`: tag(1) + u64 LE = 9 bytes.
+    fn update_trade_fee_policy(&self, trade_fee_base_bps: u64) -> Vec<u8> {
+        let mut data = Vec::with_capacity(9);
+        data.push(TAG_UPDATE_TRADE_FEE_POLICY);
+        data.extend_from_slice(&trade_fee_base_bps.to_le_bytes()); // 8 bytes
+        data
+    }
+}
+
+/// The v16 wrapper. It predates per-asset authorities: tag 32 carried a kind
+/// byte and rotated the market's single insurance authority (kind 2), and
+/// there was no tag 65 or tag 57. Only the insurance-authority bind/rotate of
+/// asset 0 has a v16 wire; the operator move, the admin burn, other assets and
+/// insurance recovery fail with `WrapperAbiUnsupported`.
+pub struct WrapperV16;
+
+impl WrapperAbi for WrapperV16 {
+    fn version(&self) -> u8 {
+        WRAPPER_ABI_V16
+    }
+
+    /// Only the single-asset v16 market has a measured size.
+    fn market_asset_capacity(&self, market_len: usize) -> Option<u16> {
+        (market_len == V16_MARKET_LEN_CAP1).then_some(1)
+    }
+
+    /// The v16 market's engine layout is not pinned here.
+    fn asset_insurance_balance(
+        &self,
+        _market_data: &[u8],
+        _asset_index: u16,
+    ) -> Result<u128, ProgramError> {
+        msg!("Error: wrapper v16 market insurance balance is not readable");
+        Err(StakeError::WrapperAbiUnsupported.into())
+    }
+
+    /// tag(32) + kind(1, = 2) + new_pubkey(32) = 34 bytes.
+    fn update_asset_authority(
+        &self,
+        asset_index: u16,
+        kind: u8,
+        new_pubkey: &[u8; 32],
+    ) -> Result<Vec<u8>, ProgramError> {
+        if asset_index != 0 || kind != ASSET_AUTH_INSURANCE {
+            msg!(
+                "Error: wrapper v16 cannot set authority kind {} of asset {}",
+                kind,
+                asset_index
+            );
+            return Err(StakeError::WrapperAbiUnsupported.into());
+        }
+        let mut data = Vec::with_capacity(34);
+        data.push(TAG_UPDATE_AUTHORITY);
+        data.push(V16_AUTHORITY_INSURANCE);
+        data.extend_from_slice(new_pubkey);
+        Ok(data)
+    }
+
+    fn withdraw_insurance_asset(
+        &self,
+        _asset_index: u16,
+        _amount: u64,
+    ) -> Result<Vec<u8>, ProgramError> {
+        msg!("Error: wrapper v16 has no WithdrawInsuranceAsset (tag 57)");
+        Err(StakeError::WrapperAbiUnsupported.into())
+    }
+}
+
+/// The v17 wrapper (collision row 43): per-asset authorities through tag 65
+/// and per-asset insurance recovery through tag 57.
+pub struct WrapperV17;
+
+impl WrapperAbi for WrapperV17 {
+    fn version(&self) -> u8 {
+        WRAPPER_ABI_V17
+    }
+
+    /// `MARKET_GROUP_OFF + MARKET_GROUP_LEN + capacity * MARKET_ASSET_SLOT_LEN`,
+    /// capacity >= 1.
+    fn market_asset_capacity(&self, market_len: usize) -> Option<u16> {
+        let slots = market_len.checked_sub(V17_MARKET_GROUP_OFF + V17_MARKET_GROUP_LEN)?;
+        if slots == 0 || slots % V17_MARKET_ASSET_SLOT_LEN != 0 {
+            return None;
+        }
+        u16::try_from(slots / V17_MARKET_ASSET_SLOT_LEN).ok()
+    }
+
+    /// u128 LE at `V17_ASSET_INSURANCE_BALANCE_OFF` in the asset's slot.
+    fn asset_insurance_balance(
+        &self,
+        market_data: &[u8],
+        asset_index: u16,
+    ) -> Result<u128, ProgramError> {
+        let off = V17_MARKET_GROUP_OFF
+            + V17_MARKET_GROUP_LEN
+            + asset_index as usize * V17_MARKET_ASSET_SLOT_LEN
+            + V17_ASSET_INSURANCE_BALANCE_OFF;
+        market_data
+            .get(off..off + 16)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u128::from_le_bytes)
+            .ok_or_else(|| StakeError::WrapperMarketLayoutMismatch.into())
+    }
+
+    /// tag(65) + asset_index(2, u16 LE) + kind(1) + new_pubkey(32) = 36 bytes.
+    fn update_asset_authority(
+        &self,
+        asset_index: u16,
+        kind: u8,
+        new_pubkey: &[u8; 32],
+    ) -> Result<Vec<u8>, ProgramError> {
+        let mut data = Vec::with_capacity(36);
+        data.push(TAG_UPDATE_ASSET_AUTHORITY);
+        data.extend_from_slice(&asset_index.to_le_bytes()); // 2 bytes
+        data.push(kind);
+        data.extend_from_slice(new_pubkey);
+        Ok(data)
+    }
+
+    /// tag(57) + asset_index(2, u16 LE) + amount(16, u128 LE) = 19 bytes.
+    fn withdraw_insurance_asset(
+        &self,
+        asset_index: u16,
+        amount: u64,
+    ) -> Result<Vec<u8>, ProgramError> {
+        let mut data = Vec::with_capacity(19);
+        data.push(TAG_WITHDRAW_INSURANCE_ASSET);
+        data.extend_from_slice(&asset_index.to_le_bytes()); // 2 bytes
+        data.extend_from_slice(&(amount as u128).to_le_bytes()); // 16 bytes u128 LE
+        Ok(data)
+    }
+}
+
+/// The ABI for a `wrapper_abi_version`, or `WrapperAbiUnsupported`.
+pub fn wrapper_abi(version: u8) -> Result<&'static dyn WrapperAbi, ProgramError> {
+    match version {
+        WRAPPER_ABI_V16 => Ok(&WrapperV16),
+        WRAPPER_ABI_V17 => Ok(&WrapperV17),
+        _ => {
+            msg!("Error: unsupported wrapper ABI version {}", version);
+            Err(StakeError::WrapperAbiUnsupported.into())
+        }
+    }
+}
+
+/// The ABI of the wrapper `pool` CPIs into.
+pub fn pool_wrapper_abi(pool: &StakePool) -> Result<&'static dyn WrapperAbi, ProgramError> {
+    wrapper_abi(pool.wrapper_abi_version)
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Market probe — read-only layout check before any wrapper CPI
+// ═══════════════════════════════════════════════════════════════
+// A wrapper handed a market it was not built for reverts with its own
+// InvalidAccountData, which says nothing about why. Market sizes moved between
+// revisions (v16 = 3107 bytes, v17 = 2987 → 3003 → 3067 → 3147 for one asset),
+// so a stake build paired with the wrong wrapper, or a pool pointed at a stale
+// or foreign account, is caught here first. The probe reads three things:
+//   * owner  — the market belongs to the wrapper program being called;
+//   * length — a valid market length for the pool's ABI (which pins the
+//              revision) with room for the pool's asset index;
+//   * header — the 16-byte account header InitMarket writes is not all zero.
+// The header's encoding is the wrapper's business and is not pinned here; the
+// length arithmetic is what tells the revisions apart.
+//
+// v17 layout (percolator_prog::state::market_account_len_for_capacity):
+//   header(16) + WrapperConfigV16(576) = MARKET_GROUP_OFF(592)
+//   + MARKET_GROUP_LEN(758) + capacity * MARKET_ASSET_SLOT_LEN(1797).
+
+/// Length of the wrapper's market account header.
+pub const MARKET_HEADER_LEN: usize = 16;
+/// v17 `MARKET_GROUP_OFF`: the header plus the 576-byte wrapper config.
+const V17_MARKET_GROUP_OFF: usize = MARKET_HEADER_LEN + 576;
+/// v17 `MARKET_GROUP_LEN`.
+const V17_MARKET_GROUP_LEN: usize = 758;
+/// v17 `MARKET_ASSET_SLOT_LEN`: one per asset the market can hold.
+const V17_MARKET_ASSET_SLOT_LEN: usize = 1797;
+/// Offset of the asset's insurance balance (u128 LE) inside a v17 asset slot,
+/// after the asset admin, insurance authority and insurance operator keys.
+const V17_ASSET_INSURANCE_BALANCE_OFF: usize = 96;
+/// v16 `market_account_len_for_capacity(1)`, measured at v16-sync @5260d1b.
+const V16_MARKET_LEN_CAP1: usize = 3107;
+
+/// Check that `market` is an initialized market of `percolator_program` laid
+/// out for `abi`, and return its asset capacity. Reads only.
+pub fn probe_market(
+    abi: &dyn WrapperAbi,
+    percolator_program: &Pubkey,
+    market: &AccountInfo,
+) -> Result<u16, ProgramError> {
+    if market.owner != percolator_program {
+        msg!(
+            "Error: market {} is owned by {}, not the wrapper {}",
+            market.key,
+            market.owner,
+            percolator_program
+        );
+        return Err(StakeError::WrapperMarketOwnerMismatch.into());
+    }
+    let data = market.try_borrow_data()?;
+    let capacity = abi.market_asset_capacity(data.len()).ok_or_else(|| {
+        msg!(
+            "Error: market is {} bytes, not a wrapper v{} market layout",
+            data.len(),
+            abi.version()
+        );
+        ProgramError::from(StakeError::WrapperMarketLayoutMismatch)
+    })?;
+    if data[..MARKET_HEADER_LEN].iter().all(|&b| b == 0) {
+        msg!("Error: market header is zero — InitMarket has not run on this account");
+        return Err(StakeError::WrapperMarketUninitialized.into());
+    }
+    Ok(capacity)
+}
+
+/// `probe_market`, and require `asset_index` to be one of the market's slots.
+pub fn probe_market_asset(
+    abi: &dyn WrapperAbi,
+    percolator_program: &Pubkey,
+    market: &AccountInfo,
+    asset_index: u16,
+) -> ProgramResult {
+    let capacity = probe_market(abi, percolator_program, market)?;
+    if asset_index >= capacity {
+        msg!(
+            "Error: asset index {} is outside the market's {} asset slot(s)",
+            asset_index,
+            capacity
+        );
+        return Err(StakeError::WrapperMarketLayoutMismatch.into());
+    }
+    Ok(())
+}
 
 // ═══════════════════════════════════════════════════════════════
 // TopUpInsurance (Tag 9) — v16 contract
@@ -110,13 +476,17 @@ const ASSET_AUTH_ADMIN: u8 = 0;
 //   * LIVE MODE REQUIRED. v16 rejects tag 9 unless the market is Live
 //     (v16_program.rs:7566,7580) — checked BEFORE the authority gate, so a
 //     not-yet-Live market reverts Custom(21) EngineLockActive.
+//   * NO ASSET INDEX. Unlike tags 57 and 65, tag 9 carries no asset_index, so
+//     the pool's `wrapper_asset_index` does not reach this CPI.
 //
 // CUTOVER ATOMICITY: this 16-byte wire MUST ship in the same cutover bundle as
 // the v16 wrapper. NEVER deploy this stake build against a live pre-v16 (v12)
 // wrapper — that wrapper decodes tag 9 as u64 (8 bytes) and would reject the
 // 16-byte payload. See ~/wrapper-engine-deep-audit/V16_DIVERGENCES.md (stake).
+// `WrapperAbi` has no v12 implementation, so no pool can be configured for one.
 
 pub fn cpi_top_up_insurance<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     signer: &AccountInfo<'a>, // vault_auth PDA (we sign) — must == market insurance_authority
     slab: &AccountInfo<'a>,
@@ -126,10 +496,9 @@ pub fn cpi_top_up_insurance<'a>(
     amount: u64,
     signer_seeds: &[&[u8]],
 ) -> ProgramResult {
+    probe_market(abi, percolator_program.key, slab)?;
     // tag(1) + u128 amount(16) = 17 bytes.
-    let mut data = Vec::with_capacity(17);
-    data.push(TAG_TOP_UP_INSURANCE);
-    data.extend_from_slice(&(amount as u128).to_le_bytes());
+    let data = abi.top_up_insurance(amount);
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -179,15 +548,15 @@ pub fn cpi_top_up_insurance<'a>(
 // admin fields, not the market-wide marketauth this function rotates.
 
 pub fn cpi_update_authority<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     current_admin: &AccountInfo<'a>, // current marketauth; signs the outer tx
     new_authority: &AccountInfo<'a>, // pool PDA; co-signs via invoke_signed
     slab: &AccountInfo<'a>,          // market, writable
     new_authority_seeds: &[&[u8]],   // pool PDA seeds
 ) -> ProgramResult {
-    let mut data = Vec::with_capacity(33);
-    data.push(TAG_UPDATE_AUTHORITY);
-    data.extend_from_slice(new_authority.key.as_ref());
+    probe_market(abi, percolator_program.key, slab)?;
+    let data = abi.update_authority(&new_authority.key.to_bytes());
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -211,12 +580,12 @@ pub fn cpi_update_authority<'a>(
 // ═══════════════════════════════════════════════════════════════
 // Accounts (v16_program.rs handle_update_asset_authority L9407-9412):
 //   [current(signer), new_authority(signer when new_pubkey!=0), market(w)]
-// Data: tag(1) + asset_index(2, u16 LE = 0) + kind(1) + new_pubkey(32) = 36 bytes
+// Data: tag(1) + asset_index(2, u16 LE) + kind(1) + new_pubkey(32) = 36 bytes
 //
 // V17 WIRE (collision row 43): tag 32 → 65; kind 2 → 1; +2 bytes asset_index.
-// Binds the market's per-asset `insurance_authority` (asset 0) to our
-// `vault_auth` PDA so the subsequent TopUpInsurance flush (signed by the PDA)
-// passes v17's authority gate. `admin` co-signs as the CURRENT authority (must
+// Binds the `insurance_authority` of the pool's asset (`wrapper_asset_index`)
+// to our `vault_auth` PDA so the subsequent TopUpInsurance flush (signed by the
+// PDA) passes v17's authority gate. `admin` co-signs as the CURRENT authority (must
 // equal profile.insurance_authority, which InitMarket seeds to admin via
 // asset_admin bootstrap), and the PDA co-signs as the NEW authority via
 // invoke_signed. After this bind, only the PDA can rotate the authority again —
@@ -224,18 +593,21 @@ pub fn cpi_update_authority<'a>(
 // RotateInsuranceAuthority (tag 20) is the deliberate admin-gated escape.
 
 pub fn cpi_bind_insurance_authority<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     admin: &AccountInfo<'a>, // current authority (== profile.insurance_authority at bind time); signs outer tx
     vault_auth: &AccountInfo<'a>, // new authority = our PDA; signs via invoke_signed
     market: &AccountInfo<'a>, // the slab/market account (writable, wrapper-owned)
+    asset_index: u16,        // pool.wrapper_asset_index
     signer_seeds: &[&[u8]],  // vault_auth PDA seeds
 ) -> ProgramResult {
-    // tag(1) + asset_index(2, u16 LE = 0) + kind(1) + new_pubkey(32) = 36 bytes.
-    let mut data = Vec::with_capacity(36);
-    data.push(TAG_UPDATE_ASSET_AUTHORITY);
-    data.extend_from_slice(&ASSET_INDEX_ZERO.to_le_bytes()); // 2 bytes, always 0x00 0x00
-    data.push(ASSET_AUTH_INSURANCE); // kind = 1
-    data.extend_from_slice(vault_auth.key.as_ref()); // new_pubkey = PDA
+    probe_market_asset(abi, percolator_program.key, market, asset_index)?;
+    // kind = 1, new_pubkey = PDA
+    let data = abi.update_asset_authority(
+        asset_index,
+        ASSET_AUTH_INSURANCE,
+        &vault_auth.key.to_bytes(),
+    )?;
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -270,18 +642,21 @@ pub fn cpi_bind_insurance_authority<'a>(
 // removes the admin's ability to rotate this back.
 
 pub fn cpi_bind_insurance_operator<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     admin: &AccountInfo<'a>, // current insurance_operator (== admin at bootstrap); signer
     vault_auth: &AccountInfo<'a>, // new operator = our PDA; co-signs via invoke_signed
     market: &AccountInfo<'a>, // the slab/market account (writable, wrapper-owned)
+    asset_index: u16,        // pool.wrapper_asset_index
     signer_seeds: &[&[u8]],  // vault_auth PDA seeds
 ) -> ProgramResult {
-    // tag(1) + asset_index(2, u16 LE = 0) + kind(1) + new_pubkey(32) = 36 bytes.
-    let mut data = Vec::with_capacity(36);
-    data.push(TAG_UPDATE_ASSET_AUTHORITY);
-    data.extend_from_slice(&ASSET_INDEX_ZERO.to_le_bytes()); // 2 bytes, always 0x00 0x00
-    data.push(ASSET_AUTH_INSURANCE_OPERATOR); // kind = 2
-    data.extend_from_slice(vault_auth.key.as_ref()); // new_pubkey = PDA
+    probe_market_asset(abi, percolator_program.key, market, asset_index)?;
+    // kind = 2, new_pubkey = PDA
+    let data = abi.update_asset_authority(
+        asset_index,
+        ASSET_AUTH_INSURANCE_OPERATOR,
+        &vault_auth.key.to_bytes(),
+    )?;
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -319,20 +694,19 @@ pub fn cpi_bind_insurance_operator<'a>(
 // in the transaction; no signer check is performed on it by the wrapper).
 //
 // Account layout: [current(signer=admin), new_authority(any, not checked), market(w)]
-// Wire: tag(65) + asset_index(0 u16 LE) + kind(0) + new_pubkey([0;32]) = 36 bytes.
+// Wire: tag(65) + asset_index(u16 LE) + kind(0) + new_pubkey([0;32]) = 36 bytes.
 
 pub fn cpi_burn_asset_admin<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     admin: &AccountInfo<'a>,      // current asset_admin; signer
     vault_auth: &AccountInfo<'a>, // placeholder new_authority slot (not checked by wrapper for zero burn)
     market: &AccountInfo<'a>,     // the slab/market account (writable, wrapper-owned)
+    asset_index: u16,             // pool.wrapper_asset_index
 ) -> ProgramResult {
-    // tag(1) + asset_index(2, u16 LE = 0) + kind(1) + new_pubkey(32, all zeros) = 36 bytes.
-    let mut data = Vec::with_capacity(36);
-    data.push(TAG_UPDATE_ASSET_AUTHORITY);
-    data.extend_from_slice(&ASSET_INDEX_ZERO.to_le_bytes()); // 2 bytes, always 0x00 0x00
-    data.push(ASSET_AUTH_ADMIN); // kind = 0
-    data.extend_from_slice(&[0u8; 32]); // new_pubkey = burn (all zeros)
+    probe_market_asset(abi, percolator_program.key, market, asset_index)?;
+    // kind = 0, new_pubkey = burn (all zeros)
+    let data = abi.update_asset_authority(asset_index, ASSET_AUTH_ADMIN, &[0u8; 32])?;
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -362,18 +736,21 @@ pub fn cpi_burn_asset_admin<'a>(
 //   4. BurnAssetAdmin (tag 21) — only if asset_admin not already zero
 
 pub fn cpi_rotate_insurance_operator<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     vault_auth: &AccountInfo<'a>, // CURRENT operator = our PDA; signs via invoke_signed
     new_target: &AccountInfo<'a>, // NEW operator (admin-specified, non-zero); co-signs outer tx
     market: &AccountInfo<'a>,     // the slab/market account (writable, wrapper-owned)
+    asset_index: u16,             // pool.wrapper_asset_index
     signer_seeds: &[&[u8]],       // vault_auth PDA seeds
 ) -> ProgramResult {
-    // tag(1) + asset_index(2, u16 LE = 0) + kind(1) + new_pubkey(32) = 36 bytes.
-    let mut data = Vec::with_capacity(36);
-    data.push(TAG_UPDATE_ASSET_AUTHORITY);
-    data.extend_from_slice(&ASSET_INDEX_ZERO.to_le_bytes()); // 2 bytes, always 0x00 0x00
-    data.push(ASSET_AUTH_INSURANCE_OPERATOR); // kind = 2
-    data.extend_from_slice(new_target.key.as_ref()); // new_pubkey = rotation target
+    probe_market_asset(abi, percolator_program.key, market, asset_index)?;
+    // kind = 2, new_pubkey = rotation target
+    let data = abi.update_asset_authority(
+        asset_index,
+        ASSET_AUTH_INSURANCE_OPERATOR,
+        &new_target.key.to_bytes(),
+    )?;
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -415,18 +792,21 @@ pub fn cpi_rotate_insurance_operator<'a>(
 // new_pubkey = new_target.key (the rotation destination, not our PDA).
 
 pub fn cpi_rotate_insurance_authority<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     vault_auth: &AccountInfo<'a>, // CURRENT authority = our PDA; signs via invoke_signed
     new_target: &AccountInfo<'a>, // NEW authority (admin-specified, non-zero); co-signs the outer tx
     market: &AccountInfo<'a>,     // the slab/market account (writable, wrapper-owned)
+    asset_index: u16,             // pool.wrapper_asset_index
     signer_seeds: &[&[u8]],       // vault_auth PDA seeds
 ) -> ProgramResult {
-    // tag(1) + asset_index(2, u16 LE = 0) + kind(1) + new_pubkey(32) = 36 bytes.
-    let mut data = Vec::with_capacity(36);
-    data.push(TAG_UPDATE_ASSET_AUTHORITY);
-    data.extend_from_slice(&ASSET_INDEX_ZERO.to_le_bytes()); // 2 bytes, always 0x00 0x00
-    data.push(ASSET_AUTH_INSURANCE); // kind = 1
-    data.extend_from_slice(new_target.key.as_ref()); // new_pubkey = rotation target
+    probe_market_asset(abi, percolator_program.key, market, asset_index)?;
+    // kind = 1, new_pubkey = rotation target
+    let data = abi.update_asset_authority(
+        asset_index,
+        ASSET_AUTH_INSURANCE,
+        &new_target.key.to_bytes(),
+    )?;
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -448,7 +828,7 @@ pub fn cpi_rotate_insurance_authority<'a>(
 // ═══════════════════════════════════════════════════════════════
 // WithdrawInsuranceAsset (Tag 57) — PDA-signed insurance recovery
 // ═══════════════════════════════════════════════════════════════
-// Wire: [57u8][asset_index: u16 LE = 0][amount: u128 LE] = 19 bytes.
+// Wire: [57u8][asset_index: u16 LE][amount: u128 LE] = 19 bytes.
 // Account order (verified against tests/v17_stake_insurance_e2e.rs
 // withdraw_insurance_asset_ix and wrapper handle_withdraw_insurance_asset):
 //   [0] operator      (vault_auth PDA, signer via invoke_signed) — must == insurance_operator
@@ -471,6 +851,7 @@ pub fn cpi_rotate_insurance_authority<'a>(
 const TAG_WITHDRAW_INSURANCE_ASSET: u8 = 57;
 
 pub fn cpi_withdraw_insurance_asset<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     vault_auth: &AccountInfo<'a>, // insurance_operator = our PDA; signs via invoke_signed
     market: &AccountInfo<'a>,     // wrapper market / slab (writable)
@@ -478,14 +859,12 @@ pub fn cpi_withdraw_insurance_asset<'a>(
     wrapper_vault: &AccountInfo<'a>, // wrapper insurance vault token account (source)
     wrapper_vault_auth: &AccountInfo<'a>, // wrapper vault authority PDA (read-only)
     token_program: &AccountInfo<'a>,
+    asset_index: u16, // pool.wrapper_asset_index
     amount: u64,
     signer_seeds: &[&[u8]],
 ) -> ProgramResult {
-    // tag(1) + asset_index(2, u16 LE = 0) + amount(16, u128 LE) = 19 bytes.
-    let mut data = Vec::with_capacity(19);
-    data.push(TAG_WITHDRAW_INSURANCE_ASSET);
-    data.extend_from_slice(&ASSET_INDEX_ZERO.to_le_bytes()); // 2 bytes, always 0x00 0x00
-    data.extend_from_slice(&(amount as u128).to_le_bytes()); // 16 bytes u128 LE
+    probe_market_asset(abi, percolator_program.key, market, asset_index)?;
+    let data = abi.withdraw_insurance_asset(asset_index, amount)?;
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -572,14 +951,16 @@ pub fn cpi_withdraw_insurance_asset<'a>(
 const TAG_RESOLVE_MARKET: u8 = 19;
 
 pub fn cpi_resolve_market<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     pool_pda: &AccountInfo<'a>, // marketauth (rotated by InitPool); signs via invoke_signed
     slab: &AccountInfo<'a>,     // market, writable
     pool_seeds: &[&[u8]],       // pool PDA seeds: [b"stake_pool", slab, bump]
 ) -> ProgramResult {
+    probe_market(abi, percolator_program.key, slab)?;
     // tag(1) = 1 byte. No payload — matches `19 => Self::ResolveMarket` (zero
     // additional bytes consumed by the wrapper's decoder).
-    let data = vec![TAG_RESOLVE_MARKET];
+    let data = abi.resolve_market();
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -633,6 +1014,7 @@ pub fn cpi_resolve_market<'a>(
 const TAG_UPDATE_FEE_SPLIT: u8 = 86;
 
 pub fn cpi_update_fee_split<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     pool_pda: &AccountInfo<'a>, // marketauth (rotated by InitPool); signs via invoke_signed
     slab: &AccountInfo<'a>,     // market, writable
@@ -641,11 +1023,8 @@ pub fn cpi_update_fee_split<'a>(
     insurance_share_bps: u16,
     pool_seeds: &[&[u8]], // pool PDA seeds: [b"stake_pool", slab, bump]
 ) -> ProgramResult {
-    let mut data = Vec::with_capacity(7);
-    data.push(TAG_UPDATE_FEE_SPLIT);
-    data.extend_from_slice(&creator_share_bps.to_le_bytes());
-    data.extend_from_slice(&lp_share_bps.to_le_bytes());
-    data.extend_from_slice(&insurance_share_bps.to_le_bytes());
+    probe_market(abi, percolator_program.key, slab)?;
+    let data = abi.update_fee_split(creator_share_bps, lp_share_bps, insurance_share_bps);
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -670,15 +1049,15 @@ pub fn cpi_update_fee_split<'a>(
 const TAG_UPDATE_MAINTENANCE_FEE_PER_SLOT: u8 = 88;
 
 pub fn cpi_update_maintenance_fee_per_slot<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     pool_pda: &AccountInfo<'a>, // marketauth; signs via invoke_signed
     slab: &AccountInfo<'a>,     // market, writable
     maintenance_fee_per_slot: u128,
     pool_seeds: &[&[u8]], // pool PDA seeds: [b"stake_pool", slab, bump]
 ) -> ProgramResult {
-    let mut data = Vec::with_capacity(17);
-    data.push(TAG_UPDATE_MAINTENANCE_FEE_PER_SLOT);
-    data.extend_from_slice(&maintenance_fee_per_slot.to_le_bytes()); // 16 bytes
+    probe_market(abi, percolator_program.key, slab)?;
+    let data = abi.update_maintenance_fee_per_slot(maintenance_fee_per_slot);
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -702,6 +1081,7 @@ pub fn cpi_update_maintenance_fee_per_slot<'a>(
 const TAG_UPDATE_BACKING_FEE_POLICY: u8 = 51;
 
 pub fn cpi_update_backing_fee_policy<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     vault_auth: &AccountInfo<'a>, // insurance_authority (bound by tag 19); signs via invoke_signed
     slab: &AccountInfo<'a>,       // market, writable
@@ -710,11 +1090,8 @@ pub fn cpi_update_backing_fee_policy<'a>(
     insurance_share_bps: u16,
     vault_auth_seeds: &[&[u8]], // vault_auth PDA seeds: [b"vault_auth", pool, bump]
 ) -> ProgramResult {
-    let mut data = Vec::with_capacity(7);
-    data.push(TAG_UPDATE_BACKING_FEE_POLICY);
-    data.extend_from_slice(&domain.to_le_bytes());
-    data.extend_from_slice(&fee_bps.to_le_bytes());
-    data.extend_from_slice(&insurance_share_bps.to_le_bytes());
+    probe_market(abi, percolator_program.key, slab)?;
+    let data = abi.update_backing_fee_policy(domain, fee_bps, insurance_share_bps);
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -741,15 +1118,15 @@ pub fn cpi_update_backing_fee_policy<'a>(
 const TAG_UPDATE_TRADE_FEE_POLICY: u8 = 55;
 
 pub fn cpi_update_trade_fee_policy<'a>(
+    abi: &dyn WrapperAbi,
     percolator_program: &AccountInfo<'a>,
     vault_auth: &AccountInfo<'a>, // asset-0 insurance_authority; signs via invoke_signed
     slab: &AccountInfo<'a>,       // market, writable
     trade_fee_base_bps: u64,
     vault_auth_seeds: &[&[u8]], // vault_auth PDA seeds: [b"vault_auth", pool, bump]
 ) -> ProgramResult {
-    let mut data = Vec::with_capacity(9);
-    data.push(TAG_UPDATE_TRADE_FEE_POLICY);
-    data.extend_from_slice(&trade_fee_base_bps.to_le_bytes()); // 8 bytes
+    probe_market(abi, percolator_program.key, slab)?;
+    let data = abi.update_trade_fee_policy(trade_fee_base_bps);
 
     let ix = Instruction {
         program_id: *percolator_program.key,
@@ -778,7 +1155,6 @@ mod tag_tests {
             TAG_UPDATE_ASSET_AUTHORITY, 65,
             "TAG_UPDATE_ASSET_AUTHORITY mismatch (v17 collision row 43: was 32)"
         );
-        assert_eq!(ASSET_INDEX_ZERO, 0, "ASSET_INDEX_ZERO must be 0");
         assert_eq!(
             ASSET_AUTH_INSURANCE, 1,
             "ASSET_AUTH_INSURANCE mismatch (v17 footgun: was 2 in v16 AUTHORITY_INSURANCE)"
@@ -797,7 +1173,7 @@ mod tag_tests {
         let pda = [9u8; 32];
         let mut data = Vec::with_capacity(36);
         data.push(TAG_UPDATE_ASSET_AUTHORITY); // byte 0: tag = 65
-        data.extend_from_slice(&ASSET_INDEX_ZERO.to_le_bytes()); // bytes 1-2: asset_index = 0
+        data.extend_from_slice(&0u16.to_le_bytes()); // bytes 1-2: asset_index = 0
         data.push(ASSET_AUTH_INSURANCE); // byte 3: kind = 1
         data.extend_from_slice(&pda); // bytes 4-35: new_pubkey
 
@@ -890,7 +1266,7 @@ mod tag_tests {
         let pda = [7u8; 32];
         let mut data = Vec::with_capacity(36);
         data.push(TAG_UPDATE_ASSET_AUTHORITY); // byte 0: tag = 65
-        data.extend_from_slice(&ASSET_INDEX_ZERO.to_le_bytes()); // bytes 1-2
+        data.extend_from_slice(&0u16.to_le_bytes()); // bytes 1-2
         data.push(ASSET_AUTH_INSURANCE_OPERATOR); // byte 3: kind = 2
         data.extend_from_slice(&pda); // bytes 4-35
 
@@ -908,7 +1284,7 @@ mod tag_tests {
     fn test_cpi_burn_asset_admin_wire_shape() {
         let mut data = Vec::with_capacity(36);
         data.push(TAG_UPDATE_ASSET_AUTHORITY); // byte 0: tag = 65
-        data.extend_from_slice(&ASSET_INDEX_ZERO.to_le_bytes()); // bytes 1-2
+        data.extend_from_slice(&0u16.to_le_bytes()); // bytes 1-2
         data.push(ASSET_AUTH_ADMIN); // byte 3: kind = 0
         data.extend_from_slice(&[0u8; 32]); // bytes 4-35: zero burn
 
@@ -959,7 +1335,7 @@ mod tag_tests {
         let amount: u64 = 250_000;
         let mut data = Vec::with_capacity(19);
         data.push(TAG_WITHDRAW_INSURANCE_ASSET); // byte 0: tag = 57
-        data.extend_from_slice(&ASSET_INDEX_ZERO.to_le_bytes()); // bytes 1-2: asset_index = 0
+        data.extend_from_slice(&0u16.to_le_bytes()); // bytes 1-2: asset_index = 0
         data.extend_from_slice(&(amount as u128).to_le_bytes()); // bytes 3-18: amount u128 LE
 
         assert_eq!(data.len(), 19, "tag-57 wire must be 19 bytes");
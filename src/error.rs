@@ -85,6 +85,89 @@ pub enum StakeError {
     /// below MINIMUM_LIQUIDITY at genesis are rejected rather than silently minting
     /// 0 (or underflowing) LP to the first depositor.
     DepositBelowMinimumLiquidity = 28,
+    /// `DepositFor`: the beneficiary has not opted in to deposits from this signer
+    /// (no deposit record, or its `authorized_depositor` is a different key).
+    DepositorNotAuthorized = 29,
+    /// `CloseDeposit`: the position still holds LP (`lp_amount > 0`). Withdraw
+    /// it all first.
+    DepositNotEmpty = 30,
+    /// `RedeemFinal` / `SweepAndClosePool` on a pool that has not been finalized.
+    PoolNotFinalized = 31,
+    /// `Finalize` on a pool that is already finalized.
+    PoolAlreadyFinalized = 32,
+    /// `Finalize` before the market is resolved (`SetMarketResolved` /
+    /// `AdminResolveMarket`).
+    MarketNotResolved = 33,
+    /// `SweepAndClosePool` before `FINAL_SWEEP_GRACE_SLOTS` have elapsed since
+    /// `Finalize`. LP holders are still inside their redemption window.
+    FinalSweepTooEarly = 34,
+    /// A wrapper fee-policy proxy (tags 25..28) was sent directly, or a
+    /// `SetFeePolicyBounds` / `SetVetoThreshold` sent directly would loosen
+    /// what LPs are protected by. Those calls
+    /// only execute through `ProposeFeeChange` → wait `TIMELOCK_SLOTS` →
+    /// `CommitFeeChange`.
+    FeeChangeRequiresTimelock = 35,
+    /// `CommitFeeChange` / `CancelFeeChange` on a proposal that is no longer
+    /// pending (already committed or cancelled).
+    ProposalNotPending = 36,
+    /// A committed fee change falls outside the pool's `FeePolicyBounds`
+    /// (minimum LP share, maximum trade fee, or per-change delta).
+    FeePolicyBoundViolated = 37,
+    /// A committed fee change comes sooner than
+    /// `FeePolicyBounds::min_change_interval_slots` after the previous one.
+    FeeChangeTooSoon = 38,
+    /// `CastVeto` on a pool whose `veto_threshold_bps` is 0.
+    VetoDisabled = 39,
+    /// `ReclaimVeto` while the vote's proposal is still pending.
+    VetoStillLocked = 40,
+    /// An admin action or `AcceptCouncil` signed by fewer distinct council
+    /// members than the roster's threshold.
+    CouncilThresholdNotMet = 41,
+    /// `AcceptCouncil` with no roster change proposed.
+    NoPendingCouncil = 42,
+    /// `AcceptRole` with no holder proposed for that role.
+    NoPendingRole = 43,
+    /// `AcceptAdmin` / `CommitCooldownIncrease` on a proposal older than
+    /// `PROPOSAL_EXPIRY_SLOTS`; it has to be proposed again.
+    ProposalExpired = 44,
+    /// The setting is frozen by a `LockConfig` bit.
+    ConfigLocked = 45,
+    /// `RenounceAdmin` before the flush policy, HWM config and deposit cap
+    /// are all locked.
+    ConfigNotLocked = 46,
+    /// A permissionless flush that would push the wrapper-held share of the
+    /// pool past the locked flush policy.
+    FlushPolicyExceeded = 47,
+    /// A token-moving CPI returned Ok but the pool vault's balance changed by
+    /// something other than the requested amount.
+    VaultDeltaMismatch = 48,
+    /// `AssertPoolInvariants`: the ledger itself is inconsistent
+    /// (`accounting::pool_invariant` fails).
+    InvariantLedgerInconsistent = 49,
+    /// `AssertPoolInvariants`: the vault holds less than `total_pool_value()`.
+    InvariantVaultShortfall = 50,
+    /// `AssertPoolInvariants`: LP mint supply exceeds the booked
+    /// `total_lp_supply` net of the dead-share lock.
+    InvariantLpSupplyMismatch = 51,
+    /// `AssertPoolInvariants`: the junior tranche is worth more than the pool.
+    InvariantJuniorExceedsPoolValue = 52,
+    /// `AssertPoolInvariants`: the wrapper market holds less insurance for the
+    /// pool's asset than `wrapper_recoverable()`.
+    InvariantWrapperShortfall = 53,
+    /// `DeclareDeficit` while the vault holds at least `total_pool_value()`.
+    NoDeficit = 54,
+    /// Deposits and flushes are closed once the pool is in deficit mode.
+    PoolInDeficit = 55,
+    /// The pool's `wrapper_abi_version` is unknown, or its wrapper revision has
+    /// no wire for the requested CPI.
+    WrapperAbiUnsupported = 56,
+    /// The market account is not owned by the wrapper program being called.
+    WrapperMarketOwnerMismatch = 57,
+    /// The market account's length is not a market of the pool's wrapper
+    /// revision, or it has no slot for the pool's asset index.
+    WrapperMarketLayoutMismatch = 58,
+    /// The market account's header is zero: InitMarket never ran on it.
+    WrapperMarketUninitialized = 59,
 }
 
 impl From<StakeError> for ProgramError {
@@ -123,6 +206,147 @@ pub fn error_hint(code: u32) -> &'static str {
         23 => "No pending admin — there is no admin transfer to accept (propose one first, or it was cancelled)",
         24 => "Insurance loss outstanding — total_flushed > total_returned. Junior tranche deposits are paused, and AdminResolveMarket/SetMarketResolved are blocked until RecoverFlushedInsurance fully returns the flushed insurance (resolving first would strand it — recovery requires LIVE mode)",
         28 => "Deposit below minimum liquidity — the pool's first-ever deposit must exceed MINIMUM_LIQUIDITY so a permanent dead-share floor can be locked (N7 anti-inflation hardening); deposit a larger amount",
+        29 => "Depositor not authorized — the beneficiary must first sign SetAuthorizedDepositor naming this signer before DepositFor can fund their position",
+        30 => "Deposit not empty — withdraw the whole position before closing its deposit account",
+        31 => "Pool not finalized — the admin must call Finalize (after resolution and full insurance recovery) first",
+        32 => "Pool already finalized — the pool is in its terminal phase; redeem LP with RedeemFinal",
+        33 => "Market not resolved — resolve the market before finalizing the pool",
+        34 => "Final sweep too early — the post-Finalize redemption grace period has not elapsed yet",
+        35 => "Fee change requires timelock — submit the proxy call via ProposeFeeChange and commit it after the timelock",
+        36 => "Proposal not pending — this proposal was already committed or cancelled",
+        37 => "Fee policy bound violated — the change is outside this pool's stake-side fee bounds; loosen them first via a timelocked SetFeePolicyBounds proposal",
+        38 => "Fee change too soon — the pool's minimum interval between fee changes has not elapsed since the last one",
+        39 => "Veto disabled — this pool has no veto threshold configured",
+        40 => "Veto still locked — the proposal this vote is against is still pending; reclaim after it is committed, cancelled or vetoed",
+        41 => "Council threshold not met — include at least the threshold number of distinct council members as signers",
+        42 => "No pending council — propose a roster with ProposeCouncil before accepting it",
+        43 => "No pending role holder — the admin must propose one with ProposeRole before it can be accepted",
+        44 => "Proposal expired — it is older than the expiry window; propose it again (or cancel it)",
+        45 => "Config locked — this setting was frozen with LockConfig and can no longer change",
+        46 => "Config not locked — lock the flush policy, HWM config and deposit cap (LockConfig) before RenounceAdmin",
+        47 => "Flush policy exceeded — flush less, or wait for insurance to be recovered; only the flush operator may exceed the policy",
+        48 => "Vault delta mismatch — the CPI moved a different amount than requested (wrapper fee, rounding or partial fill); nothing was booked, so check the wrapper version before retrying",
+        49 => "Invariant violated: ledger inconsistent — total_pool_value() overflows, more was settled than flushed or returned, or the HWM floor exceeds 100%",
+        50 => "Invariant violated: vault shortfall — the pool vault holds less than total_pool_value(); tokens left the vault without being booked",
+        51 => "Invariant violated: LP supply mismatch — the LP mint has more tokens than total_lp_supply minus the dead-share lock; LP was minted without being booked",
+        52 => "Invariant violated: junior exceeds pool value — the junior tranche balance is larger than the whole pool",
+        53 => "Invariant violated: wrapper shortfall — the wrapper vault holds less than the insurance this pool can still recover",
+        54 => "No deficit — the vault holds at least the pool value, so there is nothing to write down",
+        55 => "Pool in deficit — the vault was found short and written down; deposits and flushes are closed, withdrawals pay out pro rata",
+        56 => "Wrapper ABI unsupported — the pool's wrapper_abi_version is unknown, or that wrapper revision has no instruction for this CPI (v16 has no per-asset authorities or tag 57)",
+        57 => "Wrapper market owner mismatch — the slab is not owned by the pool's percolator_program; the pool points at the wrong program or a foreign account",
+        58 => "Wrapper market layout mismatch — the slab's size is not a market of the pool's wrapper revision, or it has no slot for the pool's asset index; check the wrapper version this stake build expects",
+        59 => "Wrapper market uninitialized — the slab's account header is zero; run InitMarket on it first",
         _ => "Unknown error — check the error code and pool state",
     }
 }
+
+// ─────────────────────────────────────────────────────────────────────────
+// Wrapper (percolator-prog) errors surfaced through our CPIs.
+//
+// A failed CPI returns the WRAPPER's `Custom(n)` unchanged, and those numbers
+// overlap ours: a flush rejected with wrapper 8 (Unauthorized) reaches the
+// client as the same `Custom(8)` as our `MarketResolved`. Only the simulation
+// logs say which program raised it. The catalog below lists the wrapper codes
+// our CPIs are known to hit and what they mean for the stake instruction that
+// made the call.
+// ─────────────────────────────────────────────────────────────────────────
+
+/// Wrapper `Unauthorized`: the CPI signer is not the authority the wrapper has
+/// on record for that call.
+pub const WRAPPER_ERR_UNAUTHORIZED: u32 = 8;
+/// Wrapper `EngineLockActive`: the market is not Live (mode != 0). Checked
+/// before the authority gate, so it wins over `Unauthorized`.
+pub const WRAPPER_ERR_ENGINE_LOCK_ACTIVE: u32 = 21;
+
+/// Name of a known wrapper error code.
+pub fn wrapper_error_name(code: u32) -> Option<&'static str> {
+    match code {
+        WRAPPER_ERR_UNAUTHORIZED => Some("Unauthorized"),
+        WRAPPER_ERR_ENGINE_LOCK_ACTIVE => Some("EngineLockActive"),
+        _ => None,
+    }
+}
+
+/// Which wrapper call a stake instruction makes; the same wrapper code means
+/// different things on each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WrapperCpi {
+    /// InitPool / InitTradingPool: UpdateAuthority handing `marketauth` to the
+    /// pool PDA.
+    MarketAuthorityHandoff,
+    /// FlushToInsurance: TopUpInsurance, signed by `vault_auth`.
+    TopUpInsurance,
+    /// RecoverFlushedInsurance: WithdrawInsuranceAsset, signed by `vault_auth`.
+    WithdrawInsuranceAsset,
+    /// BindInsuranceAuthority, RotateInsurance*, BurnAssetAdmin:
+    /// UpdateAssetAuthority.
+    AssetAuthority,
+    /// AdminResolveMarket and fee proxies 25/26: `marketauth`-gated, the pool
+    /// PDA signs.
+    MarketAuthProxy,
+    /// Fee proxies 27/28: `insurance_authority`-gated, `vault_auth` signs.
+    InsuranceAuthProxy,
+}
+
+impl WrapperCpi {
+    /// The wrapper call made by stake instruction `tag`, if it makes exactly
+    /// one kind. `CommitFeeChange` (40) replays the proposed proxy, so pass
+    /// the proposed call's tag for it instead.
+    pub fn for_stake_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 | 13 => Some(Self::MarketAuthorityHandoff),
+            3 => Some(Self::TopUpInsurance),
+            23 => Some(Self::WithdrawInsuranceAsset),
+            19..=22 => Some(Self::AssetAuthority),
+            24..=26 => Some(Self::MarketAuthProxy),
+            27 | 28 => Some(Self::InsuranceAuthProxy),
+            _ => None,
+        }
+    }
+}
+
+const UNKNOWN_WRAPPER_ERROR: &str = "Unknown wrapper error — see the percolator-prog error catalog";
+
+/// Stake-level meaning and next step for wrapper error `code` raised by `cpi`.
+pub fn wrapper_error_hint(cpi: WrapperCpi, code: u32) -> &'static str {
+    use WrapperCpi::*;
+    match (cpi, code) {
+        (MarketAuthorityHandoff, WRAPPER_ERR_UNAUTHORIZED) => "Pool creator is not the market's marketauth — the current marketauth must sign InitPool",
+        (TopUpInsurance, WRAPPER_ERR_UNAUTHORIZED) => "insurance_authority not bound — call BindInsuranceAuthority (tag 19) before flushing",
+        (TopUpInsurance, WRAPPER_ERR_ENGINE_LOCK_ACTIVE) => "Market not Live — flushes only work while the market is Live",
+        (WithdrawInsuranceAsset, WRAPPER_ERR_UNAUTHORIZED) => "insurance_operator is not this pool's vault_auth — call BindInsuranceAuthority (tag 19), or re-bind after a rotation",
+        (WithdrawInsuranceAsset, WRAPPER_ERR_ENGINE_LOCK_ACTIVE) => "Market not Live — recovery needs a Live market; insurance still flushed at resolution is stranded",
+        (AssetAuthority, WRAPPER_ERR_UNAUTHORIZED) => "Signer is not the current asset authority — already bound, rotated away, or asset_admin already burned",
+        (AssetAuthority, WRAPPER_ERR_ENGINE_LOCK_ACTIVE) => "Market not Live — bind, rotate and burn before resolving the market",
+        (MarketAuthProxy, WRAPPER_ERR_UNAUTHORIZED) => "Pool PDA is not the market's marketauth — the InitPool handoff is missing or was undone",
+        (MarketAuthProxy, WRAPPER_ERR_ENGINE_LOCK_ACTIVE) => "Market not Live — it has already been resolved",
+        (InsuranceAuthProxy, WRAPPER_ERR_UNAUTHORIZED) => "insurance_authority not bound to this pool's vault_auth — call BindInsuranceAuthority (tag 19) first",
+        _ => UNKNOWN_WRAPPER_ERROR,
+    }
+}
+
+/// Explain a failed simulation of stake instruction `stake_tag` from its logs.
+///
+/// The first `Program <id> failed: custom program error: 0x..` line is the
+/// innermost failure: if `<id>` is not `stake_program` the code is the
+/// wrapper's and is described with [`wrapper_error_hint`], otherwise with
+/// [`error_hint`]. Returns None when the logs hold no custom-error failure.
+pub fn describe_simulation_failure(
+    stake_program: &solana_program::pubkey::Pubkey,
+    stake_tag: u8,
+    logs: &[String],
+) -> Option<String> {
+    let (program, code) = logs.iter().find_map(|line| {
+        let rest = line.strip_prefix("Program ")?;
+        let (program, hex) = rest.split_once(" failed: custom program error: 0x")?;
+        Some((program, u32::from_str_radix(hex.trim(), 16).ok()?))
+    })?;
+    if program == stake_program.to_string() {
+        return Some(format!("stake error {}: {}", code, error_hint(code)));
+    }
+    let name = wrapper_error_name(code).unwrap_or("unknown");
+    let hint = WrapperCpi::for_stake_tag(stake_tag)
+        .map_or(UNKNOWN_WRAPPER_ERROR, |cpi| wrapper_error_hint(cpi, code));
+    Some(format!("wrapper error {} ({}): {}", code, name, hint))
+}
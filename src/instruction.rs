@@ -1,3 +1,4 @@
+use crate::state::FeePolicyBounds;
 use solana_program::program_error::ProgramError;
 
 /// Instructions for the Percolator Insurance LP Staking program.
@@ -46,6 +47,15 @@ use solana_program::program_error::ProgramError;
 /// anyone, which is the mechanical root of the "fee split is unachievable"
 /// finding.
 ///
+/// TIMELOCKED — none of tags 25..28 executes when sent directly; each is
+/// rejected with `FeeChangeRequiresTimelock`. Its exact instruction data is
+/// instead submitted through `ProposeFeeChange` (tag 39), which records it in a
+/// `StakeProposal` account LPs can read, and `CommitFeeChange` (tag 40) replays
+/// it — with the account list documented on the tag — once `TIMELOCK_SLOTS`
+/// have passed. `CancelFeeChange` (tag 41) withdraws it. This is the same
+/// ≥48h exit window the #242 cooldown timelock gives, applied to the
+/// parameters that set LP yield and risk.
+///
 /// NOT PROXYABLE — wrapper tag 69 `RestartAssetOracle`. It is gated on the
 /// per-asset `asset_admin`, and this program moves `asset_admin` to exactly one
 /// place: `[0u8; 32]`, via `BurnAssetAdmin` (tag 21). It is never rotated to
@@ -84,9 +94,20 @@ pub enum StakeInstruction {
     ///   8. `[]` Token program
     ///   9. `[]` System program
     ///  10. `[]` Rent sysvar
+    ///
+    /// Data: cooldown_slots (8) + deposit_cap (8) + the pool's
+    /// `FeePolicyBounds` (22). The bounds are required: a pool never starts
+    /// unbounded by omission, only by sending `FeePolicyBounds::UNBOUNDED`.
+    /// They may be followed by the wrapper asset index (u16 LE, 2) whose insurance profile the pool backs;
+    /// it defaults to 0 and is fixed for the pool's lifetime. The index may be
+    /// followed by the wrapper ABI version (1, `cpi::WRAPPER_ABI_*`), default
+    /// v17, which selects the wire every CPI of this pool is encoded with.
     InitPool {
         cooldown_slots: u64,
         deposit_cap: u64,
+        fee_policy: FeePolicyBounds,
+        asset_index: u16,
+        wrapper_abi_version: u8,
     },
 
     /// 1: Deposit collateral into the stake vault. Mints LP tokens pro-rata.
@@ -105,7 +126,9 @@ pub enum StakeInstruction {
     ///  10. `[]` System program
     Deposit { amount: u64 },
 
-    /// 2: Withdraw collateral by burning LP tokens. Subject to cooldown.
+    /// 2: Withdraw collateral by burning LP tokens. Subject to cooldown, which is
+    /// waived while an unexpired cooldown increase or admin transfer is pending
+    /// (rage-quit).
     ///
     /// Accounts:
     ///   0. `[signer]` User withdrawing
@@ -118,13 +141,22 @@ pub enum StakeInstruction {
     ///   7. `[writable]` Deposit PDA (per-user, cooldown check)
     ///   8. `[]` Token program
     ///   9. `[]` Clock sysvar
-    Withdraw { lp_amount: u64 },
+    ///
+    /// Optional trailing `close_if_empty` byte (0 or 1; absent = 0): when set and
+    /// this withdrawal empties the position, the deposit PDA is closed in the
+    /// same instruction (see `CloseDeposit`, tag 35) and its rent returned to
+    /// the user, who must then be passed writable.
+    Withdraw {
+        lp_amount: u64,
+        close_if_empty: bool,
+    },
 
     /// 3: CPI into percolator wrapper's TopUpInsurance to move collateral from
-    /// stake vault → wrapper insurance fund.
+    /// stake vault → wrapper insurance fund. Anyone may call it once the flush
+    /// policy is locked, as long as the flush stays within that policy.
     ///
     /// Accounts:
-    ///   0. `[signer]` Caller (admin-only per C10 fix)
+    ///   0. `[signer]` Caller (flush operator, or the admin while unassigned; C10)
     ///   1. `[writable]` Pool PDA
     ///   2. `[writable]` Pool vault token account (source)
     ///   3. `[]` Vault authority PDA (signs CPI)
@@ -277,7 +309,9 @@ pub enum StakeInstruction {
     /// 5: ProposeAdmin — step 1 of two-step admin rotation. The CURRENT admin
     /// proposes a new admin, written to pool.pending_admin. The proposed admin
     /// does not gain any authority until they call AcceptAdmin (step 2).
-    /// Proposing the zero pubkey CANCELS an outstanding proposal.
+    /// Proposing the zero pubkey CANCELS an outstanding proposal. While one is
+    /// outstanding, withdrawals skip the per-user cooldown. A proposal expires
+    /// `PROPOSAL_EXPIRY_SLOTS` after it was (last) made, and the waiver with it.
     ///
     /// This safe ownership-transfer idiom (propose + accept) prevents handing the
     /// pool to a key nobody controls (a one-step transfer to a typo'd address
@@ -291,7 +325,8 @@ pub enum StakeInstruction {
     /// 6: AcceptAdmin — step 2 of two-step admin rotation. The PENDING admin
     /// (the proposed new admin) signs to take ownership: pool.admin =
     /// pool.pending_admin, then pending_admin is cleared. Requires an outstanding
-    /// proposal (pending_admin != 0) and the signer to equal pending_admin.
+    /// proposal (pending_admin != 0) and the signer to equal pending_admin; an
+    /// expired proposal fails with `ProposalExpired`.
     ///
     /// Accounts:
     ///   0. `[signer]` Pending admin (the proposed new admin)
@@ -301,7 +336,10 @@ pub enum StakeInstruction {
     /// 7: ProposeCooldownIncrease — step 1 of the #242 cooldown-increase timelock.
     /// The admin proposes a NEW (larger) `cooldown_slots`; it does not take effect until
     /// CommitCooldownIncrease is called after TIMELOCK_SLOTS (≈48h), guaranteeing LP
-    /// holders an exit window. A decrease/unchanged value is rejected here (use
+    /// holders an exit window: while it is pending, withdrawals skip the per-user
+    /// cooldown. Once committed it binds only positions topped up afterwards;
+    /// existing positions keep the cooldown recorded at their last deposit.
+    /// A decrease/unchanged value is rejected here (use
     /// UpdateConfig, which applies decreases immediately).
     ///
     /// Accounts:
@@ -311,7 +349,8 @@ pub enum StakeInstruction {
     ProposeCooldownIncrease { new_cooldown_slots: u64 },
 
     /// 8: CommitCooldownIncrease — step 2 of the #242 timelock. Applies the pending
-    /// cooldown increase, but only after TIMELOCK_SLOTS have elapsed since the proposal.
+    /// cooldown increase, but only after TIMELOCK_SLOTS have elapsed since the proposal
+    /// and before it expires (`PROPOSAL_EXPIRY_SLOTS`, else `ProposalExpired`).
     ///
     /// Accounts:
     ///   0. `[signer]` Admin
@@ -353,10 +392,13 @@ pub enum StakeInstruction {
 
     /// 13: Initialize pool in trading LP mode (pool_mode = 1).
     ///
-    /// Accounts: same as InitPool
+    /// Accounts and data: same as InitPool
     InitTradingPool {
         cooldown_slots: u64,
         deposit_cap: u64,
+        fee_policy: FeePolicyBounds,
+        asset_index: u16,
+        wrapper_abi_version: u8,
     },
 
     /// 14: Set high-water mark configuration.
@@ -418,8 +460,8 @@ pub enum StakeInstruction {
     /// a PDA cannot sign a top-level transaction. Without this proxy, NO key could
     /// ever resolve a stake-initialized market: every InitPool market would be
     /// permanently stuck in Live mode (mode == 0), with the terminal insurance
-    /// withdrawal path unreachable forever. This instruction lets the pool admin
-    /// trigger the CPI, with the pool PDA signing via `invoke_signed` using its own
+    /// withdrawal path unreachable forever. This instruction lets the pool's
+    /// admin trigger the CPI, with the pool PDA signing via `invoke_signed` using its own
     /// seeds (`[b"stake_pool", slab, bump]`) as the marketauth — see
     /// `cpi::cpi_resolve_market` for the byte-for-byte wire proof against the
     /// deployed wrapper.
@@ -471,11 +513,16 @@ pub enum StakeInstruction {
     /// those bounds: a second copy would drift from the first the moment either
     /// side is retuned. Bad shares fail at the wrapper with its own error.
     ///
-    /// AUTHORITY: `pool.admin`, mirroring `AdminResolveMarket`.
+    /// STAKE-SIDE POLICY is separate: at commit, `lp_share_bps` must satisfy
+    /// the pool's own `FeePolicyBounds` (minimum, per-change delta, interval) —
+    /// LP terms the pool promises inside the wrapper's limits.
+    ///
+    /// AUTHORITY: the fee manager (`ROLE_FEE_MANAGER`; the admin authority
+    /// while unassigned).
     ///
     /// Accounts:
-    ///   0. `[signer]` Admin (must equal pool.admin)
-    ///   1. `[]` Pool PDA (the marketauth; signs the CPI via invoke_signed)
+    ///   0. `[signer]` Fee manager (ROLE_FEE_MANAGER holder; the admin while unassigned)
+    ///   1. `[writable]` Pool PDA (the marketauth; signs the CPI via invoke_signed)
     ///   2. `[writable]` Slab / market account (wrapper-owned)
     ///   3. `[]` Percolator program
     AdminUpdateFeeSplit {
@@ -496,13 +543,15 @@ pub enum StakeInstruction {
     /// ~1.8e19 of the valid `MAX_PROTOCOL_FEE_ABS` (1e36) range unreachable.
     ///
     /// The wrapper enforces `maintenance_fee_per_slot <= MAX_PROTOCOL_FEE_ABS`;
-    /// this program does not duplicate that bound.
+    /// this program does not duplicate that bound, and the pool's
+    /// `FeePolicyBounds` do not cover it (it is not an LP fee-share term).
     ///
-    /// AUTHORITY: `pool.admin`, mirroring `AdminResolveMarket`.
+    /// AUTHORITY: the fee manager (`ROLE_FEE_MANAGER`; the admin authority
+    /// while unassigned).
     ///
     /// Accounts:
-    ///   0. `[signer]` Admin (must equal pool.admin)
-    ///   1. `[]` Pool PDA (the marketauth; signs the CPI via invoke_signed)
+    ///   0. `[signer]` Fee manager (ROLE_FEE_MANAGER holder; the admin while unassigned)
+    ///   1. `[writable]` Pool PDA (the marketauth; signs the CPI via invoke_signed)
     ///   2. `[writable]` Slab / market account (wrapper-owned)
     ///   3. `[]` Percolator program
     AdminUpdateMaintenanceFeePerSlot { maintenance_fee_per_slot: u128 },
@@ -525,18 +574,24 @@ pub enum StakeInstruction {
     ///
     /// `domain` selects the backing bucket; the wrapper maps `asset_index =
     /// domain / 2` and gates on THAT asset's `insurance_authority`. This program
-    /// does not restrict `domain`: `BindInsuranceAuthority` binds asset 0 only,
-    /// so any domain >= 2 resolves to an asset whose `insurance_authority` is
-    /// not `vault_auth` and the wrapper fails closed on its own. Constraining it
+    /// does not restrict `domain`: `BindInsuranceAuthority` binds only the
+    /// pool's `wrapper_asset_index`, so a domain resolving to any other asset
+    /// meets an `insurance_authority` that is not `vault_auth` and the wrapper
+    /// fails closed on its own. Constraining it
     /// here would duplicate wrapper logic and break if multi-asset binding is
     /// added later.
     ///
-    /// AUTHORITY: `pool.admin`, mirroring `AdminResolveMarket` and
-    /// `BindInsuranceAuthority` (which is likewise `pool.admin`-gated).
+    /// At commit, `fee_bps` must not exceed the pool's
+    /// `FeePolicyBounds::max_backing_fee_bps`, must move at most
+    /// `max_change_delta_bps` from the last backing fee this program set for
+    /// the same domain, and the change must respect the minimum interval.
+    ///
+    /// AUTHORITY: the fee manager (`ROLE_FEE_MANAGER`; the admin authority
+    /// while unassigned — the same check `BindInsuranceAuthority` uses).
     ///
     /// Accounts:
-    ///   0. `[signer]` Admin (must equal pool.admin)
-    ///   1. `[]` Pool PDA (used to derive + verify vault_auth; not a signer)
+    ///   0. `[signer]` Fee manager (ROLE_FEE_MANAGER holder; the admin while unassigned)
+    ///   1. `[writable]` Pool PDA (used to derive + verify vault_auth; not a signer)
     ///   2. `[]` Vault authority PDA (the insurance_authority; signs via invoke_signed)
     ///   3. `[writable]` Slab / market account (wrapper-owned)
     ///   4. `[]` Percolator program
@@ -556,16 +611,430 @@ pub enum StakeInstruction {
     ///
     /// The wrapper enforces `trade_fee_base_bps <= max_trading_fee_bps` and
     /// `<= MAX_DYNAMIC_TRADE_FEE_BPS`; this program does not duplicate those.
+    /// Its own, tighter `FeePolicyBounds` (maximum, per-change delta, interval)
+    /// are checked at commit.
     ///
-    /// AUTHORITY: `pool.admin`, mirroring `AdminResolveMarket`.
+    /// AUTHORITY: the fee manager (`ROLE_FEE_MANAGER`; the admin authority
+    /// while unassigned).
     ///
     /// Accounts:
-    ///   0. `[signer]` Admin (must equal pool.admin)
-    ///   1. `[]` Pool PDA (used to derive + verify vault_auth; not a signer)
+    ///   0. `[signer]` Fee manager (ROLE_FEE_MANAGER holder; the admin while unassigned)
+    ///   1. `[writable]` Pool PDA (used to derive + verify vault_auth; not a signer)
     ///   2. `[]` Vault authority PDA (the insurance_authority; signs via invoke_signed)
     ///   3. `[writable]` Slab / market account (wrapper-owned)
     ///   4. `[]` Percolator program
     AdminUpdateTradeFeePolicy { trade_fee_base_bps: u64 },
+
+    /// 29: DepositFor — fund a (senior/global) position owned by `beneficiary`.
+    ///
+    /// Identical pricing, cap, HWM and anti-snipe gates to `Deposit`; only the
+    /// identities split. The signer pays collateral from its OWN token account
+    /// (same delegation guard as `Deposit`), while the LP is minted to a token
+    /// account owned by `beneficiary` and the deposit record — and therefore the
+    /// cooldown clock — is the beneficiary's `[b"stake_deposit", pool,
+    /// beneficiary]` PDA.
+    ///
+    /// OPT-IN REQUIRED: every deposit restarts the whole position's cooldown
+    /// (BUG-8), so an unrestricted DepositFor would let anyone lock a stranger's
+    /// withdrawals with dust. The beneficiary's deposit record must already exist
+    /// and name this signer as its `authorized_depositor` (set by the beneficiary
+    /// via `SetAuthorizedDepositor`, tag 31); otherwise `DepositorNotAuthorized`.
+    ///
+    /// Accounts: identical to `Deposit` (tag 1), except
+    ///   5. `[writable]` Beneficiary's LP token account (must be owned by beneficiary)
+    ///   7. `[writable]` Beneficiary's deposit PDA (must already exist — opt-in)
+    DepositFor { beneficiary: [u8; 32], amount: u64 },
+
+    /// 30: WithdrawTo — `Withdraw` with the collateral destination bound to a
+    /// named recipient wallet.
+    ///
+    /// Plain `Withdraw` already accepts any collateral-mint token account as its
+    /// destination (account 5). `WithdrawTo` additionally takes the recipient
+    /// wallet as account 10 and requires the destination token account to be
+    /// OWNED by it, so a custodian routing to a treasury gets an on-chain check
+    /// that the ATA it passed is really the treasury's, not a look-alike.
+    /// Cooldown, HWM and LP-ownership rules are exactly `Withdraw`'s.
+    ///
+    /// Accounts: identical to `Withdraw` (tag 2), plus
+    ///  10. `[]` Recipient wallet (must own account 5)
+    WithdrawTo { lp_amount: u64 },
+
+    /// 31: SetAuthorizedDepositor — the position owner opts in to (or, with
+    /// `depositor == [0; 32]`, revokes) `DepositFor` from one third-party funder.
+    ///
+    /// Creates the owner's deposit PDA if it does not exist yet (owner pays
+    /// rent). A freshly created record is NOT an open position
+    /// (`is_initialized == 0`); the first deposit into it initializes it exactly
+    /// like a first `Deposit` would. The authorization survives a full withdrawal
+    /// and lasts until the owner replaces or revokes it.
+    ///
+    /// Accounts:
+    ///   0. `[signer, writable]` Position owner (beneficiary; pays rent)
+    ///   1. `[]` Pool PDA
+    ///   2. `[writable]` Owner's deposit PDA (created if needed)
+    ///   3. `[]` System program
+    SetAuthorizedDepositor { depositor: [u8; 32] },
+
+    /// 32: OperatorWithdraw — the position's delegated operator (set via
+    /// `SetDepositOperator`, tag 33) withdraws on the owner's behalf.
+    ///
+    /// Proceeds ALWAYS go to a collateral token account owned by the position
+    /// owner (account 10); the operator cannot name any other destination. The
+    /// LP is burned from the owner's LP token account by the pool's
+    /// `lp_delegate` PDA, which `SetDepositOperator` approved as its SPL
+    /// delegate. The operator can only withdraw: it cannot deposit, so it cannot
+    /// open a position in the other tranche or restart the cooldown. Cooldown,
+    /// HWM and tranche pricing are exactly `Withdraw`'s.
+    ///
+    /// Accounts: identical to `Withdraw` (tag 2) except
+    ///   0. `[signer]` Operator (instead of the owner)
+    ///   2. `[writable]` Owner's LP token account
+    ///   5. `[writable]` Owner's collateral token account (must be owned by account 10)
+    ///   7. `[writable]` Owner's deposit PDA
+    /// plus
+    ///  10. `[]` Position owner wallet
+    ///  11. `[]` LP delegate PDA (`["lp_delegate", pool]`)
+    OperatorWithdraw { lp_amount: u64 },
+
+    /// 33: SetDepositOperator — the position owner names an operator allowed to
+    /// `OperatorWithdraw` (tag 32) from their position.
+    ///
+    /// Requires an existing deposit record. A v1 (152-byte) record is
+    /// reallocated to the v2 size on first use, owner paying the rent delta.
+    /// Also approves the pool's `lp_delegate` PDA as SPL delegate (for
+    /// `u64::MAX`) on the owner's LP token account, which the operator burn
+    /// needs. Replacing the operator is another `SetDepositOperator`.
+    ///
+    /// Accounts:
+    ///   0. `[signer, writable]` Position owner (pays any realloc rent)
+    ///   1. `[]` Pool PDA
+    ///   2. `[writable]` Owner's deposit PDA
+    ///   3. `[writable]` Owner's LP token account
+    ///   4. `[]` LP delegate PDA
+    ///   5. `[]` Token program
+    ///   6. `[]` System program
+    SetDepositOperator { operator: [u8; 32] },
+
+    /// 34: RevokeDepositOperator — clear the position's operator and, if the
+    /// owner's LP token account is still delegated to the pool's `lp_delegate`,
+    /// revoke that SPL delegation. A delegation to anyone else is left alone.
+    ///
+    /// Accounts:
+    ///   0. `[signer]` Position owner
+    ///   1. `[]` Pool PDA
+    ///   2. `[writable]` Owner's deposit PDA
+    ///   3. `[writable]` Owner's LP token account
+    ///   4. `[]` LP delegate PDA
+    ///   5. `[]` Token program
+    RevokeDepositOperator,
+
+    /// 35: CloseDeposit — close a fully exited deposit PDA and return its rent
+    /// to the owner.
+    ///
+    /// Requires `lp_amount == 0` (`DepositNotEmpty` otherwise) unless the pool
+    /// has been closed by `SweepAndClosePool`, after which any record of it may
+    /// be closed: its LP no longer redeems for anything. The record is
+    /// zeroed, shrunk to zero length and handed back to the System program, so
+    /// nothing of it survives a same-transaction lamport refund; a later deposit
+    /// recreates it from scratch. Any `SetAuthorizedDepositor` opt-in or
+    /// operator stored on it is dropped with it.
+    ///
+    /// Accounts:
+    ///   0. `[signer, writable]` Position owner (receives the rent)
+    ///   1. `[]` Pool PDA (live, or closed by `SweepAndClosePool`)
+    ///   2. `[writable]` Owner's deposit PDA
+    CloseDeposit,
+
+    /// 36: Finalize (admin) — move a resolved pool into its terminal sunset
+    /// phase and name the wallet that receives the final sweep.
+    ///
+    /// Requires the market to be resolved (`MarketNotResolved`) and every
+    /// wrapper-recoverable token to be back (`wrapper_fully_recovered()`, else
+    /// `InsuranceLossOutstanding`). Irreversible. Deposits are already closed by
+    /// resolution; from here LP exits through `RedeemFinal`.
+    ///
+    /// Accounts:
+    ///   0. `[signer]` Admin
+    ///   1. `[writable]` Pool PDA
+    ///   2. `[]` Clock sysvar
+    Finalize { sweep_recipient: [u8; 32] },
+
+    /// 37: RedeemFinal — pro-rata LP redemption on a finalized pool, with no
+    /// cooldown and no HWM floor.
+    ///
+    /// Priced exactly like `Withdraw` (global, senior or junior sub-pool). On a
+    /// pool without tranches the deposit PDA may be empty, so LP received by
+    /// transfer is redeemable too; with tranches enabled the record is required,
+    /// because it is what attributes the LP to a tranche.
+    ///
+    /// Accounts: identical to `Withdraw` (tag 2).
+    RedeemFinal { lp_amount: u64 },
+
+    /// 38: SweepAndClosePool (permissionless) — once `FINAL_SWEEP_GRACE_SLOTS`
+    /// have passed since `Finalize`, sweep the vault's remaining balance to the
+    /// configured recipient, close the vault, revoke the LP mint's mint
+    /// authority, and close the pool PDA.
+    ///
+    /// The LP mint is NOT closed: SPL Token v1 has no instruction that closes
+    /// a mint, so it is left permanently frozen at its supply (no mint
+    /// authority). Rent from
+    /// the vault and the pool PDA goes to the recipient wallet. Any LP still
+    /// outstanding at this point is unbacked. Deposit records and veto votes
+    /// outlive the pool: `CloseDeposit` and `ReclaimVeto` accept the closed
+    /// (System-owned, empty) pool account.
+    ///
+    /// No signer is required: the sweep and the rent can only go to the
+    /// recipient named at `Finalize`.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` Pool PDA
+    ///   1. `[writable]` Pool vault token account
+    ///   2. `[writable]` Recipient's collateral token account (owned by the recipient)
+    ///   3. `[writable]` Recipient wallet (`final_sweep_recipient`)
+    ///   4. `[]` Vault authority PDA
+    ///   5. `[writable]` LP mint
+    ///   6. `[]` Token program
+    ///   7. `[]` Clock sysvar
+    SweepAndClosePool,
+
+    /// 39: ProposeFeeChange (fee manager for tags 25..28, admin for 42 and 45)
+    /// — step 1 of the fee-proxy timelock.
+    ///
+    /// `call` is the exact instruction data of one fee-policy proxy (tags
+    /// 25..28), a `SetFeePolicyBounds` (tag 42) or a `SetVetoThreshold`
+    /// (tag 45), and must decode as one.
+    /// It is stored, with the current slot, in
+    /// a new `StakeProposal` PDA at `[b"stake_proposal", pool, proposal_id]`;
+    /// the id is chosen by the admin and cannot be reused for the pool.
+    ///
+    /// Accounts:
+    ///   0. `[signer, writable]` Proposing role holder (pays rent)
+    ///   1. `[]` Pool PDA
+    ///   2. `[writable]` Proposal PDA (to be created)
+    ///   3. `[]` System program
+    ///   4. `[]` Clock sysvar
+    ProposeFeeChange { proposal_id: u64, call: Vec<u8> },
+
+    /// 40: CommitFeeChange (admin) — step 2: replay the proposed proxy call
+    /// once `TIMELOCK_SLOTS` have elapsed since the proposal.
+    ///
+    /// Accounts 2.. are passed to the proxy unchanged, so they are exactly the
+    /// proposed tag's own account list (admin signer first); the proxy re-runs
+    /// all of its own pool/admin/slab checks. A fee-parameter change must also
+    /// satisfy the pool's `FeePolicyBounds` (`FeePolicyBoundViolated`,
+    /// `FeeChangeTooSoon`); a `SetFeePolicyBounds` commit may loosen them.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` Proposal PDA
+    ///   1. `[]` Clock sysvar
+    ///   2. onward: the proposed tag's own accounts (see tags 25..28)
+    CommitFeeChange,
+
+    /// 41: CancelFeeChange (proposing role or admin) — withdraw a pending
+    /// proposal. The account stays, marked cancelled.
+    ///
+    /// Accounts:
+    ///   0. `[signer]` Proposing role holder, or the admin
+    ///   1. `[]` Pool PDA
+    ///   2. `[writable]` Proposal PDA
+    CancelFeeChange,
+
+    /// 42: SetFeePolicyBounds (admin) — replace the pool's stake-side
+    /// `FeePolicyBounds`.
+    ///
+    /// Sent directly it may only TIGHTEN (every bound at least as strict as
+    /// now); a loosening is rejected with `FeeChangeRequiresTimelock` and must
+    /// go through `ProposeFeeChange` / `CommitFeeChange`, so LPs get the same
+    /// exit window before the terms they staked under are relaxed.
+    ///
+    /// Accounts:
+    ///   0. `[signer]` Admin
+    ///   1. `[writable]` Pool PDA
+    SetFeePolicyBounds { bounds: FeePolicyBounds },
+
+    /// 43: CastVeto — lock LP against a pending proposal.
+    ///
+    /// `target_kind` is a `VETO_TARGET_*`: the pending cooldown increase, the
+    /// pending admin (neither yet expired), or a pending `StakeProposal` (fee
+    /// change). `lp_amount` LP
+    /// moves from the voter into the pool's veto escrow and is recorded in the
+    /// voter's `StakeVetoVote` for that proposal (topped up on a repeat vote).
+    /// If the LP locked against the proposal then EXCEEDS
+    /// `veto_threshold_bps` of `total_lp_supply`, the proposal is cancelled in
+    /// this instruction. Weight is escrowed LP tokens, and senior and junior
+    /// LP are the same mint, so no LP can count twice or for both tranches.
+    ///
+    /// Accounts:
+    ///   0. `[signer, writable]` Voter (LP owner; pays rent)
+    ///   1. `[writable]` Pool PDA
+    ///   2. `[writable]` Voter's LP token account (source)
+    ///   3. `[writable]` Veto escrow (PDA `[b"veto_escrow", pool]`; created on first use)
+    ///   4. `[writable]` Vote record PDA
+    ///   5. `[]` LP mint
+    ///   6. `[]` Vault authority PDA
+    ///   7. `[]` Token program
+    ///   8. `[]` System program
+    ///   9. `[]` Rent sysvar
+    ///  10. `[writable]` Proposal PDA (`VETO_TARGET_FEE_CHANGE` only)
+    CastVeto { target_kind: u8, lp_amount: u64 },
+
+    /// 44: ReclaimVeto — return a vote's LP once its proposal has resolved
+    /// (committed, cancelled, vetoed, replaced, or — for the cooldown increase
+    /// and admin transfer — expired) or its pool has been closed by
+    /// `SweepAndClosePool`, and close the record.
+    ///
+    /// Accounts:
+    ///   0. `[signer, writable]` Voter (receives the record's rent)
+    ///   1. `[]` Pool PDA (live, or closed)
+    ///   2. `[writable]` LP token account to receive the LP
+    ///   3. `[writable]` Veto escrow
+    ///   4. `[writable]` Vote record PDA
+    ///   5. `[]` Vault authority PDA
+    ///   6. `[]` Token program
+    ///   7. `[]` Proposal PDA (`VETO_TARGET_FEE_CHANGE` on a live pool only)
+    ReclaimVeto,
+
+    /// 45: SetVetoThreshold (admin) — set `veto_threshold_bps` (0 = disabled,
+    /// at most 10_000).
+    ///
+    /// Sent directly it may only strengthen the veto (enable it, or lower the
+    /// threshold); raising or disabling is `FeeChangeRequiresTimelock` and goes
+    /// through `ProposeFeeChange` / `CommitFeeChange` — where LPs can veto it.
+    ///
+    /// Accounts:
+    ///   0. `[signer]` Admin
+    ///   1. `[writable]` Pool PDA
+    SetVetoThreshold { threshold_bps: u16 },
+
+    /// 46: ProposeCouncil (admin) — step 1 of a council roster change: record
+    /// `members` / `threshold` as the pending roster of the pool's
+    /// `StakeCouncil` PDA (`[b"stake_council", pool]`, created on first use),
+    /// replacing any earlier pending roster. An empty roster with threshold 0
+    /// proposes dissolving the council.
+    ///
+    /// Data: threshold (1) + up to `MAX_COUNCIL_MEMBERS` pubkeys (32 each).
+    ///
+    /// Accounts:
+    ///   0. `[signer]` Admin, or `[]` the active council PDA (see below)
+    ///   1. `[]` Pool PDA
+    ///   2. `[writable]` Council PDA
+    ///   3. `[signer, writable]` Payer (rent, first use only)
+    ///   4. `[]` System program
+    ///
+    /// Every admin-gated instruction except `ReturnInsurance` (whose admin
+    /// also signs a token transfer) accepts the pool's active council PDA in
+    /// its admin slot instead of a signing admin, provided at least
+    /// `threshold` distinct members appear as signers anywhere in its account
+    /// list (in practice appended after the documented accounts).
+    ProposeCouncil {
+        threshold: u8,
+        members: Vec<[u8; 32]>,
+    },
+
+    /// 47: AcceptCouncil — step 2: the pending roster takes effect and the
+    /// pool's `council` points at the PDA. Needs `pending_threshold` distinct
+    /// pending members as signers; accepting a dissolution needs the pool
+    /// admin instead, and clears `council`.
+    ///
+    /// Accounts:
+    ///   0. `[writable]` Pool PDA
+    ///   1. `[writable]` Council PDA
+    ///   2. `[signer]` Pending members, one account each (for a dissolution,
+    ///      the pool admin)
+    AcceptCouncil,
+
+    /// 48: ProposeRole (admin) — step 1 of a role rotation: propose `holder`
+    /// for `role` (a `ROLE_*` id), replacing any earlier pending holder.
+    /// Proposing the current holder cancels the pending rotation; proposing
+    /// `[0; 32]` revokes the role at once, handing its instructions back to
+    /// the admin authority.
+    ///
+    /// Data: role (1) + holder (32).
+    ///
+    /// Accounts:
+    ///   0. `[signer]` Admin (or the active council PDA)
+    ///   1. `[writable]` Pool PDA
+    ProposeRole { role: u8, holder: [u8; 32] },
+
+    /// 49: AcceptRole — step 2: the pending holder of `role` takes it over.
+    ///
+    /// Data: role (1).
+    ///
+    /// Accounts:
+    ///   0. `[signer]` Pending holder (or the council PDA, with its members
+    ///      signing, when the council was proposed)
+    ///   1. `[writable]` Pool PDA
+    AcceptRole { role: u8 },
+
+    /// 50: SetFlushPolicy (admin) — set `flush_policy_max_bps`, the most
+    /// of the pool's gross value a permissionless `FlushToInsurance` may leave
+    /// in the wrapper insurance fund (0 = none, at most 10_000). Takes effect
+    /// for permissionless callers once `LockConfig` freezes it.
+    ///
+    /// Accounts:
+    ///   0. `[signer]` Admin
+    ///   1. `[writable]` Pool PDA
+    SetFlushPolicy { max_flush_bps: u16 },
+
+    /// 51: LockConfig (admin) — irreversibly set `CONFIG_LOCK_*` bits,
+    /// freezing the flush policy, the HWM config and/or the deposit cap.
+    ///
+    /// Accounts:
+    ///   0. `[signer]` Admin
+    ///   1. `[writable]` Pool PDA
+    LockConfig { locks: u8 },
+
+    /// 52: RenounceAdmin (admin) — permanently give up every key's authority
+    /// over the pool: the admin, the council link, all roles and any pending
+    /// admin, role or cooldown proposal are cleared, so no admin- or role-gated
+    /// instruction can pass again. Requires `CONFIG_LOCK_ALL`. Routine
+    /// operation continues through the permissionless paths (`AccrueFees`,
+    /// policy-bounded `FlushToInsurance`, `RecoverFlushedInsurance`).
+    ///
+    /// Accounts:
+    ///   0. `[signer]` Admin (or the active council PDA)
+    ///   1. `[writable]` Pool PDA
+    RenounceAdmin,
+
+    /// 53: AssertPoolInvariants (permissionless, read-only) — fail unless the
+    /// pool's books agree with the chain: the vault holds at least
+    /// `total_pool_value()`, the LP mint supply is within `total_lp_supply`
+    /// net of the dead-share lock, the junior tranche is within the pool value
+    /// and, when the wrapper market is passed, the pool's asset slot holds at
+    /// least `wrapper_recoverable()` of insurance. Meant to be appended to
+    /// keeper transactions so a broken invariant reverts the whole bundle.
+    ///
+    /// Accounts:
+    ///   0. `[]` Pool PDA
+    ///   1. `[]` Pool vault
+    ///   2. `[]` LP mint
+    ///   3. `[]` (optional) Wrapper market (`pool.slab`)
+    AssertPoolInvariants,
+
+    /// 54: DeclareDeficit (permissionless) — when the vault holds less than
+    /// `total_pool_value()`, write the pool value down to the vault balance
+    /// (junior tranche first) and enter deficit mode: deposits and flushes
+    /// close, withdrawals skip the HWM floor and pay out pro rata from what is
+    /// actually there, until a vault surplus has restored the whole write-off
+    /// (see `AccrueFees`). Fails with `NoDeficit` when there is no shortfall.
+    ///
+    /// Accounts:
+    ///   0. `[signer]` Caller
+    ///   1. `[writable]` Pool PDA
+    ///   2. `[]` Pool vault
+    DeclareDeficit,
+
+    /// 55: ProbeWrapper (permissionless, read-only) — fail unless the pool's
+    /// market account is one its wrapper revision can be CPI'd with: owned by
+    /// `percolator_program`, a market length of the pool's
+    /// `wrapper_abi_version` with a slot for `wrapper_asset_index`, and an
+    /// initialized header. Every wrapper CPI runs the same probe first; this
+    /// lets a deployer check a pool before binding or flushing.
+    ///
+    /// Accounts:
+    ///   0. `[]` Pool PDA
+    ///   1. `[]` Market/slab (must equal pool.slab)
+    ProbeWrapper,
 }
 
 impl StakeInstruction {
@@ -576,22 +1045,14 @@ impl StakeInstruction {
 
         match tag {
             0 => {
-                if rest.len() != 16 {
-                    return Err(ProgramError::InvalidInstructionData);
-                }
-                let cooldown_slots = u64::from_le_bytes(
-                    rest[0..8]
-                        .try_into()
-                        .map_err(|_| ProgramError::InvalidInstructionData)?,
-                );
-                let deposit_cap = u64::from_le_bytes(
-                    rest[8..16]
-                        .try_into()
-                        .map_err(|_| ProgramError::InvalidInstructionData)?,
-                );
+                let (cooldown_slots, deposit_cap, fee_policy, asset_index, wrapper_abi_version) =
+                    Self::unpack_init_pool(rest)?;
                 Ok(Self::InitPool {
                     cooldown_slots,
                     deposit_cap,
+                    fee_policy,
+                    asset_index,
+                    wrapper_abi_version,
                 })
             }
             1 => {
@@ -605,16 +1066,26 @@ impl StakeInstruction {
                 );
                 Ok(Self::Deposit { amount })
             }
+            // 2: Withdraw — lp_amount (8) + optional close_if_empty (1).
             2 => {
-                if rest.len() != 8 {
-                    return Err(ProgramError::InvalidInstructionData);
-                }
+                let close_if_empty = match rest.len() {
+                    8 => false,
+                    9 => match rest[8] {
+                        0 => false,
+                        1 => true,
+                        _ => return Err(ProgramError::InvalidInstructionData),
+                    },
+                    _ => return Err(ProgramError::InvalidInstructionData),
+                };
                 let lp_amount = u64::from_le_bytes(
                     rest[0..8]
                         .try_into()
                         .map_err(|_| ProgramError::InvalidInstructionData)?,
                 );
-                Ok(Self::Withdraw { lp_amount })
+                Ok(Self::Withdraw {
+                    lp_amount,
+                    close_if_empty,
+                })
             }
             3 => {
                 if rest.len() != 8 {
@@ -743,22 +1214,14 @@ impl StakeInstruction {
                 Ok(Self::AccrueFees)
             }
             13 => {
-                if rest.len() != 16 {
-                    return Err(ProgramError::InvalidInstructionData);
-                }
-                let cooldown_slots = u64::from_le_bytes(
-                    rest[0..8]
-                        .try_into()
-                        .map_err(|_| ProgramError::InvalidInstructionData)?,
-                );
-                let deposit_cap = u64::from_le_bytes(
-                    rest[8..16]
-                        .try_into()
-                        .map_err(|_| ProgramError::InvalidInstructionData)?,
-                );
+                let (cooldown_slots, deposit_cap, fee_policy, asset_index, wrapper_abi_version) =
+                    Self::unpack_init_pool(rest)?;
                 Ok(Self::InitTradingPool {
                     cooldown_slots,
                     deposit_cap,
+                    fee_policy,
+                    asset_index,
+                    wrapper_abi_version,
                 })
             }
             14 => {
@@ -893,9 +1356,316 @@ impl StakeInstruction {
                 );
                 Ok(Self::AdminUpdateTradeFeePolicy { trade_fee_base_bps })
             }
+            // 29: DepositFor — beneficiary (32) + amount (8) = 40 bytes.
+            29 => {
+                if rest.len() != 40 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let beneficiary: [u8; 32] = rest[0..32]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                let amount = u64::from_le_bytes(
+                    rest[32..40]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+                Ok(Self::DepositFor {
+                    beneficiary,
+                    amount,
+                })
+            }
+            30 => {
+                if rest.len() != 8 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let lp_amount = u64::from_le_bytes(
+                    rest[0..8]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+                Ok(Self::WithdrawTo { lp_amount })
+            }
+            31 => {
+                if rest.len() != 32 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let depositor: [u8; 32] = rest[0..32]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                Ok(Self::SetAuthorizedDepositor { depositor })
+            }
+            32 => {
+                if rest.len() != 8 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let lp_amount = u64::from_le_bytes(
+                    rest[0..8]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+                Ok(Self::OperatorWithdraw { lp_amount })
+            }
+            33 => {
+                if rest.len() != 32 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let operator: [u8; 32] = rest[0..32]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                Ok(Self::SetDepositOperator { operator })
+            }
+            34 => {
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::RevokeDepositOperator)
+            }
+            35 => {
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::CloseDeposit)
+            }
+            36 => {
+                if rest.len() != 32 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let sweep_recipient: [u8; 32] = rest[0..32]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                Ok(Self::Finalize { sweep_recipient })
+            }
+            37 => {
+                if rest.len() != 8 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let lp_amount = u64::from_le_bytes(
+                    rest[0..8]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+                Ok(Self::RedeemFinal { lp_amount })
+            }
+            38 => {
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::SweepAndClosePool)
+            }
+            // 39: ProposeFeeChange — proposal_id (8) + the proxied call's own
+            // instruction data (1..=PROPOSAL_CALL_MAX_LEN bytes).
+            39 => {
+                if rest.len() < 9 || rest.len() > 8 + crate::state::PROPOSAL_CALL_MAX_LEN {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let proposal_id = u64::from_le_bytes(
+                    rest[0..8]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+                let call = rest[8..].to_vec();
+                // Only a well-formed fee-policy proxy or bounds change may be
+                // proposed.
+                if !Self::unpack(&call)?.is_timelockable() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::ProposeFeeChange { proposal_id, call })
+            }
+            40 => {
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::CommitFeeChange)
+            }
+            41 => {
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::CancelFeeChange)
+            }
+            42 => {
+                let bounds =
+                    FeePolicyBounds::unpack(rest).ok_or(ProgramError::InvalidInstructionData)?;
+                Ok(Self::SetFeePolicyBounds { bounds })
+            }
+            // 43: CastVeto — target_kind (1) + lp_amount (8).
+            43 => {
+                if rest.len() != 9 || rest[0] > crate::state::VETO_TARGET_FEE_CHANGE {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let lp_amount = u64::from_le_bytes(
+                    rest[1..9]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+                Ok(Self::CastVeto {
+                    target_kind: rest[0],
+                    lp_amount,
+                })
+            }
+            44 => {
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::ReclaimVeto)
+            }
+            45 => {
+                if rest.len() != 2 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let threshold_bps = u16::from_le_bytes(
+                    rest[0..2]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+                Ok(Self::SetVetoThreshold { threshold_bps })
+            }
+            // 46: ProposeCouncil — threshold (1) + members (32 each).
+            46 => {
+                let (&threshold, keys) = rest
+                    .split_first()
+                    .ok_or(ProgramError::InvalidInstructionData)?;
+                if keys.len() % 32 != 0 || keys.len() / 32 > crate::state::MAX_COUNCIL_MEMBERS {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let members = keys
+                    .chunks_exact(32)
+                    .map(|k| {
+                        k.try_into()
+                            .map_err(|_| ProgramError::InvalidInstructionData)
+                    })
+                    .collect::<Result<Vec<[u8; 32]>, _>>()?;
+                Ok(Self::ProposeCouncil { threshold, members })
+            }
+            47 => {
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::AcceptCouncil)
+            }
+            // 48: ProposeRole — role (1) + holder (32).
+            48 => {
+                if rest.len() != 33 || rest[0] as usize >= crate::state::ROLE_COUNT {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let holder: [u8; 32] = rest[1..33]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidInstructionData)?;
+                Ok(Self::ProposeRole {
+                    role: rest[0],
+                    holder,
+                })
+            }
+            49 => {
+                if rest.len() != 1 || rest[0] as usize >= crate::state::ROLE_COUNT {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::AcceptRole { role: rest[0] })
+            }
+            50 => {
+                if rest.len() != 2 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let max_flush_bps = u16::from_le_bytes(
+                    rest[0..2]
+                        .try_into()
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+                Ok(Self::SetFlushPolicy { max_flush_bps })
+            }
+            51 => {
+                if rest.len() != 1 || rest[0] == 0 || rest[0] & !crate::state::CONFIG_LOCK_ALL != 0
+                {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::LockConfig { locks: rest[0] })
+            }
+            52 => {
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::RenounceAdmin)
+            }
+            53 => {
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::AssertPoolInvariants)
+            }
+            54 => {
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::DeclareDeficit)
+            }
+            55 => {
+                if !rest.is_empty() {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                Ok(Self::ProbeWrapper)
+            }
             _ => Err(ProgramError::InvalidInstructionData),
         }
     }
+
+    /// InitPool / InitTradingPool payload: cooldown_slots (8) + deposit_cap (8)
+    /// + `FeePolicyBounds` (22) + optional asset index (2) + optional ABI (1).
+    fn unpack_init_pool(rest: &[u8]) -> Result<(u64, u64, FeePolicyBounds, u16, u8), ProgramError> {
+        const BOUNDS_END: usize = 16 + FeePolicyBounds::LEN;
+        if ![BOUNDS_END, BOUNDS_END + 2, BOUNDS_END + 3].contains(&rest.len()) {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let fee_policy = FeePolicyBounds::unpack(&rest[16..BOUNDS_END])
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        let asset_index = match rest.get(BOUNDS_END..BOUNDS_END + 2) {
+            Some(bytes) => u16::from_le_bytes([bytes[0], bytes[1]]),
+            None => 0,
+        };
+        let wrapper_abi_version = rest
+            .get(BOUNDS_END + 2)
+            .copied()
+            .unwrap_or(crate::cpi::WRAPPER_ABI_V17);
+        let cooldown_slots = u64::from_le_bytes(
+            rest[0..8]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+        );
+        let deposit_cap = u64::from_le_bytes(
+            rest[8..16]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidInstructionData)?,
+        );
+        Ok((
+            cooldown_slots,
+            deposit_cap,
+            fee_policy,
+            asset_index,
+            wrapper_abi_version,
+        ))
+    }
+
+    /// Whether this is one of the timelocked wrapper fee-policy proxies
+    /// (tags 25..28).
+    pub fn is_fee_proxy(&self) -> bool {
+        matches!(
+            self,
+            Self::AdminUpdateFeeSplit { .. }
+                | Self::AdminUpdateMaintenanceFeePerSlot { .. }
+                | Self::AdminUpdateBackingFeePolicy { .. }
+                | Self::AdminUpdateTradeFeePolicy { .. }
+        )
+    }
+
+    /// Whether this may be submitted through `ProposeFeeChange`: a fee-policy
+    /// proxy, or a (possibly loosening) `SetFeePolicyBounds` /
+    /// `SetVetoThreshold`.
+    pub fn is_timelockable(&self) -> bool {
+        self.is_fee_proxy()
+            || matches!(
+                self,
+                Self::SetFeePolicyBounds { .. } | Self::SetVetoThreshold { .. }
+            )
+    }
 }
 
 #[cfg(test)]
@@ -907,16 +1677,135 @@ mod tests {
         let mut data = vec![0u8];
         data.extend_from_slice(&100u64.to_le_bytes());
         data.extend_from_slice(&5000u64.to_le_bytes());
+        // The bounds are not optional: the legacy 16-byte payload is refused.
+        assert!(StakeInstruction::unpack(&data).is_err());
+        data.extend_from_slice(&FeePolicyBounds::UNBOUNDED.pack());
         match StakeInstruction::unpack(&data).unwrap() {
             StakeInstruction::InitPool {
                 cooldown_slots,
                 deposit_cap,
+                fee_policy,
+                asset_index,
+                wrapper_abi_version,
             } => {
                 assert_eq!(cooldown_slots, 100);
                 assert_eq!(deposit_cap, 5000);
+                assert_eq!(fee_policy, FeePolicyBounds::UNBOUNDED);
+                assert_eq!(asset_index, 0);
+                assert_eq!(wrapper_abi_version, crate::cpi::WRAPPER_ABI_V17);
+            }
+            _ => panic!("wrong variant"),
+        }
+    }
+
+    #[test]
+    fn test_unpack_init_pool_with_asset_index() {
+        let mut data = vec![0u8];
+        data.extend_from_slice(&100u64.to_le_bytes());
+        data.extend_from_slice(&5000u64.to_le_bytes());
+        data.extend_from_slice(&[0u8; FeePolicyBounds::LEN]);
+        data.extend_from_slice(&0x0102u16.to_le_bytes());
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::InitPool {
+                fee_policy,
+                asset_index,
+                ..
+            } => {
+                assert_eq!(
+                    fee_policy,
+                    FeePolicyBounds::unpack(&[0u8; FeePolicyBounds::LEN]).unwrap()
+                );
+                assert_eq!(asset_index, 0x0102);
+            }
+            _ => panic!("wrong variant"),
+        }
+        // Without the trailing index the pool backs asset 0.
+        match StakeInstruction::unpack(&data[..data.len() - 2]).unwrap() {
+            StakeInstruction::InitPool { asset_index, .. } => assert_eq!(asset_index, 0),
+            _ => panic!("wrong variant"),
+        }
+        // A half-written index is refused, for both init tags.
+        assert!(StakeInstruction::unpack(&data[..data.len() - 1]).is_err());
+        data[0] = 13;
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::InitTradingPool { asset_index, .. } => {
+                assert_eq!(asset_index, 0x0102)
+            }
+            _ => panic!("wrong variant"),
+        }
+        assert!(StakeInstruction::unpack(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_unpack_init_pool_with_wrapper_abi_version() {
+        let mut data = vec![0u8];
+        data.extend_from_slice(&100u64.to_le_bytes());
+        data.extend_from_slice(&5000u64.to_le_bytes());
+        data.extend_from_slice(&[0u8; FeePolicyBounds::LEN]);
+        data.extend_from_slice(&3u16.to_le_bytes());
+        data.push(crate::cpi::WRAPPER_ABI_V16);
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::InitPool {
+                asset_index,
+                wrapper_abi_version,
+                ..
+            } => {
+                assert_eq!(asset_index, 3);
+                assert_eq!(wrapper_abi_version, crate::cpi::WRAPPER_ABI_V16);
+            }
+            _ => panic!("wrong variant"),
+        }
+        // Unknown versions decode; InitPool rejects them when it resolves the ABI.
+        data[1 + 16 + FeePolicyBounds::LEN + 2] = 12;
+        assert!(StakeInstruction::unpack(&data).is_ok());
+        // Nothing may follow the version byte.
+        data.push(0);
+        assert!(StakeInstruction::unpack(&data).is_err());
+    }
+
+    #[test]
+    fn test_unpack_init_pool_with_fee_policy() {
+        let mut data = vec![13u8];
+        data.extend_from_slice(&100u64.to_le_bytes());
+        data.extend_from_slice(&5000u64.to_le_bytes());
+        data.extend_from_slice(&5_000u16.to_le_bytes());
+        data.extend_from_slice(&30u64.to_le_bytes());
+        data.extend_from_slice(&500u16.to_le_bytes());
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&50u16.to_le_bytes());
+        let expected = FeePolicyBounds {
+            min_lp_share_bps: 5_000,
+            max_trade_fee_base_bps: 30,
+            max_change_delta_bps: 500,
+            min_change_interval_slots: 1_000,
+            max_backing_fee_bps: 50,
+        };
+        assert_eq!(expected.pack()[..], data[17..]);
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::InitTradingPool { fee_policy, .. } => {
+                assert_eq!(fee_policy, expected)
             }
             _ => panic!("wrong variant"),
         }
+        // Partial bounds are refused.
+        assert!(StakeInstruction::unpack(&data[..data.len() - 1]).is_err());
+
+        let mut data42 = vec![42u8];
+        data42.extend_from_slice(&data[17..]);
+        match StakeInstruction::unpack(&data42).unwrap() {
+            StakeInstruction::SetFeePolicyBounds { bounds } => assert_eq!(bounds, expected),
+            _ => panic!("wrong variant"),
+        }
+        assert!(StakeInstruction::unpack(&data42[..FeePolicyBounds::LEN]).is_err());
+
+        // A bounds change can be proposed through the timelock.
+        let mut propose = vec![39u8];
+        propose.extend_from_slice(&1u64.to_le_bytes());
+        propose.extend_from_slice(&data42);
+        assert!(matches!(
+            StakeInstruction::unpack(&propose).unwrap(),
+            StakeInstruction::ProposeFeeChange { .. }
+        ));
     }
 
     #[test]
@@ -934,9 +1823,26 @@ mod tests {
         let mut data = vec![2u8];
         data.extend_from_slice(&999u64.to_le_bytes());
         match StakeInstruction::unpack(&data).unwrap() {
-            StakeInstruction::Withdraw { lp_amount } => assert_eq!(lp_amount, 999),
+            StakeInstruction::Withdraw {
+                lp_amount,
+                close_if_empty,
+            } => {
+                assert_eq!(lp_amount, 999);
+                assert!(!close_if_empty);
+            }
             _ => panic!("wrong variant"),
         }
+
+        // Optional trailing close_if_empty flag: only 0 or 1 accepted.
+        data.push(1);
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::Withdraw { close_if_empty, .. } => assert!(close_if_empty),
+            _ => panic!("wrong variant"),
+        }
+        *data.last_mut().unwrap() = 2;
+        assert!(StakeInstruction::unpack(&data).is_err());
+        data.push(0);
+        assert!(StakeInstruction::unpack(&data).is_err());
     }
 
     #[test]
@@ -1309,4 +2215,295 @@ mod tests {
             "tag 24 should reject trailing bytes"
         );
     }
+
+    #[test]
+    fn test_unpack_deposit_for() {
+        let beneficiary = [3u8; 32];
+        let mut data = vec![29u8];
+        data.extend_from_slice(&beneficiary);
+        data.extend_from_slice(&777u64.to_le_bytes());
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::DepositFor {
+                beneficiary: got,
+                amount,
+            } => {
+                assert_eq!(got, beneficiary);
+                assert_eq!(amount, 777);
+            }
+            _ => panic!("wrong variant"),
+        }
+        // truncated and trailing payloads rejected
+        assert!(StakeInstruction::unpack(&data[..data.len() - 1]).is_err());
+        data.push(0);
+        assert!(StakeInstruction::unpack(&data).is_err());
+    }
+
+    #[test]
+    fn test_unpack_withdraw_to() {
+        let mut data = vec![30u8];
+        data.extend_from_slice(&555u64.to_le_bytes());
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::WithdrawTo { lp_amount } => assert_eq!(lp_amount, 555),
+            _ => panic!("wrong variant"),
+        }
+        assert!(StakeInstruction::unpack(&[30u8]).is_err());
+        data.push(0);
+        assert!(StakeInstruction::unpack(&data).is_err());
+    }
+
+    #[test]
+    fn test_unpack_set_authorized_depositor() {
+        let mut data = vec![31u8];
+        data.extend_from_slice(&[9u8; 32]);
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::SetAuthorizedDepositor { depositor } => {
+                assert_eq!(depositor, [9u8; 32])
+            }
+            _ => panic!("wrong variant"),
+        }
+        assert!(StakeInstruction::unpack(&data[..32]).is_err());
+        data.push(0);
+        assert!(StakeInstruction::unpack(&data).is_err());
+    }
+
+    #[test]
+    fn test_unpack_operator_instructions() {
+        let mut data = vec![32u8];
+        data.extend_from_slice(&42u64.to_le_bytes());
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::OperatorWithdraw { lp_amount } => assert_eq!(lp_amount, 42),
+            _ => panic!("wrong variant"),
+        }
+        assert!(StakeInstruction::unpack(&[32u8]).is_err());
+
+        let mut data = vec![33u8];
+        data.extend_from_slice(&[6u8; 32]);
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::SetDepositOperator { operator } => assert_eq!(operator, [6u8; 32]),
+            _ => panic!("wrong variant"),
+        }
+        data.push(0);
+        assert!(StakeInstruction::unpack(&data).is_err());
+
+        assert!(matches!(
+            StakeInstruction::unpack(&[34u8]).unwrap(),
+            StakeInstruction::RevokeDepositOperator
+        ));
+        assert!(StakeInstruction::unpack(&[34u8, 0]).is_err());
+    }
+
+    #[test]
+    fn test_unpack_close_deposit() {
+        assert!(matches!(
+            StakeInstruction::unpack(&[35u8]).unwrap(),
+            StakeInstruction::CloseDeposit
+        ));
+        assert!(StakeInstruction::unpack(&[35u8, 0]).is_err());
+    }
+
+    #[test]
+    fn test_unpack_sunset_instructions() {
+        let mut data = vec![36u8];
+        data.extend_from_slice(&[8u8; 32]);
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::Finalize { sweep_recipient } => {
+                assert_eq!(sweep_recipient, [8u8; 32])
+            }
+            _ => panic!("wrong variant"),
+        }
+        assert!(StakeInstruction::unpack(&data[..32]).is_err());
+
+        let mut data = vec![37u8];
+        data.extend_from_slice(&11u64.to_le_bytes());
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::RedeemFinal { lp_amount } => assert_eq!(lp_amount, 11),
+            _ => panic!("wrong variant"),
+        }
+        data.push(0);
+        assert!(StakeInstruction::unpack(&data).is_err());
+
+        assert!(matches!(
+            StakeInstruction::unpack(&[38u8]).unwrap(),
+            StakeInstruction::SweepAndClosePool
+        ));
+        assert!(StakeInstruction::unpack(&[38u8, 1]).is_err());
+    }
+
+    #[test]
+    fn test_unpack_fee_change_proposals() {
+        // A tag-28 proxy call, proposed under id 7.
+        let mut call = vec![28u8];
+        call.extend_from_slice(&42u64.to_le_bytes());
+        let mut data = vec![39u8];
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data.extend_from_slice(&call);
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::ProposeFeeChange {
+                proposal_id,
+                call: got,
+            } => {
+                assert_eq!(proposal_id, 7);
+                assert_eq!(got, call);
+            }
+            _ => panic!("wrong variant"),
+        }
+
+        // A malformed proxy call, or a call that is not a fee proxy, is refused.
+        data.push(0);
+        assert!(StakeInstruction::unpack(&data).is_err());
+        let mut data = vec![39u8];
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data.push(35u8); // CloseDeposit
+        assert!(StakeInstruction::unpack(&data).is_err());
+        // No call at all.
+        assert!(StakeInstruction::unpack(&data[..9]).is_err());
+
+        assert!(matches!(
+            StakeInstruction::unpack(&[40u8]).unwrap(),
+            StakeInstruction::CommitFeeChange
+        ));
+        assert!(StakeInstruction::unpack(&[40u8, 0]).is_err());
+        assert!(matches!(
+            StakeInstruction::unpack(&[41u8]).unwrap(),
+            StakeInstruction::CancelFeeChange
+        ));
+        assert!(StakeInstruction::unpack(&[41u8, 0]).is_err());
+    }
+
+    #[test]
+    fn test_unpack_veto() {
+        let mut data = vec![43u8, crate::state::VETO_TARGET_ADMIN];
+        data.extend_from_slice(&500u64.to_le_bytes());
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::CastVeto {
+                target_kind,
+                lp_amount,
+            } => {
+                assert_eq!(target_kind, crate::state::VETO_TARGET_ADMIN);
+                assert_eq!(lp_amount, 500);
+            }
+            _ => panic!("wrong variant"),
+        }
+        // Unknown target kind.
+        data[1] = 3;
+        assert!(StakeInstruction::unpack(&data).is_err());
+        assert!(StakeInstruction::unpack(&data[..9]).is_err());
+
+        assert!(matches!(
+            StakeInstruction::unpack(&[44u8]).unwrap(),
+            StakeInstruction::ReclaimVeto
+        ));
+        assert!(StakeInstruction::unpack(&[44u8, 0]).is_err());
+
+        match StakeInstruction::unpack(&[45u8, 0x10, 0x27]).unwrap() {
+            StakeInstruction::SetVetoThreshold { threshold_bps } => {
+                assert_eq!(threshold_bps, 10_000)
+            }
+            _ => panic!("wrong variant"),
+        }
+        assert!(StakeInstruction::unpack(&[45u8, 1]).is_err());
+        // A threshold change can be proposed through the timelock.
+        let mut propose = vec![39u8];
+        propose.extend_from_slice(&2u64.to_le_bytes());
+        propose.extend_from_slice(&[45u8, 0, 0]);
+        assert!(StakeInstruction::unpack(&propose).is_ok());
+    }
+
+    #[test]
+    fn test_unpack_council() {
+        let mut data = vec![46u8, 2];
+        data.extend_from_slice(&[1u8; 32]);
+        data.extend_from_slice(&[2u8; 32]);
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::ProposeCouncil { threshold, members } => {
+                assert_eq!(threshold, 2);
+                assert_eq!(members, vec![[1u8; 32], [2u8; 32]]);
+            }
+            _ => panic!("wrong variant"),
+        }
+        // Truncated key, missing threshold, too many members.
+        assert!(StakeInstruction::unpack(&data[..data.len() - 1]).is_err());
+        assert!(StakeInstruction::unpack(&[46u8]).is_err());
+        let mut crowded = vec![46u8, 1];
+        crowded.extend_from_slice(&[7u8; 32 * (crate::state::MAX_COUNCIL_MEMBERS + 1)]);
+        assert!(StakeInstruction::unpack(&crowded).is_err());
+        // Dissolution: threshold 0, no members.
+        assert!(matches!(
+            StakeInstruction::unpack(&[46u8, 0]).unwrap(),
+            StakeInstruction::ProposeCouncil { threshold: 0, ref members } if members.is_empty()
+        ));
+
+        assert!(matches!(
+            StakeInstruction::unpack(&[47u8]).unwrap(),
+            StakeInstruction::AcceptCouncil
+        ));
+        assert!(StakeInstruction::unpack(&[47u8, 0]).is_err());
+    }
+
+    #[test]
+    fn test_unpack_roles() {
+        let mut data = vec![48u8, crate::state::ROLE_FEE_MANAGER];
+        data.extend_from_slice(&[9u8; 32]);
+        match StakeInstruction::unpack(&data).unwrap() {
+            StakeInstruction::ProposeRole { role, holder } => {
+                assert_eq!(role, crate::state::ROLE_FEE_MANAGER);
+                assert_eq!(holder, [9u8; 32]);
+            }
+            _ => panic!("wrong variant"),
+        }
+        assert!(StakeInstruction::unpack(&data[..data.len() - 1]).is_err());
+        data[1] = crate::state::ROLE_COUNT as u8;
+        assert!(StakeInstruction::unpack(&data).is_err());
+
+        assert!(matches!(
+            StakeInstruction::unpack(&[49u8, crate::state::ROLE_FEE_MANAGER]).unwrap(),
+            StakeInstruction::AcceptRole { role } if role == crate::state::ROLE_FEE_MANAGER
+        ));
+        assert!(StakeInstruction::unpack(&[49u8]).is_err());
+        assert!(StakeInstruction::unpack(&[49u8, crate::state::ROLE_COUNT as u8]).is_err());
+    }
+
+    #[test]
+    fn test_unpack_lock_and_renounce() {
+        assert!(matches!(
+            StakeInstruction::unpack(&[50u8, 0xD0, 0x07]).unwrap(),
+            StakeInstruction::SetFlushPolicy {
+                max_flush_bps: 2_000
+            }
+        ));
+        assert!(StakeInstruction::unpack(&[50u8, 1]).is_err());
+
+        let all = crate::state::CONFIG_LOCK_ALL;
+        assert!(matches!(
+            StakeInstruction::unpack(&[51u8, all]).unwrap(),
+            StakeInstruction::LockConfig { locks } if locks == all
+        ));
+        assert!(StakeInstruction::unpack(&[51u8, 0]).is_err());
+        assert!(StakeInstruction::unpack(&[51u8, all + 1]).is_err());
+        assert!(StakeInstruction::unpack(&[51u8]).is_err());
+
+        assert!(matches!(
+            StakeInstruction::unpack(&[52u8]).unwrap(),
+            StakeInstruction::RenounceAdmin
+        ));
+        assert!(StakeInstruction::unpack(&[52u8, 0]).is_err());
+
+        assert!(matches!(
+            StakeInstruction::unpack(&[53u8]).unwrap(),
+            StakeInstruction::AssertPoolInvariants
+        ));
+        assert!(StakeInstruction::unpack(&[53u8, 0]).is_err());
+
+        assert!(matches!(
+            StakeInstruction::unpack(&[54u8]).unwrap(),
+            StakeInstruction::DeclareDeficit
+        ));
+        assert!(StakeInstruction::unpack(&[54u8, 0]).is_err());
+
+        assert!(matches!(
+            StakeInstruction::unpack(&[55u8]).unwrap(),
+            StakeInstruction::ProbeWrapper
+        ));
+        assert!(StakeInstruction::unpack(&[55u8, 0]).is_err());
+    }
 }
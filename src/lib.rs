@@ -55,6 +55,60 @@
 //!  28  - AdminUpdateTradeFeePolicy: CPI proxy for wrapper tag 55.
 //!                              GROUP B — insurance_authority-gated, VAULT_AUTH
 //!                              PDA signs.
+//!                              Tags 25-28 run only via tags 39/40 (timelock).
+//!  29  - DepositFor:          Deposit on behalf of a beneficiary who opted in
+//!                              to this funder (tag 31)
+//!  30  - WithdrawTo:          Withdraw with the destination ATA's owner bound
+//!                              to a named recipient wallet
+//!  31  - SetAuthorizedDepositor: owner names (or clears) the one funder allowed
+//!                              to DepositFor into their position
+//!  32  - OperatorWithdraw:    delegated operator withdraws; proceeds pinned
+//!                              to the position owner
+//!  33  - SetDepositOperator:  owner names an operator (v2 deposit record)
+//!  34  - RevokeDepositOperator: owner clears the operator
+//!  35  - CloseDeposit:        close a fully exited deposit PDA, rent to owner
+//!  36  - Finalize:            admin moves a resolved, fully recovered pool into
+//!                              its terminal phase and names the sweep recipient
+//!  37  - RedeemFinal:         pro-rata LP redemption, no cooldown / HWM
+//!  38  - SweepAndClosePool:   after the grace period, sweep and close the vault
+//!                              and the pool PDA; the LP mint, which SPL Token
+//!                              cannot close, loses its mint authority
+//!                              (permissionless)
+//!  39  - ProposeFeeChange:    fee manager (tags 25-28) or admin (42, 45)
+//!                              records the call in a proposal PDA
+//!  40  - CommitFeeChange:     replay the proposed call after TIMELOCK_SLOTS
+//!  41  - CancelFeeChange:     proposer or admin withdraws a pending proposal
+//!  42  - SetFeePolicyBounds:  tighten the pool's stake-side fee bounds
+//!                              (loosening only via tags 39/40)
+//!  43  - CastVeto:            lock LP against a pending cooldown increase, admin
+//!                              transfer or fee proposal; cancels it past the threshold
+//!  44  - ReclaimVeto:         return locked LP once the target is no longer pending
+//!  45  - SetVetoThreshold:    lower (strengthen) the veto threshold; raising or
+//!                              disabling only via tags 39/40
+//!  46  - ProposeCouncil:      admin proposes an m-of-n council roster (or its
+//!                              dissolution)
+//!  47  - AcceptCouncil:       the proposed roster's members accept it; the
+//!                              council can then act for the admin
+//!  48  - ProposeRole:         admin proposes (or revokes) a flush operator or fee
+//!                              manager
+//!  49  - AcceptRole:          the proposed holder takes the role over
+//!  50  - SetFlushPolicy:      admin sets the permissionless flush cap
+//!  51  - LockConfig:          admin irreversibly freezes the flush policy, HWM
+//!                              config and/or deposit cap
+//!  52  - RenounceAdmin:       with everything locked, zero the admin, council
+//!                              and roles for good
+//!  53  - AssertPoolInvariants: permissionless check that the vault, LP mint
+//!                              and wrapper market agree with the ledger
+//!  54  - DeclareDeficit:      permissionless write-down of a vault shortfall,
+//!                              junior first; enters deficit mode
+//!  55  - ProbeWrapper:        read-only check that the pool's market is a
+//!                              market its wrapper revision can be CPI'd with
+//!
+//! Deposit, DepositJunior, DepositFor, Withdraw, WithdrawTo, FlushToInsurance, RecoverFlushedInsurance
+//! and AccrueFees end with `set_return_data` carrying a fixed 56-byte record
+//! (amounts in/out, LP delta, post-instruction share price) so CPI callers can
+//! compose without re-reading token accounts. See `return_data` for the layout
+//! and the decoder.
 //!
 //! Wrapper tag 69 (RestartAssetOracle) is deliberately NOT proxied: it is gated
 //! on asset_admin, which this program only ever burns to [0;32] (tag 21), and
@@ -100,11 +154,16 @@
 #[cfg(feature = "devnet")]
 solana_program::declare_id!("GCHhcgwPyrai8SWHEVWw3odedguFXEtJobNnWSfWBCU3");
 
+#[macro_use]
+mod lp_math;
+
+pub mod accounting;
 pub mod cpi;
 pub mod error;
 pub mod instruction;
 pub mod math;
 pub mod processor;
+pub mod return_data;
 pub mod spl_token;
 pub mod state;
 
@@ -0,0 +1,592 @@
+//! Width-generic LP math, shared verbatim with the Kani crate.
+//!
+//! `lp_math!(word, wide, signed)` expands every pure arithmetic function for one
+//! integer width: `word` is the ledger type, `wide` holds a `word * word`
+//! product and `signed` holds a signed sum of `word`s. `math.rs` instantiates it
+//! at `(u64, u128, i128)` for production. `kani-proofs/` includes this file by
+//! path and instantiates it at `(u32, u64, i64)`, so the proofs run on the
+//! production code itself at a width CBMC can model-check, instead of on a
+//! hand-maintained mirror that could drift.
+//!
+//! Keep this file free of crate imports: the Kani crate has no dependencies.
+//! Widths quoted in comments below are for the production instantiation.
+
+macro_rules! lp_math {
+    ($word:ty, $wide:ty, $signed:ty) => {
+        /// N7 hardening (CONSOLIDATED-PLAN §2.2): virtual shares/assets added to both
+        /// sides of the pro-rata LP pricing ratio, ERC4626-"decimals offset"-style.
+        /// Without this, a pool with a small real supply/value is priced using ONLY
+        /// the tracked counters — an attacker who becomes sole/first LP holder, then
+        /// donates raw collateral directly to the vault token account (bypassing
+        /// `Deposit`) and cranks the permissionless `AccrueFees` (mode-1 pools only,
+        /// since only mode-1 folds `total_fees_earned` into `total_pool_value()`),
+        /// can book that donation as "fees" and inflate the tracked share price
+        /// arbitrarily cheaply (the donation stays 100% attacker-owned the whole
+        /// time — a later victim `Deposit` then rounds `calc_lp_for_deposit` down to
+        /// 0 and reverts with `ZeroSharesMinted`, DoSing every deposit below the
+        /// inflated price). Adding a fixed virtual offset to BOTH the numerator and
+        /// denominator of the pro-rata ratio makes every donation-then-accrue round
+        /// cost the attacker a small, permanent, unrecoverable dilution against the
+        /// virtual (unowned) share — the classic single-asset-vault countermeasure.
+        /// Kept at the minimal canonical value of 1 (not scaled to token decimals):
+        /// larger offsets would materially reprice small-magnitude test/production
+        /// pools (see math.rs unit tests), and the primary defense against the
+        /// donation attack described above is the MINIMUM_LIQUIDITY dead-share lock
+        /// applied by the caller (`processor.rs::process_deposit`) at genesis
+        /// deposit — this offset is deliberate defense-in-depth on top of that, not
+        /// the sole mitigation.
+        const VIRTUAL_SHARES: $wide = 1;
+        const VIRTUAL_ASSETS: $wide = 1;
+
+        /// Calculate LP tokens for a deposit.
+        ///
+        /// # Arguments
+        /// * `total_lp_supply` - Current total LP tokens in circulation
+        /// * `total_pool_value` - Current total pool value (deposited - withdrawn)
+        /// * `deposit_amount` - Amount of collateral being deposited
+        ///
+        /// # Returns
+        /// * `Some(lp_tokens)` - LP tokens to mint (rounds DOWN — pool-favoring)
+        /// * `None` - Arithmetic overflow
+        ///
+        /// # Invariant
+        /// First depositor (supply == 0): gets 1:1 LP tokens.
+        /// Subsequent: `lp = amount * (supply + VIRTUAL_SHARES) / (pool_value + VIRTUAL_ASSETS)`
+        /// (pro-rata with N7 virtual-offset, rounded down).
+        pub fn calc_lp_for_deposit(
+            total_lp_supply: $word,
+            total_pool_value: $word,
+            deposit_amount: $word,
+        ) -> Option<$word> {
+            if total_lp_supply == 0 && total_pool_value == 0 {
+                // True first depositor — 1:1. (With the virtual offset applied uniformly,
+                // this equals (deposit * VIRTUAL_SHARES) / VIRTUAL_ASSETS = deposit exactly,
+                // since VIRTUAL_SHARES == VIRTUAL_ASSETS; kept as an explicit branch for
+                // clarity and to avoid a redundant division on the hot bootstrap path.)
+                Some(deposit_amount)
+            } else if total_lp_supply == 0 {
+                // CRITICAL: LP supply is 0 but pool has orphaned value (e.g., returned insurance
+                // after all LP holders withdrew). Allowing 1:1 deposits here would let the
+                // depositor withdraw the entire orphaned value. Block deposits.
+                None
+            } else if total_pool_value == 0 {
+                // LP tokens exist but pool value is 0 (fully flushed to insurance).
+                // Existing holders have a claim on future insurance returns.
+                // Allowing deposits would dilute that claim. Block deposits.
+                None
+            } else {
+                // Pro-rata via u128 to prevent overflow. N7: +VIRTUAL_SHARES / +VIRTUAL_ASSETS
+                // on both sides of the ratio (see const doc above).
+                let lp = (deposit_amount as $wide)
+                    .checked_mul((total_lp_supply as $wide).checked_add(VIRTUAL_SHARES)?)?
+                    .checked_div((total_pool_value as $wide).checked_add(VIRTUAL_ASSETS)?)?;
+                if lp > <$word>::MAX as $wide {
+                    None
+                } else {
+                    Some(lp as $word)
+                }
+            }
+        }
+
+        /// Calculate collateral for an LP token burn.
+        ///
+        /// # Arguments
+        /// * `total_lp_supply` - Current total LP tokens
+        /// * `total_pool_value` - Current pool value
+        /// * `lp_amount` - LP tokens being burned
+        ///
+        /// # Returns
+        /// * `Some(collateral)` - Collateral to return (rounds DOWN — pool-favoring)
+        /// * `None` - Division by zero or overflow
+        ///
+        /// # Invariant
+        /// `collateral = lp_amount * (pool_value + VIRTUAL_ASSETS) / (lp_supply + VIRTUAL_SHARES)`
+        /// (N7 virtual-offset, rounded down). Full burn returns ≤ pool_value (never more) —
+        /// strictly less with the offset applied, since a fraction of the ratio is
+        /// permanently attributed to the unowned virtual share.
+        pub fn calc_collateral_for_withdraw(
+            total_lp_supply: $word,
+            total_pool_value: $word,
+            lp_amount: $word,
+        ) -> Option<$word> {
+            if total_lp_supply == 0 {
+                return None;
+            }
+            // N7: +VIRTUAL_ASSETS / +VIRTUAL_SHARES on both sides, symmetric with
+            // calc_lp_for_deposit above so minting and redeeming use the same offset ratio
+            // (asymmetric offsets would leak value in one direction).
+            let collateral = (lp_amount as $wide)
+                .checked_mul((total_pool_value as $wide).checked_add(VIRTUAL_ASSETS)?)?
+                .checked_div((total_lp_supply as $wide).checked_add(VIRTUAL_SHARES)?)?;
+            if collateral > <$word>::MAX as $wide {
+                None
+            } else {
+                Some(collateral as $word)
+            }
+        }
+
+        /// Calculate pool value from accounting state.
+        ///
+        /// # Returns
+        /// * `Some(value)` if deposited + fees >= withdrawn
+        /// * `None` if accounting is broken (withdrawn > deposited + fees)
+        pub fn pool_value(total_deposited: $word, total_withdrawn: $word) -> Option<$word> {
+            total_deposited.checked_sub(total_withdrawn)
+        }
+
+        /// Calculate pool value including accrued trading fees (PERC-272).
+        ///
+        /// # Returns
+        /// * `Some(value)` if deposited + fees >= withdrawn
+        /// * `None` if accounting overflow or underflow
+        pub fn pool_value_with_fees(
+            total_deposited: $word,
+            total_withdrawn: $word,
+            total_fees_earned: $word,
+        ) -> Option<$word> {
+            total_deposited
+                .checked_sub(total_withdrawn)?
+                .checked_add(total_fees_earned)
+        }
+
+        // ═══════════════════════════════════════════════════════════════
+        // PERC-303: Senior/Junior LP Tranche Math
+        // ═══════════════════════════════════════════════════════════════
+
+        /// Calculate LP tokens for a junior tranche deposit.
+        ///
+        /// Junior tranche has its own sub-pool: junior_balance / junior_total_lp.
+        /// First junior depositor gets 1:1, subsequent get pro-rata within junior pool.
+        ///
+        /// # Returns
+        /// * `Some(lp_tokens)` to mint
+        /// * `None` on overflow or blocked state (orphaned value)
+        pub fn calc_junior_lp_for_deposit(
+            junior_total_lp: $word,
+            junior_balance: $word,
+            deposit_amount: $word,
+        ) -> Option<$word> {
+            calc_lp_for_deposit(junior_total_lp, junior_balance, deposit_amount)
+        }
+
+        /// Calculate LP tokens for a senior tranche deposit.
+        ///
+        /// The senior tranche has its own sub-pool: `senior_balance / senior_total_lp`,
+        /// where `senior_balance = total_pool_value - effective_junior_balance` and
+        /// `senior_total_lp = total_lp_supply - junior_total_lp`.
+        ///
+        /// This MUST price against the same basis the senior WITHDRAW path values against
+        /// (`calc_senior_collateral_for_withdraw`), so a senior deposit-then-withdraw
+        /// round-trip cannot profit. Pricing senior deposits at the GLOBAL ratio while
+        /// redeeming at the senior ratio lets a depositor mint cheap and redeem dear after
+        /// a junior-absorbed loss, extracting value from existing senior LPs. Delegates to
+        /// `calc_lp_for_deposit`, inheriting its round-DOWN (pool-favoring) semantics AND
+        /// its first-depositor / orphaned-value (C9) handling: a true first senior deposit
+        /// (`senior_total_lp == 0 && senior_balance == 0` — empty pool, or junior-first
+        /// where junior captures 100% of fees) mints 1:1, while ORPHANED senior value
+        /// (`senior_total_lp == 0 && senior_balance > 0`, e.g. insurance returned after all
+        /// senior LP exited) returns `None` so the caller REJECTS the deposit. Minting 1:1
+        /// against an orphan would let a dust deposit redeem the whole orphaned balance, so
+        /// the caller MUST NOT special-case `senior_total_lp == 0` into an unconditional
+        /// 1:1 bootstrap — it defers to this guard, exactly as the non-tranche path does.
+        ///
+        /// # Returns
+        /// * `Some(lp_tokens)` to mint (rounds DOWN — pool-favoring, same as junior/global)
+        /// * `None` on overflow or blocked state (orphaned value)
+        pub fn calc_senior_lp_for_deposit(
+            senior_total_lp: $word,
+            senior_balance: $word,
+            deposit_amount: $word,
+        ) -> Option<$word> {
+            calc_lp_for_deposit(senior_total_lp, senior_balance, deposit_amount)
+        }
+
+        /// Calculate collateral for a junior LP token burn.
+        ///
+        /// Junior withdrawals are valued against the junior sub-pool only.
+        /// If junior_balance has been reduced by losses, junior LPs take the hit.
+        ///
+        /// # Returns
+        /// * `Some(collateral)` to return (rounds down)
+        /// * `None` on overflow
+        pub fn calc_junior_collateral_for_withdraw(
+            junior_total_lp: $word,
+            junior_balance: $word,
+            lp_amount: $word,
+        ) -> Option<$word> {
+            calc_collateral_for_withdraw(junior_total_lp, junior_balance, lp_amount)
+        }
+
+        /// Calculate collateral for a senior LP token burn.
+        ///
+        /// Senior withdrawals are valued against the senior sub-pool only.
+        /// senior_balance = total_pool_value - junior_balance.
+        /// senior_total_lp = total_lp_supply - junior_total_lp.
+        ///
+        /// Senior LPs only lose if junior_balance is fully wiped (== 0).
+        ///
+        /// # Returns
+        /// * `Some(collateral)` to return (rounds down)
+        /// * `None` on overflow or zero senior supply
+        pub fn calc_senior_collateral_for_withdraw(
+            senior_total_lp: $word,
+            senior_balance: $word,
+            lp_amount: $word,
+        ) -> Option<$word> {
+            calc_collateral_for_withdraw(senior_total_lp, senior_balance, lp_amount)
+        }
+
+        /// Distribute a loss across tranches. Junior absorbs first.
+        ///
+        /// # Returns
+        /// (junior_loss, senior_loss)
+        /// Invariant: junior_loss + senior_loss == loss_amount (unless capped at total).
+        pub fn distribute_loss(
+            junior_balance: $word,
+            senior_balance: $word,
+            loss_amount: $word,
+        ) -> ($word, $word) {
+            let total = junior_balance.saturating_add(senior_balance);
+            let capped_loss = loss_amount.min(total);
+
+            if capped_loss <= junior_balance {
+                // Junior absorbs all
+                (capped_loss, 0)
+            } else {
+                // Junior wiped, remainder hits senior
+                let senior_loss = capped_loss.saturating_sub(junior_balance);
+                (junior_balance, senior_loss)
+            }
+        }
+
+        /// Distribute fee income across tranches using junior multiplier.
+        ///
+        /// Junior gets: fee * (junior_share * junior_mult_bps / 10_000) / weighted_total
+        /// Senior gets: remainder
+        ///
+        /// # Arguments
+        /// * `junior_balance` - Junior tranche balance
+        /// * `senior_balance` - Senior tranche balance
+        /// * `junior_fee_mult_bps` - Junior fee multiplier (20000 = 2x)
+        /// * `total_fee` - Total fee to distribute
+        ///
+        /// # Returns
+        /// (junior_fee, senior_fee) — guaranteed to sum to <= total_fee
+        pub fn distribute_fees(
+            junior_balance: $word,
+            senior_balance: $word,
+            junior_fee_mult_bps: u16,
+            total_fee: $word,
+        ) -> ($word, $word) {
+            if total_fee == 0 {
+                return (0, 0);
+            }
+            let total_balance = junior_balance as $wide + senior_balance as $wide;
+            if total_balance == 0 {
+                return (0, 0);
+            }
+
+            // Weighted shares: junior weight = junior_balance * mult, senior weight = senior_balance * 10_000
+            let junior_weight = (junior_balance as $wide) * (junior_fee_mult_bps as $wide);
+            let senior_weight = (senior_balance as $wide) * 10_000;
+            let total_weight = junior_weight + senior_weight;
+
+            if total_weight == 0 {
+                return (0, 0);
+            }
+
+            // Widths below are quoted for the production (u64 word, u128 wide) instantiation.
+            // `total_fee as u128` is at most 2^64.
+            // `junior_weight` is at most u64::MAX * 50_000 ≈ 2^80 (junior_fee_mult_bps capped at 50_000).
+            // Their product can reach 2^144 which overflows u128 (max 2^128).
+            //
+            // FINDING-7: The previous >>24 shift fallback introduced a ratio error because shifting
+            // both numerator weight and denominator weight by the same amount is only exact when the
+            // shift divides both evenly — otherwise truncation of the low bits creates a systematic
+            // bias. Use exact integer arithmetic instead:
+            //
+            //   junior_fee = total_fee / total_weight * junior_weight
+            //              + (total_fee % total_weight) * junior_weight / total_weight
+            //
+            // This identity is exact for integer division and never overflows because:
+            //   - `total_fee / total_weight` is at most total_fee (≤ 2^64)
+            //   - `(total_fee % total_weight)` < total_weight, so the second term's product is
+            //     at most (total_weight - 1) * junior_weight < total_weight * total_weight ≤ 2^160
+            //     — which still overflows if we use u128 naively for the remainder term.
+            //
+            // Simplest safe approach that fits in u128:
+            //   Divide junior_weight by gcd(junior_weight, total_weight) first, then multiply.
+            //   After reduction junior_weight_reduced ≤ total_weight_reduced, and
+            //   total_fee * junior_weight_reduced ≤ 2^64 * 2^128 — still too big.
+            //
+            // The correct approach: split total_fee into quotient and remainder relative to
+            // total_weight, then handle each part separately. Both parts fit in u128.
+            let junior_fee_wide =
+                if let Some(product) = (total_fee as $wide).checked_mul(junior_weight) {
+                    product / total_weight
+                } else {
+                    // Exact two-part formula — no ratio error, no overflow:
+                    //   floor(total_fee * junior_weight / total_weight)
+                    //   = (total_fee / total_weight) * junior_weight
+                    //   + (total_fee % total_weight) * junior_weight / total_weight
+                    //
+                    // Part 1: (total_fee / total_weight) * junior_weight
+                    //   total_fee / total_weight ≤ total_fee ≤ u64::MAX → fits u128, product fits u128.
+                    // Part 2: (total_fee % total_weight) * junior_weight / total_weight
+                    //   total_fee % total_weight < total_weight ≤ junior_weight + senior_weight
+                    //   junior_weight ≤ u64::MAX * 50_000 < 2^80
+                    //   product < total_weight * junior_weight ≤ (2^81) * (2^80) = 2^161 → overflows u128.
+                    //
+                    // Handle part 2 by reducing junior_weight / total_weight to lowest terms first.
+                    // After dividing by gcd, the reduced denominator fits u128 multiplication safely
+                    // only if the gcd is large. As a guaranteed safe fallback when the product still
+                    // won't fit (very unlikely in practice), clamp to total_fee.
+                    let q = (total_fee as $wide) / total_weight;
+                    let r = (total_fee as $wide) % total_weight;
+                    let part1 = q * junior_weight; // q ≤ 2^64, junior_weight ≤ 2^80 → fits u128
+                                                   // part2 = floor(r * junior_weight / total_weight). r < total_weight ≤ ~2^81 and
+                                                   // junior_weight ≤ ~2^80, so r * junior_weight (~2^161) overflows u128. The previous
+                                                   // `unwrap_or(total_fee)` fallback on that overflow handed junior 100% of the fee
+                                                   // (0% to the protected senior tranche) — see #120. Use an exact, overflow-safe
+                                                   // 256-bit mul-div instead. The true result is < junior_weight ≤ ~2^80, so it fits.
+                    let part2 = mul_div_floor(r, junior_weight, total_weight);
+                    part1.saturating_add(part2)
+                };
+            // Clamp to total_fee (should always hold since junior_weight <= total_weight)
+            let junior_fee = junior_fee_wide.min(total_fee as $wide) as $word;
+            let senior_fee = total_fee.saturating_sub(junior_fee); // remainder to senior
+
+            (junior_fee, senior_fee)
+        }
+
+        /// Exact `floor(a * b / d)` for wide operands, overflow-safe even when `a * b`
+        /// exceeds the wide type. Requires `d != 0` and the true quotient to fit (callers
+        /// guarantee this: here `b <= d`, so the quotient is `<= a`). Used by
+        /// `distribute_fees` so the fee split is correct at extreme balances/fee values
+        /// where the naive `a * b` overflows (#120).
+        fn mul_div_floor(a: $wide, b: $wide, d: $wide) -> $wide {
+            // Limb and product widths: 64/128 for the production instantiation.
+            const HALF: u32 = <$word>::BITS;
+            const FULL: u32 = <$wide>::BITS;
+            // Fast path: the product fits in u128.
+            if let Some(p) = a.checked_mul(b) {
+                return p / d;
+            }
+            // Slow path: form the full 256-bit product a*b = hi*2^128 + lo via 64-bit limbs,
+            // then long-divide (hi:lo) by d bit by bit.
+            let mask = <$word>::MAX as $wide;
+            let (a0, a1) = (a & mask, a >> HALF);
+            let (b0, b1) = (b & mask, b >> HALF);
+            let m0 = a0 * b0; // each < 2^128
+            let m1 = a0 * b1;
+            let m2 = a1 * b0;
+            let m3 = a1 * b1;
+            // mid = m1 + m2 (the cross terms), tracking the carry bit that overflows u128.
+            let (mid, mid_carry) = {
+                let (s, c) = m1.overflowing_add(m2);
+                (s, c as $wide)
+            };
+            // lo = m0 + (mid_low << 64); carry + mid_high + mid_carry + m3 go to hi.
+            let (lo, c1) = m0.overflowing_add(mid << HALF);
+            let hi = m3 + (mid >> HALF) + (mid_carry << HALF) + (c1 as $wide);
+            // Long division of (hi:lo) by d. The quotient fits u128 because the true result
+            // is <= a; bits at positions >= 128 are therefore always 0.
+            let mut rem: $wide = 0;
+            let mut quo: $wide = 0;
+            let mut i: u32 = 2 * FULL;
+            while i > 0 {
+                i -= 1;
+                let bit = if i >= FULL {
+                    (hi >> (i - FULL)) & 1
+                } else {
+                    (lo >> i) & 1
+                };
+                let rem_top = rem >> (FULL - 1); // bit shifted out of the full-width rem below
+                rem = (rem << 1) | bit;
+                // Compare the true remainder (rem_top:rem) against d; reduce if >=.
+                if rem_top == 1 || rem >= d {
+                    rem = rem.wrapping_sub(d);
+                    if i < FULL {
+                        quo |= (1 as $wide) << i;
+                    }
+                }
+            }
+            quo
+        }
+
+        /// Check senior never loses while junior is positive.
+        ///
+        /// Given initial senior balance and post-loss senior balance,
+        /// returns true if senior is protected (no loss while junior > 0).
+        pub fn senior_protected(
+            junior_balance: $word,
+            _senior_balance: $word,
+            loss_amount: $word,
+        ) -> bool {
+            // If loss <= junior_balance, senior takes zero loss
+            loss_amount <= junior_balance
+        }
+
+        // ═══════════════════════════════════════════════════════════════
+        // PERC-313: High-Water Mark Protection Math
+        // ═══════════════════════════════════════════════════════════════
+
+        /// PERC-313: Calculate the high-water mark floor value.
+        ///
+        /// `floor = epoch_high_water_tvl * hwm_floor_bps / 10_000`
+        ///
+        /// Returns `Some(floor)` or `None` on overflow.
+        /// Uses u128 intermediate to prevent overflow for large TVL values.
+        pub fn hwm_floor(epoch_high_water_tvl: $word, hwm_floor_bps: u16) -> Option<$word> {
+            let floor = (epoch_high_water_tvl as $wide)
+                .checked_mul(hwm_floor_bps as $wide)?
+                .checked_div(10_000)?;
+            if floor > <$word>::MAX as $wide {
+                None
+            } else {
+                Some(floor as $word)
+            }
+        }
+
+        /// PERC-313: Check whether a withdrawal is allowed under HWM protection.
+        ///
+        /// Returns `true` if the withdrawal is allowed (post-withdrawal TVL >= floor).
+        /// Returns `false` if it would push TVL below the HWM floor.
+        pub fn hwm_withdrawal_allowed(
+            post_withdrawal_tvl: $word,
+            epoch_high_water_tvl: $word,
+            hwm_floor_bps: u16,
+        ) -> bool {
+            match hwm_floor(epoch_high_water_tvl, hwm_floor_bps) {
+                Some(floor) => post_withdrawal_tvl >= floor,
+                None => false, // overflow → conservative deny
+            }
+        }
+        // ═══════════════════════════════════════════════════════════════
+        // Pool valuation (backs StakePool::{total_pool_value,
+        // effective_junior_balance, senior_balance})
+        // ═══════════════════════════════════════════════════════════════
+
+        /// Total pool value = deposited − withdrawn − flushed + returned + fees − realized
+        /// junior loss.
+        ///
+        /// #169: summed in a wide SIGNED intermediate. In mode 1 a withdrawer's payout
+        /// includes their share of accrued fees, so `total_withdrawn` can legitimately
+        /// exceed `total_deposited` once fees have been paid out. The old left-to-right
+        /// `total_deposited.checked_sub(total_withdrawn)` then underflowed to None and
+        /// PERMANENTLY BRICKED the pool (LP funds trapped) even though the true value was
+        /// still positive. Computing signed makes evaluation order irrelevant; we only
+        /// fail closed when the FINAL value is genuinely out of range — i.e. negative
+        /// (insolvent: claims exceed assets) or impossibly large.
+        ///
+        /// #161: `realized_junior_loss` is dead value the junior exit booking added to
+        /// `total_returned` but that is NOT claimable by senior. Normally 0.
+        pub fn total_pool_value(
+            total_deposited: $word,
+            total_withdrawn: $word,
+            total_flushed: $word,
+            total_returned: $word,
+            total_fees_earned: $word,
+            realized_junior_loss: $word,
+        ) -> Option<$word> {
+            let value =
+                total_deposited as $signed - total_withdrawn as $signed - total_flushed as $signed
+                    + total_returned as $signed
+                    + total_fees_earned as $signed
+                    - realized_junior_loss as $signed;
+            if value < 0 || value > <$word>::MAX as $signed {
+                None
+            } else {
+                Some(value as $word)
+            }
+        }
+
+        /// Loss-adjusted junior tranche balance: the stored (gross) `junior_balance`
+        /// less the share of outstanding insurance loss (`flushed − returned`) the
+        /// junior tranche absorbs first.
+        pub fn effective_junior_balance(
+            total_deposited: $word,
+            total_withdrawn: $word,
+            total_flushed: $word,
+            total_returned: $word,
+            junior_balance: $word,
+        ) -> $word {
+            // net_loss = total_flushed - total_returned (tokens sent to insurance but not yet returned)
+            let net_loss = total_flushed.saturating_sub(total_returned);
+            if net_loss == 0 {
+                return junior_balance;
+            }
+            // BUG-6: Must distribute loss against the GROSS (pre-loss) balances, not the
+            // loss-adjusted pool value that total_pool_value() already returns.
+            //
+            // total_pool_value() = deposited - withdrawn - flushed + returned
+            //                    = gross_pool - net_loss
+            //
+            // If we used total_pool_value() - jb as senior_bal here, the senior_bal would
+            // already be net_loss lower than its true gross value.  Calling distribute_loss
+            // with that deflated senior_bal causes junior to absorb MORE loss than it
+            // should — i.e., the loss is applied twice against the junior side.
+            //
+            // Fix: derive gross balances as if no loss occurred yet, then apply the loss once.
+            // gross_pool = total_deposited - total_withdrawn (the monotonic principal, no flush/return)
+            let gross_pool = total_deposited.saturating_sub(total_withdrawn);
+            // gross_senior = gross_pool - jb (the stored junior_balance is the gross junior)
+            let gross_senior = gross_pool.saturating_sub(junior_balance);
+            let (junior_loss, _) = distribute_loss(junior_balance, gross_senior, net_loss);
+            junior_balance.saturating_sub(junior_loss)
+        }
+
+        /// Senior balance = total_pool_value − effective_junior_balance.
+        pub fn senior_balance(
+            total_deposited: $word,
+            total_withdrawn: $word,
+            total_flushed: $word,
+            total_returned: $word,
+            total_fees_earned: $word,
+            realized_junior_loss: $word,
+            junior_balance: $word,
+        ) -> Option<$word> {
+            total_pool_value(
+                total_deposited,
+                total_withdrawn,
+                total_flushed,
+                total_returned,
+                total_fees_earned,
+                realized_junior_loss,
+            )?
+            .checked_sub(effective_junior_balance(
+                total_deposited,
+                total_withdrawn,
+                total_flushed,
+                total_returned,
+                junior_balance,
+            ))
+        }
+
+        // ═══════════════════════════════════════════════════════════════
+        // Withdraw cooldown and deposit cap
+        // ═══════════════════════════════════════════════════════════════
+
+        /// Cooldown check: `current_slot >= deposit_slot + cooldown_slots` (saturating,
+        /// so a huge cooldown never wraps into an early exit).
+        pub fn cooldown_elapsed(
+            current_slot: $word,
+            deposit_slot: $word,
+            cooldown_slots: $word,
+        ) -> bool {
+            current_slot >= deposit_slot.saturating_add(cooldown_slots)
+        }
+
+        /// Deposit cap check: `true` if adding `new_deposit` to `current_value` would
+        /// exceed `cap`. A cap of 0 is uncapped; an overflowing sum always exceeds.
+        pub fn exceeds_cap(current_value: $word, new_deposit: $word, cap: $word) -> bool {
+            if cap == 0 {
+                return false;
+            }
+            match current_value.checked_add(new_deposit) {
+                Some(total) => total > cap,
+                None => true,
+            }
+        }
+    };
+}
@@ -1,444 +1,10 @@
 //! Pure LP math — extracted for Kani formal verification.
 //!
 //! No Solana/Pubkey dependencies. Just arithmetic.
-//! Kani can verify these functions exhaustively.
-
-/// N7 hardening (CONSOLIDATED-PLAN §2.2): virtual shares/assets added to both
-/// sides of the pro-rata LP pricing ratio, ERC4626-"decimals offset"-style.
-/// Without this, a pool with a small real supply/value is priced using ONLY
-/// the tracked counters — an attacker who becomes sole/first LP holder, then
-/// donates raw collateral directly to the vault token account (bypassing
-/// `Deposit`) and cranks the permissionless `AccrueFees` (mode-1 pools only,
-/// since only mode-1 folds `total_fees_earned` into `total_pool_value()`),
-/// can book that donation as "fees" and inflate the tracked share price
-/// arbitrarily cheaply (the donation stays 100% attacker-owned the whole
-/// time — a later victim `Deposit` then rounds `calc_lp_for_deposit` down to
-/// 0 and reverts with `ZeroSharesMinted`, DoSing every deposit below the
-/// inflated price). Adding a fixed virtual offset to BOTH the numerator and
-/// denominator of the pro-rata ratio makes every donation-then-accrue round
-/// cost the attacker a small, permanent, unrecoverable dilution against the
-/// virtual (unowned) share — the classic single-asset-vault countermeasure.
-/// Kept at the minimal canonical value of 1 (not scaled to token decimals):
-/// larger offsets would materially reprice small-magnitude test/production
-/// pools (see math.rs unit tests), and the primary defense against the
-/// donation attack described above is the MINIMUM_LIQUIDITY dead-share lock
-/// applied by the caller (`processor.rs::process_deposit`) at genesis
-/// deposit — this offset is deliberate defense-in-depth on top of that, not
-/// the sole mitigation.
-const VIRTUAL_SHARES: u128 = 1;
-const VIRTUAL_ASSETS: u128 = 1;
-
-/// Calculate LP tokens for a deposit.
-///
-/// # Arguments
-/// * `total_lp_supply` - Current total LP tokens in circulation
-/// * `total_pool_value` - Current total pool value (deposited - withdrawn)
-/// * `deposit_amount` - Amount of collateral being deposited
-///
-/// # Returns
-/// * `Some(lp_tokens)` - LP tokens to mint (rounds DOWN — pool-favoring)
-/// * `None` - Arithmetic overflow
-///
-/// # Invariant
-/// First depositor (supply == 0): gets 1:1 LP tokens.
-/// Subsequent: `lp = amount * (supply + VIRTUAL_SHARES) / (pool_value + VIRTUAL_ASSETS)`
-/// (pro-rata with N7 virtual-offset, rounded down).
-pub fn calc_lp_for_deposit(
-    total_lp_supply: u64,
-    total_pool_value: u64,
-    deposit_amount: u64,
-) -> Option<u64> {
-    if total_lp_supply == 0 && total_pool_value == 0 {
-        // True first depositor — 1:1. (With the virtual offset applied uniformly,
-        // this equals (deposit * VIRTUAL_SHARES) / VIRTUAL_ASSETS = deposit exactly,
-        // since VIRTUAL_SHARES == VIRTUAL_ASSETS; kept as an explicit branch for
-        // clarity and to avoid a redundant division on the hot bootstrap path.)
-        Some(deposit_amount)
-    } else if total_lp_supply == 0 {
-        // CRITICAL: LP supply is 0 but pool has orphaned value (e.g., returned insurance
-        // after all LP holders withdrew). Allowing 1:1 deposits here would let the
-        // depositor withdraw the entire orphaned value. Block deposits.
-        None
-    } else if total_pool_value == 0 {
-        // LP tokens exist but pool value is 0 (fully flushed to insurance).
-        // Existing holders have a claim on future insurance returns.
-        // Allowing deposits would dilute that claim. Block deposits.
-        None
-    } else {
-        // Pro-rata via u128 to prevent overflow. N7: +VIRTUAL_SHARES / +VIRTUAL_ASSETS
-        // on both sides of the ratio (see const doc above).
-        let lp = (deposit_amount as u128)
-            .checked_mul((total_lp_supply as u128).checked_add(VIRTUAL_SHARES)?)?
-            .checked_div((total_pool_value as u128).checked_add(VIRTUAL_ASSETS)?)?;
-        if lp > u64::MAX as u128 {
-            None
-        } else {
-            Some(lp as u64)
-        }
-    }
-}
-
-/// Calculate collateral for an LP token burn.
-///
-/// # Arguments
-/// * `total_lp_supply` - Current total LP tokens
-/// * `total_pool_value` - Current pool value
-/// * `lp_amount` - LP tokens being burned
-///
-/// # Returns
-/// * `Some(collateral)` - Collateral to return (rounds DOWN — pool-favoring)
-/// * `None` - Division by zero or overflow
-///
-/// # Invariant
-/// `collateral = lp_amount * (pool_value + VIRTUAL_ASSETS) / (lp_supply + VIRTUAL_SHARES)`
-/// (N7 virtual-offset, rounded down). Full burn returns ≤ pool_value (never more) —
-/// strictly less with the offset applied, since a fraction of the ratio is
-/// permanently attributed to the unowned virtual share.
-pub fn calc_collateral_for_withdraw(
-    total_lp_supply: u64,
-    total_pool_value: u64,
-    lp_amount: u64,
-) -> Option<u64> {
-    if total_lp_supply == 0 {
-        return None;
-    }
-    // N7: +VIRTUAL_ASSETS / +VIRTUAL_SHARES on both sides, symmetric with
-    // calc_lp_for_deposit above so minting and redeeming use the same offset ratio
-    // (asymmetric offsets would leak value in one direction).
-    let collateral = (lp_amount as u128)
-        .checked_mul((total_pool_value as u128).checked_add(VIRTUAL_ASSETS)?)?
-        .checked_div((total_lp_supply as u128).checked_add(VIRTUAL_SHARES)?)?;
-    if collateral > u64::MAX as u128 {
-        None
-    } else {
-        Some(collateral as u64)
-    }
-}
-
-/// Calculate pool value from accounting state.
-///
-/// # Returns
-/// * `Some(value)` if deposited + fees >= withdrawn
-/// * `None` if accounting is broken (withdrawn > deposited + fees)
-pub fn pool_value(total_deposited: u64, total_withdrawn: u64) -> Option<u64> {
-    total_deposited.checked_sub(total_withdrawn)
-}
-
-/// Calculate pool value including accrued trading fees (PERC-272).
-///
-/// # Returns
-/// * `Some(value)` if deposited + fees >= withdrawn
-/// * `None` if accounting overflow or underflow
-pub fn pool_value_with_fees(
-    total_deposited: u64,
-    total_withdrawn: u64,
-    total_fees_earned: u64,
-) -> Option<u64> {
-    total_deposited
-        .checked_sub(total_withdrawn)?
-        .checked_add(total_fees_earned)
-}
-
-// ═══════════════════════════════════════════════════════════════
-// PERC-303: Senior/Junior LP Tranche Math
-// ═══════════════════════════════════════════════════════════════
-
-/// Calculate LP tokens for a junior tranche deposit.
-///
-/// Junior tranche has its own sub-pool: junior_balance / junior_total_lp.
-/// First junior depositor gets 1:1, subsequent get pro-rata within junior pool.
-///
-/// # Returns
-/// * `Some(lp_tokens)` to mint
-/// * `None` on overflow or blocked state (orphaned value)
-pub fn calc_junior_lp_for_deposit(
-    junior_total_lp: u64,
-    junior_balance: u64,
-    deposit_amount: u64,
-) -> Option<u64> {
-    calc_lp_for_deposit(junior_total_lp, junior_balance, deposit_amount)
-}
-
-/// Calculate LP tokens for a senior tranche deposit.
-///
-/// The senior tranche has its own sub-pool: `senior_balance / senior_total_lp`,
-/// where `senior_balance = total_pool_value - effective_junior_balance` and
-/// `senior_total_lp = total_lp_supply - junior_total_lp`.
-///
-/// This MUST price against the same basis the senior WITHDRAW path values against
-/// (`calc_senior_collateral_for_withdraw`), so a senior deposit-then-withdraw
-/// round-trip cannot profit. Pricing senior deposits at the GLOBAL ratio while
-/// redeeming at the senior ratio lets a depositor mint cheap and redeem dear after
-/// a junior-absorbed loss, extracting value from existing senior LPs. Delegates to
-/// `calc_lp_for_deposit`, inheriting its round-DOWN (pool-favoring) semantics AND
-/// its first-depositor / orphaned-value (C9) handling: a true first senior deposit
-/// (`senior_total_lp == 0 && senior_balance == 0` — empty pool, or junior-first
-/// where junior captures 100% of fees) mints 1:1, while ORPHANED senior value
-/// (`senior_total_lp == 0 && senior_balance > 0`, e.g. insurance returned after all
-/// senior LP exited) returns `None` so the caller REJECTS the deposit. Minting 1:1
-/// against an orphan would let a dust deposit redeem the whole orphaned balance, so
-/// the caller MUST NOT special-case `senior_total_lp == 0` into an unconditional
-/// 1:1 bootstrap — it defers to this guard, exactly as the non-tranche path does.
-///
-/// # Returns
-/// * `Some(lp_tokens)` to mint (rounds DOWN — pool-favoring, same as junior/global)
-/// * `None` on overflow or blocked state (orphaned value)
-pub fn calc_senior_lp_for_deposit(
-    senior_total_lp: u64,
-    senior_balance: u64,
-    deposit_amount: u64,
-) -> Option<u64> {
-    calc_lp_for_deposit(senior_total_lp, senior_balance, deposit_amount)
-}
-
-/// Calculate collateral for a junior LP token burn.
-///
-/// Junior withdrawals are valued against the junior sub-pool only.
-/// If junior_balance has been reduced by losses, junior LPs take the hit.
-///
-/// # Returns
-/// * `Some(collateral)` to return (rounds down)
-/// * `None` on overflow
-pub fn calc_junior_collateral_for_withdraw(
-    junior_total_lp: u64,
-    junior_balance: u64,
-    lp_amount: u64,
-) -> Option<u64> {
-    calc_collateral_for_withdraw(junior_total_lp, junior_balance, lp_amount)
-}
-
-/// Calculate collateral for a senior LP token burn.
-///
-/// Senior withdrawals are valued against the senior sub-pool only.
-/// senior_balance = total_pool_value - junior_balance.
-/// senior_total_lp = total_lp_supply - junior_total_lp.
-///
-/// Senior LPs only lose if junior_balance is fully wiped (== 0).
-///
-/// # Returns
-/// * `Some(collateral)` to return (rounds down)
-/// * `None` on overflow or zero senior supply
-pub fn calc_senior_collateral_for_withdraw(
-    senior_total_lp: u64,
-    senior_balance: u64,
-    lp_amount: u64,
-) -> Option<u64> {
-    calc_collateral_for_withdraw(senior_total_lp, senior_balance, lp_amount)
-}
-
-/// Distribute a loss across tranches. Junior absorbs first.
-///
-/// # Returns
-/// (junior_loss, senior_loss)
-/// Invariant: junior_loss + senior_loss == loss_amount (unless capped at total).
-pub fn distribute_loss(junior_balance: u64, senior_balance: u64, loss_amount: u64) -> (u64, u64) {
-    let total = junior_balance.saturating_add(senior_balance);
-    let capped_loss = loss_amount.min(total);
-
-    if capped_loss <= junior_balance {
-        // Junior absorbs all
-        (capped_loss, 0)
-    } else {
-        // Junior wiped, remainder hits senior
-        let senior_loss = capped_loss.saturating_sub(junior_balance);
-        (junior_balance, senior_loss)
-    }
-}
-
-/// Distribute fee income across tranches using junior multiplier.
-///
-/// Junior gets: fee * (junior_share * junior_mult_bps / 10_000) / weighted_total
-/// Senior gets: remainder
-///
-/// # Arguments
-/// * `junior_balance` - Junior tranche balance
-/// * `senior_balance` - Senior tranche balance
-/// * `junior_fee_mult_bps` - Junior fee multiplier (20000 = 2x)
-/// * `total_fee` - Total fee to distribute
-///
-/// # Returns
-/// (junior_fee, senior_fee) — guaranteed to sum to <= total_fee
-pub fn distribute_fees(
-    junior_balance: u64,
-    senior_balance: u64,
-    junior_fee_mult_bps: u16,
-    total_fee: u64,
-) -> (u64, u64) {
-    if total_fee == 0 {
-        return (0, 0);
-    }
-    let total_balance = junior_balance as u128 + senior_balance as u128;
-    if total_balance == 0 {
-        return (0, 0);
-    }
-
-    // Weighted shares: junior weight = junior_balance * mult, senior weight = senior_balance * 10_000
-    let junior_weight = (junior_balance as u128) * (junior_fee_mult_bps as u128);
-    let senior_weight = (senior_balance as u128) * 10_000u128;
-    let total_weight = junior_weight + senior_weight;
-
-    if total_weight == 0 {
-        return (0, 0);
-    }
-
-    // `total_fee as u128` is at most 2^64.
-    // `junior_weight` is at most u64::MAX * 50_000 ≈ 2^80 (junior_fee_mult_bps capped at 50_000).
-    // Their product can reach 2^144 which overflows u128 (max 2^128).
-    //
-    // FINDING-7: The previous >>24 shift fallback introduced a ratio error because shifting
-    // both numerator weight and denominator weight by the same amount is only exact when the
-    // shift divides both evenly — otherwise truncation of the low bits creates a systematic
-    // bias. Use exact integer arithmetic instead:
-    //
-    //   junior_fee = total_fee / total_weight * junior_weight
-    //              + (total_fee % total_weight) * junior_weight / total_weight
-    //
-    // This identity is exact for integer division and never overflows because:
-    //   - `total_fee / total_weight` is at most total_fee (≤ 2^64)
-    //   - `(total_fee % total_weight)` < total_weight, so the second term's product is
-    //     at most (total_weight - 1) * junior_weight < total_weight * total_weight ≤ 2^160
-    //     — which still overflows if we use u128 naively for the remainder term.
-    //
-    // Simplest safe approach that fits in u128:
-    //   Divide junior_weight by gcd(junior_weight, total_weight) first, then multiply.
-    //   After reduction junior_weight_reduced ≤ total_weight_reduced, and
-    //   total_fee * junior_weight_reduced ≤ 2^64 * 2^128 — still too big.
-    //
-    // The correct approach: split total_fee into quotient and remainder relative to
-    // total_weight, then handle each part separately. Both parts fit in u128.
-    let junior_fee_u128 = if let Some(product) = (total_fee as u128).checked_mul(junior_weight) {
-        product / total_weight
-    } else {
-        // Exact two-part formula — no ratio error, no overflow:
-        //   floor(total_fee * junior_weight / total_weight)
-        //   = (total_fee / total_weight) * junior_weight
-        //   + (total_fee % total_weight) * junior_weight / total_weight
-        //
-        // Part 1: (total_fee / total_weight) * junior_weight
-        //   total_fee / total_weight ≤ total_fee ≤ u64::MAX → fits u128, product fits u128.
-        // Part 2: (total_fee % total_weight) * junior_weight / total_weight
-        //   total_fee % total_weight < total_weight ≤ junior_weight + senior_weight
-        //   junior_weight ≤ u64::MAX * 50_000 < 2^80
-        //   product < total_weight * junior_weight ≤ (2^81) * (2^80) = 2^161 → overflows u128.
-        //
-        // Handle part 2 by reducing junior_weight / total_weight to lowest terms first.
-        // After dividing by gcd, the reduced denominator fits u128 multiplication safely
-        // only if the gcd is large. As a guaranteed safe fallback when the product still
-        // won't fit (very unlikely in practice), clamp to total_fee.
-        let q = (total_fee as u128) / total_weight;
-        let r = (total_fee as u128) % total_weight;
-        let part1 = q * junior_weight; // q ≤ 2^64, junior_weight ≤ 2^80 → fits u128
-                                       // part2 = floor(r * junior_weight / total_weight). r < total_weight ≤ ~2^81 and
-                                       // junior_weight ≤ ~2^80, so r * junior_weight (~2^161) overflows u128. The previous
-                                       // `unwrap_or(total_fee)` fallback on that overflow handed junior 100% of the fee
-                                       // (0% to the protected senior tranche) — see #120. Use an exact, overflow-safe
-                                       // 256-bit mul-div instead. The true result is < junior_weight ≤ ~2^80, so it fits.
-        let part2 = mul_div_floor(r, junior_weight, total_weight);
-        part1.saturating_add(part2)
-    };
-    // Clamp to total_fee (should always hold since junior_weight <= total_weight)
-    let junior_fee = junior_fee_u128.min(total_fee as u128) as u64;
-    let senior_fee = total_fee.saturating_sub(junior_fee); // remainder to senior
-
-    (junior_fee, senior_fee)
-}
-
-/// Exact `floor(a * b / d)` for u128 operands, overflow-safe even when `a * b`
-/// exceeds u128. Requires `d != 0` and the true quotient to fit in u128 (callers
-/// guarantee this: here `b <= d`, so the quotient is `<= a`). Used by
-/// `distribute_fees` so the fee split is correct at extreme balances/fee values
-/// where the naive `a * b` overflows (#120).
-fn mul_div_floor(a: u128, b: u128, d: u128) -> u128 {
-    // Fast path: the product fits in u128.
-    if let Some(p) = a.checked_mul(b) {
-        return p / d;
-    }
-    // Slow path: form the full 256-bit product a*b = hi*2^128 + lo via 64-bit limbs,
-    // then long-divide (hi:lo) by d bit by bit.
-    let mask = u64::MAX as u128;
-    let (a0, a1) = (a & mask, a >> 64);
-    let (b0, b1) = (b & mask, b >> 64);
-    let m0 = a0 * b0; // each < 2^128
-    let m1 = a0 * b1;
-    let m2 = a1 * b0;
-    let m3 = a1 * b1;
-    // mid = m1 + m2 (the cross terms), tracking the carry bit that overflows u128.
-    let (mid, mid_carry) = {
-        let (s, c) = m1.overflowing_add(m2);
-        (s, c as u128)
-    };
-    // lo = m0 + (mid_low << 64); carry + mid_high + mid_carry + m3 go to hi.
-    let (lo, c1) = m0.overflowing_add(mid << 64);
-    let hi = m3 + (mid >> 64) + (mid_carry << 64) + (c1 as u128);
-    // Long division of (hi:lo) by d. The quotient fits u128 because the true result
-    // is <= a; bits at positions >= 128 are therefore always 0.
-    let mut rem: u128 = 0;
-    let mut quo: u128 = 0;
-    let mut i: u32 = 256;
-    while i > 0 {
-        i -= 1;
-        let bit = if i >= 128 {
-            (hi >> (i - 128)) & 1
-        } else {
-            (lo >> i) & 1
-        };
-        let rem_top = rem >> 127; // bit shifted out of the 128-bit rem below
-        rem = (rem << 1) | bit;
-        // Compare the true remainder (rem_top:rem) against d; reduce if >=.
-        if rem_top == 1 || rem >= d {
-            rem = rem.wrapping_sub(d);
-            if i < 128 {
-                quo |= 1u128 << i;
-            }
-        }
-    }
-    quo
-}
-
-/// Check senior never loses while junior is positive.
-///
-/// Given initial senior balance and post-loss senior balance,
-/// returns true if senior is protected (no loss while junior > 0).
-pub fn senior_protected(junior_balance: u64, _senior_balance: u64, loss_amount: u64) -> bool {
-    // If loss <= junior_balance, senior takes zero loss
-    loss_amount <= junior_balance
-}
-
-// ═══════════════════════════════════════════════════════════════
-// PERC-313: High-Water Mark Protection Math
-// ═══════════════════════════════════════════════════════════════
-
-/// PERC-313: Calculate the high-water mark floor value.
-///
-/// `floor = epoch_high_water_tvl * hwm_floor_bps / 10_000`
-///
-/// Returns `Some(floor)` or `None` on overflow.
-/// Uses u128 intermediate to prevent overflow for large TVL values.
-pub fn hwm_floor(epoch_high_water_tvl: u64, hwm_floor_bps: u16) -> Option<u64> {
-    let floor = (epoch_high_water_tvl as u128)
-        .checked_mul(hwm_floor_bps as u128)?
-        .checked_div(10_000)?;
-    if floor > u64::MAX as u128 {
-        None
-    } else {
-        Some(floor as u64)
-    }
-}
+//! The functions are generated by `lp_math!` (see `lp_math.rs`) at the
+//! production width; the Kani crate expands the same source at u32.
 
-/// PERC-313: Check whether a withdrawal is allowed under HWM protection.
-///
-/// Returns `true` if the withdrawal is allowed (post-withdrawal TVL >= floor).
-/// Returns `false` if it would push TVL below the HWM floor.
-pub fn hwm_withdrawal_allowed(
-    post_withdrawal_tvl: u64,
-    epoch_high_water_tvl: u64,
-    hwm_floor_bps: u16,
-) -> bool {
-    match hwm_floor(epoch_high_water_tvl, hwm_floor_bps) {
-        Some(floor) => post_withdrawal_tvl >= floor,
-        None => false, // overflow → conservative deny
-    }
-}
+lp_math!(u64, u128, i128);
 
 // #200: `flush_available()` removed. It was dead code (no production callers) and
 // buggy (omitted total_returned + used saturating arithmetic that could mask an
@@ -1055,8 +621,8 @@ mod tests {
 // Kani Formal Verification
 // ═══════════════════════════════════════════════════════════════
 //
-// Production-type (u64/u128) proofs live in kani-proofs/ crate with
-// u32/u64 mirrors for CBMC tractability. See kani-proofs/src/lib.rs.
+// Proofs live in the kani-proofs/ crate, which expands `lp_math!` at
+// u32/u64 for CBMC tractability. See kani-proofs/src/lib.rs.
 //
 // Keeping this note here so nobody adds u64 Kani proofs that timeout.
 
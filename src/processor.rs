@@ -45,6 +45,13 @@ fn verify_token_program(token_program: &AccountInfo) -> ProgramResult {
 /// seeds incl. bump (used by every `invoke_signed`). Any sub-step error reverts the whole
 /// transaction, so no half-created account can persist. Callers must run the
 /// wrong-owner-with-data guard and the PDA address-binding check first.
+///
+/// CloseDeposit (tag 35) makes "closed, then recreated" a reachable lifecycle, so the
+/// squat-state precondition is now enforced here rather than assumed: the target must
+/// be System-owned with empty data. `close_deposit_pda` zeroes, shrinks and re-assigns
+/// the record to the System program, so a closed PDA — even one a griefer refunds in
+/// the same transaction — is exactly that squat state and is recreated zero-filled;
+/// anything else (a program-owned shell carrying stale bytes) is refused outright.
 fn create_or_adopt_pda<'a>(
     target: &AccountInfo<'a>,
     payer: &AccountInfo<'a>,
@@ -53,6 +60,10 @@ fn create_or_adopt_pda<'a>(
     space: usize,
     signer_seeds: &[&[u8]],
 ) -> ProgramResult {
+    if *target.owner != solana_program::system_program::id() || !target.data_is_empty() {
+        msg!("Error: PDA to create is not an empty System-owned account");
+        return Err(StakeError::InvalidAccount.into());
+    }
     let rent = Rent::get()?;
     let need = rent.minimum_balance(space);
 
@@ -117,6 +128,14 @@ const MAX_COOLDOWN_SLOTS: u64 = 78_840_000;
 /// what the cooldown timelock already provides.
 pub const TIMELOCK_SLOTS: u64 = 432_000; // ~48 hours at 2.5 slots/sec
 
+// A proposal must stay actionable past its own timelock (`state::PROPOSAL_EXPIRY_SLOTS`).
+const _: () = assert!(PROPOSAL_EXPIRY_SLOTS > TIMELOCK_SLOTS);
+
+/// Sunset grace period: how long after `Finalize` LP holders have to `RedeemFinal`
+/// before anyone may `SweepAndClosePool` the remainder to the configured recipient.
+/// ~1 year at 2.5 slots/sec — the same horizon as `MAX_COOLDOWN_SLOTS`.
+pub const FINAL_SWEEP_GRACE_SLOTS: u64 = 78_840_000;
+
 /// #242: returns whether the timelock window has elapsed for a proposal made at slot
 /// `proposed_at`, given the current slot `now` and `timelock_slots`. Pure + checked
 /// (a `proposed_at + timelock_slots` overflow ⇒ `Err`, never a panic). The caller is
@@ -227,6 +246,16 @@ fn validate_account_owner(account: &AccountInfo, expected_owner: &Pubkey) -> Pro
 /// as a fresh-start reseed with zero live v2 pools, so no in-place migration
 /// was written. Any future upgrade over live v2 pools needs the same
 /// drain-before-upgrade discipline.
+///
+/// And to the v4 -> v5 bump (408 -> 736 bytes: the sunset, fee-policy, veto,
+/// council, role, proposal-expiry, flush-policy and deficit fields, see
+/// `StakePool::CURRENT_VERSION`). Live v4 pools freeze the moment this build
+/// lands, so they MUST be drained before the upgrade. The wrapper also checks
+/// the pool's version byte EXACTLY and its length against `STAKE_POOL_LEN`
+/// (see the layout contract in state.rs): this upgrade ships only together
+/// with a wrapper release bumping `STAKE_POOL_VERSION` to 5 and
+/// `STAKE_POOL_LEN` to 736, or tag-87 stops paying the insurance fee leg to
+/// every v5 pool.
 fn validate_pool_version(pool: &StakePool) -> ProgramResult {
     let version = pool.version();
     if version != StakePool::CURRENT_VERSION {
@@ -240,6 +269,77 @@ fn validate_pool_version(pool: &StakePool) -> ProgramResult {
     Ok(())
 }
 
+/// Authorize an admin-gated instruction. `admin` is the account in the
+/// instruction's admin slot: the pool admin, which must sign, or the pool's
+/// active council PDA, in which case at least its `threshold` distinct members
+/// must sign somewhere in `accounts`.
+fn authorize_admin(
+    program_id: &Pubkey,
+    pool: &StakePool,
+    admin: &AccountInfo,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let authority = if pool.council != [0u8; 32] && admin.key.to_bytes() == pool.council {
+        pool.council
+    } else {
+        pool.admin
+    };
+    authorize_key(program_id, pool, authority, admin, accounts)
+}
+
+/// Authorize a role-gated instruction (see the `ROLE_*` table in state.rs).
+/// An unassigned role falls back to `authorize_admin`; an assigned one
+/// accepts only its holder — which may itself be the council PDA.
+fn authorize_role(
+    program_id: &Pubkey,
+    pool: &StakePool,
+    role: u8,
+    caller: &AccountInfo,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let holder = pool.role_holder(role);
+    if holder == [0u8; 32] {
+        return authorize_admin(program_id, pool, caller, accounts);
+    }
+    authorize_key(program_id, pool, holder, caller, accounts)
+}
+
+/// `caller` must be `key` and act for it: sign, or — when `key` is the pool's
+/// active council PDA — carry a threshold of member signatures in `accounts`.
+fn authorize_key(
+    program_id: &Pubkey,
+    pool: &StakePool,
+    key: [u8; 32],
+    caller: &AccountInfo,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    if key == [0u8; 32] || caller.key.to_bytes() != key {
+        if !caller.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        return Err(StakeError::Unauthorized.into());
+    }
+    if key != pool.council {
+        if !caller.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        return Ok(());
+    }
+    validate_account_owner(caller, program_id)?;
+    let council_data = caller.try_borrow_data()?;
+    let council = council_from_data(&council_data[..])?;
+    if council.is_initialized != 1 || !council.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    let signers = accounts.iter().filter(|a| a.is_signer).map(|a| a.key);
+    if state::count_council_signers(council.active_members(), signers) < council.threshold as usize
+    {
+        msg!("Error: council threshold {} not met", council.threshold);
+        return Err(StakeError::CouncilThresholdNotMet.into());
+    }
+    Ok(())
+}
+
 /// Validate that an account is writable.
 /// Returns InvalidAccount error if account is read-only.
 fn validate_account_writable(account: &AccountInfo) -> ProgramResult {
@@ -310,11 +410,20 @@ fn validate_account_empty(account: &AccountInfo) -> ProgramResult {
     Ok(())
 }
 
+use crate::accounting::{self, apply_minimum_liquidity_lock};
 use crate::cpi;
 use crate::error::StakeError;
 use crate::instruction::StakeInstruction;
+use crate::return_data::{StakeReturnData, TRANCHE_JUNIOR, TRANCHE_POOL, TRANCHE_SENIOR};
 use crate::state::{
-    self, derive_vault_authority, StakeDeposit, StakePool, STAKE_DEPOSIT_SIZE, STAKE_POOL_SIZE,
+    self, derive_vault_authority, proposal_expired, FeePolicyBounds, StakeCouncil, StakeDeposit,
+    StakePool, StakeProposal, StakeVetoVote, CONFIG_LOCK_ALL, CONFIG_LOCK_DEPOSIT_CAP,
+    CONFIG_LOCK_FLUSH_POLICY, CONFIG_LOCK_HWM, FEE_HISTORY_BACKING_FEE_KNOWN,
+    FEE_HISTORY_LP_SHARE_KNOWN, FEE_HISTORY_TRADE_FEE_KNOWN, PROPOSAL_CALL_MAX_LEN,
+    PROPOSAL_EXPIRY_SLOTS, PROPOSAL_STATUS_CANCELLED, PROPOSAL_STATUS_COMMITTED,
+    PROPOSAL_STATUS_PENDING, ROLE_FEE_MANAGER, ROLE_FLUSH_OPERATOR, STAKE_COUNCIL_SIZE,
+    STAKE_DEPOSIT_SIZE, STAKE_POOL_SIZE, STAKE_PROPOSAL_SIZE, STAKE_VETO_VOTE_SIZE,
+    VETO_TARGET_ADMIN, VETO_TARGET_COOLDOWN_INCREASE, VETO_TARGET_FEE_CHANGE,
 };
 
 // ─────────────────────────────────────────────────────────────────────────
@@ -363,6 +472,116 @@ fn deposit_from_data(data: &[u8]) -> Result<&StakeDeposit, ProgramError> {
         .map_err(|_| ProgramError::InvalidAccountData)
 }
 
+fn proposal_from_data_mut(data: &mut [u8]) -> Result<&mut StakeProposal, ProgramError> {
+    if data.len() < STAKE_PROPOSAL_SIZE {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    bytemuck::try_from_bytes_mut::<StakeProposal>(&mut data[..STAKE_PROPOSAL_SIZE])
+        .map_err(|_| ProgramError::InvalidAccountData)
+}
+
+fn veto_vote_from_data_mut(data: &mut [u8]) -> Result<&mut StakeVetoVote, ProgramError> {
+    if data.len() < STAKE_VETO_VOTE_SIZE {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    bytemuck::try_from_bytes_mut::<StakeVetoVote>(&mut data[..STAKE_VETO_VOTE_SIZE])
+        .map_err(|_| ProgramError::InvalidAccountData)
+}
+
+fn council_from_data(data: &[u8]) -> Result<&StakeCouncil, ProgramError> {
+    if data.len() < STAKE_COUNCIL_SIZE {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    bytemuck::try_from_bytes::<StakeCouncil>(&data[..STAKE_COUNCIL_SIZE])
+        .map_err(|_| ProgramError::InvalidAccountData)
+}
+
+fn council_from_data_mut(data: &mut [u8]) -> Result<&mut StakeCouncil, ProgramError> {
+    if data.len() < STAKE_COUNCIL_SIZE {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    bytemuck::try_from_bytes_mut::<StakeCouncil>(&mut data[..STAKE_COUNCIL_SIZE])
+        .map_err(|_| ProgramError::InvalidAccountData)
+}
+
+// ─────────────────────────────────────────────────────────────────────────
+// Post-CPI vault balance verification.
+//
+// Every ledger counter that tracks tokens in or out of `pool.vault`
+// (total_deposited, total_withdrawn, total_flushed, total_returned,
+// total_recovered_from_wrapper) used to advance by the REQUESTED amount as soon
+// as the token-moving CPI returned Ok. That trusts the callee to have moved
+// exactly that amount — fine for the frozen SPL Token program, but not for the
+// upgradeable wrapper, where a fee, a rounding change or a partial fill would
+// make the ledger silently diverge from the vault. `cpi_with_vault_delta`
+// snapshots the vault balance around the CPI and returns the OBSERVED movement,
+// failing the whole instruction unless it equals the requested amount.
+// ─────────────────────────────────────────────────────────────────────────
+
+/// Direction a token-moving CPI is expected to move `pool.vault`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum VaultFlow {
+    /// Tokens land in the vault (deposit, ReturnInsurance, recovery).
+    In,
+    /// Tokens leave the vault (withdraw, flush).
+    Out,
+}
+
+/// SPL token balance of `vault`, which must be an initialized token account.
+fn vault_token_balance(vault: &AccountInfo) -> Result<u64, ProgramError> {
+    if *vault.owner != crate::spl_token::id() {
+        msg!("Error: vault is not owned by the SPL Token program");
+        return Err(StakeError::InvalidAccount.into());
+    }
+    let data = vault.try_borrow_data()?;
+    let state = crate::spl_token::state::Account::unpack(&data)?;
+    if state.state != crate::spl_token::state::AccountState::Initialized {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(state.amount)
+}
+
+/// The vault movement from `before` to `after` in `flow`'s direction, or
+/// `VaultDeltaMismatch` unless it is exactly `expected`.
+fn observed_vault_delta(
+    before: u64,
+    after: u64,
+    flow: VaultFlow,
+    expected: u64,
+) -> Result<u64, ProgramError> {
+    let moved = match flow {
+        VaultFlow::In => after.checked_sub(before),
+        VaultFlow::Out => before.checked_sub(after),
+    };
+    if moved != Some(expected) {
+        msg!(
+            "Error: vault moved {} -> {} ({:?}), expected a delta of {}",
+            before,
+            after,
+            flow,
+            expected
+        );
+        return Err(StakeError::VaultDeltaMismatch.into());
+    }
+    Ok(expected)
+}
+
+/// Runs `cpi` — a CPI moving `expected` tokens into or out of `vault` — and
+/// returns the movement it actually produced, for the caller to book.
+///
+/// `vault` must not be borrowed across this call: the callee needs to write it.
+fn cpi_with_vault_delta(
+    vault: &AccountInfo,
+    flow: VaultFlow,
+    expected: u64,
+    cpi: impl FnOnce() -> ProgramResult,
+) -> Result<u64, ProgramError> {
+    let before = vault_token_balance(vault)?;
+    cpi()?;
+    let after = vault_token_balance(vault)?;
+    observed_vault_delta(before, after, flow, expected)
+}
+
 pub fn process(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -374,11 +593,23 @@ pub fn process(
         StakeInstruction::InitPool {
             cooldown_slots,
             deposit_cap,
-        } => process_init_pool(program_id, accounts, cooldown_slots, deposit_cap),
+            fee_policy,
+            asset_index,
+            wrapper_abi_version,
+        } => process_init_pool(
+            program_id,
+            accounts,
+            cooldown_slots,
+            deposit_cap,
+            fee_policy,
+            asset_index,
+            wrapper_abi_version,
+        ),
         StakeInstruction::Deposit { amount } => process_deposit(program_id, accounts, amount),
-        StakeInstruction::Withdraw { lp_amount } => {
-            process_withdraw(program_id, accounts, lp_amount)
-        }
+        StakeInstruction::Withdraw {
+            lp_amount,
+            close_if_empty,
+        } => process_withdraw(program_id, accounts, lp_amount, close_if_empty),
         StakeInstruction::FlushToInsurance { amount } => {
             process_flush_to_insurance(program_id, accounts, amount)
         }
@@ -419,7 +650,18 @@ pub fn process(
         StakeInstruction::InitTradingPool {
             cooldown_slots,
             deposit_cap,
-        } => process_init_trading_pool(program_id, accounts, cooldown_slots, deposit_cap),
+            fee_policy,
+            asset_index,
+            wrapper_abi_version,
+        } => process_init_trading_pool(
+            program_id,
+            accounts,
+            cooldown_slots,
+            deposit_cap,
+            fee_policy,
+            asset_index,
+            wrapper_abi_version,
+        ),
         StakeInstruction::AdminSetHwmConfig {
             enabled,
             hwm_floor_bps,
@@ -432,38 +674,81 @@ pub fn process(
         }
         StakeInstruction::SetMarketResolved => process_set_market_resolved(program_id, accounts),
         StakeInstruction::AdminResolveMarket => process_admin_resolve_market(program_id, accounts),
-        StakeInstruction::AdminUpdateFeeSplit {
-            creator_share_bps,
-            lp_share_bps,
-            insurance_share_bps,
-        } => process_admin_update_fee_split(
-            program_id,
-            accounts,
-            creator_share_bps,
-            lp_share_bps,
-            insurance_share_bps,
-        ),
-        StakeInstruction::AdminUpdateMaintenanceFeePerSlot {
-            maintenance_fee_per_slot,
-        } => process_admin_update_maintenance_fee_per_slot(
-            program_id,
-            accounts,
-            maintenance_fee_per_slot,
-        ),
-        StakeInstruction::AdminUpdateBackingFeePolicy {
-            domain,
-            fee_bps,
-            insurance_share_bps,
-        } => process_admin_update_backing_fee_policy(
+        // Fee-policy proxies run only as the replay of a timelocked proposal
+        // (tags 39/40), never on the admin's direct signature.
+        StakeInstruction::AdminUpdateFeeSplit { .. }
+        | StakeInstruction::AdminUpdateMaintenanceFeePerSlot { .. }
+        | StakeInstruction::AdminUpdateBackingFeePolicy { .. }
+        | StakeInstruction::AdminUpdateTradeFeePolicy { .. } => {
+            msg!("Error: fee-policy proxies execute only via ProposeFeeChange / CommitFeeChange");
+            Err(StakeError::FeeChangeRequiresTimelock.into())
+        }
+        StakeInstruction::DepositFor {
+            beneficiary,
+            amount,
+        } => process_deposit_for(
             program_id,
             accounts,
-            domain,
-            fee_bps,
-            insurance_share_bps,
+            Pubkey::new_from_array(beneficiary),
+            amount,
         ),
-        StakeInstruction::AdminUpdateTradeFeePolicy { trade_fee_base_bps } => {
-            process_admin_update_trade_fee_policy(program_id, accounts, trade_fee_base_bps)
+        StakeInstruction::WithdrawTo { lp_amount } => {
+            process_withdraw_to(program_id, accounts, lp_amount)
+        }
+        StakeInstruction::SetAuthorizedDepositor { depositor } => {
+            process_set_authorized_depositor(program_id, accounts, depositor)
+        }
+        StakeInstruction::OperatorWithdraw { lp_amount } => {
+            process_operator_withdraw(program_id, accounts, lp_amount)
+        }
+        StakeInstruction::SetDepositOperator { operator } => {
+            process_set_deposit_operator(program_id, accounts, operator)
+        }
+        StakeInstruction::RevokeDepositOperator => {
+            process_revoke_deposit_operator(program_id, accounts)
+        }
+        StakeInstruction::CloseDeposit => process_close_deposit(program_id, accounts),
+        StakeInstruction::Finalize { sweep_recipient } => {
+            process_finalize(program_id, accounts, sweep_recipient)
         }
+        StakeInstruction::RedeemFinal { lp_amount } => {
+            process_redeem_final(program_id, accounts, lp_amount)
+        }
+        StakeInstruction::SweepAndClosePool => process_sweep_and_close_pool(program_id, accounts),
+        StakeInstruction::ProposeFeeChange { proposal_id, call } => {
+            process_propose_fee_change(program_id, accounts, proposal_id, &call)
+        }
+        StakeInstruction::CommitFeeChange => process_commit_fee_change(program_id, accounts),
+        StakeInstruction::CancelFeeChange => process_cancel_fee_change(program_id, accounts),
+        StakeInstruction::SetFeePolicyBounds { bounds } => {
+            process_set_fee_policy_bounds(program_id, accounts, bounds, false)
+        }
+        StakeInstruction::CastVeto {
+            target_kind,
+            lp_amount,
+        } => process_cast_veto(program_id, accounts, target_kind, lp_amount),
+        StakeInstruction::ReclaimVeto => process_reclaim_veto(program_id, accounts),
+        StakeInstruction::SetVetoThreshold { threshold_bps } => {
+            process_set_veto_threshold(program_id, accounts, threshold_bps, false)
+        }
+        StakeInstruction::ProposeCouncil { threshold, members } => {
+            process_propose_council(program_id, accounts, threshold, &members)
+        }
+        StakeInstruction::AcceptCouncil => process_accept_council(program_id, accounts),
+        StakeInstruction::ProposeRole { role, holder } => {
+            process_propose_role(program_id, accounts, role, holder)
+        }
+        StakeInstruction::AcceptRole { role } => process_accept_role(program_id, accounts, role),
+        StakeInstruction::SetFlushPolicy { max_flush_bps } => {
+            process_set_flush_policy(program_id, accounts, max_flush_bps)
+        }
+        StakeInstruction::LockConfig { locks } => process_lock_config(program_id, accounts, locks),
+        StakeInstruction::RenounceAdmin => process_renounce_admin(program_id, accounts),
+        StakeInstruction::AssertPoolInvariants => {
+            process_assert_pool_invariants(program_id, accounts)
+        }
+        StakeInstruction::DeclareDeficit => process_declare_deficit(program_id, accounts),
+        StakeInstruction::ProbeWrapper => process_probe_wrapper(program_id, accounts),
     }
 }
 
@@ -476,6 +761,9 @@ fn process_init_pool(
     accounts: &[AccountInfo],
     cooldown_slots: u64,
     deposit_cap: u64,
+    fee_policy: FeePolicyBounds,
+    asset_index: u16,
+    wrapper_abi_version: u8,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
@@ -529,6 +817,10 @@ fn process_init_pool(
     // updated to any non-zero value without a race window where it had no cooldown.
     validate_cooldown_slots(cooldown_slots)?;
 
+    // Every CPI this pool will ever issue is encoded for this wrapper revision;
+    // an unknown one is refused before anything is created.
+    let abi = cpi::wrapper_abi(wrapper_abi_version)?;
+
     // Derive and verify pool PDA
     let (expected_pool, pool_bump) = state::derive_pool_pda(program_id, slab.key);
     if *pool_pda.key != expected_pool {
@@ -605,7 +897,7 @@ fn process_init_pool(
     // InitPool CPI (percolator-vault@eb3ebe8 src/processor.rs:340) so a redeploy of
     // THIS program preserves the exact on-chain behavior the launch wizard's
     // authority-sequencing depends on. See `cpi::cpi_update_authority` doc comment.
-    cpi::cpi_update_authority(percolator_program, admin, pool_pda, slab, pool_seeds)?;
+    cpi::cpi_update_authority(abi, percolator_program, admin, pool_pda, slab, pool_seeds)?;
 
     // Create LP mint (mint authority = vault_auth PDA, freeze authority = None).
     // FINDING-4: Passing Some(vault_auth.key) as freeze authority would allow the
@@ -699,11 +991,20 @@ fn process_init_pool(
     pool.last_vault_snapshot = 0;
     pool.pool_mode = 0; // InitTradingPool overrides to 1 after this call
     pool.pending_admin = [0u8; 32];
+    pool.admin_proposed_at_slot = 0;
+    // The LP terms fee-policy proxies are held to from here on; only a
+    // timelocked SetFeePolicyBounds can relax them.
+    pool.set_fee_policy_bounds(fee_policy);
+    // The wrapper asset every tag-65/57 CPI names. Immutable: the bind, burn
+    // and recovery CPIs must keep addressing the profile the pool backs.
+    pool.wrapper_asset_index = asset_index;
+    pool.wrapper_abi_version = abi.version();
     pool.set_discriminator();
 
     msg!(
-        "StakePool initialized for slab {} (admin transfer pending)",
-        slab.key
+        "StakePool initialized for slab {} asset {} (admin transfer pending)",
+        slab.key,
+        asset_index
     );
     Ok(())
 }
@@ -713,6 +1014,20 @@ fn process_init_pool(
 // ═══════════════════════════════════════════════════════════════
 
 fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    deposit_inner(program_id, accounts, amount, None)
+}
+
+/// Shared body of `Deposit` (tag 1) and `DepositFor` (tag 29). `beneficiary` is
+/// `None` for a plain deposit, where the signer is also the position owner.
+/// With `Some(owner)`, the signer still funds from its own token account, but
+/// the LP recipient and the deposit PDA (and so the cooldown clock) are the
+/// beneficiary's, and the beneficiary's opt-in is enforced up front.
+fn deposit_inner(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    beneficiary: Option<Pubkey>,
+) -> ProgramResult {
     if amount == 0 {
         return Err(StakeError::ZeroAmount.into());
     }
@@ -734,6 +1049,7 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
     if !user.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
+    let owner_key = beneficiary.unwrap_or(*user.key);
 
     // I4: Validate pool account exists and is owned by stake program
     validate_account_not_empty(pool_pda)?;
@@ -769,6 +1085,13 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
         return Err(StakeError::InvalidAccount.into());
     }
 
+    // DepositFor: reject before any token moves unless the beneficiary opted in to
+    // THIS signer — a deposit restarts the position's cooldown (BUG-8), so an
+    // unauthorized third party could otherwise lock the beneficiary out.
+    if beneficiary.is_some() {
+        validate_deposit_for_opt_in(program_id, pool_pda, deposit_pda, &owner_key, user.key)?;
+    }
+
     // Check deposit cap against CURRENT pool value, not lifetime deposits.
     // Using total_deposited (monotonically increasing) would permanently lock
     // the pool once lifetime deposits hit the cap, even if 99% was withdrawn.
@@ -777,10 +1100,7 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
         // #154: enforce the cap on PRINCIPAL TVL (no accrued fees), not total_pool_value();
         // otherwise fee appreciation on a mode-1 trading pool silently locks out new deposits.
         let current_value = pool.principal_tvl().ok_or(StakeError::Overflow)?;
-        let new_value = current_value
-            .checked_add(amount)
-            .ok_or(StakeError::Overflow)?;
-        if new_value > pool.deposit_cap {
+        if crate::math::exceeds_cap(current_value, amount, pool.deposit_cap) {
             return Err(StakeError::DepositCapExceeded.into());
         }
     }
@@ -822,10 +1142,11 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
         }
     }
 
-    // #211: the LP mint destination (user_lp_ata) must be owned by the depositor
-    // source. Otherwise LP receipts can land in a token account the depositor
-    // does not control while the deposit record stays keyed to the depositor.
-    validate_lp_recipient_account(user_lp_ata, &pool.lp_mint, user.key)?;
+    // #211: the LP mint destination (user_lp_ata) must be owned by the position
+    // owner (the signer, or the DepositFor beneficiary). Otherwise LP receipts can
+    // land in a token account the owner does not control while the deposit record
+    // stays keyed to the owner.
+    validate_lp_recipient_account(user_lp_ata, &pool.lp_mint, &owner_key)?;
 
     // #136: crystallize pending trading-fee surplus into share price BEFORE pricing this
     // deposit (modes 0 and 1 as of the 2026-07-19 fee-collection design's THIRD EDIT), so
@@ -838,6 +1159,13 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
     // which is dropped here. The senior recovery-snipe gate below is added AFTER it.)
     pre_accrue_fee_modes(pool, vault)?;
 
+    // Deficit mode: no new capital into a pool whose vault was found short —
+    // including a shortfall the pre-accrue above just detected (that write-down
+    // reverts with this deposit; DeclareDeficit is what makes it stick).
+    if pool.in_deficit() {
+        return Err(StakeError::PoolInDeficit.into());
+    }
+
     // Insurance recovery-snipe gate (SENIOR path) — completes #150 for the senior tranche
     // (see #159). When tranches are on and a flushed loss has spilled PAST junior into
     // senior, the senior sub-pool is marked down (distribute_loss: senior_loss > 0 IFF
@@ -930,22 +1258,24 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
     let mint_amount = apply_minimum_liquidity_lock(total_lp_supply_before, lp_to_mint)?;
 
     // Transfer collateral: user ATA → stake vault
-    invoke(
-        &crate::spl_token::transfer(
-            token_program.key,
-            user_ata.key,
-            vault.key,
-            user.key,
-            &[],
-            amount,
-        )?,
-        &[
-            user_ata.clone(),
-            vault.clone(),
-            user.clone(),
-            token_program.clone(),
-        ],
-    )?;
+    let deposited = cpi_with_vault_delta(vault, VaultFlow::In, amount, || {
+        invoke(
+            &crate::spl_token::transfer(
+                token_program.key,
+                user_ata.key,
+                vault.key,
+                user.key,
+                &[],
+                amount,
+            )?,
+            &[
+                user_ata.clone(),
+                vault.clone(),
+                user.clone(),
+                token_program.clone(),
+            ],
+        )
+    })?;
 
     // Mint LP tokens to user. N7: mints `mint_amount` (== lp_to_mint minus the
     // MINIMUM_LIQUIDITY dead-share floor on the genesis deposit only), not the
@@ -977,7 +1307,7 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
     // circulating supply IS the dead-share lock (see state::MINIMUM_LIQUIDITY doc).
     pool.total_deposited = pool
         .total_deposited
-        .checked_add(amount)
+        .checked_add(deposited)
         .ok_or(StakeError::Overflow)?;
     pool.total_lp_supply = pool
         .total_lp_supply
@@ -993,7 +1323,7 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
 
     // Create or update per-user deposit PDA (cooldown tracking)
     let (expected_deposit_pda, deposit_bump) =
-        state::derive_deposit_pda(program_id, pool_pda.key, user.key);
+        state::derive_deposit_pda(program_id, pool_pda.key, &owner_key);
     if *deposit_pda.key != expected_deposit_pda {
         return Err(StakeError::InvalidPda.into());
     }
@@ -1007,7 +1337,7 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
         let deposit_seeds: &[&[u8]] = &[
             b"stake_deposit",
             pool_pda.key.as_ref(),
-            user.key.as_ref(),
+            owner_key.as_ref(),
             &[deposit_bump],
         ];
         // Robust against deposit-PDA squatting: a griefer who pre-funds this
@@ -1046,13 +1376,15 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
     deposit.is_initialized = 1;
     deposit.bump = deposit_bump;
     deposit.pool = pool_pda.key.to_bytes();
-    deposit.user = user.key.to_bytes();
+    deposit.user = owner_key.to_bytes();
     // BUG-8 (design note): Any deposit by a user resets last_deposit_slot for their ENTIRE
     // position, restarting the cooldown clock for all LP tokens they hold — not just the
     // newly minted ones.  This is intentional: it prevents users from avoiding cooldown by
     // making tiny "top-up" deposits while their main stake sits uncooled.  The trade-off is
     // that adding to an existing position extends the withdrawal wait for the whole balance.
+    // The top-up likewise re-freezes the position's cooldown terms at the pool's current value.
     deposit.last_deposit_slot = clock.slot;
+    deposit.set_recorded_cooldown_slots(pool.cooldown_slots);
     deposit.lp_amount = deposit
         .lp_amount
         .checked_add(lp_to_mint)
@@ -1063,6 +1395,24 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
         amount,
         lp_to_mint
     );
+
+    // Return data last: the runtime clears it on every CPI above. lp_minted is what
+    // the depositor actually received (`mint_amount`, post-N7 lock).
+    let tranche = if pool.tranche_enabled() {
+        TRANCHE_SENIOR
+    } else {
+        TRANCHE_POOL
+    };
+    StakeReturnData {
+        amount_in: amount,
+        lp_minted: mint_amount,
+        ..StakeReturnData::with_share_price(
+            pool,
+            if beneficiary.is_some() { 29 } else { 1 },
+            tranche,
+        )
+    }
+    .set();
     Ok(())
 }
 
@@ -1074,6 +1424,45 @@ fn process_withdraw(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     lp_amount: u64,
+    close_if_empty: bool,
+) -> ProgramResult {
+    withdraw_inner(
+        program_id,
+        accounts,
+        lp_amount,
+        WithdrawMode::Owner,
+        close_if_empty,
+    )
+}
+
+/// Which withdraw variant `withdraw_inner` is running.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WithdrawMode {
+    /// `Withdraw` (tag 2): the owner signs; any collateral-mint destination.
+    Owner,
+    /// `WithdrawTo` (tag 30): account 10 is the recipient wallet and the
+    /// collateral destination (account 5) must be owned by it.
+    ToRecipient,
+    /// `OperatorWithdraw` (tag 32): the position's delegated operator signs,
+    /// account 10 is the owner wallet and account 11 the `lp_delegate` PDA that
+    /// burns the owner's LP; the destination must be owned by the owner.
+    Operator,
+    /// `RedeemFinal` (tag 37): finalized pool only; no cooldown, no HWM floor. On
+    /// a tranche-less pool the deposit record is optional.
+    Redeem,
+}
+
+/// Shared body of `Withdraw` (tag 2), `WithdrawTo` (tag 30), `OperatorWithdraw`
+/// (tag 32) and `RedeemFinal` (tag 37). Everything except who signs, who owns the
+/// destination, who burns the LP and which exit gates apply is identical across
+/// `mode`s. With `close_if_empty` (Withdraw only) a withdrawal that empties the
+/// position also closes the deposit PDA, rent going to the signer.
+fn withdraw_inner(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    lp_amount: u64,
+    mode: WithdrawMode,
+    close_if_empty: bool,
 ) -> ProgramResult {
     if lp_amount == 0 {
         return Err(StakeError::ZeroAmount.into());
@@ -1091,10 +1480,29 @@ fn process_withdraw(
     let deposit_pda = next_account_info(accounts_iter)?;
     let token_program = next_account_info(accounts_iter)?;
     let clock_sysvar = next_account_info(accounts_iter)?;
+    let recipient = if mode == WithdrawMode::ToRecipient {
+        Some(next_account_info(accounts_iter)?)
+    } else {
+        None
+    };
+    // `user` is the signer; `owner` is the position owner. They differ only for
+    // OperatorWithdraw, where `user` is the operator.
+    let (owner, lp_delegate) = if mode == WithdrawMode::Operator {
+        (
+            next_account_info(accounts_iter)?,
+            Some(next_account_info(accounts_iter)?),
+        )
+    } else {
+        (user, None)
+    };
 
     if !user.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
+    if close_if_empty {
+        // The closed record's rent is credited to the signer.
+        validate_account_writable(user)?;
+    }
 
     // BUG-1: Validate pool account exists, is owned by the stake program, and is writable
     // (matching the same guards in process_deposit lines 389-391).
@@ -1113,6 +1521,9 @@ fn process_withdraw(
     }
     // BUG-2: Validate pool version, matching process_deposit line 403.
     validate_pool_version(pool)?;
+    if mode == WithdrawMode::Redeem && !pool.is_finalized() {
+        return Err(StakeError::PoolNotFinalized.into());
+    }
     if pool.lp_mint != lp_mint.key.to_bytes() {
         return Err(StakeError::InvalidMint.into());
     }
@@ -1123,7 +1534,8 @@ fn process_withdraw(
     // Validate token program BEFORE any invoke_signed that grants PDA signer authority.
     verify_token_program(token_program)?;
 
-    // Verify user_lp_ata is owned by the signer (not merely delegated) and holds the LP mint.
+    // Verify user_lp_ata is owned by the position owner (not merely delegated) and holds the
+    // LP mint. Outside OperatorWithdraw the owner is the signer.
     // SPL token account layout: bytes [0..32] = mint, bytes [32..64] = owner.
     {
         let lp_ata_data = user_lp_ata.try_borrow_data()?;
@@ -1145,8 +1557,10 @@ fn process_withdraw(
         let owner_bytes: &[u8; 32] = lp_ata_data[32..64]
             .try_into()
             .map_err(|_| StakeError::InvalidAccount)?;
-        if owner_bytes != user.key.as_ref() {
-            msg!("Error: user_lp_ata is not owned by the signer — delegation attack blocked");
+        if owner_bytes != owner.key.as_ref() {
+            msg!(
+                "Error: user_lp_ata is not owned by the position owner — delegation attack blocked"
+            );
             return Err(StakeError::Unauthorized.into());
         }
     }
@@ -1183,6 +1597,18 @@ fn process_withdraw(
             msg!("Error: user_ata mint does not match pool collateral_mint");
             return Err(StakeError::InvalidMint.into());
         }
+        // WithdrawTo: the destination must belong to the named recipient wallet.
+        if let Some(recipient) = recipient {
+            if &ata_data[32..64] != recipient.key.as_ref() {
+                msg!("Error: destination token account is not owned by the recipient");
+                return Err(StakeError::Unauthorized.into());
+            }
+        }
+        // OperatorWithdraw: proceeds can only go to the position owner.
+        if mode == WithdrawMode::Operator && &ata_data[32..64] != owner.key.as_ref() {
+            msg!("Error: operator withdrawal destination is not owned by the position owner");
+            return Err(StakeError::Unauthorized.into());
+        }
     }
 
     // I5: Validate vault_auth PDA derivation
@@ -1193,25 +1619,32 @@ fn process_withdraw(
 
     // Validate deposit PDA derivation and ownership (defense-in-depth)
     let (expected_deposit_pda, _deposit_bump) =
-        state::derive_deposit_pda(program_id, pool_pda.key, user.key);
+        state::derive_deposit_pda(program_id, pool_pda.key, owner.key);
     if *deposit_pda.key != expected_deposit_pda {
         return Err(StakeError::InvalidPda.into());
     }
-    if *deposit_pda.owner != *program_id {
-        return Err(StakeError::InvalidAccount.into());
-    }
-    if deposit_pda.data_len() < STAKE_DEPOSIT_SIZE {
-        return Err(StakeError::InvalidAccount.into());
+    // RedeemFinal on a tranche-less pool prices every LP token identically, so the
+    // record is not needed to attribute it: LP received by transfer (no record, or
+    // a record holding less) is redeemable, and the record is only kept in step.
+    let record_optional = mode == WithdrawMode::Redeem && !pool.tranche_enabled();
+    let has_record = !(record_optional && deposit_pda.data_is_empty());
+    if has_record {
+        if *deposit_pda.owner != *program_id {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        if deposit_pda.data_len() < STAKE_DEPOSIT_SIZE {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        // N-10: deposit_pda is mutated later (lp_amount update); verify writability here
+        // to match pool_pda (checked at line ~817) and give a clear StakeError rather than
+        // an opaque runtime WritableAccountIsReadonly on a client-side bug.
+        validate_account_writable(deposit_pda)?;
     }
-    // N-10: deposit_pda is mutated later (lp_amount update); verify writability here
-    // to match pool_pda (checked at line ~817) and give a clear StakeError rather than
-    // an opaque runtime WritableAccountIsReadonly on a client-side bug.
-    validate_account_writable(deposit_pda)?;
 
     // Check cooldown + read tranche flag in same borrow
     let clock = Clock::from_account_info(clock_sysvar)?;
-    let is_junior;
-    {
+    let mut is_junior = false;
+    if has_record {
         let deposit_data_ref = deposit_pda.try_borrow_data()?;
         let deposit = deposit_from_data(&deposit_data_ref[..])?;
 
@@ -1219,20 +1652,38 @@ fn process_withdraw(
         if !deposit.validate_discriminator() {
             return Err(StakeError::InvalidAccount.into());
         }
-        if deposit.is_initialized != 1
-            || deposit.user != user.key.to_bytes()
+        if (deposit.is_initialized != 1 && !record_optional)
+            || deposit.user != owner.key.to_bytes()
             || deposit.pool != pool_pda.key.to_bytes()
         {
             return Err(StakeError::Unauthorized.into());
         }
-        if clock.slot
-            < deposit
-                .last_deposit_slot
-                .saturating_add(pool.cooldown_slots)
+        if mode == WithdrawMode::Operator
+            && (state::deposit_operator(&deposit_data_ref[..]) != user.key.to_bytes()
+                || *user.key == Pubkey::default())
+        {
+            msg!("Error: signer is not this position's operator");
+            return Err(StakeError::Unauthorized.into());
+        }
+        // Sunset: a finalized pool owes every holder an exit, so no cooldown.
+        // Rage-quit: neither does a pool with an adverse proposal pending —
+        // otherwise a cooldown as long as the timelock turns it into a race.
+        // The waiver lasts only while the proposal can still be acted on; once
+        // it expires the cooldown applies again. HWM and buffer limits below
+        // still apply.
+        // The cooldown is the one frozen into the position at its last top-up,
+        // unless the pool's has since come down.
+        if mode != WithdrawMode::Redeem
+            && !pool.adverse_proposal_pending(clock.slot)
+            && !crate::math::cooldown_elapsed(
+                clock.slot,
+                deposit.last_deposit_slot,
+                deposit.effective_cooldown_slots(pool.cooldown_slots),
+            )
         {
             return Err(StakeError::CooldownNotElapsed.into());
         }
-        if lp_amount > deposit.lp_amount {
+        if lp_amount > deposit.lp_amount && !record_optional {
             return Err(StakeError::InsufficientLpTokens.into());
         }
         // Read tranche flag while we have the borrow
@@ -1281,7 +1732,14 @@ fn process_withdraw(
     // PERC-313: High-water mark floor enforcement.
     // A fully-wiped junior exit has zero collateral payout and cannot reduce TVL,
     // so HWM should not block the LP burn/deposit cleanup path.
-    if pool.hwm_enabled() && !fully_wiped_junior_exit {
+    // RedeemFinal is exempt: once finalized the floor would only trap the last holders.
+    // So is deficit mode: the write-down already took TVL below the mark, and the
+    // floor would turn pro-rata exits into a permanent freeze.
+    if pool.hwm_enabled()
+        && !fully_wiped_junior_exit
+        && mode != WithdrawMode::Redeem
+        && !pool.in_deficit()
+    {
         let current_tvl = pool.total_pool_value().ok_or(StakeError::Overflow)?;
         let hwm = pool.refresh_hwm(clock.epoch, current_tvl);
         let post_tvl = current_tvl
@@ -1299,22 +1757,49 @@ fn process_withdraw(
     }
 
     // Burn LP tokens from user
-    invoke(
-        &crate::spl_token::burn(
-            token_program.key,
-            user_lp_ata.key,
-            lp_mint.key,
-            user.key,
-            &[],
-            lp_amount,
-        )?,
-        &[
-            user_lp_ata.clone(),
-            lp_mint.clone(),
-            user.clone(),
-            token_program.clone(),
-        ],
-    )?;
+    if let Some(lp_delegate) = lp_delegate {
+        // OperatorWithdraw: the operator holds no authority over the owner's LP;
+        // the lp_delegate PDA burns as the SPL delegate SetDepositOperator approved.
+        let (expected_lp_delegate, lp_delegate_bump) =
+            state::derive_lp_delegate(program_id, pool_pda.key);
+        if *lp_delegate.key != expected_lp_delegate {
+            return Err(StakeError::InvalidPda.into());
+        }
+        invoke_signed(
+            &crate::spl_token::burn(
+                token_program.key,
+                user_lp_ata.key,
+                lp_mint.key,
+                lp_delegate.key,
+                &[],
+                lp_amount,
+            )?,
+            &[
+                user_lp_ata.clone(),
+                lp_mint.clone(),
+                lp_delegate.clone(),
+                token_program.clone(),
+            ],
+            &[&[b"lp_delegate", pool_pda.key.as_ref(), &[lp_delegate_bump]]],
+        )?;
+    } else {
+        invoke(
+            &crate::spl_token::burn(
+                token_program.key,
+                user_lp_ata.key,
+                lp_mint.key,
+                user.key,
+                &[],
+                lp_amount,
+            )?,
+            &[
+                user_lp_ata.clone(),
+                lp_mint.clone(),
+                user.clone(),
+                token_program.clone(),
+            ],
+        )?;
+    }
 
     // Transfer collateral: vault → user ATA (single transfer)
     // BUG-10: Use the vault_authority_bump stored in pool state rather than calling
@@ -1340,30 +1825,34 @@ fn process_withdraw(
     }
     let vault_auth_seeds: &[&[u8]] = &[b"vault_auth", pool_pda.key.as_ref(), &[vault_auth_bump]];
 
-    if withdrawal_amount > 0 {
-        invoke_signed(
-            &crate::spl_token::transfer(
-                token_program.key,
-                vault.key,
-                user_ata.key,
-                vault_auth.key,
-                &[],
-                withdrawal_amount,
-            )?,
-            &[
-                vault.clone(),
-                user_ata.clone(),
-                vault_auth.clone(),
-                token_program.clone(),
-            ],
-            &[vault_auth_seeds],
-        )?;
-    }
+    let withdrawn = if withdrawal_amount > 0 {
+        cpi_with_vault_delta(vault, VaultFlow::Out, withdrawal_amount, || {
+            invoke_signed(
+                &crate::spl_token::transfer(
+                    token_program.key,
+                    vault.key,
+                    user_ata.key,
+                    vault_auth.key,
+                    &[],
+                    withdrawal_amount,
+                )?,
+                &[
+                    vault.clone(),
+                    user_ata.clone(),
+                    vault_auth.clone(),
+                    token_program.clone(),
+                ],
+                &[vault_auth_seeds],
+            )
+        })?
+    } else {
+        0
+    };
 
     // Update pool totals
     pool.total_withdrawn = pool
         .total_withdrawn
-        .checked_add(withdrawal_amount)
+        .checked_add(withdrawn)
         .ok_or(StakeError::Overflow)?;
     pool.total_lp_supply = pool
         .total_lp_supply
@@ -1447,40 +1936,74 @@ fn process_withdraw(
     }
 
     // Update deposit PDA
-    let mut deposit_data_mut = deposit_pda.try_borrow_mut_data()?;
-    let deposit_mut = deposit_from_data_mut(&mut deposit_data_mut[..])?;
-    deposit_mut.lp_amount = deposit_mut
-        .lp_amount
-        .checked_sub(lp_amount)
-        .ok_or(StakeError::InsufficientLpTokens)?;
-
-    // #155: once the position is fully withdrawn, reset the record's init + tranche
-    // flag so the (pool,user) PDA can be reused for EITHER tranche on the next deposit.
-    // Without this, _reserved[8] (the junior flag) and is_initialized persist, so PERC-303's
-    // anti-mixing guard permanently blocks the wallet from depositing into the OTHER tranche
-    // (and a junior who fully exits can never go senior, or vice-versa). Safe vs PERC-303:
-    // mixing requires a RESIDUAL liened position; with lp_amount == 0 there is nothing to mix.
-    // Metadata-only — touches no token/LP/accounting field. The deposit re-init path
-    // (process_deposit / process_deposit_junior) correctly re-initializes a zeroed record.
-    if deposit_mut.lp_amount == 0 {
-        deposit_mut.is_initialized = 0;
-        deposit_mut._reserved[8] = 0;
+    let mut close_now = false;
+    if has_record {
+        let mut deposit_data_mut = deposit_pda.try_borrow_mut_data()?;
+        let deposit_mut = deposit_from_data_mut(&mut deposit_data_mut[..])?;
+        deposit_mut.lp_amount = if record_optional {
+            deposit_mut.lp_amount.saturating_sub(lp_amount)
+        } else {
+            deposit_mut
+                .lp_amount
+                .checked_sub(lp_amount)
+                .ok_or(StakeError::InsufficientLpTokens)?
+        };
+        deposit_mut.stamp_legacy_cooldown(pool.cooldown_slots);
+
+        // #155: once the position is fully withdrawn, reset the record's init + tranche
+        // flag so the (pool,user) PDA can be reused for EITHER tranche on the next deposit.
+        // Without this, _reserved[8] (the junior flag) and is_initialized persist, so PERC-303's
+        // anti-mixing guard permanently blocks the wallet from depositing into the OTHER tranche
+        // (and a junior who fully exits can never go senior, or vice-versa). Safe vs PERC-303:
+        // mixing requires a RESIDUAL liened position; with lp_amount == 0 there is nothing to mix.
+        // Metadata-only — touches no token/LP/accounting field. The deposit re-init path
+        // (process_deposit / process_deposit_junior) correctly re-initializes a zeroed record.
+        if deposit_mut.lp_amount == 0 {
+            deposit_mut.is_initialized = 0;
+            deposit_mut._reserved[8] = 0;
+        }
+        close_now = close_if_empty && deposit_mut.lp_amount == 0;
+    }
+    if close_now {
+        close_pda(deposit_pda, user)?;
+        msg!("Position fully exited — deposit PDA closed");
     }
 
-    if pool.tranche_enabled() && is_junior {
+    let tranche = if pool.tranche_enabled() && is_junior {
         msg!(
             "Junior withdrew {} collateral, burned {} LP tokens",
             withdrawal_amount,
             lp_amount
         );
+        TRANCHE_JUNIOR
     } else {
         msg!(
             "Withdrew {} collateral, burned {} LP tokens",
             withdrawal_amount,
             lp_amount
         );
-    }
+        if pool.tranche_enabled() {
+            TRANCHE_SENIOR
+        } else {
+            TRANCHE_POOL
+        }
+    };
 
+    StakeReturnData {
+        amount_out: withdrawal_amount,
+        lp_burned: lp_amount,
+        ..StakeReturnData::with_share_price(
+            pool,
+            match mode {
+                WithdrawMode::Owner => 2,
+                WithdrawMode::ToRecipient => 30,
+                WithdrawMode::Operator => 32,
+                WithdrawMode::Redeem => 37,
+            },
+            tranche,
+        )
+    }
+    .set();
     Ok(())
 }
 
@@ -1508,10 +2031,6 @@ fn process_flush_to_insurance(
     let percolator_program = next_account_info(accounts_iter)?;
     let token_program = next_account_info(accounts_iter)?;
 
-    if !caller.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
     // FINDING-2: Validate pool account ownership and non-emptiness before reading it.
     // Without these guards an attacker can pass a crafted account; bytemuck would
     // reinterpret foreign data as StakePool state and all subsequent field checks
@@ -1543,8 +2062,15 @@ fn process_flush_to_insurance(
     // Without this, ANY signer can drain the stake vault to wrapper insurance,
     // locking all LP holder withdrawals until market resolution.
     // This is a DoS vector that freezes depositor funds indefinitely.
-    if pool.admin != caller.key.to_bytes() {
-        return Err(StakeError::Unauthorized.into());
+    //
+    // The one exception is a LOCKED, non-zero flush policy: anyone may then
+    // flush, but only while the wrapper-held share stays within the policy
+    // (checked below), so the vault can never be drained past it.
+    let operator = authorize_role(program_id, pool, ROLE_FLUSH_OPERATOR, caller, accounts);
+    if operator.is_err()
+        && (!pool.is_config_locked(CONFIG_LOCK_FLUSH_POLICY) || pool.flush_policy_max_bps == 0)
+    {
+        return operator;
     }
 
     if pool.slab != slab.key.to_bytes() {
@@ -1578,6 +2104,11 @@ fn process_flush_to_insurance(
         msg!("FlushToInsurance: market is resolved — use ReturnInsurance path instead");
         return Err(StakeError::MarketResolved.into());
     }
+    // A pool in deficit mode is winding down at what its vault really holds;
+    // sending more of it to the wrapper would only shrink the pro-rata exits.
+    if pool.in_deficit() {
+        return Err(StakeError::PoolInDeficit.into());
+    }
 
     // Validate wrapper_vault holds the correct collateral mint (defense-in-depth).
     // The percolator CPI also validates this, but an explicit check here gives a clear
@@ -1604,26 +2135,26 @@ fn process_flush_to_insurance(
         }
     }
 
-    // Verify vault balance — can't flush more than the pool's value physically in the vault.
-    //
-    // #198: the previous inline chain `((D - W) - F) + R` (u64, left-to-right) UNDERFLOWED
-    // at the intermediate `(D - W) - F` whenever `D - W < F` (equivalently total_pool_value()
-    // < total_returned) and failed the flush with Overflow even though `D - W - F + R` was
-    // positive and the tokens were in the vault — the same false-underflow brick that
-    // total_pool_value() was widened to i128 to avoid (#169); this duplicate was missed.
-    //
-    // It ALSO over-counted by omitting `- realized_junior_loss`: the #161 last-junior-exit
-    // booking raises total_returned by the forfeited loss WITHOUT a token movement (a phantom
-    // return recorded in realized_junior_loss), so `D - W - F + R` exceeds the real vault
-    // balance by realized_junior_loss. The vault physically holds exactly total_pool_value()
-    // (= D - W - F + R - realized_junior_loss for a mode-0 pool; fees are 0 here because flush
-    // is mode-0 only, gated above). Reuse the canonical i128-widened fn: it is the exact
-    // flushable balance, can never report more than the vault holds, and returns None only on
-    // genuine insolvency — which correctly fails the flush closed.
-    let available = pool.total_pool_value().ok_or(StakeError::Overflow)?;
+    // Verify vault balance — can't flush more than the pool's value physically in the vault
+    // (#198: see `accounting::flush_capacity`).
+    let available = accounting::flush_capacity(pool)?;
     if amount > available {
         return Err(StakeError::InsufficientVaultBalance.into());
     }
+    if operator.is_err()
+        && !state::flush_within_policy(
+            pool.wrapper_recoverable(),
+            available,
+            amount,
+            pool.flush_policy_max_bps,
+        )
+    {
+        msg!(
+            "Error: flush exceeds the {} bps flush policy",
+            pool.flush_policy_max_bps
+        );
+        return Err(StakeError::FlushPolicyExceeded.into());
+    }
 
     // Derive vault authority for signing
     let (expected_vault_auth, vault_auth_bump) =
@@ -1637,45 +2168,35 @@ fn process_flush_to_insurance(
     // CPI TopUpInsurance: vault_auth PDA signs, stake vault is the "signer_ata"
     // TopUpInsurance checks: verify_token_account(a_user_ata, a_user.key, &mint)
     // Our vault's owner (in SPL token terms) = vault_auth PDA = signer. ✓
-    cpi::cpi_top_up_insurance(
-        percolator_program,
-        vault_auth, // signer (PDA, we invoke_signed)
-        slab,
-        vault,         // signer_ata (owned by vault_auth PDA)
-        wrapper_vault, // percolator vault
-        token_program,
-        amount,
-        vault_auth_seeds,
-    )?;
-
-    // Update pool tracking
-    pool.total_flushed = pool
-        .total_flushed
-        .checked_add(amount)
-        .ok_or(StakeError::Overflow)?;
+    // The vault must show exactly `amount` leaving it before anything is booked.
+    let abi = cpi::pool_wrapper_abi(pool)?;
+    let flushed = cpi_with_vault_delta(vault, VaultFlow::Out, amount, || {
+        cpi::cpi_top_up_insurance(
+            abi,
+            percolator_program,
+            vault_auth, // signer (PDA, we invoke_signed)
+            slab,
+            vault,         // signer_ata (owned by vault_auth PDA)
+            wrapper_vault, // percolator vault
+            token_program,
+            amount,
+            vault_auth_seeds,
+        )
+    })?;
 
-    // PERC-313 HWM: a flush is a realized insurance LOSS — pool TVL drops by `amount`.
-    // The high-water-mark withdrawal floor must track that loss, or LPs get frozen out
-    // of a pool that just lost money. `refresh_hwm` only RAISES the mark within an epoch
-    // and is never called here, so the mark must be lowered explicitly. Lower it by
-    // exactly the flushed amount (the realized loss) so the floor recomputes against the
-    // loss-adjusted peak (peak − Σ losses). This preserves anti-drain protection:
-    // WITHDRAWALS never lower the mark (only refresh_hwm's raise and this flush do), so a
-    // withdrawal-driven drain is still floored; only a real loss lowers the floor, and only
-    // by the loss amount — no free withdrawal headroom is created (TVL dropped by the same
-    // `amount`). saturating_sub is panic-free; if a stale/zero mark is below `amount` it
-    // floors at 0 (floor 0 = no restriction), and the next withdraw's refresh_hwm re-bases
-    // the mark to live TVL. Left ungated on hwm_enabled(): the mark is only ever READ in the
-    // hwm_enabled branch, and a lowered mark is strictly more permissive, so tracking the
-    // loss unconditionally keeps "mark = peak − losses" true and avoids a stale-high mark
-    // re-freezing if HWM is toggled on mid-epoch after a flush. ReturnInsurance is left
-    // untouched: recovery rides the existing same-epoch refresh_hwm raise (clamped to TVL).
-    pool.set_epoch_high_water_tvl(pool.epoch_high_water_tvl().saturating_sub(amount));
+    // Update pool tracking; the flush also lowers the HWM mark by the realized loss.
+    accounting::book_flush(pool, flushed)?;
 
     msg!(
         "Flushed {} collateral to percolator insurance via CPI",
-        amount
+        flushed
     );
+
+    StakeReturnData {
+        amount_out: flushed,
+        ..StakeReturnData::with_share_price(pool, 3, TRANCHE_POOL)
+    }
+    .set();
     Ok(())
 }
 
@@ -1694,10 +2215,6 @@ fn process_update_config(
     let admin = next_account_info(accounts_iter)?;
     let pool_pda = next_account_info(accounts_iter)?;
 
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
     // BUG-5: Validate pool account is owned by this program before reading it.
     // Without this, an attacker could pass a crafted account and manipulate config
     // without an authentic pool PDA.
@@ -1717,9 +2234,7 @@ fn process_update_config(
     }
     // FINDING-5: Validate pool version on UpdateConfig.
     validate_pool_version(pool)?;
-    if pool.admin != admin.key.to_bytes() {
-        return Err(StakeError::Unauthorized.into());
-    }
+    authorize_admin(program_id, pool, admin, accounts)?;
 
     if let Some(cooldown) = new_cooldown_slots {
         validate_cooldown_slots(cooldown)?;
@@ -1733,6 +2248,9 @@ fn process_update_config(
         pool.cooldown_slots = cooldown;
     }
     if let Some(cap) = new_deposit_cap {
+        if pool.is_config_locked(CONFIG_LOCK_DEPOSIT_CAP) {
+            return Err(StakeError::ConfigLocked.into());
+        }
         // deposit_cap can be 0 (unlimited) or any positive value
         // no validation needed, u64 can't be negative
         pool.deposit_cap = cap;
@@ -1760,16 +2278,15 @@ fn process_propose_admin(
     let admin = next_account_info(accounts_iter)?;
     let pool_pda = next_account_info(accounts_iter)?;
 
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
     // Validate pool account before bytemuck reinterpretation (matches every
     // other admin path).
     validate_account_owner(pool_pda, program_id)?;
     validate_account_not_empty(pool_pda)?;
     validate_account_writable(pool_pda)?; // N-4: propose_admin mutates pending_admin
 
+    // The sysvar, not an account: tags 5/6 keep their original [admin, pool] shape.
+    let clock = Clock::get()?;
+
     let mut pool_data = pool_pda.try_borrow_mut_data()?;
     let pool = pool_from_data_mut(&mut pool_data[..])?;
 
@@ -1780,16 +2297,36 @@ fn process_propose_admin(
         return Err(StakeError::InvalidAccount.into());
     }
     validate_pool_version(pool)?;
-    if pool.admin != admin.key.to_bytes() {
-        return Err(StakeError::Unauthorized.into());
-    }
+    authorize_admin(program_id, pool, admin, accounts)?;
 
+    // A different proposed admin is a new proposal: earlier veto votes no
+    // longer apply to it (and become reclaimable).
+    if new_admin != [0u8; 32] && new_admin != pool.pending_admin {
+        pool.admin_proposal_nonce = pool.next_proposal_nonce();
+        pool.admin_veto_lp = 0;
+    }
+    let cancelled = new_admin == [0u8; 32] && pool.pending_admin != [0u8; 32];
+    let cancelled_nonce = pool.admin_proposal_nonce;
     pool.pending_admin = new_admin;
+    // Any (re-)proposal restarts the expiry window.
+    pool.admin_proposed_at_slot = if new_admin == [0u8; 32] {
+        0
+    } else {
+        clock.slot
+    };
 
-    if new_admin == [0u8; 32] {
-        msg!("ProposeAdmin: pending admin proposal cancelled");
+    if cancelled {
+        msg!(
+            "ProposeAdmin: pending admin proposal (nonce {}) cancelled by the proposer",
+            cancelled_nonce
+        );
+    } else if new_admin == [0u8; 32] {
+        msg!("ProposeAdmin: no pending admin proposal to cancel");
     } else {
-        msg!("ProposeAdmin: new admin proposed (awaiting AcceptAdmin)");
+        msg!(
+            "ProposeAdmin: new admin proposed (awaiting AcceptAdmin before slot {})",
+            clock.slot.saturating_add(PROPOSAL_EXPIRY_SLOTS)
+        );
     }
     Ok(())
 }
@@ -1815,6 +2352,8 @@ fn process_accept_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> Progra
     validate_account_not_empty(pool_pda)?;
     validate_account_writable(pool_pda)?; // N-4: accept_admin mutates pool.admin
 
+    let clock = Clock::get()?;
+
     let mut pool_data = pool_pda.try_borrow_mut_data()?;
     let pool = pool_from_data_mut(&mut pool_data[..])?;
 
@@ -1834,9 +2373,21 @@ fn process_accept_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> Progra
     if pool.pending_admin != new_admin.key.to_bytes() {
         return Err(StakeError::Unauthorized.into());
     }
+    if proposal_expired(
+        pool.admin_proposed_at_slot,
+        PROPOSAL_EXPIRY_SLOTS,
+        clock.slot,
+    ) {
+        msg!(
+            "Error: admin proposal from slot {} has expired",
+            pool.admin_proposed_at_slot
+        );
+        return Err(StakeError::ProposalExpired.into());
+    }
 
     pool.admin = pool.pending_admin;
     pool.pending_admin = [0u8; 32];
+    pool.admin_proposed_at_slot = 0;
 
     msg!("AcceptAdmin: admin rotation complete");
     Ok(())
@@ -1867,10 +2418,6 @@ fn process_propose_cooldown_increase(
     let pool_pda = next_account_info(accounts_iter)?;
     let clock_sysvar = next_account_info(accounts_iter)?;
 
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
     validate_account_owner(pool_pda, program_id)?;
     validate_account_not_empty(pool_pda)?;
     validate_account_writable(pool_pda)?;
@@ -1887,9 +2434,7 @@ fn process_propose_cooldown_increase(
         return Err(StakeError::InvalidAccount.into());
     }
     validate_pool_version(pool)?;
-    if pool.admin != admin.key.to_bytes() {
-        return Err(StakeError::Unauthorized.into());
-    }
+    authorize_admin(program_id, pool, admin, accounts)?;
 
     // Same absolute cap as the immediate path.
     validate_cooldown_slots(new_cooldown_slots)?;
@@ -1901,8 +2446,15 @@ fn process_propose_cooldown_increase(
     pool.set_pending_cooldown_slots(new_cooldown_slots);
     // clock.slot is never 0 on a live chain, so it is a safe "active proposal" sentinel.
     pool.set_cooldown_proposed_at_slot(clock.slot);
+    // A (re-)proposal is a new veto target; votes against the old one are released.
+    pool.cooldown_proposal_nonce = pool.next_proposal_nonce();
+    pool.cooldown_veto_lp = 0;
 
-    msg!("ProposeCooldownIncrease: pending; commit after TIMELOCK_SLOTS");
+    msg!(
+        "ProposeCooldownIncrease: pending; commit between slots {} and {}",
+        clock.slot.saturating_add(TIMELOCK_SLOTS),
+        clock.slot.saturating_add(PROPOSAL_EXPIRY_SLOTS)
+    );
     Ok(())
 }
 
@@ -1926,10 +2478,6 @@ fn process_commit_cooldown_increase(
     let pool_pda = next_account_info(accounts_iter)?;
     let clock_sysvar = next_account_info(accounts_iter)?;
 
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
     validate_account_owner(pool_pda, program_id)?;
     validate_account_not_empty(pool_pda)?;
     validate_account_writable(pool_pda)?;
@@ -1946,9 +2494,7 @@ fn process_commit_cooldown_increase(
         return Err(StakeError::InvalidAccount.into());
     }
     validate_pool_version(pool)?;
-    if pool.admin != admin.key.to_bytes() {
-        return Err(StakeError::Unauthorized.into());
-    }
+    authorize_admin(program_id, pool, admin, accounts)?;
 
     let proposed_at = pool.cooldown_proposed_at_slot();
     if proposed_at == 0 {
@@ -1957,6 +2503,13 @@ fn process_commit_cooldown_increase(
     if !timelock_window_elapsed(proposed_at, TIMELOCK_SLOTS, clock.slot)? {
         return Err(StakeError::TimelockNotElapsed.into());
     }
+    if proposal_expired(proposed_at, PROPOSAL_EXPIRY_SLOTS, clock.slot) {
+        msg!(
+            "Error: cooldown proposal from slot {} has expired",
+            proposed_at
+        );
+        return Err(StakeError::ProposalExpired.into());
+    }
 
     let pending = pool.pending_cooldown_slots();
     // Defensive re-validation: the cap may matter even though propose enforced it.
@@ -1988,10 +2541,6 @@ fn process_cancel_cooldown_increase(
     let admin = next_account_info(accounts_iter)?;
     let pool_pda = next_account_info(accounts_iter)?;
 
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
     validate_account_owner(pool_pda, program_id)?;
     validate_account_not_empty(pool_pda)?;
     validate_account_writable(pool_pda)?;
@@ -2006,17 +2555,20 @@ fn process_cancel_cooldown_increase(
         return Err(StakeError::InvalidAccount.into());
     }
     validate_pool_version(pool)?;
-    if pool.admin != admin.key.to_bytes() {
-        return Err(StakeError::Unauthorized.into());
-    }
+    authorize_admin(program_id, pool, admin, accounts)?;
 
     if pool.cooldown_proposed_at_slot() == 0 {
         return Err(StakeError::NoPendingCooldownProposal.into());
     }
+    let cancelled_slots = pool.pending_cooldown_slots();
     pool.set_pending_cooldown_slots(0);
     pool.set_cooldown_proposed_at_slot(0);
 
-    msg!("CancelCooldownIncrease: pending proposal cleared");
+    msg!(
+        "CancelCooldownIncrease: proposal (nonce {}, {} slots) cancelled by the proposer",
+        pool.cooldown_proposal_nonce,
+        cancelled_slots
+    );
     Ok(())
 }
 
@@ -2033,6 +2585,10 @@ fn process_cancel_cooldown_increase(
 ///   (b) admin_shutdown_authorized path → BLOCKED by D-STAKE-1 guard when
 ///       insurance_authority != zero AND by the asset_index==0 guard.
 ///
+/// Both CPIs target the pool's `wrapper_asset_index`. For a non-zero asset the
+/// asset_index==0 guard no longer applies, so path (b) is closed by D-STAKE-1
+/// alone — which this bind satisfies.
+///
 /// After this call:
 ///   - insurance_authority == vault_auth PDA
 ///   - insurance_operator  == vault_auth PDA
@@ -2056,17 +2612,13 @@ fn process_bind_insurance_authority(
     let slab = next_account_info(accounts_iter)?;
     let percolator_program = next_account_info(accounts_iter)?;
 
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
     // Validate pool account before bytemuck reinterpretation.
     validate_account_owner(pool_pda, program_id)?;
     validate_account_not_empty(pool_pda)?;
 
     // Read pool (immutable — we don't mutate stake state here) and copy out the
     // fields we need so the borrow is released before the CPIs.
-    let (pool_slab, pool_percolator) = {
+    let (pool_slab, pool_percolator, asset_index, abi) = {
         let pool_data = pool_pda.try_borrow_data()?;
         let pool = pool_from_data(&pool_data[..])?;
         if pool.is_initialized != 1 {
@@ -2080,10 +2632,13 @@ fn process_bind_insurance_authority(
         // be the current insurance_authority and insurance_operator (bootstrapped
         // to marketauth=admin at InitMarket). Any divergence causes the wrapper
         // CPI to reject with Unauthorized.
-        if pool.admin != admin.key.to_bytes() {
-            return Err(StakeError::Unauthorized.into());
-        }
-        (pool.slab, pool.percolator_program)
+        authorize_admin(program_id, pool, admin, accounts)?;
+        (
+            pool.slab,
+            pool.percolator_program,
+            pool.wrapper_asset_index,
+            cpi::pool_wrapper_abi(pool)?,
+        )
     };
 
     // Bind to the pool's recorded market + wrapper program (prevents pointing the
@@ -2107,10 +2662,12 @@ fn process_bind_insurance_authority(
     // CPI 1: bind insurance_authority (kind=1) → vault_auth PDA.
     // Admin is the current insurance_authority; PDA co-signs via invoke_signed.
     cpi::cpi_bind_insurance_authority(
+        abi,
         percolator_program,
         admin,      // current insurance_authority (== admin at bootstrap)
         vault_auth, // new authority (PDA), signed via invoke_signed
         slab,       // market
+        asset_index,
         vault_auth_seeds,
     )?;
 
@@ -2120,10 +2677,12 @@ fn process_bind_insurance_authority(
     // pass local_authorized in WithdrawInsuranceAsset (tag 57) because
     // insurance_operator != admin.
     cpi::cpi_bind_insurance_operator(
+        abi,
         percolator_program,
         admin,      // current insurance_operator (== admin at bootstrap)
         vault_auth, // new operator (PDA), signed via invoke_signed
         slab,       // market
+        asset_index,
         vault_auth_seeds,
     )?;
 
@@ -2171,15 +2730,11 @@ fn process_burn_asset_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> Pr
     let slab = next_account_info(accounts_iter)?;
     let percolator_program = next_account_info(accounts_iter)?;
 
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
     validate_account_writable(pool_pda)?;
     validate_account_owner(pool_pda, program_id)?;
     validate_account_not_empty(pool_pda)?;
 
-    let (pool_slab, pool_percolator) = {
+    let (pool_slab, pool_percolator, asset_index, abi) = {
         let pool_data = pool_pda.try_borrow_data()?;
         let pool = pool_from_data(&pool_data[..])?;
         if pool.is_initialized != 1 {
@@ -2189,14 +2744,17 @@ fn process_burn_asset_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> Pr
             return Err(StakeError::InvalidAccount.into());
         }
         validate_pool_version(pool)?;
-        if pool.admin != admin.key.to_bytes() {
-            return Err(StakeError::Unauthorized.into());
-        }
+        authorize_admin(program_id, pool, admin, accounts)?;
         if pool.asset_admin_burned() {
             msg!("BurnAssetAdmin: asset_admin already burned for this pool");
             return Err(StakeError::Unauthorized.into());
         }
-        (pool.slab, pool.percolator_program)
+        (
+            pool.slab,
+            pool.percolator_program,
+            pool.wrapper_asset_index,
+            cpi::pool_wrapper_abi(pool)?,
+        )
     };
 
     if pool_slab != slab.key.to_bytes() {
@@ -2216,10 +2774,12 @@ fn process_burn_asset_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> Pr
     }
 
     cpi::cpi_burn_asset_admin(
+        abi,
         percolator_program,
         admin,      // current asset_admin; signer
         vault_auth, // placeholder slot (not checked for zero-burn)
         slab,       // market
+        asset_index,
     )?;
 
     {
@@ -2267,10 +2827,6 @@ fn process_rotate_insurance_operator(
     let new_target = next_account_info(accounts_iter)?;
     let slab = next_account_info(accounts_iter)?;
     let percolator_program = next_account_info(accounts_iter)?;
-
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
     // The wrapper requires the NEW operator to co-sign for non-zero keys.
     if !new_target.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
@@ -2279,7 +2835,7 @@ fn process_rotate_insurance_operator(
     validate_account_owner(pool_pda, program_id)?;
     validate_account_not_empty(pool_pda)?;
 
-    let (pool_slab, pool_percolator) = {
+    let (pool_slab, pool_percolator, asset_index, abi) = {
         let pool_data = pool_pda.try_borrow_data()?;
         let pool = pool_from_data(&pool_data[..])?;
         if pool.is_initialized != 1 {
@@ -2289,14 +2845,17 @@ fn process_rotate_insurance_operator(
             return Err(StakeError::InvalidAccount.into());
         }
         validate_pool_version(pool)?;
-        if pool.admin != admin.key.to_bytes() {
-            return Err(StakeError::Unauthorized.into());
-        }
+        authorize_admin(program_id, pool, admin, accounts)?;
         if pool.asset_admin_burned() {
             msg!("RotateInsuranceOperator: asset_admin burn is final; rotate-back is disabled");
             return Err(StakeError::Unauthorized.into());
         }
-        (pool.slab, pool.percolator_program)
+        (
+            pool.slab,
+            pool.percolator_program,
+            pool.wrapper_asset_index,
+            cpi::pool_wrapper_abi(pool)?,
+        )
     };
 
     if pool_slab != slab.key.to_bytes() {
@@ -2314,10 +2873,12 @@ fn process_rotate_insurance_operator(
 
     let vault_auth_seeds: &[&[u8]] = &[b"vault_auth", pool_pda.key.as_ref(), &[vault_auth_bump]];
     cpi::cpi_rotate_insurance_operator(
+        abi,
         percolator_program,
         vault_auth, // current operator (the PDA), signed via invoke_signed
         new_target, // new operator (admin-specified), co-signs the outer tx
         slab,       // market
+        asset_index,
         vault_auth_seeds,
     )?;
 
@@ -2348,10 +2909,6 @@ fn process_rotate_insurance_authority(
     let new_target = next_account_info(accounts_iter)?;
     let slab = next_account_info(accounts_iter)?;
     let percolator_program = next_account_info(accounts_iter)?;
-
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
     // The wrapper requires the NEW authority to co-sign; surface a clear error
     // here rather than an opaque CPI failure if it didn't sign.
     if !new_target.is_signer {
@@ -2361,7 +2918,7 @@ fn process_rotate_insurance_authority(
     validate_account_owner(pool_pda, program_id)?;
     validate_account_not_empty(pool_pda)?;
 
-    let (pool_slab, pool_percolator) = {
+    let (pool_slab, pool_percolator, asset_index, abi) = {
         let pool_data = pool_pda.try_borrow_data()?;
         let pool = pool_from_data(&pool_data[..])?;
         if pool.is_initialized != 1 {
@@ -2372,14 +2929,17 @@ fn process_rotate_insurance_authority(
         }
         validate_pool_version(pool)?;
         // Admin-gated: only the pool admin may rotate the insurance authority.
-        if pool.admin != admin.key.to_bytes() {
-            return Err(StakeError::Unauthorized.into());
-        }
+        authorize_admin(program_id, pool, admin, accounts)?;
         if pool.asset_admin_burned() {
             msg!("RotateInsuranceAuthority: asset_admin burn is final; rotate-back is disabled");
             return Err(StakeError::Unauthorized.into());
         }
-        (pool.slab, pool.percolator_program)
+        (
+            pool.slab,
+            pool.percolator_program,
+            pool.wrapper_asset_index,
+            cpi::pool_wrapper_abi(pool)?,
+        )
     };
 
     if pool_slab != slab.key.to_bytes() {
@@ -2398,10 +2958,12 @@ fn process_rotate_insurance_authority(
 
     let vault_auth_seeds: &[&[u8]] = &[b"vault_auth", pool_pda.key.as_ref(), &[vault_auth_bump]];
     cpi::cpi_rotate_insurance_authority(
+        abi,
         percolator_program,
         vault_auth, // current authority (the PDA), signed via invoke_signed
         new_target, // new authority (admin-specified), co-signs the outer tx
         slab,       // market
+        asset_index,
         vault_auth_seeds,
     )?;
 
@@ -2409,51 +2971,6 @@ fn process_rotate_insurance_authority(
     Ok(())
 }
 
-// ============================================================================
-// N7: MINIMUM_LIQUIDITY dead-share lock (CONSOLIDATED-PLAN §2.2)
-// ============================================================================
-
-/// Applies the `state::MINIMUM_LIQUIDITY` dead-share lock at the pool's TRUE
-/// genesis deposit. Shared by `process_deposit` (senior/global bootstrap) and
-/// `process_deposit_junior` (junior bootstrap) — both mutate the SAME
-/// `pool.total_lp_supply` counter, so whichever instruction is called FIRST on a
-/// freshly-initialized pool is the genesis deposit, regardless of which entry
-/// point it comes through.
-///
-/// `total_lp_supply_before` MUST be read BEFORE the caller applies this
-/// deposit's own `total_lp_supply` increment. `lp_to_mint` is the FULL LP
-/// amount computed by the pro-rata/bootstrap math (at genesis this comes from
-/// the `total_lp_supply == 0 && total_pool_value == 0` branch of
-/// `calc_lp_for_deposit`, so `lp_to_mint == deposit_amount` exactly). The
-/// caller must still add the FULL `lp_to_mint` to `pool.total_lp_supply`
-/// unchanged — only the amount actually SPL-minted to the depositor's ATA is
-/// reduced here. The `MINIMUM_LIQUIDITY` difference is never minted to any
-/// account, so it becomes permanently unredeemable "dead" supply (the
-/// Uniswap-V2-style anti-inflation floor — see the constant's doc comment).
-///
-/// Returns `DepositBelowMinimumLiquidity` if the genesis deposit is too small
-/// to carve out the dead-share floor (rather than silently underflowing or
-/// minting 0 real LP while still transferring in collateral).
-fn apply_minimum_liquidity_lock(
-    total_lp_supply_before: u64,
-    lp_to_mint: u64,
-) -> Result<u64, ProgramError> {
-    if total_lp_supply_before != 0 {
-        // Not the pool's genesis deposit — mint the full computed amount, as before.
-        return Ok(lp_to_mint);
-    }
-    let mint_amount = lp_to_mint
-        .checked_sub(state::MINIMUM_LIQUIDITY)
-        .ok_or(StakeError::DepositBelowMinimumLiquidity)?;
-    // Reject an exact-equal genesis deposit too: it would carve the dead-share
-    // floor out fully and mint 0 real LP to the depositor while still
-    // transferring their collateral in — same S-4 class of bug as ZeroSharesMinted.
-    if mint_amount == 0 {
-        return Err(StakeError::DepositBelowMinimumLiquidity.into());
-    }
-    Ok(mint_amount)
-}
-
 // ============================================================================
 // PERC-272: LP Vault — Fee Accrual & Trading Pool Init
 // ============================================================================
@@ -2462,6 +2979,7 @@ fn apply_minimum_liquidity_lock(
 /// (`process_deposit`, `process_withdraw`, `process_deposit_junior`). Crystallizes any
 /// pending fee-accruing-mode surplus into share price BEFORE pricing, so LP cannot be
 /// minted/redeemed at the stale pre-accrual price and capture fees earned before joining.
+/// A vault SHORT of the books is written down the same way (`accounting::write_down_deficit`).
 ///
 /// MUST be called AFTER the caller has verified `pool.vault == vault.key` and BEFORE the
 /// caller's user<->vault transfer, so the balance read reflects only the fee surplus and
@@ -2501,6 +3019,19 @@ fn pre_accrue_fee_modes(pool: &mut state::StakePool, vault: &AccountInfo) -> Pro
             }
             acct.amount
         };
+        // A vault holding LESS than the books puts the pool into deficit mode
+        // before pricing, so this exit is paid pro rata from what is actually
+        // there rather than at the overstated value. It runs first because it
+        // also rebuilds a ledger `total_pool_value()` cannot represent, which
+        // the accrual below would refuse.
+        let shortfall = accounting::write_down_deficit(pool, current_balance)?;
+        if shortfall > 0 {
+            msg!(
+                "Deficit: vault {} is {} short of the books; written down, junior first",
+                current_balance,
+                shortfall
+            );
+        }
         accrue_fees_inner(pool, current_balance)?;
     }
     Ok(())
@@ -2508,67 +3039,26 @@ fn pre_accrue_fee_modes(pool: &mut state::StakePool, vault: &AccountInfo) -> Pro
 
 /// Crystallize any un-accrued vault surplus into pool share price. Shared by the
 /// permissionless `AccrueFees` instruction AND the deposit/withdraw pre-accrue guard
-/// (#136) so every pricing path applies byte-identical accounting.
-///
-/// `current_balance` MUST be the verified vault token-account balance read BEFORE any
-/// deposit transfer in the calling instruction — otherwise the deposit's own collateral
-/// would be mis-credited as fees. The caller must have already confirmed the vault key +
-/// SPL-Token ownership. Mutates only `pool`. No-op when there is no surplus or no LP
-/// holders, preserving the first-depositor bootstrap / anti-brick guard.
+/// (#136) so every pricing path applies byte-identical accounting. The booking
+/// itself is `accounting::accrue_fees`; see it for the `current_balance` contract.
+/// In deficit mode the surplus first restores the write-off
+/// (`accounting::restore_deficit`); only what is left over is fees.
 fn accrue_fees_inner(pool: &mut state::StakePool, current_balance: u64) -> ProgramResult {
-    // total_pool_value() = deposited - withdrawn - flushed + returned + fees_earned (mode 1)
-    // — the authoritative expected balance; any excess is un-accrued fee revenue.
-    let pool_value = pool.total_pool_value().ok_or(StakeError::Overflow)?;
-
-    // Only accrue when there are active LP holders. Accruing at total_lp_supply == 0
-    // would set total_fees_earned > 0 at zero supply, tripping calc_lp_for_deposit's
-    // orphaned-value guard and permanently bricking the first deposit (an attacker can
-    // donate 1 token to the vault pre-first-deposit to trigger it).
-    if current_balance > pool_value && pool.total_lp_supply > 0 {
-        let fee_delta = current_balance - pool_value;
-
-        // Snapshot pre-fee tranche balances BEFORE incrementing total_fees_earned.
-        // senior_balance() derives from total_pool_value() which includes
-        // total_fees_earned, so reading it post-increment would inflate the senior
-        // weight in distribute_fees and systematically shortchange the junior tranche.
-        let distribute_to_junior = pool.tranche_enabled() && pool.junior_total_lp() > 0;
-        let (snapshot_junior_bal, snapshot_senior_bal) = if distribute_to_junior {
-            (
-                pool.junior_balance(),
-                pool.senior_balance().ok_or(StakeError::Overflow)?,
-            )
-        } else {
-            (0, 0)
-        };
-
-        pool.total_fees_earned = pool
-            .total_fees_earned
-            .checked_add(fee_delta)
-            .ok_or(StakeError::Overflow)?;
-
-        // PERC-303: distribute the fee delta between junior/senior sub-pools using the
-        // junior fee multiplier. Senior implicitly receives the remainder since
-        // senior_balance = total_pool_value() - junior_balance and total_fees_earned
-        // was already incremented by the full fee_delta above.
-        if distribute_to_junior {
-            let (junior_fee, _) = crate::math::distribute_fees(
-                snapshot_junior_bal,
-                snapshot_senior_bal,
-                pool.junior_fee_mult_bps(),
-                fee_delta,
-            );
-            pool.set_junior_balance(
-                pool.junior_balance()
-                    .checked_add(junior_fee)
-                    .ok_or(StakeError::Overflow)?,
-            );
-            // M-2: guard — reject accrual if it would push junior_balance above pool value.
-            let pv = pool.total_pool_value().ok_or(StakeError::Overflow)?;
-            if pool.junior_balance() > pv {
-                return Err(StakeError::Overflow.into());
+    let restored = accounting::restore_deficit(pool, current_balance)?;
+    if restored > 0 {
+        msg!(
+            "Deficit: {} restored, {} still written off{}",
+            restored,
+            pool.deficit_written_off,
+            if pool.in_deficit() {
+                ""
+            } else {
+                "; deficit mode cleared"
             }
-        }
-
+        );
+    }
+    let fee_delta = accounting::accrue_fees(pool, current_balance)?;
+    if fee_delta > 0 {
         msg!(
             "AccrueFees: accrued {} fees, total_fees_earned={}",
             fee_delta,
@@ -2683,11 +3173,21 @@ fn process_accrue_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> Program
     // #136: fold any un-accrued vault surplus into share price via the shared helper,
     // so this permissionless instruction and the deposit/withdraw pre-accrue guard apply
     // byte-identical accounting (snapshot-before-increment + tranche distribution).
+    let fees_before = pool.total_fees_earned;
     accrue_fees_inner(pool, current_balance)?;
 
     pool.last_fee_accrual_slot = clock.slot;
     pool.last_vault_snapshot = current_balance;
 
+    // amount_in = surplus crystallized by THIS call (already in the vault; no transfer).
+    StakeReturnData {
+        amount_in: pool
+            .total_fees_earned
+            .checked_sub(fees_before)
+            .ok_or(StakeError::Overflow)?,
+        ..StakeReturnData::with_share_price(pool, 12, TRANCHE_POOL)
+    }
+    .set();
     Ok(())
 }
 
@@ -2706,10 +3206,6 @@ fn process_admin_set_hwm_config(
     let admin = next_account_info(accounts_iter)?;
     let pool_pda = next_account_info(accounts_iter)?;
 
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
     // BUG-4: Validate pool account ownership and non-emptiness before reading it.
     validate_account_owner(pool_pda, program_id)?;
     validate_account_not_empty(pool_pda)?;
@@ -2731,8 +3227,9 @@ fn process_admin_set_hwm_config(
     // fired before the admin identity check, letting any signer distinguish in-range vs
     // out-of-range hwm_floor_bps values from InvalidArgument vs Unauthorized. Move the
     // validation after the auth check so only the actual admin can probe the bounds.
-    if pool.admin != admin.key.to_bytes() {
-        return Err(StakeError::Unauthorized.into());
+    authorize_admin(program_id, pool, admin, accounts)?;
+    if pool.is_config_locked(CONFIG_LOCK_HWM) {
+        return Err(StakeError::ConfigLocked.into());
     }
     if enabled {
         validate_hwm_floor_bps(hwm_floor_bps)?;
@@ -2779,10 +3276,6 @@ fn process_admin_set_tranche_config(
     let admin = next_account_info(accounts_iter)?;
     let pool_ai = next_account_info(accounts_iter)?;
 
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
     // BUG-4: Validate pool account ownership and non-emptiness before reading it.
     validate_account_owner(pool_ai, program_id)?;
     validate_account_not_empty(pool_ai)?;
@@ -2800,9 +3293,7 @@ fn process_admin_set_tranche_config(
     }
     // FINDING-5: Validate pool version on AdminSetTrancheConfig.
     validate_pool_version(pool)?;
-    if pool.admin != admin.key.to_bytes() {
-        return Err(StakeError::Unauthorized.into());
-    }
+    authorize_admin(program_id, pool, admin, accounts)?;
 
     // Validate multiplier: minimum 10000 (1x), maximum 50000 (5x)
     if !(10_000..=50_000).contains(&junior_fee_mult_bps) {
@@ -2918,10 +3409,7 @@ fn process_deposit_junior(
         // #154: enforce the cap on PRINCIPAL TVL (no accrued fees), not total_pool_value();
         // otherwise fee appreciation on a mode-1 trading pool silently locks out new deposits.
         let current_value = pool.principal_tvl().ok_or(StakeError::Overflow)?;
-        let new_value = current_value
-            .checked_add(amount)
-            .ok_or(StakeError::Overflow)?;
-        if new_value > pool.deposit_cap {
+        if crate::math::exceeds_cap(current_value, amount, pool.deposit_cap) {
             return Err(StakeError::DepositCapExceeded.into());
         }
     }
@@ -2976,6 +3464,13 @@ fn process_deposit_junior(
     // place of it, so #148's JIT fee-snipe guard stays intact.)
     pre_accrue_fee_modes(pool, vault)?;
 
+    // Deficit mode: no new capital into a pool whose vault was found short —
+    // including a shortfall the pre-accrue above just detected (that write-down
+    // reverts with this deposit; DeclareDeficit is what makes it stick).
+    if pool.in_deficit() {
+        return Err(StakeError::PoolInDeficit.into());
+    }
+
     // Pause junior deposits while an insurance loss is OUTSTANDING (flushed but not
     // yet returned). effective_junior_balance() applies the pool's CURRENT net_loss
     // to the junior tranche with no baseline for when the cohort began, so a junior
@@ -3031,22 +3526,24 @@ fn process_deposit_junior(
     let total_lp_supply_before = pool.total_lp_supply;
     let mint_amount = apply_minimum_liquidity_lock(total_lp_supply_before, lp_to_mint)?;
 
-    invoke(
-        &crate::spl_token::transfer(
-            token_program.key,
-            user_ata.key,
-            vault.key,
-            user.key,
-            &[],
-            amount,
-        )?,
-        &[
-            user_ata.clone(),
-            vault.clone(),
-            user.clone(),
-            token_program.clone(),
-        ],
-    )?;
+    let deposited = cpi_with_vault_delta(vault, VaultFlow::In, amount, || {
+        invoke(
+            &crate::spl_token::transfer(
+                token_program.key,
+                user_ata.key,
+                vault.key,
+                user.key,
+                &[],
+                amount,
+            )?,
+            &[
+                user_ata.clone(),
+                vault.clone(),
+                user.clone(),
+                token_program.clone(),
+            ],
+        )
+    })?;
 
     let (_, vault_auth_bump) = state::derive_vault_authority(program_id, pool_pda.key);
     let vault_auth_seeds: &[&[u8]] = &[b"vault_auth", pool_pda.key.as_ref(), &[vault_auth_bump]];
@@ -3071,7 +3568,7 @@ fn process_deposit_junior(
 
     pool.total_deposited = pool
         .total_deposited
-        .checked_add(amount)
+        .checked_add(deposited)
         .ok_or(StakeError::Overflow)?;
     pool.total_lp_supply = pool
         .total_lp_supply
@@ -3160,6 +3657,7 @@ fn process_deposit_junior(
     deposit.pool = pool_pda.key.to_bytes();
     deposit.user = user.key.to_bytes();
     deposit.last_deposit_slot = clock.slot;
+    deposit.set_recorded_cooldown_slots(pool.cooldown_slots);
     deposit.lp_amount = deposit
         .lp_amount
         .checked_add(lp_to_mint)
@@ -3170,6 +3668,13 @@ fn process_deposit_junior(
         amount,
         lp_to_mint
     );
+
+    StakeReturnData {
+        amount_in: amount,
+        lp_minted: mint_amount,
+        ..StakeReturnData::with_share_price(pool, 16, TRANCHE_JUNIOR)
+    }
+    .set();
     Ok(())
 }
 
@@ -3180,9 +3685,20 @@ fn process_init_trading_pool(
     accounts: &[AccountInfo],
     cooldown_slots: u64,
     deposit_cap: u64,
+    fee_policy: FeePolicyBounds,
+    asset_index: u16,
+    wrapper_abi_version: u8,
 ) -> ProgramResult {
     // Reuse InitPool logic
-    process_init_pool(program_id, accounts, cooldown_slots, deposit_cap)?;
+    process_init_pool(
+        program_id,
+        accounts,
+        cooldown_slots,
+        deposit_cap,
+        fee_policy,
+        asset_index,
+        wrapper_abi_version,
+    )?;
 
     // Now update pool_mode to 1 (trading LP)
     // AUDIT HIGH-4: Validate pool_pda ownership instead of trusting hardcoded index
@@ -3308,20 +3824,22 @@ fn process_return_insurance(
         &[],
         amount,
     )?;
-    invoke(
-        &transfer_ix,
-        &[admin_ata.clone(), vault.clone(), admin.clone()],
-    )?;
+    let returned = cpi_with_vault_delta(vault, VaultFlow::In, amount, || {
+        invoke(
+            &transfer_ix,
+            &[admin_ata.clone(), vault.clone(), admin.clone()],
+        )
+    })?;
 
     // Update accounting
     pool.total_returned = pool
         .total_returned
-        .checked_add(amount)
+        .checked_add(returned)
         .ok_or(StakeError::Overflow)?;
 
     msg!(
         "ReturnInsurance: {} tokens returned to pool vault (total_returned: {})",
-        amount,
+        returned,
         pool.total_returned
     );
     Ok(())
@@ -3441,49 +3959,47 @@ fn process_recover_flushed_insurance(
     }
 
     let vault_auth_seeds: &[&[u8]] = &[b"vault_auth", pool_pda.key.as_ref(), &[vault_auth_bump]];
+    let asset_index = pool.wrapper_asset_index;
+    let abi = cpi::pool_wrapper_abi(pool)?;
 
     // CPI: WithdrawInsuranceAsset (wrapper tag 57).
     // vault_auth PDA signs as insurance_operator (set by BindInsuranceAuthority tag 19).
     // Tokens flow: wrapper_vault → vault (= pool.vault). The drain check above ensures
     // vault == pool.vault so tokens can only land in the stake pool's own vault.
-    cpi::cpi_withdraw_insurance_asset(
-        percolator_program,
-        vault_auth,
-        market,
-        vault,         // dest_token = pool.vault (drain-check-verified above)
-        wrapper_vault, // source = wrapper insurance vault
-        wrapper_vault_auth,
-        token_program,
-        amount,
-        vault_auth_seeds,
-    )?;
-
-    // CONSERVATION: total_returned += amount. Update AFTER the CPI so the accounting
-    // only advances when the token movement actually succeeded.
-    pool.total_returned = pool
-        .total_returned
-        .checked_add(amount)
-        .ok_or(StakeError::Overflow)?;
+    let recovered = cpi_with_vault_delta(vault, VaultFlow::In, amount, || {
+        cpi::cpi_withdraw_insurance_asset(
+            abi,
+            percolator_program,
+            vault_auth,
+            market,
+            vault,         // dest_token = pool.vault (drain-check-verified above)
+            wrapper_vault, // source = wrapper insurance vault
+            wrapper_vault_auth,
+            token_program,
+            asset_index,
+            amount,
+            vault_auth_seeds,
+        )
+    })?;
 
-    // H-1 re-review fix: this is the ONLY site that increments
-    // `total_recovered_from_wrapper` — it runs exclusively after the tag-57
-    // WithdrawInsuranceAsset CPI above has succeeded, so this counter tracks
-    // real wrapper-side recovery only. `process_return_insurance` (admin's own
-    // wallet -> pool.vault, no wrapper CPI) and the #161 last-junior-exit
-    // phantom write-off (realized_junior_loss, zero token movement) must NEVER
-    // touch this counter — see its doc comment on `StakePool` and the H-1 gates
-    // in `process_admin_resolve_market` / `process_set_market_resolved`.
-    pool.total_recovered_from_wrapper = pool
-        .total_recovered_from_wrapper
-        .checked_add(amount)
-        .ok_or(StakeError::Overflow)?;
+    // CONSERVATION: total_returned and total_recovered_from_wrapper += the observed
+    // vault delta. Booked AFTER the CPI so the accounting only advances when the token
+    // movement actually landed in pool.vault — the only place
+    // total_recovered_from_wrapper is ever incremented.
+    accounting::book_recovery(pool, recovered)?;
 
     msg!(
         "RecoverFlushedInsurance: {} tokens recovered to pool vault (total_returned: {}, total_recovered_from_wrapper: {})",
-        amount,
+        recovered,
         pool.total_returned,
         pool.total_recovered_from_wrapper
     );
+
+    StakeReturnData {
+        amount_in: recovered,
+        ..StakeReturnData::with_share_price(pool, 23, TRANCHE_POOL)
+    }
+    .set();
     Ok(())
 }
 
@@ -3495,10 +4011,6 @@ fn process_set_market_resolved(program_id: &Pubkey, accounts: &[AccountInfo]) ->
     let accounts_iter = &mut accounts.iter();
     let admin = next_account_info(accounts_iter)?;
     let pool_pda = next_account_info(accounts_iter)?;
-
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
     validate_account_owner(pool_pda, program_id)?;
     // #184: mirror the #177/#183 fix — reject empty/undersized pool accounts
     // before bytemuck reinterprets the data (a too-short slice would panic).
@@ -3517,9 +4029,7 @@ fn process_set_market_resolved(program_id: &Pubkey, accounts: &[AccountInfo]) ->
         return Err(StakeError::InvalidAccount.into());
     }
     validate_pool_version(pool)?;
-    if pool.admin != admin.key.to_bytes() {
-        return Err(StakeError::Unauthorized.into());
-    }
+    authorize_admin(program_id, pool, admin, accounts)?;
 
     if pool.market_resolved() {
         msg!("Market already resolved");
@@ -3575,7 +4085,7 @@ fn process_set_market_resolved(program_id: &Pubkey, accounts: &[AccountInfo]) ->
 // the byte-for-byte wire proof against the deployed wrapper.
 //
 // Accounts:
-//   0. `[signer]` Admin (must equal pool.admin)
+//   0. `[signer]` Admin
 //   1. `[]` Pool PDA (the marketauth; signs the CPI via invoke_signed)
 //   2. `[writable]` Slab / market account (wrapper-owned)
 //   3. `[]` Percolator program
@@ -3587,10 +4097,6 @@ fn process_admin_resolve_market(program_id: &Pubkey, accounts: &[AccountInfo]) -
     let slab = next_account_info(accounts_iter)?;
     let percolator_program = next_account_info(accounts_iter)?;
 
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
     validate_account_owner(pool_pda, program_id)?;
     validate_account_not_empty(pool_pda)?;
 
@@ -3598,7 +4104,7 @@ fn process_admin_resolve_market(program_id: &Pubkey, accounts: &[AccountInfo]) -
     // instruction; SetMarketResolved is still the separate, explicit local
     // bookkeeping step) and copy out what we need so the borrow is released
     // before the CPI, which needs to clone pool_pda's AccountInfo.
-    let pool_bump = {
+    let (pool_bump, abi) = {
         let pool_data = pool_pda.try_borrow_data()?;
         let pool = pool_from_data(&pool_data[..])?;
         if pool.is_initialized != 1 {
@@ -3608,10 +4114,7 @@ fn process_admin_resolve_market(program_id: &Pubkey, accounts: &[AccountInfo]) -
             return Err(StakeError::InvalidAccount.into());
         }
         validate_pool_version(pool)?;
-        // Admin-gated: only the pool admin may trigger resolution.
-        if pool.admin != admin.key.to_bytes() {
-            return Err(StakeError::Unauthorized.into());
-        }
+        authorize_admin(program_id, pool, admin, accounts)?;
         if pool.slab != slab.key.to_bytes() {
             return Err(StakeError::InvalidPda.into());
         }
@@ -3642,11 +4145,11 @@ fn process_admin_resolve_market(program_id: &Pubkey, accounts: &[AccountInfo]) -
             );
             return Err(StakeError::InsuranceLossOutstanding.into());
         }
-        pool.bump
+        (pool.bump, cpi::pool_wrapper_abi(pool)?)
     };
 
     let pool_seeds: &[&[u8]] = &[b"stake_pool", slab.key.as_ref(), &[pool_bump]];
-    cpi::cpi_resolve_market(percolator_program, pool_pda, slab, pool_seeds)?;
+    cpi::cpi_resolve_market(abi, percolator_program, pool_pda, slab, pool_seeds)?;
 
     msg!("AdminResolveMarket: wrapper market resolved via pool PDA CPI");
     Ok(())
@@ -3666,36 +4169,35 @@ fn process_admin_resolve_market(program_id: &Pubkey, accounts: &[AccountInfo]) -
 // (minus that instruction's resolve-specific H-1 insurance gate, which has no
 // meaning for a fee-policy setter — nothing is stranded by changing a fee).
 //
-// AUTHORITY MODEL — `pool.admin` for all four, matching `AdminResolveMarket`
-// and `BindInsuranceAuthority`. This is the conservative choice and it is worth
-// being explicit about what it does and does not mean: tags 51/55 set the fee
-// policy that determines LP and staker revenue, so "the pool admin decides" and
-// "the stakers decide" are genuinely different answers. This program has no
-// governance primitive of any kind — no vote, no quorum, no timelock — so
-// "stakers decide" is not implementable here without inventing one, which is
-// out of scope for a proxy task and would be a far larger change than the
-// proxies themselves. `pool.admin` also strictly matches the pre-stake status
-// quo: before `InitPool`/`BindInsuranceAuthority`, the market creator held
-// `marketauth` and `insurance_authority` and could set these same values
-// unilaterally. These proxies restore that capability to the same human; they
-// do not grant anyone new power over the market. See the report for the
-// residual concern (an admin can retune the LP/insurance split under staked
-// depositors, which a timelock would mitigate).
+// AUTHORITY MODEL — the fee manager (ROLE_FEE_MANAGER) for all four; while
+// that role is unassigned it falls back to the admin authority, the same
+// `authorize_admin` check `BindInsuranceAuthority` uses. It is worth being
+// explicit about what this does and does not mean: tags 51/55 set the fee
+// policy that determines LP and staker revenue, so "the pool's fee manager
+// decides" and "the stakers decide" are genuinely different answers. The
+// unassigned default strictly matches the pre-stake status quo: before
+// `InitPool`/`BindInsuranceAuthority`, the market creator held `marketauth` and
+// `insurance_authority` and could set these same values unilaterally. These
+// proxies restore that capability to the same human, or to whoever the admin
+// delegates it to; they do not grant anyone new power over the market.
+//
+// What they no longer do is act immediately. An admin retuning the LP/insurance
+// split under staked depositors was the residual concern here, so the handlers
+// below are reachable only as the replay of a `ProposeFeeChange` proposal after
+// TIMELOCK_SLOTS (`process_commit_fee_change`); sent directly, tags 25..28 fail
+// with `FeeChangeRequiresTimelock`.
 
 /// Shared validation for the GROUP A (marketauth-gated) proxies: reproduces
 /// `process_admin_resolve_market`'s account and pool checks and returns the
 /// pool bump needed to sign as `cfg.marketauth`.
 fn validate_group_a_proxy(
     program_id: &Pubkey,
+    accounts: &[AccountInfo],
     admin: &AccountInfo,
     pool_pda: &AccountInfo,
     slab: &AccountInfo,
     percolator_program: &AccountInfo,
-) -> Result<u8, ProgramError> {
-    if !admin.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
+) -> Result<(u8, &'static dyn cpi::WrapperAbi), ProgramError> {
     validate_account_owner(pool_pda, program_id)?;
     validate_account_not_empty(pool_pda)?;
 
@@ -3708,10 +4210,9 @@ fn validate_group_a_proxy(
         return Err(StakeError::InvalidAccount.into());
     }
     validate_pool_version(pool)?;
-    // Admin-gated: only the pool admin may drive the pool's market authority.
-    if pool.admin != admin.key.to_bytes() {
-        return Err(StakeError::Unauthorized.into());
-    }
+    // Fee-manager-gated: only the ROLE_FEE_MANAGER holder (the admin authority
+    // while unassigned) may drive the pool's market authority.
+    authorize_role(program_id, pool, ROLE_FEE_MANAGER, admin, accounts)?;
     // Bind the CPI to the pool's OWN recorded market and wrapper program, so a
     // caller cannot point a validly-signed proxy at a different market.
     if pool.slab != slab.key.to_bytes() {
@@ -3720,7 +4221,7 @@ fn validate_group_a_proxy(
     if pool.percolator_program != percolator_program.key.to_bytes() {
         return Err(StakeError::InvalidPercolatorProgram.into());
     }
-    Ok(pool.bump)
+    Ok((pool.bump, cpi::pool_wrapper_abi(pool)?))
 }
 
 /// Shared validation for the GROUP B (`insurance_authority`-gated) proxies.
@@ -3730,34 +4231,44 @@ fn validate_group_a_proxy(
 /// `process_recover_flushed_insurance`, the existing vault_auth-signing paths.
 fn validate_group_b_proxy(
     program_id: &Pubkey,
+    accounts: &[AccountInfo],
     admin: &AccountInfo,
     pool_pda: &AccountInfo,
     vault_auth: &AccountInfo,
     slab: &AccountInfo,
     percolator_program: &AccountInfo,
-) -> Result<u8, ProgramError> {
+) -> Result<(u8, &'static dyn cpi::WrapperAbi), ProgramError> {
     // Identical pool/admin/slab/program checks as Group A; the returned pool
     // bump is not used here because this group signs as vault_auth instead.
-    let _pool_bump = validate_group_a_proxy(program_id, admin, pool_pda, slab, percolator_program)?;
-
-    let (expected_vault_auth, vault_auth_bump) =
+    let (_pool_bump, abi) = validate_group_a_proxy(
+        program_id,
+        accounts,
+        admin,
+        pool_pda,
+        slab,
+        percolator_program,
+    )?;
+
+    let (expected_vault_auth, vault_auth_bump) =
         state::derive_vault_authority(program_id, pool_pda.key);
     if *vault_auth.key != expected_vault_auth {
         return Err(StakeError::InvalidPda.into());
     }
-    Ok(vault_auth_bump)
+    Ok((vault_auth_bump, abi))
 }
 
 // ── 25: AdminUpdateFeeSplit -> wrapper tag 86 (marketauth, pool PDA signs) ──
 //
 // Accounts:
-//   0. `[signer]` Admin (must equal pool.admin)
-//   1. `[]` Pool PDA (the marketauth; signs the CPI via invoke_signed)
+//   0. `[signer]` Fee manager (ROLE_FEE_MANAGER holder; the admin while unassigned)
+//   1. `[writable]` Pool PDA (the marketauth; signs the CPI via invoke_signed)
 //   2. `[writable]` Slab / market account (wrapper-owned)
 //   3. `[]` Percolator program
 //
 // Share validation is the WRAPPER's (`policy_v16::validate_fee_split`), not
-// duplicated here — see the cpi.rs doc block.
+// duplicated here — see the cpi.rs doc block. The pool's own, tighter
+// `FeePolicyBounds` were already applied by `CommitFeeChange`
+// (`apply_fee_policy_change`); the pool is writable for that record.
 fn process_admin_update_fee_split(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -3771,10 +4282,18 @@ fn process_admin_update_fee_split(
     let slab = next_account_info(accounts_iter)?;
     let percolator_program = next_account_info(accounts_iter)?;
 
-    let pool_bump = validate_group_a_proxy(program_id, admin, pool_pda, slab, percolator_program)?;
+    let (pool_bump, abi) = validate_group_a_proxy(
+        program_id,
+        accounts,
+        admin,
+        pool_pda,
+        slab,
+        percolator_program,
+    )?;
 
     let pool_seeds: &[&[u8]] = &[b"stake_pool", slab.key.as_ref(), &[pool_bump]];
     cpi::cpi_update_fee_split(
+        abi,
         percolator_program,
         pool_pda,
         slab,
@@ -3805,10 +4324,18 @@ fn process_admin_update_maintenance_fee_per_slot(
     let slab = next_account_info(accounts_iter)?;
     let percolator_program = next_account_info(accounts_iter)?;
 
-    let pool_bump = validate_group_a_proxy(program_id, admin, pool_pda, slab, percolator_program)?;
+    let (pool_bump, abi) = validate_group_a_proxy(
+        program_id,
+        accounts,
+        admin,
+        pool_pda,
+        slab,
+        percolator_program,
+    )?;
 
     let pool_seeds: &[&[u8]] = &[b"stake_pool", slab.key.as_ref(), &[pool_bump]];
     cpi::cpi_update_maintenance_fee_per_slot(
+        abi,
         percolator_program,
         pool_pda,
         slab,
@@ -3827,8 +4354,8 @@ fn process_admin_update_maintenance_fee_per_slot(
 // `insurance_authority` to `vault_auth`, this CPI is the only way to reach it.
 //
 // Accounts:
-//   0. `[signer]` Admin (must equal pool.admin)
-//   1. `[]` Pool PDA (used to derive + verify vault_auth; NOT a signer here)
+//   0. `[signer]` Fee manager (ROLE_FEE_MANAGER holder; the admin while unassigned)
+//   1. `[writable]` Pool PDA (used to derive + verify vault_auth; NOT a signer here)
 //   2. `[]` Vault authority PDA (the insurance_authority; signs via invoke_signed)
 //   3. `[writable]` Slab / market account (wrapper-owned)
 //   4. `[]` Percolator program
@@ -3846,8 +4373,9 @@ fn process_admin_update_backing_fee_policy(
     let slab = next_account_info(accounts_iter)?;
     let percolator_program = next_account_info(accounts_iter)?;
 
-    let vault_auth_bump = validate_group_b_proxy(
+    let (vault_auth_bump, abi) = validate_group_b_proxy(
         program_id,
+        accounts,
         admin,
         pool_pda,
         vault_auth,
@@ -3857,6 +4385,7 @@ fn process_admin_update_backing_fee_policy(
 
     let vault_auth_seeds: &[&[u8]] = &[b"vault_auth", pool_pda.key.as_ref(), &[vault_auth_bump]];
     cpi::cpi_update_backing_fee_policy(
+        abi,
         percolator_program,
         vault_auth,
         slab,
@@ -3885,8 +4414,9 @@ fn process_admin_update_trade_fee_policy(
     let slab = next_account_info(accounts_iter)?;
     let percolator_program = next_account_info(accounts_iter)?;
 
-    let vault_auth_bump = validate_group_b_proxy(
+    let (vault_auth_bump, abi) = validate_group_b_proxy(
         program_id,
+        accounts,
         admin,
         pool_pda,
         vault_auth,
@@ -3896,6 +4426,7 @@ fn process_admin_update_trade_fee_policy(
 
     let vault_auth_seeds: &[&[u8]] = &[b"vault_auth", pool_pda.key.as_ref(), &[vault_auth_bump]];
     cpi::cpi_update_trade_fee_policy(
+        abi,
         percolator_program,
         vault_auth,
         slab,
@@ -3907,916 +4438,6459 @@ fn process_admin_update_trade_fee_policy(
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use bytemuck::Zeroable;
+// ═══════════════════════════════════════════════════════════════
+// 29/30/31: DepositFor / WithdrawTo / SetAuthorizedDepositor
+// ═══════════════════════════════════════════════════════════════
+// Custodian and aggregator flows. DepositFor and WithdrawTo reuse the Deposit /
+// Withdraw bodies (`deposit_inner` / `withdraw_inner`) so the pricing, cap, HWM
+// and anti-snipe gates cannot drift between the variants.
 
-    // ── N7 (CONSOLIDATED-PLAN §2.2): apply_minimum_liquidity_lock ───────────
+// ── 29: DepositFor ──
+fn process_deposit_for(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    beneficiary: Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    deposit_inner(program_id, accounts, amount, Some(beneficiary))
+}
 
-    #[test]
-    fn n7_genesis_deposit_above_minimum_liquidity_carves_out_dead_shares() {
-        let lp_to_mint = state::MINIMUM_LIQUIDITY + 1;
-        let mint_amount = apply_minimum_liquidity_lock(0, lp_to_mint).unwrap();
-        assert_eq!(
-            mint_amount, 1,
-            "genesis mint = lp_to_mint - MINIMUM_LIQUIDITY"
-        );
+/// DepositFor opt-in gate: the beneficiary's deposit PDA must already exist (it
+/// is created by `SetAuthorizedDepositor`) and name `funder` as its
+/// `authorized_depositor`.
+fn validate_deposit_for_opt_in(
+    program_id: &Pubkey,
+    pool_pda: &AccountInfo,
+    deposit_pda: &AccountInfo,
+    beneficiary: &Pubkey,
+    funder: &Pubkey,
+) -> ProgramResult {
+    let (expected_deposit_pda, _) =
+        state::derive_deposit_pda(program_id, pool_pda.key, beneficiary);
+    if *deposit_pda.key != expected_deposit_pda {
+        return Err(StakeError::InvalidPda.into());
     }
-
-    #[test]
-    fn n7_genesis_deposit_at_exactly_minimum_liquidity_rejected() {
-        // mint_amount would be exactly 0 — reject rather than mint 0 real LP
-        // while still transferring the depositor's collateral in.
-        let err = apply_minimum_liquidity_lock(0, state::MINIMUM_LIQUIDITY).unwrap_err();
-        assert_eq!(err, StakeError::DepositBelowMinimumLiquidity.into());
+    if deposit_pda.data_is_empty() {
+        msg!("DepositFor: beneficiary has no deposit record — no opt-in");
+        return Err(StakeError::DepositorNotAuthorized.into());
     }
-
-    #[test]
-    fn n7_genesis_deposit_below_minimum_liquidity_rejected() {
-        let err = apply_minimum_liquidity_lock(0, state::MINIMUM_LIQUIDITY - 1).unwrap_err();
-        assert_eq!(err, StakeError::DepositBelowMinimumLiquidity.into());
+    validate_account_owner(deposit_pda, program_id)?;
+    let deposit_data = deposit_pda.try_borrow_data()?;
+    let deposit = deposit_from_data(&deposit_data[..])?;
+    if !deposit.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
     }
+    if deposit.authorized_depositor() != funder.to_bytes() {
+        msg!("DepositFor: signer is not the beneficiary's authorized depositor");
+        return Err(StakeError::DepositorNotAuthorized.into());
+    }
+    Ok(())
+}
 
-    #[test]
-    fn n7_genesis_deposit_of_zero_lp_rejected_not_underflow_panic() {
-        // Defense-in-depth: lp_to_mint=0 shouldn't reach this helper in production
-        // (S-4's ZeroSharesMinted check runs first), but the helper itself must not
-        // panic on checked_sub underflow if it ever did.
-        let err = apply_minimum_liquidity_lock(0, 0).unwrap_err();
-        assert_eq!(err, StakeError::DepositBelowMinimumLiquidity.into());
+// ── 30: WithdrawTo ──
+fn process_withdraw_to(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    lp_amount: u64,
+) -> ProgramResult {
+    withdraw_inner(
+        program_id,
+        accounts,
+        lp_amount,
+        WithdrawMode::ToRecipient,
+        false,
+    )
+}
+
+// ── 31: SetAuthorizedDepositor ──
+//
+// Accounts:
+//   0. `[signer, writable]` Position owner (pays rent if the PDA is created)
+//   1. `[]` Pool PDA
+//   2. `[writable]` Owner's deposit PDA (created if needed)
+//   3. `[]` System program
+fn process_set_authorized_depositor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    depositor: [u8; 32],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    let deposit_pda = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    #[test]
-    fn n7_non_genesis_deposit_mints_full_amount_unchanged() {
-        // total_lp_supply_before != 0 -> not genesis -> pass through unchanged,
-        // even for amounts far below MINIMUM_LIQUIDITY (only the pool's FIRST
-        // deposit ever pays the dead-share floor).
-        assert_eq!(apply_minimum_liquidity_lock(1, 1).unwrap(), 1);
-        assert_eq!(apply_minimum_liquidity_lock(1_000_000, 5).unwrap(), 5);
-        assert_eq!(
-            apply_minimum_liquidity_lock(42, state::MINIMUM_LIQUIDITY).unwrap(),
-            state::MINIMUM_LIQUIDITY
-        );
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    {
+        let pool_data = pool_pda.try_borrow_data()?;
+        let pool = pool_from_data(&pool_data[..])?;
+        if pool.is_initialized != 1 {
+            return Err(StakeError::NotInitialized.into());
+        }
+        if !pool.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        validate_pool_version(pool)?;
     }
 
-    /// MUTATION CHECK (requested by the coordinator's verification-pass addendum):
-    /// if the dead-share carve-out were removed (i.e. apply_minimum_liquidity_lock
-    /// degenerated to `Ok(lp_to_mint)` unconditionally, matching pre-N7 behavior),
-    /// this harness's own invariant — genesis mint_amount < lp_to_mint whenever
-    /// MINIMUM_LIQUIDITY > 0 — would need to fail. We assert the CURRENT (fixed)
-    /// behavior directly against that mutated baseline to make the regression
-    /// this test would catch explicit and auditable, without needing to actually
-    /// revert the source: the pre-N7 formula computed here inline is exactly
-    /// `Ok(lp_to_mint)`, and we assert our real function's output is STRICTLY LESS
-    /// than that baseline at genesis — i.e. the dead-share floor is actually being
-    /// deducted, not a no-op.
-    #[test]
-    fn n7_mutation_check_dead_share_floor_is_strictly_deducted_at_genesis() {
-        let lp_to_mint = state::MINIMUM_LIQUIDITY + 12_345;
-        let pre_n7_baseline_mint_amount = lp_to_mint; // what `Ok(lp_to_mint)` (the mutant) would return
-        let actual_mint_amount = apply_minimum_liquidity_lock(0, lp_to_mint).unwrap();
-        assert!(
-            actual_mint_amount < pre_n7_baseline_mint_amount,
-            "N7 REGRESSION: genesis mint_amount ({actual_mint_amount}) must be strictly less \
-             than the un-mitigated lp_to_mint ({pre_n7_baseline_mint_amount}) — if these are \
-             equal, the dead-share carve-out has been silently removed"
-        );
-        assert_eq!(
-            pre_n7_baseline_mint_amount - actual_mint_amount,
-            state::MINIMUM_LIQUIDITY,
-            "the deducted amount must be exactly MINIMUM_LIQUIDITY, not some other value"
-        );
+    let (expected_deposit_pda, deposit_bump) =
+        state::derive_deposit_pda(program_id, pool_pda.key, owner.key);
+    if *deposit_pda.key != expected_deposit_pda {
+        return Err(StakeError::InvalidPda.into());
     }
+    validate_account_writable(deposit_pda)?;
 
-    // ── #242 timelock_window_elapsed (pure helper) ──────────────────────────
-    #[test]
-    fn timelock_not_elapsed_before_window() {
-        // proposed at 1000, 432_000-slot window ⇒ earliest = 433_000.
-        assert_eq!(
-            timelock_window_elapsed(1_000, TIMELOCK_SLOTS, 1_000).unwrap(),
-            false
-        );
-        assert_eq!(
-            timelock_window_elapsed(1_000, TIMELOCK_SLOTS, 433_000 - 1).unwrap(),
-            false
-        );
+    if deposit_pda.data_is_empty() {
+        let deposit_seeds: &[&[u8]] = &[
+            b"stake_deposit",
+            pool_pda.key.as_ref(),
+            owner.key.as_ref(),
+            &[deposit_bump],
+        ];
+        // Same squat-robust creation as the deposit paths (#163).
+        create_or_adopt_pda(
+            deposit_pda,
+            owner,
+            system_program,
+            program_id,
+            STAKE_DEPOSIT_SIZE,
+            deposit_seeds,
+        )?;
+        let mut deposit_data = deposit_pda.try_borrow_mut_data()?;
+        let deposit = deposit_from_data_mut(&mut deposit_data[..])?;
+        // Not an open position yet (is_initialized stays 0): the first deposit
+        // initializes it exactly as a first Deposit would.
+        deposit.set_discriminator();
+        deposit.bump = deposit_bump;
+        deposit.pool = pool_pda.key.to_bytes();
+        deposit.user = owner.key.to_bytes();
+    } else if *deposit_pda.owner != *program_id {
+        return Err(StakeError::InvalidAccount.into());
     }
 
-    #[test]
-    fn timelock_elapsed_at_or_after_window() {
-        // Boundary is inclusive: now == proposed + window ⇒ elapsed.
-        assert_eq!(
-            timelock_window_elapsed(1_000, TIMELOCK_SLOTS, 1_000 + TIMELOCK_SLOTS).unwrap(),
-            true
-        );
-        assert_eq!(
-            timelock_window_elapsed(1_000, TIMELOCK_SLOTS, 1_000 + TIMELOCK_SLOTS + 5).unwrap(),
-            true
-        );
+    let mut deposit_data = deposit_pda.try_borrow_mut_data()?;
+    let deposit = deposit_from_data_mut(&mut deposit_data[..])?;
+    if !deposit.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
     }
+    deposit.set_authorized_depositor(depositor);
 
-    #[test]
-    fn timelock_overflow_is_error_not_panic() {
-        assert_eq!(
-            timelock_window_elapsed(u64::MAX, TIMELOCK_SLOTS, u64::MAX),
-            Err(StakeError::Overflow.into())
+    if depositor == [0u8; 32] {
+        msg!("SetAuthorizedDepositor: third-party deposits revoked");
+    } else {
+        msg!(
+            "SetAuthorizedDepositor: {} may DepositFor this position",
+            Pubkey::new_from_array(depositor)
         );
     }
+    Ok(())
+}
 
-    // ── #242 StakePool pending-cooldown accessors round-trip ────────────────
-    #[test]
-    fn pending_cooldown_accessors_roundtrip_and_default_zero() {
-        let mut pool = StakePool::zeroed();
-        // Fresh (zeroed) pool ⇒ no active proposal.
-        assert_eq!(pool.pending_cooldown_slots(), 0);
-        assert_eq!(pool.cooldown_proposed_at_slot(), 0);
-        pool.set_pending_cooldown_slots(987_654);
-        pool.set_cooldown_proposed_at_slot(42);
-        assert_eq!(pool.pending_cooldown_slots(), 987_654);
-        assert_eq!(pool.cooldown_proposed_at_slot(), 42);
-        // Clearing the proposal slot restores the sentinel.
-        pool.set_cooldown_proposed_at_slot(0);
-        assert_eq!(pool.cooldown_proposed_at_slot(), 0);
-    }
+// ═══════════════════════════════════════════════════════════════
+// 32/33/34: OperatorWithdraw / SetDepositOperator / RevokeDepositOperator
+// ═══════════════════════════════════════════════════════════════
+// Delegated position management (e.g. an automated rebalancer). The operator is
+// stored in the deposit record's v2 tail (`state::deposit_operator`). It can
+// trigger withdrawals and nothing else: proceeds are pinned to the owner, the LP
+// burn is signed by the pool's `lp_delegate` PDA (never by the operator, so the
+// operator can never move the owner's LP), and it has no deposit path, so it
+// cannot change the position's tranche or restart its cooldown.
+
+// ── 32: OperatorWithdraw ──
+fn process_operator_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    lp_amount: u64,
+) -> ProgramResult {
+    withdraw_inner(
+        program_id,
+        accounts,
+        lp_amount,
+        WithdrawMode::Operator,
+        false,
+    )
+}
 
-    #[test]
-    fn pending_cooldown_bytes_do_not_collide_with_neighbors() {
-        // _reserved[10..26] must not disturb the discriminator/version[0..9],
-        // market_resolved[9], tranche[32+], or realized_junior_loss[51..59].
-        let mut pool = StakePool::zeroed();
-        pool.set_discriminator();
-        pool.set_market_resolved(true);
-        pool.set_realized_junior_loss(7_777);
-        pool.set_tranche_enabled(true);
-        pool.set_pending_cooldown_slots(u64::MAX);
-        pool.set_cooldown_proposed_at_slot(123);
-        assert!(pool.validate_discriminator());
-        assert_eq!(pool.version(), StakePool::CURRENT_VERSION);
-        assert!(pool.market_resolved());
-        assert!(pool.tranche_enabled());
-        assert_eq!(pool.realized_junior_loss(), 7_777);
-        assert_eq!(pool.pending_cooldown_slots(), u64::MAX);
-        assert_eq!(pool.cooldown_proposed_at_slot(), 123);
+/// Shared checks for SetDepositOperator / RevokeDepositOperator: the pool is a
+/// live current-version pool, `deposit_pda` is `owner`'s record in it,
+/// `owner_lp_ata` is an LP token account owned by `owner`, and `lp_delegate` is
+/// the pool's delegate PDA.
+fn validate_operator_accounts(
+    program_id: &Pubkey,
+    owner: &AccountInfo,
+    pool_pda: &AccountInfo,
+    deposit_pda: &AccountInfo,
+    owner_lp_ata: &AccountInfo,
+    lp_delegate: &AccountInfo,
+) -> ProgramResult {
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    #[test]
-    fn return_insurance_rejects_admin_ata_not_owned_by_admin() {
-        use solana_program::account_info::AccountInfo;
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    let lp_mint = {
+        let pool_data = pool_pda.try_borrow_data()?;
+        let pool = pool_from_data(&pool_data[..])?;
+        if pool.is_initialized != 1 {
+            return Err(StakeError::NotInitialized.into());
+        }
+        if !pool.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        validate_pool_version(pool)?;
+        pool.lp_mint
+    };
 
-        let program_id = Pubkey::new_from_array([9u8; 32]);
-        let admin_key = Pubkey::new_from_array([1u8; 32]);
-        let pool_key = Pubkey::new_from_array([2u8; 32]);
-        let admin_ata_key = Pubkey::new_from_array([3u8; 32]);
+    let (expected_deposit_pda, _) = state::derive_deposit_pda(program_id, pool_pda.key, owner.key);
+    if *deposit_pda.key != expected_deposit_pda {
+        return Err(StakeError::InvalidPda.into());
+    }
+    validate_account_owner(deposit_pda, program_id)?;
+    validate_account_writable(deposit_pda)?;
+    {
+        let deposit_data = deposit_pda.try_borrow_data()?;
+        let deposit = deposit_from_data(&deposit_data[..])?;
+        if !deposit.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        if deposit.user != owner.key.to_bytes() || deposit.pool != pool_pda.key.to_bytes() {
+            return Err(StakeError::Unauthorized.into());
+        }
+    }
+
+    if *owner_lp_ata.owner != crate::spl_token::id() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    {
+        let lp_ata_data = owner_lp_ata.try_borrow_data()?;
+        if lp_ata_data.len() < crate::spl_token::state::ACCOUNT_LEN {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        if lp_ata_data[0..32] != lp_mint {
+            return Err(StakeError::InvalidMint.into());
+        }
+        if &lp_ata_data[32..64] != owner.key.as_ref() {
+            return Err(StakeError::Unauthorized.into());
+        }
+    }
+
+    let (expected_lp_delegate, _) = state::derive_lp_delegate(program_id, pool_pda.key);
+    if *lp_delegate.key != expected_lp_delegate {
+        return Err(StakeError::InvalidPda.into());
+    }
+    Ok(())
+}
+
+// ── 33: SetDepositOperator ──
+//
+// Accounts:
+//   0. `[signer, writable]` Position owner (pays any realloc rent)
+//   1. `[]` Pool PDA
+//   2. `[writable]` Owner's deposit PDA
+//   3. `[writable]` Owner's LP token account
+//   4. `[]` LP delegate PDA
+//   5. `[]` Token program
+//   6. `[]` System program
+fn process_set_deposit_operator(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    operator: [u8; 32],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    let deposit_pda = next_account_info(accounts_iter)?;
+    let owner_lp_ata = next_account_info(accounts_iter)?;
+    let lp_delegate = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    // Clearing goes through RevokeDepositOperator, which also drops the SPL delegation.
+    if operator == [0u8; 32] {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    validate_operator_accounts(
+        program_id,
+        owner,
+        pool_pda,
+        deposit_pda,
+        owner_lp_ata,
+        lp_delegate,
+    )?;
+    verify_token_program(token_program)?;
+
+    // v1 → v2: grow the record to hold the operator tail. The new bytes are
+    // zero-filled, i.e. "no operator", until set below.
+    if deposit_pda.data_len() < state::STAKE_DEPOSIT_V2_SIZE {
+        let need = Rent::get()?.minimum_balance(state::STAKE_DEPOSIT_V2_SIZE);
+        let have = deposit_pda.lamports();
+        if have < need {
+            invoke(
+                &system_instruction::transfer(owner.key, deposit_pda.key, need - have),
+                &[owner.clone(), deposit_pda.clone(), system_program.clone()],
+            )?;
+        }
+        #[allow(deprecated)]
+        deposit_pda.realloc(state::STAKE_DEPOSIT_V2_SIZE, true)?;
+    }
+    {
+        let mut deposit_data = deposit_pda.try_borrow_mut_data()?;
+        state::set_deposit_operator(&mut deposit_data[..], operator)?;
+    }
+
+    invoke(
+        &crate::spl_token::approve(
+            token_program.key,
+            owner_lp_ata.key,
+            lp_delegate.key,
+            owner.key,
+            &[],
+            u64::MAX,
+        )?,
+        &[
+            owner_lp_ata.clone(),
+            lp_delegate.clone(),
+            owner.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    msg!(
+        "SetDepositOperator: {} may withdraw this position to its owner",
+        Pubkey::new_from_array(operator)
+    );
+    Ok(())
+}
+
+// ── 34: RevokeDepositOperator ──
+//
+// Accounts:
+//   0. `[signer]` Position owner
+//   1. `[]` Pool PDA
+//   2. `[writable]` Owner's deposit PDA
+//   3. `[writable]` Owner's LP token account
+//   4. `[]` LP delegate PDA
+//   5. `[]` Token program
+fn process_revoke_deposit_operator(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    let deposit_pda = next_account_info(accounts_iter)?;
+    let owner_lp_ata = next_account_info(accounts_iter)?;
+    let lp_delegate = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    validate_operator_accounts(
+        program_id,
+        owner,
+        pool_pda,
+        deposit_pda,
+        owner_lp_ata,
+        lp_delegate,
+    )?;
+    verify_token_program(token_program)?;
+
+    // A v1 record never had an operator; nothing to clear.
+    if deposit_pda.data_len() >= state::STAKE_DEPOSIT_V2_SIZE {
+        let mut deposit_data = deposit_pda.try_borrow_mut_data()?;
+        state::set_deposit_operator(&mut deposit_data[..], [0u8; 32])?;
+    }
+
+    // Only undo OUR delegation. SPL layout: [72..76] delegate COption tag, [76..108] delegate.
+    let delegated_to_us = {
+        let lp_ata_data = owner_lp_ata.try_borrow_data()?;
+        lp_ata_data[72..76] == 1u32.to_le_bytes()
+            && &lp_ata_data[76..108] == lp_delegate.key.as_ref()
+    };
+    if delegated_to_us {
+        invoke(
+            &crate::spl_token::revoke(token_program.key, owner_lp_ata.key, owner.key, &[])?,
+            &[owner_lp_ata.clone(), owner.clone(), token_program.clone()],
+        )?;
+    }
+
+    msg!("RevokeDepositOperator: operator cleared");
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// 35: CloseDeposit
+// ═══════════════════════════════════════════════════════════════
+
+/// Close a program-owned PDA (a deposit record, or the pool at sunset): move all
+/// its lamports to `destination`, zero its data, shrink it to zero length and
+/// re-assign it to the System program.
+///
+/// Zeroing lamports alone is not a close inside the transaction that does it:
+/// the runtime only purges the account at the end, and a same-transaction
+/// refund keeps it alive, program-owned, with its old bytes (a revived record).
+/// Leaving it System-owned and empty instead means a refunded address is just
+/// the #166 squat state, which `create_or_adopt_pda` recreates from scratch.
+fn close_pda(deposit_pda: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    let lamports = deposit_pda.lamports();
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(StakeError::Overflow)?;
+    **deposit_pda.try_borrow_mut_lamports()? = 0;
+    deposit_pda.try_borrow_mut_data()?.fill(0);
+    #[allow(deprecated)]
+    deposit_pda.realloc(0, false)?;
+    deposit_pda.assign(&solana_program::system_program::id());
+    Ok(())
+}
+
+/// A pool `SweepAndClosePool` has closed: `close_pda` leaves it System-owned
+/// and empty. Records naming it (deposits, veto votes) must stay reclaimable;
+/// each is bound to this key by its own PDA derivation and stored `pool`, and
+/// only a live pool ever creates one.
+fn pool_is_closed(pool_pda: &AccountInfo) -> bool {
+    *pool_pda.owner == solana_program::system_program::id() && pool_pda.data_is_empty()
+}
+
+// Accounts:
+//   0. `[signer, writable]` Position owner (receives the rent)
+//   1. `[]` Pool PDA (live, or closed by SweepAndClosePool)
+//   2. `[writable]` Owner's deposit PDA
+fn process_close_deposit(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let owner = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    let deposit_pda = next_account_info(accounts_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    validate_account_writable(owner)?;
+
+    // No initialized/version gate on the pool: reclaiming rent from an empty
+    // record must stay possible whatever state the pool is in, including after
+    // SweepAndClosePool has closed it. The pool only anchors the PDA derivation.
+    let pool_closed = pool_is_closed(pool_pda);
+    if !pool_closed {
+        validate_account_owner(pool_pda, program_id)?;
+    }
+
+    let (expected_deposit_pda, _) = state::derive_deposit_pda(program_id, pool_pda.key, owner.key);
+    if *deposit_pda.key != expected_deposit_pda {
+        return Err(StakeError::InvalidPda.into());
+    }
+    validate_account_owner(deposit_pda, program_id)?;
+    validate_account_writable(deposit_pda)?;
+    {
+        let deposit_data = deposit_pda.try_borrow_data()?;
+        let deposit = deposit_from_data(&deposit_data[..])?;
+        if !deposit.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        if deposit.user != owner.key.to_bytes() || deposit.pool != pool_pda.key.to_bytes() {
+            return Err(StakeError::Unauthorized.into());
+        }
+        // Once the pool is closed its vault is gone and its LP redeems for
+        // nothing, so an unredeemed position no longer pins the record.
+        if deposit.lp_amount != 0 && !pool_closed {
+            return Err(StakeError::DepositNotEmpty.into());
+        }
+    }
+
+    let reclaimed = deposit_pda.lamports();
+    close_pda(deposit_pda, owner)?;
+    msg!("CloseDeposit: reclaimed {} lamports", reclaimed);
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// 36/37/38: Finalize / RedeemFinal / SweepAndClosePool — pool sunset
+// ═══════════════════════════════════════════════════════════════
+// Terminal lifecycle after resolution. Finalize (admin, irreversible) requires the
+// same gates resolution does plus resolution itself, so nothing is left in the
+// wrapper. RedeemFinal then lets every holder exit pro-rata with no cooldown or HWM
+// floor. After FINAL_SWEEP_GRACE_SLOTS, SweepAndClosePool moves whatever is left —
+// the MINIMUM_LIQUIDITY dead-share value, rounding dust and any unredeemed share —
+// to the recipient named at Finalize, and closes the vault and the pool PDA.
+
+// ── 36: Finalize ──
+//
+// Accounts:
+//   0. `[signer]` Admin
+//   1. `[writable]` Pool PDA
+//   2. `[]` Clock sysvar
+fn process_finalize(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    sweep_recipient: [u8; 32],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    let clock_sysvar = next_account_info(accounts_iter)?;
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_account_writable(pool_pda)?;
+
+    let mut pool_data = pool_pda.try_borrow_mut_data()?;
+    let pool = pool_from_data_mut(&mut pool_data[..])?;
+
+    if pool.is_initialized != 1 {
+        return Err(StakeError::NotInitialized.into());
+    }
+    if !pool.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    validate_pool_version(pool)?;
+    authorize_admin(program_id, pool, admin, accounts)?;
+
+    if pool.is_finalized() {
+        return Err(StakeError::PoolAlreadyFinalized.into());
+    }
+    if !pool.market_resolved() {
+        return Err(StakeError::MarketNotResolved.into());
+    }
+    // Same H-1 threshold as resolution: nothing recoverable may be left in the
+    // wrapper, or the sweep would close the pool on top of it.
+    if !pool.wrapper_fully_recovered() {
+        msg!(
+            "Finalize: {} tokens flushed-but-not-recovered-from-wrapper — call RecoverFlushedInsurance first",
+            pool.wrapper_recoverable()
+        );
+        return Err(StakeError::InsuranceLossOutstanding.into());
+    }
+    if sweep_recipient == [0u8; 32] {
+        return Err(StakeError::InvalidAccount.into());
+    }
+
+    let clock = Clock::from_account_info(clock_sysvar)?;
+    // 0 is the "not finalized" sentinel; a live slot is never 0, but be explicit.
+    pool.finalized_at_slot = clock.slot.max(1);
+    pool.final_sweep_recipient = sweep_recipient;
+
+    msg!(
+        "Finalize: pool finalized at slot {}; sweep to {} after slot {}",
+        pool.finalized_at_slot,
+        Pubkey::new_from_array(sweep_recipient),
+        pool.finalized_at_slot
+            .saturating_add(FINAL_SWEEP_GRACE_SLOTS)
+    );
+    Ok(())
+}
+
+// ── 37: RedeemFinal ──
+fn process_redeem_final(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    lp_amount: u64,
+) -> ProgramResult {
+    withdraw_inner(program_id, accounts, lp_amount, WithdrawMode::Redeem, false)
+}
+
+// ── 38: SweepAndClosePool ──
+//
+// Permissionless, and no signer is needed: the sweep can only pay the
+// recipient fixed at Finalize, and the rent goes to that same wallet.
+//
+// Accounts:
+//   0. `[writable]` Pool PDA
+//   1. `[writable]` Pool vault token account
+//   2. `[writable]` Recipient's collateral token account
+//   3. `[writable]` Recipient wallet (`final_sweep_recipient`)
+//   4. `[]` Vault authority PDA
+//   5. `[writable]` LP mint
+//   6. `[]` Token program
+//   7. `[]` Clock sysvar
+fn process_sweep_and_close_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let pool_pda = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let recipient_ata = next_account_info(accounts_iter)?;
+    let recipient = next_account_info(accounts_iter)?;
+    let vault_auth = next_account_info(accounts_iter)?;
+    let lp_mint = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let clock_sysvar = next_account_info(accounts_iter)?;
+
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_account_writable(pool_pda)?;
+    verify_token_program(token_program)?;
+
+    let clock = Clock::from_account_info(clock_sysvar)?;
+    let (collateral_mint, vault_auth_bump) = {
+        let pool_data = pool_pda.try_borrow_data()?;
+        let pool = pool_from_data(&pool_data[..])?;
+        if pool.is_initialized != 1 {
+            return Err(StakeError::NotInitialized.into());
+        }
+        if !pool.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        validate_pool_version(pool)?;
+        if !pool.is_finalized() {
+            return Err(StakeError::PoolNotFinalized.into());
+        }
+        if clock.slot
+            < pool
+                .finalized_at_slot
+                .saturating_add(FINAL_SWEEP_GRACE_SLOTS)
+        {
+            return Err(StakeError::FinalSweepTooEarly.into());
+        }
+        if pool.vault != vault.key.to_bytes() {
+            return Err(StakeError::InvalidPda.into());
+        }
+        if pool.lp_mint != lp_mint.key.to_bytes() {
+            return Err(StakeError::InvalidMint.into());
+        }
+        if pool.final_sweep_recipient != recipient.key.to_bytes() {
+            msg!("Error: recipient is not the pool's final_sweep_recipient");
+            return Err(StakeError::Unauthorized.into());
+        }
+        (pool.collateral_mint, pool.vault_authority_bump)
+    };
+
+    // FINDING-11 discipline: stored bump must match the derived one.
+    let (expected_vault_auth, derived_bump) = derive_vault_authority(program_id, pool_pda.key);
+    if *vault_auth.key != expected_vault_auth || vault_auth_bump != derived_bump {
+        return Err(StakeError::InvalidPda.into());
+    }
+
+    if recipient_ata.key == vault.key || *recipient_ata.owner != crate::spl_token::id() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    {
+        let ata_data = recipient_ata.try_borrow_data()?;
+        if ata_data.len() < crate::spl_token::state::ACCOUNT_LEN {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        if ata_data[0..32] != collateral_mint {
+            return Err(StakeError::InvalidMint.into());
+        }
+        if &ata_data[32..64] != recipient.key.as_ref() {
+            msg!("Error: sweep destination is not owned by the recipient");
+            return Err(StakeError::Unauthorized.into());
+        }
+    }
+
+    let swept = {
+        let vault_data = vault.try_borrow_data()?;
+        crate::spl_token::state::Account::unpack(&vault_data)?.amount
+    };
+    let vault_auth_seeds: &[&[u8]] = &[b"vault_auth", pool_pda.key.as_ref(), &[vault_auth_bump]];
+
+    if swept > 0 {
+        invoke_signed(
+            &crate::spl_token::transfer(
+                token_program.key,
+                vault.key,
+                recipient_ata.key,
+                vault_auth.key,
+                &[],
+                swept,
+            )?,
+            &[
+                vault.clone(),
+                recipient_ata.clone(),
+                vault_auth.clone(),
+                token_program.clone(),
+            ],
+            &[vault_auth_seeds],
+        )?;
+    }
+    invoke_signed(
+        &crate::spl_token::close_account(
+            token_program.key,
+            vault.key,
+            recipient.key,
+            vault_auth.key,
+            &[],
+        )?,
+        &[
+            vault.clone(),
+            recipient.clone(),
+            vault_auth.clone(),
+            token_program.clone(),
+        ],
+        &[vault_auth_seeds],
+    )?;
+    // SPL Token mints cannot be closed; dropping the mint authority is the
+    // terminal state — no LP can ever be minted against this pool again.
+    invoke_signed(
+        &crate::spl_token::set_mint_authority(
+            token_program.key,
+            lp_mint.key,
+            None,
+            vault_auth.key,
+            &[],
+        )?,
+        &[lp_mint.clone(), vault_auth.clone(), token_program.clone()],
+        &[vault_auth_seeds],
+    )?;
+
+    close_pda(pool_pda, recipient)?;
+
+    msg!(
+        "SweepAndClosePool: swept {} collateral to {}; vault and pool closed",
+        swept,
+        recipient.key
+    );
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// 39/40/41/42: ProposeFeeChange / CommitFeeChange / CancelFeeChange / SetFeePolicyBounds
+// ═══════════════════════════════════════════════════════════════
+// The #242 timelock, generalized to the four wrapper fee-policy proxies (tags
+// 25..28). A proposal is its own PDA holding the proxied call's exact
+// instruction data, so LPs see precisely what will run and have
+// TIMELOCK_SLOTS to exit before it can. Commit replays that data through the
+// unchanged proxy handlers, which re-run `validate_group_a_proxy` /
+// `validate_group_b_proxy` against the accounts supplied at commit time.
+//
+// Commit is also where the pool's stake-side `FeePolicyBounds` bite: the
+// wrapper's bounds are constitutional, these are the narrower terms LPs staked
+// under. They are set at InitPool, and `SetFeePolicyBounds` (tag 42) relaxes
+// them only through this same propose/commit path.
+
+/// Check a fee-policy proxy against the pool's `FeePolicyBounds` at slot `now`
+/// and, if it passes, record it as the pool's latest fee change. Pure over the
+/// pool struct.
+///
+/// - tag 25: `lp_share_bps >= min_lp_share_bps`, and within
+///   `max_change_delta_bps` of the last split this program set;
+/// - tag 28: `trade_fee_base_bps <= max_trade_fee_base_bps`, and within
+///   `max_change_delta_bps` of the last trade fee this program set;
+/// - tag 27: backing `fee_bps <= max_backing_fee_bps`, and within
+///   `max_change_delta_bps` of the last backing fee this program set for the
+///   same domain of the pool's asset;
+/// - tag 26 (maintenance fee) is not an LP fee-share term and passes untouched.
+///
+/// Tags 25/27/28 must also come at least `min_change_interval_slots` after the
+/// previous recorded change. Parameters set before staking are never read
+/// back from the wrapper, so the first change of each is held only to the
+/// absolute bounds.
+pub fn apply_fee_policy_change(
+    pool: &mut StakePool,
+    call: &StakeInstruction,
+    now: u64,
+) -> ProgramResult {
+    let bounds = pool.fee_policy_bounds();
+    if matches!(
+        call,
+        StakeInstruction::AdminUpdateMaintenanceFeePerSlot { .. }
+    ) {
+        return Ok(());
+    }
+    // clock.slot is never 0 on a live chain, so 0 means "no change yet".
+    if pool.last_fee_change_slot != 0
+        && !timelock_window_elapsed(
+            pool.last_fee_change_slot,
+            bounds.min_change_interval_slots,
+            now,
+        )?
+    {
+        return Err(StakeError::FeeChangeTooSoon.into());
+    }
+    let delta = u64::from(bounds.max_change_delta_bps);
+    match *call {
+        StakeInstruction::AdminUpdateFeeSplit { lp_share_bps, .. } => {
+            if lp_share_bps < bounds.min_lp_share_bps {
+                return Err(StakeError::FeePolicyBoundViolated.into());
+            }
+            if pool.fee_history_flags & FEE_HISTORY_LP_SHARE_KNOWN != 0
+                && u64::from(lp_share_bps.abs_diff(pool.last_lp_share_bps)) > delta
+            {
+                return Err(StakeError::FeePolicyBoundViolated.into());
+            }
+            pool.last_lp_share_bps = lp_share_bps;
+            pool.fee_history_flags |= FEE_HISTORY_LP_SHARE_KNOWN;
+        }
+        StakeInstruction::AdminUpdateTradeFeePolicy { trade_fee_base_bps } => {
+            if trade_fee_base_bps > bounds.max_trade_fee_base_bps {
+                return Err(StakeError::FeePolicyBoundViolated.into());
+            }
+            if pool.fee_history_flags & FEE_HISTORY_TRADE_FEE_KNOWN != 0
+                && trade_fee_base_bps.abs_diff(pool.last_trade_fee_base_bps) > delta
+            {
+                return Err(StakeError::FeePolicyBoundViolated.into());
+            }
+            pool.last_trade_fee_base_bps = trade_fee_base_bps;
+            pool.fee_history_flags |= FEE_HISTORY_TRADE_FEE_KNOWN;
+        }
+        StakeInstruction::AdminUpdateBackingFeePolicy {
+            domain, fee_bps, ..
+        } => {
+            if fee_bps > bounds.max_backing_fee_bps {
+                return Err(StakeError::FeePolicyBoundViolated.into());
+            }
+            // Only the two domains of the pool's own asset can commit; any
+            // other fails at the wrapper, reverting this with it.
+            let lane = usize::from(domain % 2);
+            let known = FEE_HISTORY_BACKING_FEE_KNOWN << lane;
+            if pool.fee_history_flags & known != 0
+                && u64::from(fee_bps.abs_diff(pool.last_backing_fee_bps[lane])) > delta
+            {
+                return Err(StakeError::FeePolicyBoundViolated.into());
+            }
+            pool.last_backing_fee_bps[lane] = fee_bps;
+            pool.fee_history_flags |= known;
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    }
+    pool.last_fee_change_slot = now;
+    Ok(())
+}
+
+/// Load a proposal for commit/cancel: program-owned, initialized, bound to
+/// `pool`, and still pending.
+fn validate_pending_proposal(
+    program_id: &Pubkey,
+    proposal_pda: &AccountInfo,
+    pool: &Pubkey,
+) -> ProgramResult {
+    validate_account_owner(proposal_pda, program_id)?;
+    validate_account_not_empty(proposal_pda)?;
+    validate_account_writable(proposal_pda)?;
+    let mut data = proposal_pda.try_borrow_mut_data()?;
+    let proposal = proposal_from_data_mut(&mut data[..])?;
+    if proposal.is_initialized != 1 || !proposal.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    if proposal.pool != pool.to_bytes() {
+        return Err(StakeError::InvalidPda.into());
+    }
+    if !proposal.is_pending() {
+        return Err(StakeError::ProposalNotPending.into());
+    }
+    Ok(())
+}
+
+/// Execute a decoded fee-policy proxy, bounds or veto-threshold change.
+/// Reached only from `CommitFeeChange`.
+fn dispatch_fee_proxy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    call: StakeInstruction,
+) -> ProgramResult {
+    match call {
+        StakeInstruction::AdminUpdateFeeSplit {
+            creator_share_bps,
+            lp_share_bps,
+            insurance_share_bps,
+        } => process_admin_update_fee_split(
+            program_id,
+            accounts,
+            creator_share_bps,
+            lp_share_bps,
+            insurance_share_bps,
+        ),
+        StakeInstruction::AdminUpdateMaintenanceFeePerSlot {
+            maintenance_fee_per_slot,
+        } => process_admin_update_maintenance_fee_per_slot(
+            program_id,
+            accounts,
+            maintenance_fee_per_slot,
+        ),
+        StakeInstruction::AdminUpdateBackingFeePolicy {
+            domain,
+            fee_bps,
+            insurance_share_bps,
+        } => process_admin_update_backing_fee_policy(
+            program_id,
+            accounts,
+            domain,
+            fee_bps,
+            insurance_share_bps,
+        ),
+        StakeInstruction::AdminUpdateTradeFeePolicy { trade_fee_base_bps } => {
+            process_admin_update_trade_fee_policy(program_id, accounts, trade_fee_base_bps)
+        }
+        StakeInstruction::SetFeePolicyBounds { bounds } => {
+            process_set_fee_policy_bounds(program_id, accounts, bounds, true)
+        }
+        StakeInstruction::SetVetoThreshold { threshold_bps } => {
+            process_set_veto_threshold(program_id, accounts, threshold_bps, true)
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// Role that proposes (and may cancel) a timelocked call: the fee manager for
+/// the fee proxies, `None` (the admin) for the pool's own bounds and veto
+/// threshold.
+fn timelocked_call_role(call: &[u8]) -> Option<u8> {
+    match StakeInstruction::unpack(call) {
+        Ok(ix) if ix.is_fee_proxy() => Some(ROLE_FEE_MANAGER),
+        _ => None,
+    }
+}
+
+// ── 39: ProposeFeeChange ──
+//
+// Accounts:
+//   0. `[signer, writable]` Fee manager for tags 25..28, admin for 42 / 45
+//      (pays rent), or `[]` the council PDA
+//   1. `[]` Pool PDA
+//   2. `[writable]` Proposal PDA (to be created)
+//   3. `[]` System program
+//   4. `[]` Clock sysvar
+//   5. `[signer, writable]` Rent payer (council only; the PDA cannot pay)
+fn process_propose_fee_change(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proposal_id: u64,
+    call: &[u8],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    let proposal_pda = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let clock_sysvar = next_account_info(accounts_iter)?;
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    {
+        let pool_data = pool_pda.try_borrow_data()?;
+        let pool = pool_from_data(&pool_data[..])?;
+        if pool.is_initialized != 1 {
+            return Err(StakeError::NotInitialized.into());
+        }
+        if !pool.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        validate_pool_version(pool)?;
+        match timelocked_call_role(call) {
+            Some(role) => authorize_role(program_id, pool, role, admin, accounts)?,
+            None => authorize_admin(program_id, pool, admin, accounts)?,
+        }
+    }
+    // Unpack already checks this; the stored bytes must never be anything else.
+    if call.is_empty() || call.len() > PROPOSAL_CALL_MAX_LEN {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let payer = if admin.is_signer {
+        admin
+    } else {
+        next_account_info(accounts_iter)?
+    };
+
+    let (expected_proposal, proposal_bump) =
+        state::derive_proposal_pda(program_id, pool_pda.key, proposal_id);
+    if *proposal_pda.key != expected_proposal {
+        return Err(StakeError::InvalidPda.into());
+    }
+    validate_account_writable(proposal_pda)?;
+
+    let clock = Clock::from_account_info(clock_sysvar)?;
+
+    let id_bytes = proposal_id.to_le_bytes();
+    let proposal_seeds: &[&[u8]] = &[
+        b"stake_proposal",
+        pool_pda.key.as_ref(),
+        &id_bytes,
+        &[proposal_bump],
+    ];
+    // Refuses an id that is already in use for this pool.
+    create_or_adopt_pda(
+        proposal_pda,
+        payer,
+        system_program,
+        program_id,
+        STAKE_PROPOSAL_SIZE,
+        proposal_seeds,
+    )?;
+
+    let mut proposal_data = proposal_pda.try_borrow_mut_data()?;
+    let proposal = proposal_from_data_mut(&mut proposal_data[..])?;
+    proposal.set_discriminator();
+    proposal.is_initialized = 1;
+    proposal.bump = proposal_bump;
+    proposal.status = PROPOSAL_STATUS_PENDING;
+    proposal.pool = pool_pda.key.to_bytes();
+    proposal.proposal_id = proposal_id;
+    // clock.slot is never 0 on a live chain; same sentinel reasoning as #242.
+    proposal.proposed_at_slot = clock.slot;
+    proposal.call_len = call.len() as u8;
+    proposal.call[..call.len()].copy_from_slice(call);
+
+    msg!(
+        "ProposeFeeChange: proposal {} (proxy tag {}) pending; commit after slot {}",
+        proposal_id,
+        call[0],
+        clock.slot.saturating_add(TIMELOCK_SLOTS)
+    );
+    Ok(())
+}
+
+// ── 40: CommitFeeChange ──
+//
+// Accounts:
+//   0. `[writable]` Proposal PDA
+//   1. `[]` Clock sysvar
+//   2. onward: the proposed tag's own accounts (admin signer first, then pool PDA)
+fn process_commit_fee_change(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let proposal_pda = next_account_info(accounts_iter)?;
+    let clock_sysvar = next_account_info(accounts_iter)?;
+    let proxy_accounts = &accounts[2..];
+    // Every proxy's account list starts [admin, pool_pda, ...].
+    let pool_pda = proxy_accounts
+        .get(1)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    validate_pending_proposal(program_id, proposal_pda, pool_pda.key)?;
+
+    let clock = Clock::from_account_info(clock_sysvar)?;
+    let call = {
+        let mut proposal_data = proposal_pda.try_borrow_mut_data()?;
+        let proposal = proposal_from_data_mut(&mut proposal_data[..])?;
+        if !timelock_window_elapsed(proposal.proposed_at_slot, TIMELOCK_SLOTS, clock.slot)? {
+            return Err(StakeError::TimelockNotElapsed.into());
+        }
+        let call = StakeInstruction::unpack(proposal.call_data())?;
+        if !call.is_timelockable() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        // Marked before the replay; any failure below reverts this too.
+        proposal.status = PROPOSAL_STATUS_COMMITTED;
+        call
+    };
+
+    if call.is_fee_proxy() {
+        // The proxy re-checks the admin and pool binding; here the pool only
+        // has to be a live pool account we may write the fee history to.
+        validate_account_owner(pool_pda, program_id)?;
+        validate_account_not_empty(pool_pda)?;
+        validate_account_writable(pool_pda)?;
+        let mut pool_data = pool_pda.try_borrow_mut_data()?;
+        let pool = pool_from_data_mut(&mut pool_data[..])?;
+        if pool.is_initialized != 1 {
+            return Err(StakeError::NotInitialized.into());
+        }
+        if !pool.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        validate_pool_version(pool)?;
+        apply_fee_policy_change(pool, &call, clock.slot)?;
+    }
+
+    dispatch_fee_proxy(program_id, proxy_accounts, call)?;
+
+    msg!("CommitFeeChange: proposal executed");
+    Ok(())
+}
+
+// ── 41: CancelFeeChange ──
+//
+// Accounts:
+//   0. `[signer]` The proposing role (see `timelocked_call_role`) or the admin
+//   1. `[]` Pool PDA
+//   2. `[writable]` Proposal PDA
+fn process_cancel_fee_change(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    let proposal_pda = next_account_info(accounts_iter)?;
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_pending_proposal(program_id, proposal_pda, pool_pda.key)?;
+    let mut proposal_data = proposal_pda.try_borrow_mut_data()?;
+    let proposal = proposal_from_data_mut(&mut proposal_data[..])?;
+    {
+        let pool_data = pool_pda.try_borrow_data()?;
+        let pool = pool_from_data(&pool_data[..])?;
+        if pool.is_initialized != 1 {
+            return Err(StakeError::NotInitialized.into());
+        }
+        if !pool.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        validate_pool_version(pool)?;
+        // The admin may stop any pending change, not only its own.
+        match timelocked_call_role(proposal.call_data()) {
+            Some(role) => authorize_role(program_id, pool, role, admin, accounts)
+                .or_else(|_| authorize_admin(program_id, pool, admin, accounts))?,
+            None => authorize_admin(program_id, pool, admin, accounts)?,
+        }
+    }
+
+    proposal.status = PROPOSAL_STATUS_CANCELLED;
+
+    msg!(
+        "CancelFeeChange: proposal {} cancelled",
+        proposal.proposal_id
+    );
+    Ok(())
+}
+
+// ── 42: SetFeePolicyBounds ──
+//
+// Accounts:
+//   0. `[signer]` Admin
+//   1. `[writable]` Pool PDA
+//
+// `via_timelock` is true only when replayed by `CommitFeeChange`; a direct
+// call may tighten the bounds but never loosen them.
+fn process_set_fee_policy_bounds(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    bounds: FeePolicyBounds,
+    via_timelock: bool,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_account_writable(pool_pda)?;
+    let mut pool_data = pool_pda.try_borrow_mut_data()?;
+    let pool = pool_from_data_mut(&mut pool_data[..])?;
+    if pool.is_initialized != 1 {
+        return Err(StakeError::NotInitialized.into());
+    }
+    if !pool.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    validate_pool_version(pool)?;
+    authorize_admin(program_id, pool, admin, accounts)?;
+    if !via_timelock && !bounds.is_no_looser_than(&pool.fee_policy_bounds()) {
+        msg!("Error: loosening fee-policy bounds requires ProposeFeeChange / CommitFeeChange");
+        return Err(StakeError::FeeChangeRequiresTimelock.into());
+    }
+
+    pool.set_fee_policy_bounds(bounds);
+    msg!(
+        "SetFeePolicyBounds: min_lp_share={} max_trade_fee={} max_delta={} min_interval={}",
+        bounds.min_lp_share_bps,
+        bounds.max_trade_fee_base_bps,
+        bounds.max_change_delta_bps,
+        bounds.min_change_interval_slots
+    );
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// 43/44/45: CastVeto / ReclaimVeto / SetVetoThreshold
+// ═══════════════════════════════════════════════════════════════
+// The timelocks give LPs a window to exit; the veto gives them a way to stop
+// the change instead. LP is locked by moving the tokens into one pool-wide
+// escrow (authority = vault_auth), so the weight of a vote is a token count:
+// senior and junior LP share the mint, and a token in escrow cannot also sit
+// in a wallet, another vote, or a tranche's LP ledger twice. `total_lp_supply`
+// — both tranches — is the denominator. The LP stays in escrow until its
+// proposal stops being pending, however that happens.
+
+/// Whether `locked_lp` is strictly more than `threshold_bps` of
+/// `total_lp_supply`. A threshold of 0 (veto disabled) is never reached.
+pub fn veto_threshold_exceeded(locked_lp: u64, total_lp_supply: u64, threshold_bps: u16) -> bool {
+    threshold_bps != 0
+        && (locked_lp as u128) * 10_000 > (threshold_bps as u128) * (total_lp_supply as u128)
+}
+
+/// How much LP a veto needs, as an orderable quantity: lower is stronger, and
+/// a disabled veto (0) is the weakest of all.
+fn veto_threshold_rank(threshold_bps: u16) -> u32 {
+    if threshold_bps == 0 {
+        u32::MAX
+    } else {
+        u32::from(threshold_bps)
+    }
+}
+
+// ── 43: CastVeto ──
+//
+// Accounts:
+//   0. `[signer, writable]` Voter (LP owner; pays rent)
+//   1. `[writable]` Pool PDA
+//   2. `[writable]` Voter's LP token account (source)
+//   3. `[writable]` Veto escrow (created on first use)
+//   4. `[writable]` Vote record PDA
+//   5. `[]` LP mint
+//   6. `[]` Vault authority PDA
+//   7. `[]` Token program
+//   8. `[]` System program
+//   9. `[]` Rent sysvar
+//  10. `[writable]` Proposal PDA (VETO_TARGET_FEE_CHANGE only)
+fn process_cast_veto(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    target_kind: u8,
+    lp_amount: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let voter = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    let voter_lp = next_account_info(accounts_iter)?;
+    let escrow = next_account_info(accounts_iter)?;
+    let vote_pda = next_account_info(accounts_iter)?;
+    let lp_mint = next_account_info(accounts_iter)?;
+    let vault_auth = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let rent_sysvar = next_account_info(accounts_iter)?;
+    let proposal_pda = if target_kind == VETO_TARGET_FEE_CHANGE {
+        Some(next_account_info(accounts_iter)?)
+    } else {
+        None
+    };
+
+    if !voter.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if lp_amount == 0 {
+        return Err(StakeError::ZeroAmount.into());
+    }
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_account_writable(pool_pda)?;
+
+    let target_nonce = {
+        let pool_data = pool_pda.try_borrow_data()?;
+        let pool = pool_from_data(&pool_data[..])?;
+        if pool.is_initialized != 1 {
+            return Err(StakeError::NotInitialized.into());
+        }
+        if !pool.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        validate_pool_version(pool)?;
+        if pool.veto_threshold_bps == 0 {
+            return Err(StakeError::VetoDisabled.into());
+        }
+        if *lp_mint.key != pool.lp_mint_pubkey() {
+            return Err(StakeError::InvalidMint.into());
+        }
+        match (target_kind, proposal_pda) {
+            (VETO_TARGET_FEE_CHANGE, Some(proposal_pda)) => {
+                validate_pending_proposal(program_id, proposal_pda, pool_pda.key)?;
+                let mut proposal_data = proposal_pda.try_borrow_mut_data()?;
+                proposal_from_data_mut(&mut proposal_data[..])?.proposal_id
+            }
+            (VETO_TARGET_COOLDOWN_INCREASE, _) => pool
+                .pending_proposal_nonce(target_kind, Clock::get()?.slot)
+                .ok_or(StakeError::NoPendingCooldownProposal)?,
+            (VETO_TARGET_ADMIN, _) => pool
+                .pending_proposal_nonce(target_kind, Clock::get()?.slot)
+                .ok_or(StakeError::NoPendingAdmin)?,
+            _ => return Err(ProgramError::InvalidInstructionData),
+        }
+    };
+
+    let (expected_vault_auth, _) = derive_vault_authority(program_id, pool_pda.key);
+    if *vault_auth.key != expected_vault_auth {
+        return Err(StakeError::InvalidPda.into());
+    }
+    verify_token_program(token_program)?;
+
+    // The escrow is a program-derived LP token account, created on first use.
+    let (expected_escrow, escrow_bump) = state::derive_veto_escrow(program_id, pool_pda.key);
+    if *escrow.key != expected_escrow {
+        return Err(StakeError::InvalidPda.into());
+    }
+    validate_account_writable(escrow)?;
+    if escrow.data_is_empty() {
+        let _ = Rent::from_account_info(rent_sysvar)?;
+        let escrow_seeds: &[&[u8]] = &[b"veto_escrow", pool_pda.key.as_ref(), &[escrow_bump]];
+        create_or_adopt_pda(
+            escrow,
+            voter,
+            system_program,
+            token_program.key,
+            crate::spl_token::state::ACCOUNT_LEN,
+            escrow_seeds,
+        )?;
+        invoke(
+            &crate::spl_token::initialize_account(
+                token_program.key,
+                escrow.key,
+                lp_mint.key,
+                vault_auth.key,
+            )?,
+            &[
+                escrow.clone(),
+                lp_mint.clone(),
+                vault_auth.clone(),
+                rent_sysvar.clone(),
+            ],
+        )?;
+    } else {
+        validate_account_owner(escrow, token_program.key)?;
+    }
+
+    // One record per (voter, target instance); a repeat vote tops it up.
+    let (expected_vote, vote_bump) = state::derive_veto_vote_pda(
+        program_id,
+        pool_pda.key,
+        voter.key,
+        target_kind,
+        target_nonce,
+    );
+    if *vote_pda.key != expected_vote {
+        return Err(StakeError::InvalidPda.into());
+    }
+    validate_account_writable(vote_pda)?;
+    let fresh_vote = vote_pda.data_is_empty();
+    if fresh_vote {
+        let nonce_bytes = target_nonce.to_le_bytes();
+        let vote_seeds: &[&[u8]] = &[
+            b"stake_veto",
+            pool_pda.key.as_ref(),
+            voter.key.as_ref(),
+            &[target_kind],
+            &nonce_bytes,
+            &[vote_bump],
+        ];
+        create_or_adopt_pda(
+            vote_pda,
+            voter,
+            system_program,
+            program_id,
+            STAKE_VETO_VOTE_SIZE,
+            vote_seeds,
+        )?;
+    } else {
+        validate_account_owner(vote_pda, program_id)?;
+    }
+    {
+        let mut vote_data = vote_pda.try_borrow_mut_data()?;
+        let vote = veto_vote_from_data_mut(&mut vote_data[..])?;
+        if fresh_vote {
+            vote.set_discriminator();
+            vote.is_initialized = 1;
+            vote.bump = vote_bump;
+            vote.target_kind = target_kind;
+            vote.pool = pool_pda.key.to_bytes();
+            vote.voter = voter.key.to_bytes();
+            vote.target_nonce = target_nonce;
+        } else if vote.is_initialized != 1 || !vote.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        vote.locked_lp = vote
+            .locked_lp
+            .checked_add(lp_amount)
+            .ok_or(StakeError::Overflow)?;
+    }
+
+    invoke(
+        &crate::spl_token::transfer(
+            token_program.key,
+            voter_lp.key,
+            escrow.key,
+            voter.key,
+            &[],
+            lp_amount,
+        )?,
+        &[
+            voter_lp.clone(),
+            escrow.clone(),
+            voter.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    let mut pool_data = pool_pda.try_borrow_mut_data()?;
+    let pool = pool_from_data_mut(&mut pool_data[..])?;
+    let (total_lp, threshold) = (pool.total_lp_supply, pool.veto_threshold_bps);
+    let vetoed = match proposal_pda {
+        Some(proposal_pda) => {
+            let mut proposal_data = proposal_pda.try_borrow_mut_data()?;
+            let proposal = proposal_from_data_mut(&mut proposal_data[..])?;
+            let tally = proposal
+                .veto_lp()
+                .checked_add(lp_amount)
+                .ok_or(StakeError::Overflow)?;
+            proposal.set_veto_lp(tally);
+            let vetoed = veto_threshold_exceeded(tally, total_lp, threshold);
+            if vetoed {
+                proposal.status = PROPOSAL_STATUS_CANCELLED;
+            }
+            vetoed
+        }
+        None if target_kind == VETO_TARGET_COOLDOWN_INCREASE => {
+            pool.cooldown_veto_lp = pool
+                .cooldown_veto_lp
+                .checked_add(lp_amount)
+                .ok_or(StakeError::Overflow)?;
+            let vetoed = veto_threshold_exceeded(pool.cooldown_veto_lp, total_lp, threshold);
+            if vetoed {
+                pool.set_pending_cooldown_slots(0);
+                pool.set_cooldown_proposed_at_slot(0);
+            }
+            vetoed
+        }
+        None => {
+            pool.admin_veto_lp = pool
+                .admin_veto_lp
+                .checked_add(lp_amount)
+                .ok_or(StakeError::Overflow)?;
+            let vetoed = veto_threshold_exceeded(pool.admin_veto_lp, total_lp, threshold);
+            if vetoed {
+                pool.pending_admin = [0u8; 32];
+                pool.admin_proposed_at_slot = 0;
+            }
+            vetoed
+        }
+    };
+
+    if vetoed {
+        msg!(
+            "CastVeto: veto threshold exceeded; proposal (kind {}, nonce {}) cancelled",
+            target_kind,
+            target_nonce
+        );
+    } else {
+        msg!("CastVeto: {} LP locked", lp_amount);
+    }
+    Ok(())
+}
+
+// ── 44: ReclaimVeto ──
+//
+// Accounts:
+//   0. `[signer, writable]` Voter (receives the record's rent)
+//   1. `[]` Pool PDA (live, or closed by SweepAndClosePool)
+//   2. `[writable]` LP token account to receive the LP
+//   3. `[writable]` Veto escrow
+//   4. `[writable]` Vote record PDA
+//   5. `[]` Vault authority PDA
+//   6. `[]` Token program
+//   7. `[]` Proposal PDA (VETO_TARGET_FEE_CHANGE on a live pool only)
+fn process_reclaim_veto(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let voter = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    let dest_lp = next_account_info(accounts_iter)?;
+    let escrow = next_account_info(accounts_iter)?;
+    let vote_pda = next_account_info(accounts_iter)?;
+    let vault_auth = next_account_info(accounts_iter)?;
+    let token_program = next_account_info(accounts_iter)?;
+
+    if !voter.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let pool_closed = pool_is_closed(pool_pda);
+    if !pool_closed {
+        validate_account_owner(pool_pda, program_id)?;
+        validate_account_not_empty(pool_pda)?;
+    }
+    validate_account_owner(vote_pda, program_id)?;
+    validate_account_not_empty(vote_pda)?;
+    validate_account_writable(vote_pda)?;
+
+    let (target_kind, target_nonce, locked_lp) = {
+        let mut vote_data = vote_pda.try_borrow_mut_data()?;
+        let vote = veto_vote_from_data_mut(&mut vote_data[..])?;
+        if vote.is_initialized != 1 || !vote.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        if vote.pool != pool_pda.key.to_bytes() {
+            return Err(StakeError::InvalidPda.into());
+        }
+        if vote.voter != voter.key.to_bytes() {
+            return Err(StakeError::Unauthorized.into());
+        }
+        (vote.target_kind, vote.target_nonce, vote.locked_lp)
+    };
+
+    // A closed pool has nothing left to veto: whatever was pending died with it.
+    let still_pending = if pool_closed {
+        false
+    } else if target_kind == VETO_TARGET_FEE_CHANGE {
+        let proposal_pda = next_account_info(accounts_iter)?;
+        let (expected_proposal, _) =
+            state::derive_proposal_pda(program_id, pool_pda.key, target_nonce);
+        if *proposal_pda.key != expected_proposal {
+            return Err(StakeError::InvalidPda.into());
+        }
+        validate_account_owner(proposal_pda, program_id)?;
+        let proposal_data = proposal_pda.try_borrow_data()?;
+        if proposal_data.len() < STAKE_PROPOSAL_SIZE {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        let proposal: &StakeProposal =
+            bytemuck::try_from_bytes(&proposal_data[..STAKE_PROPOSAL_SIZE])
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+        proposal.is_pending()
+    } else {
+        let pool_data = pool_pda.try_borrow_data()?;
+        let pool = pool_from_data(&pool_data[..])?;
+        if !pool.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        pool.pending_proposal_nonce(target_kind, Clock::get()?.slot) == Some(target_nonce)
+    };
+    if still_pending {
+        return Err(StakeError::VetoStillLocked.into());
+    }
+
+    let (expected_escrow, _) = state::derive_veto_escrow(program_id, pool_pda.key);
+    if *escrow.key != expected_escrow {
+        return Err(StakeError::InvalidPda.into());
+    }
+    let (expected_vault_auth, vault_auth_bump) = derive_vault_authority(program_id, pool_pda.key);
+    if *vault_auth.key != expected_vault_auth {
+        return Err(StakeError::InvalidPda.into());
+    }
+    verify_token_program(token_program)?;
+
+    let vault_auth_seeds: &[&[u8]] = &[b"vault_auth", pool_pda.key.as_ref(), &[vault_auth_bump]];
+    invoke_signed(
+        &crate::spl_token::transfer(
+            token_program.key,
+            escrow.key,
+            dest_lp.key,
+            vault_auth.key,
+            &[],
+            locked_lp,
+        )?,
+        &[
+            escrow.clone(),
+            dest_lp.clone(),
+            vault_auth.clone(),
+            token_program.clone(),
+        ],
+        &[vault_auth_seeds],
+    )?;
+    close_pda(vote_pda, voter)?;
+
+    msg!("ReclaimVeto: {} LP returned", locked_lp);
+    Ok(())
+}
+
+// ── 45: SetVetoThreshold ──
+//
+// Accounts:
+//   0. `[signer]` Admin
+//   1. `[writable]` Pool PDA
+//
+// `via_timelock` as for SetFeePolicyBounds: a direct call may only make the
+// veto easier to reach.
+fn process_set_veto_threshold(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    threshold_bps: u16,
+    via_timelock: bool,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    if threshold_bps > 10_000 {
+        msg!(
+            "Invalid veto threshold: {} bps exceeds 10_000",
+            threshold_bps
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_account_writable(pool_pda)?;
+    let mut pool_data = pool_pda.try_borrow_mut_data()?;
+    let pool = pool_from_data_mut(&mut pool_data[..])?;
+    if pool.is_initialized != 1 {
+        return Err(StakeError::NotInitialized.into());
+    }
+    if !pool.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    validate_pool_version(pool)?;
+    authorize_admin(program_id, pool, admin, accounts)?;
+    if !via_timelock
+        && veto_threshold_rank(threshold_bps) > veto_threshold_rank(pool.veto_threshold_bps)
+    {
+        msg!("Error: weakening the LP veto requires ProposeFeeChange / CommitFeeChange");
+        return Err(StakeError::FeeChangeRequiresTimelock.into());
+    }
+
+    pool.veto_threshold_bps = threshold_bps;
+    msg!("SetVetoThreshold: {} bps", threshold_bps);
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// 46/47: ProposeCouncil / AcceptCouncil
+// ═══════════════════════════════════════════════════════════════
+// The council is an alternative admin authority, not a replacement: while
+// `pool.council` is set, `authorize_admin` accepts either the admin's
+// signature or a threshold of the active roster's. Roster changes follow the
+// ProposeAdmin / AcceptAdmin shape so a typo'd key or an unreachable threshold
+// can never be installed: the NEW roster has to sign for it.
+
+// ── 46: ProposeCouncil ──
+//
+// Accounts:
+//   0. `[signer]` Admin, or `[]` the active council PDA
+//   1. `[]` Pool PDA
+//   2. `[writable]` Council PDA (created on first use)
+//   3. `[signer, writable]` Payer
+//   4. `[]` System program
+fn process_propose_council(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    threshold: u8,
+    members: &[[u8; 32]],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    let council_pda = next_account_info(accounts_iter)?;
+    let payer = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !state::is_valid_council_roster(threshold, members) {
+        msg!("Invalid council roster: need 1 <= threshold <= members, distinct non-zero keys");
+        return Err(ProgramError::InvalidArgument);
+    }
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    {
+        let pool_data = pool_pda.try_borrow_data()?;
+        let pool = pool_from_data(&pool_data[..])?;
+        if pool.is_initialized != 1 {
+            return Err(StakeError::NotInitialized.into());
+        }
+        if !pool.validate_discriminator() {
+            return Err(StakeError::InvalidAccount.into());
+        }
+        validate_pool_version(pool)?;
+        authorize_admin(program_id, pool, admin, accounts)?;
+    }
+
+    let (expected_council, council_bump) = state::derive_council_pda(program_id, pool_pda.key);
+    if *council_pda.key != expected_council {
+        return Err(StakeError::InvalidPda.into());
+    }
+    validate_account_writable(council_pda)?;
+    let fresh = council_pda.data_is_empty();
+    if fresh {
+        if !payer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let council_seeds: &[&[u8]] = &[b"stake_council", pool_pda.key.as_ref(), &[council_bump]];
+        create_or_adopt_pda(
+            council_pda,
+            payer,
+            system_program,
+            program_id,
+            STAKE_COUNCIL_SIZE,
+            council_seeds,
+        )?;
+    } else {
+        validate_account_owner(council_pda, program_id)?;
+    }
+
+    let mut council_data = council_pda.try_borrow_mut_data()?;
+    let council = council_from_data_mut(&mut council_data[..])?;
+    if fresh {
+        council.set_discriminator();
+        council.is_initialized = 1;
+        council.bump = council_bump;
+        council.pool = pool_pda.key.to_bytes();
+    } else if council.is_initialized != 1 || !council.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    council.has_pending = 1;
+    council.pending_threshold = threshold;
+    council.pending_member_count = members.len() as u8;
+    council.pending_members = [[0u8; 32]; state::MAX_COUNCIL_MEMBERS];
+    council.pending_members[..members.len()].copy_from_slice(members);
+
+    if members.is_empty() {
+        msg!("ProposeCouncil: dissolution pending; the pool admin must accept");
+    } else {
+        msg!(
+            "ProposeCouncil: {}-of-{} roster pending acceptance",
+            threshold,
+            members.len()
+        );
+    }
+    Ok(())
+}
+
+// ── 47: AcceptCouncil ──
+//
+// Accounts:
+//   0. `[writable]` Pool PDA
+//   1. `[writable]` Council PDA
+//   2. `[signer]` Pending members, one account each (for a dissolution, the
+//      pool admin)
+fn process_accept_council(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let pool_pda = next_account_info(accounts_iter)?;
+    let council_pda = next_account_info(accounts_iter)?;
+
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_account_writable(pool_pda)?;
+    validate_account_owner(council_pda, program_id)?;
+    validate_account_not_empty(council_pda)?;
+    validate_account_writable(council_pda)?;
+
+    let mut pool_data = pool_pda.try_borrow_mut_data()?;
+    let pool = pool_from_data_mut(&mut pool_data[..])?;
+    if pool.is_initialized != 1 {
+        return Err(StakeError::NotInitialized.into());
+    }
+    if !pool.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    validate_pool_version(pool)?;
+
+    let mut council_data = council_pda.try_borrow_mut_data()?;
+    let council = council_from_data_mut(&mut council_data[..])?;
+    if council.is_initialized != 1 || !council.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    if council.pool != pool_pda.key.to_bytes() {
+        return Err(StakeError::InvalidPda.into());
+    }
+    if council.has_pending != 1 {
+        return Err(StakeError::NoPendingCouncil.into());
+    }
+
+    if council.pending_member_count == 0 {
+        // Dissolution leaves the admin as the only authority, so it is the
+        // admin who accepts it.
+        let admin_signed = pool.admin != [0u8; 32]
+            && accounts
+                .iter()
+                .any(|a| a.is_signer && a.key.to_bytes() == pool.admin);
+        if !admin_signed {
+            return Err(StakeError::Unauthorized.into());
+        }
+        council.threshold = 0;
+        council.member_count = 0;
+        council.members = [[0u8; 32]; state::MAX_COUNCIL_MEMBERS];
+        pool.council = [0u8; 32];
+        msg!("AcceptCouncil: council dissolved");
+    } else {
+        let signers = accounts.iter().filter(|a| a.is_signer).map(|a| a.key);
+        if state::count_council_signers(council.pending_roster(), signers)
+            < council.pending_threshold as usize
+        {
+            msg!(
+                "Error: pending council threshold {} not met",
+                council.pending_threshold
+            );
+            return Err(StakeError::CouncilThresholdNotMet.into());
+        }
+        council.threshold = council.pending_threshold;
+        council.member_count = council.pending_member_count;
+        council.members = council.pending_members;
+        pool.council = council_pda.key.to_bytes();
+        msg!(
+            "AcceptCouncil: {}-of-{} council active",
+            council.threshold,
+            council.member_count
+        );
+    }
+    council.has_pending = 0;
+    council.pending_threshold = 0;
+    council.pending_member_count = 0;
+    council.pending_members = [[0u8; 32]; state::MAX_COUNCIL_MEMBERS];
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// 48/49: ProposeRole / AcceptRole
+// ═══════════════════════════════════════════════════════════════
+// Role rotation is the ProposeAdmin / AcceptAdmin idiom per role: the admin
+// authority proposes, the new holder has to act to take the role over, so a
+// role can never be handed to a key nobody controls. Revoking needs no
+// acceptance — it only hands the role back to the admin authority.
+
+// ── 48: ProposeRole ──
+//
+// Accounts:
+//   0. `[signer]` Admin, or `[]` the active council PDA
+//   1. `[writable]` Pool PDA
+fn process_propose_role(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    role: u8,
+    holder: [u8; 32],
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    if role as usize >= state::ROLE_COUNT {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_account_writable(pool_pda)?;
+    let mut pool_data = pool_pda.try_borrow_mut_data()?;
+    let pool = pool_from_data_mut(&mut pool_data[..])?;
+    if pool.is_initialized != 1 {
+        return Err(StakeError::NotInitialized.into());
+    }
+    if !pool.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    validate_pool_version(pool)?;
+    authorize_admin(program_id, pool, admin, accounts)?;
+
+    let idx = role as usize;
+    if holder == pool.roles[idx] {
+        pool.pending_roles[idx] = [0u8; 32];
+        msg!("ProposeRole: pending rotation of role {} cancelled", role);
+    } else if holder == [0u8; 32] {
+        pool.roles[idx] = [0u8; 32];
+        pool.pending_roles[idx] = [0u8; 32];
+        msg!("ProposeRole: role {} revoked to the admin", role);
+    } else {
+        pool.pending_roles[idx] = holder;
+        msg!(
+            "ProposeRole: new holder of role {} proposed (awaiting AcceptRole)",
+            role
+        );
+    }
+    Ok(())
+}
+
+// ── 49: AcceptRole ──
+//
+// Accounts:
+//   0. `[signer]` Pending holder, or `[]` the council PDA (members sign)
+//   1. `[writable]` Pool PDA
+fn process_accept_role(program_id: &Pubkey, accounts: &[AccountInfo], role: u8) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let new_holder = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    if role as usize >= state::ROLE_COUNT {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_account_writable(pool_pda)?;
+    let mut pool_data = pool_pda.try_borrow_mut_data()?;
+    let pool = pool_from_data_mut(&mut pool_data[..])?;
+    if pool.is_initialized != 1 {
+        return Err(StakeError::NotInitialized.into());
+    }
+    if !pool.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    validate_pool_version(pool)?;
+
+    let idx = role as usize;
+    let pending = pool.pending_roles[idx];
+    if pending == [0u8; 32] {
+        return Err(StakeError::NoPendingRole.into());
+    }
+    authorize_key(program_id, pool, pending, new_holder, accounts)?;
+
+    pool.roles[idx] = pending;
+    pool.pending_roles[idx] = [0u8; 32];
+    msg!("AcceptRole: role {} rotation complete", role);
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// 50-52: SetFlushPolicy / LockConfig / RenounceAdmin
+// ═══════════════════════════════════════════════════════════════
+// The path to a pool no key can change: the admin sets a flush policy, locks
+// it together with the HWM config and deposit cap, and then
+// renounces. What is left runs on the permissionless instructions — fee
+// accrual, policy-bounded flushes and recovery of flushed insurance.
+
+// ── 50: SetFlushPolicy ──
+//
+// Accounts:
+//   0. `[signer]` Admin
+//   1. `[writable]` Pool PDA
+fn process_set_flush_policy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    max_flush_bps: u16,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_account_writable(pool_pda)?;
+    let mut pool_data = pool_pda.try_borrow_mut_data()?;
+    let pool = pool_from_data_mut(&mut pool_data[..])?;
+    if pool.is_initialized != 1 {
+        return Err(StakeError::NotInitialized.into());
+    }
+    if !pool.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    validate_pool_version(pool)?;
+    authorize_admin(program_id, pool, admin, accounts)?;
+    if pool.is_config_locked(CONFIG_LOCK_FLUSH_POLICY) {
+        return Err(StakeError::ConfigLocked.into());
+    }
+    if max_flush_bps > 10_000 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    pool.flush_policy_max_bps = max_flush_bps;
+    msg!("SetFlushPolicy: max_flush_bps={}", max_flush_bps);
+    Ok(())
+}
+
+// ── 51: LockConfig ──
+//
+// Accounts:
+//   0. `[signer]` Admin, or `[]` the active council PDA
+//   1. `[writable]` Pool PDA
+fn process_lock_config(program_id: &Pubkey, accounts: &[AccountInfo], locks: u8) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    if locks == 0 || locks & !CONFIG_LOCK_ALL != 0 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_account_writable(pool_pda)?;
+    let mut pool_data = pool_pda.try_borrow_mut_data()?;
+    let pool = pool_from_data_mut(&mut pool_data[..])?;
+    if pool.is_initialized != 1 {
+        return Err(StakeError::NotInitialized.into());
+    }
+    if !pool.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    validate_pool_version(pool)?;
+    authorize_admin(program_id, pool, admin, accounts)?;
+
+    pool.config_locks |= locks;
+    msg!("LockConfig: locks now {:#04x}", pool.config_locks);
+    Ok(())
+}
+
+// ── 52: RenounceAdmin ──
+//
+// Accounts:
+//   0. `[signer]` Admin, or `[]` the active council PDA
+//   1. `[writable]` Pool PDA
+fn process_renounce_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let admin = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_account_writable(pool_pda)?;
+    let mut pool_data = pool_pda.try_borrow_mut_data()?;
+    let pool = pool_from_data_mut(&mut pool_data[..])?;
+    if pool.is_initialized != 1 {
+        return Err(StakeError::NotInitialized.into());
+    }
+    if !pool.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    validate_pool_version(pool)?;
+    authorize_admin(program_id, pool, admin, accounts)?;
+    if !pool.is_config_locked(CONFIG_LOCK_ALL) {
+        msg!(
+            "Error: RenounceAdmin needs every config lock (have {:#04x})",
+            pool.config_locks
+        );
+        return Err(StakeError::ConfigNotLocked.into());
+    }
+
+    // With admin, council and every role zeroed, `authorize_role` /
+    // `authorize_admin` have no key left to accept.
+    pool.admin = [0u8; 32];
+    pool.council = [0u8; 32];
+    pool.roles = [[0u8; 32]; state::ROLE_COUNT];
+    pool.pending_roles = [[0u8; 32]; state::ROLE_COUNT];
+    pool.pending_admin = [0u8; 32];
+    pool.admin_proposed_at_slot = 0;
+    pool.set_pending_cooldown_slots(0);
+    pool.set_cooldown_proposed_at_slot(0);
+
+    msg!("RenounceAdmin: pool admin renounced permanently");
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// 53: AssertPoolInvariants — permissionless, read-only
+// ═══════════════════════════════════════════════════════════════
+// Checks the pool's books against the balances actually on chain (see
+// `accounting::check_pool_invariants`) and fails with the matching
+// `Invariant*` error. It writes nothing, so keepers can append it to any
+// transaction: if an earlier instruction in the bundle broke an invariant,
+// the whole bundle reverts.
+//
+// The wrapper market is optional. When passed it must be the pool's slab, and
+// the insurance balance is read from the pool's asset slot through the pool's
+// `WrapperAbi` after the same probe every wrapper CPI runs. A v16 pool has no
+// readable slot, so passing its market fails with `WrapperAbiUnsupported`.
+//
+// Accounts:
+//   0. `[]` Pool PDA
+//   1. `[]` Pool vault (must equal pool.vault)
+//   2. `[]` LP mint (must equal pool.lp_mint)
+//   3. `[]` (optional) Wrapper market (must equal pool.slab)
+fn process_assert_pool_invariants(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let pool_pda = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    let lp_mint = next_account_info(accounts_iter)?;
+    let market = next_account_info(accounts_iter).ok();
+
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    let pool_data = pool_pda.try_borrow_data()?;
+    let pool = pool_from_data(&pool_data[..])?;
+    if pool.is_initialized != 1 {
+        return Err(StakeError::NotInitialized.into());
+    }
+    if !pool.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    validate_pool_version(pool)?;
+
+    if pool.vault != vault.key.to_bytes() {
+        return Err(StakeError::InvalidPda.into());
+    }
+    if pool.lp_mint != lp_mint.key.to_bytes() {
+        return Err(StakeError::InvalidMint.into());
+    }
+    let vault_balance = vault_token_balance(vault)?;
+    if *lp_mint.owner != crate::spl_token::id() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    let lp_mint_supply = crate::spl_token::state::Mint::unpack(&lp_mint.try_borrow_data()?)?.supply;
+    let wrapper_insurance_balance = match market {
+        Some(market) => {
+            if pool.slab != market.key.to_bytes() {
+                return Err(StakeError::InvalidPda.into());
+            }
+            let abi = cpi::pool_wrapper_abi(pool)?;
+            let percolator_program = Pubkey::new_from_array(pool.percolator_program);
+            cpi::probe_market_asset(abi, &percolator_program, market, pool.wrapper_asset_index)?;
+            Some(abi.asset_insurance_balance(&market.try_borrow_data()?, pool.wrapper_asset_index)?)
+        }
+        None => None,
+    };
+
+    if let Err(e) = accounting::check_pool_invariants(
+        pool,
+        vault_balance,
+        lp_mint_supply,
+        wrapper_insurance_balance,
+    ) {
+        msg!(
+            "AssertPoolInvariants: {:?} (vault={} pool_value={:?} lp_supply={} total_lp_supply={} junior={} wrapper_insurance={:?} wrapper_recoverable={})",
+            e,
+            vault_balance,
+            pool.total_pool_value(),
+            lp_mint_supply,
+            pool.total_lp_supply,
+            pool.effective_junior_balance(),
+            wrapper_insurance_balance,
+            pool.wrapper_recoverable()
+        );
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// 54: DeclareDeficit — permissionless
+// ═══════════════════════════════════════════════════════════════
+// Enters deficit mode when the vault holds less than `total_pool_value()`
+// (wrapper misbehaviour, a token fee, a bug): the shortfall is written off the
+// books, junior first (`accounting::write_down_deficit`). From then on
+// deposits and flushes are closed, withdrawals skip the HWM floor, and every
+// exit is priced against what the vault really holds. Deposit and withdraw
+// apply the same write-down when they observe a shortfall; this makes it
+// stick without anyone having to trade. Later shortfalls can be declared
+// again. A ledger whose `total_pool_value()` cannot be represented is rebuilt
+// on the vault balance the same way, and the call succeeds even when nothing
+// ends up short: it is what unbricks the pool. A vault that recovers shows up as a surplus, which `AccrueFees` and
+// the pricing paths apply to the write-off first (`accounting::restore_deficit`);
+// the pool leaves deficit mode once the whole write-off is back.
+//
+// Accounts:
+//   0. `[signer]` Caller (permissionless)
+//   1. `[writable]` Pool PDA
+//   2. `[]` Pool vault (must equal pool.vault)
+fn process_declare_deficit(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let caller = next_account_info(accounts_iter)?;
+    let pool_pda = next_account_info(accounts_iter)?;
+    let vault = next_account_info(accounts_iter)?;
+    if !caller.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    validate_account_writable(pool_pda)?;
+    let mut pool_data = pool_pda.try_borrow_mut_data()?;
+    let pool = pool_from_data_mut(&mut pool_data[..])?;
+    if pool.is_initialized != 1 {
+        return Err(StakeError::NotInitialized.into());
+    }
+    if !pool.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    validate_pool_version(pool)?;
+    if pool.vault != vault.key.to_bytes() {
+        return Err(StakeError::InvalidPda.into());
+    }
+
+    let vault_balance = vault_token_balance(vault)?;
+    let unrepresentable = pool.total_pool_value().is_none();
+    let shortfall = accounting::write_down_deficit(pool, vault_balance)?;
+    if shortfall == 0 && !unrepresentable {
+        return Err(StakeError::NoDeficit.into());
+    }
+    msg!(
+        "DeclareDeficit: vault {} was {} short{} (written off {}, junior_balance={}, deficit_mode={})",
+        vault_balance,
+        shortfall,
+        if unrepresentable {
+            "; unrepresentable ledger rebuilt"
+        } else {
+            ""
+        },
+        pool.deficit_written_off,
+        pool.junior_balance(),
+        pool.deficit_mode
+    );
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════
+// 55: ProbeWrapper — permissionless, read-only
+// ═══════════════════════════════════════════════════════════════
+// Runs `cpi::probe_market_asset` — the check every wrapper CPI makes before it
+// is issued — on its own. A deployer pairing a stake build with a wrapper
+// release, or a keeper before its first flush, learns that the market is the
+// wrong program's, the wrong revision's size, or never initialized from a
+// stake error instead of an opaque wrapper revert. Writes nothing.
+//
+// Accounts:
+//   0. `[]` Pool PDA
+//   1. `[]` Market/slab (must equal pool.slab)
+fn process_probe_wrapper(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let pool_pda = next_account_info(accounts_iter)?;
+    let slab = next_account_info(accounts_iter)?;
+
+    validate_account_owner(pool_pda, program_id)?;
+    validate_account_not_empty(pool_pda)?;
+    let pool_data = pool_pda.try_borrow_data()?;
+    let pool = pool_from_data(&pool_data[..])?;
+    if pool.is_initialized != 1 {
+        return Err(StakeError::NotInitialized.into());
+    }
+    if !pool.validate_discriminator() {
+        return Err(StakeError::InvalidAccount.into());
+    }
+    validate_pool_version(pool)?;
+    if pool.slab != slab.key.to_bytes() {
+        return Err(StakeError::InvalidPda.into());
+    }
+
+    let abi = cpi::pool_wrapper_abi(pool)?;
+    let percolator_program = Pubkey::new_from_array(pool.percolator_program);
+    cpi::probe_market_asset(abi, &percolator_program, slab, pool.wrapper_asset_index)?;
+    msg!(
+        "ProbeWrapper: market {} ok for wrapper v{} asset {} ({} bytes)",
+        slab.key,
+        abi.version(),
+        pool.wrapper_asset_index,
+        slab.data_len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    // ── N7 (CONSOLIDATED-PLAN §2.2): apply_minimum_liquidity_lock ───────────
+
+    #[test]
+    fn n7_genesis_deposit_above_minimum_liquidity_carves_out_dead_shares() {
+        let lp_to_mint = state::MINIMUM_LIQUIDITY + 1;
+        let mint_amount = apply_minimum_liquidity_lock(0, lp_to_mint).unwrap();
+        assert_eq!(
+            mint_amount, 1,
+            "genesis mint = lp_to_mint - MINIMUM_LIQUIDITY"
+        );
+    }
+
+    #[test]
+    fn n7_genesis_deposit_at_exactly_minimum_liquidity_rejected() {
+        // mint_amount would be exactly 0 — reject rather than mint 0 real LP
+        // while still transferring the depositor's collateral in.
+        let err = apply_minimum_liquidity_lock(0, state::MINIMUM_LIQUIDITY).unwrap_err();
+        assert_eq!(err, StakeError::DepositBelowMinimumLiquidity.into());
+    }
+
+    #[test]
+    fn n7_genesis_deposit_below_minimum_liquidity_rejected() {
+        let err = apply_minimum_liquidity_lock(0, state::MINIMUM_LIQUIDITY - 1).unwrap_err();
+        assert_eq!(err, StakeError::DepositBelowMinimumLiquidity.into());
+    }
+
+    #[test]
+    fn n7_genesis_deposit_of_zero_lp_rejected_not_underflow_panic() {
+        // Defense-in-depth: lp_to_mint=0 shouldn't reach this helper in production
+        // (S-4's ZeroSharesMinted check runs first), but the helper itself must not
+        // panic on checked_sub underflow if it ever did.
+        let err = apply_minimum_liquidity_lock(0, 0).unwrap_err();
+        assert_eq!(err, StakeError::DepositBelowMinimumLiquidity.into());
+    }
+
+    #[test]
+    fn n7_non_genesis_deposit_mints_full_amount_unchanged() {
+        // total_lp_supply_before != 0 -> not genesis -> pass through unchanged,
+        // even for amounts far below MINIMUM_LIQUIDITY (only the pool's FIRST
+        // deposit ever pays the dead-share floor).
+        assert_eq!(apply_minimum_liquidity_lock(1, 1).unwrap(), 1);
+        assert_eq!(apply_minimum_liquidity_lock(1_000_000, 5).unwrap(), 5);
+        assert_eq!(
+            apply_minimum_liquidity_lock(42, state::MINIMUM_LIQUIDITY).unwrap(),
+            state::MINIMUM_LIQUIDITY
+        );
+    }
+
+    /// MUTATION CHECK (requested by the coordinator's verification-pass addendum):
+    /// if the dead-share carve-out were removed (i.e. apply_minimum_liquidity_lock
+    /// degenerated to `Ok(lp_to_mint)` unconditionally, matching pre-N7 behavior),
+    /// this harness's own invariant — genesis mint_amount < lp_to_mint whenever
+    /// MINIMUM_LIQUIDITY > 0 — would need to fail. We assert the CURRENT (fixed)
+    /// behavior directly against that mutated baseline to make the regression
+    /// this test would catch explicit and auditable, without needing to actually
+    /// revert the source: the pre-N7 formula computed here inline is exactly
+    /// `Ok(lp_to_mint)`, and we assert our real function's output is STRICTLY LESS
+    /// than that baseline at genesis — i.e. the dead-share floor is actually being
+    /// deducted, not a no-op.
+    #[test]
+    fn n7_mutation_check_dead_share_floor_is_strictly_deducted_at_genesis() {
+        let lp_to_mint = state::MINIMUM_LIQUIDITY + 12_345;
+        let pre_n7_baseline_mint_amount = lp_to_mint; // what `Ok(lp_to_mint)` (the mutant) would return
+        let actual_mint_amount = apply_minimum_liquidity_lock(0, lp_to_mint).unwrap();
+        assert!(
+            actual_mint_amount < pre_n7_baseline_mint_amount,
+            "N7 REGRESSION: genesis mint_amount ({actual_mint_amount}) must be strictly less \
+             than the un-mitigated lp_to_mint ({pre_n7_baseline_mint_amount}) — if these are \
+             equal, the dead-share carve-out has been silently removed"
+        );
+        assert_eq!(
+            pre_n7_baseline_mint_amount - actual_mint_amount,
+            state::MINIMUM_LIQUIDITY,
+            "the deducted amount must be exactly MINIMUM_LIQUIDITY, not some other value"
+        );
+    }
+
+    // ── #242 timelock_window_elapsed (pure helper) ──────────────────────────
+    #[test]
+    fn timelock_not_elapsed_before_window() {
+        // proposed at 1000, 432_000-slot window ⇒ earliest = 433_000.
+        assert_eq!(
+            timelock_window_elapsed(1_000, TIMELOCK_SLOTS, 1_000).unwrap(),
+            false
+        );
+        assert_eq!(
+            timelock_window_elapsed(1_000, TIMELOCK_SLOTS, 433_000 - 1).unwrap(),
+            false
+        );
+    }
+
+    #[test]
+    fn timelock_elapsed_at_or_after_window() {
+        // Boundary is inclusive: now == proposed + window ⇒ elapsed.
+        assert_eq!(
+            timelock_window_elapsed(1_000, TIMELOCK_SLOTS, 1_000 + TIMELOCK_SLOTS).unwrap(),
+            true
+        );
+        assert_eq!(
+            timelock_window_elapsed(1_000, TIMELOCK_SLOTS, 1_000 + TIMELOCK_SLOTS + 5).unwrap(),
+            true
+        );
+    }
+
+    #[test]
+    fn timelock_overflow_is_error_not_panic() {
+        assert_eq!(
+            timelock_window_elapsed(u64::MAX, TIMELOCK_SLOTS, u64::MAX),
+            Err(StakeError::Overflow.into())
+        );
+    }
+
+    // ── #242 StakePool pending-cooldown accessors round-trip ────────────────
+    #[test]
+    fn pending_cooldown_accessors_roundtrip_and_default_zero() {
+        let mut pool = StakePool::zeroed();
+        // Fresh (zeroed) pool ⇒ no active proposal.
+        assert_eq!(pool.pending_cooldown_slots(), 0);
+        assert_eq!(pool.cooldown_proposed_at_slot(), 0);
+        pool.set_pending_cooldown_slots(987_654);
+        pool.set_cooldown_proposed_at_slot(42);
+        assert_eq!(pool.pending_cooldown_slots(), 987_654);
+        assert_eq!(pool.cooldown_proposed_at_slot(), 42);
+        // Clearing the proposal slot restores the sentinel.
+        pool.set_cooldown_proposed_at_slot(0);
+        assert_eq!(pool.cooldown_proposed_at_slot(), 0);
+    }
+
+    #[test]
+    fn pending_cooldown_bytes_do_not_collide_with_neighbors() {
+        // _reserved[10..26] must not disturb the discriminator/version[0..9],
+        // market_resolved[9], tranche[32+], or realized_junior_loss[51..59].
+        let mut pool = StakePool::zeroed();
+        pool.set_discriminator();
+        pool.set_market_resolved(true);
+        pool.set_realized_junior_loss(7_777);
+        pool.set_tranche_enabled(true);
+        pool.set_pending_cooldown_slots(u64::MAX);
+        pool.set_cooldown_proposed_at_slot(123);
+        assert!(pool.validate_discriminator());
+        assert_eq!(pool.version(), StakePool::CURRENT_VERSION);
+        assert!(pool.market_resolved());
+        assert!(pool.tranche_enabled());
+        assert_eq!(pool.realized_junior_loss(), 7_777);
+        assert_eq!(pool.pending_cooldown_slots(), u64::MAX);
+        assert_eq!(pool.cooldown_proposed_at_slot(), 123);
+    }
+
+    #[test]
+    fn return_insurance_rejects_admin_ata_not_owned_by_admin() {
+        use solana_program::account_info::AccountInfo;
+
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let admin_ata_key = Pubkey::new_from_array([3u8; 32]);
+        let vault_key = Pubkey::new_from_array([4u8; 32]);
+        let collateral_mint = Pubkey::new_from_array([5u8; 32]);
+        let other_owner = Pubkey::new_from_array([6u8; 32]);
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes();
+        pool.vault = vault_key.to_bytes();
+        pool.collateral_mint = collateral_mint.to_bytes();
+        pool.total_flushed = 100;
+        pool.total_returned = 0;
+        pool.set_discriminator();
+
+        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
+
+        let mut admin_ata_data = vec![0u8; crate::spl_token::state::ACCOUNT_LEN];
+        admin_ata_data[0..32].copy_from_slice(collateral_mint.as_ref());
+        admin_ata_data[32..64].copy_from_slice(other_owner.as_ref());
+
+        let mut vault_data = vec![0u8; crate::spl_token::state::ACCOUNT_LEN];
+
+        let mut admin_lamports = 0u64;
+        let mut pool_lamports = 0u64;
+        let mut admin_ata_lamports = 0u64;
+        let mut vault_lamports = 0u64;
+        let mut token_program_lamports = 0u64;
+
+        let mut admin_data = vec![];
+        let mut token_program_data = vec![];
+
+        let token_program_id = crate::spl_token::id();
+        let system_program_id = solana_program::system_program::id();
+
+        let accounts = vec![
+            AccountInfo::new(
+                &admin_key,
+                true,
+                false,
+                &mut admin_lamports,
+                &mut admin_data,
+                &system_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &pool_key,
+                false,
+                true,
+                &mut pool_lamports,
+                &mut pool_data,
+                &program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &admin_ata_key,
+                false,
+                true,
+                &mut admin_ata_lamports,
+                &mut admin_ata_data,
+                &token_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &vault_key,
+                false,
+                true,
+                &mut vault_lamports,
+                &mut vault_data,
+                &token_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &token_program_id,
+                false,
+                false,
+                &mut token_program_lamports,
+                &mut token_program_data,
+                &system_program_id,
+                false,
+                0,
+            ),
+        ];
+
+        let result = process(
+            &program_id,
+            &accounts,
+            &[
+                10u8, // ReturnInsurance
+                10, 0, 0, 0, 0, 0, 0, 0, // amount = 10u64 little-endian
+            ],
+        );
+
+        assert!(
+        matches!(result, Err(e) if e == StakeError::Unauthorized.into()),
+        "ReturnInsurance should reject admin_ata when the token account owner is not the admin signer"
+    );
+    }
+
+    // #185 regression: disabling HWM must NOT clobber the stored floor, so that
+    // a later re-enable preserves the operator's original floor. The disable
+    // path skips validate_hwm_floor_bps and typically carries hwm_floor_bps=0;
+    // before the fix that 0 overwrote the stored floor.
+    #[test]
+    fn test_apply_hwm_config_disable_preserves_floor() {
+        let mut pool = StakePool::zeroed();
+
+        // Enable with a real floor.
+        apply_hwm_config(&mut pool, true, 5000);
+        assert!(pool.hwm_enabled());
+        assert_eq!(pool.hwm_floor_bps(), 5000);
+
+        // Disable with the validation-skipped sentinel (0): floor must survive.
+        apply_hwm_config(&mut pool, false, 0);
+        assert!(!pool.hwm_enabled(), "disable must clear the enabled flag");
+        assert_eq!(
+            pool.hwm_floor_bps(),
+            5000,
+            "disabling HWM must preserve the previously stored floor"
+        );
+
+        // Re-enable without re-specifying the floor (0 again): the preserved
+        // floor remains in effect because disable never wiped it.
+        apply_hwm_config(&mut pool, true, 0);
+        assert!(pool.hwm_enabled());
+        // Re-enabling with an explicit 0 floor IS an intentional write (the
+        // enable path is validated upstream), so the floor now tracks the arg.
+        assert_eq!(pool.hwm_floor_bps(), 0);
+    }
+
+    // Enabling always writes the supplied (upstream-validated) floor.
+    #[test]
+    fn test_apply_hwm_config_enable_writes_floor() {
+        let mut pool = StakePool::zeroed();
+
+        apply_hwm_config(&mut pool, true, 7500);
+        assert!(pool.hwm_enabled());
+        assert_eq!(pool.hwm_floor_bps(), 7500);
+
+        // Re-enable with a different floor overwrites it.
+        apply_hwm_config(&mut pool, true, 9000);
+        assert!(pool.hwm_enabled());
+        assert_eq!(pool.hwm_floor_bps(), 9000);
+    }
+
+    // ── #196: hwm_floor_bps must be bounded strictly below 100% ──
+    //
+    // The HWM floor is a per-epoch drain rate limiter, not a kill switch. At exactly
+    // 10_000 (100%) the floor equals the high-water mark and — because refresh_hwm
+    // keeps the mark >= current TVL and re-anchors it every new epoch — EVERY positive
+    // withdrawal is permanently blocked (a pool-wide freeze that survives epoch
+    // rollover). validate_hwm_floor_bps must therefore reject 100% (and anything above
+    // the cap) while still accepting the full legitimate rate-limiter range.
+
+    #[test]
+    fn test_validate_hwm_floor_bps_rejects_100_percent_and_above_cap() {
+        // The exact freeze value from #196.
+        assert!(
+            validate_hwm_floor_bps(10_000).is_err(),
+            "100% floor must be rejected: it permanently freezes all withdrawals (#196)"
+        );
+        // One basis point past the cap, and the u16 extreme, must also be rejected.
+        assert!(validate_hwm_floor_bps(MAX_HWM_FLOOR_BPS + 1).is_err());
+        assert!(validate_hwm_floor_bps(u16::MAX).is_err());
+    }
+
+    #[test]
+    fn test_validate_hwm_floor_bps_rejects_zero() {
+        // Unchanged: a 0 floor offers no anti-drain protection and is rejected on the
+        // (validated) enable path.
+        assert!(validate_hwm_floor_bps(0).is_err());
+    }
+
+    #[test]
+    fn test_validate_hwm_floor_bps_accepts_legitimate_range_up_to_cap() {
+        // Every legitimate rate-limiter floor, including the new maximum, is accepted.
+        for bps in [1u16, 5_000, 7_500, MAX_HWM_FLOOR_BPS] {
+            assert!(
+                validate_hwm_floor_bps(bps).is_ok(),
+                "{bps} bps is a valid non-freezing floor and must be accepted"
+            );
+        }
+        // The cap is strictly below 100%, so a withdrawal margin always exists.
+        assert!(MAX_HWM_FLOOR_BPS < 10_000);
+    }
+
+    // Behavioral proof through the same math the withdraw gate calls
+    // (processor.rs withdraw path -> math::hwm_floor / hwm_withdrawal_allowed): at the
+    // capped maximum a healthy pool still permits a positive withdrawal (the feature
+    // stays a limiter), whereas the now-rejected 100% value blocked even a 1-unit exit.
+    #[test]
+    fn test_capped_floor_preserves_a_withdrawal_margin() {
+        use crate::math::{hwm_floor, hwm_withdrawal_allowed};
+
+        let tvl: u64 = 1_000_000;
+        let hwm = tvl; // refresh_hwm invariant: hwm >= current_tvl
+
+        let floor = hwm_floor(hwm, MAX_HWM_FLOOR_BPS).expect("floor computes");
+        assert!(floor < tvl, "capped floor must leave a withdrawable margin");
+        let margin = tvl - floor; // 100_000 at 9_000 bps (10% of peak)
+        assert!(margin > 0);
+        assert!(
+            hwm_withdrawal_allowed(tvl - margin, hwm, MAX_HWM_FLOOR_BPS),
+            "a withdrawal down to the floor must be allowed at the cap"
+        );
+
+        // Contrast: the rejected 100% value blocks even a single unit.
+        assert!(
+            !hwm_withdrawal_allowed(tvl - 1, hwm, 10_000),
+            "100% floor (now rejected by the setter) would block every withdrawal"
+        );
+    }
+
+    // ── #199: length-checked bytemuck reinterpretation helpers ──
+    //
+    // Every handler reinterprets account data via these helpers. They must return a
+    // clean ProgramError on an undersized/zero-length buffer instead of panicking on
+    // the `[..SIZE]` slice (the #199 bug), and must succeed on a correctly-sized buffer.
+    // The attack shape: a StakeDeposit-sized buffer (STAKE_DEPOSIT_SIZE < STAKE_POOL_SIZE)
+    // passed where a pool is expected.
+
+    #[test]
+    fn test_pool_helpers_reject_undersized_without_panic() {
+        assert!(
+            STAKE_DEPOSIT_SIZE < STAKE_POOL_SIZE,
+            "deposit-sized buffer is a valid undersize"
+        );
+        let mut deposit_sized = vec![0u8; STAKE_DEPOSIT_SIZE];
+        assert!(
+            pool_from_data_mut(&mut deposit_sized).is_err(),
+            "mut: undersized -> Err, not panic"
+        );
+        assert!(
+            pool_from_data(&deposit_sized).is_err(),
+            "ro: undersized -> Err, not panic"
+        );
+
+        let mut one_short = vec![0u8; STAKE_POOL_SIZE - 1];
+        assert!(pool_from_data_mut(&mut one_short).is_err());
+        assert!(pool_from_data(&one_short).is_err());
+
+        let mut empty: Vec<u8> = Vec::new();
+        assert!(pool_from_data_mut(&mut empty).is_err());
+        assert!(pool_from_data(&empty).is_err());
+    }
+
+    #[test]
+    fn test_pool_helpers_accept_exact_and_oversized() {
+        let mut buf = vec![0u8; STAKE_POOL_SIZE];
+        let pool = pool_from_data_mut(&mut buf).expect("exact-size buffer must be Ok (mut)");
+        assert_eq!(pool.is_initialized, 0);
+        assert!(
+            pool_from_data(&buf).is_ok(),
+            "exact-size buffer must be Ok (ro)"
+        );
+        // Real Solana accounts may carry trailing bytes; only the first SIZE are read.
+        let mut over = vec![0u8; STAKE_POOL_SIZE + 32];
+        assert!(
+            pool_from_data_mut(&mut over).is_ok(),
+            "oversized buffer must be Ok"
+        );
+    }
+
+    #[test]
+    fn test_deposit_helpers_reject_undersized_without_panic() {
+        let mut short = vec![0u8; STAKE_DEPOSIT_SIZE - 1];
+        assert!(deposit_from_data_mut(&mut short).is_err());
+        assert!(deposit_from_data(&short).is_err());
+        let empty: Vec<u8> = Vec::new();
+        assert!(deposit_from_data(&empty).is_err());
+    }
+
+    #[test]
+    fn test_deposit_helpers_accept_exact_size() {
+        let mut buf = vec![0u8; STAKE_DEPOSIT_SIZE];
+        assert!(deposit_from_data_mut(&mut buf).is_ok());
+        assert!(deposit_from_data(&buf).is_ok());
+    }
+
+    /// #210 PoC: `SetMarketResolved` must reject a non-writable pool account
+    /// before mutating its state, like every other state-mutating handler.
+    #[test]
+    fn poc_set_market_resolved_should_reject_readonly_pool_account() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes();
+        pool.set_discriminator();
+        pool.set_market_resolved(false);
+
+        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
+
+        let mut admin_lamports = 0u64;
+        let mut pool_lamports = 0u64;
+
+        let mut admin_data = vec![];
+
+        let accounts = vec![
+            AccountInfo::new(
+                &admin_key,
+                true,
+                false,
+                &mut admin_lamports,
+                &mut admin_data,
+                &system_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &pool_key,
+                false,
+                false, // intentionally NOT writable
+                &mut pool_lamports,
+                &mut pool_data,
+                &program_id,
+                false,
+                0,
+            ),
+        ];
+
+        let result = process(&program_id, &accounts, &[18u8]);
+
+        assert_eq!(
+            result,
+            Err(StakeError::InvalidAccount.into()),
+            "SetMarketResolved should fail with InvalidAccount for a read-only pool account"
+        );
+    }
+
+    /// H-1: SetMarketResolved must reject when flushed insurance has not been
+    /// recovered from the wrapper (total_flushed > total_recovered_from_wrapper)
+    /// — defense-in-depth mirroring the primary gate on AdminResolveMarket.
+    #[test]
+    fn h1_set_market_resolved_blocks_when_flushed_not_returned() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes();
+        pool.total_flushed = 500;
+        pool.total_returned = 200; // 300 outstanding
+        pool.set_discriminator();
+
+        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
+        let mut admin_lamports = 0u64;
+        let mut pool_lamports = 0u64;
+        let mut admin_data = vec![];
+
+        let accounts = vec![
+            AccountInfo::new(
+                &admin_key,
+                true,
+                false,
+                &mut admin_lamports,
+                &mut admin_data,
+                &system_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &pool_key,
+                false,
+                true,
+                &mut pool_lamports,
+                &mut pool_data,
+                &program_id,
+                false,
+                0,
+            ),
+        ];
+
+        let result = process(&program_id, &accounts, &[18u8]);
+        assert_eq!(
+            result,
+            Err(StakeError::InsuranceLossOutstanding.into()),
+            "SetMarketResolved must reject while total_flushed > total_recovered_from_wrapper"
+        );
+    }
+
+    /// H-1 RE-REVIEW REGRESSION: SetMarketResolved must STILL reject when
+    /// `total_returned` alone has caught up with `total_flushed` via
+    /// `ReturnInsurance`-style bookkeeping (admin's own wallet -> pool.vault, no
+    /// wrapper CPI) but `total_recovered_from_wrapper` has NOT. This is exactly
+    /// the H-1 re-review finding: gating on `total_returned` let an admin
+    /// satisfy the check without ever recovering the flushed capital from the
+    /// wrapper, stranding it forever post-resolution.
+    #[test]
+    fn h1_set_market_resolved_blocks_when_only_total_returned_caught_up() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes();
+        pool.total_flushed = 500;
+        pool.total_returned = 500; // fully "returned" via ReturnInsurance-style bookkeeping
+        pool.total_recovered_from_wrapper = 0; // but NOTHING actually recovered from the wrapper
+        pool.set_discriminator();
+
+        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
+        let mut admin_lamports = 0u64;
+        let mut pool_lamports = 0u64;
+        let mut admin_data = vec![];
+
+        let accounts = vec![
+            AccountInfo::new(
+                &admin_key,
+                true,
+                false,
+                &mut admin_lamports,
+                &mut admin_data,
+                &system_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &pool_key,
+                false,
+                true,
+                &mut pool_lamports,
+                &mut pool_data,
+                &program_id,
+                false,
+                0,
+            ),
+        ];
+
+        let result = process(&program_id, &accounts, &[18u8]);
+        assert_eq!(
+            result,
+            Err(StakeError::InsuranceLossOutstanding.into()),
+            "SetMarketResolved must reject on total_returned alone — total_recovered_from_wrapper is the correct gate"
+        );
+
+        let pool_after = pool_from_data(&pool_data).unwrap();
+        assert!(
+            !pool_after.market_resolved(),
+            "pool must NOT be marked resolved"
+        );
+    }
+
+    /// H-1: SetMarketResolved succeeds once total_recovered_from_wrapper has
+    /// caught up with total_flushed (nothing outstanding to recover from the
+    /// wrapper).
+    #[test]
+    fn h1_set_market_resolved_allows_when_fully_recovered_from_wrapper() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes();
+        pool.total_flushed = 500;
+        pool.total_returned = 500;
+        pool.total_recovered_from_wrapper = 500; // fully recovered FROM THE WRAPPER
+        pool.set_discriminator();
+
+        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
+        let mut admin_lamports = 0u64;
+        let mut pool_lamports = 0u64;
+        let mut admin_data = vec![];
+
+        let accounts = vec![
+            AccountInfo::new(
+                &admin_key,
+                true,
+                false,
+                &mut admin_lamports,
+                &mut admin_data,
+                &system_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &pool_key,
+                false,
+                true,
+                &mut pool_lamports,
+                &mut pool_data,
+                &program_id,
+                false,
+                0,
+            ),
+        ];
+
+        let result = process(&program_id, &accounts, &[18u8]);
+        assert!(
+            result.is_ok(),
+            "SetMarketResolved must succeed once fully recovered from wrapper: {result:?}"
+        );
+
+        let pool_after = pool_from_data(&pool_data).unwrap();
+        assert!(pool_after.market_resolved(), "pool must be marked resolved");
+    }
+
+    /// C-1: AdminResolveMarket must reject a signer who is not pool.admin,
+    /// proving the new CPI proxy is admin-gated. This path returns before the
+    /// wrapper CPI is ever issued, so no real percolator program is needed.
+    #[test]
+    fn c1_admin_resolve_market_rejects_non_admin_signer() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let attacker_key = Pubkey::new_from_array([0xAAu8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let slab_key = Pubkey::new_from_array([3u8; 32]);
+        let percolator_program_id = Pubkey::new_from_array([4u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes(); // real admin — attacker will sign instead
+        pool.slab = slab_key.to_bytes();
+        pool.percolator_program = percolator_program_id.to_bytes();
+        pool.bump = 255;
+        pool.set_discriminator();
+
+        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
+        let mut attacker_lamports = 0u64;
+        let mut pool_lamports = 0u64;
+        let mut slab_lamports = 0u64;
+        let mut program_lamports = 0u64;
+        let mut attacker_data = vec![];
+        let mut slab_data = vec![];
+        let mut program_data = vec![];
+
+        let accounts = vec![
+            AccountInfo::new(
+                &attacker_key,
+                true,
+                false,
+                &mut attacker_lamports,
+                &mut attacker_data,
+                &system_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &pool_key,
+                false,
+                false,
+                &mut pool_lamports,
+                &mut pool_data,
+                &program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &slab_key,
+                false,
+                true,
+                &mut slab_lamports,
+                &mut slab_data,
+                &percolator_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &percolator_program_id,
+                false,
+                false,
+                &mut program_lamports,
+                &mut program_data,
+                &system_program_id,
+                true,
+                0,
+            ),
+        ];
+
+        let result = process(&program_id, &accounts, &[24u8]);
+        assert_eq!(
+            result,
+            Err(StakeError::Unauthorized.into()),
+            "AdminResolveMarket must reject a signer that is not pool.admin"
+        );
+    }
+
+    /// H-1: AdminResolveMarket must reject while flushed insurance is
+    /// outstanding — this is the PRIMARY enforcement point (see doc comment on
+    /// process_admin_resolve_market). Reached before the wrapper CPI, so no real
+    /// percolator program is needed to observe the rejection.
+    #[test]
+    fn h1_admin_resolve_market_blocks_when_flushed_not_returned() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let slab_key = Pubkey::new_from_array([3u8; 32]);
+        let percolator_program_id = Pubkey::new_from_array([4u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes();
+        pool.slab = slab_key.to_bytes();
+        pool.percolator_program = percolator_program_id.to_bytes();
+        pool.bump = 255;
+        pool.total_flushed = 1_000;
+        pool.total_returned = 400; // 600 outstanding
+        pool.set_discriminator();
+
+        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
+        let mut admin_lamports = 0u64;
+        let mut pool_lamports = 0u64;
+        let mut slab_lamports = 0u64;
+        let mut program_lamports = 0u64;
+        let mut admin_data = vec![];
+        let mut slab_data = vec![];
+        let mut program_data = vec![];
+
+        let accounts = vec![
+            AccountInfo::new(
+                &admin_key,
+                true,
+                false,
+                &mut admin_lamports,
+                &mut admin_data,
+                &system_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &pool_key,
+                false,
+                false,
+                &mut pool_lamports,
+                &mut pool_data,
+                &program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &slab_key,
+                false,
+                true,
+                &mut slab_lamports,
+                &mut slab_data,
+                &percolator_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &percolator_program_id,
+                false,
+                false,
+                &mut program_lamports,
+                &mut program_data,
+                &system_program_id,
+                true,
+                0,
+            ),
+        ];
+
+        let result = process(&program_id, &accounts, &[24u8]);
+        assert_eq!(
+            result,
+            Err(StakeError::InsuranceLossOutstanding.into()),
+            "AdminResolveMarket must reject while total_flushed > total_returned (H-1)"
+        );
+    }
+
+    /// C-1: AdminResolveMarket must reject a mismatched slab account (not
+    /// pool.slab) before ever reaching the CPI.
+    #[test]
+    fn c1_admin_resolve_market_rejects_wrong_slab() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let real_slab_key = Pubkey::new_from_array([3u8; 32]);
+        let wrong_slab_key = Pubkey::new_from_array([0xBBu8; 32]);
+        let percolator_program_id = Pubkey::new_from_array([4u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes();
+        pool.slab = real_slab_key.to_bytes();
+        pool.percolator_program = percolator_program_id.to_bytes();
+        pool.bump = 255;
+        pool.set_discriminator();
+
+        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
+        let mut admin_lamports = 0u64;
+        let mut pool_lamports = 0u64;
+        let mut slab_lamports = 0u64;
+        let mut program_lamports = 0u64;
+        let mut admin_data = vec![];
+        let mut slab_data = vec![];
+        let mut program_data = vec![];
+
+        let accounts = vec![
+            AccountInfo::new(
+                &admin_key,
+                true,
+                false,
+                &mut admin_lamports,
+                &mut admin_data,
+                &system_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &pool_key,
+                false,
+                false,
+                &mut pool_lamports,
+                &mut pool_data,
+                &program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &wrong_slab_key,
+                false,
+                true,
+                &mut slab_lamports,
+                &mut slab_data,
+                &percolator_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &percolator_program_id,
+                false,
+                false,
+                &mut program_lamports,
+                &mut program_data,
+                &system_program_id,
+                true,
+                0,
+            ),
+        ];
+
+        let result = process(&program_id, &accounts, &[24u8]);
+        assert_eq!(
+            result,
+            Err(StakeError::InvalidPda.into()),
+            "AdminResolveMarket must reject a slab account that doesn't match pool.slab"
+        );
+    }
+
+    /// DepositFor must reject before any token movement when the beneficiary has
+    /// not opted in: first with no deposit record at all, then with a record that
+    /// authorizes a DIFFERENT funder.
+    #[test]
+    fn deposit_for_requires_beneficiary_opt_in() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let funder_key = Pubkey::new_from_array([1u8; 32]);
+        let beneficiary = Pubkey::new_from_array([0xBEu8; 32]);
+        let lp_mint_key = Pubkey::new_from_array([5u8; 32]);
         let vault_key = Pubkey::new_from_array([4u8; 32]);
-        let collateral_mint = Pubkey::new_from_array([5u8; 32]);
-        let other_owner = Pubkey::new_from_array([6u8; 32]);
+        let slab_key = Pubkey::new_from_array([3u8; 32]);
+        let (pool_key, _) = state::derive_pool_pda(&program_id, &slab_key);
+        let (vault_auth_key, _) = derive_vault_authority(&program_id, &pool_key);
+        let (deposit_key, _) = state::derive_deposit_pda(&program_id, &pool_key, &beneficiary);
+        let system_program_id = solana_program::system_program::id();
+        let other = Pubkey::new_from_array([0x77u8; 32]);
 
         let mut pool = StakePool::zeroed();
         pool.is_initialized = 1;
-        pool.admin = admin_key.to_bytes();
+        pool.slab = slab_key.to_bytes();
+        pool.lp_mint = lp_mint_key.to_bytes();
         pool.vault = vault_key.to_bytes();
-        pool.collateral_mint = collateral_mint.to_bytes();
+        pool.set_discriminator();
+
+        let mut data = vec![29u8];
+        data.extend_from_slice(beneficiary.as_ref());
+        data.extend_from_slice(&10_000u64.to_le_bytes());
+
+        let token_id = crate::spl_token::id();
+        let run = |deposit_data: &[u8], deposit_owner: Pubkey| -> ProgramResult {
+            let metas = [
+                (funder_key, true, true, system_program_id),
+                (pool_key, false, true, program_id),
+                (other, false, true, token_id),
+                (vault_key, false, true, token_id),
+                (lp_mint_key, false, true, token_id),
+                (other, false, true, token_id),
+                (vault_auth_key, false, false, system_program_id),
+                (deposit_key, false, true, deposit_owner),
+                (token_id, false, false, system_program_id),
+                (
+                    solana_program::sysvar::clock::id(),
+                    false,
+                    false,
+                    system_program_id,
+                ),
+                (system_program_id, false, false, system_program_id),
+            ];
+            let mut datas = vec![vec![]; metas.len()];
+            datas[1] = bytemuck::bytes_of(&pool).to_vec();
+            datas[7] = deposit_data.to_vec();
+            run_with(&program_id, &metas, &mut datas, &data)
+        };
+
+        // No record: the beneficiary never opted in.
+        assert_eq!(
+            run(&[], system_program_id),
+            Err(StakeError::DepositorNotAuthorized.into())
+        );
+
+        // Record authorizes someone else.
+        let mut dep = StakeDeposit::zeroed();
+        dep.set_discriminator();
+        dep.set_authorized_depositor(other.to_bytes());
+        assert_eq!(
+            run(bytemuck::bytes_of(&dep), program_id),
+            Err(StakeError::DepositorNotAuthorized.into())
+        );
+    }
+
+    /// CloseDeposit refuses while the position still holds LP — closing would
+    /// orphan LP whose cooldown and tranche are tracked by this record.
+    #[test]
+    fn close_deposit_rejects_open_position() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let owner_key = Pubkey::new_from_array([1u8; 32]);
+        let (pool_key, _) = state::derive_pool_pda(&program_id, &Pubkey::new_from_array([3u8; 32]));
+        let (deposit_key, _) = state::derive_deposit_pda(&program_id, &pool_key, &owner_key);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut dep = StakeDeposit::zeroed();
+        dep.set_discriminator();
+        dep.is_initialized = 1;
+        dep.pool = pool_key.to_bytes();
+        dep.user = owner_key.to_bytes();
+        dep.lp_amount = 1;
+        let mut deposit_data = bytemuck::bytes_of(&dep).to_vec();
+        let mut pool_data = bytemuck::bytes_of(&StakePool::zeroed()).to_vec();
+        let mut owner_data = vec![];
+        let (mut l0, mut l1, mut l2) = (0u64, 0u64, 1_000_000u64);
+        let accounts = vec![
+            AccountInfo::new(
+                &owner_key,
+                true,
+                true,
+                &mut l0,
+                &mut owner_data,
+                &system_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &pool_key,
+                false,
+                false,
+                &mut l1,
+                &mut pool_data,
+                &program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &deposit_key,
+                false,
+                true,
+                &mut l2,
+                &mut deposit_data,
+                &program_id,
+                false,
+                0,
+            ),
+        ];
+        assert_eq!(
+            process(&program_id, &accounts, &[35u8]),
+            Err(StakeError::DepositNotEmpty.into())
+        );
+        assert_eq!(accounts[2].lamports(), 1_000_000, "rent must stay put");
+    }
+
+    /// Bincode image of the Clock sysvar (slot, epoch_start_timestamp, epoch,
+    /// leader_schedule_epoch, unix_timestamp) at `slot`.
+    fn clock_bytes(slot: u64) -> Vec<u8> {
+        let mut data = vec![0u8; 40];
+        data[0..8].copy_from_slice(&slot.to_le_bytes());
+        data
+    }
+
+    /// Finalize is gated on resolution and full wrapper recovery, records the slot
+    /// and recipient, and is one-shot.
+    #[test]
+    fn finalize_gates_and_records_sunset() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let clock_id = solana_program::sysvar::clock::id();
+        let system_program_id = solana_program::system_program::id();
+        let recipient = [0x5Eu8; 32];
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes();
+        pool.set_discriminator();
+
+        let mut data = vec![36u8];
+        data.extend_from_slice(&recipient);
+
+        let run = |pool_data: &mut Vec<u8>| -> ProgramResult {
+            let (mut l0, mut l1, mut l2) = (0u64, 0u64, 0u64);
+            let mut admin_data = vec![];
+            let mut clock_data = clock_bytes(777);
+            let accounts = vec![
+                AccountInfo::new(
+                    &admin_key,
+                    true,
+                    false,
+                    &mut l0,
+                    &mut admin_data,
+                    &system_program_id,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &pool_key,
+                    false,
+                    true,
+                    &mut l1,
+                    pool_data,
+                    &program_id,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &clock_id,
+                    false,
+                    false,
+                    &mut l2,
+                    &mut clock_data,
+                    &system_program_id,
+                    false,
+                    0,
+                ),
+            ];
+            process(&program_id, &accounts, &data)
+        };
+
+        let mut unresolved = bytemuck::bytes_of(&pool).to_vec();
+        assert_eq!(
+            run(&mut unresolved),
+            Err(StakeError::MarketNotResolved.into())
+        );
+
+        pool.set_market_resolved(true);
         pool.total_flushed = 100;
-        pool.total_returned = 0;
+        let mut outstanding = bytemuck::bytes_of(&pool).to_vec();
+        assert_eq!(
+            run(&mut outstanding),
+            Err(StakeError::InsuranceLossOutstanding.into())
+        );
+
+        pool.total_recovered_from_wrapper = 100;
+        let mut ready = bytemuck::bytes_of(&pool).to_vec();
+        assert_eq!(run(&mut ready), Ok(()));
+        let finalized: StakePool = bytemuck::pod_read_unaligned(&ready[..]);
+        assert!(finalized.is_finalized());
+        assert_eq!(finalized.finalized_at_slot, 777);
+        assert_eq!(finalized.final_sweep_recipient, recipient);
+
+        assert_eq!(
+            run(&mut ready),
+            Err(StakeError::PoolAlreadyFinalized.into())
+        );
+    }
+
+    /// SweepAndClosePool refuses inside the redemption grace period, before it
+    /// touches any token account.
+    #[test]
+    fn sweep_refused_inside_grace_period() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let other = Pubkey::new_from_array([0x77u8; 32]);
+        let clock_id = solana_program::sysvar::clock::id();
+        let token_id = crate::spl_token::id();
+        let system_program_id = solana_program::system_program::id();
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
         pool.set_discriminator();
+        pool.set_market_resolved(true);
+        pool.finalized_at_slot = 1_000;
 
         let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
+        let mut clock_data = clock_bytes(1_000 + FINAL_SWEEP_GRACE_SLOTS - 1);
+        let mut lamports = [0u64; 8];
+        let mut empties: Vec<Vec<u8>> = vec![vec![]; 6];
+        let [l1, l2, l3, l4, l5, l6, l7, l8] = &mut lamports;
+        let [e2, e3, e4, e5, e6, e7] = &mut empties[..] else {
+            unreachable!()
+        };
+        let accounts = vec![
+            AccountInfo::new(
+                &pool_key,
+                false,
+                true,
+                l1,
+                &mut pool_data,
+                &program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(&other, false, true, l2, e2, &token_id, false, 0),
+            AccountInfo::new(&other, false, true, l3, e3, &token_id, false, 0),
+            AccountInfo::new(&other, false, true, l4, e4, &system_program_id, false, 0),
+            AccountInfo::new(&other, false, false, l5, e5, &system_program_id, false, 0),
+            AccountInfo::new(&other, false, true, l6, e6, &token_id, false, 0),
+            AccountInfo::new(&token_id, false, false, l7, e7, &system_program_id, true, 0),
+            AccountInfo::new(
+                &clock_id,
+                false,
+                false,
+                l8,
+                &mut clock_data,
+                &system_program_id,
+                false,
+                0,
+            ),
+        ];
+        assert_eq!(
+            process(&program_id, &accounts, &[38u8]),
+            Err(StakeError::FinalSweepTooEarly.into())
+        );
+    }
 
-        let mut admin_ata_data = vec![0u8; crate::spl_token::state::ACCOUNT_LEN];
-        admin_ata_data[0..32].copy_from_slice(collateral_mint.as_ref());
-        admin_ata_data[32..64].copy_from_slice(other_owner.as_ref());
+    /// Tags 25..28 never execute on a direct admin signature; they must go
+    /// through the proposal timelock. Rejected before any account is read.
+    #[test]
+    fn fee_proxies_rejected_when_sent_directly() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let mut trade_fee = vec![28u8];
+        trade_fee.extend_from_slice(&42u64.to_le_bytes());
+        let mut maintenance = vec![26u8];
+        maintenance.extend_from_slice(&7u128.to_le_bytes());
+        for data in [
+            vec![25u8, 0x40, 0x06, 0xC0, 0x12, 0x40, 0x06],
+            maintenance,
+            vec![27u8, 0, 0, 25, 0, 0xB8, 0x0B],
+            trade_fee,
+        ] {
+            assert_eq!(
+                process(&program_id, &[], &data),
+                Err(StakeError::FeeChangeRequiresTimelock.into()),
+                "tag {} must require the timelock",
+                data[0]
+            );
+        }
+    }
 
-        let mut vault_data = vec![0u8; crate::spl_token::state::ACCOUNT_LEN];
+    /// A pending tag-28 proposal made at slot 1_000 against pool `[2; 32]`.
+    fn pending_trade_fee_proposal() -> StakeProposal {
+        let mut proposal = StakeProposal::zeroed();
+        proposal.set_discriminator();
+        proposal.is_initialized = 1;
+        proposal.status = PROPOSAL_STATUS_PENDING;
+        proposal.pool = [2u8; 32];
+        proposal.proposal_id = 7;
+        proposal.proposed_at_slot = 1_000;
+        proposal.call[0] = 28;
+        proposal.call[1..9].copy_from_slice(&42u64.to_le_bytes());
+        proposal.call_len = 9;
+        proposal
+    }
 
-        let mut admin_lamports = 0u64;
-        let mut pool_lamports = 0u64;
-        let mut admin_ata_lamports = 0u64;
-        let mut vault_lamports = 0u64;
-        let mut token_program_lamports = 0u64;
+    /// CommitFeeChange refuses inside the timelock window, on a resolved
+    /// proposal, and on a proposal for another pool — all before any CPI.
+    #[test]
+    fn commit_fee_change_gates() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let proposal_key = Pubkey::new_from_array([3u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let clock_id = solana_program::sysvar::clock::id();
+        let system_program_id = solana_program::system_program::id();
+
+        let run = |proposal: &StakeProposal, slot: u64| -> ProgramResult {
+            let (mut l0, mut l1, mut l2, mut l3) = (0u64, 0u64, 0u64, 0u64);
+            let mut proposal_data = bytemuck::bytes_of(proposal).to_vec();
+            let mut clock_data = clock_bytes(slot);
+            let (mut admin_data, mut pool_data) = (vec![], vec![0u8; STAKE_POOL_SIZE]);
+            let accounts = vec![
+                AccountInfo::new(
+                    &proposal_key,
+                    false,
+                    true,
+                    &mut l0,
+                    &mut proposal_data,
+                    &program_id,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &clock_id,
+                    false,
+                    false,
+                    &mut l1,
+                    &mut clock_data,
+                    &system_program_id,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &admin_key,
+                    true,
+                    false,
+                    &mut l2,
+                    &mut admin_data,
+                    &system_program_id,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &pool_key,
+                    false,
+                    false,
+                    &mut l3,
+                    &mut pool_data,
+                    &program_id,
+                    false,
+                    0,
+                ),
+            ];
+            process(&program_id, &accounts, &[40u8])
+        };
+
+        let proposal = pending_trade_fee_proposal();
+        assert_eq!(
+            run(&proposal, 1_000 + TIMELOCK_SLOTS - 1),
+            Err(StakeError::TimelockNotElapsed.into())
+        );
+
+        let mut committed = proposal;
+        committed.status = PROPOSAL_STATUS_COMMITTED;
+        assert_eq!(
+            run(&committed, 1_000 + TIMELOCK_SLOTS),
+            Err(StakeError::ProposalNotPending.into())
+        );
+
+        let mut foreign = proposal;
+        foreign.pool = [0x44u8; 32];
+        assert_eq!(
+            run(&foreign, 1_000 + TIMELOCK_SLOTS),
+            Err(StakeError::InvalidPda.into())
+        );
+    }
+
+    /// CancelFeeChange is admin-only, marks the proposal cancelled, and cannot
+    /// be repeated.
+    #[test]
+    fn cancel_fee_change_marks_cancelled_once() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let stranger_key = Pubkey::new_from_array([0x66u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let proposal_key = Pubkey::new_from_array([3u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes();
+        pool.set_discriminator();
+        let pool_bytes = bytemuck::bytes_of(&pool).to_vec();
+
+        let run = |signer: &Pubkey, proposal_data: &mut Vec<u8>| -> ProgramResult {
+            let (mut l0, mut l1, mut l2) = (0u64, 0u64, 0u64);
+            let mut signer_data = vec![];
+            let mut pool_data = pool_bytes.clone();
+            let accounts = vec![
+                AccountInfo::new(
+                    signer,
+                    true,
+                    false,
+                    &mut l0,
+                    &mut signer_data,
+                    &system_program_id,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &pool_key,
+                    false,
+                    false,
+                    &mut l1,
+                    &mut pool_data,
+                    &program_id,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &proposal_key,
+                    false,
+                    true,
+                    &mut l2,
+                    proposal_data,
+                    &program_id,
+                    false,
+                    0,
+                ),
+            ];
+            process(&program_id, &accounts, &[41u8])
+        };
+
+        let mut proposal_data = bytemuck::bytes_of(&pending_trade_fee_proposal()).to_vec();
+        assert_eq!(
+            run(&stranger_key, &mut proposal_data),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(run(&admin_key, &mut proposal_data), Ok(()));
+        let cancelled: StakeProposal = bytemuck::pod_read_unaligned(&proposal_data[..]);
+        assert_eq!(cancelled.status, PROPOSAL_STATUS_CANCELLED);
+        assert_eq!(
+            run(&admin_key, &mut proposal_data),
+            Err(StakeError::ProposalNotPending.into())
+        );
+    }
+
+    fn bounded_pool() -> StakePool {
+        let mut pool = StakePool::zeroed();
+        pool.set_fee_policy_bounds(FeePolicyBounds {
+            min_lp_share_bps: 4_000,
+            max_trade_fee_base_bps: 50,
+            max_change_delta_bps: 500,
+            min_change_interval_slots: 1_000,
+            max_backing_fee_bps: 40,
+        });
+        pool
+    }
+
+    fn fee_split(lp_share_bps: u16) -> StakeInstruction {
+        StakeInstruction::AdminUpdateFeeSplit {
+            creator_share_bps: 0,
+            lp_share_bps,
+            insurance_share_bps: 10_000 - lp_share_bps,
+        }
+    }
+
+    // ── stake-side fee-policy bounds (pure helper) ──────────────────────────
+    #[test]
+    fn fee_policy_absolute_bounds() {
+        let mut pool = bounded_pool();
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &fee_split(3_999), 10),
+            Err(StakeError::FeePolicyBoundViolated.into())
+        );
+        let fee = StakeInstruction::AdminUpdateTradeFeePolicy {
+            trade_fee_base_bps: 51,
+        };
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &fee, 10),
+            Err(StakeError::FeePolicyBoundViolated.into())
+        );
+        // The backing fee has its own ceiling, below the trade fee's here.
+        let backing = StakeInstruction::AdminUpdateBackingFeePolicy {
+            domain: 0,
+            fee_bps: 41,
+            insurance_share_bps: 0,
+        };
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &backing, 10),
+            Err(StakeError::FeePolicyBoundViolated.into())
+        );
+        // Nothing was recorded by the refused calls.
+        assert_eq!(pool.last_fee_change_slot, 0);
+        assert_eq!(pool.fee_history_flags, 0);
+
+        // The first change of each parameter is held only to the absolute bounds.
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &fee_split(9_000), 10),
+            Ok(())
+        );
+        assert_eq!(pool.last_lp_share_bps, 9_000);
+        assert_eq!(pool.last_fee_change_slot, 10);
+        assert_ne!(pool.fee_history_flags & FEE_HISTORY_LP_SHARE_KNOWN, 0);
+        assert_eq!(pool.fee_history_flags & FEE_HISTORY_TRADE_FEE_KNOWN, 0);
+
+        // The maintenance fee is outside the stake-side policy.
+        let maintenance = StakeInstruction::AdminUpdateMaintenanceFeePerSlot {
+            maintenance_fee_per_slot: u128::MAX,
+        };
+        assert_eq!(apply_fee_policy_change(&mut pool, &maintenance, 11), Ok(()));
+        assert_eq!(pool.last_fee_change_slot, 10);
+    }
+
+    #[test]
+    fn fee_policy_delta_and_interval() {
+        let mut pool = bounded_pool();
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &fee_split(6_000), 100),
+            Ok(())
+        );
+
+        // Inside the interval, even a tiny move is refused.
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &fee_split(6_001), 1_099),
+            Err(StakeError::FeeChangeTooSoon.into())
+        );
+        // After it, moves are capped at max_change_delta_bps in either direction.
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &fee_split(5_499), 1_100),
+            Err(StakeError::FeePolicyBoundViolated.into())
+        );
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &fee_split(6_501), 1_100),
+            Err(StakeError::FeePolicyBoundViolated.into())
+        );
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &fee_split(5_500), 1_100),
+            Ok(())
+        );
+
+        // The interval is shared: a trade-fee change right after a split is too soon.
+        let fee = |bps| StakeInstruction::AdminUpdateTradeFeePolicy {
+            trade_fee_base_bps: bps,
+        };
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &fee(10), 1_500),
+            Err(StakeError::FeeChangeTooSoon.into())
+        );
+        assert_eq!(apply_fee_policy_change(&mut pool, &fee(10), 2_100), Ok(()));
+        assert_eq!(pool.last_trade_fee_base_bps, 10);
+
+        // With no bounds, any bps sequence passes back to back.
+        let mut open = StakePool::zeroed();
+        open.set_fee_policy_bounds(FeePolicyBounds::UNBOUNDED);
+        assert_eq!(apply_fee_policy_change(&mut open, &fee(0), 5), Ok(()));
+        assert_eq!(apply_fee_policy_change(&mut open, &fee(10_000), 5), Ok(()));
+    }
+
+    /// Backing-fee changes are held to their own ceiling and, per domain of
+    /// the pool's asset, to the shared per-change delta.
+    #[test]
+    fn fee_policy_backing_fee_delta_per_domain() {
+        let mut pool = bounded_pool();
+        let mut bounds = pool.fee_policy_bounds();
+        bounds.max_change_delta_bps = 10;
+        pool.set_fee_policy_bounds(bounds);
+        let backing = |domain, fee_bps| StakeInstruction::AdminUpdateBackingFeePolicy {
+            domain,
+            fee_bps,
+            insurance_share_bps: 0,
+        };
+
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &backing(6, 40), 100),
+            Ok(())
+        );
+        assert_eq!(pool.last_backing_fee_bps, [40, 0]);
+        // The other domain's first change is held only to the ceiling.
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &backing(7, 0), 1_100),
+            Ok(())
+        );
+        assert_eq!(pool.last_backing_fee_bps, [40, 0]);
+        assert_eq!(
+            pool.fee_history_flags,
+            FEE_HISTORY_BACKING_FEE_KNOWN | FEE_HISTORY_BACKING_FEE_KNOWN << 1
+        );
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &backing(6, 29), 2_100),
+            Err(StakeError::FeePolicyBoundViolated.into())
+        );
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &backing(7, 11), 2_100),
+            Err(StakeError::FeePolicyBoundViolated.into())
+        );
+        assert_eq!(
+            apply_fee_policy_change(&mut pool, &backing(6, 30), 2_100),
+            Ok(())
+        );
+        assert_eq!(pool.last_backing_fee_bps, [30, 0]);
+    }
+
+    #[test]
+    fn fee_policy_bounds_ordering() {
+        let current = bounded_pool().fee_policy_bounds();
+        assert!(current.is_no_looser_than(&current));
+        let mut tighter = current;
+        tighter.min_lp_share_bps += 1;
+        tighter.min_change_interval_slots += 1;
+        assert!(tighter.is_no_looser_than(&current));
+        assert!(!current.is_no_looser_than(&tighter));
+        let mut looser = current;
+        looser.max_trade_fee_base_bps += 1;
+        assert!(!looser.is_no_looser_than(&current));
+        let mut looser = current;
+        looser.max_backing_fee_bps += 1;
+        assert!(!looser.is_no_looser_than(&current));
+        assert!(current.is_no_looser_than(&FeePolicyBounds::UNBOUNDED));
+    }
+
+    /// SetFeePolicyBounds sent directly tightens, but refuses to loosen.
+    #[test]
+    fn set_fee_policy_bounds_direct_only_tightens() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut pool = bounded_pool();
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes();
+        pool.set_discriminator();
+        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
+
+        let mut run = |bounds: FeePolicyBounds| -> ProgramResult {
+            let (mut l0, mut l1) = (0u64, 0u64);
+            let mut admin_data = vec![];
+            let accounts = vec![
+                AccountInfo::new(
+                    &admin_key,
+                    true,
+                    false,
+                    &mut l0,
+                    &mut admin_data,
+                    &system_program_id,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &pool_key,
+                    false,
+                    true,
+                    &mut l1,
+                    &mut pool_data,
+                    &program_id,
+                    false,
+                    0,
+                ),
+            ];
+            let mut data = vec![42u8];
+            data.extend_from_slice(&bounds.pack());
+            process(&program_id, &accounts, &data)
+        };
 
-        let mut admin_data = vec![];
-        let mut token_program_data = vec![];
+        let current = pool.fee_policy_bounds();
+        let mut looser = current;
+        looser.max_change_delta_bps += 1;
+        assert_eq!(
+            run(looser),
+            Err(StakeError::FeeChangeRequiresTimelock.into())
+        );
+        let mut tighter = current;
+        tighter.max_trade_fee_base_bps = 30;
+        assert_eq!(run(tighter), Ok(()));
+        let stored: StakePool = bytemuck::pod_read_unaligned(&pool_data[..]);
+        assert_eq!(stored.fee_policy_bounds(), tighter);
+    }
 
-        let token_program_id = crate::spl_token::id();
+    /// CommitFeeChange applies the pool's bounds before the proxy runs.
+    #[test]
+    fn commit_fee_change_enforces_pool_bounds() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let proposal_key = Pubkey::new_from_array([3u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let clock_id = solana_program::sysvar::clock::id();
         let system_program_id = solana_program::system_program::id();
 
+        // pending_trade_fee_proposal() sets trade_fee_base_bps = 42.
+        let mut pool = bounded_pool();
+        pool.fee_policy_max_trade_fee_base_bps = 41;
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes();
+        pool.set_discriminator();
+
+        let (mut l0, mut l1, mut l2, mut l3) = (0u64, 0u64, 0u64, 0u64);
+        let mut proposal_data = bytemuck::bytes_of(&pending_trade_fee_proposal()).to_vec();
+        let mut clock_data = clock_bytes(1_000 + TIMELOCK_SLOTS);
+        let mut admin_data = vec![];
+        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
         let accounts = vec![
             AccountInfo::new(
-                &admin_key,
-                true,
+                &proposal_key,
                 false,
-                &mut admin_lamports,
-                &mut admin_data,
-                &system_program_id,
+                true,
+                &mut l0,
+                &mut proposal_data,
+                &program_id,
                 false,
                 0,
             ),
             AccountInfo::new(
-                &pool_key,
+                &clock_id,
                 false,
-                true,
-                &mut pool_lamports,
-                &mut pool_data,
-                &program_id,
+                false,
+                &mut l1,
+                &mut clock_data,
+                &system_program_id,
                 false,
                 0,
             ),
             AccountInfo::new(
-                &admin_ata_key,
-                false,
+                &admin_key,
                 true,
-                &mut admin_ata_lamports,
-                &mut admin_ata_data,
-                &token_program_id,
+                false,
+                &mut l2,
+                &mut admin_data,
+                &system_program_id,
                 false,
                 0,
             ),
             AccountInfo::new(
-                &vault_key,
+                &pool_key,
                 false,
                 true,
-                &mut vault_lamports,
-                &mut vault_data,
-                &token_program_id,
+                &mut l3,
+                &mut pool_data,
+                &program_id,
                 false,
                 0,
             ),
-            AccountInfo::new(
-                &token_program_id,
+        ];
+        assert_eq!(
+            process(&program_id, &accounts, &[40u8]),
+            Err(StakeError::FeePolicyBoundViolated.into())
+        );
+    }
+
+    #[test]
+    fn veto_threshold_is_strict_and_zero_disables() {
+        assert!(!veto_threshold_exceeded(u64::MAX, 1, 0));
+        assert!(!veto_threshold_exceeded(3_000, 10_000, 3_000));
+        assert!(veto_threshold_exceeded(3_001, 10_000, 3_000));
+        assert!(veto_threshold_exceeded(1, 0, 1));
+        assert!(!veto_threshold_exceeded(u64::MAX, u64::MAX, 10_000));
+        // Disabled ranks weaker than any enabled threshold.
+        assert!(veto_threshold_rank(0) > veto_threshold_rank(10_000));
+        assert!(veto_threshold_rank(1) < veto_threshold_rank(2));
+    }
+
+    /// A direct SetVetoThreshold may only make the veto easier to reach.
+    #[test]
+    fn set_veto_threshold_direct_only_strengthens() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.admin = admin_key.to_bytes();
+        pool.set_discriminator();
+        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
+
+        let mut run = |threshold_bps: u16| -> ProgramResult {
+            let (mut l0, mut l1) = (0u64, 0u64);
+            let mut admin_data = vec![];
+            let accounts = vec![
+                AccountInfo::new(
+                    &admin_key,
+                    true,
+                    false,
+                    &mut l0,
+                    &mut admin_data,
+                    &system_program_id,
+                    false,
+                    0,
+                ),
+                AccountInfo::new(
+                    &pool_key,
+                    false,
+                    true,
+                    &mut l1,
+                    &mut pool_data,
+                    &program_id,
+                    false,
+                    0,
+                ),
+            ];
+            let mut data = vec![45u8];
+            data.extend_from_slice(&threshold_bps.to_le_bytes());
+            process(&program_id, &accounts, &data)
+        };
+
+        assert_eq!(run(10_001), Err(ProgramError::InvalidArgument));
+        // Enabling from disabled strengthens it.
+        assert_eq!(run(5_000), Ok(()));
+        assert_eq!(run(2_000), Ok(()));
+        assert_eq!(
+            run(3_000),
+            Err(StakeError::FeeChangeRequiresTimelock.into())
+        );
+        assert_eq!(run(0), Err(StakeError::FeeChangeRequiresTimelock.into()));
+        let stored: StakePool = bytemuck::pod_read_unaligned(&pool_data[..]);
+        assert_eq!(stored.veto_threshold_bps, 2_000);
+    }
+
+    /// CastVeto's argument and target checks all run before any CPI.
+    #[test]
+    fn cast_veto_rejects_before_locking() {
+        let _double = CpiDouble::enable();
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let voter_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let lp_mint_key = Pubkey::new_from_array([4u8; 32]);
+        let other_key = Pubkey::new_from_array([5u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let run = |pool: &StakePool, target_kind: u8, lp_amount: u64| -> ProgramResult {
+            let mut lamports = [0u64; 10];
+            let mut datas: Vec<Vec<u8>> = vec![vec![]; 10];
+            datas[1] = bytemuck::bytes_of(pool).to_vec();
+            let keys = [
+                voter_key,
+                pool_key,
+                other_key,
+                other_key,
+                other_key,
+                lp_mint_key,
+                other_key,
+                other_key,
+                system_program_id,
+                other_key,
+            ];
+            let mut accounts = Vec::new();
+            for (i, (l, d)) in lamports.iter_mut().zip(datas.iter_mut()).enumerate() {
+                let owner = if i == 1 {
+                    &program_id
+                } else {
+                    &system_program_id
+                };
+                accounts.push(AccountInfo::new(
+                    &keys[i],
+                    i == 0,
+                    i <= 4,
+                    l,
+                    d,
+                    owner,
+                    false,
+                    0,
+                ));
+            }
+            let mut data = vec![43u8, target_kind];
+            data.extend_from_slice(&lp_amount.to_le_bytes());
+            process(&program_id, &accounts, &data)
+        };
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.set_discriminator();
+        pool.lp_mint = lp_mint_key.to_bytes();
+        assert_eq!(
+            run(&pool, VETO_TARGET_ADMIN, 1),
+            Err(StakeError::VetoDisabled.into())
+        );
+        pool.veto_threshold_bps = 3_000;
+        assert_eq!(
+            run(&pool, VETO_TARGET_ADMIN, 0),
+            Err(StakeError::ZeroAmount.into())
+        );
+        assert_eq!(
+            run(&pool, VETO_TARGET_ADMIN, 1),
+            Err(StakeError::NoPendingAdmin.into())
+        );
+        assert_eq!(
+            run(&pool, VETO_TARGET_COOLDOWN_INCREASE, 1),
+            Err(StakeError::NoPendingCooldownProposal.into())
+        );
+        pool.lp_mint = other_key.to_bytes();
+        assert_eq!(
+            run(&pool, VETO_TARGET_ADMIN, 1),
+            Err(StakeError::InvalidMint.into())
+        );
+    }
+
+    /// Locked LP cannot be reclaimed while the vetoed proposal is pending,
+    /// and a replacement proposal (new nonce) or its expiry releases it.
+    #[test]
+    fn reclaim_veto_locked_while_target_pending() {
+        let _double = CpiDouble::enable();
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let voter_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let vote_key = Pubkey::new_from_array([3u8; 32]);
+        let other_key = Pubkey::new_from_array([5u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut vote = StakeVetoVote::zeroed();
+        vote.set_discriminator();
+        vote.is_initialized = 1;
+        vote.target_kind = VETO_TARGET_ADMIN;
+        vote.pool = pool_key.to_bytes();
+        vote.voter = voter_key.to_bytes();
+        vote.target_nonce = 7;
+        vote.locked_lp = 100;
+
+        let run = |pool: &StakePool, voter: &Pubkey| -> ProgramResult {
+            let mut lamports = [0u64; 7];
+            let mut datas: Vec<Vec<u8>> = vec![vec![]; 7];
+            datas[1] = bytemuck::bytes_of(pool).to_vec();
+            datas[4] = bytemuck::bytes_of(&vote).to_vec();
+            let keys = [
+                *voter, pool_key, other_key, other_key, vote_key, other_key, other_key,
+            ];
+            let mut accounts = Vec::new();
+            for (i, (l, d)) in lamports.iter_mut().zip(datas.iter_mut()).enumerate() {
+                let owner = if i == 1 || i == 4 {
+                    &program_id
+                } else {
+                    &system_program_id
+                };
+                accounts.push(AccountInfo::new(
+                    &keys[i],
+                    i == 0,
+                    i != 1,
+                    l,
+                    d,
+                    owner,
+                    false,
+                    0,
+                ));
+            }
+            process(&program_id, &accounts, &[44u8])
+        };
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.set_discriminator();
+        pool.pending_admin = other_key.to_bytes();
+        pool.admin_proposal_nonce = 7;
+        pool.admin_proposed_at_slot = 100;
+        assert_eq!(run(&pool, &other_key), Err(StakeError::Unauthorized.into()));
+        CpiDouble::set_slot(100 + PROPOSAL_EXPIRY_SLOTS);
+        assert_eq!(
+            run(&pool, &voter_key),
+            Err(StakeError::VetoStillLocked.into())
+        );
+        // Expired: it can no longer be accepted, so the vote is released.
+        CpiDouble::set_slot(100 + PROPOSAL_EXPIRY_SLOTS + 1);
+        assert_eq!(run(&pool, &voter_key), Err(StakeError::InvalidPda.into()));
+        CpiDouble::set_slot(100);
+        // Superseded by a new proposal: no longer locked, so the next check
+        // reached is the (dummy) escrow address.
+        pool.admin_proposal_nonce = 8;
+        assert_eq!(run(&pool, &voter_key), Err(StakeError::InvalidPda.into()));
+        pool.admin_proposal_nonce = 7;
+        pool.pending_admin = [0u8; 32];
+        assert_eq!(run(&pool, &voter_key), Err(StakeError::InvalidPda.into()));
+    }
+
+    /// Once SweepAndClosePool has closed the pool, nothing is pending any more:
+    /// a vote on a fee change that was still open gets past the lock (to the
+    /// dummy escrow check here) without the pool or the proposal.
+    #[test]
+    fn reclaim_veto_released_by_closed_pool() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let voter_key = Pubkey::new_from_array([1u8; 32]);
+        let pool_key = Pubkey::new_from_array([2u8; 32]);
+        let other_key = Pubkey::new_from_array([5u8; 32]);
+        let system_program_id = solana_program::system_program::id();
+
+        let mut vote = StakeVetoVote::zeroed();
+        vote.set_discriminator();
+        vote.is_initialized = 1;
+        vote.target_kind = VETO_TARGET_FEE_CHANGE;
+        vote.pool = pool_key.to_bytes();
+        vote.voter = voter_key.to_bytes();
+        vote.target_nonce = 7;
+        vote.locked_lp = 100;
+
+        let run = |pool_owner: Pubkey, pool_data: Vec<u8>| -> ProgramResult {
+            let metas = [
+                (voter_key, true, true, system_program_id),
+                (pool_key, false, false, pool_owner),
+                (other_key, false, true, system_program_id),
+                (other_key, false, true, system_program_id),
+                (Pubkey::new_from_array([3u8; 32]), false, true, program_id),
+                (other_key, false, false, system_program_id),
+                (other_key, false, false, system_program_id),
+            ];
+            let mut datas = vec![vec![]; metas.len()];
+            datas[1] = pool_data;
+            datas[4] = bytemuck::bytes_of(&vote).to_vec();
+            run_with(&program_id, &metas, &mut datas, &[44u8])
+        };
+
+        // A live pool wants the proposal PDA (account 7) to tell.
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.set_discriminator();
+        assert_eq!(
+            run(program_id, bytemuck::bytes_of(&pool).to_vec()),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        assert_eq!(
+            run(system_program_id, vec![]),
+            Err(StakeError::InvalidPda.into())
+        );
+        // A System-owned account that still has data is not a closed pool.
+        assert_eq!(
+            run(system_program_id, vec![0u8; 8]),
+            Err(StakeError::InvalidAccount.into())
+        );
+    }
+
+    /// Rage-quit: a pending cooldown increase or admin transfer waives the
+    /// per-user cooldown; otherwise the position's frozen terms apply.
+    /// Withdrawing one LP more than the record holds fails on the check right
+    /// after the cooldown, so the error shows which gate stopped it without
+    /// reaching a CPI.
+    #[test]
+    fn withdraw_cooldown_waiver_and_frozen_terms() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let user_key = Pubkey::new_from_array([1u8; 32]);
+        let (pool_key, _) = state::derive_pool_pda(&program_id, &Pubkey::new_from_array([3u8; 32]));
+        let (deposit_key, _) = state::derive_deposit_pda(&program_id, &pool_key, &user_key);
+        let (vault_auth_key, _) = derive_vault_authority(&program_id, &pool_key);
+        let lp_mint_key = Pubkey::new_from_array([4u8; 32]);
+        let vault_key = Pubkey::new_from_array([5u8; 32]);
+        let collateral_mint = [6u8; 32];
+        let other_key = Pubkey::new_from_array([7u8; 32]);
+        let token_program_id = crate::spl_token::id();
+        let clock_id = solana_program::sysvar::clock::id();
+        let system_program_id = solana_program::system_program::id();
+
+        let mut dep = StakeDeposit::zeroed();
+        dep.set_discriminator();
+        dep.is_initialized = 1;
+        dep.pool = pool_key.to_bytes();
+        dep.user = user_key.to_bytes();
+        dep.last_deposit_slot = 100;
+        dep.lp_amount = 10;
+
+        let token_account = |mint: &[u8; 32], owner: &Pubkey| {
+            let mut data = vec![0u8; crate::spl_token::state::ACCOUNT_LEN];
+            data[0..32].copy_from_slice(mint);
+            data[32..64].copy_from_slice(owner.as_ref());
+            data
+        };
+
+        let run = |pool: &StakePool, dep: &StakeDeposit| -> ProgramResult {
+            let keys = [
+                user_key,
+                pool_key,
+                other_key,
+                lp_mint_key,
+                vault_key,
+                other_key,
+                vault_auth_key,
+                deposit_key,
+                token_program_id,
+                clock_id,
+            ];
+            let owners = [
+                system_program_id,
+                program_id,
+                token_program_id,
+                token_program_id,
+                token_program_id,
+                token_program_id,
+                system_program_id,
+                program_id,
+                system_program_id,
+                system_program_id,
+            ];
+            let mut lamports = [0u64; 10];
+            let mut datas: Vec<Vec<u8>> = vec![vec![]; 10];
+            datas[1] = bytemuck::bytes_of(pool).to_vec();
+            datas[2] = token_account(&lp_mint_key.to_bytes(), &user_key);
+            datas[5] = token_account(&collateral_mint, &user_key);
+            datas[7] = bytemuck::bytes_of(dep).to_vec();
+            datas[9] = clock_bytes(200);
+            let accounts: Vec<AccountInfo> = lamports
+                .iter_mut()
+                .zip(datas.iter_mut())
+                .enumerate()
+                .map(|(i, (l, d))| {
+                    AccountInfo::new(&keys[i], i == 0, true, l, d, &owners[i], false, 0)
+                })
+                .collect();
+            let mut data = vec![2u8];
+            data.extend_from_slice(&11u64.to_le_bytes());
+            process(&program_id, &accounts, &data)
+        };
+
+        let mut pool = StakePool::zeroed();
+        pool.is_initialized = 1;
+        pool.set_discriminator();
+        pool.lp_mint = lp_mint_key.to_bytes();
+        pool.vault = vault_key.to_bytes();
+        pool.collateral_mint = collateral_mint;
+        pool.cooldown_slots = 1_000;
+        assert_eq!(run(&pool, &dep), Err(StakeError::CooldownNotElapsed.into()));
+
+        let mut cooldown_pending = pool;
+        cooldown_pending.set_pending_cooldown_slots(5_000);
+        cooldown_pending.set_cooldown_proposed_at_slot(150);
+        assert_eq!(
+            run(&cooldown_pending, &dep),
+            Err(StakeError::InsufficientLpTokens.into())
+        );
+
+        let mut admin_pending = pool;
+        admin_pending.pending_admin = other_key.to_bytes();
+        assert_eq!(
+            run(&admin_pending, &dep),
+            Err(StakeError::InsufficientLpTokens.into())
+        );
+
+        // Frozen terms: slot 200 is past the 50-slot cooldown recorded at
+        // deposit, however long the pool's cooldown has grown since.
+        dep.set_recorded_cooldown_slots(50);
+        assert_eq!(
+            run(&pool, &dep),
+            Err(StakeError::InsufficientLpTokens.into())
+        );
+        // ...but not past a recorded 500, even once the pool has lowered its
+        // own to 150 — the smaller value applies.
+        dep.set_recorded_cooldown_slots(500);
+        assert_eq!(run(&pool, &dep), Err(StakeError::CooldownNotElapsed.into()));
+        pool.cooldown_slots = 150;
+        assert_eq!(run(&pool, &dep), Err(StakeError::CooldownNotElapsed.into()));
+        pool.cooldown_slots = 50;
+        assert_eq!(
+            run(&pool, &dep),
+            Err(StakeError::InsufficientLpTokens.into())
+        );
+    }
+
+    /// Run `ix` over accounts described as (key, is_signer, is_writable, owner),
+    /// with `datas` as their data (mutated in place).
+    fn run_with(
+        program_id: &Pubkey,
+        metas: &[(Pubkey, bool, bool, Pubkey)],
+        datas: &mut [Vec<u8>],
+        ix: &[u8],
+    ) -> ProgramResult {
+        let mut lamports = vec![1_000_000u64; metas.len()];
+        let accounts: Vec<AccountInfo> = metas
+            .iter()
+            .zip(lamports.iter_mut().zip(datas.iter_mut()))
+            .map(|((key, signer, writable, owner), (l, d))| {
+                AccountInfo::new(key, *signer, *writable, l, d, owner, false, 0)
+            })
+            .collect();
+        process(program_id, &accounts, ix)
+    }
+
+    // ── native handler harness ──────────────────────────────────────────────
+    //
+    // Off-chain, `invoke` lands in `program_stubs`, whose default does nothing,
+    // so a handler test used to stop at the first `cpi_with_vault_delta`. A
+    // test that holds a `CpiDouble::enable()` guard instead gets the SPL Token
+    // and System instructions this program issues applied to the passed
+    // accounts, `Rent::get`, `Clock::get` (at `CpiDouble::set_slot`), and
+    // captured return data. The switch is per thread; every other test keeps
+    // the no-op default.
+
+    thread_local! {
+        static CPI_DOUBLE_ON: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+        static CLOCK_SLOT: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+        static RETURN_DATA: std::cell::RefCell<Option<Vec<u8>>> =
+            const { std::cell::RefCell::new(None) };
+    }
+
+    struct CpiDouble;
+
+    /// Turns the double off again when the test ends.
+    struct CpiDoubleGuard;
+
+    impl Drop for CpiDoubleGuard {
+        fn drop(&mut self) {
+            CPI_DOUBLE_ON.with(|on| on.set(false));
+        }
+    }
+
+    impl CpiDouble {
+        fn enable() -> CpiDoubleGuard {
+            static INSTALL: std::sync::Once = std::sync::Once::new();
+            INSTALL.call_once(|| {
+                solana_program::program_stubs::set_syscall_stubs(Box::new(CpiDouble));
+            });
+            CPI_DOUBLE_ON.with(|on| on.set(true));
+            CLOCK_SLOT.with(|slot| slot.set(0));
+            RETURN_DATA.with(|r| *r.borrow_mut() = None);
+            CpiDoubleGuard
+        }
+
+        /// The slot `Clock::get` reports.
+        fn set_slot(slot: u64) {
+            CLOCK_SLOT.with(|s| s.set(slot));
+        }
+
+        fn on() -> bool {
+            CPI_DOUBLE_ON.with(|on| on.get())
+        }
+
+        /// The last instruction's return data, decoded.
+        fn return_data() -> crate::return_data::StakeReturnData {
+            let data = RETURN_DATA
+                .with(|r| r.borrow().clone())
+                .expect("handler set return data");
+            crate::return_data::StakeReturnData::unpack(&data).unwrap()
+        }
+
+        fn apply(
+            ix: &solana_program::instruction::Instruction,
+            infos: &[AccountInfo],
+        ) -> ProgramResult {
+            let acct = |i: usize| {
+                infos
+                    .iter()
+                    .find(|a| *a.key == ix.accounts[i].pubkey)
+                    .expect("CPI account passed")
+            };
+            let amount_at =
+                |off: usize| u64::from_le_bytes(ix.data[off..off + 8].try_into().unwrap());
+            if ix.program_id == crate::spl_token::id() {
+                match ix.data[0] {
+                    3 => {
+                        let amount = amount_at(1);
+                        add_token_amount(acct(0), -(amount as i128))?;
+                        add_token_amount(acct(1), amount as i128)?;
+                    }
+                    7 => {
+                        let amount = amount_at(1);
+                        add_mint_supply(acct(0), amount as i128);
+                        add_token_amount(acct(1), amount as i128)?;
+                    }
+                    8 => {
+                        let amount = amount_at(1);
+                        add_token_amount(acct(0), -(amount as i128))?;
+                        add_mint_supply(acct(1), -(amount as i128));
+                    }
+                    4 => {
+                        let mut d = acct(0).try_borrow_mut_data()?;
+                        d[72..76].copy_from_slice(&1u32.to_le_bytes());
+                        d[76..108].copy_from_slice(acct(1).key.as_ref());
+                        d[121..129].copy_from_slice(&amount_at(1).to_le_bytes());
+                    }
+                    5 => {
+                        let mut d = acct(0).try_borrow_mut_data()?;
+                        d[72..108].fill(0);
+                        d[121..129].fill(0);
+                    }
+                    9 => {
+                        let (from, to) = (acct(0), acct(1));
+                        **to.try_borrow_mut_lamports()? += from.lamports();
+                        **from.try_borrow_mut_lamports()? = 0;
+                        from.try_borrow_mut_data()?.fill(0);
+                    }
+                    _ => {}
+                }
+            } else if ix.program_id == solana_program::system_program::id()
+                && ix.data[0..4] == 2u32.to_le_bytes()
+            {
+                let amount = amount_at(4);
+                let (from, to) = (acct(0), acct(1));
+                let left = from
+                    .lamports()
+                    .checked_sub(amount)
+                    .ok_or(ProgramError::InsufficientFunds)?;
+                **from.try_borrow_mut_lamports()? = left;
+                **to.try_borrow_mut_lamports()? += amount;
+            }
+            Ok(())
+        }
+    }
+
+    /// An SPL transfer made outside the program under test.
+    fn move_tokens(from: &mut TestAccount, to: &mut TestAccount, amount: u64) {
+        let (from_balance, to_balance) = (token_balance(from) - amount, token_balance(to) + amount);
+        from.data_mut()[64..72].copy_from_slice(&from_balance.to_le_bytes());
+        to.data_mut()[64..72].copy_from_slice(&to_balance.to_le_bytes());
+    }
+
+    /// SPL Token's `InsufficientFunds` when a balance would go negative.
+    fn add_token_amount(account: &AccountInfo, delta: i128) -> ProgramResult {
+        let mut d = account.try_borrow_mut_data()?;
+        let amount = u64::from_le_bytes(d[64..72].try_into().unwrap()) as i128 + delta;
+        let amount = u64::try_from(amount).map_err(|_| ProgramError::Custom(1))?;
+        d[64..72].copy_from_slice(&amount.to_le_bytes());
+        Ok(())
+    }
+
+    fn add_mint_supply(mint: &AccountInfo, delta: i128) {
+        let mut d = mint.try_borrow_mut_data().unwrap();
+        let supply = u64::from_le_bytes(d[36..44].try_into().unwrap()) as i128 + delta;
+        d[36..44].copy_from_slice(&(supply as u64).to_le_bytes());
+    }
+
+    impl solana_program::program_stubs::SyscallStubs for CpiDouble {
+        fn sol_invoke_signed(
+            &self,
+            instruction: &solana_program::instruction::Instruction,
+            account_infos: &[AccountInfo],
+            _signers_seeds: &[&[&[u8]]],
+        ) -> ProgramResult {
+            if !CpiDouble::on() {
+                return Ok(());
+            }
+            CpiDouble::apply(instruction, account_infos)
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            if !CpiDouble::on() {
+                return solana_program::program_error::UNSUPPORTED_SYSVAR;
+            }
+            // SAFETY: `Rent::get` passes a pointer to a `Rent`.
+            unsafe { *(var_addr as *mut Rent) = Rent::default() };
+            0
+        }
+
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            if !CpiDouble::on() {
+                return solana_program::program_error::UNSUPPORTED_SYSVAR;
+            }
+            let clock = Clock {
+                slot: CLOCK_SLOT.with(|s| s.get()),
+                ..Clock::default()
+            };
+            // SAFETY: `Clock::get` passes a pointer to a `Clock`.
+            unsafe { *(var_addr as *mut Clock) = clock };
+            0
+        }
+
+        fn sol_set_return_data(&self, data: &[u8]) {
+            RETURN_DATA.with(|r| *r.borrow_mut() = Some(data.to_vec()));
+        }
+    }
+
+    /// The key with the 4 bytes the runtime serializes before it: the
+    /// account's data length at instruction start, which `realloc` reads.
+    #[repr(C)]
+    struct KeySlot {
+        _padding: u32,
+        original_data_len: u32,
+        key: Pubkey,
+    }
+
+    /// An account for `run_accounts`, laid out as the runtime serializes one
+    /// so `realloc` works natively: the current data length sits in the 8
+    /// bytes before the data, which has `MAX_PERMITTED_DATA_INCREASE` of room.
+    struct TestAccount {
+        slot: KeySlot,
+        is_signer: bool,
+        is_writable: bool,
+        owner: Pubkey,
+        lamports: u64,
+        buf: Vec<u8>,
+    }
+
+    impl TestAccount {
+        fn new(
+            key: Pubkey,
+            is_signer: bool,
+            is_writable: bool,
+            owner: Pubkey,
+            data: &[u8],
+        ) -> Self {
+            let mut buf =
+                vec![0u8; 8 + data.len() + solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE];
+            buf[..8].copy_from_slice(&(data.len() as u64).to_le_bytes());
+            buf[8..8 + data.len()].copy_from_slice(data);
+            Self {
+                slot: KeySlot {
+                    _padding: 0,
+                    original_data_len: 0,
+                    key,
+                },
+                is_signer,
+                is_writable,
+                owner,
+                lamports: 1_000_000,
+                buf,
+            }
+        }
+
+        /// A system-owned wallet, signing and writable.
+        fn wallet(key: Pubkey) -> Self {
+            Self::new(key, true, true, solana_program::system_program::id(), &[])
+        }
+
+        /// A read-only account: a program, a sysvar.
+        fn readonly(key: Pubkey, data: &[u8]) -> Self {
+            Self::new(
+                key,
                 false,
                 false,
-                &mut token_program_lamports,
-                &mut token_program_data,
-                &system_program_id,
+                solana_program::system_program::id(),
+                data,
+            )
+        }
+
+        fn key(&self) -> Pubkey {
+            self.slot.key
+        }
+
+        fn len(&self) -> usize {
+            u64::from_le_bytes(self.buf[..8].try_into().unwrap()) as usize
+        }
+
+        fn data(&self) -> &[u8] {
+            &self.buf[8..8 + self.len()]
+        }
+
+        fn data_mut(&mut self) -> &mut [u8] {
+            let len = self.len();
+            &mut self.buf[8..8 + len]
+        }
+    }
+
+    /// Run `ix` over `accounts`, whose data, length and lamports it updates.
+    fn run_accounts(
+        program_id: &Pubkey,
+        accounts: &mut [&mut TestAccount],
+        ix: &[u8],
+    ) -> ProgramResult {
+        let infos: Vec<AccountInfo> = accounts
+            .iter_mut()
+            .map(|a| {
+                let len = a.len();
+                a.slot.original_data_len = len as u32;
+                AccountInfo::new(
+                    &a.slot.key,
+                    a.is_signer,
+                    a.is_writable,
+                    &mut a.lamports,
+                    &mut a.buf[8..8 + len],
+                    &a.owner,
+                    false,
+                    0,
+                )
+            })
+            .collect();
+        process(program_id, &infos, ix)
+    }
+
+    fn spl_token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Vec<u8> {
+        let mut d = vec![0u8; crate::spl_token::state::ACCOUNT_LEN];
+        d[0..32].copy_from_slice(mint.as_ref());
+        d[32..64].copy_from_slice(owner.as_ref());
+        d[64..72].copy_from_slice(&amount.to_le_bytes());
+        d[108] = 1; // Initialized
+        d
+    }
+
+    fn spl_mint(supply: u64) -> Vec<u8> {
+        let mut d = vec![0u8; 82];
+        d[36..44].copy_from_slice(&supply.to_le_bytes());
+        d[45] = 1; // is_initialized
+        d
+    }
+
+    fn token_balance(account: &TestAccount) -> u64 {
+        u64::from_le_bytes(account.data()[64..72].try_into().unwrap())
+    }
+
+    /// One pool and the fixed accounts around it, for handler tests that run
+    /// whole instructions under the `CpiDouble`.
+    struct PoolFixture {
+        program_id: Pubkey,
+        pool_key: Pubkey,
+        vault_auth: Pubkey,
+        lp_mint: Pubkey,
+        collateral_mint: Pubkey,
+        pool: TestAccount,
+        vault_account: TestAccount,
+        lp_mint_account: TestAccount,
+        slot: u64,
+    }
+
+    /// A depositor: wallet, collateral account, LP account, deposit record.
+    struct Holder {
+        wallet: TestAccount,
+        ata: TestAccount,
+        lp_ata: TestAccount,
+        record: TestAccount,
+    }
+
+    impl Holder {
+        fn edit_record(&mut self, edit: impl FnOnce(&mut StakeDeposit)) {
+            edit(bytemuck::from_bytes_mut::<StakeDeposit>(
+                &mut self.record.data_mut()[..STAKE_DEPOSIT_SIZE],
+            ));
+        }
+    }
+
+    impl PoolFixture {
+        /// An empty initialized mode-0 pool with a 10-slot cooldown.
+        fn new() -> Self {
+            let program_id = Pubkey::new_from_array([9u8; 32]);
+            let slab = Pubkey::new_from_array([3u8; 32]);
+            let (pool_key, _) = state::derive_pool_pda(&program_id, &slab);
+            let (vault_auth, vault_auth_bump) = derive_vault_authority(&program_id, &pool_key);
+            let vault = Pubkey::new_from_array([5u8; 32]);
+            let lp_mint = Pubkey::new_from_array([4u8; 32]);
+            let collateral_mint = Pubkey::new_from_array([6u8; 32]);
+            let token = crate::spl_token::id();
+
+            let mut pool = StakePool::zeroed();
+            pool.is_initialized = 1;
+            pool.set_discriminator();
+            pool.admin = [1u8; 32];
+            pool.slab = slab.to_bytes();
+            pool.lp_mint = lp_mint.to_bytes();
+            pool.vault = vault.to_bytes();
+            pool.collateral_mint = collateral_mint.to_bytes();
+            pool.cooldown_slots = 10;
+            pool.vault_authority_bump = vault_auth_bump;
+            Self {
+                program_id,
+                pool_key,
+                vault_auth,
+                lp_mint,
+                collateral_mint,
+                pool: TestAccount::new(
+                    pool_key,
+                    false,
+                    true,
+                    program_id,
+                    bytemuck::bytes_of(&pool),
+                ),
+                vault_account: TestAccount::new(
+                    vault,
+                    false,
+                    true,
+                    token,
+                    &spl_token_account(&collateral_mint, &vault_auth, 0),
+                ),
+                lp_mint_account: TestAccount::new(lp_mint, false, true, token, &spl_mint(0)),
+                slot: 100,
+            }
+        }
+
+        fn pool(&self) -> StakePool {
+            *bytemuck::from_bytes::<StakePool>(&self.pool.data()[..STAKE_POOL_SIZE])
+        }
+
+        fn edit_pool(&mut self, edit: impl FnOnce(&mut StakePool)) {
+            let pool =
+                bytemuck::from_bytes_mut::<StakePool>(&mut self.pool.data_mut()[..STAKE_POOL_SIZE]);
+            edit(pool);
+        }
+
+        /// Tokens that reach the vault outside any instruction (a fee push).
+        fn credit_vault(&mut self, amount: u64) {
+            let balance = token_balance(&self.vault_account) + amount;
+            self.vault_account.data_mut()[64..72].copy_from_slice(&balance.to_le_bytes());
+        }
+
+        /// Tokens that leave the vault outside any instruction (a shortfall).
+        fn debit_vault(&mut self, amount: u64) {
+            let balance = token_balance(&self.vault_account) - amount;
+            self.vault_account.data_mut()[64..72].copy_from_slice(&balance.to_le_bytes());
+        }
+
+        /// A holder with `collateral` tokens and an initialized, empty deposit
+        /// record (creating one needs the system program).
+        fn holder(&self, seed: u8, collateral: u64) -> Holder {
+            let user = Pubkey::new_from_array([seed; 32]);
+            let token = crate::spl_token::id();
+            let (record_key, bump) =
+                state::derive_deposit_pda(&self.program_id, &self.pool_key, &user);
+            let mut dep = StakeDeposit::zeroed();
+            dep.set_discriminator();
+            dep.is_initialized = 1;
+            dep.bump = bump;
+            dep.pool = self.pool_key.to_bytes();
+            dep.user = user.to_bytes();
+            Holder {
+                wallet: TestAccount::wallet(user),
+                ata: TestAccount::new(
+                    Pubkey::new_from_array([seed.wrapping_add(0x40); 32]),
+                    false,
+                    true,
+                    token,
+                    &spl_token_account(&self.collateral_mint, &user, collateral),
+                ),
+                lp_ata: TestAccount::new(
+                    Pubkey::new_from_array([seed.wrapping_add(0x80); 32]),
+                    false,
+                    true,
+                    token,
+                    &spl_token_account(&self.lp_mint, &user, 0),
+                ),
+                record: TestAccount::new(
+                    record_key,
+                    false,
+                    true,
+                    self.program_id,
+                    bytemuck::bytes_of(&dep),
+                ),
+            }
+        }
+
+        /// Deposit (1) or DepositJunior (16): the 11-account shape.
+        fn deposit(&mut self, h: &mut Holder, tag: u8, amount: u64) -> ProgramResult {
+            let mut ix = vec![tag];
+            ix.extend_from_slice(&amount.to_le_bytes());
+            let (mut vault_auth, mut token, mut clock, mut system) = self.programs();
+            run_accounts(
+                &self.program_id,
+                &mut [
+                    &mut h.wallet,
+                    &mut self.pool,
+                    &mut h.ata,
+                    &mut self.vault_account,
+                    &mut self.lp_mint_account,
+                    &mut h.lp_ata,
+                    &mut vault_auth,
+                    &mut h.record,
+                    &mut token,
+                    &mut clock,
+                    &mut system,
+                ],
+                &ix,
+            )
+        }
+
+        /// Withdraw (2), with the optional `close_if_empty` byte when given.
+        fn withdraw(
+            &mut self,
+            h: &mut Holder,
+            lp_amount: u64,
+            close_if_empty: Option<bool>,
+        ) -> ProgramResult {
+            let mut ix = vec![2u8];
+            ix.extend_from_slice(&lp_amount.to_le_bytes());
+            if let Some(close) = close_if_empty {
+                ix.push(close as u8);
+            }
+            self.exit(h, &ix)
+        }
+
+        /// RedeemFinal (37).
+        fn redeem_final(&mut self, h: &mut Holder, lp_amount: u64) -> ProgramResult {
+            let mut ix = vec![37u8];
+            ix.extend_from_slice(&lp_amount.to_le_bytes());
+            self.exit(h, &ix)
+        }
+
+        /// The 10-account shape Withdraw and RedeemFinal share.
+        fn exit(&mut self, h: &mut Holder, ix: &[u8]) -> ProgramResult {
+            let (mut vault_auth, mut token, mut clock, _) = self.programs();
+            run_accounts(
+                &self.program_id,
+                &mut [
+                    &mut h.wallet,
+                    &mut self.pool,
+                    &mut h.lp_ata,
+                    &mut self.lp_mint_account,
+                    &mut self.vault_account,
+                    &mut h.ata,
+                    &mut vault_auth,
+                    &mut h.record,
+                    &mut token,
+                    &mut clock,
+                ],
+                ix,
+            )
+        }
+
+        /// WithdrawTo (30): collateral to `dest`, bound to `recipient` (account 10).
+        fn withdraw_to(
+            &mut self,
+            h: &mut Holder,
+            lp_amount: u64,
+            dest: &mut TestAccount,
+            recipient: &mut TestAccount,
+        ) -> ProgramResult {
+            let mut ix = vec![30u8];
+            ix.extend_from_slice(&lp_amount.to_le_bytes());
+            let (mut vault_auth, mut token, mut clock, _) = self.programs();
+            run_accounts(
+                &self.program_id,
+                &mut [
+                    &mut h.wallet,
+                    &mut self.pool,
+                    &mut h.lp_ata,
+                    &mut self.lp_mint_account,
+                    &mut self.vault_account,
+                    dest,
+                    &mut vault_auth,
+                    &mut h.record,
+                    &mut token,
+                    &mut clock,
+                    recipient,
+                ],
+                &ix,
+            )
+        }
+
+        /// OperatorWithdraw (32), signed by `operator`, paying into `dest`,
+        /// burning from `lp_ata`.
+        fn operator_withdraw(
+            &mut self,
+            h: &mut Holder,
+            operator: &mut TestAccount,
+            lp_amount: u64,
+            lp_ata: Option<&mut TestAccount>,
+            dest: Option<&mut TestAccount>,
+        ) -> ProgramResult {
+            let mut ix = vec![32u8];
+            ix.extend_from_slice(&lp_amount.to_le_bytes());
+            let (mut vault_auth, mut token, mut clock, _) = self.programs();
+            let mut lp_delegate = TestAccount::readonly(self.lp_delegate(), &[]);
+            let mut owner = TestAccount::readonly(h.wallet.key(), &[]);
+            run_accounts(
+                &self.program_id,
+                &mut [
+                    operator,
+                    &mut self.pool,
+                    lp_ata.unwrap_or(&mut h.lp_ata),
+                    &mut self.lp_mint_account,
+                    &mut self.vault_account,
+                    dest.unwrap_or(&mut h.ata),
+                    &mut vault_auth,
+                    &mut h.record,
+                    &mut token,
+                    &mut clock,
+                    &mut owner,
+                    &mut lp_delegate,
+                ],
+                &ix,
+            )
+        }
+
+        /// SetDepositOperator (33) or, with no operator, RevokeDepositOperator (34).
+        fn set_operator(&mut self, h: &mut Holder, operator: Option<Pubkey>) -> ProgramResult {
+            let ix = match operator {
+                Some(op) => [&[33u8][..], op.as_ref()].concat(),
+                None => vec![34u8],
+            };
+            let (_, mut token, _, mut system) = self.programs();
+            let mut lp_delegate = TestAccount::readonly(self.lp_delegate(), &[]);
+            let mut pool = TestAccount::new(
+                self.pool_key,
                 false,
-                0,
-            ),
-        ];
+                false,
+                self.program_id,
+                self.pool.data(),
+            );
+            run_accounts(
+                &self.program_id,
+                &mut [
+                    &mut h.wallet,
+                    &mut pool,
+                    &mut h.record,
+                    &mut h.lp_ata,
+                    &mut lp_delegate,
+                    &mut token,
+                    &mut system,
+                ],
+                &ix,
+            )
+        }
+
+        fn lp_delegate(&self) -> Pubkey {
+            state::derive_lp_delegate(&self.program_id, &self.pool_key).0
+        }
 
-        let result = process(
-            &program_id,
-            &accounts,
-            &[
-                10u8, // ReturnInsurance
-                10, 0, 0, 0, 0, 0, 0, 0, // amount = 10u64 little-endian
-            ],
-        );
+        /// SweepAndClosePool (38), paying the sweep to `recipient`.
+        fn sweep(&mut self, recipient: &mut TestAccount) -> ProgramResult {
+            let mut recipient_ata = TestAccount::new(
+                Pubkey::new_from_array([0x5E; 32]),
+                false,
+                true,
+                crate::spl_token::id(),
+                &spl_token_account(&self.collateral_mint, &recipient.key(), 0),
+            );
+            let (mut vault_auth, mut token, mut clock, _) = self.programs();
+            run_accounts(
+                &self.program_id,
+                &mut [
+                    &mut self.pool,
+                    &mut self.vault_account,
+                    &mut recipient_ata,
+                    recipient,
+                    &mut vault_auth,
+                    &mut self.lp_mint_account,
+                    &mut token,
+                    &mut clock,
+                ],
+                &[38u8],
+            )
+        }
 
-        assert!(
-        matches!(result, Err(e) if e == StakeError::Unauthorized.into()),
-        "ReturnInsurance should reject admin_ata when the token account owner is not the admin signer"
-    );
+        /// CloseDeposit (35).
+        fn close_deposit(&mut self, h: &mut Holder) -> ProgramResult {
+            run_accounts(
+                &self.program_id,
+                &mut [&mut h.wallet, &mut self.pool, &mut h.record],
+                &[35u8],
+            )
+        }
+
+        /// AccrueFees (12).
+        fn accrue_fees(&mut self) -> ProgramResult {
+            let mut caller = TestAccount::wallet(Pubkey::new_from_array([0xCA; 32]));
+            let (_, _, mut clock, _) = self.programs();
+            run_accounts(
+                &self.program_id,
+                &mut [
+                    &mut caller,
+                    &mut self.pool,
+                    &mut self.vault_account,
+                    &mut clock,
+                ],
+                &[12u8],
+            )
+        }
+
+        /// DeclareDeficit (54).
+        fn declare_deficit(&mut self) -> ProgramResult {
+            let mut caller = TestAccount::wallet(Pubkey::new_from_array([0xCA; 32]));
+            run_accounts(
+                &self.program_id,
+                &mut [&mut caller, &mut self.pool, &mut self.vault_account],
+                &[54u8],
+            )
+        }
+
+        /// An admin-side instruction in the [signer, pool, clock] shape
+        /// (ProposeAdmin, AcceptAdmin and the cooldown-increase timelock).
+        fn admin_ix(&mut self, signer: Pubkey, ix: &[u8]) -> ProgramResult {
+            // Tags 5/6 read `Clock::get` and ignore the trailing clock account.
+            CpiDouble::set_slot(self.slot);
+            let mut signer = TestAccount::wallet(signer);
+            let (_, _, mut clock, _) = self.programs();
+            run_accounts(
+                &self.program_id,
+                &mut [&mut signer, &mut self.pool, &mut clock],
+                ix,
+            )
+        }
+
+        /// The vault authority, token program, clock and system program.
+        fn programs(&self) -> (TestAccount, TestAccount, TestAccount, TestAccount) {
+            (
+                TestAccount::readonly(self.vault_auth, &[]),
+                TestAccount::readonly(crate::spl_token::id(), &[]),
+                TestAccount::readonly(solana_program::sysvar::clock::id(), &clock_bytes(self.slot)),
+                TestAccount::readonly(solana_program::system_program::id(), &[]),
+            )
+        }
     }
 
-    // #185 regression: disabling HWM must NOT clobber the stored floor, so that
-    // a later re-enable preserves the operator's original floor. The disable
-    // path skips validate_hwm_floor_bps and typically carries hwm_floor_bps=0;
-    // before the fix that 0 overwrote the stored floor.
+    /// Each value-moving handler ends with its return-data record: the genesis
+    /// deposit reports what the depositor received (net of the dead shares),
+    /// later ones the full mint, and every record carries the post-update share
+    /// price of the sub-pool it priced against.
     #[test]
-    fn test_apply_hwm_config_disable_preserves_floor() {
-        let mut pool = StakePool::zeroed();
-
-        // Enable with a real floor.
-        apply_hwm_config(&mut pool, true, 5000);
-        assert!(pool.hwm_enabled());
-        assert_eq!(pool.hwm_floor_bps(), 5000);
+    fn value_moving_handlers_emit_return_data() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        let mut a = f.holder(0x11, 100_000);
+
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        let rd = CpiDouble::return_data();
+        assert_eq!((rd.instruction_tag, rd.tranche), (1, TRANCHE_POOL));
+        assert_eq!(rd.amount_in, 10_000);
+        assert_eq!(rd.lp_minted, 10_000 - state::MINIMUM_LIQUIDITY);
+        assert_eq!(token_balance(&a.lp_ata), rd.lp_minted);
+        assert_eq!((rd.share_price_num, rd.share_price_den), (10_000, 10_000));
+
+        assert_eq!(f.deposit(&mut a, 1, 5_000), Ok(()));
+        let rd = CpiDouble::return_data();
+        assert_eq!((rd.amount_in, rd.lp_minted), (5_000, 5_000));
+        assert_eq!((rd.share_price_num, rd.share_price_den), (15_000, 15_000));
+
+        // A 1_500 fee push, crystallized by this call and by no one else.
+        f.credit_vault(1_500);
+        assert_eq!(f.accrue_fees(), Ok(()));
+        let rd = CpiDouble::return_data();
+        assert_eq!((rd.instruction_tag, rd.amount_in), (12, 1_500));
+        assert_eq!((rd.lp_minted, rd.lp_burned), (0, 0));
+        assert_eq!((rd.share_price_num, rd.share_price_den), (16_500, 15_000));
+
+        // 1_500 LP at 1.1 pays 1_650, less the rounding kept for the pool.
+        f.slot += 10;
+        assert_eq!(f.withdraw(&mut a, 1_500, None), Ok(()));
+        let rd = CpiDouble::return_data();
+        assert_eq!((rd.instruction_tag, rd.tranche), (2, TRANCHE_POOL));
+        assert_eq!((rd.amount_out, rd.lp_burned), (1_649, 1_500));
+        assert_eq!((rd.share_price_num, rd.share_price_den), (14_851, 13_500));
+        assert_eq!(token_balance(&a.ata), 100_000 - 15_000 + 1_649);
+        assert_eq!(f.pool().total_lp_supply, 13_500);
+    }
+
+    /// With tranches on, a senior deposit reports the senior price and a
+    /// junior deposit or withdrawal the junior one.
+    #[test]
+    fn tranche_return_data_names_the_priced_sub_pool() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        f.edit_pool(|p| {
+            p.set_tranche_enabled(true);
+            p.set_junior_fee_mult_bps(10_000);
+        });
+        let mut senior = f.holder(0x11, 100_000);
+        let mut junior = f.holder(0x22, 100_000);
+        junior.edit_record(|d| d.set_junior_deposit(true));
+
+        assert_eq!(f.deposit(&mut senior, 1, 10_000), Ok(()));
+        let rd = CpiDouble::return_data();
+        assert_eq!((rd.instruction_tag, rd.tranche), (1, TRANCHE_SENIOR));
+        assert_eq!((rd.share_price_num, rd.share_price_den), (10_000, 10_000));
+
+        assert_eq!(f.deposit(&mut junior, 16, 4_000), Ok(()));
+        let rd = CpiDouble::return_data();
+        assert_eq!((rd.instruction_tag, rd.tranche), (16, TRANCHE_JUNIOR));
+        assert_eq!((rd.amount_in, rd.lp_minted), (4_000, 4_000));
+        assert_eq!((rd.share_price_num, rd.share_price_den), (4_000, 4_000));
+
+        f.slot += 10;
+        assert_eq!(f.withdraw(&mut junior, 1_000, None), Ok(()));
+        let rd = CpiDouble::return_data();
+        assert_eq!((rd.instruction_tag, rd.tranche), (2, TRANCHE_JUNIOR));
+        assert_eq!((rd.amount_out, rd.lp_burned), (1_000, 1_000));
+        assert_eq!((rd.share_price_num, rd.share_price_den), (3_000, 3_000));
+    }
+
+    /// WithdrawTo pays only into a token account owned by the recipient named
+    /// in account 10 — a destination owned by anyone else is refused before
+    /// any LP is burned.
+    #[test]
+    fn withdraw_to_binds_destination_to_recipient() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        let mut a = f.holder(0x11, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        f.slot += 10;
+
+        let token = crate::spl_token::id();
+        let recipient_key = Pubkey::new_from_array([0x33; 32]);
+        let mut recipient = TestAccount::readonly(recipient_key, &[]);
+        let mallory = Pubkey::new_from_array([0x44; 32]);
+        let mut foreign = TestAccount::new(
+            Pubkey::new_from_array([0x45; 32]),
+            false,
+            true,
+            token,
+            &spl_token_account(&f.collateral_mint, &mallory, 0),
+        );
+        assert_eq!(
+            f.withdraw_to(&mut a, 1_000, &mut foreign, &mut recipient),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(token_balance(&a.lp_ata), 9_000);
+
+        let mut dest = TestAccount::new(
+            Pubkey::new_from_array([0x34; 32]),
+            false,
+            true,
+            token,
+            &spl_token_account(&f.collateral_mint, &recipient_key, 0),
+        );
+        assert_eq!(
+            f.withdraw_to(&mut a, 1_000, &mut dest, &mut recipient),
+            Ok(())
+        );
+        assert_eq!(token_balance(&dest), 1_000);
+        assert_eq!(token_balance(&a.ata), 90_000);
+        assert_eq!(token_balance(&a.lp_ata), 8_000);
+    }
 
-        // Disable with the validation-skipped sentinel (0): floor must survive.
-        apply_hwm_config(&mut pool, false, 0);
-        assert!(!pool.hwm_enabled(), "disable must clear the enabled flag");
+    /// SetDepositOperator grows a v1 record to v2 — topping its rent up from
+    /// the owner — and approves the pool's LP delegate; RevokeDepositOperator
+    /// clears both again.
+    #[test]
+    fn set_deposit_operator_reallocs_and_revoke_clears() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        let mut a = f.holder(0x11, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        assert_eq!(a.record.len(), STAKE_DEPOSIT_SIZE);
+        a.wallet.lamports = 10_000_000_000;
+        let operator = Pubkey::new_from_array([0x0B; 32]);
+
+        assert_eq!(f.set_operator(&mut a, Some(operator)), Ok(()));
+        let need = Rent::default().minimum_balance(state::STAKE_DEPOSIT_V2_SIZE);
+        assert_eq!(a.record.len(), state::STAKE_DEPOSIT_V2_SIZE);
+        assert_eq!(a.record.lamports, need);
+        assert_eq!(a.wallet.lamports, 10_000_000_000 - (need - 1_000_000));
         assert_eq!(
-            pool.hwm_floor_bps(),
-            5000,
-            "disabling HWM must preserve the previously stored floor"
+            state::deposit_operator(a.record.data()),
+            operator.to_bytes()
         );
+        assert_eq!(a.lp_ata.data()[72..76], 1u32.to_le_bytes());
+        assert_eq!(a.lp_ata.data()[76..108], f.lp_delegate().to_bytes());
 
-        // Re-enable without re-specifying the floor (0 again): the preserved
-        // floor remains in effect because disable never wiped it.
-        apply_hwm_config(&mut pool, true, 0);
-        assert!(pool.hwm_enabled());
-        // Re-enabling with an explicit 0 floor IS an intentional write (the
-        // enable path is validated upstream), so the floor now tracks the arg.
-        assert_eq!(pool.hwm_floor_bps(), 0);
+        // Re-pointing the operator neither grows nor charges again.
+        let lamports = a.wallet.lamports;
+        let operator2 = Pubkey::new_from_array([0x0C; 32]);
+        assert_eq!(f.set_operator(&mut a, Some(operator2)), Ok(()));
+        assert_eq!(a.record.len(), state::STAKE_DEPOSIT_V2_SIZE);
+        assert_eq!(a.wallet.lamports, lamports);
+
+        assert_eq!(f.set_operator(&mut a, None), Ok(()));
+        assert_eq!(state::deposit_operator(a.record.data()), [0u8; 32]);
+        assert_eq!(a.lp_ata.data()[72..108], [0u8; 36]);
     }
 
-    // Enabling always writes the supplied (upstream-validated) floor.
+    /// Revoke only undoes the pool's own delegation: an approval the owner gave
+    /// someone else outlives it.
     #[test]
-    fn test_apply_hwm_config_enable_writes_floor() {
-        let mut pool = StakePool::zeroed();
+    fn revoke_deposit_operator_leaves_foreign_delegate() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        let mut a = f.holder(0x11, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        a.wallet.lamports = 10_000_000_000;
+        let operator = Pubkey::new_from_array([0x0B; 32]);
+        assert_eq!(f.set_operator(&mut a, Some(operator)), Ok(()));
+
+        let foreign = Pubkey::new_from_array([0xF0; 32]);
+        a.lp_ata.data_mut()[76..108].copy_from_slice(foreign.as_ref());
+        assert_eq!(f.set_operator(&mut a, None), Ok(()));
+        assert_eq!(state::deposit_operator(a.record.data()), [0u8; 32]);
+        assert_eq!(a.lp_ata.data()[72..76], 1u32.to_le_bytes());
+        assert_eq!(a.lp_ata.data()[76..108], foreign.to_bytes());
+    }
+
+    /// OperatorWithdraw pays only the position owner, only for the recorded
+    /// operator, and only from the owner's own LP account — another holder's
+    /// LP account delegated to the same pool delegate is not a way in.
+    #[test]
+    fn operator_withdraw_is_pinned_to_operator_owner_and_owner_lp() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        let mut a = f.holder(0x11, 100_000);
+        let mut b = f.holder(0x22, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        assert_eq!(f.deposit(&mut b, 1, 10_000), Ok(()));
+        a.wallet.lamports = 10_000_000_000;
+        b.wallet.lamports = 10_000_000_000;
+        let operator_key = Pubkey::new_from_array([0x0B; 32]);
+        assert_eq!(f.set_operator(&mut a, Some(operator_key)), Ok(()));
+        assert_eq!(f.set_operator(&mut b, Some(operator_key)), Ok(()));
+        f.slot += 10;
+
+        let mut stranger = TestAccount::wallet(Pubkey::new_from_array([0x0D; 32]));
+        assert_eq!(
+            f.operator_withdraw(&mut a, &mut stranger, 1_000, None, None),
+            Err(StakeError::Unauthorized.into())
+        );
 
-        apply_hwm_config(&mut pool, true, 7500);
-        assert!(pool.hwm_enabled());
-        assert_eq!(pool.hwm_floor_bps(), 7500);
+        let mut operator = TestAccount::wallet(operator_key);
+        let token = crate::spl_token::id();
+        let mut operators_ata = TestAccount::new(
+            Pubkey::new_from_array([0x0E; 32]),
+            false,
+            true,
+            token,
+            &spl_token_account(&f.collateral_mint, &operator_key, 0),
+        );
+        assert_eq!(
+            f.operator_withdraw(&mut a, &mut operator, 1_000, None, Some(&mut operators_ata)),
+            Err(StakeError::Unauthorized.into())
+        );
 
-        // Re-enable with a different floor overwrites it.
-        apply_hwm_config(&mut pool, true, 9000);
-        assert!(pool.hwm_enabled());
-        assert_eq!(pool.hwm_floor_bps(), 9000);
-    }
+        // B's LP account is delegated to the same PDA, but it isn't A's.
+        assert_eq!(
+            f.operator_withdraw(&mut a, &mut operator, 1_000, Some(&mut b.lp_ata), None),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(token_balance(&b.lp_ata), 10_000);
 
-    // ── #196: hwm_floor_bps must be bounded strictly below 100% ──
-    //
-    // The HWM floor is a per-epoch drain rate limiter, not a kill switch. At exactly
-    // 10_000 (100%) the floor equals the high-water mark and — because refresh_hwm
-    // keeps the mark >= current TVL and re-anchors it every new epoch — EVERY positive
-    // withdrawal is permanently blocked (a pool-wide freeze that survives epoch
-    // rollover). validate_hwm_floor_bps must therefore reject 100% (and anything above
-    // the cap) while still accepting the full legitimate rate-limiter range.
+        assert_eq!(
+            f.operator_withdraw(&mut a, &mut operator, 1_000, None, None),
+            Ok(())
+        );
+        assert_eq!(token_balance(&a.lp_ata), 9_000 - 1_000);
+        assert_eq!(token_balance(&a.ata), 90_000 + 1_000);
+        assert_eq!(token_balance(&operators_ata), 0);
+    }
 
+    /// RedeemFinal on a tranche-less pool: no cooldown, LP received by
+    /// transfer redeems with no record at all, and a record holding less than
+    /// the redeemed LP saturates at zero instead of failing.
     #[test]
-    fn test_validate_hwm_floor_bps_rejects_100_percent_and_above_cap() {
-        // The exact freeze value from #196.
-        assert!(
-            validate_hwm_floor_bps(10_000).is_err(),
-            "100% floor must be rejected: it permanently freezes all withdrawals (#196)"
+    fn redeem_final_record_is_optional_and_saturates() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        let mut a = f.holder(0x11, 100_000);
+        let mut b = f.holder(0x22, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        assert_eq!(f.deposit(&mut b, 1, 10_000), Ok(()));
+        // B hands 4_000 LP to A and 1_000 to C, who never deposited.
+        let mut c = f.holder(0x33, 0);
+        c.record = TestAccount::new(
+            c.record.key(),
+            false,
+            true,
+            solana_program::system_program::id(),
+            &[],
         );
-        // One basis point past the cap, and the u16 extreme, must also be rejected.
-        assert!(validate_hwm_floor_bps(MAX_HWM_FLOOR_BPS + 1).is_err());
-        assert!(validate_hwm_floor_bps(u16::MAX).is_err());
+        move_tokens(&mut b.lp_ata, &mut a.lp_ata, 4_000);
+        move_tokens(&mut b.lp_ata, &mut c.lp_ata, 1_000);
+
+        assert_eq!(
+            f.redeem_final(&mut a, 1_000),
+            Err(StakeError::PoolNotFinalized.into())
+        );
+        let slot = f.slot;
+        f.edit_pool(|p| p.finalized_at_slot = slot);
+
+        // Same slot as the deposit: the cooldown does not apply.
+        assert_eq!(f.redeem_final(&mut a, 13_000), Ok(()));
+        assert_eq!(token_balance(&a.ata), 90_000 + 13_000);
+        let dep = *bytemuck::from_bytes::<StakeDeposit>(&a.record.data()[..STAKE_DEPOSIT_SIZE]);
+        assert_eq!((dep.lp_amount, dep.is_initialized), (0, 0));
+
+        assert_eq!(f.redeem_final(&mut c, 1_000), Ok(()));
+        assert_eq!(token_balance(&c.ata), 1_000);
+        assert_eq!(token_balance(&c.lp_ata), 0);
+        assert_eq!(c.record.len(), 0);
+
+        assert_eq!(f.redeem_final(&mut b, 5_000), Ok(()));
+        let dep = *bytemuck::from_bytes::<StakeDeposit>(&b.record.data()[..STAKE_DEPOSIT_SIZE]);
+        assert_eq!(dep.lp_amount, 5_000);
+        assert_eq!(f.pool().total_lp_supply, state::MINIMUM_LIQUIDITY);
     }
 
+    /// With tranches on, the record is what attributes LP to a tranche, so
+    /// RedeemFinal still requires it.
     #[test]
-    fn test_validate_hwm_floor_bps_rejects_zero() {
-        // Unchanged: a 0 floor offers no anti-drain protection and is rejected on the
-        // (validated) enable path.
-        assert!(validate_hwm_floor_bps(0).is_err());
+    fn redeem_final_requires_record_with_tranches() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        f.edit_pool(|p| {
+            p.set_tranche_enabled(true);
+            p.set_junior_fee_mult_bps(10_000);
+        });
+        let mut a = f.holder(0x11, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        let mut c = f.holder(0x33, 0);
+        c.record = TestAccount::new(
+            c.record.key(),
+            false,
+            true,
+            solana_program::system_program::id(),
+            &[],
+        );
+        move_tokens(&mut a.lp_ata, &mut c.lp_ata, 1_000);
+        let slot = f.slot;
+        f.edit_pool(|p| p.finalized_at_slot = slot);
+
+        assert_eq!(
+            f.redeem_final(&mut c, 1_000),
+            Err(StakeError::InvalidAccount.into())
+        );
+        assert_eq!(token_balance(&c.lp_ata), 1_000);
     }
 
+    /// SweepAndClosePool leaves the pool System-owned and empty; deposit
+    /// records of it — emptied or still holding now-unbacked LP — can then
+    /// still be closed for their rent.
     #[test]
-    fn test_validate_hwm_floor_bps_accepts_legitimate_range_up_to_cap() {
-        // Every legitimate rate-limiter floor, including the new maximum, is accepted.
-        for bps in [1u16, 5_000, 7_500, MAX_HWM_FLOOR_BPS] {
-            assert!(
-                validate_hwm_floor_bps(bps).is_ok(),
-                "{bps} bps is a valid non-freezing floor and must be accepted"
-            );
+    fn close_deposit_after_pool_is_swept() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        let mut a = f.holder(0x11, 100_000);
+        let mut b = f.holder(0x22, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        assert_eq!(f.deposit(&mut b, 1, 10_000), Ok(()));
+        let (slot, recipient_key) = (f.slot, Pubkey::new_from_array([0x5F; 32]));
+        f.edit_pool(|p| {
+            p.finalized_at_slot = slot;
+            p.final_sweep_recipient = recipient_key.to_bytes();
+        });
+        assert_eq!(f.redeem_final(&mut a, 9_000), Ok(()));
+        // Live pool: B's open position still pins its record.
+        assert_eq!(
+            f.close_deposit(&mut b),
+            Err(StakeError::DepositNotEmpty.into())
+        );
+
+        f.slot += FINAL_SWEEP_GRACE_SLOTS;
+        let mut recipient = TestAccount::wallet(recipient_key);
+        assert_eq!(f.sweep(&mut recipient), Ok(()));
+        assert_eq!(f.pool.len(), 0);
+        assert_eq!(f.pool.owner, solana_program::system_program::id());
+
+        for h in [&mut a, &mut b] {
+            let (wallet, rent) = (h.wallet.lamports, h.record.lamports);
+            assert_eq!(f.close_deposit(h), Ok(()));
+            assert_eq!(h.wallet.lamports, wallet + rent);
+            assert_eq!((h.record.lamports, h.record.len()), (0, 0));
         }
-        // The cap is strictly below 100%, so a withdrawal margin always exists.
-        assert!(MAX_HWM_FLOOR_BPS < 10_000);
     }
 
-    // Behavioral proof through the same math the withdraw gate calls
-    // (processor.rs withdraw path -> math::hwm_floor / hwm_withdrawal_allowed): at the
-    // capped maximum a healthy pool still permits a positive withdrawal (the feature
-    // stays a limiter), whereas the now-rejected 100% value blocked even a 1-unit exit.
+    /// A position keeps the cooldown it deposited under: raising the pool's
+    /// afterwards does not hold it longer.
     #[test]
-    fn test_capped_floor_preserves_a_withdrawal_margin() {
-        use crate::math::{hwm_floor, hwm_withdrawal_allowed};
-
-        let tvl: u64 = 1_000_000;
-        let hwm = tvl; // refresh_hwm invariant: hwm >= current_tvl
-
-        let floor = hwm_floor(hwm, MAX_HWM_FLOOR_BPS).expect("floor computes");
-        assert!(floor < tvl, "capped floor must leave a withdrawable margin");
-        let margin = tvl - floor; // 100_000 at 9_000 bps (10% of peak)
-        assert!(margin > 0);
-        assert!(
-            hwm_withdrawal_allowed(tvl - margin, hwm, MAX_HWM_FLOOR_BPS),
-            "a withdrawal down to the floor must be allowed at the cap"
+    fn withdraw_ignores_cooldown_raised_after_deposit() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        let mut a = f.holder(0x11, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        f.edit_pool(|p| p.cooldown_slots = 1_000);
+
+        f.slot += 9;
+        assert_eq!(
+            f.withdraw(&mut a, 1_000, None),
+            Err(StakeError::CooldownNotElapsed.into())
         );
+        f.slot += 1;
+        assert_eq!(f.withdraw(&mut a, 1_000, None), Ok(()));
+    }
 
-        // Contrast: the rejected 100% value blocks even a single unit.
-        assert!(
-            !hwm_withdrawal_allowed(tvl - 1, hwm, 10_000),
-            "100% floor (now rejected by the setter) would block every withdrawal"
+    /// Lowering the pool's cooldown shortens every open position's.
+    #[test]
+    fn withdraw_applies_cooldown_lowered_after_deposit() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        f.edit_pool(|p| p.cooldown_slots = 100);
+        let mut a = f.holder(0x11, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        f.edit_pool(|p| p.cooldown_slots = 20);
+
+        f.slot += 19;
+        assert_eq!(
+            f.withdraw(&mut a, 1_000, None),
+            Err(StakeError::CooldownNotElapsed.into())
         );
+        f.slot += 1;
+        assert_eq!(f.withdraw(&mut a, 1_000, None), Ok(()));
     }
 
-    // ── #199: length-checked bytemuck reinterpretation helpers ──
-    //
-    // Every handler reinterprets account data via these helpers. They must return a
-    // clean ProgramError on an undersized/zero-length buffer instead of panicking on
-    // the `[..SIZE]` slice (the #199 bug), and must succeed on a correctly-sized buffer.
-    // The attack shape: a StakeDeposit-sized buffer (STAKE_DEPOSIT_SIZE < STAKE_POOL_SIZE)
-    // passed where a pool is expected.
-
+    /// Rage-quit waives the cooldown but not the HWM floor: with a cooldown
+    /// increase pending, an exit that would take TVL below half the epoch's
+    /// mark is still refused, and one above it goes through.
     #[test]
-    fn test_pool_helpers_reject_undersized_without_panic() {
-        assert!(
-            STAKE_DEPOSIT_SIZE < STAKE_POOL_SIZE,
-            "deposit-sized buffer is a valid undersize"
-        );
-        let mut deposit_sized = vec![0u8; STAKE_DEPOSIT_SIZE];
-        assert!(
-            pool_from_data_mut(&mut deposit_sized).is_err(),
-            "mut: undersized -> Err, not panic"
-        );
-        assert!(
-            pool_from_data(&deposit_sized).is_err(),
-            "ro: undersized -> Err, not panic"
+    fn rage_quit_still_applies_hwm_floor() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        f.edit_pool(|p| {
+            p.cooldown_slots = 1_000;
+            p.set_hwm_enabled(true);
+            p.set_hwm_floor_bps(5_000);
+        });
+        let mut a = f.holder(0x11, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        let admin = Pubkey::new_from_array([1u8; 32]);
+        let mut ix = vec![7u8];
+        ix.extend_from_slice(&5_000u64.to_le_bytes());
+        assert_eq!(f.admin_ix(admin, &ix), Ok(()));
+
+        f.slot += 1;
+        assert_eq!(
+            f.withdraw(&mut a, 6_000, None),
+            Err(StakeError::WithdrawalBelowHwmFloor.into())
         );
-
-        let mut one_short = vec![0u8; STAKE_POOL_SIZE - 1];
-        assert!(pool_from_data_mut(&mut one_short).is_err());
-        assert!(pool_from_data(&one_short).is_err());
-
-        let mut empty: Vec<u8> = Vec::new();
-        assert!(pool_from_data_mut(&mut empty).is_err());
-        assert!(pool_from_data(&empty).is_err());
+        assert_eq!(f.withdraw(&mut a, 4_000, None), Ok(()));
+        assert_eq!(f.pool().epoch_high_water_tvl(), 10_000);
     }
 
+    /// The waiver ends with the proposal: cancelling or accepting an admin
+    /// transfer, and cancelling or committing a cooldown increase, each put
+    /// the position back under its cooldown.
     #[test]
-    fn test_pool_helpers_accept_exact_and_oversized() {
-        let mut buf = vec![0u8; STAKE_POOL_SIZE];
-        let pool = pool_from_data_mut(&mut buf).expect("exact-size buffer must be Ok (mut)");
-        assert_eq!(pool.is_initialized, 0);
-        assert!(
-            pool_from_data(&buf).is_ok(),
-            "exact-size buffer must be Ok (ro)"
+    fn rage_quit_waiver_ends_on_cancel_or_accept() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        f.edit_pool(|p| p.cooldown_slots = 1_000_000);
+        let mut a = f.holder(0x11, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        f.slot += 1;
+        let cooling = Err(StakeError::CooldownNotElapsed.into());
+        assert_eq!(f.withdraw(&mut a, 100, None), cooling);
+
+        let (admin, next_admin) = (
+            Pubkey::new_from_array([1u8; 32]),
+            Pubkey::new_from_array([8u8; 32]),
         );
-        // Real Solana accounts may carry trailing bytes; only the first SIZE are read.
-        let mut over = vec![0u8; STAKE_POOL_SIZE + 32];
-        assert!(
-            pool_from_data_mut(&mut over).is_ok(),
-            "oversized buffer must be Ok"
+        let propose_admin = |new_admin: Pubkey| [&[5u8][..], new_admin.as_ref()].concat();
+        assert_eq!(f.admin_ix(admin, &propose_admin(next_admin)), Ok(()));
+        assert_eq!(f.withdraw(&mut a, 100, None), Ok(()));
+        assert_eq!(f.admin_ix(admin, &propose_admin(Pubkey::default())), Ok(()));
+        assert_eq!(f.withdraw(&mut a, 100, None), cooling);
+
+        assert_eq!(f.admin_ix(admin, &propose_admin(next_admin)), Ok(()));
+        assert_eq!(f.withdraw(&mut a, 100, None), Ok(()));
+        assert_eq!(f.admin_ix(next_admin, &[6u8]), Ok(()));
+        assert_eq!(f.withdraw(&mut a, 100, None), cooling);
+
+        let mut propose_cooldown = vec![7u8];
+        propose_cooldown.extend_from_slice(&2_000_000u64.to_le_bytes());
+        assert_eq!(f.admin_ix(next_admin, &propose_cooldown), Ok(()));
+        assert_eq!(f.withdraw(&mut a, 100, None), Ok(()));
+        assert_eq!(f.admin_ix(next_admin, &[9u8]), Ok(()));
+        assert_eq!(f.withdraw(&mut a, 100, None), cooling);
+
+        assert_eq!(f.admin_ix(next_admin, &propose_cooldown), Ok(()));
+        f.slot += TIMELOCK_SLOTS;
+        assert_eq!(f.withdraw(&mut a, 100, None), Ok(()));
+        assert_eq!(f.admin_ix(next_admin, &[8u8]), Ok(()));
+        assert_eq!(f.withdraw(&mut a, 100, None), cooling);
+        assert_eq!(f.pool().cooldown_slots, 2_000_000);
+    }
+
+    /// The rage-quit waiver lasts only as long as the proposal can still be
+    /// acted on: once an admin transfer or cooldown increase has expired, the
+    /// position's cooldown applies again until a fresh proposal is made.
+    #[test]
+    fn withdraw_cooldown_enforced_again_after_proposal_expires() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        f.edit_pool(|p| p.cooldown_slots = 2 * PROPOSAL_EXPIRY_SLOTS);
+        let mut a = f.holder(0x11, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        let proposed_at = f.slot;
+
+        f.edit_pool(|p| {
+            p.pending_admin = [8u8; 32];
+            p.admin_proposed_at_slot = proposed_at;
+        });
+        f.slot = proposed_at + PROPOSAL_EXPIRY_SLOTS;
+        assert_eq!(f.withdraw(&mut a, 1_000, None), Ok(()));
+        f.slot += 1;
+        assert_eq!(
+            f.withdraw(&mut a, 1_000, None),
+            Err(StakeError::CooldownNotElapsed.into())
+        );
+
+        f.edit_pool(|p| {
+            p.pending_admin = [0u8; 32];
+            p.set_pending_cooldown_slots(3 * PROPOSAL_EXPIRY_SLOTS);
+            p.set_cooldown_proposed_at_slot(proposed_at);
+        });
+        assert_eq!(
+            f.withdraw(&mut a, 1_000, None),
+            Err(StakeError::CooldownNotElapsed.into())
         );
+        // Re-proposing restarts the window, and the waiver with it.
+        let now = f.slot;
+        f.edit_pool(|p| p.set_cooldown_proposed_at_slot(now));
+        assert_eq!(f.withdraw(&mut a, 1_000, None), Ok(()));
     }
 
+    /// A record from before cooldowns were recorded is held to the pool's
+    /// current one, and its first withdrawal stamps that in: a later raise
+    /// no longer reaches it.
     #[test]
-    fn test_deposit_helpers_reject_undersized_without_panic() {
-        let mut short = vec![0u8; STAKE_DEPOSIT_SIZE - 1];
-        assert!(deposit_from_data_mut(&mut short).is_err());
-        assert!(deposit_from_data(&short).is_err());
-        let empty: Vec<u8> = Vec::new();
-        assert!(deposit_from_data(&empty).is_err());
+    fn withdraw_stamps_legacy_record_cooldown() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        let mut a = f.holder(0x11, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        a.edit_record(|d| d.set_recorded_cooldown_slots(0));
+
+        f.slot += 10;
+        assert_eq!(f.withdraw(&mut a, 1_000, None), Ok(()));
+        let dep = *bytemuck::from_bytes::<StakeDeposit>(&a.record.data()[..STAKE_DEPOSIT_SIZE]);
+        assert_eq!(dep.recorded_cooldown_slots(), Some(10));
+
+        f.edit_pool(|p| p.cooldown_slots = 1_000);
+        assert_eq!(f.withdraw(&mut a, 1_000, None), Ok(()));
     }
 
+    /// A pool whose value overflows still yields a record — the "no price"
+    /// `0 / 0` — rather than failing an instruction after its CPIs ran.
     #[test]
-    fn test_deposit_helpers_accept_exact_size() {
-        let mut buf = vec![0u8; STAKE_DEPOSIT_SIZE];
-        assert!(deposit_from_data_mut(&mut buf).is_ok());
-        assert!(deposit_from_data(&buf).is_ok());
+    fn unpriceable_pool_reports_no_price_instead_of_failing() {
+        let mut pool = StakePool::zeroed();
+        pool.total_deposited = u64::MAX;
+        pool.total_fees_earned = 1;
+        pool.total_lp_supply = 7;
+        assert_eq!(pool.total_pool_value(), None);
+        let rd = crate::return_data::StakeReturnData::with_share_price(&pool, 12, TRANCHE_POOL);
+        assert_eq!((rd.share_price_num, rd.share_price_den), (0, 0));
     }
 
-    /// #210 PoC: `SetMarketResolved` must reject a non-writable pool account
-    /// before mutating its state, like every other state-mutating handler.
+    /// Roster changes are two-step and need the NEW members' signatures; an
+    /// active council then stands in for the admin on admin-gated calls, and
+    /// only the admin can accept its dissolution.
     #[test]
-    fn poc_set_market_resolved_should_reject_readonly_pool_account() {
+    fn council_two_step_roster_and_admin_authority() {
         let program_id = Pubkey::new_from_array([9u8; 32]);
-        let admin_key = Pubkey::new_from_array([1u8; 32]);
-        let pool_key = Pubkey::new_from_array([2u8; 32]);
-        let system_program_id = solana_program::system_program::id();
+        let admin = Pubkey::new_from_array([1u8; 32]);
+        let (pool_key, _) = state::derive_pool_pda(&program_id, &Pubkey::new_from_array([3u8; 32]));
+        let (council_key, council_bump) = state::derive_council_pda(&program_id, &pool_key);
+        let system = solana_program::system_program::id();
+        let m: Vec<Pubkey> = (0..3)
+            .map(|i| Pubkey::new_from_array([0x40 + i; 32]))
+            .collect();
 
         let mut pool = StakePool::zeroed();
         pool.is_initialized = 1;
-        pool.admin = admin_key.to_bytes();
-        pool.set_discriminator();
-        pool.set_market_resolved(false);
-
-        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
-
-        let mut admin_lamports = 0u64;
-        let mut pool_lamports = 0u64;
-
-        let mut admin_data = vec![];
-
-        let accounts = vec![
-            AccountInfo::new(
-                &admin_key,
-                true,
-                false,
-                &mut admin_lamports,
-                &mut admin_data,
-                &system_program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &pool_key,
-                false,
-                false, // intentionally NOT writable
-                &mut pool_lamports,
-                &mut pool_data,
-                &program_id,
-                false,
-                0,
-            ),
+        pool.admin = admin.to_bytes();
+        pool.set_discriminator();
+        // An existing (e.g. previously dissolved) council record; creating
+        // one needs the system program, which native tests cannot invoke.
+        let mut council = StakeCouncil::zeroed();
+        council.set_discriminator();
+        council.is_initialized = 1;
+        council.bump = council_bump;
+        council.pool = pool_key.to_bytes();
+        let mut datas = vec![
+            bytemuck::bytes_of(&pool).to_vec(),
+            bytemuck::bytes_of(&council).to_vec(),
         ];
 
-        let result = process(&program_id, &accounts, &[18u8]);
+        let propose = |signer: (Pubkey, bool),
+                       extra: &[Pubkey],
+                       threshold: u8,
+                       roster: &[Pubkey],
+                       datas: &mut Vec<Vec<u8>>| {
+            let owner0 = if signer.1 { system } else { program_id };
+            let mut metas = vec![
+                (signer.0, signer.1, false, owner0),
+                (pool_key, false, false, program_id),
+                (council_key, false, true, program_id),
+                (admin, true, true, system),
+                (system, false, false, system),
+            ];
+            metas.extend(extra.iter().map(|k| (*k, true, false, system)));
+            let mut ds = vec![
+                if signer.1 { vec![] } else { datas[1].clone() },
+                datas[0].clone(),
+                datas[1].clone(),
+                vec![],
+                vec![],
+            ];
+            ds.extend(extra.iter().map(|_| vec![]));
+            let mut ix = vec![46u8, threshold];
+            for k in roster {
+                ix.extend_from_slice(k.as_ref());
+            }
+            let r = run_with(&program_id, &metas, &mut ds, &ix);
+            datas[1] = ds[2].clone();
+            r
+        };
+        let accept = |signers: &[Pubkey], datas: &mut Vec<Vec<u8>>| {
+            let mut metas = vec![
+                (pool_key, false, true, program_id),
+                (council_key, false, true, program_id),
+            ];
+            metas.extend(signers.iter().map(|k| (*k, true, false, system)));
+            let mut ds = vec![datas[0].clone(), datas[1].clone()];
+            ds.extend(signers.iter().map(|_| vec![]));
+            let r = run_with(&program_id, &metas, &mut ds, &[47u8]);
+            datas[0] = ds[0].clone();
+            datas[1] = ds[1].clone();
+            r
+        };
+        // SetVetoThreshold(1_000) with the council in the admin slot.
+        let council_call = |signers: &[Pubkey], datas: &mut Vec<Vec<u8>>| {
+            let mut metas = vec![
+                (council_key, false, false, program_id),
+                (pool_key, false, true, program_id),
+            ];
+            metas.extend(signers.iter().map(|k| (*k, true, false, system)));
+            let mut ds = vec![datas[1].clone(), datas[0].clone()];
+            ds.extend(signers.iter().map(|_| vec![]));
+            let r = run_with(&program_id, &metas, &mut ds, &[45u8, 0xE8, 0x03]);
+            datas[0] = ds[1].clone();
+            r
+        };
 
         assert_eq!(
-            result,
-            Err(StakeError::InvalidAccount.into()),
-            "SetMarketResolved should fail with InvalidAccount for a read-only pool account"
+            accept(&[m[0], m[1]], &mut datas),
+            Err(StakeError::NoPendingCouncil.into())
+        );
+        assert_eq!(
+            propose((admin, true), &[], 4, &m, &mut datas),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(
+            propose((m[0], true), &[], 2, &m, &mut datas),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(propose((admin, true), &[], 2, &m, &mut datas), Ok(()));
+        // Not linked yet: the council cannot act for the admin.
+        assert_eq!(
+            council_call(&[m[0], m[1]], &mut datas),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        // One distinct member, even listed twice, is below 2-of-3.
+        assert_eq!(
+            accept(&[m[0], m[0], admin], &mut datas),
+            Err(StakeError::CouncilThresholdNotMet.into())
+        );
+        assert_eq!(accept(&[m[2], m[0]], &mut datas), Ok(()));
+        let linked: StakePool = bytemuck::pod_read_unaligned(&datas[0][..]);
+        assert_eq!(linked.council, council_key.to_bytes());
+
+        assert_eq!(
+            council_call(&[m[1]], &mut datas),
+            Err(StakeError::CouncilThresholdNotMet.into())
+        );
+        assert_eq!(council_call(&[m[1], m[2]], &mut datas), Ok(()));
+        let stored: StakePool = bytemuck::pod_read_unaligned(&datas[0][..]);
+        assert_eq!(stored.veto_threshold_bps, 1_000);
+
+        // The council proposes its own dissolution; only the admin accepts it.
+        assert_eq!(
+            propose((council_key, false), &[m[0], m[1]], 0, &[], &mut datas),
+            Ok(())
+        );
+        assert_eq!(
+            accept(&[m[0], m[1], m[2]], &mut datas),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(accept(&[admin], &mut datas), Ok(()));
+        let dissolved: StakePool = bytemuck::pod_read_unaligned(&datas[0][..]);
+        assert_eq!(dissolved.council, [0u8; 32]);
+        assert_eq!(
+            council_call(&[m[1], m[2]], &mut datas),
+            Err(ProgramError::MissingRequiredSignature)
         );
     }
 
-    /// H-1: SetMarketResolved must reject when flushed insurance has not been
-    /// recovered from the wrapper (total_flushed > total_recovered_from_wrapper)
-    /// — defense-in-depth mirroring the primary gate on AdminResolveMarket.
+    /// A role is the admin's until assigned; once accepted only its holder
+    /// passes the role's checks, and revoking hands it straight back. The
+    /// admin keeps its own powers throughout.
     #[test]
-    fn h1_set_market_resolved_blocks_when_flushed_not_returned() {
+    fn roles_rotate_two_step_and_gate_instructions() {
         let program_id = Pubkey::new_from_array([9u8; 32]);
-        let admin_key = Pubkey::new_from_array([1u8; 32]);
-        let pool_key = Pubkey::new_from_array([2u8; 32]);
-        let system_program_id = solana_program::system_program::id();
+        let admin = Pubkey::new_from_array([1u8; 32]);
+        let ops = Pubkey::new_from_array([0x50; 32]);
+        let stranger = Pubkey::new_from_array([0x51; 32]);
+        let (pool_key, _) = state::derive_pool_pda(&program_id, &Pubkey::new_from_array([3u8; 32]));
+        let proposal_key = Pubkey::new_from_array([0x52; 32]);
+        let system = solana_program::system_program::id();
 
         let mut pool = StakePool::zeroed();
         pool.is_initialized = 1;
-        pool.admin = admin_key.to_bytes();
-        pool.total_flushed = 500;
-        pool.total_returned = 200; // 300 outstanding
+        pool.admin = admin.to_bytes();
         pool.set_discriminator();
+        let mut datas = vec![bytemuck::bytes_of(&pool).to_vec()];
+
+        let call = |caller: Pubkey, ix: &[u8], datas: &mut Vec<Vec<u8>>| {
+            let metas = [
+                (caller, true, false, system),
+                (pool_key, false, true, program_id),
+            ];
+            let mut ds = vec![vec![], datas[0].clone()];
+            let r = run_with(&program_id, &metas, &mut ds, ix);
+            datas[0] = ds[1].clone();
+            r
+        };
+        // CancelFeeChange on a fresh pending trade-fee proposal: the fee
+        // manager's check (or the admin's).
+        let cancel_fee = |caller: Pubkey, datas: &mut Vec<Vec<u8>>| {
+            let mut proposal = pending_trade_fee_proposal();
+            proposal.pool = pool_key.to_bytes();
+            let metas = [
+                (caller, true, false, system),
+                (pool_key, false, false, program_id),
+                (proposal_key, false, true, program_id),
+            ];
+            let mut ds = vec![
+                vec![],
+                datas[0].clone(),
+                bytemuck::bytes_of(&proposal).to_vec(),
+            ];
+            run_with(&program_id, &metas, &mut ds, &[41u8])
+        };
+        let propose_fee_manager = |holder: Pubkey| {
+            let mut ix = vec![48u8, ROLE_FEE_MANAGER];
+            ix.extend_from_slice(holder.as_ref());
+            ix
+        };
+        let accept_fee_manager = [49u8, ROLE_FEE_MANAGER];
+        let veto = |bps: u16| {
+            let mut ix = vec![45u8];
+            ix.extend_from_slice(&bps.to_le_bytes());
+            ix
+        };
 
-        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
-        let mut admin_lamports = 0u64;
-        let mut pool_lamports = 0u64;
-        let mut admin_data = vec![];
+        // Unassigned: the admin holds the fee manager's powers.
+        assert_eq!(cancel_fee(admin, &mut datas), Ok(()));
+        assert_eq!(
+            cancel_fee(ops, &mut datas),
+            Err(StakeError::Unauthorized.into())
+        );
 
-        let accounts = vec![
-            AccountInfo::new(
-                &admin_key,
-                true,
-                false,
-                &mut admin_lamports,
-                &mut admin_data,
-                &system_program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &pool_key,
-                false,
-                true,
-                &mut pool_lamports,
-                &mut pool_data,
-                &program_id,
-                false,
-                0,
-            ),
-        ];
+        assert_eq!(
+            call(ops, &accept_fee_manager, &mut datas),
+            Err(StakeError::NoPendingRole.into())
+        );
+        assert_eq!(
+            call(ops, &propose_fee_manager(ops), &mut datas),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(call(admin, &propose_fee_manager(ops), &mut datas), Ok(()));
+        // Re-proposing the current (unassigned) holder cancels the rotation.
+        assert_eq!(
+            call(admin, &propose_fee_manager(Pubkey::default()), &mut datas),
+            Ok(())
+        );
+        assert_eq!(
+            call(ops, &accept_fee_manager, &mut datas),
+            Err(StakeError::NoPendingRole.into())
+        );
 
-        let result = process(&program_id, &accounts, &[18u8]);
+        assert_eq!(call(admin, &propose_fee_manager(ops), &mut datas), Ok(()));
         assert_eq!(
-            result,
-            Err(StakeError::InsuranceLossOutstanding.into()),
-            "SetMarketResolved must reject while total_flushed > total_recovered_from_wrapper"
+            call(stranger, &accept_fee_manager, &mut datas),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(call(ops, &accept_fee_manager, &mut datas), Ok(()));
+        let stored: StakePool = bytemuck::pod_read_unaligned(&datas[0][..]);
+        assert_eq!(stored.role_holder(ROLE_FEE_MANAGER), ops.to_bytes());
+        assert_eq!(stored.pending_roles, [[0u8; 32]; state::ROLE_COUNT]);
+
+        // Assigned: the holder acts, the other roles still fall back to the
+        // admin, and the holder gains none of the admin's own powers.
+        assert_eq!(cancel_fee(ops, &mut datas), Ok(()));
+        assert_eq!(
+            cancel_fee(stranger, &mut datas),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(stored.role_holder(ROLE_FLUSH_OPERATOR), [0u8; 32]);
+        assert_eq!(
+            call(ops, &veto(500), &mut datas),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(call(admin, &veto(500), &mut datas), Ok(()));
+
+        // Revoking needs no acceptance.
+        assert_eq!(
+            call(ops, &propose_fee_manager(Pubkey::default()), &mut datas),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(
+            call(admin, &propose_fee_manager(Pubkey::default()), &mut datas),
+            Ok(())
+        );
+        assert_eq!(
+            cancel_fee(ops, &mut datas),
+            Err(StakeError::Unauthorized.into())
         );
     }
 
-    /// H-1 RE-REVIEW REGRESSION: SetMarketResolved must STILL reject when
-    /// `total_returned` alone has caught up with `total_flushed` via
-    /// `ReturnInsurance`-style bookkeeping (admin's own wallet -> pool.vault, no
-    /// wrapper CPI) but `total_recovered_from_wrapper` has NOT. This is exactly
-    /// the H-1 re-review finding: gating on `total_returned` let an admin
-    /// satisfy the check without ever recovering the flushed capital from the
-    /// wrapper, stranding it forever post-resolution.
+    /// A pending admin transfer or cooldown increase stops being actionable
+    /// `PROPOSAL_EXPIRY_SLOTS` after it was made; re-proposing restarts it.
     #[test]
-    fn h1_set_market_resolved_blocks_when_only_total_returned_caught_up() {
+    fn admin_and_cooldown_proposals_expire() {
         let program_id = Pubkey::new_from_array([9u8; 32]);
-        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let admin = Pubkey::new_from_array([1u8; 32]);
+        let next_admin = Pubkey::new_from_array([0x60; 32]);
         let pool_key = Pubkey::new_from_array([2u8; 32]);
-        let system_program_id = solana_program::system_program::id();
+        let clock_id = solana_program::sysvar::clock::id();
+        let system = solana_program::system_program::id();
 
         let mut pool = StakePool::zeroed();
         pool.is_initialized = 1;
-        pool.admin = admin_key.to_bytes();
-        pool.total_flushed = 500;
-        pool.total_returned = 500; // fully "returned" via ReturnInsurance-style bookkeeping
-        pool.total_recovered_from_wrapper = 0; // but NOTHING actually recovered from the wrapper
+        pool.admin = admin.to_bytes();
+        pool.cooldown_slots = 10;
         pool.set_discriminator();
-
         let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
-        let mut admin_lamports = 0u64;
-        let mut pool_lamports = 0u64;
-        let mut admin_data = vec![];
 
-        let accounts = vec![
-            AccountInfo::new(
-                &admin_key,
-                true,
-                false,
-                &mut admin_lamports,
-                &mut admin_data,
-                &system_program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &pool_key,
-                false,
-                true,
-                &mut pool_lamports,
-                &mut pool_data,
-                &program_id,
-                false,
-                0,
-            ),
-        ];
+        // Tags 5/6 read `Clock::get`; 7/8 the clock account.
+        let _double = CpiDouble::enable();
+        let mut call = |signer: Pubkey, slot: u64, ix: &[u8]| {
+            CpiDouble::set_slot(slot);
+            let metas = [
+                (signer, true, false, system),
+                (pool_key, false, true, program_id),
+                (clock_id, false, false, solana_program::sysvar::id()),
+            ];
+            let mut ds = vec![vec![], pool_data.clone(), clock_bytes(slot)];
+            let r = run_with(&program_id, &metas, &mut ds, ix);
+            pool_data = ds[1].clone();
+            r
+        };
+        let mut propose = vec![5u8];
+        propose.extend_from_slice(next_admin.as_ref());
 
-        let result = process(&program_id, &accounts, &[18u8]);
+        assert_eq!(call(admin, 100, &propose), Ok(()));
         assert_eq!(
-            result,
-            Err(StakeError::InsuranceLossOutstanding.into()),
-            "SetMarketResolved must reject on total_returned alone — total_recovered_from_wrapper is the correct gate"
+            call(next_admin, 100 + PROPOSAL_EXPIRY_SLOTS + 1, &[6u8]),
+            Err(StakeError::ProposalExpired.into())
+        );
+        // Re-proposing the same key restarts the window.
+        assert_eq!(call(admin, 5_000, &propose), Ok(()));
+        assert_eq!(
+            call(next_admin, 5_000 + PROPOSAL_EXPIRY_SLOTS, &[6u8]),
+            Ok(())
         );
 
-        let pool_after = pool_from_data(&pool_data).unwrap();
-        assert!(
-            !pool_after.market_resolved(),
-            "pool must NOT be marked resolved"
+        let mut increase = vec![7u8];
+        increase.extend_from_slice(&20u64.to_le_bytes());
+        assert_eq!(call(next_admin, 100, &increase), Ok(()));
+        assert_eq!(
+            call(next_admin, 100 + PROPOSAL_EXPIRY_SLOTS + 1, &[8u8]),
+            Err(StakeError::ProposalExpired.into())
         );
+        assert_eq!(call(next_admin, 100 + TIMELOCK_SLOTS, &[8u8]), Ok(()));
+
+        let stored: StakePool = bytemuck::pod_read_unaligned(&pool_data[..]);
+        assert_eq!(stored.admin, next_admin.to_bytes());
+        assert_eq!(stored.pending_admin, [0u8; 32]);
+        assert_eq!(stored.admin_proposed_at_slot, 0);
+        assert_eq!(stored.cooldown_slots, 20);
+
+        assert!(!proposal_expired(100, 10, 110));
+        assert!(proposal_expired(100, 10, 111));
+        assert!(!proposal_expired(100, 10, 50));
     }
 
-    /// H-1: SetMarketResolved succeeds once total_recovered_from_wrapper has
-    /// caught up with total_flushed (nothing outstanding to recover from the
-    /// wrapper).
+    /// Locks are one-way and gate their settings; a locked flush policy opens
+    /// a bounded permissionless flush; renouncing needs every lock and leaves
+    /// no key with authority.
     #[test]
-    fn h1_set_market_resolved_allows_when_fully_recovered_from_wrapper() {
+    fn lock_config_policy_flush_and_renounce_admin() {
         let program_id = Pubkey::new_from_array([9u8; 32]);
-        let admin_key = Pubkey::new_from_array([1u8; 32]);
+        let admin = Pubkey::new_from_array([1u8; 32]);
+        let stranger = Pubkey::new_from_array([0x70; 32]);
         let pool_key = Pubkey::new_from_array([2u8; 32]);
-        let system_program_id = solana_program::system_program::id();
+        let vault = Pubkey::new_from_array([0x71; 32]);
+        let slab = Pubkey::new_from_array([0x72; 32]);
+        let wrapper_vault = Pubkey::new_from_array([0x73; 32]);
+        let percolator = Pubkey::new_from_array([0x74; 32]);
+        let mint = [0x75u8; 32];
+        let (vault_auth, _) = state::derive_vault_authority(&program_id, &pool_key);
+        let system = solana_program::system_program::id();
+        let token = crate::spl_token::id();
 
         let mut pool = StakePool::zeroed();
         pool.is_initialized = 1;
-        pool.admin = admin_key.to_bytes();
-        pool.total_flushed = 500;
-        pool.total_returned = 500;
-        pool.total_recovered_from_wrapper = 500; // fully recovered FROM THE WRAPPER
+        pool.admin = admin.to_bytes();
+        pool.vault = vault.to_bytes();
+        pool.slab = slab.to_bytes();
+        pool.percolator_program = percolator.to_bytes();
+        pool.collateral_mint = mint;
+        pool.total_deposited = 1_000;
         pool.set_discriminator();
-
         let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
-        let mut admin_lamports = 0u64;
-        let mut pool_lamports = 0u64;
-        let mut admin_data = vec![];
 
-        let accounts = vec![
-            AccountInfo::new(
-                &admin_key,
-                true,
-                false,
-                &mut admin_lamports,
-                &mut admin_data,
-                &system_program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &pool_key,
-                false,
-                true,
-                &mut pool_lamports,
-                &mut pool_data,
-                &program_id,
-                false,
-                0,
-            ),
-        ];
+        let call = |signer: Pubkey, ix: &[u8], pool_data: &mut Vec<u8>| {
+            let metas = [
+                (signer, true, false, system),
+                (pool_key, false, true, program_id),
+            ];
+            let mut ds = vec![vec![], pool_data.clone()];
+            let r = run_with(&program_id, &metas, &mut ds, ix);
+            *pool_data = ds[1].clone();
+            r
+        };
+        // FlushToInsurance up to the point of its CPI.
+        let flush = |signer: Pubkey, amount: u64, pool_data: &[u8]| {
+            let metas = [
+                (signer, true, false, system),
+                (pool_key, false, true, program_id),
+                (vault, false, true, token),
+                (vault_auth, false, false, system),
+                (slab, false, true, percolator),
+                (wrapper_vault, false, true, token),
+                (percolator, false, false, system),
+                (token, false, false, system),
+            ];
+            let mut wrapper_vault_data = vec![0u8; crate::spl_token::state::ACCOUNT_LEN];
+            wrapper_vault_data[0..32].copy_from_slice(&mint);
+            let mut ds = vec![
+                vec![],
+                pool_data.to_vec(),
+                vec![],
+                vec![],
+                vec![],
+                wrapper_vault_data,
+                vec![],
+                vec![],
+            ];
+            let mut ix = vec![3u8];
+            ix.extend_from_slice(&amount.to_le_bytes());
+            run_with(&program_id, &metas, &mut ds, &ix)
+        };
 
-        let result = process(&program_id, &accounts, &[18u8]);
-        assert!(
-            result.is_ok(),
-            "SetMarketResolved must succeed once fully recovered from wrapper: {result:?}"
+        // No policy: flushing stays with the flush operator (here the admin).
+        assert_eq!(
+            flush(stranger, 100, &pool_data),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(
+            call(admin, &[50u8, 0x10, 0x27 + 1], &mut pool_data),
+            Err(ProgramError::InvalidArgument)
+        );
+        assert_eq!(call(admin, &[50u8, 0xD0, 0x07], &mut pool_data), Ok(()));
+        // Set but not locked: still not permissionless.
+        assert_eq!(
+            flush(stranger, 100, &pool_data),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(
+            call(stranger, &[51u8, CONFIG_LOCK_FLUSH_POLICY], &mut pool_data),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(
+            call(admin, &[51u8, CONFIG_LOCK_FLUSH_POLICY], &mut pool_data),
+            Ok(())
+        );
+        assert_eq!(
+            call(admin, &[50u8, 0, 0], &mut pool_data),
+            Err(StakeError::ConfigLocked.into())
+        );
+        // 2_000 bps of 1_000: 201 would leave too much in the wrapper.
+        assert_eq!(
+            flush(stranger, 201, &pool_data),
+            Err(StakeError::FlushPolicyExceeded.into())
         );
 
-        let pool_after = pool_from_data(&pool_data).unwrap();
-        assert!(pool_after.market_resolved(), "pool must be marked resolved");
+        assert_eq!(
+            call(admin, &[52u8], &mut pool_data),
+            Err(StakeError::ConfigNotLocked.into())
+        );
+        assert_eq!(
+            call(admin, &[51u8, CONFIG_LOCK_ALL], &mut pool_data),
+            Ok(())
+        );
+        let mut cap = vec![4u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
+        cap.extend_from_slice(&5_000u64.to_le_bytes());
+        assert_eq!(
+            call(admin, &cap, &mut pool_data),
+            Err(StakeError::ConfigLocked.into())
+        );
+        assert_eq!(
+            call(admin, &[14u8, 1, 0x10, 0x27], &mut pool_data),
+            Err(StakeError::ConfigLocked.into())
+        );
+
+        assert_eq!(
+            call(stranger, &[52u8], &mut pool_data),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(call(admin, &[52u8], &mut pool_data), Ok(()));
+        let stored: StakePool = bytemuck::pod_read_unaligned(&pool_data[..]);
+        assert_eq!(stored.admin, [0u8; 32]);
+        assert_eq!(stored.config_locks, CONFIG_LOCK_ALL);
+        // Nobody holds the admin or any fallen-back role any more.
+        assert_eq!(
+            call(admin, &[52u8], &mut pool_data),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(
+            call(admin, &[45u8, 0xE8, 0x03], &mut pool_data),
+            Err(StakeError::Unauthorized.into())
+        );
+        assert_eq!(
+            flush(admin, 201, &pool_data),
+            Err(StakeError::FlushPolicyExceeded.into())
+        );
     }
 
-    /// C-1: AdminResolveMarket must reject a signer who is not pool.admin,
-    /// proving the new CPI proxy is admin-gated. This path returns before the
-    /// wrapper CPI is ever issued, so no real percolator program is needed.
+    /// The vault must move by exactly the requested amount, in the expected
+    /// direction, for a token-moving CPI to be booked.
     #[test]
-    fn c1_admin_resolve_market_rejects_non_admin_signer() {
-        let program_id = Pubkey::new_from_array([9u8; 32]);
-        let admin_key = Pubkey::new_from_array([1u8; 32]);
-        let attacker_key = Pubkey::new_from_array([0xAAu8; 32]);
-        let pool_key = Pubkey::new_from_array([2u8; 32]);
-        let slab_key = Pubkey::new_from_array([3u8; 32]);
-        let percolator_program_id = Pubkey::new_from_array([4u8; 32]);
-        let system_program_id = solana_program::system_program::id();
+    fn cpi_vault_delta_must_match_the_requested_amount() {
+        assert_eq!(
+            observed_vault_delta(1_000, 1_250, VaultFlow::In, 250),
+            Ok(250)
+        );
+        assert_eq!(
+            observed_vault_delta(1_000, 750, VaultFlow::Out, 250),
+            Ok(250)
+        );
+        let mismatch = Err(StakeError::VaultDeltaMismatch.into());
+        // Short (a fee or partial fill), long, and the wrong direction.
+        assert_eq!(
+            observed_vault_delta(1_000, 1_249, VaultFlow::In, 250),
+            mismatch
+        );
+        assert_eq!(
+            observed_vault_delta(1_000, 749, VaultFlow::Out, 250),
+            mismatch
+        );
+        assert_eq!(
+            observed_vault_delta(1_000, 1_250, VaultFlow::Out, 250),
+            mismatch
+        );
+        assert_eq!(
+            observed_vault_delta(1_000, 1_000, VaultFlow::In, 250),
+            mismatch
+        );
+
+        let key = Pubkey::new_from_array([7u8; 32]);
+        let token = crate::spl_token::id();
+        let mut lamports = 1_000_000u64;
+        let mut data = vec![0u8; crate::spl_token::state::ACCOUNT_LEN];
+        data[64..72].copy_from_slice(&1_000u64.to_le_bytes());
+        data[108] = 1; // Initialized
+        let vault = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &token,
+            false,
+            0,
+        );
+        // A callee that keeps 1 token as a fee: the CPI "succeeds" but nothing books.
+        let set_balance = |amount: u64| {
+            vault.try_borrow_mut_data()?[64..72].copy_from_slice(&amount.to_le_bytes());
+            Ok(())
+        };
+        assert_eq!(
+            cpi_with_vault_delta(&vault, VaultFlow::Out, 100, || set_balance(901)),
+            Err(StakeError::VaultDeltaMismatch.into())
+        );
+        assert_eq!(
+            cpi_with_vault_delta(&vault, VaultFlow::Out, 100, || set_balance(801)),
+            Ok(100)
+        );
+        // A failing CPI propagates its own error before any balance is compared.
+        assert_eq!(
+            cpi_with_vault_delta(&vault, VaultFlow::In, 100, || Err(
+                ProgramError::InvalidArgument
+            )),
+            Err(ProgramError::InvalidArgument)
+        );
+        // Not a token account: rejected before the CPI runs.
+        let system = solana_program::system_program::id();
+        let mut lamports = 1_000_000u64;
+        let mut data = vec![0u8; crate::spl_token::state::ACCOUNT_LEN];
+        let fake = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &system,
+            false,
+            0,
+        );
+        assert_eq!(
+            cpi_with_vault_delta(&fake, VaultFlow::In, 100, || panic!("CPI must not run")),
+            Err(StakeError::InvalidAccount.into())
+        );
+    }
+
+    /// AssertPoolInvariants passes on consistent books (a vault surplus is
+    /// fine) and names the first invariant that breaks.
+    #[test]
+    fn assert_pool_invariants_reports_each_violation() {
+        let program_id = Pubkey::new_from_array([9u8; 32]);
+        let slab = Pubkey::new_from_array([3u8; 32]);
+        let (pool_key, _) = state::derive_pool_pda(&program_id, &slab);
+        let vault = Pubkey::new_from_array([4u8; 32]);
+        let lp_mint = Pubkey::new_from_array([5u8; 32]);
+        let percolator = Pubkey::new_from_array([6u8; 32]);
+        let token = crate::spl_token::id();
+        let mint = [8u8; 32];
 
         let mut pool = StakePool::zeroed();
         pool.is_initialized = 1;
-        pool.admin = admin_key.to_bytes(); // real admin — attacker will sign instead
-        pool.slab = slab_key.to_bytes();
-        pool.percolator_program = percolator_program_id.to_bytes();
-        pool.bump = 255;
+        pool.slab = slab.to_bytes();
+        pool.percolator_program = percolator.to_bytes();
+        pool.wrapper_abi_version = cpi::WRAPPER_ABI_V17;
+        pool.vault = vault.to_bytes();
+        pool.lp_mint = lp_mint.to_bytes();
+        pool.collateral_mint = mint;
+        pool.total_deposited = 10_000;
+        pool.total_flushed = 4_000;
+        pool.total_lp_supply = 10_000;
         pool.set_discriminator();
 
-        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
-        let mut attacker_lamports = 0u64;
-        let mut pool_lamports = 0u64;
-        let mut slab_lamports = 0u64;
-        let mut program_lamports = 0u64;
-        let mut attacker_data = vec![];
-        let mut slab_data = vec![];
-        let mut program_data = vec![];
+        let token_account = |amount: u64| {
+            let mut d = vec![0u8; crate::spl_token::state::ACCOUNT_LEN];
+            d[0..32].copy_from_slice(&mint);
+            d[64..72].copy_from_slice(&amount.to_le_bytes());
+            d[108] = 1;
+            d
+        };
+        // A one-asset v17 market holding `insurance` in asset 0's slot.
+        let market = |insurance: u128| {
+            let mut d = vec![0u8; 3147];
+            d[0] = 1;
+            d[1350 + 96..1350 + 112].copy_from_slice(&insurance.to_le_bytes());
+            d
+        };
+        let assert_with =
+            |pool: &StakePool, vault_balance: u64, supply: u64, wrapper: Option<u128>| {
+                let mut mint_data = vec![0u8; crate::spl_token::state::MINT_LEN];
+                mint_data[36..44].copy_from_slice(&supply.to_le_bytes());
+                mint_data[45] = 1;
+                let mut metas = vec![
+                    (pool_key, false, false, program_id),
+                    (vault, false, false, token),
+                    (lp_mint, false, false, token),
+                ];
+                let mut ds = vec![
+                    bytemuck::bytes_of(pool).to_vec(),
+                    token_account(vault_balance),
+                    mint_data,
+                ];
+                if let Some(insurance) = wrapper {
+                    metas.push((slab, false, false, percolator));
+                    ds.push(market(insurance));
+                }
+                run_with(&program_id, &metas, &mut ds, &[53u8])
+            };
+        let assert = |pool: &StakePool, vault_balance: u64, supply: u64| {
+            assert_with(pool, vault_balance, supply, None)
+        };
 
-        let accounts = vec![
-            AccountInfo::new(
-                &attacker_key,
-                true,
-                false,
-                &mut attacker_lamports,
-                &mut attacker_data,
-                &system_program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &pool_key,
-                false,
-                false,
-                &mut pool_lamports,
-                &mut pool_data,
-                &program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &slab_key,
-                false,
-                true,
-                &mut slab_lamports,
-                &mut slab_data,
-                &percolator_program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &percolator_program_id,
-                false,
-                false,
-                &mut program_lamports,
-                &mut program_data,
-                &system_program_id,
-                true,
-                0,
-            ),
-        ];
+        let supply = 10_000 - state::MINIMUM_LIQUIDITY;
+        assert_eq!(assert(&pool, 6_000, supply), Ok(()));
+        assert_eq!(assert(&pool, 6_500, supply - 1), Ok(()));
+        assert_eq!(
+            assert(&pool, 5_999, supply),
+            Err(StakeError::InvariantVaultShortfall.into())
+        );
+        assert_eq!(
+            assert(&pool, 6_000, supply + 1),
+            Err(StakeError::InvariantLpSupplyMismatch.into())
+        );
 
-        let result = process(&program_id, &accounts, &[24u8]);
+        // The wrapper must still hold what the pool can recover from it.
+        assert_eq!(assert_with(&pool, 6_000, supply, Some(4_000)), Ok(()));
         assert_eq!(
-            result,
-            Err(StakeError::Unauthorized.into()),
-            "AdminResolveMarket must reject a signer that is not pool.admin"
+            assert_with(&pool, 6_000, supply, Some(3_999)),
+            Err(StakeError::InvariantWrapperShortfall.into())
+        );
+        // Only the pool's own market of its own wrapper counts.
+        let mut foreign = pool;
+        foreign.slab = [7u8; 32];
+        assert_eq!(
+            assert_with(&foreign, 6_000, supply, Some(4_000)),
+            Err(StakeError::InvalidPda.into())
+        );
+        let mut v16 = pool;
+        v16.wrapper_abi_version = cpi::WRAPPER_ABI_V16;
+        assert_eq!(
+            assert_with(&v16, 6_000, supply, Some(4_000)),
+            Err(StakeError::WrapperMarketLayoutMismatch.into())
+        );
+
+        let mut junior = pool;
+        junior.set_tranche_enabled(true);
+        junior.total_returned = 4_000;
+        junior.total_recovered_from_wrapper = 4_000;
+        junior.set_junior_balance(10_001);
+        assert_eq!(
+            assert(&junior, 10_000, supply),
+            Err(StakeError::InvariantJuniorExceedsPoolValue.into())
+        );
+
+        let mut ledger = pool;
+        ledger.total_recovered_from_wrapper = 1;
+        assert_eq!(
+            assert(&ledger, 6_000, supply),
+            Err(StakeError::InvariantLedgerInconsistent.into())
+        );
+
+        // The vault must be the pool's own.
+        let mut other = pool;
+        other.vault = [7u8; 32];
+        assert_eq!(
+            assert(&other, 6_000, supply),
+            Err(StakeError::InvalidPda.into())
         );
     }
 
-    /// H-1: AdminResolveMarket must reject while flushed insurance is
-    /// outstanding — this is the PRIMARY enforcement point (see doc comment on
-    /// process_admin_resolve_market). Reached before the wrapper CPI, so no real
-    /// percolator program is needed to observe the rejection.
+    /// DeclareDeficit writes the pool value down to the vault balance, junior
+    /// first, and refuses when the vault covers the books.
     #[test]
-    fn h1_admin_resolve_market_blocks_when_flushed_not_returned() {
+    fn declare_deficit_writes_down_junior_first() {
         let program_id = Pubkey::new_from_array([9u8; 32]);
-        let admin_key = Pubkey::new_from_array([1u8; 32]);
-        let pool_key = Pubkey::new_from_array([2u8; 32]);
-        let slab_key = Pubkey::new_from_array([3u8; 32]);
-        let percolator_program_id = Pubkey::new_from_array([4u8; 32]);
-        let system_program_id = solana_program::system_program::id();
+        let (pool_key, _) = state::derive_pool_pda(&program_id, &Pubkey::new_from_array([3u8; 32]));
+        let caller = Pubkey::new_from_array([1u8; 32]);
+        let vault = Pubkey::new_from_array([4u8; 32]);
+        let system = solana_program::system_program::id();
+        let token = crate::spl_token::id();
 
         let mut pool = StakePool::zeroed();
         pool.is_initialized = 1;
-        pool.admin = admin_key.to_bytes();
-        pool.slab = slab_key.to_bytes();
-        pool.percolator_program = percolator_program_id.to_bytes();
-        pool.bump = 255;
-        pool.total_flushed = 1_000;
-        pool.total_returned = 400; // 600 outstanding
+        pool.vault = vault.to_bytes();
+        pool.total_deposited = 10_000;
+        pool.set_tranche_enabled(true);
+        pool.set_junior_balance(3_000);
         pool.set_discriminator();
-
         let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
-        let mut admin_lamports = 0u64;
-        let mut pool_lamports = 0u64;
-        let mut slab_lamports = 0u64;
-        let mut program_lamports = 0u64;
-        let mut admin_data = vec![];
-        let mut slab_data = vec![];
-        let mut program_data = vec![];
 
-        let accounts = vec![
-            AccountInfo::new(
-                &admin_key,
-                true,
-                false,
-                &mut admin_lamports,
-                &mut admin_data,
-                &system_program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &pool_key,
-                false,
-                false,
-                &mut pool_lamports,
-                &mut pool_data,
-                &program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &slab_key,
-                false,
-                true,
-                &mut slab_lamports,
-                &mut slab_data,
-                &percolator_program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &percolator_program_id,
-                false,
-                false,
-                &mut program_lamports,
-                &mut program_data,
-                &system_program_id,
-                true,
-                0,
-            ),
-        ];
+        let declare = |vault_balance: u64, pool_data: &mut Vec<u8>| {
+            let mut vault_data = vec![0u8; crate::spl_token::state::ACCOUNT_LEN];
+            vault_data[64..72].copy_from_slice(&vault_balance.to_le_bytes());
+            vault_data[108] = 1;
+            let metas = [
+                (caller, true, false, system),
+                (pool_key, false, true, program_id),
+                (vault, false, false, token),
+            ];
+            let mut ds = vec![vec![], pool_data.clone(), vault_data];
+            let r = run_with(&program_id, &metas, &mut ds, &[54u8]);
+            *pool_data = ds[1].clone();
+            r
+        };
 
-        let result = process(&program_id, &accounts, &[24u8]);
         assert_eq!(
-            result,
-            Err(StakeError::InsuranceLossOutstanding.into()),
-            "AdminResolveMarket must reject while total_flushed > total_returned (H-1)"
+            declare(10_000, &mut pool_data),
+            Err(StakeError::NoDeficit.into())
+        );
+        // 2_000 short: junior (3_000) covers it all, senior keeps 7_000.
+        assert_eq!(declare(8_000, &mut pool_data), Ok(()));
+        let stored: StakePool = bytemuck::pod_read_unaligned(&pool_data[..]);
+        assert!(stored.in_deficit());
+        assert_eq!(stored.deficit_written_off, 2_000);
+        assert_eq!(stored.total_pool_value(), Some(8_000));
+        assert_eq!(stored.effective_junior_balance(), 1_000);
+        assert_eq!(stored.senior_balance(), Some(7_000));
+        // A further 3_000 shortfall wipes junior and the rest reaches senior.
+        assert_eq!(declare(5_000, &mut pool_data), Ok(()));
+        let stored: StakePool = bytemuck::pod_read_unaligned(&pool_data[..]);
+        assert_eq!(stored.deficit_written_off, 5_000);
+        assert_eq!(stored.deficit_junior_written_off, 3_000);
+        assert_eq!(stored.effective_junior_balance(), 0);
+        assert_eq!(stored.senior_balance(), Some(5_000));
+        assert_eq!(
+            declare(5_000, &mut pool_data),
+            Err(StakeError::NoDeficit.into())
+        );
+        // The books agree with the vault again.
+        assert_eq!(
+            accounting::check_pool_invariants(&stored, 5_000, 0, None),
+            Ok(())
         );
     }
 
-    /// C-1: AdminResolveMarket must reject a mismatched slab account (not
-    /// pool.slab) before ever reaching the CPI.
+    /// A shortfall a withdrawal finds puts the pool into deficit mode: that
+    /// exit and every later one are paid pro rata from the vault, past an HWM
+    /// floor they would otherwise breach, and deposits are refused.
     #[test]
-    fn c1_admin_resolve_market_rejects_wrong_slab() {
+    fn deficit_mode_refuses_deposits_and_pays_exits_pro_rata() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        f.edit_pool(|p| {
+            p.set_hwm_enabled(true);
+            p.set_hwm_floor_bps(9_000);
+        });
+        let mut a = f.holder(0x11, 100_000);
+        let mut b = f.holder(0x22, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        assert_eq!(f.deposit(&mut b, 1, 10_000), Ok(()));
+        f.slot += 10;
+
+        // 20_000 booked, 16_000 held: every LP is worth 0.8.
+        f.debit_vault(4_000);
+        assert_eq!(f.withdraw(&mut a, 4_500, None), Ok(()));
+        let rd = CpiDouble::return_data();
+        assert_eq!((rd.amount_out, rd.lp_burned), (3_600, 4_500));
+        let pool = f.pool();
+        assert!(pool.in_deficit());
+        assert_eq!(pool.deficit_written_off, 4_000);
+        assert_eq!(pool.total_pool_value(), Some(12_400));
+
+        assert_eq!(
+            f.deposit(&mut b, 1, 1_000),
+            Err(StakeError::PoolInDeficit.into())
+        );
+        assert_eq!(f.withdraw(&mut b, 10_000, None), Ok(()));
+        assert_eq!(CpiDouble::return_data().amount_out, 8_000);
+    }
+
+    /// A ledger whose `total_pool_value()` is None (it has paid out more than
+    /// it booked) is rebuilt on the vault balance instead of bricking the
+    /// pool: DeclareDeficit and the withdraw path both price exits pro rata
+    /// from what the vault holds.
+    #[test]
+    fn unrepresentable_ledger_is_written_down_and_exits_pro_rata() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        let mut a = f.holder(0x11, 100_000);
+        let mut b = f.holder(0x22, 100_000);
+        assert_eq!(f.deposit(&mut a, 1, 10_000), Ok(()));
+        assert_eq!(f.deposit(&mut b, 1, 10_000), Ok(()));
+        f.slot += 10;
+
+        // 20_000 held, but the books say 5_000 more went out than came in.
+        f.edit_pool(|p| p.total_withdrawn = 25_000);
+        assert_eq!(f.pool().total_pool_value(), None);
+        assert_eq!(f.declare_deficit(), Ok(()));
+        assert_eq!(f.pool().total_pool_value(), Some(20_000));
+        assert_eq!(f.withdraw(&mut a, 5_000, None), Ok(()));
+        assert_eq!(CpiDouble::return_data().amount_out, 5_000);
+
+        // Broken again and 3_000 lighter: the withdrawal itself rebuilds it.
+        f.edit_pool(|p| p.total_withdrawn += 20_000);
+        f.debit_vault(3_000);
+        assert_eq!(f.pool().total_pool_value(), None);
+        assert_eq!(f.withdraw(&mut a, 4_000, None), Ok(()));
+        // 12_000 held for 15_000 LP: 0.8 each.
+        assert_eq!(CpiDouble::return_data().amount_out, 3_200);
+        assert_eq!(f.withdraw(&mut b, 10_000, None), Ok(()));
+        assert_eq!(CpiDouble::return_data().amount_out, 8_000);
+    }
+
+    /// Tokens coming back restore the write-off before any fee is booked —
+    /// senior's share first, junior's last — and the pool leaves deficit
+    /// mode, reopening deposits, once the whole write-off is back.
+    #[test]
+    fn deficit_recovery_restores_senior_then_junior_before_fees() {
+        let _double = CpiDouble::enable();
+        let mut f = PoolFixture::new();
+        f.edit_pool(|p| {
+            p.set_tranche_enabled(true);
+            p.set_junior_fee_mult_bps(10_000);
+        });
+        let mut senior = f.holder(0x11, 100_000);
+        let mut junior = f.holder(0x22, 100_000);
+        junior.edit_record(|d| d.set_junior_deposit(true));
+        assert_eq!(f.deposit(&mut senior, 1, 10_000), Ok(()));
+        assert_eq!(f.deposit(&mut junior, 16, 4_000), Ok(()));
+
+        // 6_000 short: junior's 4_000 goes first, senior loses 2_000.
+        f.debit_vault(6_000);
+        assert_eq!(f.declare_deficit(), Ok(()));
+        let pool = f.pool();
+        assert_eq!(pool.junior_balance(), 0);
+        assert_eq!(pool.senior_balance(), Some(8_000));
+
+        // 3_000 back: senior is made whole, junior gets the other 1_000.
+        f.credit_vault(3_000);
+        assert_eq!(f.accrue_fees(), Ok(()));
+        let pool = f.pool();
+        assert_eq!(pool.senior_balance(), Some(10_000));
+        assert_eq!(pool.junior_balance(), 1_000);
+        assert_eq!(
+            (pool.deficit_written_off, pool.deficit_junior_written_off),
+            (3_000, 3_000)
+        );
+        assert!(pool.in_deficit());
+        assert_eq!(pool.total_fees_earned, 0);
+        assert_eq!(
+            f.deposit(&mut senior, 1, 1_000),
+            Err(StakeError::PoolInDeficit.into())
+        );
+
+        // The rest, plus 700 over: the write-off is closed and only the
+        // excess is fees.
+        f.credit_vault(3_700);
+        assert_eq!(f.accrue_fees(), Ok(()));
+        let pool = f.pool();
+        assert!(!pool.in_deficit());
+        assert_eq!(pool.deficit_written_off, 0);
+        assert_eq!(pool.total_fees_earned, 700);
+        assert_eq!(pool.junior_balance(), 4_000 + 200);
+        assert_eq!(pool.total_pool_value(), Some(14_700));
+        assert_eq!(f.deposit(&mut senior, 1, 1_000), Ok(()));
+    }
+
+    /// ProbeWrapper accepts an initialized market of the pool's wrapper
+    /// revision and names what is wrong with anything else.
+    #[test]
+    fn probe_wrapper_checks_owner_layout_and_header() {
         let program_id = Pubkey::new_from_array([9u8; 32]);
-        let admin_key = Pubkey::new_from_array([1u8; 32]);
-        let pool_key = Pubkey::new_from_array([2u8; 32]);
-        let real_slab_key = Pubkey::new_from_array([3u8; 32]);
-        let wrong_slab_key = Pubkey::new_from_array([0xBBu8; 32]);
-        let percolator_program_id = Pubkey::new_from_array([4u8; 32]);
-        let system_program_id = solana_program::system_program::id();
+        let slab = Pubkey::new_from_array([3u8; 32]);
+        let percolator = Pubkey::new_from_array([4u8; 32]);
+        let (pool_key, _) = state::derive_pool_pda(&program_id, &slab);
 
         let mut pool = StakePool::zeroed();
         pool.is_initialized = 1;
-        pool.admin = admin_key.to_bytes();
-        pool.slab = real_slab_key.to_bytes();
-        pool.percolator_program = percolator_program_id.to_bytes();
-        pool.bump = 255;
+        pool.slab = slab.to_bytes();
+        pool.percolator_program = percolator.to_bytes();
+        pool.wrapper_abi_version = cpi::WRAPPER_ABI_V17;
         pool.set_discriminator();
 
-        let mut pool_data = bytemuck::bytes_of(&pool).to_vec();
-        let mut admin_lamports = 0u64;
-        let mut pool_lamports = 0u64;
-        let mut slab_lamports = 0u64;
-        let mut program_lamports = 0u64;
-        let mut admin_data = vec![];
-        let mut slab_data = vec![];
-        let mut program_data = vec![];
+        let market = |len: usize| {
+            let mut d = vec![0u8; len];
+            if len >= cpi::MARKET_HEADER_LEN {
+                d[0] = 1;
+            }
+            d
+        };
+        let probe = |pool: &StakePool, owner: Pubkey, market: Vec<u8>| {
+            let metas = [
+                (pool_key, false, false, program_id),
+                (slab, false, false, owner),
+            ];
+            let mut ds = vec![bytemuck::bytes_of(pool).to_vec(), market];
+            run_with(&program_id, &metas, &mut ds, &[55u8])
+        };
 
-        let accounts = vec![
-            AccountInfo::new(
-                &admin_key,
-                true,
-                false,
-                &mut admin_lamports,
-                &mut admin_data,
-                &system_program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &pool_key,
-                false,
-                false,
-                &mut pool_lamports,
-                &mut pool_data,
-                &program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &wrong_slab_key,
-                false,
-                true,
-                &mut slab_lamports,
-                &mut slab_data,
-                &percolator_program_id,
-                false,
-                0,
-            ),
-            AccountInfo::new(
-                &percolator_program_id,
-                false,
-                false,
-                &mut program_lamports,
-                &mut program_data,
-                &system_program_id,
-                true,
-                0,
-            ),
-        ];
+        assert_eq!(probe(&pool, percolator, market(3147)), Ok(()));
+        assert_eq!(
+            probe(&pool, program_id, market(3147)),
+            Err(StakeError::WrapperMarketOwnerMismatch.into())
+        );
+        assert_eq!(
+            probe(&pool, percolator, market(3067)),
+            Err(StakeError::WrapperMarketLayoutMismatch.into())
+        );
+        assert_eq!(
+            probe(&pool, percolator, vec![0u8; 3147]),
+            Err(StakeError::WrapperMarketUninitialized.into())
+        );
 
-        let result = process(&program_id, &accounts, &[24u8]);
+        // The asset index must be one of the market's slots.
+        let mut second = pool;
+        second.wrapper_asset_index = 1;
         assert_eq!(
-            result,
-            Err(StakeError::InvalidPda.into()),
-            "AdminResolveMarket must reject a slab account that doesn't match pool.slab"
+            probe(&second, percolator, market(3147)),
+            Err(StakeError::WrapperMarketLayoutMismatch.into())
+        );
+        assert_eq!(probe(&second, percolator, market(3147 + 1797)), Ok(()));
+
+        // A pool must name a known wrapper revision; 0 is not one.
+        let mut unset = pool;
+        unset.wrapper_abi_version = 0;
+        assert_eq!(
+            probe(&unset, percolator, market(3147)),
+            Err(StakeError::WrapperAbiUnsupported.into())
+        );
+
+        // A v16 pool expects the v16 market size.
+        let mut v16 = pool;
+        v16.wrapper_abi_version = cpi::WRAPPER_ABI_V16;
+        assert_eq!(probe(&v16, percolator, market(3107)), Ok(()));
+        assert_eq!(
+            probe(&v16, percolator, market(3147)),
+            Err(StakeError::WrapperMarketLayoutMismatch.into())
+        );
+
+        // The market must be the pool's own.
+        let mut other = pool;
+        other.slab = [7u8; 32];
+        assert_eq!(
+            probe(&other, percolator, market(3147)),
+            Err(StakeError::InvalidPda.into())
         );
     }
 }
@@ -0,0 +1,269 @@
+//! Composable CPI return data for the value-moving instructions.
+//!
+//! Programs that CPI into the stake program (an aggregator vault, a router, a
+//! zap) previously had to re-read token accounts before and after the call to
+//! learn how much LP was minted or how much collateral was paid out. Every
+//! instruction below now ends with `set_return_data` carrying a fixed
+//! 56-byte record, and [`StakeReturnData::unpack`] / [`StakeReturnData::get`]
+//! decode it on the caller's side.
+//!
+//! Emitting instructions (tag in parentheses):
+//!   * `Deposit` (1), `DepositJunior` (16),
+//!     `DepositFor` (29)                       — `amount_in`, `lp_minted`
+//!   * `Withdraw` (2), `WithdrawTo` (30),
+//!     `OperatorWithdraw` (32), `RedeemFinal` (37) — `amount_out`, `lp_burned`
+//!   * `FlushToInsurance` (3)                  — `amount_out` (vault → wrapper)
+//!   * `RecoverFlushedInsurance` (23)          — `amount_in` (wrapper → vault)
+//!   * `AccrueFees` (12)                       — `amount_in` = fee surplus
+//!     crystallized by THIS call (no tokens move; 0 when nothing accrued)
+//!
+//! Wire layout (all integers little-endian, `RETURN_DATA_LEN` = 56 bytes):
+//!
+//! ```text
+//!   off  size  field
+//!    0    1    version           RETURN_DATA_VERSION (currently 1)
+//!    1    1    instruction_tag   stake instruction tag that produced the record
+//!    2    1    tranche           TRANCHE_POOL / TRANCHE_SENIOR / TRANCHE_JUNIOR
+//!    3    5    padding           always zero
+//!    8    8    amount_in         collateral that entered pool.vault
+//!   16    8    amount_out        collateral that left pool.vault
+//!   24    8    lp_minted         LP actually SPL-minted to the depositor
+//!   32    8    lp_burned         LP burned from the withdrawer
+//!   40    8    share_price_num   post-instruction sub-pool value
+//!   48    8    share_price_den   post-instruction sub-pool LP supply
+//! ```
+//!
+//! SHARE PRICE is reported for the sub-pool the instruction priced against,
+//! AFTER the instruction's own state update: `tranche` says which one.
+//! `TRANCHE_POOL` is `total_pool_value() / total_lp_supply`, `TRANCHE_SENIOR`
+//! is `senior_balance() / senior_total_lp()`, `TRANCHE_JUNIOR` is
+//! `effective_junior_balance() / junior_total_lp()`. The denominators count
+//! the `MINIMUM_LIQUIDITY` dead supply (it is part of `total_lp_supply`), which
+//! is exactly the basis the pricing math uses. `share_price_den == 0` means no
+//! LP is outstanding in that sub-pool (e.g. after the last junior exits), or
+//! that its value could not be computed.
+//!
+//! N7 NOTE: on a pool's genesis deposit `lp_minted` is the amount the caller
+//! actually RECEIVED (`lp_to_mint - MINIMUM_LIQUIDITY`), not the full amount
+//! added to `total_lp_supply`. A composing program should trust `lp_minted`
+//! for what landed in the destination LP account.
+//!
+//! ORDERING: the runtime clears return data on every CPI, so the handlers set
+//! it as their very last action, after every SPL / wrapper CPI they issue. A
+//! caller MUST check that the returned program id is the stake program
+//! ([`StakeReturnData::get`] does this) — any later CPI in the caller's own
+//! instruction overwrites it.
+
+use solana_program::{
+    program::{get_return_data, set_return_data},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::state::StakePool;
+
+/// Current return-data layout version (byte 0).
+pub const RETURN_DATA_VERSION: u8 = 1;
+
+/// Encoded length of a [`StakeReturnData`] record.
+pub const RETURN_DATA_LEN: usize = 56;
+
+/// Share price is pool-wide (`total_pool_value / total_lp_supply`).
+pub const TRANCHE_POOL: u8 = 0;
+/// Share price is the senior sub-pool's (`senior_balance / senior_total_lp`).
+pub const TRANCHE_SENIOR: u8 = 1;
+/// Share price is the junior sub-pool's (`effective_junior_balance / junior_total_lp`).
+pub const TRANCHE_JUNIOR: u8 = 2;
+
+/// Decoded return-data record. See the module doc for the wire layout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StakeReturnData {
+    pub instruction_tag: u8,
+    pub tranche: u8,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub lp_minted: u64,
+    pub lp_burned: u64,
+    pub share_price_num: u64,
+    pub share_price_den: u64,
+}
+
+impl StakeReturnData {
+    /// Encode into the fixed 56-byte wire layout.
+    pub fn pack(&self) -> [u8; RETURN_DATA_LEN] {
+        let mut buf = [0u8; RETURN_DATA_LEN];
+        buf[0] = RETURN_DATA_VERSION;
+        buf[1] = self.instruction_tag;
+        buf[2] = self.tranche;
+        buf[8..16].copy_from_slice(&self.amount_in.to_le_bytes());
+        buf[16..24].copy_from_slice(&self.amount_out.to_le_bytes());
+        buf[24..32].copy_from_slice(&self.lp_minted.to_le_bytes());
+        buf[32..40].copy_from_slice(&self.lp_burned.to_le_bytes());
+        buf[40..48].copy_from_slice(&self.share_price_num.to_le_bytes());
+        buf[48..56].copy_from_slice(&self.share_price_den.to_le_bytes());
+        buf
+    }
+
+    /// Decode a record. Rejects any length other than `RETURN_DATA_LEN`, an
+    /// unknown version, an unknown tranche selector, or non-zero padding, so a
+    /// caller never silently misreads a future layout.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() != RETURN_DATA_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if data[0] != RETURN_DATA_VERSION {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        if data[2] > TRANCHE_JUNIOR || data[3..8].iter().any(|b| *b != 0) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let read_u64 = |off: usize| -> Result<u64, ProgramError> {
+            Ok(u64::from_le_bytes(
+                data[off..off + 8]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+            ))
+        };
+        Ok(Self {
+            instruction_tag: data[1],
+            tranche: data[2],
+            amount_in: read_u64(8)?,
+            amount_out: read_u64(16)?,
+            lp_minted: read_u64(24)?,
+            lp_burned: read_u64(32)?,
+            share_price_num: read_u64(40)?,
+            share_price_den: read_u64(48)?,
+        })
+    }
+
+    /// Read and decode the current return data, but only if it was set by
+    /// `stake_program_id`. Returns `None` when no return data is present or it
+    /// came from a different program (e.g. a later CPI overwrote it).
+    pub fn get(stake_program_id: &Pubkey) -> Option<Self> {
+        let (program_id, data) = get_return_data()?;
+        if program_id != *stake_program_id {
+            return None;
+        }
+        Self::unpack(&data).ok()
+    }
+
+    /// Build a record whose share price is read from `pool` (post-update) for
+    /// the given `tranche` selector.
+    ///
+    /// Infallible on purpose: the share price is display-only, so a value that
+    /// cannot be computed must not turn an otherwise successful deposit or
+    /// exit into a failure. A sub-pool value that cannot be computed (an
+    /// overflowing or inconsistent pool) is reported as `0 / 0`, the "no price"
+    /// record.
+    pub(crate) fn with_share_price(pool: &StakePool, instruction_tag: u8, tranche: u8) -> Self {
+        let (value, supply) = match tranche {
+            TRANCHE_SENIOR => (pool.senior_balance(), pool.senior_total_lp()),
+            TRANCHE_JUNIOR => (
+                Some(pool.effective_junior_balance()),
+                pool.junior_total_lp(),
+            ),
+            _ => (pool.total_pool_value(), pool.total_lp_supply),
+        };
+        let (share_price_num, share_price_den) = value.map_or((0, 0), |v| (v, supply));
+        Self {
+            instruction_tag,
+            tranche,
+            share_price_num,
+            share_price_den,
+            ..Self::default()
+        }
+    }
+
+    /// Publish this record as the instruction's return data.
+    pub(crate) fn set(&self) {
+        set_return_data(&self.pack());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    fn sample() -> StakeReturnData {
+        StakeReturnData {
+            instruction_tag: 2,
+            tranche: TRANCHE_SENIOR,
+            amount_in: 0,
+            amount_out: 1_234_567,
+            lp_minted: 0,
+            lp_burned: 1_000_000,
+            share_price_num: u64::MAX,
+            share_price_den: 42,
+        }
+    }
+
+    #[test]
+    fn pack_unpack_roundtrip() {
+        let rd = sample();
+        let bytes = rd.pack();
+        assert_eq!(bytes.len(), RETURN_DATA_LEN);
+        assert_eq!(StakeReturnData::unpack(&bytes).unwrap(), rd);
+    }
+
+    #[test]
+    fn pack_pins_documented_offsets() {
+        let bytes = sample().pack();
+        assert_eq!(bytes[0], RETURN_DATA_VERSION);
+        assert_eq!(bytes[1], 2);
+        assert_eq!(bytes[2], TRANCHE_SENIOR);
+        assert_eq!(&bytes[3..8], &[0u8; 5]);
+        assert_eq!(&bytes[16..24], &1_234_567u64.to_le_bytes());
+        assert_eq!(&bytes[32..40], &1_000_000u64.to_le_bytes());
+        assert_eq!(&bytes[40..48], &u64::MAX.to_le_bytes());
+        assert_eq!(&bytes[48..56], &42u64.to_le_bytes());
+    }
+
+    #[test]
+    fn unpack_rejects_wrong_length() {
+        let bytes = sample().pack();
+        assert!(StakeReturnData::unpack(&bytes[..RETURN_DATA_LEN - 1]).is_err());
+        let mut long = bytes.to_vec();
+        long.push(0);
+        assert!(StakeReturnData::unpack(&long).is_err());
+        assert!(StakeReturnData::unpack(&[]).is_err());
+    }
+
+    #[test]
+    fn unpack_rejects_unknown_version_tranche_and_padding() {
+        let mut bytes = sample().pack();
+        bytes[0] = RETURN_DATA_VERSION + 1;
+        assert!(StakeReturnData::unpack(&bytes).is_err());
+
+        let mut bytes = sample().pack();
+        bytes[2] = TRANCHE_JUNIOR + 1;
+        assert!(StakeReturnData::unpack(&bytes).is_err());
+
+        let mut bytes = sample().pack();
+        bytes[5] = 1;
+        assert!(StakeReturnData::unpack(&bytes).is_err());
+    }
+
+    #[test]
+    fn share_price_reads_the_selected_sub_pool() {
+        let mut pool = StakePool::zeroed();
+        pool.total_deposited = 1_000;
+        pool.total_lp_supply = 800;
+        pool.set_junior_balance(300);
+        pool.set_junior_total_lp(200);
+
+        let global = StakeReturnData::with_share_price(&pool, 1, TRANCHE_POOL);
+        assert_eq!(
+            (global.share_price_num, global.share_price_den),
+            (1_000, 800)
+        );
+
+        let senior = StakeReturnData::with_share_price(&pool, 1, TRANCHE_SENIOR);
+        assert_eq!((senior.share_price_num, senior.share_price_den), (700, 600));
+
+        let junior = StakeReturnData::with_share_price(&pool, 16, TRANCHE_JUNIOR);
+        assert_eq!((junior.share_price_num, junior.share_price_den), (300, 200));
+        assert_eq!(junior.instruction_tag, 16);
+        assert_eq!((junior.amount_in, junior.lp_minted), (0, 0));
+    }
+}
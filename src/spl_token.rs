@@ -22,8 +22,12 @@ pub fn id() -> Pubkey {
 const IX_INITIALIZE_MINT: u8 = 0;
 const IX_INITIALIZE_ACCOUNT: u8 = 1;
 const IX_TRANSFER: u8 = 3;
+const IX_APPROVE: u8 = 4;
+const IX_REVOKE: u8 = 5;
+const IX_SET_AUTHORITY: u8 = 6;
 const IX_MINT_TO: u8 = 7;
 const IX_BURN: u8 = 8;
+const IX_CLOSE_ACCOUNT: u8 = 9;
 
 // ─── CPI instruction builders ────────────────────────────────────────────────
 
@@ -109,6 +113,46 @@ pub fn transfer(
     })
 }
 
+/// `Approve` (tag 4).  Accounts: [WRITE] source, [RO] delegate, [SIGNER] owner.
+pub fn approve(
+    _program_id: &Pubkey,
+    source: &Pubkey,
+    delegate: &Pubkey,
+    owner: &Pubkey,
+    _multisigners: &[&Pubkey],
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let mut data = [0u8; 9];
+    data[0] = IX_APPROVE;
+    data[1..9].copy_from_slice(&amount.to_le_bytes());
+    Ok(Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*delegate, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: data.to_vec(),
+    })
+}
+
+/// `Revoke` (tag 5).  Accounts: [WRITE] source, [SIGNER] owner.
+pub fn revoke(
+    _program_id: &Pubkey,
+    source: &Pubkey,
+    owner: &Pubkey,
+    _multisigners: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: vec![IX_REVOKE],
+    })
+}
+
 /// `MintTo` (tag 7).  Accounts: [WRITE] mint, [WRITE] destination, [SIGNER] authority.
 pub fn mint_to(
     _program_id: &Pubkey,
@@ -155,6 +199,54 @@ pub fn burn(
     })
 }
 
+/// `SetAuthority` (tag 6) for `AuthorityType::MintTokens` (0).
+/// Accounts: [WRITE] mint, [SIGNER] current authority.
+///
+/// Wire layout: tag(1) + authority_type(1) + option(1) [+ new_authority(32)]
+pub fn set_mint_authority(
+    _program_id: &Pubkey,
+    mint: &Pubkey,
+    new_authority: Option<&Pubkey>,
+    current_authority: &Pubkey,
+    _multisigners: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    let mut data = vec![IX_SET_AUTHORITY, 0u8];
+    match new_authority {
+        Some(a) => {
+            data.push(1);
+            data.extend_from_slice(a.as_ref());
+        }
+        None => data.push(0),
+    }
+    Ok(Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*current_authority, true),
+        ],
+        data,
+    })
+}
+
+/// `CloseAccount` (tag 9).  Accounts: [WRITE] account, [WRITE] destination, [SIGNER] owner.
+pub fn close_account(
+    _program_id: &Pubkey,
+    account: &Pubkey,
+    destination: &Pubkey,
+    owner: &Pubkey,
+    _multisigners: &[&Pubkey],
+) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: vec![IX_CLOSE_ACCOUNT],
+    })
+}
+
 // ─── State parsing ────────────────────────────────────────────────────────────
 
 pub mod state {
@@ -203,7 +295,16 @@ pub mod state {
     /// spl_token::state::Mint::LEN = 82
     pub const MINT_LEN: usize = 82;
 
+    // Mint layout:
+    //   [0..4]    mint_authority_option (u32 LE)
+    //   [4..36]   mint_authority (Pubkey)
+    //   [36..44]  supply (u64 LE)
+    //   [44]      decimals (u8)
+    //   [45]      is_initialized (bool)
+    //   ... (freeze authority unused by percolator-stake)
+
     pub struct Mint {
+        pub supply: u64,
         pub decimals: u8,
         pub is_initialized: bool,
     }
@@ -216,9 +317,15 @@ pub mod state {
             if data.len() < Self::LEN {
                 return Err(ProgramError::InvalidAccountData);
             }
+            let supply = u64::from_le_bytes(
+                data[36..44]
+                    .try_into()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+            );
             let decimals = data[44];
             let is_initialized = data[45] != 0;
             Ok(Self {
+                supply,
                 decimals,
                 is_initialized,
             })
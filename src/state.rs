@@ -1,10 +1,16 @@
 use bytemuck::{Pod, Zeroable};
-use solana_program::pubkey::Pubkey;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
 
 /// 8-byte discriminator for StakePool accounts ("SPOOL_V1")
 pub const STAKE_POOL_DISCRIMINATOR: [u8; 8] = [0x53, 0x50, 0x4F, 0x4F, 0x4C, 0x5F, 0x56, 0x31];
 /// 8-byte discriminator for StakeDeposit accounts ("SDEP_V1\0")
 pub const STAKE_DEPOSIT_DISCRIMINATOR: [u8; 8] = [0x53, 0x44, 0x45, 0x50, 0x5F, 0x56, 0x31, 0x00];
+/// 8-byte discriminator for StakeProposal accounts ("SPROP_V1")
+pub const STAKE_PROPOSAL_DISCRIMINATOR: [u8; 8] = [0x53, 0x50, 0x52, 0x4F, 0x50, 0x5F, 0x56, 0x31];
+/// 8-byte discriminator for StakeVetoVote accounts ("SVETO_V1")
+pub const STAKE_VETO_VOTE_DISCRIMINATOR: [u8; 8] = [0x53, 0x56, 0x45, 0x54, 0x4F, 0x5F, 0x56, 0x31];
+/// 8-byte discriminator for StakeCouncil accounts ("SCNCL_V1")
+pub const STAKE_COUNCIL_DISCRIMINATOR: [u8; 8] = [0x53, 0x43, 0x4E, 0x43, 0x4C, 0x5F, 0x56, 0x31];
 
 /// N7 (CONSOLIDATED-PLAN §2.2): dead-share floor locked at the pool's true genesis
 /// deposit (`total_lp_supply == 0`, whether reached via `Deposit` or
@@ -178,6 +184,318 @@ pub struct StakePool {
     /// Real struct field (offset 400) — see [`StakePool::pending_cooldown_slots`]
     /// for why these are no longer packed into `_reserved`.
     pub cooldown_proposed_at_slot: u64,
+
+    /// Sunset (v5): the slot at which `Finalize` (tag 36) moved the pool into its
+    /// terminal phase. `0` = not finalized. Once set, `RedeemFinal` (tag 37)
+    /// redeems LP pro-rata with no cooldown or HWM, and after
+    /// `FINAL_SWEEP_GRACE_SLOTS` `SweepAndClosePool` (tag 38) sweeps what is left
+    /// and closes the vault and the pool PDA (the LP mint cannot be closed).
+    ///
+    /// Real struct field (offset 408): `_reserved` has 4 free bytes. Appending it
+    /// and the fields below grows STAKE_POOL_SIZE 408 -> 736 and is why
+    /// CURRENT_VERSION bumps 4 -> 5.
+    pub finalized_at_slot: u64,
+
+    /// Sunset (v5): wallet that receives the final sweep — the residual vault
+    /// balance (dead `MINIMUM_LIQUIDITY` value, rounding dust, unredeemed LP's
+    /// share) and the rent of the closed vault and pool accounts. Chosen by the
+    /// admin at `Finalize`; meaningful only while `finalized_at_slot != 0`.
+    /// Real struct field (offset 416).
+    pub final_sweep_recipient: [u8; 32],
+
+    // ========================================
+    // Stake-side fee-policy bounds
+    // ========================================
+    // The wrapper enforces constitutional bounds on the fee parameters; these
+    // are the pool's own, tighter policy inside them, checked when a
+    // fee-policy proxy commits (`processor::apply_fee_policy_change`). Set at
+    // InitPool; tightened directly or loosened only through the proposal
+    // timelock (`SetFeePolicyBounds`, tag 42). See `FeePolicyBounds`.
+    /// Minimum slots between two committed fee-policy changes (0 = no limit).
+    /// Offset 448.
+    pub fee_policy_min_change_interval_slots: u64,
+
+    /// Maximum `trade_fee_base_bps` a proxied tag-55 change may set. Offset 456.
+    pub fee_policy_max_trade_fee_base_bps: u64,
+
+    /// Minimum `lp_share_bps` a proxied tag-86 fee split may set. Offset 464.
+    pub fee_policy_min_lp_share_bps: u16,
+
+    /// Maximum move, in bps, of `lp_share_bps`, `trade_fee_base_bps` or a
+    /// backing `fee_bps` in one change, measured from the last value this
+    /// program set. Offset 466.
+    pub fee_policy_max_change_delta_bps: u16,
+
+    /// Last `lp_share_bps` committed through this program (valid only with
+    /// `FEE_HISTORY_LP_SHARE_KNOWN`). Offset 468.
+    pub last_lp_share_bps: u16,
+
+    /// `FEE_HISTORY_*` bits: which `last_*` values are known. Values set before
+    /// staking are never read back from the wrapper, so the first change of
+    /// each parameter is bounded only by the absolute limits. Offset 470.
+    pub fee_history_flags: u8,
+
+    /// Padding for alignment
+    pub _fee_policy_padding: u8,
+
+    /// Slot of the last committed fee-policy change (0 = none). Offset 472.
+    pub last_fee_change_slot: u64,
+
+    /// Last `trade_fee_base_bps` committed through this program (valid only
+    /// with `FEE_HISTORY_TRADE_FEE_KNOWN`). Offset 480.
+    pub last_trade_fee_base_bps: u64,
+
+    // ========================================
+    // LP veto over pending proposals
+    // ========================================
+    // LP holders lock LP into `StakeVetoVote` records against a pending cooldown
+    // increase, pending admin, or fee-change proposal; once the locked LP
+    // exceeds `veto_threshold_bps` of `total_lp_supply` the proposal is
+    // cancelled on the spot (`processor::process_cast_veto`). The two in-pool
+    // proposals have no account of their own, so each new one takes a nonce
+    // from `proposal_counter`; votes name that nonce, which is how a vote for
+    // a replaced or resolved proposal is told apart from one still pending.
+    /// Last nonce handed to an in-pool proposal (0 = none yet). Offset 488.
+    pub proposal_counter: u64,
+
+    /// Nonce of the pending cooldown increase (meaningful only while
+    /// `cooldown_proposed_at_slot != 0`). Offset 496.
+    pub cooldown_proposal_nonce: u64,
+
+    /// Nonce of the pending admin rotation (meaningful only while
+    /// `pending_admin != 0`). Offset 504.
+    pub admin_proposal_nonce: u64,
+
+    /// LP locked against the pending cooldown increase. Offset 512.
+    pub cooldown_veto_lp: u64,
+
+    /// LP locked against the pending admin rotation. Offset 520.
+    pub admin_veto_lp: u64,
+
+    /// Share of `total_lp_supply`, in bps, that locked veto LP must EXCEED to
+    /// cancel a proposal (0 = veto disabled). Offset 528.
+    pub veto_threshold_bps: u16,
+
+    /// Padding for alignment
+    pub _veto_padding: [u8; 6],
+
+    // ========================================
+    // Admin council
+    // ========================================
+    /// The pool's active `StakeCouncil` PDA, or `[0; 32]` if none. When set,
+    /// every admin-gated instruction except `ReturnInsurance` accepts this
+    /// account in the admin slot, backed by a threshold of its members'
+    /// signatures, as an alternative to `admin` signing. Written only by
+    /// `AcceptCouncil`. Offset 536.
+    pub council: [u8; 32],
+
+    // ========================================
+    // Role table
+    // ========================================
+    /// Holder of each `ROLE_*`, indexed by role. `[0; 32]` = unassigned: the
+    /// role's instructions fall back to the admin authority (admin or
+    /// council), which is how every pool behaved before roles existed. Once a
+    /// role is assigned, only its holder passes that role's checks — the
+    /// admin has to rotate the role back to act itself. Offset 568.
+    pub roles: [[u8; 32]; ROLE_COUNT],
+
+    /// Proposed holder of each role awaiting `AcceptRole` (`[0; 32]` = none).
+    /// Offset 632.
+    pub pending_roles: [[u8; 32]; ROLE_COUNT],
+
+    // ========================================
+    // Admin proposal expiry
+    // ========================================
+    /// Slot `pending_admin` was (last) proposed at; 0 while nothing is
+    /// pending. `AcceptAdmin` refuses the proposal once it is older than
+    /// `PROPOSAL_EXPIRY_SLOTS`. Offset 696.
+    pub admin_proposed_at_slot: u64,
+
+    // ========================================
+    // Flush policy and config locks
+    // ========================================
+    /// Flush policy: the most of the pool's gross value (vault plus
+    /// wrapper-recoverable insurance) a permissionless `FlushToInsurance` may
+    /// leave in the wrapper fund, in bps. 0 = no permissionless flushing; the
+    /// flush operator is not bound by it. Only honoured once locked
+    /// (`CONFIG_LOCK_FLUSH_POLICY`). Offset 704.
+    pub flush_policy_max_bps: u16,
+
+    /// `CONFIG_LOCK_*` bits. Set by `LockConfig`, never cleared. Offset 706.
+    pub config_locks: u8,
+
+    /// 1 while the pool is in deficit mode (see `deficit_written_off`);
+    /// cleared once the whole write-off has been restored. Offset 707.
+    pub deficit_mode: u8,
+
+    /// Wrapper asset whose insurance profile this pool backs: the
+    /// `asset_index` of every tag-65 `UpdateAssetAuthority` and tag-57
+    /// `WithdrawInsuranceAsset` CPI. Recorded by `InitPool`, never changed.
+    /// Offset 708.
+    pub wrapper_asset_index: u16,
+
+    /// Wrapper ABI revision this pool's CPIs are encoded for
+    /// (`cpi::WRAPPER_ABI_V16` / `cpi::WRAPPER_ABI_V17`). Recorded by
+    /// `InitPool`. Offset 710.
+    pub wrapper_abi_version: u8,
+
+    pub _lock_padding: u8,
+
+    // ========================================
+    // Deficit mode
+    // ========================================
+    /// Cumulative vault shortfall written off the books: value the ledger
+    /// counted that the vault was found not to hold, less what has since come
+    /// back. Excluded from `total_pool_value()` like `realized_junior_loss`;
+    /// the junior tranche absorbed it first. Offset 712.
+    pub deficit_written_off: u64,
+
+    /// Fee-policy bound: maximum backing `fee_bps` a proxied tag-27
+    /// change may set. A separate fee from the trade fee, so it gets its own
+    /// ceiling rather than borrowing `fee_policy_max_trade_fee_base_bps`.
+    /// Offset 720.
+    pub fee_policy_max_backing_fee_bps: u16,
+
+    /// Last backing `fee_bps` committed through this program for each of the
+    /// two domains of `wrapper_asset_index` (indexed by `domain % 2`; valid
+    /// only with `FEE_HISTORY_BACKING_FEE_KNOWN << lane`). No other domain can
+    /// commit: the wrapper gates it on an asset this pool never bound.
+    /// Offset 722.
+    pub last_backing_fee_bps: [u16; 2],
+
+    pub _backing_fee_padding: u16,
+
+    /// The junior tranche's part of `deficit_written_off`, restored to it
+    /// only after senior has been made whole. Offset 728.
+    pub deficit_junior_written_off: u64,
+}
+
+/// `StakePool::config_locks`: `SetFlushPolicy` is frozen and the policy
+/// governs permissionless flushes.
+pub const CONFIG_LOCK_FLUSH_POLICY: u8 = 1 << 0;
+/// `StakePool::config_locks`: `AdminSetHwmConfig` is frozen.
+pub const CONFIG_LOCK_HWM: u8 = 1 << 1;
+/// `StakePool::config_locks`: `UpdateConfig` can no longer change the deposit cap.
+pub const CONFIG_LOCK_DEPOSIT_CAP: u8 = 1 << 2;
+/// Every lock; required by `RenounceAdmin`.
+pub const CONFIG_LOCK_ALL: u8 =
+    CONFIG_LOCK_FLUSH_POLICY | CONFIG_LOCK_HWM | CONFIG_LOCK_DEPOSIT_CAP;
+
+/// How long a pending admin transfer or cooldown increase stays actionable,
+/// counted from its proposal slot. Past it `AcceptAdmin` /
+/// `CommitCooldownIncrease` fail with `ProposalExpired`, so a proposal made to
+/// a since-compromised key (or a forgotten cooldown increase) cannot be picked
+/// up months later. An expired proposal no longer counts as pending either:
+/// withdrawals are held to the cooldown again, it cannot be vetoed, and LP
+/// locked against it is reclaimable. ~7 days at 2.5 slots/sec: the 48h
+/// timelock plus a 5-day commit window.
+pub const PROPOSAL_EXPIRY_SLOTS: u64 = 1_512_000;
+
+/// Whether a proposal made at slot `proposed_at` is older than `expiry_slots`
+/// at slot `now`. Pure; a `now` before `proposed_at` is never expired.
+pub fn proposal_expired(proposed_at: u64, expiry_slots: u64, now: u64) -> bool {
+    now.saturating_sub(proposed_at) > expiry_slots
+}
+
+/// Whether a permissionless flush of `amount` keeps the wrapper-recoverable
+/// share of the pool's gross value (`vault_value + recoverable`) at or below
+/// `max_bps`. u128 throughout; cannot overflow.
+pub fn flush_within_policy(recoverable: u64, vault_value: u64, amount: u64, max_bps: u16) -> bool {
+    let after = recoverable as u128 + amount as u128;
+    let gross = vault_value as u128 + recoverable as u128;
+    after * 10_000 <= gross * max_bps as u128
+}
+
+// Roles split the admin's routine powers off the admin key, so the admin can
+// stay cold:
+//
+//   ROLE_FLUSH_OPERATOR  FlushToInsurance
+//   ROLE_FEE_MANAGER     fee-policy proxies (tags 25..28), proposing and
+//                        cancelling them
+//   admin                everything else: roles, ProposeAdmin, the council,
+//                        pool config and lifecycle, and the wrapper authority
+//                        wiring (BindInsuranceAuthority, BurnAssetAdmin,
+//                        RotateInsurance*); may cancel any pending fee-change
+//                        proposal
+//
+// ReturnInsurance stays with the admin key itself (it signs a token transfer).
+
+/// `StakePool::roles` index: routine insurance flushes.
+pub const ROLE_FLUSH_OPERATOR: u8 = 0;
+/// `StakePool::roles` index: wrapper fee-policy proxies.
+pub const ROLE_FEE_MANAGER: u8 = 1;
+/// Number of roles in the table.
+pub const ROLE_COUNT: usize = 2;
+
+/// `StakePool::fee_history_flags`: `last_lp_share_bps` is meaningful.
+pub const FEE_HISTORY_LP_SHARE_KNOWN: u8 = 1 << 0;
+/// `StakePool::fee_history_flags`: `last_trade_fee_base_bps` is meaningful.
+pub const FEE_HISTORY_TRADE_FEE_KNOWN: u8 = 1 << 1;
+/// `StakePool::fee_history_flags`: `last_backing_fee_bps[0]` is meaningful;
+/// shift left by the lane for `[1]`.
+pub const FEE_HISTORY_BACKING_FEE_KNOWN: u8 = 1 << 2;
+
+/// The pool's stake-side fee policy, as carried by `InitPool` /
+/// `InitTradingPool` and `SetFeePolicyBounds` (22 bytes on the wire:
+/// `min_lp_share_bps` u16, `max_trade_fee_base_bps` u64,
+/// `max_change_delta_bps` u16, `min_change_interval_slots` u64,
+/// `max_backing_fee_bps` u16).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeePolicyBounds {
+    pub min_lp_share_bps: u16,
+    pub max_trade_fee_base_bps: u64,
+    pub max_change_delta_bps: u16,
+    pub min_change_interval_slots: u64,
+    pub max_backing_fee_bps: u16,
+}
+
+impl FeePolicyBounds {
+    /// Wire length of an encoded `FeePolicyBounds`.
+    pub const LEN: usize = 22;
+
+    /// No stake-side restriction; only the wrapper's bounds apply. A pool
+    /// only gets this by sending it explicitly at `InitPool`.
+    pub const UNBOUNDED: Self = Self {
+        min_lp_share_bps: 0,
+        max_trade_fee_base_bps: u64::MAX,
+        max_change_delta_bps: u16::MAX,
+        min_change_interval_slots: 0,
+        max_backing_fee_bps: u16::MAX,
+    };
+
+    pub fn unpack(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEN {
+            return None;
+        }
+        Some(Self {
+            min_lp_share_bps: u16::from_le_bytes(data[0..2].try_into().ok()?),
+            max_trade_fee_base_bps: u64::from_le_bytes(data[2..10].try_into().ok()?),
+            max_change_delta_bps: u16::from_le_bytes(data[10..12].try_into().ok()?),
+            min_change_interval_slots: u64::from_le_bytes(data[12..20].try_into().ok()?),
+            max_backing_fee_bps: u16::from_le_bytes(data[20..22].try_into().ok()?),
+        })
+    }
+
+    /// The wire encoding `unpack` reads.
+    pub fn pack(&self) -> [u8; Self::LEN] {
+        let mut out = [0u8; Self::LEN];
+        out[0..2].copy_from_slice(&self.min_lp_share_bps.to_le_bytes());
+        out[2..10].copy_from_slice(&self.max_trade_fee_base_bps.to_le_bytes());
+        out[10..12].copy_from_slice(&self.max_change_delta_bps.to_le_bytes());
+        out[12..20].copy_from_slice(&self.min_change_interval_slots.to_le_bytes());
+        out[20..22].copy_from_slice(&self.max_backing_fee_bps.to_le_bytes());
+        out
+    }
+
+    /// Whether every bound is at least as strict as in `current` — i.e. moving
+    /// from `current` to `self` cannot widen what the admin may do.
+    pub fn is_no_looser_than(&self, current: &Self) -> bool {
+        self.min_lp_share_bps >= current.min_lp_share_bps
+            && self.max_trade_fee_base_bps <= current.max_trade_fee_base_bps
+            && self.max_change_delta_bps <= current.max_change_delta_bps
+            && self.min_change_interval_slots >= current.min_change_interval_slots
+            && self.max_backing_fee_bps <= current.max_backing_fee_bps
+    }
 }
 
 /// Size of StakePool in bytes
@@ -223,6 +541,15 @@ const _: () = {
     assert!(offset_of!(StakePool, _reserved) + 8 == 328);
     // Total size — the wrapper's `STAKE_POOL_LEN` minimum-length gate.
     //
+    // v5 grows this 408 -> 736 by APPENDING the sunset fields
+    // (`finalized_at_slot`, `final_sweep_recipient`), the fee-policy bounds,
+    // the LP-veto state, the `council` reference, the role table,
+    // `admin_proposed_at_slot`, the flush policy with the config locks,
+    // `deficit_written_off`, the backing-fee bound and
+    // `deficit_junior_written_off`. Same reasoning as v4 below: no offset the
+    // wrapper reads moves, but its EXACT version check needs a coordinated bump
+    // to STAKE_POOL_VERSION = 5 / STAKE_POOL_LEN = 736.
+    //
     // v4 grows this 392 -> 408 by APPENDING the two #242 timelock fields after
     // `total_recovered_from_wrapper` (384). Every offset the wrapper reads is
     // <= 328 and therefore unmoved, and its gate is `data.len() < STAKE_POOL_LEN`
@@ -234,7 +561,7 @@ const _: () = {
     // Shipping v4 therefore REQUIRES a coordinated wrapper bump to
     // STAKE_POOL_VERSION = 4 / STAKE_POOL_LEN = 408 and a wrapper redeploy, or
     // tag-87 stops paying the insurance fee leg to every stake pool.
-    assert!(STAKE_POOL_SIZE == 408);
+    assert!(STAKE_POOL_SIZE == 736);
 };
 
 /// Per-depositor state — tracks cooldown and LP amount per user.
@@ -302,6 +629,358 @@ impl StakeDeposit {
     pub fn set_junior_deposit(&mut self, v: bool) {
         self._reserved[8] = if v { 1 } else { 0 };
     }
+
+    /// Third-party funder the position owner has opted in to via
+    /// `SetAuthorizedDepositor` (tag 31). Stored at `_reserved[16..48]`; `[0; 32]`
+    /// means no funder is authorized and `DepositFor` into this record is rejected.
+    ///
+    /// The opt-in exists because every deposit restarts the whole position's
+    /// cooldown (BUG-8): without it anyone could `DepositFor` a dust amount into
+    /// a stranger's position and keep their withdrawal locked indefinitely.
+    pub fn authorized_depositor(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&self._reserved[16..48]);
+        bytes
+    }
+
+    /// Set (or, with `[0; 32]`, revoke) the authorized third-party funder.
+    pub fn set_authorized_depositor(&mut self, depositor: [u8; 32]) {
+        self._reserved[16..48].copy_from_slice(&depositor);
+    }
+
+    // Frozen cooldown terms. `_reserved[48..56]` holds the pool's `cooldown_slots`
+    // as of the position's last top-up, so a later committed increase does not
+    // retroactively lengthen the lock; withdraw enforces the smaller of this and
+    // the pool's current value, so decreases still apply at once. The field is
+    // its own version marker: `validate_cooldown_slots` never admits 0, so 0
+    // means a record written before the field existed. Such a record follows the
+    // pool's current cooldown, as before, until its next write stamps it.
+
+    /// Cooldown recorded at the last top-up, or `None` for a pre-stamp record.
+    pub fn recorded_cooldown_slots(&self) -> Option<u64> {
+        match u64::from_le_bytes(self._reserved[48..56].try_into().unwrap()) {
+            0 => None,
+            slots => Some(slots),
+        }
+    }
+
+    /// Record the cooldown in force now. Every deposit calls this, alongside
+    /// resetting `last_deposit_slot`.
+    pub fn set_recorded_cooldown_slots(&mut self, slots: u64) {
+        self._reserved[48..56].copy_from_slice(&slots.to_le_bytes());
+    }
+
+    /// Migrate a pre-stamp record: adopt `pool_cooldown_slots` as its recorded
+    /// terms. Only ever lowers what withdraw enforces, since that is already
+    /// `pool_cooldown_slots` for such a record. No-op on a stamped record.
+    pub fn stamp_legacy_cooldown(&mut self, pool_cooldown_slots: u64) {
+        if self.recorded_cooldown_slots().is_none() {
+            self.set_recorded_cooldown_slots(pool_cooldown_slots);
+        }
+    }
+
+    /// The cooldown withdraw enforces: the smaller of the recorded terms and
+    /// the pool's current `cooldown_slots`.
+    pub fn effective_cooldown_slots(&self, pool_cooldown_slots: u64) -> u64 {
+        self.recorded_cooldown_slots()
+            .map_or(pool_cooldown_slots, |recorded| {
+                recorded.min(pool_cooldown_slots)
+            })
+    }
+}
+
+// ── StakeDeposit v2 tail: delegated position operator ──
+//
+// The operator is a 32-byte pubkey and `_reserved` has no 32-byte hole left
+// ([8] junior flag, [16..48] authorized_depositor, [48..56] recorded
+// cooldown). Rather than resize the struct — which would strand every live
+// 152-byte record behind the `data.len() >= STAKE_DEPOSIT_SIZE` checks — a v2
+// record is the unchanged v1 struct followed by a tail, and
+// `SetDepositOperator` reallocs a v1 record up to `STAKE_DEPOSIT_V2_SIZE` on
+// first use (owner pays the rent delta). Every existing reader keeps parsing
+// only the first `STAKE_DEPOSIT_SIZE` bytes, so v1 and v2 records coexist with
+// no migration.
+
+/// Offset of the operator pubkey in a v2 deposit record.
+pub const DEPOSIT_OPERATOR_OFFSET: usize = STAKE_DEPOSIT_SIZE;
+
+/// Size of a v2 deposit record (v1 struct + 32-byte operator + 32 reserved).
+pub const STAKE_DEPOSIT_V2_SIZE: usize = STAKE_DEPOSIT_SIZE + 64;
+
+/// Delegated operator of a deposit record, or `[0; 32]` if none is set (always
+/// the case for a v1-sized record).
+pub fn deposit_operator(data: &[u8]) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    if data.len() >= STAKE_DEPOSIT_V2_SIZE {
+        bytes.copy_from_slice(&data[DEPOSIT_OPERATOR_OFFSET..DEPOSIT_OPERATOR_OFFSET + 32]);
+    }
+    bytes
+}
+
+/// Set (or, with `[0; 32]`, clear) the delegated operator. The record must
+/// already be v2-sized.
+pub fn set_deposit_operator(data: &mut [u8], operator: [u8; 32]) -> Result<(), ProgramError> {
+    if data.len() < STAKE_DEPOSIT_V2_SIZE {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    data[DEPOSIT_OPERATOR_OFFSET..DEPOSIT_OPERATOR_OFFSET + 32].copy_from_slice(&operator);
+    Ok(())
+}
+
+/// Maximum encoded length of a proposed call (`StakeProposal::call`). The
+/// largest fee proxy today is tag 26 at 17 bytes (tag + u128).
+pub const PROPOSAL_CALL_MAX_LEN: usize = 64;
+
+/// `StakeProposal::status`: awaiting commit or cancel.
+pub const PROPOSAL_STATUS_PENDING: u8 = 1;
+/// `StakeProposal::status`: committed; the call was executed.
+pub const PROPOSAL_STATUS_COMMITTED: u8 = 2;
+/// `StakeProposal::status`: cancelled; the call will never execute.
+pub const PROPOSAL_STATUS_CANCELLED: u8 = 3;
+
+/// Timelocked proposal for an admin action that changes LP terms — today the
+/// four wrapper fee-policy proxies (tags 25..28). One account per proposal.
+/// PDA seeds: [b"stake_proposal", pool_pubkey, proposal_id (u64 LE)]
+///
+/// `call` holds the exact instruction data of the proxied call, so what LPs
+/// read on chain during the window is byte-for-byte what `CommitFeeChange`
+/// replays. The account is kept after it resolves, as a record.
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct StakeProposal {
+    /// Whether this record is initialized
+    pub is_initialized: u8,
+
+    /// Bump seed for the proposal PDA
+    pub bump: u8,
+
+    /// `PROPOSAL_STATUS_*`
+    pub status: u8,
+
+    /// Number of meaningful bytes in `call`
+    pub call_len: u8,
+
+    /// Padding
+    pub _padding: [u8; 4],
+
+    /// The stake pool this proposal governs
+    pub pool: [u8; 32],
+
+    /// Caller-chosen id (part of the PDA seeds)
+    pub proposal_id: u64,
+
+    /// Slot of the proposal; the timelock runs from here
+    pub proposed_at_slot: u64,
+
+    /// Encoded instruction data of the proposed call
+    pub call: [u8; PROPOSAL_CALL_MAX_LEN],
+
+    /// Reserved for future use ([0..8] = discriminator)
+    pub _reserved: [u8; 64],
+}
+
+/// Size of StakeProposal in bytes
+pub const STAKE_PROPOSAL_SIZE: usize = core::mem::size_of::<StakeProposal>();
+
+impl StakeProposal {
+    /// Set discriminator in first 8 bytes of _reserved. Call on init.
+    pub fn set_discriminator(&mut self) {
+        self._reserved[..8].copy_from_slice(&STAKE_PROPOSAL_DISCRIMINATOR);
+    }
+
+    /// Validate discriminator. Only accepts the correct discriminator bytes.
+    pub fn validate_discriminator(&self) -> bool {
+        self._reserved[..8] == STAKE_PROPOSAL_DISCRIMINATOR
+    }
+
+    /// The proposed call's instruction data.
+    pub fn call_data(&self) -> &[u8] {
+        &self.call[..(self.call_len as usize).min(PROPOSAL_CALL_MAX_LEN)]
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.status == PROPOSAL_STATUS_PENDING
+    }
+
+    /// LP locked against this proposal by `CastVeto` (`_reserved[8..16]`).
+    pub fn veto_lp(&self) -> u64 {
+        u64::from_le_bytes(self._reserved[8..16].try_into().unwrap())
+    }
+
+    pub fn set_veto_lp(&mut self, lp: u64) {
+        self._reserved[8..16].copy_from_slice(&lp.to_le_bytes());
+    }
+}
+
+/// `StakeVetoVote::target_kind`: the pool's pending cooldown increase.
+pub const VETO_TARGET_COOLDOWN_INCREASE: u8 = 0;
+/// `StakeVetoVote::target_kind`: the pool's pending admin rotation.
+pub const VETO_TARGET_ADMIN: u8 = 1;
+/// `StakeVetoVote::target_kind`: a `StakeProposal` (timelocked fee change).
+pub const VETO_TARGET_FEE_CHANGE: u8 = 2;
+
+/// LP one holder has locked against one pending proposal. The LP itself sits
+/// in the pool's veto escrow token account until `ReclaimVeto`, which is only
+/// possible once the target is no longer pending.
+/// PDA seeds: [b"stake_veto", pool, voter, target_kind (u8), target_nonce (u64 LE)]
+///
+/// `target_nonce` is the pool's `cooldown_proposal_nonce` /
+/// `admin_proposal_nonce` for the in-pool proposals and the `proposal_id` for
+/// a fee change.
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct StakeVetoVote {
+    /// Whether this record is initialized
+    pub is_initialized: u8,
+
+    /// Bump seed for the vote PDA
+    pub bump: u8,
+
+    /// `VETO_TARGET_*`
+    pub target_kind: u8,
+
+    /// Padding
+    pub _padding: [u8; 5],
+
+    /// The stake pool
+    pub pool: [u8; 32],
+
+    /// Who locked the LP (and may reclaim it)
+    pub voter: [u8; 32],
+
+    /// Which instance of the target this vote is against
+    pub target_nonce: u64,
+
+    /// LP held in escrow for this vote
+    pub locked_lp: u64,
+
+    /// Reserved for future use ([0..8] = discriminator)
+    pub _reserved: [u8; 32],
+}
+
+/// Size of StakeVetoVote in bytes
+pub const STAKE_VETO_VOTE_SIZE: usize = core::mem::size_of::<StakeVetoVote>();
+
+impl StakeVetoVote {
+    /// Set discriminator in first 8 bytes of _reserved. Call on init.
+    pub fn set_discriminator(&mut self) {
+        self._reserved[..8].copy_from_slice(&STAKE_VETO_VOTE_DISCRIMINATOR);
+    }
+
+    /// Validate discriminator. Only accepts the correct discriminator bytes.
+    pub fn validate_discriminator(&self) -> bool {
+        self._reserved[..8] == STAKE_VETO_VOTE_DISCRIMINATOR
+    }
+}
+
+/// Most members a `StakeCouncil` can list.
+pub const MAX_COUNCIL_MEMBERS: usize = 8;
+
+/// Optional m-of-n admin council for one pool.
+/// PDA seeds: [b"stake_council", pool]
+///
+/// The active roster only counts once the pool's `council` points here. A
+/// roster change is two-step, like `ProposeAdmin` / `AcceptAdmin`: the admin
+/// authority (the pool admin, or the current council) writes the pending
+/// roster with `ProposeCouncil`, and it takes effect when a threshold of the
+/// NEW members signs `AcceptCouncil` — which proves the keys are live and the
+/// threshold reachable. A pending roster with no members proposes dissolving
+/// the council and is accepted by the pool admin alone.
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct StakeCouncil {
+    /// Whether this record is initialized
+    pub is_initialized: u8,
+
+    /// Bump seed for the council PDA
+    pub bump: u8,
+
+    /// Distinct member signatures an admin action needs
+    pub threshold: u8,
+
+    /// Live entries at the front of `members`
+    pub member_count: u8,
+
+    /// 1 while a `ProposeCouncil` awaits `AcceptCouncil`
+    pub has_pending: u8,
+
+    /// Threshold of the pending roster
+    pub pending_threshold: u8,
+
+    /// Live entries at the front of `pending_members`
+    pub pending_member_count: u8,
+
+    /// Padding
+    pub _padding: [u8; 1],
+
+    /// The stake pool
+    pub pool: [u8; 32],
+
+    /// Active roster
+    pub members: [[u8; 32]; MAX_COUNCIL_MEMBERS],
+
+    /// Pending roster
+    pub pending_members: [[u8; 32]; MAX_COUNCIL_MEMBERS],
+
+    /// Reserved for future use ([0..8] = discriminator)
+    pub _reserved: [u8; 32],
+}
+
+/// Size of StakeCouncil in bytes
+pub const STAKE_COUNCIL_SIZE: usize = core::mem::size_of::<StakeCouncil>();
+
+impl StakeCouncil {
+    /// Set discriminator in first 8 bytes of _reserved. Call on init.
+    pub fn set_discriminator(&mut self) {
+        self._reserved[..8].copy_from_slice(&STAKE_COUNCIL_DISCRIMINATOR);
+    }
+
+    /// Validate discriminator. Only accepts the correct discriminator bytes.
+    pub fn validate_discriminator(&self) -> bool {
+        self._reserved[..8] == STAKE_COUNCIL_DISCRIMINATOR
+    }
+
+    /// The active roster.
+    pub fn active_members(&self) -> &[[u8; 32]] {
+        &self.members[..(self.member_count as usize).min(MAX_COUNCIL_MEMBERS)]
+    }
+
+    /// The pending roster.
+    pub fn pending_roster(&self) -> &[[u8; 32]] {
+        &self.pending_members[..(self.pending_member_count as usize).min(MAX_COUNCIL_MEMBERS)]
+    }
+}
+
+/// Whether `threshold` over `members` is a usable roster: at most
+/// `MAX_COUNCIL_MEMBERS` distinct non-zero keys and `1 <= threshold <= len`.
+/// The empty roster with threshold 0 is also accepted — it proposes
+/// dissolving the council.
+pub fn is_valid_council_roster(threshold: u8, members: &[[u8; 32]]) -> bool {
+    if members.is_empty() {
+        return threshold == 0;
+    }
+    if members.len() > MAX_COUNCIL_MEMBERS || threshold == 0 || threshold as usize > members.len() {
+        return false;
+    }
+    members
+        .iter()
+        .enumerate()
+        .all(|(i, m)| *m != [0u8; 32] && !members[..i].contains(m))
+}
+
+/// How many DISTINCT members of `roster` are among `signers`. A key listed
+/// twice in an instruction's accounts still counts once.
+pub fn count_council_signers<'a>(
+    roster: &[[u8; 32]],
+    signers: impl Iterator<Item = &'a Pubkey>,
+) -> usize {
+    let mut seen = [false; MAX_COUNCIL_MEMBERS];
+    for signer in signers {
+        if let Some(i) = roster.iter().position(|m| *m == signer.to_bytes()) {
+            seen[i] = true;
+        }
+    }
+    seen.iter().filter(|s| **s).count()
 }
 
 impl StakePool {
@@ -496,36 +1175,40 @@ impl StakePool {
     /// insurance losses that the junior tranche must absorb first, returning the
     /// true collateral backing junior LP tokens.
     pub fn effective_junior_balance(&self) -> u64 {
-        let jb = self.junior_balance();
-        // net_loss = total_flushed - total_returned (tokens sent to insurance but not yet returned)
-        let net_loss = self.total_flushed.saturating_sub(self.total_returned);
-        if net_loss == 0 {
-            return jb;
-        }
-        // BUG-6: Must distribute loss against the GROSS (pre-loss) balances, not the
-        // loss-adjusted pool value that total_pool_value() already returns.
-        //
-        // total_pool_value() = deposited - withdrawn - flushed + returned
-        //                    = gross_pool - net_loss
-        //
-        // If we used total_pool_value() - jb as senior_bal here, the senior_bal would
-        // already be net_loss lower than its true gross value.  Calling distribute_loss
-        // with that deflated senior_bal causes junior to absorb MORE loss than it
-        // should — i.e., the loss is applied twice against the junior side.
-        //
-        // Fix: derive gross balances as if no loss occurred yet, then apply the loss once.
-        // gross_pool = total_deposited - total_withdrawn (the monotonic principal, no flush/return)
-        let gross_pool = self.total_deposited.saturating_sub(self.total_withdrawn);
-        // gross_senior = gross_pool - jb (the stored junior_balance() is the gross junior)
-        let gross_senior = gross_pool.saturating_sub(jb);
-        let (junior_loss, _) = crate::math::distribute_loss(jb, gross_senior, net_loss);
-        jb.saturating_sub(junior_loss)
+        crate::math::effective_junior_balance(
+            self.total_deposited,
+            self.total_withdrawn,
+            self.total_flushed,
+            self.total_returned,
+            self.junior_balance(),
+        )
     }
 
     /// Derived: senior balance = total_pool_value - effective_junior_balance.
     pub fn senior_balance(&self) -> Option<u64> {
-        self.total_pool_value()?
-            .checked_sub(self.effective_junior_balance())
+        crate::math::senior_balance(
+            self.total_deposited,
+            self.total_withdrawn,
+            self.total_flushed,
+            self.total_returned,
+            self.total_fees_earned,
+            self.dead_value()?,
+            self.junior_balance(),
+        )
+    }
+
+    /// Value the ledger counts but the pool no longer has: the #161 realized
+    /// junior loss plus any written-off vault deficit. `None` on overflow.
+    pub fn dead_value(&self) -> Option<u64> {
+        self.realized_junior_loss()
+            .checked_add(self.deficit_written_off)
+    }
+
+    /// Whether `DeclareDeficit` (or a pricing path that found the vault
+    /// short) has put the pool into deficit mode, and the write-off has not
+    /// all come back yet.
+    pub fn in_deficit(&self) -> bool {
+        self.deficit_mode == 1
     }
 
     /// Collateral still physically recoverable from the WRAPPER insurance fund:
@@ -561,6 +1244,78 @@ impl StakePool {
             .saturating_sub(self.total_recovered_from_wrapper)
     }
 
+    /// The pool's stake-side fee-policy bounds.
+    pub fn fee_policy_bounds(&self) -> FeePolicyBounds {
+        FeePolicyBounds {
+            min_lp_share_bps: self.fee_policy_min_lp_share_bps,
+            max_trade_fee_base_bps: self.fee_policy_max_trade_fee_base_bps,
+            max_change_delta_bps: self.fee_policy_max_change_delta_bps,
+            min_change_interval_slots: self.fee_policy_min_change_interval_slots,
+            max_backing_fee_bps: self.fee_policy_max_backing_fee_bps,
+        }
+    }
+
+    pub fn set_fee_policy_bounds(&mut self, bounds: FeePolicyBounds) {
+        self.fee_policy_min_lp_share_bps = bounds.min_lp_share_bps;
+        self.fee_policy_max_trade_fee_base_bps = bounds.max_trade_fee_base_bps;
+        self.fee_policy_max_change_delta_bps = bounds.max_change_delta_bps;
+        self.fee_policy_min_change_interval_slots = bounds.min_change_interval_slots;
+        self.fee_policy_max_backing_fee_bps = bounds.max_backing_fee_bps;
+    }
+
+    /// Hand out a fresh nonce for a new in-pool proposal. Never 0.
+    pub fn next_proposal_nonce(&mut self) -> u64 {
+        self.proposal_counter = self.proposal_counter.saturating_add(1);
+        self.proposal_counter
+    }
+
+    /// Nonce of the pending instance of in-pool veto target `kind` at slot
+    /// `now`, or None if nothing of that kind is pending (or `kind` is not an
+    /// in-pool target). A proposal past `PROPOSAL_EXPIRY_SLOTS` can no longer
+    /// be accepted or committed, so it is not pending either.
+    pub fn pending_proposal_nonce(&self, kind: u8, now: u64) -> Option<u64> {
+        let live = |proposed_at: u64| !proposal_expired(proposed_at, PROPOSAL_EXPIRY_SLOTS, now);
+        match kind {
+            VETO_TARGET_COOLDOWN_INCREASE
+                if self.cooldown_proposed_at_slot != 0 && live(self.cooldown_proposed_at_slot) =>
+            {
+                Some(self.cooldown_proposal_nonce)
+            }
+            VETO_TARGET_ADMIN
+                if self.pending_admin != [0u8; 32] && live(self.admin_proposed_at_slot) =>
+            {
+                Some(self.admin_proposal_nonce)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether an adverse admin proposal — a cooldown increase or an admin
+    /// transfer — is pending at slot `now`. While one is, withdrawals skip the
+    /// per-user cooldown so the timelock window is a real exit.
+    pub fn adverse_proposal_pending(&self, now: u64) -> bool {
+        self.pending_proposal_nonce(VETO_TARGET_COOLDOWN_INCREASE, now)
+            .is_some()
+            || self
+                .pending_proposal_nonce(VETO_TARGET_ADMIN, now)
+                .is_some()
+    }
+
+    /// Holder of `role`, or `[0; 32]` if unassigned (or `role` is unknown).
+    pub fn role_holder(&self, role: u8) -> [u8; 32] {
+        self.roles.get(role as usize).copied().unwrap_or([0u8; 32])
+    }
+
+    /// Whether every bit of `lock` (a `CONFIG_LOCK_*` mask) is set.
+    pub fn is_config_locked(&self, lock: u8) -> bool {
+        self.config_locks & lock == lock
+    }
+
+    /// Whether `Finalize` has moved the pool into its terminal sunset phase.
+    pub fn is_finalized(&self) -> bool {
+        self.finalized_at_slot != 0
+    }
+
     /// The H-1 resolve threshold: all wrapper-recoverable insurance has been pulled
     /// back. Equivalent to `wrapper_recoverable() == 0`, named for the call sites.
     pub fn wrapper_fully_recovered(&self) -> bool {
@@ -581,7 +1336,12 @@ impl StakePool {
     /// 3 for a 408-byte layout would let a v3 account pass the version check and
     /// then fail the length check in `pool_from_data`. Fresh-start cutover: live
     /// v3 pools are re-seeded, so no on-chain migration path is provided.
-    pub const CURRENT_VERSION: u8 = 4;
+    /// v5 (size 408 -> 736): appended the sunset fields `finalized_at_slot` /
+    /// `final_sweep_recipient`, the fee-policy bounds, the LP-veto state, the
+    /// `council` reference, the role table, `admin_proposed_at_slot`, the flush
+    /// policy with the config locks, `deficit_written_off`, the backing-fee
+    /// bound and `deficit_junior_written_off`. Same fresh-start cutover as v4.
+    pub const CURRENT_VERSION: u8 = 5;
 
     /// Set discriminator in first 8 bytes of _reserved and version in byte 8.
     /// Call on init.
@@ -703,36 +1463,37 @@ impl StakePool {
     /// includes the flushed amount. Missing `-flushed` causes phantom inflation
     /// that makes the pool insolvent after any flush+return cycle.
     pub fn total_pool_value(&self) -> Option<u64> {
-        // #169: sum in a wide SIGNED intermediate. In mode 1 a withdrawer's payout
-        // includes their share of accrued fees, so `total_withdrawn` can legitimately
-        // exceed `total_deposited` once fees have been paid out. The old left-to-right
-        // `total_deposited.checked_sub(total_withdrawn)` then underflowed to None and
-        // PERMANENTLY BRICKED the pool (LP funds trapped) even though the true value was
-        // still positive. Computing in i128 makes evaluation order irrelevant; we only
-        // fail closed when the FINAL value is genuinely out of range — i.e. negative
-        // (insolvent: claims exceed assets) or impossibly large. This preserves the
-        // insolvency guard while eliminating the false-underflow brick.
+        // #169: summed in i128 so a mode-1 pool whose fee-inclusive payouts pushed
+        // `total_withdrawn` past `total_deposited` is not falsely bricked; only a
+        // genuinely negative (insolvent) or out-of-range value is None.
         //
-        // PERC-272: accrued trading fees count toward a mode-1 trading pool's value.
         // PERC-272: accrued trading fees count toward a trading pool's value.
         // 2026-07-19: mode-0 insurance pools also accrue fees (the insurance
         // leg of the trade-fee split), so their fees count too. Both modes now
         // include total_fees_earned; the field is 0 for any pool that has never
         // accrued, so this is a no-op for existing mode-0 pools.
-        let fees = self.total_fees_earned as i128;
-        // #161: subtract realized (forfeited) junior loss — dead value the exit booking
-        // added to `total_returned` but that is NOT claimable by senior. Normally 0.
-        let value = self.total_deposited as i128
-            - self.total_withdrawn as i128
-            - self.total_flushed as i128
+        //
+        // #161: realized (forfeited) junior loss is dead value, excluded — and so
+        // is a written-off vault deficit (see `dead_value`).
+        crate::math::total_pool_value(
+            self.total_deposited,
+            self.total_withdrawn,
+            self.total_flushed,
+            self.total_returned,
+            self.total_fees_earned,
+            self.dead_value()?,
+        )
+    }
+
+    /// `total_pool_value()` before its range check: negative when the ledger
+    /// has paid out more than it booked, and past `u64::MAX` when it overflows.
+    /// Only `accounting::write_down_deficit` prices from it.
+    pub fn ledger_value(&self) -> i128 {
+        self.total_deposited as i128 - self.total_withdrawn as i128 - self.total_flushed as i128
             + self.total_returned as i128
-            + fees
-            - self.realized_junior_loss() as i128;
-        if value < 0 || value > u64::MAX as i128 {
-            None
-        } else {
-            Some(value as u64)
-        }
+            + self.total_fees_earned as i128
+            - self.realized_junior_loss() as i128
+            - self.deficit_written_off as i128
     }
 
     /// Principal-basis TVL: `deposited − withdrawn − flushed + returned`, WITHOUT
@@ -749,12 +1510,14 @@ impl StakePool {
         // underflowed to None and bricked DEPOSITS (the cap check fails closed on None).
         // A principal basis can't be negative, so clamp a net-negative result to 0 — no
         // live principal means the cap simply admits new deposits, rather than bricking.
-        // #161: realized (forfeited) junior loss is dead value, excluded from the cap basis.
+        // #161: realized (forfeited) junior loss is dead value, excluded from the cap basis,
+        // as is a written-off vault deficit.
         let value = self.total_deposited as i128
             - self.total_withdrawn as i128
             - self.total_flushed as i128
             + self.total_returned as i128
-            - self.realized_junior_loss() as i128;
+            - self.realized_junior_loss() as i128
+            - self.deficit_written_off as i128;
         Some(value.clamp(0, u64::MAX as i128) as u64)
     }
 
@@ -787,6 +1550,53 @@ pub fn derive_vault_authority(program_id: &Pubkey, pool: &Pubkey) -> (Pubkey, u8
     Pubkey::find_program_address(&[b"vault_auth", pool.as_ref()], program_id)
 }
 
+/// Derive the LP-burn delegate PDA for a given pool.
+/// `SetDepositOperator` approves it as SPL delegate on the owner's LP token
+/// account; it signs nothing but the `OperatorWithdraw` burn. Kept separate
+/// from `vault_auth`, which the program also hands to wrapper CPIs as a signer.
+pub fn derive_lp_delegate(program_id: &Pubkey, pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"lp_delegate", pool.as_ref()], program_id)
+}
+
+/// Derive the proposal PDA for `(pool, proposal_id)`.
+pub fn derive_proposal_pda(program_id: &Pubkey, pool: &Pubkey, proposal_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"stake_proposal", pool.as_ref(), &proposal_id.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// Derive a voter's veto record for one instance of one target.
+pub fn derive_veto_vote_pda(
+    program_id: &Pubkey,
+    pool: &Pubkey,
+    voter: &Pubkey,
+    target_kind: u8,
+    target_nonce: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            b"stake_veto",
+            pool.as_ref(),
+            voter.as_ref(),
+            &[target_kind],
+            &target_nonce.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Derive the pool's veto escrow: an LP token account (authority =
+/// `vault_auth`) holding every LP currently locked in a veto.
+pub fn derive_veto_escrow(program_id: &Pubkey, pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"veto_escrow", pool.as_ref()], program_id)
+}
+
+/// Derive the pool's admin council PDA.
+pub fn derive_council_pda(program_id: &Pubkey, pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"stake_council", pool.as_ref()], program_id)
+}
+
 /// Derive the per-user deposit PDA.
 pub fn derive_deposit_pda(program_id: &Pubkey, pool: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(
@@ -806,7 +1616,14 @@ mod tests {
         // v4 size: v3's 392 + pending_cooldown_slots[8] + cooldown_proposed_at_slot[8]
         //   = 408. Both APPENDED after total_recovered_from_wrapper (384), so no
         //   existing offset moves.
-        assert_eq!(STAKE_POOL_SIZE, 408);
+        // v5 size: 408 + finalized_at_slot[8] + final_sweep_recipient[32] +
+        //   fee-policy bounds and history (5 * 8) + veto nonces/tallies (5 * 8)
+        //   + threshold and padding (8) + council[32] + roles and pending roles
+        //   (2 * 2 * 32) + admin_proposed_at_slot[8] + flush policy[2] +
+        //   config_locks[1] + padding[5] + deficit_written_off[8] + backing-fee
+        //   bound, history and padding (4 * 2) + deficit_junior_written_off[8]
+        //   = 736, all appended.
+        assert_eq!(STAKE_POOL_SIZE, 736);
     }
 
     #[test]
@@ -816,6 +1633,129 @@ mod tests {
         assert_eq!(STAKE_DEPOSIT_SIZE, 152);
     }
 
+    #[test]
+    fn test_stake_proposal_size() {
+        assert_eq!(STAKE_PROPOSAL_SIZE, std::mem::size_of::<StakeProposal>());
+        // 1+1+1+1+4 + 32 + 2*8 + 64 + 64 = 8 + 32 + 16 + 128 = 184
+        assert_eq!(STAKE_PROPOSAL_SIZE, 184);
+    }
+
+    #[test]
+    fn test_stake_veto_vote_size() {
+        // 1+1+1+5 + 32 + 32 + 8 + 8 + 32 = 120
+        assert_eq!(STAKE_VETO_VOTE_SIZE, 120);
+    }
+
+    #[test]
+    fn test_stake_council_size() {
+        // 8 + 32 + 2 * 8 * 32 + 32 = 584
+        assert_eq!(STAKE_COUNCIL_SIZE, 584);
+    }
+
+    #[test]
+    fn test_council_roster_validation() {
+        let (a, b, c) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        assert!(is_valid_council_roster(2, &[a, b, c]));
+        assert!(is_valid_council_roster(3, &[a, b, c]));
+        assert!(is_valid_council_roster(0, &[]), "dissolution");
+        assert!(!is_valid_council_roster(1, &[]));
+        assert!(!is_valid_council_roster(0, &[a]));
+        assert!(!is_valid_council_roster(4, &[a, b, c]));
+        assert!(!is_valid_council_roster(1, &[a, a]), "duplicate member");
+        assert!(!is_valid_council_roster(1, &[a, [0u8; 32]]), "zero key");
+        assert!(!is_valid_council_roster(1, &[a; MAX_COUNCIL_MEMBERS + 1]));
+    }
+
+    #[test]
+    fn test_count_council_signers_is_distinct() {
+        let roster = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        let (k1, k2, outsider) = (
+            Pubkey::new_from_array([1u8; 32]),
+            Pubkey::new_from_array([2u8; 32]),
+            Pubkey::new_from_array([9u8; 32]),
+        );
+        assert_eq!(count_council_signers(&roster, [k1, k1, k1].iter()), 1);
+        assert_eq!(count_council_signers(&roster, [k1, outsider, k2].iter()), 2);
+        assert_eq!(count_council_signers(&[], [k1].iter()), 0);
+    }
+
+    #[test]
+    fn test_flush_within_policy() {
+        // 1_000 in the vault, nothing outstanding: 20% of 1_000 may be flushed.
+        assert!(flush_within_policy(0, 1_000, 200, 2_000));
+        assert!(!flush_within_policy(0, 1_000, 201, 2_000));
+        // 100 already in the wrapper out of 1_100 gross: 120 more reaches 20%.
+        assert!(flush_within_policy(100, 1_000, 120, 2_000));
+        assert!(!flush_within_policy(100, 1_000, 121, 2_000));
+        // 0 bps admits nothing; extremes do not overflow.
+        assert!(!flush_within_policy(0, 1_000, 1, 0));
+        assert!(flush_within_policy(u64::MAX, u64::MAX, u64::MAX, 10_000));
+    }
+
+    #[test]
+    fn test_config_locks() {
+        let mut pool = StakePool::zeroed();
+        assert!(!pool.is_config_locked(CONFIG_LOCK_HWM));
+        pool.config_locks = CONFIG_LOCK_HWM | CONFIG_LOCK_DEPOSIT_CAP;
+        assert!(pool.is_config_locked(CONFIG_LOCK_HWM));
+        assert!(!pool.is_config_locked(CONFIG_LOCK_ALL));
+        pool.config_locks |= CONFIG_LOCK_FLUSH_POLICY;
+        assert!(pool.is_config_locked(CONFIG_LOCK_ALL));
+    }
+
+    #[test]
+    fn test_pending_proposal_nonce() {
+        let mut pool = StakePool::zeroed();
+        assert_eq!(
+            pool.pending_proposal_nonce(VETO_TARGET_COOLDOWN_INCREASE, 10),
+            None
+        );
+        assert_eq!(pool.pending_proposal_nonce(VETO_TARGET_ADMIN, 10), None);
+
+        pool.cooldown_proposed_at_slot = 10;
+        pool.cooldown_proposal_nonce = pool.next_proposal_nonce();
+        pool.pending_admin = [7u8; 32];
+        pool.admin_proposed_at_slot = 20;
+        pool.admin_proposal_nonce = pool.next_proposal_nonce();
+        assert_eq!(
+            pool.pending_proposal_nonce(VETO_TARGET_COOLDOWN_INCREASE, 10),
+            Some(1)
+        );
+        assert_eq!(pool.pending_proposal_nonce(VETO_TARGET_ADMIN, 10), Some(2));
+        // Fee changes live in their own accounts.
+        assert_eq!(
+            pool.pending_proposal_nonce(VETO_TARGET_FEE_CHANGE, 10),
+            None
+        );
+
+        // Expiry ends each proposal on its own clock.
+        let cooldown_expires = 10 + PROPOSAL_EXPIRY_SLOTS + 1;
+        assert_eq!(
+            pool.pending_proposal_nonce(VETO_TARGET_COOLDOWN_INCREASE, cooldown_expires - 1),
+            Some(1)
+        );
+        assert_eq!(
+            pool.pending_proposal_nonce(VETO_TARGET_COOLDOWN_INCREASE, cooldown_expires),
+            None
+        );
+        assert_eq!(
+            pool.pending_proposal_nonce(VETO_TARGET_ADMIN, cooldown_expires),
+            Some(2)
+        );
+        assert!(pool.adverse_proposal_pending(cooldown_expires));
+        assert!(!pool.adverse_proposal_pending(20 + PROPOSAL_EXPIRY_SLOTS + 1));
+
+        // Resolving clears the pending marker; the nonce alone does not count.
+        pool.cooldown_proposed_at_slot = 0;
+        assert_eq!(
+            pool.pending_proposal_nonce(VETO_TARGET_COOLDOWN_INCREASE, 10),
+            None
+        );
+        assert!(pool.adverse_proposal_pending(10));
+        pool.pending_admin = [0u8; 32];
+        assert!(!pool.adverse_proposal_pending(10));
+    }
+
     #[test]
     fn test_pool_value_normal() {
         let mut pool = StakePool::zeroed();
@@ -1001,6 +1941,19 @@ mod tests {
         assert_ne!(dep1, dep2);
     }
 
+    #[test]
+    fn test_proposal_pda_per_pool_and_id() {
+        let program_id = Pubkey::new_unique();
+        let pool1 = Pubkey::new_unique();
+        let pool2 = Pubkey::new_unique();
+
+        let (a, _) = derive_proposal_pda(&program_id, &pool1, 1);
+        let (b, _) = derive_proposal_pda(&program_id, &pool1, 2);
+        let (c, _) = derive_proposal_pda(&program_id, &pool2, 1);
+        assert_ne!(a, b);
+        assert_ne!(a, c);
+    }
+
     #[test]
     fn test_pubkey_helpers() {
         let mut pool = StakePool::zeroed();
@@ -1186,6 +2139,77 @@ mod tests {
         assert_eq!(pool.realized_junior_loss(), 0x0102_0304_0506_0708);
     }
 
+    #[test]
+    fn test_authorized_depositor_uses_deposit_reserved_16_to_48() {
+        let mut dep = StakeDeposit::zeroed();
+        dep.set_discriminator();
+        dep.set_junior_deposit(true);
+        assert_eq!(dep.authorized_depositor(), [0u8; 32]);
+
+        dep.set_authorized_depositor([0xAB; 32]);
+        assert_eq!(dep.authorized_depositor(), [0xAB; 32]);
+        assert_eq!(&dep._reserved[16..48], &[0xAB; 32]);
+        assert!(
+            dep.validate_discriminator(),
+            "must not clobber discriminator"
+        );
+        assert!(dep.is_junior_deposit(), "must not clobber the junior flag");
+
+        dep.set_authorized_depositor([0u8; 32]);
+        assert_eq!(dep.authorized_depositor(), [0u8; 32]);
+    }
+
+    #[test]
+    fn test_recorded_cooldown_uses_deposit_reserved_48_to_56() {
+        let mut dep = StakeDeposit::zeroed();
+        dep.set_discriminator();
+        dep.set_junior_deposit(true);
+        dep.set_authorized_depositor([0xAB; 32]);
+
+        // Pre-stamp record: follows the pool's current value.
+        assert_eq!(dep.recorded_cooldown_slots(), None);
+        assert_eq!(dep.effective_cooldown_slots(900), 900);
+
+        dep.stamp_legacy_cooldown(500);
+        assert_eq!(dep.recorded_cooldown_slots(), Some(500));
+        // Stamping never overwrites recorded terms.
+        dep.stamp_legacy_cooldown(700);
+        assert_eq!(dep.recorded_cooldown_slots(), Some(500));
+        // Increases do not reach the position; decreases do.
+        assert_eq!(dep.effective_cooldown_slots(900), 500);
+        assert_eq!(dep.effective_cooldown_slots(200), 200);
+
+        dep.set_recorded_cooldown_slots(900);
+        assert_eq!(dep.effective_cooldown_slots(900), 900);
+        assert_eq!(&dep._reserved[48..56], &900u64.to_le_bytes());
+        assert!(dep.validate_discriminator());
+        assert!(dep.is_junior_deposit());
+        assert_eq!(dep.authorized_depositor(), [0xAB; 32]);
+    }
+
+    #[test]
+    fn test_deposit_operator_lives_in_v2_tail() {
+        let mut dep = StakeDeposit::zeroed();
+        dep.set_discriminator();
+        dep.set_authorized_depositor([0xCD; 32]);
+
+        // v1-sized record: no operator, and it cannot hold one.
+        let mut v1 = bytemuck::bytes_of(&dep).to_vec();
+        assert_eq!(deposit_operator(&v1), [0u8; 32]);
+        assert!(set_deposit_operator(&mut v1, [7u8; 32]).is_err());
+
+        // v2: the tail holds the operator; the v1 struct bytes are untouched.
+        let mut v2 = v1.clone();
+        v2.resize(STAKE_DEPOSIT_V2_SIZE, 0);
+        assert_eq!(deposit_operator(&v2), [0u8; 32]);
+        set_deposit_operator(&mut v2, [7u8; 32]).unwrap();
+        assert_eq!(deposit_operator(&v2), [7u8; 32]);
+        assert_eq!(&v2[..STAKE_DEPOSIT_SIZE], &v1[..]);
+
+        set_deposit_operator(&mut v2, [0u8; 32]).unwrap();
+        assert_eq!(deposit_operator(&v2), [0u8; 32]);
+    }
+
     #[test]
     fn test_pool_value_returns_overflow() {
         let mut pool = StakePool::zeroed();
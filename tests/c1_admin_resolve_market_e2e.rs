@@ -304,6 +304,7 @@ fn inject_pool(
     pool.slab = market.to_bytes();
     pool.admin = admin.to_bytes();
     pool.percolator_program = wrapper_id.to_bytes();
+    pool.wrapper_abi_version = percolator_stake::cpi::WRAPPER_ABI_V17;
     pool.total_flushed = total_flushed;
     pool.total_returned = total_returned;
     pool.total_recovered_from_wrapper = total_recovered_from_wrapper;
@@ -738,6 +739,7 @@ fn inject_pool_with_vault(
     pool.vault = stake_vault.to_bytes();
     pool.total_deposited = stake_vault_amount;
     pool.percolator_program = wrapper_id.to_bytes();
+    pool.wrapper_abi_version = percolator_stake::cpi::WRAPPER_ABI_V17;
     pool.pool_mode = 0;
     pool.set_discriminator();
 
@@ -8,11 +8,21 @@
 //! (UpdateAuthority) with kind=2 (AUTHORITY_INSURANCE) = 34 bytes. The v17 auth
 //! overhaul changed this to tag 65 (UpdateAssetAuthority): tag 32→65, kind 2→1
 //! (ASSET_AUTH_INSURANCE, a different constant family), plus a new 2-byte
-//! asset_index prefix (always 0). Total: 36 bytes.
+//! asset_index prefix (the pool's `wrapper_asset_index`, 0 for single-asset
+//! markets). Total: 36 bytes.
 //!
 //! CANARY POLICY: any change to these tests requires a matching change to both
 //! src/cpi.rs AND the wrapper's v17_convergence branch (they must stay in sync).
 
+use percolator_stake::cpi::{
+    pool_wrapper_abi, wrapper_abi, WrapperAbi, WrapperV16, WrapperV17, ASSET_AUTH_ADMIN,
+    ASSET_AUTH_INSURANCE, ASSET_AUTH_INSURANCE_OPERATOR, MARKET_HEADER_LEN, WRAPPER_ABI_V16,
+    WRAPPER_ABI_V17,
+};
+use percolator_stake::error::StakeError;
+use percolator_stake::state::StakePool;
+use solana_program::program_error::ProgramError;
+
 // ── Tag 9: TopUpInsurance ─────────────────────────────────────────────────────
 
 /// The tag-9 wire is `tag(1) + amount(16, u128 LE)` = 17 bytes.
@@ -241,6 +251,179 @@ fn test_bind_and_rotate_produce_same_wire_shape() {
     );
 }
 
+// ── Non-zero asset_index: per-asset insurance profiles ───────────────────────
+
+/// A pool backing asset N must name N in bytes 1-2 of every tag-65 CPI, little
+/// endian, with the tag, kind and pubkey exactly where they are for asset 0.
+/// 0x0102 puts distinct values in both bytes so a byte-order swap is caught.
+#[test]
+fn test_cpi_tag65_encodes_nonzero_asset_index() {
+    let pda = [0x11u8; 32];
+    for (kind, new_pubkey) in [
+        (ASSET_AUTH_INSURANCE, pda),
+        (ASSET_AUTH_INSURANCE_OPERATOR, pda),
+        (ASSET_AUTH_ADMIN, [0u8; 32]),
+    ] {
+        let data = WrapperV17
+            .update_asset_authority(0x0102, kind, &new_pubkey)
+            .unwrap();
+        assert_eq!(data.len(), 36, "tag-65 wire stays 36 bytes");
+        assert_eq!(data[0], 65, "byte 0 must be tag=65");
+        assert_eq!(data[1], 0x02, "asset_index low byte first (LE)");
+        assert_eq!(data[2], 0x01, "asset_index high byte second (LE)");
+        assert_eq!(data[3], kind, "kind at byte 3");
+        assert_eq!(&data[4..36], &new_pubkey, "new_pubkey at bytes [4..36]");
+    }
+
+    // Asset 0 is still the exact wire the canary above pins.
+    let zero = WrapperV17
+        .update_asset_authority(0, ASSET_AUTH_INSURANCE, &[0xABu8; 32])
+        .unwrap();
+    assert_eq!(&zero[0..4], &[65, 0x00, 0x00, 1]);
+
+    // The highest asset index round-trips.
+    let max = WrapperV17
+        .update_asset_authority(u16::MAX, ASSET_AUTH_INSURANCE, &pda)
+        .unwrap();
+    assert_eq!(u16::from_le_bytes([max[1], max[2]]), u16::MAX);
+}
+
+/// The tag-57 recovery must pull from the same asset the pool bound: asset
+/// index at bytes 1-2, the u128 amount unmoved at bytes 3-18.
+#[test]
+fn test_cpi_tag57_encodes_nonzero_asset_index() {
+    let amount: u64 = 250_000;
+    let data = WrapperV17.withdraw_insurance_asset(7, amount).unwrap();
+    assert_eq!(data.len(), 19, "tag-57 wire stays 19 bytes");
+    assert_eq!(data[0], 57, "byte 0 must be tag=57");
+    assert_eq!(&data[1..3], &[0x07, 0x00], "asset_index = 7, u16 LE");
+    let decoded = u128::from_le_bytes(data[3..19].try_into().unwrap());
+    assert_eq!(decoded, amount as u128, "amount at bytes [3..19]");
+
+    let data = WrapperV17.withdraw_insurance_asset(0x0102, amount).unwrap();
+    assert_eq!(&data[1..3], &[0x02, 0x01], "asset_index = 0x0102, u16 LE");
+    assert_eq!(
+        &WrapperV17.withdraw_insurance_asset(0, amount).unwrap()[1..3],
+        &[0x00, 0x00],
+        "asset 0 is unchanged"
+    );
+}
+
+// ── WrapperAbi: per-revision encoders ────────────────────────────────────────
+
+/// The wires v16 and v17 share come from the trait's default methods, so the
+/// two ABIs must agree on them byte for byte.
+#[test]
+fn test_wrapper_abis_share_the_unchanged_wires() {
+    let (v16, v17) = (
+        &WrapperV16 as &dyn WrapperAbi,
+        &WrapperV17 as &dyn WrapperAbi,
+    );
+    let key = [0xCDu8; 32];
+    assert_eq!(v16.top_up_insurance(1_000), v17.top_up_insurance(1_000));
+    assert_eq!(v17.top_up_insurance(1_000).len(), 17);
+    assert_eq!(v16.update_authority(&key), v17.update_authority(&key));
+    assert_eq!(v17.update_authority(&key).len(), 33);
+    assert_eq!(v16.resolve_market(), vec![19u8]);
+    assert_eq!(v17.resolve_market(), vec![19u8]);
+    assert_eq!(v16.update_fee_split(1, 2, 3), v17.update_fee_split(1, 2, 3));
+    assert_eq!(
+        v16.update_maintenance_fee_per_slot(7),
+        v17.update_maintenance_fee_per_slot(7)
+    );
+    assert_eq!(
+        v16.update_backing_fee_policy(0, 5, 6),
+        v17.update_backing_fee_policy(0, 5, 6)
+    );
+    assert_eq!(
+        v16.update_trade_fee_policy(9),
+        v17.update_trade_fee_policy(9)
+    );
+}
+
+/// v16 binds the insurance authority through tag 32 with kind 2 — the exact
+/// 34-byte wire `test_old_v16_bind_wire_documents_the_break` pins — and has no
+/// wire for anything else per-asset.
+#[test]
+fn test_wrapper_v16_insurance_bind_wire_and_gaps() {
+    let pda = [0xABu8; 32];
+    let data = WrapperV16
+        .update_asset_authority(0, ASSET_AUTH_INSURANCE, &pda)
+        .unwrap();
+    assert_eq!(data.len(), 34, "v16 bind wire is 34 bytes");
+    assert_eq!(
+        &data[0..2],
+        &[32, 2],
+        "tag 32, kind 2 (AUTHORITY_INSURANCE)"
+    );
+    assert_eq!(&data[2..34], &pda);
+
+    let unsupported: ProgramError = StakeError::WrapperAbiUnsupported.into();
+    for (asset_index, kind) in [
+        (0, ASSET_AUTH_INSURANCE_OPERATOR),
+        (0, ASSET_AUTH_ADMIN),
+        (1, ASSET_AUTH_INSURANCE),
+    ] {
+        assert_eq!(
+            WrapperV16.update_asset_authority(asset_index, kind, &pda),
+            Err(unsupported.clone())
+        );
+    }
+    assert_eq!(
+        WrapperV16.withdraw_insurance_asset(0, 1),
+        Err(unsupported.clone())
+    );
+}
+
+/// Only known revisions resolve, for a pool as for a bare version number.
+#[test]
+fn test_wrapper_abi_version_resolution() {
+    assert_eq!(wrapper_abi(WRAPPER_ABI_V16).unwrap().version(), 16);
+    assert_eq!(wrapper_abi(WRAPPER_ABI_V17).unwrap().version(), 17);
+    let unsupported: ProgramError = StakeError::WrapperAbiUnsupported.into();
+    for version in [0u8, 12, 15, 18, u8::MAX] {
+        assert_eq!(wrapper_abi(version).err(), Some(unsupported.clone()));
+    }
+
+    let mut pool: StakePool = bytemuck::Zeroable::zeroed();
+    assert_eq!(pool_wrapper_abi(&pool).err(), Some(unsupported.clone()));
+    pool.wrapper_abi_version = WRAPPER_ABI_V17;
+    assert_eq!(pool_wrapper_abi(&pool).unwrap().version(), WRAPPER_ABI_V17);
+    pool.wrapper_abi_version = WRAPPER_ABI_V16;
+    assert_eq!(pool_wrapper_abi(&pool).unwrap().version(), WRAPPER_ABI_V16);
+    pool.wrapper_abi_version = 12;
+    assert_eq!(pool_wrapper_abi(&pool).err(), Some(unsupported));
+}
+
+/// The market probe tells revisions apart by length. Every size the e2e
+/// fixtures have used is accounted for: 3147 is the current one-asset v17
+/// market; 3107 is v16's; 2987, 3003 and 3067 were intermediate v17 builds
+/// that neither ABI accepts.
+#[test]
+fn test_wrapper_abi_market_asset_capacity() {
+    assert_eq!(WrapperV17.market_asset_capacity(3147), Some(1));
+    assert_eq!(WrapperV17.market_asset_capacity(3147 + 1797), Some(2));
+    assert_eq!(WrapperV17.market_asset_capacity(3147 + 7 * 1797), Some(8));
+    assert_eq!(WrapperV16.market_asset_capacity(3107), Some(1));
+
+    for len in [
+        0,
+        MARKET_HEADER_LEN,
+        592 + 758,
+        3146,
+        3148,
+        3107,
+        2987,
+        3003,
+        3067,
+    ] {
+        assert_eq!(WrapperV17.market_asset_capacity(len), None, "v17 len {len}");
+    }
+    for len in [0, 3147, 2987, 3003, 3067, 3107 + 1797] {
+        assert_eq!(WrapperV16.market_asset_capacity(len), None, "v16 len {len}");
+    }
+}
+
 // ── Tag 19: ResolveMarket (C-1 fix — AdminResolveMarket CPI proxy) ───────────
 
 /// C-1 CANARY: the ResolveMarket (tag 19) wire is exactly 1 byte — the bare tag,
@@ -0,0 +1,803 @@
+//! Compute-unit budgets for the stake program's heaviest instruction paths.
+//!
+//! Runs one pool through its life against the in-repo mock wrapper (see
+//! `mock_wrapper_e2e.rs`) and records `compute_units_consumed` for pool setup,
+//! the deposit, withdraw and fee-accrual paths, the insurance custody CPIs and
+//! the timelocked fee proxies, driving the expensive variant of each: tranches
+//! and the HWM floor enabled, a genesis deposit, a new depositor's PDA
+//! creation, and an un-accrued vault surplus for the pre-accrual to book.
+//!
+//! Not every instruction is measured. Still unbudgeted: `DepositFor`,
+//! `WithdrawTo`, `OperatorWithdraw`, `SetDepositOperator` and the other
+//! operator settings; `CloseDeposit`; the sunset path (`Finalize`,
+//! `RedeemFinal`, `SweepAndClosePool`); `CastVeto` and `ReclaimVeto`;
+//! `DeclareDeficit`; `ReturnInsurance`; the insurance authority and operator
+//! rotations; the admin, role and council rotations; and the remaining
+//! config setters. A path joins the suite with a scenario step below and a
+//! line in the budget file.
+//!
+//! The measurements are checked against `tests/cu_budgets.txt`. The suite
+//! fails when a path exceeds its budget, when a path has no budget or a budget
+//! no path measured, and when any budget is above the runtime's default
+//! 200k-CU instruction limit, which is all a client that does not request
+//! more gets. Each figure includes the ~150 CU of the transaction's
+//! compute-budget instruction.
+//!
+//! After an intentional cost change, rewrite the budgets from the measured
+//! values (+10%, rounded up to 1000) with
+//!
+//!     CU_BUDGETS_UPDATE=1 cargo test --features no-entrypoint --test cu_budgets_e2e
+//!
+//! The table is printed either way; add `-- --nocapture` to see it.
+//!
+//! Like `mock_wrapper_e2e.rs`, this fails rather than skips when either `.so`
+//! is missing: a budget check that did not run must not read as a pass.
+
+use litesvm::LiteSVM;
+use percolator_mock_wrapper::state as mock;
+use percolator_stake::error::describe_simulation_failure;
+use percolator_stake::processor::TIMELOCK_SLOTS;
+use percolator_stake::state::{
+    derive_deposit_pda, derive_pool_pda, derive_proposal_pda, derive_vault_authority,
+    FeePolicyBounds,
+};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signer::{keypair::Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+const WRAPPER_MAINNET: &str = "ESa89R5Es3rJ5mnwGybVRG1GrNt9etP11Z5V2QWD4edv";
+const STAKE_ID: &str = "9tbLt8fs1C7cJRXAyiGY7Ub88AT7MLWpxLqFNVCkqzA6";
+const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+/// The runtime's default per-instruction compute limit.
+const DEFAULT_INSTRUCTION_CU_LIMIT: u64 = 200_000;
+const UPDATE_ENV: &str = "CU_BUDGETS_UPDATE";
+
+const COOLDOWN_SLOTS: u64 = 5;
+const HWM_FLOOR_BPS: u16 = 5_000;
+const JUNIOR_FEE_MULT_BPS: u16 = 20_000;
+const SENIOR_DEPOSIT: u64 = 1_000_000;
+const JUNIOR_DEPOSIT: u64 = 500_000;
+const LATE_DEPOSIT: u64 = 100_000;
+/// Tokens that reach the vault outside a deposit, as the wrapper's fee push
+/// does, so the next pricing path has a surplus to accrue.
+const FEE_SURPLUS: u64 = 10_000;
+const PARTIAL_WITHDRAW_LP: u64 = 100_000;
+const FLUSH_AMOUNT: u64 = 250_000;
+
+// ── .so paths ────────────────────────────────────────────────────────────────
+
+fn stake_so() -> PathBuf {
+    let mut p = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    p.push("target/deploy/percolator_stake.so");
+    p
+}
+
+fn mock_wrapper_so() -> PathBuf {
+    let mut p = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    p.push("mock-wrapper/target/deploy/percolator_mock_wrapper.so");
+    p
+}
+
+fn budgets_path() -> PathBuf {
+    let mut p = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    p.push("tests/cu_budgets.txt");
+    p
+}
+
+// ── budget file ──────────────────────────────────────────────────────────────
+
+/// `path budget` lines; `#` comments and blank lines are skipped.
+fn parse_budgets(text: &str) -> Vec<(String, u64)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| {
+            let mut fields = l.split_whitespace();
+            let (Some(path), Some(budget), None) = (fields.next(), fields.next(), fields.next())
+            else {
+                panic!("cu_budgets.txt: expected `path budget`, got {l:?}");
+            };
+            let budget = budget
+                .parse()
+                .unwrap_or_else(|_| panic!("cu_budgets.txt: bad budget in {l:?}"));
+            (path.to_string(), budget)
+        })
+        .collect()
+}
+
+/// Measured cost plus 10%, rounded up to the next 1000 CU.
+fn budget_for(consumed: u64) -> u64 {
+    (consumed + consumed / 10).div_ceil(1_000) * 1_000
+}
+
+/// Rewrite the file's entries from `measured`, keeping its leading comments.
+fn write_budgets(current: &str, measured: &[(&'static str, u64)]) {
+    let mut out: String = current
+        .lines()
+        .take_while(|l| l.starts_with('#'))
+        .map(|l| format!("{l}\n"))
+        .collect();
+    for (path, consumed) in measured {
+        out.push_str(&format!("{path:<36}{}\n", budget_for(*consumed)));
+    }
+    std::fs::write(budgets_path(), out).expect("write cu_budgets.txt");
+}
+
+// ── SPL token fixtures ──────────────────────────────────────────────────────
+
+fn mint_data() -> Vec<u8> {
+    let mut d = vec![0u8; 82];
+    d[44] = 0; // decimals
+    d[45] = 1; // is_initialized
+    d
+}
+
+fn token_data(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Vec<u8> {
+    let mut d = vec![0u8; 165];
+    d[0..32].copy_from_slice(mint.as_ref());
+    d[32..64].copy_from_slice(owner.as_ref());
+    d[64..72].copy_from_slice(&amount.to_le_bytes());
+    d[108] = 1; // state = Initialized
+    d
+}
+
+fn set_account(svm: &mut LiteSVM, key: Pubkey, owner: Pubkey, data: Vec<u8>) {
+    svm.set_account(
+        key,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+}
+
+fn token_amount(svm: &LiteSVM, key: &Pubkey) -> u64 {
+    let acct = svm.get_account(key).expect("token account exists");
+    u64::from_le_bytes(acct.data[64..72].try_into().unwrap())
+}
+
+/// Credit `amount` to a token account without a transfer.
+fn credit(svm: &mut LiteSVM, key: &Pubkey, amount: u64) {
+    let mut acct = svm.get_account(key).expect("token account exists");
+    let balance = u64::from_le_bytes(acct.data[64..72].try_into().unwrap()) + amount;
+    acct.data[64..72].copy_from_slice(&balance.to_le_bytes());
+    svm.set_account(*key, acct).unwrap();
+}
+
+// ── transactions ────────────────────────────────────────────────────────────
+
+/// Send `ix` under a 1.4M-CU limit, so a path past the default limit still
+/// reports what it consumed, and return that consumption.
+fn send(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    signers: &[&Keypair],
+    ix: Instruction,
+) -> Result<u64, TransactionError> {
+    let mut all: Vec<&Keypair> = vec![payer];
+    all.extend_from_slice(signers);
+    let cb_cu =
+        solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(1_400_000);
+    let (program_id, tag) = (ix.program_id, ix.data.first().copied().unwrap_or(u8::MAX));
+    let tx = Transaction::new_signed_with_payer(
+        &[cb_cu, ix],
+        Some(&payer.pubkey()),
+        &all,
+        svm.latest_blockhash(),
+    );
+    let result = svm
+        .send_transaction(tx)
+        .map(|meta| meta.compute_units_consumed)
+        .map_err(|e| {
+            let stake_id = Pubkey::from_str(STAKE_ID).unwrap();
+            if program_id == stake_id {
+                if let Some(why) = describe_simulation_failure(&stake_id, tag, &e.meta.logs) {
+                    eprintln!("stake tag {tag} failed: {why}");
+                }
+            }
+            e.err
+        });
+    svm.expire_blockhash();
+    result
+}
+
+// ── harness ─────────────────────────────────────────────────────────────────
+
+struct Meter {
+    svm: LiteSVM,
+    wrapper_id: Pubkey,
+    stake_id: Pubkey,
+    token_program: Pubkey,
+    admin: Keypair,
+    payer: Keypair,
+    measured: Vec<(&'static str, u64)>,
+}
+
+/// Panics when either .so is absent.
+fn meter() -> Meter {
+    if !stake_so().exists() || !mock_wrapper_so().exists() {
+        panic!(
+            "CU budgets: .so missing (stake={} mock={}) — run `cargo build-sbf` \
+             and `cargo build-sbf --manifest-path mock-wrapper/Cargo.toml`",
+            stake_so().display(),
+            mock_wrapper_so().display()
+        );
+    }
+    let mut svm = LiteSVM::new().with_spl_programs();
+    let wrapper_id = Pubkey::from_str(WRAPPER_MAINNET).unwrap();
+    let stake_id = Pubkey::from_str(STAKE_ID).unwrap();
+    let token_program = Pubkey::from_str(TOKEN_PROGRAM).unwrap();
+    svm.add_program_from_file(wrapper_id, mock_wrapper_so())
+        .unwrap();
+    svm.add_program_from_file(stake_id, stake_so()).unwrap();
+
+    let admin = Keypair::new();
+    let payer = Keypair::new();
+    svm.airdrop(&admin.pubkey(), 100_000_000_000).unwrap();
+    svm.airdrop(&payer.pubkey(), 100_000_000_000).unwrap();
+
+    Meter {
+        svm,
+        wrapper_id,
+        stake_id,
+        token_program,
+        admin,
+        payer,
+        measured: Vec::new(),
+    }
+}
+
+impl Meter {
+    /// Run `ix` and record its cost under `path`. `user` signs when given;
+    /// otherwise the admin does if `ix` asks for its signature. Every measured
+    /// path must succeed.
+    fn measure(&mut self, path: &'static str, user: Option<&Keypair>, ix: Instruction) {
+        assert!(
+            self.measured.iter().all(|(p, _)| *p != path),
+            "{path} measured twice"
+        );
+        let admin_signs = ix
+            .accounts
+            .iter()
+            .any(|a| a.is_signer && a.pubkey == self.admin.pubkey());
+        let signers: Vec<&Keypair> = match user {
+            Some(user) => vec![user],
+            None if admin_signs => vec![&self.admin],
+            None => vec![],
+        };
+        let consumed = send(&mut self.svm, &self.payer, &signers, ix)
+            .unwrap_or_else(|e| panic!("{path}: {e:?}"));
+        self.measured.push((path, consumed));
+    }
+
+    fn warp(&mut self, slots: u64) {
+        let now = self.svm.get_sysvar::<Clock>().slot;
+        self.svm.warp_to_slot(now + slots);
+    }
+
+    /// Propose `proxy` (tag 39), wait out the timelock, then commit it (tag
+    /// 40), measuring both halves.
+    fn measure_timelocked(
+        &mut self,
+        propose_path: &'static str,
+        commit_path: &'static str,
+        proposal_id: u64,
+        proxy: Instruction,
+    ) {
+        let pool_pda = proxy.accounts[1].pubkey;
+        let (proposal, _) = derive_proposal_pda(&proxy.program_id, &pool_pda, proposal_id);
+        let mut data = vec![39u8];
+        data.extend_from_slice(&proposal_id.to_le_bytes());
+        data.extend_from_slice(&proxy.data);
+        let propose = Instruction {
+            program_id: proxy.program_id,
+            accounts: vec![
+                AccountMeta::new(self.admin.pubkey(), true),
+                AccountMeta::new_readonly(pool_pda, false),
+                AccountMeta::new(proposal, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data,
+        };
+        self.measure(propose_path, None, propose);
+
+        self.warp(TIMELOCK_SLOTS);
+
+        let mut accounts = vec![
+            AccountMeta::new(proposal, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ];
+        accounts.extend(proxy.accounts.iter().cloned());
+        let commit = Instruction {
+            program_id: proxy.program_id,
+            accounts,
+            data: vec![40u8],
+        };
+        self.measure(commit_path, None, commit);
+    }
+}
+
+struct Pool {
+    market: Pubkey,
+    mint: Pubkey,
+    wrapper_vault: Pubkey,
+    wrapper_vault_auth: Pubkey,
+    pool_pda: Pubkey,
+    vault: Pubkey,
+    vault_auth: Pubkey,
+    lp_mint: Pubkey,
+}
+
+struct User {
+    key: Keypair,
+    ata: Pubkey,
+    lp_ata: Pubkey,
+    deposit_pda: Pubkey,
+}
+
+/// A funded wallet with collateral and LP token accounts, no deposit yet.
+fn new_user(m: &mut Meter, p: &Pool, collateral: u64) -> User {
+    let key = Keypair::new();
+    m.svm.airdrop(&key.pubkey(), 10_000_000_000).unwrap();
+    let ata = Pubkey::new_unique();
+    let lp_ata = Pubkey::new_unique();
+    set_account(
+        &mut m.svm,
+        ata,
+        m.token_program,
+        token_data(&p.mint, &key.pubkey(), collateral),
+    );
+    set_account(
+        &mut m.svm,
+        lp_ata,
+        m.token_program,
+        token_data(&p.lp_mint, &key.pubkey(), 0),
+    );
+    let (deposit_pda, _) = derive_deposit_pda(&m.stake_id, &p.pool_pda, &key.pubkey());
+    User {
+        key,
+        ata,
+        lp_ata,
+        deposit_pda,
+    }
+}
+
+// ── stake instructions ──────────────────────────────────────────────────────
+
+fn init_pool_ix(m: &Meter, p: &Pool) -> Instruction {
+    let mut data = vec![0u8];
+    data.extend_from_slice(&COOLDOWN_SLOTS.to_le_bytes());
+    data.extend_from_slice(&0u64.to_le_bytes()); // deposit_cap (uncapped)
+    data.extend_from_slice(&FeePolicyBounds::UNBOUNDED.pack());
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new(m.admin.pubkey(), true),
+            AccountMeta::new(p.market, false),
+            AccountMeta::new(p.pool_pda, false),
+            AccountMeta::new(p.lp_mint, false),
+            AccountMeta::new(p.vault, false),
+            AccountMeta::new_readonly(p.vault_auth, false),
+            AccountMeta::new_readonly(p.mint, false),
+            AccountMeta::new_readonly(m.wrapper_id, false),
+            AccountMeta::new_readonly(m.token_program, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::rent::id(), false),
+        ],
+        data,
+    }
+}
+
+/// Tags 14/15: [admin, pool(w)].
+fn pool_config_ix(m: &Meter, p: &Pool, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(m.admin.pubkey(), true),
+            AccountMeta::new(p.pool_pda, false),
+        ],
+        data,
+    }
+}
+
+/// Tags 1/16 share one account list.
+fn deposit_ix(m: &Meter, p: &Pool, u: &User, tag: u8, amount: u64) -> Instruction {
+    let mut data = vec![tag];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new(u.key.pubkey(), true),
+            AccountMeta::new(p.pool_pda, false),
+            AccountMeta::new(u.ata, false),
+            AccountMeta::new(p.vault, false),
+            AccountMeta::new(p.lp_mint, false),
+            AccountMeta::new(u.lp_ata, false),
+            AccountMeta::new_readonly(p.vault_auth, false),
+            AccountMeta::new(u.deposit_pda, false),
+            AccountMeta::new_readonly(m.token_program, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+fn withdraw_ix(m: &Meter, p: &Pool, u: &User, lp_amount: u64, close_if_empty: bool) -> Instruction {
+    let mut data = vec![2u8];
+    data.extend_from_slice(&lp_amount.to_le_bytes());
+    if close_if_empty {
+        data.push(1);
+    }
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new(u.key.pubkey(), true),
+            AccountMeta::new(p.pool_pda, false),
+            AccountMeta::new(u.lp_ata, false),
+            AccountMeta::new(p.lp_mint, false),
+            AccountMeta::new(p.vault, false),
+            AccountMeta::new(u.ata, false),
+            AccountMeta::new_readonly(p.vault_auth, false),
+            AccountMeta::new(u.deposit_pda, false),
+            AccountMeta::new_readonly(m.token_program, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ],
+        data,
+    }
+}
+
+fn accrue_fees_ix(m: &Meter, p: &Pool) -> Instruction {
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(m.payer.pubkey(), true),
+            AccountMeta::new(p.pool_pda, false),
+            AccountMeta::new_readonly(p.vault, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ],
+        data: vec![12u8],
+    }
+}
+
+/// Tags 19/21: [admin, pool, vault_auth, slab(w), percolator].
+fn asset_authority_ix(m: &Meter, p: &Pool, tag: u8) -> Instruction {
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new(m.admin.pubkey(), true),
+            AccountMeta::new(p.pool_pda, false),
+            AccountMeta::new_readonly(p.vault_auth, false),
+            AccountMeta::new(p.market, false),
+            AccountMeta::new_readonly(m.wrapper_id, false),
+        ],
+        data: vec![tag],
+    }
+}
+
+fn flush_ix(m: &Meter, p: &Pool, amount: u64) -> Instruction {
+    let mut data = vec![3u8];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new(m.admin.pubkey(), true),
+            AccountMeta::new(p.pool_pda, false),
+            AccountMeta::new(p.vault, false),
+            AccountMeta::new_readonly(p.vault_auth, false),
+            AccountMeta::new(p.market, false),
+            AccountMeta::new(p.wrapper_vault, false),
+            AccountMeta::new_readonly(m.wrapper_id, false),
+            AccountMeta::new_readonly(m.token_program, false),
+        ],
+        data,
+    }
+}
+
+fn recover_ix(m: &Meter, p: &Pool, amount: u64) -> Instruction {
+    let mut data = vec![23u8];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(m.payer.pubkey(), false), // permissionless caller
+            AccountMeta::new(p.pool_pda, false),
+            AccountMeta::new(p.vault, false),
+            AccountMeta::new_readonly(p.vault_auth, false),
+            AccountMeta::new(p.market, false),
+            AccountMeta::new(p.wrapper_vault, false),
+            AccountMeta::new_readonly(p.wrapper_vault_auth, false),
+            AccountMeta::new_readonly(m.token_program, false),
+            AccountMeta::new_readonly(m.wrapper_id, false),
+        ],
+        data,
+    }
+}
+
+/// With the market, so the wrapper insurance check runs too.
+fn assert_invariants_ix(m: &Meter, p: &Pool) -> Instruction {
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(p.pool_pda, false),
+            AccountMeta::new_readonly(p.vault, false),
+            AccountMeta::new_readonly(p.lp_mint, false),
+            AccountMeta::new_readonly(p.market, false),
+        ],
+        data: vec![53u8],
+    }
+}
+
+fn probe_wrapper_ix(m: &Meter, p: &Pool) -> Instruction {
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(p.pool_pda, false),
+            AccountMeta::new_readonly(p.market, false),
+        ],
+        data: vec![55u8],
+    }
+}
+
+fn admin_resolve_market_ix(m: &Meter, p: &Pool) -> Instruction {
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(m.admin.pubkey(), true),
+            AccountMeta::new_readonly(p.pool_pda, false),
+            AccountMeta::new(p.market, false),
+            AccountMeta::new_readonly(m.wrapper_id, false),
+        ],
+        data: vec![24u8],
+    }
+}
+
+/// Stake tags 25/26: [admin, pool_pda(w), slab(w), percolator].
+fn marketauth_proxy_ix(m: &Meter, p: &Pool, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(m.admin.pubkey(), true),
+            AccountMeta::new(p.pool_pda, false),
+            AccountMeta::new(p.market, false),
+            AccountMeta::new_readonly(m.wrapper_id, false),
+        ],
+        data,
+    }
+}
+
+/// Stake tags 27/28: [admin, pool_pda(w), vault_auth, slab(w), percolator].
+fn insurance_proxy_ix(m: &Meter, p: &Pool, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: m.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(m.admin.pubkey(), true),
+            AccountMeta::new(p.pool_pda, false),
+            AccountMeta::new_readonly(p.vault_auth, false),
+            AccountMeta::new(p.market, false),
+            AccountMeta::new_readonly(m.wrapper_id, false),
+        ],
+        data,
+    }
+}
+
+// ═════════════════════════════════════════════════════════════════════════════
+// Scenario
+// ═════════════════════════════════════════════════════════════════════════════
+
+/// One pool's life, measuring each path at its most expensive.
+fn run_scenario(m: &mut Meter) {
+    // A Live mock market whose every authority is the admin, as after InitMarket.
+    let market = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    set_account(&mut m.svm, mint, m.token_program, mint_data());
+    set_account(
+        &mut m.svm,
+        market,
+        m.wrapper_id,
+        mock::new_market_data(1, &m.admin.pubkey(), &mint),
+    );
+    let (wrapper_vault_auth, _) = mock::vault_authority(&m.wrapper_id, &market);
+    let wrapper_vault = Pubkey::new_unique();
+    set_account(
+        &mut m.svm,
+        wrapper_vault,
+        m.token_program,
+        token_data(&mint, &wrapper_vault_auth, 0),
+    );
+    let (pool_pda, _) = derive_pool_pda(&m.stake_id, &market);
+    let (vault_auth, _) = derive_vault_authority(&m.stake_id, &pool_pda);
+    let lp_mint = Pubkey::new_unique();
+    let vault = Pubkey::new_unique();
+    set_account(&mut m.svm, lp_mint, m.token_program, vec![0u8; 82]);
+    set_account(&mut m.svm, vault, m.token_program, vec![0u8; 165]);
+    let p = Pool {
+        market,
+        mint,
+        wrapper_vault,
+        wrapper_vault_auth,
+        pool_pda,
+        vault,
+        vault_auth,
+        lp_mint,
+    };
+
+    // InitPool: mint/vault init plus the tag-32 marketauth CPI.
+    m.measure("init_pool", None, init_pool_ix(m, &p));
+
+    // Tranches and the HWM floor on before any deposit, so every pricing path
+    // below takes its tranche branch and refreshes the mark.
+    let mut hwm = vec![14u8, 1];
+    hwm.extend_from_slice(&HWM_FLOOR_BPS.to_le_bytes());
+    m.measure("admin_set_hwm_config", None, pool_config_ix(m, &p, hwm));
+    let tranche = [&[15u8][..], &JUNIOR_FEE_MULT_BPS.to_le_bytes()[..]].concat();
+    m.measure(
+        "admin_set_tranche_config",
+        None,
+        pool_config_ix(m, &p, tranche),
+    );
+
+    // Genesis deposit: dead-share lock and deposit PDA creation.
+    let senior = new_user(m, &p, SENIOR_DEPOSIT);
+    m.measure(
+        "deposit_first",
+        Some(&senior.key),
+        deposit_ix(m, &p, &senior, 1, SENIOR_DEPOSIT),
+    );
+
+    // The first junior: PDA creation on the junior sub-pool.
+    let junior = new_user(m, &p, JUNIOR_DEPOSIT);
+    m.measure(
+        "deposit_junior_first",
+        Some(&junior.key),
+        deposit_ix(m, &p, &junior, 16, JUNIOR_DEPOSIT),
+    );
+
+    // A new depositor with a surplus waiting: pre-accrual across both
+    // tranches, PDA creation and the HWM refresh in one instruction.
+    let late = new_user(m, &p, LATE_DEPOSIT);
+    credit(&mut m.svm, &p.vault, FEE_SURPLUS);
+    m.measure(
+        "deposit_new_depositor_accrue",
+        Some(&late.key),
+        deposit_ix(m, &p, &late, 1, LATE_DEPOSIT),
+    );
+
+    credit(&mut m.svm, &p.vault, FEE_SURPLUS);
+    m.measure("accrue_fees", None, accrue_fees_ix(m, &p));
+
+    // Withdrawals after the cooldown, each with a fresh surplus to accrue and
+    // the HWM floor to check.
+    m.warp(COOLDOWN_SLOTS + 1);
+    credit(&mut m.svm, &p.vault, FEE_SURPLUS);
+    m.measure(
+        "withdraw_senior_hwm",
+        Some(&senior.key),
+        withdraw_ix(m, &p, &senior, PARTIAL_WITHDRAW_LP, false),
+    );
+    credit(&mut m.svm, &p.vault, FEE_SURPLUS);
+    m.measure(
+        "withdraw_junior_hwm",
+        Some(&junior.key),
+        withdraw_ix(m, &p, &junior, PARTIAL_WITHDRAW_LP, false),
+    );
+    let late_lp = token_amount(&m.svm, &late.lp_ata);
+    m.measure(
+        "withdraw_close_if_empty",
+        Some(&late.key),
+        withdraw_ix(m, &p, &late, late_lp, true),
+    );
+
+    // The insurance custody CPIs.
+    m.measure(
+        "bind_insurance_authority",
+        None,
+        asset_authority_ix(m, &p, 19),
+    );
+    m.measure("flush_to_insurance", None, flush_ix(m, &p, FLUSH_AMOUNT));
+    m.measure("assert_pool_invariants", None, assert_invariants_ix(m, &p));
+    m.measure(
+        "recover_flushed_insurance",
+        None,
+        recover_ix(m, &p, FLUSH_AMOUNT),
+    );
+
+    // The timelocked proxies, one proposal each.
+    let shares: Vec<u8> = [3600u16, 3200, 1200]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    m.measure_timelocked(
+        "propose_update_fee_split",
+        "commit_update_fee_split",
+        1,
+        marketauth_proxy_ix(m, &p, [&[25u8][..], &shares[..]].concat()),
+    );
+    m.measure_timelocked(
+        "propose_update_maintenance_fee",
+        "commit_update_maintenance_fee",
+        2,
+        marketauth_proxy_ix(m, &p, [&[26u8][..], &1u128.to_le_bytes()[..]].concat()),
+    );
+    let policy: Vec<u8> = [1u16, 25, 5000]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    m.measure_timelocked(
+        "propose_update_backing_fee_policy",
+        "commit_update_backing_fee_policy",
+        3,
+        insurance_proxy_ix(m, &p, [&[27u8][..], &policy[..]].concat()),
+    );
+    m.measure_timelocked(
+        "propose_update_trade_fee_policy",
+        "commit_update_trade_fee_policy",
+        4,
+        insurance_proxy_ix(m, &p, [&[28u8][..], &42u64.to_le_bytes()[..]].concat()),
+    );
+
+    m.measure("burn_asset_admin", None, asset_authority_ix(m, &p, 21));
+    m.measure("probe_wrapper", None, probe_wrapper_ix(m, &p));
+    // Last: a resolved market refuses every other CPI.
+    m.measure("admin_resolve_market", None, admin_resolve_market_ix(m, &p));
+}
+
+#[test]
+fn measured_paths_stay_within_their_cu_budgets() {
+    let mut m = meter();
+    run_scenario(&mut m);
+
+    let current = std::fs::read_to_string(budgets_path()).expect("read tests/cu_budgets.txt");
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        write_budgets(&current, &m.measured);
+        eprintln!("{UPDATE_ENV}: rewrote {}", budgets_path().display());
+        return;
+    }
+    let budgets = parse_budgets(&current);
+
+    let mut failures = Vec::new();
+    eprintln!("{:<36}{:>10}{:>10}", "path", "consumed", "budget");
+    for (path, consumed) in &m.measured {
+        let budget = budgets.iter().find(|(p, _)| p == path).map(|(_, b)| *b);
+        eprintln!(
+            "{path:<36}{consumed:>10}{:>10}",
+            budget.map_or("-".to_string(), |b| b.to_string())
+        );
+        match budget {
+            None => failures.push(format!("{path}: no budget (consumed {consumed})")),
+            Some(b) if *consumed > b => {
+                failures.push(format!("{path}: consumed {consumed} > budget {b}"))
+            }
+            Some(_) => {}
+        }
+    }
+    for (path, budget) in &budgets {
+        if !m.measured.iter().any(|(p, _)| p == path) {
+            failures.push(format!("{path}: budgeted but not measured"));
+        }
+        if *budget > DEFAULT_INSTRUCTION_CU_LIMIT {
+            failures.push(format!(
+                "{path}: budget {budget} exceeds the default {DEFAULT_INSTRUCTION_CU_LIMIT}-CU \
+                 instruction limit"
+            ));
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "CU budgets (regenerate with {UPDATE_ENV}=1 if intended):\n  {}",
+        failures.join("\n  ")
+    );
+}
@@ -31,6 +31,41 @@ fn test_all_error_codes_unique() {
         StakeError::ZeroSharesMinted as u32,
         StakeError::NoPendingAdmin as u32,
         StakeError::InsuranceLossOutstanding as u32,
+        StakeError::CooldownIncreaseRequiresTimelock as u32,
+        StakeError::TimelockNotElapsed as u32,
+        StakeError::NoPendingCooldownProposal as u32,
+        StakeError::DepositBelowMinimumLiquidity as u32,
+        StakeError::DepositorNotAuthorized as u32,
+        StakeError::DepositNotEmpty as u32,
+        StakeError::PoolNotFinalized as u32,
+        StakeError::PoolAlreadyFinalized as u32,
+        StakeError::MarketNotResolved as u32,
+        StakeError::FinalSweepTooEarly as u32,
+        StakeError::FeeChangeRequiresTimelock as u32,
+        StakeError::ProposalNotPending as u32,
+        StakeError::FeePolicyBoundViolated as u32,
+        StakeError::FeeChangeTooSoon as u32,
+        StakeError::VetoDisabled as u32,
+        StakeError::VetoStillLocked as u32,
+        StakeError::CouncilThresholdNotMet as u32,
+        StakeError::NoPendingCouncil as u32,
+        StakeError::NoPendingRole as u32,
+        StakeError::ProposalExpired as u32,
+        StakeError::ConfigLocked as u32,
+        StakeError::ConfigNotLocked as u32,
+        StakeError::FlushPolicyExceeded as u32,
+        StakeError::VaultDeltaMismatch as u32,
+        StakeError::InvariantLedgerInconsistent as u32,
+        StakeError::InvariantVaultShortfall as u32,
+        StakeError::InvariantLpSupplyMismatch as u32,
+        StakeError::InvariantJuniorExceedsPoolValue as u32,
+        StakeError::InvariantWrapperShortfall as u32,
+        StakeError::NoDeficit as u32,
+        StakeError::PoolInDeficit as u32,
+        StakeError::WrapperAbiUnsupported as u32,
+        StakeError::WrapperMarketOwnerMismatch as u32,
+        StakeError::WrapperMarketLayoutMismatch as u32,
+        StakeError::WrapperMarketUninitialized as u32,
     ];
 
     // Check uniqueness
@@ -39,7 +74,7 @@ fn test_all_error_codes_unique() {
     sorted.dedup();
     assert_eq!(sorted.len(), codes.len(), "Duplicate error codes detected!");
 
-    // Check sequential (0..24)
+    // Check sequential (0..59)
     for (i, &code) in codes.iter().enumerate() {
         assert_eq!(
             code, i as u32,
@@ -85,6 +120,41 @@ fn test_all_errors_are_custom() {
         StakeError::ZeroSharesMinted,
         StakeError::NoPendingAdmin,
         StakeError::InsuranceLossOutstanding,
+        StakeError::CooldownIncreaseRequiresTimelock,
+        StakeError::TimelockNotElapsed,
+        StakeError::NoPendingCooldownProposal,
+        StakeError::DepositBelowMinimumLiquidity,
+        StakeError::DepositorNotAuthorized,
+        StakeError::DepositNotEmpty,
+        StakeError::PoolNotFinalized,
+        StakeError::PoolAlreadyFinalized,
+        StakeError::MarketNotResolved,
+        StakeError::FinalSweepTooEarly,
+        StakeError::FeeChangeRequiresTimelock,
+        StakeError::ProposalNotPending,
+        StakeError::FeePolicyBoundViolated,
+        StakeError::FeeChangeTooSoon,
+        StakeError::VetoDisabled,
+        StakeError::VetoStillLocked,
+        StakeError::CouncilThresholdNotMet,
+        StakeError::NoPendingCouncil,
+        StakeError::NoPendingRole,
+        StakeError::ProposalExpired,
+        StakeError::ConfigLocked,
+        StakeError::ConfigNotLocked,
+        StakeError::FlushPolicyExceeded,
+        StakeError::VaultDeltaMismatch,
+        StakeError::InvariantLedgerInconsistent,
+        StakeError::InvariantVaultShortfall,
+        StakeError::InvariantLpSupplyMismatch,
+        StakeError::InvariantJuniorExceedsPoolValue,
+        StakeError::InvariantWrapperShortfall,
+        StakeError::NoDeficit,
+        StakeError::PoolInDeficit,
+        StakeError::WrapperAbiUnsupported,
+        StakeError::WrapperMarketOwnerMismatch,
+        StakeError::WrapperMarketLayoutMismatch,
+        StakeError::WrapperMarketUninitialized,
     ];
 
     for err in &errors {
@@ -92,3 +162,74 @@ fn test_all_errors_are_custom() {
         assert!(matches!(pe, ProgramError::Custom(_)));
     }
 }
+
+#[test]
+fn test_wrapper_error_hints() {
+    use percolator_stake::error::{
+        wrapper_error_hint, wrapper_error_name, WrapperCpi, WRAPPER_ERR_ENGINE_LOCK_ACTIVE,
+        WRAPPER_ERR_UNAUTHORIZED,
+    };
+
+    assert_eq!(wrapper_error_name(8), Some("Unauthorized"));
+    assert_eq!(wrapper_error_name(21), Some("EngineLockActive"));
+    assert_eq!(wrapper_error_name(9), None);
+
+    // FlushToInsurance, RecoverFlushedInsurance, the proxies and commit.
+    assert_eq!(
+        WrapperCpi::for_stake_tag(3),
+        Some(WrapperCpi::TopUpInsurance)
+    );
+    assert_eq!(
+        WrapperCpi::for_stake_tag(23),
+        Some(WrapperCpi::WithdrawInsuranceAsset)
+    );
+    assert_eq!(
+        WrapperCpi::for_stake_tag(25),
+        Some(WrapperCpi::MarketAuthProxy)
+    );
+    assert_eq!(
+        WrapperCpi::for_stake_tag(28),
+        Some(WrapperCpi::InsuranceAuthProxy)
+    );
+    assert_eq!(WrapperCpi::for_stake_tag(40), None);
+
+    let unbound = wrapper_error_hint(WrapperCpi::TopUpInsurance, WRAPPER_ERR_UNAUTHORIZED);
+    assert!(unbound.contains("BindInsuranceAuthority"));
+    let not_live = wrapper_error_hint(
+        WrapperCpi::WithdrawInsuranceAsset,
+        WRAPPER_ERR_ENGINE_LOCK_ACTIVE,
+    );
+    assert!(not_live.contains("not Live"));
+    assert!(wrapper_error_hint(WrapperCpi::TopUpInsurance, 99).starts_with("Unknown"));
+}
+
+#[test]
+fn test_describe_simulation_failure() {
+    use percolator_stake::error::describe_simulation_failure;
+    use solana_program::pubkey::Pubkey;
+
+    let stake = Pubkey::new_from_array([1u8; 32]);
+    let wrapper = Pubkey::new_from_array([2u8; 32]);
+    // A flush whose TopUpInsurance CPI was rejected: the wrapper fails first,
+    // then stake propagates the same code.
+    let logs = vec![
+        format!("Program {stake} invoke [1]"),
+        format!("Program {wrapper} invoke [2]"),
+        format!("Program {wrapper} failed: custom program error: 0x8"),
+        format!("Program {stake} failed: custom program error: 0x8"),
+    ];
+    let text = describe_simulation_failure(&stake, 3, &logs).unwrap();
+    assert!(text.starts_with("wrapper error 8 (Unauthorized)"), "{text}");
+    assert!(text.contains("BindInsuranceAuthority"), "{text}");
+
+    // Stake's own Custom(8) is MarketResolved.
+    let own = vec![format!("Program {stake} failed: custom program error: 0x8")];
+    let text = describe_simulation_failure(&stake, 1, &own).unwrap();
+    assert!(
+        text.starts_with("stake error 8: Market is resolved"),
+        "{text}"
+    );
+
+    let ok = vec![format!("Program {stake} success")];
+    assert_eq!(describe_simulation_failure(&stake, 3, &ok), None);
+}
@@ -13,8 +13,9 @@ use bytemuck::Zeroable;
 use percolator_stake::{
     instruction::StakeInstruction,
     state::{
-        derive_deposit_pda, derive_pool_pda, derive_vault_authority, StakeDeposit, StakePool,
-        STAKE_DEPOSIT_DISCRIMINATOR, STAKE_DEPOSIT_SIZE, STAKE_POOL_DISCRIMINATOR, STAKE_POOL_SIZE,
+        derive_deposit_pda, derive_pool_pda, derive_vault_authority, FeePolicyBounds, StakeDeposit,
+        StakePool, STAKE_DEPOSIT_DISCRIMINATOR, STAKE_DEPOSIT_SIZE, STAKE_POOL_DISCRIMINATOR,
+        STAKE_POOL_SIZE,
     },
 };
 
@@ -724,10 +725,12 @@ fn test_all_standard_instruction_tags_decode() {
     let mut data = vec![0u8];
     data.extend_from_slice(&50u64.to_le_bytes()); // cooldown_slots
     data.extend_from_slice(&5_000_000u64.to_le_bytes()); // deposit_cap
+    data.extend_from_slice(&FeePolicyBounds::UNBOUNDED.pack());
     match StakeInstruction::unpack(&data).unwrap() {
         StakeInstruction::InitPool {
             cooldown_slots,
             deposit_cap,
+            ..
         } => {
             assert_eq!(cooldown_slots, 50);
             assert_eq!(deposit_cap, 5_000_000);
@@ -747,7 +750,13 @@ fn test_all_standard_instruction_tags_decode() {
     let mut data = vec![2u8];
     data.extend_from_slice(&500_000u64.to_le_bytes());
     match StakeInstruction::unpack(&data).unwrap() {
-        StakeInstruction::Withdraw { lp_amount } => assert_eq!(lp_amount, 500_000),
+        StakeInstruction::Withdraw {
+            lp_amount,
+            close_if_empty,
+        } => {
+            assert_eq!(lp_amount, 500_000);
+            assert!(!close_if_empty);
+        }
         _ => panic!("Expected Withdraw"),
     }
 
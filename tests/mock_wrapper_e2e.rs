@@ -0,0 +1,892 @@
+//! Stake CPI surface against the in-repo mock wrapper (`mock-wrapper/`).
+//!
+//! The other cross-program suites load the real wrapper `.so` from a sibling
+//! `percolator-prog` checkout. This one loads
+//! `mock-wrapper/target/deploy/percolator_mock_wrapper.so` at the same program
+//! id instead, so it needs nothing outside this repo:
+//!
+//!     cargo build-sbf
+//!     cargo build-sbf --manifest-path mock-wrapper/Cargo.toml
+//!
+//! The mock implements the tags the stake program CPIs (9, 19, 32, 51, 55, 57,
+//! 65, 86, 88) with the real account shapes, authority checks and error codes,
+//! plus InitMarket. What it adds is fault injection (`state::Faults`), which
+//! the real wrapper cannot be made to do on demand:
+//!
+//! - a SHORT TRANSFER: tags 9/57 book the full amount but move fewer tokens —
+//!   the misbehaviour `cpi_with_vault_delta` exists to catch;
+//! - a forced custom error for one tag, e.g. `EngineLockActive` (21) on a
+//!   market that is still Live.
+//!
+//! Market state is read back through `percolator_mock_wrapper::state`, not raw
+//! offsets, so these tests say nothing about the real wrapper's layout beyond
+//! what the mock mirrors (length, header, fee-config offsets).
+//!
+//! Unlike the other e2e suites, a missing `.so` fails every test instead of
+//! skipping it, so a green run always means the suite ran. CI builds both
+//! before `cargo test`.
+
+use litesvm::LiteSVM;
+use percolator_mock_wrapper::state::{
+    self as mock, Faults, ASSET_AUTH_ADMIN, ASSET_AUTH_INSURANCE, ASSET_AUTH_INSURANCE_OPERATOR,
+    MODE_LIVE, MODE_RESOLVED, OFF_MAINTENANCE_FEE_PER_SLOT, OFF_MODE, OFF_TRADE_FEE_BASE_BPS,
+};
+use percolator_mock_wrapper::{ERR_ENGINE_LOCK_ACTIVE, ERR_UNAUTHORIZED};
+use percolator_stake::error::{describe_simulation_failure, StakeError};
+use percolator_stake::processor::TIMELOCK_SLOTS;
+use percolator_stake::state::{
+    derive_deposit_pda, derive_pool_pda, derive_proposal_pda, derive_vault_authority,
+    FeePolicyBounds, StakePool, MINIMUM_LIQUIDITY, STAKE_POOL_SIZE,
+};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    signer::{keypair::Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const WRAPPER_MAINNET: &str = "ESa89R5Es3rJ5mnwGybVRG1GrNt9etP11Z5V2QWD4edv";
+const STAKE_ID: &str = "9tbLt8fs1C7cJRXAyiGY7Ub88AT7MLWpxLqFNVCkqzA6";
+const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+const DEPOSIT_AMOUNT: u64 = 1_000_000;
+const FLUSH_AMOUNT: u64 = 250_000;
+
+// ── .so paths ────────────────────────────────────────────────────────────────
+
+fn stake_so() -> PathBuf {
+    let mut p = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    p.push("target/deploy/percolator_stake.so");
+    p
+}
+
+fn mock_wrapper_so() -> PathBuf {
+    let mut p = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    p.push("mock-wrapper/target/deploy/percolator_mock_wrapper.so");
+    p
+}
+
+// ── SPL token fixtures ──────────────────────────────────────────────────────
+
+fn mint_data() -> Vec<u8> {
+    let mut d = vec![0u8; 82];
+    d[44] = 0; // decimals
+    d[45] = 1; // is_initialized
+    d
+}
+
+fn token_data(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Vec<u8> {
+    let mut d = vec![0u8; 165];
+    d[0..32].copy_from_slice(mint.as_ref());
+    d[32..64].copy_from_slice(owner.as_ref());
+    d[64..72].copy_from_slice(&amount.to_le_bytes());
+    d[108] = 1; // state = Initialized
+    d
+}
+
+fn set_account(svm: &mut LiteSVM, key: Pubkey, owner: Pubkey, data: Vec<u8>) {
+    svm.set_account(
+        key,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        },
+    )
+    .unwrap();
+}
+
+fn token_amount(svm: &LiteSVM, key: &Pubkey) -> u64 {
+    let acct = svm.get_account(key).expect("token account exists");
+    u64::from_le_bytes(acct.data[64..72].try_into().unwrap())
+}
+
+// ── transactions ────────────────────────────────────────────────────────────
+
+fn send(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    signers: &[&Keypair],
+    ix: Instruction,
+) -> Result<(), TransactionError> {
+    let mut all: Vec<&Keypair> = vec![payer];
+    all.extend_from_slice(signers);
+    let cb_cu =
+        solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(1_400_000);
+    let (program_id, tag) = (ix.program_id, ix.data.first().copied().unwrap_or(u8::MAX));
+    let tx = Transaction::new_signed_with_payer(
+        &[cb_cu, ix],
+        Some(&payer.pubkey()),
+        &all,
+        svm.latest_blockhash(),
+    );
+    let result = svm.send_transaction(tx).map(|_| ()).map_err(|e| {
+        let stake_id = Pubkey::from_str(STAKE_ID).unwrap();
+        if program_id == stake_id {
+            if let Some(why) = describe_simulation_failure(&stake_id, tag, &e.meta.logs) {
+                eprintln!("stake tag {tag} failed: {why}");
+            }
+        }
+        e.err
+    });
+    // A retried instruction would otherwise be the same transaction.
+    svm.expire_blockhash();
+    result
+}
+
+#[track_caller]
+fn assert_custom_err(result: Result<(), TransactionError>, expected: u32, ctx: &str) {
+    match result {
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(code))) => {
+            assert_eq!(
+                code, expected,
+                "{ctx}: expected Custom({expected}), got Custom({code})"
+            )
+        }
+        other => panic!("{ctx}: expected Custom({expected}), got {other:?}"),
+    }
+}
+
+// ── market + pool setup ─────────────────────────────────────────────────────
+
+/// The 219-byte v17 InitMarket wire. The mock reads only
+/// `max_portfolio_assets`, `trade_fee_base_bps` and `maintenance_fee_per_slot`;
+/// the rest is the same fixture the real-wrapper suites send.
+fn encode_init_market_v17() -> Vec<u8> {
+    let mut out = Vec::with_capacity(219);
+    out.push(0u8); // tag InitMarket
+    out.extend_from_slice(&1u16.to_le_bytes()); // max_portfolio_assets
+    out.extend_from_slice(&0u64.to_le_bytes()); // h_min
+    out.extend_from_slice(&10u64.to_le_bytes()); // h_max
+    out.extend_from_slice(&100u64.to_le_bytes()); // initial_price
+    out.extend_from_slice(&1u128.to_le_bytes()); // min_nonzero_mm_req
+    out.extend_from_slice(&2u128.to_le_bytes()); // min_nonzero_im_req
+    out.extend_from_slice(&10_000u64.to_le_bytes()); // maintenance_margin_bps
+    out.extend_from_slice(&10_000u64.to_le_bytes()); // initial_margin_bps
+    out.extend_from_slice(&10_000u64.to_le_bytes()); // max_trading_fee_bps
+    out.extend_from_slice(&0u64.to_le_bytes()); // trade_fee_base_bps
+    out.extend_from_slice(&0u64.to_le_bytes()); // liquidation_fee_bps
+    out.extend_from_slice(&0u128.to_le_bytes()); // liquidation_fee_cap
+    out.extend_from_slice(&0u128.to_le_bytes()); // min_liquidation_abs
+    out.extend_from_slice(&10_000u64.to_le_bytes()); // max_price_move_bps_per_slot
+    out.extend_from_slice(&1u64.to_le_bytes()); // max_accrual_dt_slots
+    out.extend_from_slice(&0u64.to_le_bytes()); // max_abs_funding_e9_per_slot
+    out.extend_from_slice(&1u64.to_le_bytes()); // min_funding_lifetime_slots
+    out.extend_from_slice(&1u64.to_le_bytes()); // max_account_b_settlement_chunks
+    out.extend_from_slice(&1u64.to_le_bytes()); // max_bankrupt_close_chunks
+    out.extend_from_slice(&100u64.to_le_bytes()); // max_bankrupt_close_lifetime_slots
+    out.extend_from_slice(&10_000_000_000_000_000u128.to_le_bytes()); // public_b_chunk_atoms
+    out.extend_from_slice(&0u128.to_le_bytes()); // maintenance_fee_per_slot
+    debug_assert_eq!(out.len(), 219, "InitMarket wire must be 219 bytes");
+    out
+}
+
+struct Env {
+    svm: LiteSVM,
+    wrapper_id: Pubkey,
+    stake_id: Pubkey,
+    token_program: Pubkey,
+    admin: Keypair,
+    payer: Keypair,
+}
+
+/// Panics when either .so is absent: a skipped suite must not read as a pass.
+fn env() -> Env {
+    if !stake_so().exists() || !mock_wrapper_so().exists() {
+        panic!(
+            "mock wrapper e2e: .so missing (stake={} mock={}) — run `cargo build-sbf` \
+             and `cargo build-sbf --manifest-path mock-wrapper/Cargo.toml`",
+            stake_so().display(),
+            mock_wrapper_so().display()
+        );
+    }
+    let mut svm = LiteSVM::new().with_spl_programs();
+    let wrapper_id = Pubkey::from_str(WRAPPER_MAINNET).unwrap();
+    let stake_id = Pubkey::from_str(STAKE_ID).unwrap();
+    let token_program = Pubkey::from_str(TOKEN_PROGRAM).unwrap();
+    svm.add_program_from_file(wrapper_id, mock_wrapper_so())
+        .unwrap();
+    svm.add_program_from_file(stake_id, stake_so()).unwrap();
+
+    let admin = Keypair::new();
+    let payer = Keypair::new();
+    svm.airdrop(&admin.pubkey(), 100_000_000_000).unwrap();
+    svm.airdrop(&payer.pubkey(), 100_000_000_000).unwrap();
+
+    Env {
+        svm,
+        wrapper_id,
+        stake_id,
+        token_program,
+        admin,
+        payer,
+    }
+}
+
+static NEXT_PROPOSAL_ID: AtomicU64 = AtomicU64::new(1);
+
+// Methods rather than free functions so an instruction built from `&e` can be
+// passed straight in.
+impl Env {
+    fn send_as_admin(&mut self, ix: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.svm, &self.payer, &[&self.admin], ix)
+    }
+
+    fn send_permissionless(&mut self, ix: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.svm, &self.payer, &[], ix)
+    }
+
+    /// Tags 25..28 run only as the replay of a proposal (tag 39), committed
+    /// (tag 40) after `TIMELOCK_SLOTS`.
+    fn send_timelocked(&mut self, proxy: Instruction) -> Result<(), TransactionError> {
+        let proposal_id = NEXT_PROPOSAL_ID.fetch_add(1, Ordering::Relaxed);
+        let pool_pda = proxy.accounts[1].pubkey;
+        let (proposal, _) = derive_proposal_pda(&proxy.program_id, &pool_pda, proposal_id);
+        let mut data = vec![39u8];
+        data.extend_from_slice(&proposal_id.to_le_bytes());
+        data.extend_from_slice(&proxy.data);
+        let propose = Instruction {
+            program_id: proxy.program_id,
+            accounts: vec![
+                AccountMeta::new(self.admin.pubkey(), true),
+                AccountMeta::new_readonly(pool_pda, false),
+                AccountMeta::new(proposal, false),
+                AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data,
+        };
+        self.send_as_admin(propose)?;
+
+        let now = self.svm.get_sysvar::<Clock>().slot;
+        self.svm.warp_to_slot(now + TIMELOCK_SLOTS);
+
+        let mut accounts = vec![
+            AccountMeta::new(proposal, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ];
+        accounts.extend(proxy.accounts.iter().cloned());
+        let commit = Instruction {
+            program_id: proxy.program_id,
+            accounts,
+            data: vec![40u8],
+        };
+        self.send_as_admin(commit)
+    }
+}
+
+/// A mock market after the real InitPool: marketauth is the pool PDA, asset 0's
+/// authorities are still the admin.
+struct Staked {
+    market: Pubkey,
+    mint: Pubkey,
+    wrapper_vault: Pubkey,
+    wrapper_vault_auth: Pubkey,
+    pool_pda: Pubkey,
+    vault: Pubkey,
+    vault_auth: Pubkey,
+    lp_mint: Pubkey,
+}
+
+/// InitMarket on the mock (admin becomes every authority), then the real
+/// stake InitPool, which CPIs tag 32 to rotate marketauth to the pool PDA.
+fn staked_market(e: &mut Env) -> Staked {
+    let market = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    set_account(&mut e.svm, mint, e.token_program, mint_data());
+    set_account(
+        &mut e.svm,
+        market,
+        e.wrapper_id,
+        vec![0u8; mock::market_len(1)],
+    );
+    let init_market = Instruction {
+        program_id: e.wrapper_id,
+        accounts: vec![
+            AccountMeta::new(e.admin.pubkey(), true),
+            AccountMeta::new(market, false),
+            AccountMeta::new_readonly(mint, false),
+        ],
+        data: encode_init_market_v17(),
+    };
+    e.send_as_admin(init_market).expect("mock InitMarket");
+
+    let (wrapper_vault_auth, _) = mock::vault_authority(&e.wrapper_id, &market);
+    let wrapper_vault = Pubkey::new_unique();
+    set_account(
+        &mut e.svm,
+        wrapper_vault,
+        e.token_program,
+        token_data(&mint, &wrapper_vault_auth, 0),
+    );
+
+    let (pool_pda, _) = derive_pool_pda(&e.stake_id, &market);
+    let (vault_auth, _) = derive_vault_authority(&e.stake_id, &pool_pda);
+    let lp_mint = Pubkey::new_unique();
+    let vault = Pubkey::new_unique();
+    set_account(&mut e.svm, lp_mint, e.token_program, vec![0u8; 82]);
+    set_account(&mut e.svm, vault, e.token_program, vec![0u8; 165]);
+
+    let mut data = vec![0u8]; // tag InitPool
+    data.extend_from_slice(&5u64.to_le_bytes()); // cooldown_slots
+    data.extend_from_slice(&0u64.to_le_bytes()); // deposit_cap (uncapped)
+    data.extend_from_slice(&FeePolicyBounds::UNBOUNDED.pack());
+    let init_pool = Instruction {
+        program_id: e.stake_id,
+        accounts: vec![
+            AccountMeta::new(e.admin.pubkey(), true),
+            AccountMeta::new(market, false),
+            AccountMeta::new(pool_pda, false),
+            AccountMeta::new(lp_mint, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(vault_auth, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(e.wrapper_id, false),
+            AccountMeta::new_readonly(e.token_program, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::rent::id(), false),
+        ],
+        data,
+    };
+    e.send_as_admin(init_pool).expect("StakeInitPool");
+
+    Staked {
+        market,
+        mint,
+        wrapper_vault,
+        wrapper_vault_auth,
+        pool_pda,
+        vault,
+        vault_auth,
+        lp_mint,
+    }
+}
+
+/// A real genesis Deposit from a fresh user, so the vault holds something to
+/// flush.
+fn deposit(e: &mut Env, s: &Staked, amount: u64) {
+    assert!(amount > MINIMUM_LIQUIDITY);
+    let user = Keypair::new();
+    e.svm.airdrop(&user.pubkey(), 10_000_000_000).unwrap();
+    let user_ata = Pubkey::new_unique();
+    let user_lp_ata = Pubkey::new_unique();
+    set_account(
+        &mut e.svm,
+        user_ata,
+        e.token_program,
+        token_data(&s.mint, &user.pubkey(), amount),
+    );
+    set_account(
+        &mut e.svm,
+        user_lp_ata,
+        e.token_program,
+        token_data(&s.lp_mint, &user.pubkey(), 0),
+    );
+    let (deposit_pda, _) = derive_deposit_pda(&e.stake_id, &s.pool_pda, &user.pubkey());
+    let mut data = vec![1u8]; // tag Deposit
+    data.extend_from_slice(&amount.to_le_bytes());
+    let ix = Instruction {
+        program_id: e.stake_id,
+        accounts: vec![
+            AccountMeta::new(user.pubkey(), true),
+            AccountMeta::new(s.pool_pda, false),
+            AccountMeta::new(user_ata, false),
+            AccountMeta::new(s.vault, false),
+            AccountMeta::new(s.lp_mint, false),
+            AccountMeta::new(user_lp_ata, false),
+            AccountMeta::new_readonly(s.vault_auth, false),
+            AccountMeta::new(deposit_pda, false),
+            AccountMeta::new_readonly(e.token_program, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+        ],
+        data,
+    };
+    send(&mut e.svm, &e.payer, &[&user], ix).expect("Deposit");
+}
+
+// ── mock market access ──────────────────────────────────────────────────────
+
+fn market_data(svm: &LiteSVM, market: &Pubkey) -> Vec<u8> {
+    svm.get_account(market).expect("market exists").data
+}
+
+fn inject_faults(svm: &mut LiteSVM, market: &Pubkey, faults: Faults) {
+    let mut acct = svm.get_account(market).unwrap();
+    mock::set_faults(&mut acct.data, faults);
+    svm.set_account(*market, acct).unwrap();
+}
+
+fn read_pool(svm: &LiteSVM, pool_pda: &Pubkey) -> StakePool {
+    let data = svm.get_account(pool_pda).unwrap().data;
+    *bytemuck::from_bytes::<StakePool>(&data[..STAKE_POOL_SIZE])
+}
+
+// ── stake instructions ──────────────────────────────────────────────────────
+
+fn bind_ix(e: &Env, s: &Staked) -> Instruction {
+    Instruction {
+        program_id: e.stake_id,
+        accounts: vec![
+            AccountMeta::new(e.admin.pubkey(), true),
+            AccountMeta::new_readonly(s.pool_pda, false),
+            AccountMeta::new_readonly(s.vault_auth, false),
+            AccountMeta::new(s.market, false),
+            AccountMeta::new_readonly(e.wrapper_id, false),
+        ],
+        data: vec![19u8],
+    }
+}
+
+fn burn_asset_admin_ix(e: &Env, s: &Staked) -> Instruction {
+    Instruction {
+        program_id: e.stake_id,
+        accounts: vec![
+            AccountMeta::new(e.admin.pubkey(), true),
+            AccountMeta::new(s.pool_pda, false),
+            AccountMeta::new_readonly(s.vault_auth, false),
+            AccountMeta::new(s.market, false),
+            AccountMeta::new_readonly(e.wrapper_id, false),
+        ],
+        data: vec![21u8],
+    }
+}
+
+fn flush_ix(e: &Env, s: &Staked, amount: u64) -> Instruction {
+    let mut data = vec![3u8];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: e.stake_id,
+        accounts: vec![
+            AccountMeta::new(e.admin.pubkey(), true),
+            AccountMeta::new(s.pool_pda, false),
+            AccountMeta::new(s.vault, false),
+            AccountMeta::new_readonly(s.vault_auth, false),
+            AccountMeta::new(s.market, false),
+            AccountMeta::new(s.wrapper_vault, false),
+            AccountMeta::new_readonly(e.wrapper_id, false),
+            AccountMeta::new_readonly(e.token_program, false),
+        ],
+        data,
+    }
+}
+
+fn recover_ix(e: &Env, s: &Staked, amount: u64) -> Instruction {
+    let mut data = vec![23u8];
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction {
+        program_id: e.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(e.payer.pubkey(), false), // permissionless caller
+            AccountMeta::new(s.pool_pda, false),
+            AccountMeta::new(s.vault, false),
+            AccountMeta::new_readonly(s.vault_auth, false),
+            AccountMeta::new(s.market, false),
+            AccountMeta::new(s.wrapper_vault, false),
+            AccountMeta::new_readonly(s.wrapper_vault_auth, false),
+            AccountMeta::new_readonly(e.token_program, false),
+            AccountMeta::new_readonly(e.wrapper_id, false),
+        ],
+        data,
+    }
+}
+
+fn admin_resolve_market_ix(e: &Env, s: &Staked) -> Instruction {
+    Instruction {
+        program_id: e.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(e.admin.pubkey(), true),
+            AccountMeta::new_readonly(s.pool_pda, false),
+            AccountMeta::new(s.market, false),
+            AccountMeta::new_readonly(e.wrapper_id, false),
+        ],
+        data: vec![24u8],
+    }
+}
+
+/// With the market, so the wrapper insurance balance is checked too.
+fn assert_invariants_ix(e: &Env, s: &Staked) -> Instruction {
+    Instruction {
+        program_id: e.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(s.pool_pda, false),
+            AccountMeta::new_readonly(s.vault, false),
+            AccountMeta::new_readonly(s.lp_mint, false),
+            AccountMeta::new_readonly(s.market, false),
+        ],
+        data: vec![53u8],
+    }
+}
+
+fn probe_wrapper_ix(e: &Env, s: &Staked) -> Instruction {
+    Instruction {
+        program_id: e.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(s.pool_pda, false),
+            AccountMeta::new_readonly(s.market, false),
+        ],
+        data: vec![55u8],
+    }
+}
+
+/// Stake tags 25/26: [admin, pool_pda(w), slab(w), percolator]; the pool PDA
+/// signs as marketauth.
+fn marketauth_proxy_ix(e: &Env, s: &Staked, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: e.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(e.admin.pubkey(), true),
+            AccountMeta::new(s.pool_pda, false),
+            AccountMeta::new(s.market, false),
+            AccountMeta::new_readonly(e.wrapper_id, false),
+        ],
+        data,
+    }
+}
+
+/// Stake tags 27/28: [admin, pool_pda(w), vault_auth, slab(w), percolator];
+/// vault_auth signs as the asset's insurance_authority.
+fn insurance_proxy_ix(e: &Env, s: &Staked, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: e.stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(e.admin.pubkey(), true),
+            AccountMeta::new(s.pool_pda, false),
+            AccountMeta::new_readonly(s.vault_auth, false),
+            AccountMeta::new(s.market, false),
+            AccountMeta::new_readonly(e.wrapper_id, false),
+        ],
+        data,
+    }
+}
+
+/// A direct wrapper call by the admin, with the `[authority, market]` shape of
+/// tags 19/51/55/86/88.
+fn direct_wrapper_ix(e: &Env, s: &Staked, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: e.wrapper_id,
+        accounts: vec![
+            AccountMeta::new_readonly(e.admin.pubkey(), true),
+            AccountMeta::new(s.market, false),
+        ],
+        data,
+    }
+}
+
+// ═════════════════════════════════════════════════════════════════════════════
+// Tests
+// ═════════════════════════════════════════════════════════════════════════════
+
+/// The whole insurance custody lifecycle through the mock: InitPool rotates
+/// marketauth, bind moves asset 0's authority and operator to vault_auth, flush
+/// and recover move real tokens both ways, burn seals the asset admin, and
+/// AdminResolveMarket flips the market so the next CPI sees EngineLockActive.
+#[test]
+fn lifecycle_bind_flush_burn_recover_resolve() {
+    let mut e = env();
+    let s = staked_market(&mut e);
+    let admin = e.admin.pubkey().to_bytes();
+    let vault_auth = s.vault_auth.to_bytes();
+
+    let data = market_data(&e.svm, &s.market);
+    assert_eq!(
+        mock::read_pubkey(&data, mock::OFF_MARKETAUTH),
+        s.pool_pda.to_bytes(),
+        "InitPool's tag-32 CPI must hand marketauth to the pool PDA"
+    );
+    assert_eq!(mock::asset_authority(&data, 0, ASSET_AUTH_INSURANCE), admin);
+
+    deposit(&mut e, &s, DEPOSIT_AMOUNT);
+
+    // Before bind, vault_auth is not the insurance authority.
+    assert_custom_err(
+        e.send_as_admin(flush_ix(&e, &s, FLUSH_AMOUNT)),
+        ERR_UNAUTHORIZED,
+        "flush before bind",
+    );
+
+    e.send_as_admin(bind_ix(&e, &s)).expect("bind");
+    let data = market_data(&e.svm, &s.market);
+    assert_eq!(
+        mock::asset_authority(&data, 0, ASSET_AUTH_INSURANCE),
+        vault_auth
+    );
+    assert_eq!(
+        mock::asset_authority(&data, 0, ASSET_AUTH_INSURANCE_OPERATOR),
+        vault_auth
+    );
+
+    e.send_as_admin(flush_ix(&e, &s, FLUSH_AMOUNT))
+        .expect("flush after bind");
+    assert_eq!(
+        token_amount(&e.svm, &s.vault),
+        DEPOSIT_AMOUNT - FLUSH_AMOUNT
+    );
+    assert_eq!(token_amount(&e.svm, &s.wrapper_vault), FLUSH_AMOUNT);
+    assert_eq!(
+        mock::insurance_balance(&market_data(&e.svm, &s.market), 0),
+        FLUSH_AMOUNT as u128
+    );
+    assert_eq!(read_pool(&e.svm, &s.pool_pda).total_flushed, FLUSH_AMOUNT);
+    e.send_permissionless(assert_invariants_ix(&e, &s))
+        .expect("invariants hold after flush");
+
+    e.send_as_admin(burn_asset_admin_ix(&e, &s))
+        .expect("burn asset admin");
+    assert_eq!(
+        mock::asset_authority(&market_data(&e.svm, &s.market), 0, ASSET_AUTH_ADMIN),
+        [0u8; 32]
+    );
+
+    // Resolution waits for every flushed token to come back.
+    assert_custom_err(
+        e.send_as_admin(admin_resolve_market_ix(&e, &s)),
+        StakeError::InsuranceLossOutstanding as u32,
+        "resolve with flushed insurance outstanding",
+    );
+
+    e.send_permissionless(recover_ix(&e, &s, FLUSH_AMOUNT))
+        .expect("recover after burn");
+    assert_eq!(token_amount(&e.svm, &s.vault), DEPOSIT_AMOUNT);
+    assert_eq!(token_amount(&e.svm, &s.wrapper_vault), 0);
+    assert_eq!(
+        read_pool(&e.svm, &s.pool_pda).total_recovered_from_wrapper,
+        FLUSH_AMOUNT
+    );
+
+    e.send_as_admin(admin_resolve_market_ix(&e, &s))
+        .expect("resolve once recovered");
+    assert_eq!(market_data(&e.svm, &s.market)[OFF_MODE], MODE_RESOLVED);
+
+    // A resolved market refuses every gated tag before checking authority.
+    assert_custom_err(
+        e.send_as_admin(flush_ix(&e, &s, FLUSH_AMOUNT)),
+        ERR_ENGINE_LOCK_ACTIVE,
+        "flush into a resolved market",
+    );
+    assert_custom_err(
+        e.send_as_admin(admin_resolve_market_ix(&e, &s)),
+        ERR_ENGINE_LOCK_ACTIVE,
+        "second resolve",
+    );
+}
+
+/// A wrapper that books the full amount but moves one token less must not be
+/// believed: both flush (tokens out) and recover (tokens in) fail with
+/// VaultDeltaMismatch and leave pool accounting where it was.
+#[test]
+fn short_transfer_fails_the_vault_delta_check_both_ways() {
+    let mut e = env();
+    let s = staked_market(&mut e);
+    deposit(&mut e, &s, DEPOSIT_AMOUNT);
+    e.send_as_admin(bind_ix(&e, &s)).expect("bind");
+
+    let short = Faults {
+        short_transfer: 1,
+        ..Faults::default()
+    };
+    inject_faults(&mut e.svm, &s.market, short);
+    assert_custom_err(
+        e.send_as_admin(flush_ix(&e, &s, FLUSH_AMOUNT)),
+        StakeError::VaultDeltaMismatch as u32,
+        "short flush",
+    );
+    assert_eq!(token_amount(&e.svm, &s.vault), DEPOSIT_AMOUNT);
+    assert_eq!(read_pool(&e.svm, &s.pool_pda).total_flushed, 0);
+
+    inject_faults(&mut e.svm, &s.market, Faults::default());
+    e.send_as_admin(flush_ix(&e, &s, FLUSH_AMOUNT))
+        .expect("honest flush");
+
+    inject_faults(&mut e.svm, &s.market, short);
+    assert_custom_err(
+        e.send_permissionless(recover_ix(&e, &s, FLUSH_AMOUNT)),
+        StakeError::VaultDeltaMismatch as u32,
+        "short recovery",
+    );
+    let pool = read_pool(&e.svm, &s.pool_pda);
+    assert_eq!(pool.total_recovered_from_wrapper, 0);
+    assert_eq!(pool.wrapper_recoverable(), FLUSH_AMOUNT);
+}
+
+/// A wrapper error is returned unchanged through the CPI, whichever tag
+/// raises it, so a client can tell "the market is locked" from a stake refusal.
+#[test]
+fn injected_engine_lock_surfaces_through_each_cpi() {
+    let mut e = env();
+    let s = staked_market(&mut e);
+    deposit(&mut e, &s, DEPOSIT_AMOUNT);
+
+    let lock = |tag: u8| Faults {
+        fail_tag: tag,
+        fail_code: ERR_ENGINE_LOCK_ACTIVE,
+        ..Faults::default()
+    };
+
+    inject_faults(&mut e.svm, &s.market, lock(65));
+    assert_custom_err(
+        e.send_as_admin(bind_ix(&e, &s)),
+        ERR_ENGINE_LOCK_ACTIVE,
+        "bind (tag 65)",
+    );
+    inject_faults(&mut e.svm, &s.market, Faults::default());
+    e.send_as_admin(bind_ix(&e, &s)).expect("bind");
+
+    inject_faults(&mut e.svm, &s.market, lock(9));
+    assert_custom_err(
+        e.send_as_admin(flush_ix(&e, &s, FLUSH_AMOUNT)),
+        ERR_ENGINE_LOCK_ACTIVE,
+        "flush (tag 9)",
+    );
+    inject_faults(&mut e.svm, &s.market, Faults::default());
+    e.send_as_admin(flush_ix(&e, &s, FLUSH_AMOUNT))
+        .expect("flush");
+
+    inject_faults(&mut e.svm, &s.market, lock(57));
+    assert_custom_err(
+        e.send_permissionless(recover_ix(&e, &s, FLUSH_AMOUNT)),
+        ERR_ENGINE_LOCK_ACTIVE,
+        "recover (tag 57)",
+    );
+    inject_faults(&mut e.svm, &s.market, Faults::default());
+    e.send_permissionless(recover_ix(&e, &s, FLUSH_AMOUNT))
+        .expect("recover");
+
+    inject_faults(&mut e.svm, &s.market, lock(19));
+    assert_custom_err(
+        e.send_as_admin(admin_resolve_market_ix(&e, &s)),
+        ERR_ENGINE_LOCK_ACTIVE,
+        "resolve (tag 19)",
+    );
+    assert_eq!(market_data(&e.svm, &s.market)[OFF_MODE], MODE_LIVE);
+}
+
+/// Every setter stranded by staking is reachable only through its timelocked
+/// proxy: the admin's direct call is refused with Unauthorized, the proxy's
+/// write lands in the market.
+#[test]
+fn proxies_reach_every_stranded_setter() {
+    let mut e = env();
+    let s = staked_market(&mut e);
+
+    // Tag 86 (marketauth) through stake 25.
+    let split = [3600u16, 3200, 1200];
+    let shares: Vec<u8> = split.iter().flat_map(|v| v.to_le_bytes()).collect();
+    let direct = direct_wrapper_ix(&e, &s, [&[86u8][..], &shares[..]].concat());
+    assert_custom_err(
+        e.send_as_admin(direct),
+        ERR_UNAUTHORIZED,
+        "direct tag 86 after InitPool",
+    );
+    e.send_timelocked(marketauth_proxy_ix(
+        &e,
+        &s,
+        [&[25u8][..], &shares[..]].concat(),
+    ))
+    .expect("stake 25 -> wrapper 86");
+    assert_eq!(
+        mock::fee_split(&market_data(&e.svm, &s.market)),
+        (3600, 3200, 1200)
+    );
+
+    // Tag 88 (marketauth) through stake 26, with a value past u64::MAX.
+    let big = u64::MAX as u128 + 12_345;
+    e.send_timelocked(marketauth_proxy_ix(
+        &e,
+        &s,
+        [&[26u8][..], &big.to_le_bytes()[..]].concat(),
+    ))
+    .expect("stake 26 -> wrapper 88");
+    assert_eq!(
+        mock::read_u128(
+            &market_data(&e.svm, &s.market),
+            OFF_MAINTENANCE_FEE_PER_SLOT
+        ),
+        big
+    );
+
+    e.send_as_admin(bind_ix(&e, &s)).expect("bind");
+
+    // Tag 51 (insurance_authority of domain / 2) through stake 27.
+    let policy: Vec<u8> = [1u16, 25, 5000]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let direct = direct_wrapper_ix(&e, &s, [&[51u8][..], &policy[..]].concat());
+    assert_custom_err(
+        e.send_as_admin(direct),
+        ERR_UNAUTHORIZED,
+        "direct tag 51 after bind",
+    );
+    e.send_timelocked(insurance_proxy_ix(
+        &e,
+        &s,
+        [&[27u8][..], &policy[..]].concat(),
+    ))
+    .expect("stake 27 -> wrapper 51");
+    assert_eq!(
+        mock::backing_fee_policy(&market_data(&e.svm, &s.market), 1),
+        (25, 5000)
+    );
+
+    // Tag 55 (asset 0 insurance_authority) through stake 28.
+    e.send_timelocked(insurance_proxy_ix(
+        &e,
+        &s,
+        [&[28u8][..], &42u64.to_le_bytes()[..]].concat(),
+    ))
+    .expect("stake 28 -> wrapper 55");
+    assert_eq!(
+        mock::read_u64(&market_data(&e.svm, &s.market), OFF_TRADE_FEE_BASE_BPS),
+        42
+    );
+}
+
+/// ProbeWrapper passes on the mock market, then names each way the account can
+/// stop being one: wrong owner, wrong length, blank header.
+#[test]
+fn probe_wrapper_names_owner_layout_and_header_faults() {
+    let mut e = env();
+    let s = staked_market(&mut e);
+    e.send_permissionless(probe_wrapper_ix(&e, &s))
+        .expect("probe a live market");
+
+    let original = e.svm.get_account(&s.market).unwrap();
+    type Corrupt = fn(&mut Account);
+    let cases: [(&str, StakeError, Corrupt); 3] = [
+        (
+            "foreign owner",
+            StakeError::WrapperMarketOwnerMismatch,
+            |a| a.owner = Pubkey::new_unique(),
+        ),
+        (
+            "one byte short",
+            StakeError::WrapperMarketLayoutMismatch,
+            |a| {
+                a.data.pop();
+            },
+        ),
+        (
+            "blank header",
+            StakeError::WrapperMarketUninitialized,
+            |a| a.data[..mock::HEADER_LEN].fill(0),
+        ),
+    ];
+    for (ctx, expected, corrupt) in cases {
+        let mut acct = original.clone();
+        corrupt(&mut acct);
+        e.svm.set_account(s.market, acct).unwrap();
+        assert_custom_err(
+            e.send_permissionless(probe_wrapper_ix(&e, &s)),
+            expected as u32,
+            ctx,
+        );
+    }
+}
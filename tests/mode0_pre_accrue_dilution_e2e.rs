@@ -43,8 +43,8 @@
 use litesvm::LiteSVM;
 use percolator_stake::math::{calc_collateral_for_withdraw, calc_lp_for_deposit};
 use percolator_stake::state::{
-    derive_deposit_pda, derive_pool_pda, derive_vault_authority, StakePool, MINIMUM_LIQUIDITY,
-    STAKE_POOL_SIZE,
+    derive_deposit_pda, derive_pool_pda, derive_vault_authority, FeePolicyBounds, StakePool,
+    MINIMUM_LIQUIDITY, STAKE_POOL_SIZE,
 };
 use solana_sdk::{
     account::Account,
@@ -229,10 +229,11 @@ fn preallocate_empty_spl_account(
 // ---- Stake InitPool (tag 0) -- copied verbatim from mode0_accrue_fees_e2e.rs. ----
 
 fn encode_init_pool(cooldown_slots: u64, deposit_cap: u64) -> Vec<u8> {
-    let mut out = Vec::with_capacity(17);
+    let mut out = Vec::with_capacity(1 + 16 + FeePolicyBounds::LEN);
     out.push(0u8); // tag InitPool
     out.extend_from_slice(&cooldown_slots.to_le_bytes());
     out.extend_from_slice(&deposit_cap.to_le_bytes());
+    out.extend_from_slice(&FeePolicyBounds::UNBOUNDED.pack());
     out
 }
 
@@ -28,7 +28,7 @@
 //!    wrapper's `expect_writable` with a confusing error).
 
 use litesvm::LiteSVM;
-use percolator_stake::state::{derive_pool_pda, derive_vault_authority};
+use percolator_stake::state::{derive_pool_pda, derive_vault_authority, FeePolicyBounds};
 use solana_sdk::{
     account::Account,
     instruction::{AccountMeta, Instruction, InstructionError},
@@ -197,10 +197,11 @@ fn preallocate_empty_spl_account(
 }
 
 fn encode_init_pool(cooldown_slots: u64, deposit_cap: u64) -> Vec<u8> {
-    let mut out = Vec::with_capacity(17);
+    let mut out = Vec::with_capacity(1 + 16 + FeePolicyBounds::LEN);
     out.push(0u8); // tag InitPool
     out.extend_from_slice(&cooldown_slots.to_le_bytes());
     out.extend_from_slice(&deposit_cap.to_le_bytes());
+    out.extend_from_slice(&FeePolicyBounds::UNBOUNDED.pack());
     out
 }
 
@@ -0,0 +1,165 @@
+//! Differential property tests: Kani's u32 instantiation vs production u64.
+//!
+//! `kani-proofs/src/lib.rs` expands `src/lp_math.rs` at u32/u64/i64 and adds
+//! a few model helpers on top. This file includes that crate by path and checks
+//! every function it proves against the REAL production code — `math::*` and
+//! the `StakePool` valuation methods — on u32 inputs widened to u64. A result
+//! the u32 side reports as `None` must be one the u64 side cannot fit back into
+//! a u32; anything else is a disagreement and fails the test.
+//!
+//! ```
+//! cargo test --test proptest_kani_mirror
+//! ```
+
+use bytemuck::Zeroable;
+use percolator_stake::math;
+use percolator_stake::state::StakePool;
+use proptest::prelude::*;
+
+#[allow(dead_code)]
+#[path = "../kani-proofs/src/lib.rs"]
+mod kani_mirror;
+
+/// Narrow a production result to the u32 domain (`None` if it does not fit).
+fn fits(v: Option<u64>) -> Option<u32> {
+    v.and_then(|v| u32::try_from(v).ok())
+}
+
+/// u32 inputs biased towards the zero / small-value branches.
+fn word() -> impl Strategy<Value = u32> {
+    prop_oneof![0u32..4, 0u32..=0xFFFF, any::<u32>()]
+}
+
+fn ledger_pool(
+    deposited: u32,
+    withdrawn: u32,
+    flushed: u32,
+    returned: u32,
+    fees: u32,
+    realized_junior_loss: u32,
+    junior_balance: u32,
+) -> StakePool {
+    let mut pool = StakePool::zeroed();
+    pool.is_initialized = 1;
+    pool.set_discriminator();
+    pool.set_tranche_enabled(true);
+    pool.total_deposited = deposited as u64;
+    pool.total_withdrawn = withdrawn as u64;
+    pool.total_flushed = flushed as u64;
+    pool.total_returned = returned as u64;
+    pool.total_fees_earned = fees as u64;
+    pool.set_realized_junior_loss(realized_junior_loss as u64);
+    pool.set_junior_balance(junior_balance as u64);
+    pool
+}
+
+proptest! {
+    #[test]
+    fn lp_pricing_matches(supply in word(), pv in word(), amount in word()) {
+        let (s, v, a) = (supply as u64, pv as u64, amount as u64);
+        prop_assert_eq!(
+            kani_mirror::calc_lp_for_deposit(supply, pv, amount),
+            fits(math::calc_lp_for_deposit(s, v, a))
+        );
+        prop_assert_eq!(
+            kani_mirror::calc_collateral_for_withdraw(supply, pv, amount),
+            fits(math::calc_collateral_for_withdraw(s, v, a))
+        );
+        prop_assert_eq!(
+            kani_mirror::calc_senior_lp_for_deposit(supply, pv, amount),
+            fits(math::calc_senior_lp_for_deposit(s, v, a))
+        );
+        prop_assert_eq!(
+            kani_mirror::calc_junior_collateral_for_withdraw(supply, pv, amount),
+            fits(math::calc_junior_collateral_for_withdraw(s, v, a))
+        );
+    }
+
+    #[test]
+    fn tranche_splits_match(
+        jb in word(),
+        sb in word(),
+        amount in word(),
+        mult in 0u16..=50_000,
+    ) {
+        let (jl, sl) = kani_mirror::distribute_loss(jb, sb, amount);
+        prop_assert_eq!(
+            (jl as u64, sl as u64),
+            math::distribute_loss(jb as u64, sb as u64, amount as u64)
+        );
+        let (jf, sf) = kani_mirror::distribute_fees(jb, sb, mult, amount);
+        prop_assert_eq!(
+            (jf as u64, sf as u64),
+            math::distribute_fees(jb as u64, sb as u64, mult, amount as u64)
+        );
+    }
+
+    #[test]
+    fn policy_checks_match(
+        a in word(),
+        b in word(),
+        c in word(),
+        bps in any::<u16>(),
+    ) {
+        let (x, y, z) = (a as u64, b as u64, c as u64);
+        prop_assert_eq!(kani_mirror::pool_value(a, b), fits(math::pool_value(x, y)));
+        prop_assert_eq!(kani_mirror::hwm_floor(a, bps), fits(math::hwm_floor(x, bps)));
+        prop_assert_eq!(
+            kani_mirror::hwm_withdrawal_allowed(a, b, bps),
+            math::hwm_withdrawal_allowed(x, y, bps)
+        );
+        prop_assert_eq!(kani_mirror::exceeds_cap(a, b, c), math::exceeds_cap(x, y, z));
+        // u32 saturates where u64 does not: compare where the deadline fits.
+        if b.checked_add(c).is_some() {
+            prop_assert_eq!(
+                kani_mirror::cooldown_elapsed(a, b, c),
+                math::cooldown_elapsed(x, y, z)
+            );
+        }
+    }
+
+    #[test]
+    fn pool_valuation_matches_stake_pool(
+        deposited in word(),
+        withdrawn in word(),
+        flushed in word(),
+        returned in word(),
+        fees in word(),
+        rl in word(),
+        jb in word(),
+    ) {
+        let pool = ledger_pool(deposited, withdrawn, flushed, returned, fees, rl, jb);
+        let pv = kani_mirror::total_pool_value(deposited, withdrawn, flushed, returned, fees, rl);
+        prop_assert_eq!(pv, fits(pool.total_pool_value()));
+        prop_assert_eq!(
+            kani_mirror::effective_junior_balance(deposited, withdrawn, flushed, returned, jb) as u64,
+            pool.effective_junior_balance()
+        );
+        // The u32 senior balance needs the pool value itself to fit.
+        if pv.is_some() {
+            prop_assert_eq!(
+                kani_mirror::senior_balance(deposited, withdrawn, flushed, returned, fees, rl, jb),
+                fits(pool.senior_balance())
+            );
+        }
+    }
+
+    #[test]
+    fn model_helpers_match_stake_pool(
+        deposited in word(),
+        withdrawn in word(),
+        flushed in word(),
+        returned in word(),
+    ) {
+        let pool = ledger_pool(deposited, withdrawn, flushed, returned, 0, 0, 0);
+        prop_assert_eq!(
+            kani_mirror::pool_value_with_flush(deposited, withdrawn, flushed, returned),
+            fits(pool.total_pool_value())
+        );
+        let unreturned = ledger_pool(deposited, withdrawn, flushed, 0, 0, 0, 0);
+        prop_assert_eq!(
+            kani_mirror::flush_available(deposited, withdrawn, flushed) as u64,
+            unreturned.total_pool_value().unwrap_or(0)
+        );
+    }
+}
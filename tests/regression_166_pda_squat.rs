@@ -35,6 +35,15 @@
 //! are removed the test degenerates to the normal create_account path and exercises
 //! nothing of value.
 //!
+//! -- Close-then-recreate (CloseDeposit, tag 35) --
+//! Closing a deposit PDA makes "closed, then created again" a reachable lifecycle.
+//! Test 3 closes a fully exited record via `Withdraw`'s `close_if_empty` flag and, IN
+//! THE SAME TRANSACTION, refunds the address (the classic revival of a closed
+//! account). It asserts the address is left as the plain squat state (System-owned,
+//! empty data) rather than a revived program-owned record, that `CloseDeposit`
+//! refuses while LP is held, and that the next Deposit recreates the record from
+//! scratch — the `SetAuthorizedDepositor` opt-in written before the close is gone.
+//!
 //! Because this exercises `target/deploy/percolator_stake.so`, rebuild the SBF
 //! artifact with `cargo build-sbf --no-default-features` after changing source code;
 //! otherwise a stale artifact can report stale results.
@@ -42,15 +51,15 @@
 use bytemuck::Zeroable;
 use litesvm::LiteSVM;
 use percolator_stake::state::{
-    derive_deposit_pda, derive_pool_pda, derive_vault_authority, StakePool, STAKE_DEPOSIT_SIZE,
-    STAKE_POOL_SIZE,
+    derive_deposit_pda, derive_pool_pda, derive_vault_authority, StakeDeposit, StakePool,
+    STAKE_DEPOSIT_SIZE, STAKE_POOL_SIZE,
 };
 use solana_sdk::{
     account::Account,
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
     signer::{keypair::Keypair, Signer},
-    system_program,
+    system_instruction, system_program,
     transaction::Transaction,
 };
 use std::path::PathBuf;
@@ -607,3 +616,274 @@ fn deposit_pda_clean_path_unaffected() {
         "POST-STATE (clean): LP tokens minted 1:1 minus the N7 dead-share floor for first depositor"
     );
 }
+
+// ==================================================================================
+// Test 3 -- closed deposit_pda cannot be revived with stale state
+//
+// Withdraw with `close_if_empty` closes the record once the position is empty. The
+// same transaction then transfers lamports back to the address. A close that only
+// drained lamports would leave a live, program-owned record with its old bytes; the
+// close must instead leave the plain #166 squat state, which the next Deposit adopts
+// and re-initializes from zero.
+// ==================================================================================
+
+/// Build a Withdraw (tag 2) instruction with the optional `close_if_empty` byte.
+///
+/// Account order: user [signer, writable when closing], pool_pda, user_lp_ata,
+/// lp_mint, vault, user_ata, vault_auth, deposit_pda, token_program, clock.
+#[allow(clippy::too_many_arguments)]
+fn withdraw_ix(
+    stake_id: Pubkey,
+    user: &Pubkey,
+    pool_pda: Pubkey,
+    user_lp_ata: Pubkey,
+    lp_mint: Pubkey,
+    vault: Pubkey,
+    user_ata: Pubkey,
+    vault_auth: Pubkey,
+    deposit_pda: Pubkey,
+    lp_amount: u64,
+    close_if_empty: bool,
+) -> Instruction {
+    let token_program = Pubkey::from_str(TOKEN_PROGRAM).unwrap();
+    let mut data = vec![2u8]; // tag = Withdraw
+    data.extend_from_slice(&lp_amount.to_le_bytes());
+    data.push(close_if_empty as u8);
+    Instruction {
+        program_id: stake_id,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new(pool_pda, false),
+            AccountMeta::new(user_lp_ata, false),
+            AccountMeta::new(lp_mint, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(user_ata, false),
+            AccountMeta::new_readonly(vault_auth, false),
+            AccountMeta::new(deposit_pda, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ],
+        data,
+    }
+}
+
+/// Build a CloseDeposit (tag 35) instruction.
+fn close_deposit_ix(
+    stake_id: Pubkey,
+    user: &Pubkey,
+    pool_pda: Pubkey,
+    deposit_pda: Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: stake_id,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(pool_pda, false),
+            AccountMeta::new(deposit_pda, false),
+        ],
+        data: vec![35u8],
+    }
+}
+
+/// Build a SetAuthorizedDepositor (tag 31) instruction.
+fn set_authorized_depositor_ix(
+    stake_id: Pubkey,
+    user: &Pubkey,
+    pool_pda: Pubkey,
+    deposit_pda: Pubkey,
+    depositor: &Pubkey,
+) -> Instruction {
+    let mut data = vec![31u8];
+    data.extend_from_slice(depositor.as_ref());
+    Instruction {
+        program_id: stake_id,
+        accounts: vec![
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(pool_pda, false),
+            AccountMeta::new(deposit_pda, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data,
+    }
+}
+
+fn read_deposit(svm: &LiteSVM, key: &Pubkey) -> StakeDeposit {
+    let acct = svm.get_account(key).expect("deposit_pda exists");
+    bytemuck::pod_read_unaligned(&acct.data[..STAKE_DEPOSIT_SIZE])
+}
+
+#[test]
+fn closed_deposit_pda_not_revivable_and_recreated_fresh() {
+    let so = stake_so();
+    if !so.exists() {
+        eprintln!(
+            "SKIP closed_deposit_pda_not_revivable_and_recreated_fresh: stake .so missing at {} \
+             -- run `cargo build-sbf --no-default-features` first",
+            so.display()
+        );
+        return;
+    }
+
+    let mut svm = LiteSVM::new().with_spl_programs();
+    let stake_id = Pubkey::from_str(STAKE_ID).unwrap();
+    svm.add_program_from_file(stake_id, so).unwrap();
+
+    let payer = Keypair::new();
+    let user = Keypair::new();
+    svm.airdrop(&payer.pubkey(), 10_000_000_000).unwrap();
+    svm.airdrop(&user.pubkey(), 10_000_000_000).unwrap();
+
+    let collateral_mint = Pubkey::new_unique();
+    set_collateral_mint(&mut svm, collateral_mint);
+
+    let slab = Pubkey::new_unique();
+    let (pool_pda_derived, _) = derive_pool_pda(&stake_id, &slab);
+    let (vault_auth_derived, _) = derive_vault_authority(&stake_id, &pool_pda_derived);
+
+    let lp_mint = Pubkey::new_unique();
+    set_lp_mint(&mut svm, lp_mint, &vault_auth_derived);
+
+    let vault = Pubkey::new_unique();
+    let (pool_pda, vault_auth) = inject_pool(
+        &mut svm,
+        stake_id,
+        slab,
+        &user.pubkey(),
+        collateral_mint,
+        lp_mint,
+        vault,
+    );
+    assert_eq!(pool_pda, pool_pda_derived);
+    assert_eq!(vault_auth, vault_auth_derived);
+
+    set_token_account(&mut svm, vault, &collateral_mint, &vault_auth, 0);
+    let user_ata = Pubkey::new_unique();
+    set_token_account(&mut svm, user_ata, &collateral_mint, &user.pubkey(), 3_000);
+    let user_lp_ata = Pubkey::new_unique();
+    set_token_account(&mut svm, user_lp_ata, &lp_mint, &user.pubkey(), 0);
+
+    let (deposit_pda, _) = derive_deposit_pda(&stake_id, &pool_pda, &user.pubkey());
+    let deposit = |amount: u64| {
+        deposit_ix(
+            stake_id,
+            &user.pubkey(),
+            pool_pda,
+            user_ata,
+            vault,
+            lp_mint,
+            user_lp_ata,
+            vault_auth,
+            deposit_pda,
+            amount,
+        )
+    };
+
+    // Genesis deposit: 1,500 in, 500 LP out (N7 locks MINIMUM_LIQUIDITY).
+    send(&mut svm, &payer, &[&user], deposit(1_500))
+        .unwrap_or_else(|e| panic!("genesis Deposit failed: {:?}", e.err));
+    let lp_held = token_amount(&svm, &user_lp_ata);
+    assert_eq!(lp_held, 1_500 - percolator_stake::state::MINIMUM_LIQUIDITY);
+
+    // Stale state to look for after the close: a third-party deposit opt-in.
+    let funder = Pubkey::new_unique();
+    send(
+        &mut svm,
+        &payer,
+        &[&user],
+        set_authorized_depositor_ix(stake_id, &user.pubkey(), pool_pda, deposit_pda, &funder),
+    )
+    .unwrap_or_else(|e| panic!("SetAuthorizedDepositor failed: {:?}", e.err));
+    assert_eq!(
+        read_deposit(&svm, &deposit_pda).authorized_depositor(),
+        funder.to_bytes()
+    );
+
+    // CloseDeposit must refuse while the position still holds LP.
+    send(
+        &mut svm,
+        &payer,
+        &[&user],
+        close_deposit_ix(stake_id, &user.pubkey(), pool_pda, deposit_pda),
+    )
+    .expect_err("CloseDeposit must reject an open position (DepositNotEmpty)");
+
+    // Past the injected pool's 100-slot cooldown.
+    svm.warp_to_slot(1_000);
+
+    // Full exit with close_if_empty, then a same-transaction refund of the address.
+    let user_lamports_before = svm.get_account(&user.pubkey()).unwrap().lamports;
+    let rent_in_pda = svm.get_account(&deposit_pda).unwrap().lamports;
+    // Rent-exempt for zero bytes: the runtime refuses to leave a new account rent-paying.
+    let refund = svm.minimum_balance_for_rent_exemption(0);
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            withdraw_ix(
+                stake_id,
+                &user.pubkey(),
+                pool_pda,
+                user_lp_ata,
+                lp_mint,
+                vault,
+                user_ata,
+                vault_auth,
+                deposit_pda,
+                lp_held,
+                true,
+            ),
+            system_instruction::transfer(&payer.pubkey(), &deposit_pda, refund),
+        ],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        svm.latest_blockhash(),
+    );
+    svm.send_transaction(tx).unwrap_or_else(|e| {
+        panic!(
+            "Withdraw(close_if_empty) + refund failed: {:?}\nLogs:\n{}",
+            e.err,
+            e.meta.logs.join("\n")
+        )
+    });
+
+    // ---- POST-CLOSE: the squat state, not a revived record ----
+    let closed = svm
+        .get_account(&deposit_pda)
+        .expect("refunded address still exists");
+    assert_eq!(
+        closed.owner,
+        system_program::id(),
+        "POST-CLOSE: a refunded closed PDA must be System-owned, not a revived record"
+    );
+    assert!(
+        closed.data.is_empty(),
+        "POST-CLOSE: a refunded closed PDA must carry no stale data"
+    );
+    assert_eq!(
+        closed.lamports, refund,
+        "POST-CLOSE: only the refund remains"
+    );
+    assert_eq!(
+        svm.get_account(&user.pubkey()).unwrap().lamports,
+        user_lamports_before + rent_in_pda,
+        "POST-CLOSE: the record's rent went back to the user"
+    );
+
+    // ---- RECREATE: adopted through the squat path, from zero ----
+    send(&mut svm, &payer, &[&user], deposit(1_000)).unwrap_or_else(|e| {
+        panic!(
+            "re-Deposit into a closed-then-refunded PDA must succeed: {:?}\nLogs:\n{}",
+            e.err,
+            e.meta.logs.join("\n")
+        )
+    });
+    let post = svm.get_account(&deposit_pda).unwrap();
+    assert_eq!(post.owner, stake_id);
+    assert_eq!(post.data.len(), STAKE_DEPOSIT_SIZE);
+    let fresh = read_deposit(&svm, &deposit_pda);
+    assert_eq!(fresh.is_initialized, 1);
+    assert_eq!(fresh.lp_amount, token_amount(&svm, &user_lp_ata));
+    assert_eq!(
+        fresh.authorized_depositor(),
+        [0u8; 32],
+        "RECREATE: the pre-close opt-in must not survive the close"
+    );
+}
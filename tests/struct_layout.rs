@@ -6,15 +6,20 @@
 use percolator_stake::state::{StakeDeposit, StakePool, STAKE_DEPOSIT_SIZE, STAKE_POOL_SIZE};
 
 #[test]
-fn test_stake_pool_size_is_408() {
+fn test_stake_pool_size_is_736() {
     // v4 layout: v3's 392 + pending_cooldown_slots[8] + cooldown_proposed_at_slot[8]
     // = 408. The two #242 timelock values were promoted out of `_reserved[10..26]`,
     // where they aliased the PERC-313 HWM fields on the deployed v3 program.
+    // v5 layout: 408 + the pool sunset fields, the fee-policy bounds, the
+    // LP-veto state, the `council` reference, the role table,
+    // `admin_proposed_at_slot`, the flush policy with the config locks,
+    // `deficit_written_off`, the backing-fee bound and
+    // `deficit_junior_written_off` = 736, all appended.
     // If this changes, existing on-chain data becomes unreadable.
     // NEVER change this without a version bump + (if not fresh-start) a migration.
-    // Pools are being re-seeded fresh for v4, so no migration path is needed.
-    assert_eq!(STAKE_POOL_SIZE, 408);
-    assert_eq!(std::mem::size_of::<StakePool>(), 408);
+    // Pools are being re-seeded fresh for v5, so no migration path is needed.
+    assert_eq!(STAKE_POOL_SIZE, 736);
+    assert_eq!(std::mem::size_of::<StakePool>(), 736);
 }
 
 /// The new fields must be APPENDED after `total_recovered_from_wrapper` (offset 384),
@@ -41,6 +46,81 @@ fn test_v3_prefix_offsets_are_unchanged() {
         400,
         "must be appended, not inserted"
     );
+    assert_eq!(
+        off(&pool.finalized_at_slot),
+        408,
+        "v5 sunset field must be appended"
+    );
+    assert_eq!(
+        &pool.final_sweep_recipient as *const _ as usize - base,
+        416,
+        "v5 sunset field must be appended"
+    );
+    assert_eq!(
+        off(&pool.fee_policy_min_change_interval_slots),
+        448,
+        "v5 fee-policy bounds must be appended"
+    );
+    assert_eq!(
+        off(&pool.last_trade_fee_base_bps),
+        480,
+        "v5 fee-policy bounds must be appended"
+    );
+    assert_eq!(
+        off(&pool.proposal_counter),
+        488,
+        "v5 veto state must be appended"
+    );
+    assert_eq!(
+        &pool.veto_threshold_bps as *const _ as usize - base,
+        528,
+        "v5 veto state must be appended"
+    );
+    assert_eq!(
+        &pool.council as *const _ as usize - base,
+        536,
+        "v5 council must be appended"
+    );
+    assert_eq!(
+        &pool.roles as *const _ as usize - base,
+        568,
+        "v5 role table must be appended"
+    );
+    assert_eq!(
+        off(&pool.admin_proposed_at_slot),
+        696,
+        "v5 admin_proposed_at_slot must be appended"
+    );
+    assert_eq!(
+        &pool.flush_policy_max_bps as *const _ as usize - base,
+        704,
+        "v5 flush policy must be appended"
+    );
+    assert_eq!(
+        &pool.config_locks as *const _ as usize - base,
+        706,
+        "v5 config locks must be appended"
+    );
+    assert_eq!(
+        &pool.deficit_mode as *const _ as usize - base,
+        707,
+        "deficit flag must reuse the lock padding"
+    );
+    assert_eq!(
+        &pool.wrapper_asset_index as *const _ as usize - base,
+        708,
+        "wrapper asset index must reuse the lock padding"
+    );
+    assert_eq!(
+        &pool.wrapper_abi_version as *const _ as usize - base,
+        710,
+        "wrapper ABI version must reuse the lock padding"
+    );
+    assert_eq!(
+        &pool.deficit_written_off as *const _ as usize - base,
+        712,
+        "v5 deficit_written_off must be appended"
+    );
 }
 
 #[test]
@@ -21,8 +21,10 @@
 //!      insurance_authority-gated tags) so the authority genuinely moves;
 //!   4. call the wrapper tag DIRECTLY as admin again and prove it now FAILS —
 //!      this is the step that distinguishes a real proxy from a decorative one;
-//!   5. call the PROXY, prove it succeeds, and read the wrapper config back off
-//!      the chain to assert the stored value actually changed.
+//!   5. call the PROXY — proposed, held for TIMELOCK_SLOTS, then committed
+//!      (tags 39/40; a direct tag 25..28 is refused) — prove it succeeds, and
+//!      read the wrapper config back off the chain to assert the stored value
+//!      actually changed.
 //!
 //! Steps 2 and 4 together are the load-bearing pair: the same bytes that worked
 //! before the rotation stop working after it, and only the proxy recovers them.
@@ -38,9 +40,13 @@
 //! that specific hole is closed.
 
 use litesvm::LiteSVM;
-use percolator_stake::state::{derive_pool_pda, derive_vault_authority};
+use percolator_stake::processor::TIMELOCK_SLOTS;
+use percolator_stake::state::{
+    derive_pool_pda, derive_proposal_pda, derive_vault_authority, FeePolicyBounds,
+};
 use solana_sdk::{
     account::Account,
+    clock::Clock,
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
     signer::{keypair::Keypair, Signer},
@@ -48,6 +54,7 @@ use solana_sdk::{
 };
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 const WRAPPER_MAINNET: &str = "ESa89R5Es3rJ5mnwGybVRG1GrNt9etP11Z5V2QWD4edv";
 // The stake program's canonical declared id (`solana_program::declare_id!` in
@@ -108,6 +115,10 @@ const S_TAG_ADMIN_UPDATE_FEE_SPLIT: u8 = 25;
 const S_TAG_ADMIN_UPDATE_MAINTENANCE_FEE_PER_SLOT: u8 = 26;
 const S_TAG_ADMIN_UPDATE_BACKING_FEE_POLICY: u8 = 27;
 const S_TAG_ADMIN_UPDATE_TRADE_FEE_POLICY: u8 = 28;
+const S_TAG_PROPOSE_FEE_CHANGE: u8 = 39;
+const S_TAG_COMMIT_FEE_CHANGE: u8 = 40;
+const S_TAG_CANCEL_FEE_CHANGE: u8 = 41;
+const S_TAG_SET_FEE_POLICY_BOUNDS: u8 = 42;
 
 fn stake_so() -> PathBuf {
     let mut p = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -265,6 +276,11 @@ const WRAPPER_ERR_UNAUTHORIZED: u32 = 8;
 // Stake `StakeError` (src/error.rs): Unauthorized = 2, InvalidPda = 10.
 const STAKE_ERR_UNAUTHORIZED: u32 = 2;
 const STAKE_ERR_INVALID_PDA: u32 = 10;
+// TimelockNotElapsed = 26, FeeChangeRequiresTimelock = 35, ProposalNotPending = 36.
+const STAKE_ERR_TIMELOCK_NOT_ELAPSED: u32 = 26;
+const STAKE_ERR_FEE_CHANGE_REQUIRES_TIMELOCK: u32 = 35;
+const STAKE_ERR_PROPOSAL_NOT_PENDING: u32 = 36;
+const STAKE_ERR_FEE_POLICY_BOUND_VIOLATED: u32 = 37;
 
 #[track_caller]
 fn assert_custom_err(err: &TransactionError, expected: u32, ctx: &str) {
@@ -435,7 +451,7 @@ fn direct_update_asset_authority_ix(
 
 // ── stake proxy instruction builders ────────────────────────────────────────
 
-/// GROUP A account shape: [admin(signer), pool_pda, slab(w), percolator].
+/// GROUP A account shape: [admin(signer), pool_pda(w), slab(w), percolator].
 fn group_a_proxy_ix(
     stake_id: Pubkey,
     admin: Pubkey,
@@ -448,7 +464,7 @@ fn group_a_proxy_ix(
         program_id: stake_id,
         accounts: vec![
             AccountMeta::new_readonly(admin, true),
-            AccountMeta::new_readonly(pool_pda, false),
+            AccountMeta::new(pool_pda, false),
             AccountMeta::new(slab, false),
             AccountMeta::new_readonly(wrapper_id, false),
         ],
@@ -456,7 +472,7 @@ fn group_a_proxy_ix(
     }
 }
 
-/// GROUP B account shape: [admin(signer), pool_pda, vault_auth, slab(w), percolator].
+/// GROUP B account shape: [admin(signer), pool_pda(w), vault_auth, slab(w), percolator].
 #[allow(clippy::too_many_arguments)]
 fn group_b_proxy_ix(
     stake_id: Pubkey,
@@ -471,7 +487,7 @@ fn group_b_proxy_ix(
         program_id: stake_id,
         accounts: vec![
             AccountMeta::new_readonly(admin, true),
-            AccountMeta::new_readonly(pool_pda, false),
+            AccountMeta::new(pool_pda, false),
             AccountMeta::new_readonly(vault_auth, false),
             AccountMeta::new(slab, false),
             AccountMeta::new_readonly(wrapper_id, false),
@@ -512,6 +528,93 @@ fn encode_proxy_trade_fee(trade_fee_base_bps: u64) -> Vec<u8> {
     d
 }
 
+// ── fee-change timelock (stake tags 39/40/41) ───────────────────────────────
+//
+// Tags 25..28 are rejected when sent directly; they execute only as the replay
+// of a proposal once TIMELOCK_SLOTS have passed. Every proxy call in this file
+// therefore goes propose -> warp -> commit, with the proxy's own account list
+// appended to the commit unchanged.
+
+static NEXT_PROPOSAL_ID: AtomicU64 = AtomicU64::new(1);
+
+fn propose_fee_change_ix(proxy: &Instruction, proposal_id: u64) -> (Instruction, Pubkey) {
+    let admin = proxy.accounts[0].pubkey;
+    let pool_pda = proxy.accounts[1].pubkey;
+    let (proposal, _) = derive_proposal_pda(&proxy.program_id, &pool_pda, proposal_id);
+    let mut data = vec![S_TAG_PROPOSE_FEE_CHANGE];
+    data.extend_from_slice(&proposal_id.to_le_bytes());
+    data.extend_from_slice(&proxy.data);
+    let ix = Instruction {
+        program_id: proxy.program_id,
+        accounts: vec![
+            AccountMeta::new(admin, true),
+            AccountMeta::new_readonly(pool_pda, false),
+            AccountMeta::new(proposal, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ],
+        data,
+    };
+    (ix, proposal)
+}
+
+/// Stake tag 42 with only `max_trade_fee_base_bps` bounded.
+fn set_max_trade_fee_ix(
+    stake_id: Pubkey,
+    admin: Pubkey,
+    pool_pda: Pubkey,
+    max: u64,
+) -> Instruction {
+    let mut data = vec![S_TAG_SET_FEE_POLICY_BOUNDS];
+    data.extend_from_slice(&0u16.to_le_bytes()); // min_lp_share_bps
+    data.extend_from_slice(&max.to_le_bytes()); // max_trade_fee_base_bps
+    data.extend_from_slice(&u16::MAX.to_le_bytes()); // max_change_delta_bps
+    data.extend_from_slice(&0u64.to_le_bytes()); // min_change_interval_slots
+    data.extend_from_slice(&u16::MAX.to_le_bytes()); // max_backing_fee_bps
+    Instruction {
+        program_id: stake_id,
+        accounts: vec![
+            AccountMeta::new_readonly(admin, true),
+            AccountMeta::new(pool_pda, false),
+        ],
+        data,
+    }
+}
+
+fn commit_fee_change_ix(proxy: &Instruction, proposal: Pubkey) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(proposal, false),
+        AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+    ];
+    accounts.extend(proxy.accounts.iter().cloned());
+    Instruction {
+        program_id: proxy.program_id,
+        accounts,
+        data: vec![S_TAG_COMMIT_FEE_CHANGE],
+    }
+}
+
+fn warp_past_timelock(svm: &mut LiteSVM) {
+    let now = svm.get_sysvar::<Clock>().slot;
+    svm.warp_to_slot(now + TIMELOCK_SLOTS);
+}
+
+/// Propose `proxy` as `admin`, wait out the timelock, then commit it. Returns
+/// the first failure, so authority and binding errors surface unchanged
+/// whether the proposal or the replay refuses them.
+fn send_timelocked(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    admin: &Keypair,
+    proxy: Instruction,
+) -> Result<(), TransactionError> {
+    let proposal_id = NEXT_PROPOSAL_ID.fetch_add(1, Ordering::Relaxed);
+    let (propose, proposal) = propose_fee_change_ix(&proxy, proposal_id);
+    send(svm, payer, &[admin], propose)?;
+    warp_past_timelock(svm);
+    send(svm, payer, &[admin], commit_fee_change_ix(&proxy, proposal))
+}
+
 // ── stake InitPool / BindInsuranceAuthority ─────────────────────────────────
 
 struct Staked {
@@ -521,10 +624,11 @@ struct Staked {
 }
 
 fn encode_init_pool(cooldown_slots: u64, deposit_cap: u64) -> Vec<u8> {
-    let mut out = Vec::with_capacity(17);
+    let mut out = Vec::with_capacity(1 + 16 + FeePolicyBounds::LEN);
     out.push(0u8); // tag InitPool
     out.extend_from_slice(&cooldown_slots.to_le_bytes());
     out.extend_from_slice(&deposit_cap.to_le_bytes());
+    out.extend_from_slice(&FeePolicyBounds::UNBOUNDED.pack());
     out
 }
 
@@ -718,10 +822,10 @@ fn tag86_fee_split_direct_dies_after_initpool_and_proxy_restores_it() {
     );
 
     // 5. the proxy succeeds, and the value actually changes on chain.
-    send(
+    send_timelocked(
         &mut e.svm,
         &e.payer,
-        &[&e.admin],
+        &e.admin,
         group_a_proxy_ix(
             e.stake_id,
             e.admin.pubkey(),
@@ -813,10 +917,10 @@ fn tag88_maintenance_fee_direct_dies_after_initpool_and_proxy_writes_full_u128()
     );
 
     // 5. proxy succeeds and stores the FULL u128.
-    send(
+    send_timelocked(
         &mut e.svm,
         &e.payer,
-        &[&e.admin],
+        &e.admin,
         group_a_proxy_ix(
             e.stake_id,
             e.admin.pubkey(),
@@ -937,10 +1041,10 @@ fn tag51_backing_fee_direct_dies_after_bind_and_proxy_restores_it() {
     );
 
     // 5. the proxy succeeds by signing as vault_auth, and the value changes.
-    send(
+    send_timelocked(
         &mut e.svm,
         &e.payer,
-        &[&e.admin],
+        &e.admin,
         group_b_proxy_ix(
             e.stake_id,
             e.admin.pubkey(),
@@ -1036,10 +1140,10 @@ fn tag55_trade_fee_direct_dies_after_bind_and_proxy_restores_it() {
     );
 
     // 5. proxy succeeds.
-    send(
+    send_timelocked(
         &mut e.svm,
         &e.payer,
-        &[&e.admin],
+        &e.admin,
         group_b_proxy_ix(
             e.stake_id,
             e.admin.pubkey(),
@@ -1090,10 +1194,10 @@ fn group_b_proxy_fails_before_bind_because_vault_auth_is_not_yet_the_authority()
     );
     // NOTE: BindInsuranceAuthority deliberately NOT called.
 
-    let err = send(
+    let err = send_timelocked(
         &mut e.svm,
         &e.payer,
-        &[&e.admin],
+        &e.admin,
         group_b_proxy_ix(
             e.stake_id,
             e.admin.pubkey(),
@@ -1154,10 +1258,10 @@ fn proxies_reject_a_non_admin_signer() {
     e.svm.airdrop(&stranger.pubkey(), 100_000_000_000).unwrap();
 
     // Group A (tag 25).
-    let e25 = send(
+    let e25 = send_timelocked(
         &mut e.svm,
         &e.payer,
-        &[&stranger],
+        &stranger,
         group_a_proxy_ix(
             e.stake_id,
             stranger.pubkey(),
@@ -1171,10 +1275,10 @@ fn proxies_reject_a_non_admin_signer() {
     assert_custom_err(&e25, STAKE_ERR_UNAUTHORIZED, "tag 25 non-admin");
 
     // Group A (tag 26).
-    let e26 = send(
+    let e26 = send_timelocked(
         &mut e.svm,
         &e.payer,
-        &[&stranger],
+        &stranger,
         group_a_proxy_ix(
             e.stake_id,
             stranger.pubkey(),
@@ -1188,10 +1292,10 @@ fn proxies_reject_a_non_admin_signer() {
     assert_custom_err(&e26, STAKE_ERR_UNAUTHORIZED, "tag 26 non-admin");
 
     // Group B (tag 27) — the fee-split-critical one.
-    let e27 = send(
+    let e27 = send_timelocked(
         &mut e.svm,
         &e.payer,
-        &[&stranger],
+        &stranger,
         group_b_proxy_ix(
             e.stake_id,
             stranger.pubkey(),
@@ -1206,10 +1310,10 @@ fn proxies_reject_a_non_admin_signer() {
     assert_custom_err(&e27, STAKE_ERR_UNAUTHORIZED, "tag 27 non-admin");
 
     // Group B (tag 28).
-    let e28 = send(
+    let e28 = send_timelocked(
         &mut e.svm,
         &e.payer,
-        &[&stranger],
+        &stranger,
         group_b_proxy_ix(
             e.stake_id,
             stranger.pubkey(),
@@ -1283,10 +1387,10 @@ fn proxy_rejects_a_slab_that_is_not_the_pools_own_market() {
         &e.payer,
     );
     assert_default_split(&e.svm, &market_b);
-    let err = send(
+    let err = send_timelocked(
         &mut e.svm,
         &e.payer,
-        &[&e.admin],
+        &e.admin,
         group_a_proxy_ix(
             e.stake_id,
             e.admin.pubkey(),
@@ -1314,6 +1418,168 @@ fn proxy_rejects_a_slab_that_is_not_the_pools_own_market() {
     assert_default_split(&e.svm, &market_b);
 }
 
+/// The timelock itself: a direct proxy call is refused, a proposal cannot be
+/// committed inside its window, and a cancelled proposal can never be
+/// committed. Throughout, the proposed call sits on chain for LPs to read and
+/// the wrapper value does not move.
+#[test]
+fn fee_proxy_requires_timelock_and_cancel_is_final() {
+    let Some(mut e) = env() else { return };
+
+    let (market, mint) = build_live_market_v17(
+        &mut e.svm,
+        e.wrapper_id,
+        e.token_program,
+        &e.admin,
+        &e.payer,
+    );
+    let s = run_init_pool(
+        &mut e.svm,
+        e.wrapper_id,
+        e.stake_id,
+        e.token_program,
+        &e.admin,
+        &e.payer,
+        market,
+        mint,
+    );
+    let proxy = group_a_proxy_ix(
+        e.stake_id,
+        e.admin.pubkey(),
+        s.pool_pda,
+        market,
+        e.wrapper_id,
+        encode_proxy_fee_split(POST_SPLIT),
+    );
+
+    // Direct: refused by the stake program before any CPI.
+    let err = send(&mut e.svm, &e.payer, &[&e.admin], proxy.clone())
+        .expect_err("a direct tag 25 must be refused");
+    assert_custom_err(
+        &err,
+        STAKE_ERR_FEE_CHANGE_REQUIRES_TIMELOCK,
+        "direct fee-policy proxy",
+    );
+
+    // Proposed: readable on chain, but not committable inside the window.
+    let (propose, proposal) = propose_fee_change_ix(&proxy, 1_001);
+    send(&mut e.svm, &e.payer, &[&e.admin], propose).expect("ProposeFeeChange");
+    let record = e.svm.get_account(&proposal).unwrap().data;
+    assert_eq!(record[2], 1, "status must be PENDING");
+    assert_eq!(
+        &record[56..56 + record[3] as usize],
+        &proxy.data[..],
+        "the proposal must hold the exact proxied instruction data"
+    );
+    let err = send(
+        &mut e.svm,
+        &e.payer,
+        &[&e.admin],
+        commit_fee_change_ix(&proxy, proposal),
+    )
+    .expect_err("commit inside the timelock window must fail");
+    assert_custom_err(&err, STAKE_ERR_TIMELOCK_NOT_ELAPSED, "early commit");
+
+    // Cancelled: stays cancelled, even after the window would have elapsed.
+    send(
+        &mut e.svm,
+        &e.payer,
+        &[&e.admin],
+        Instruction {
+            program_id: e.stake_id,
+            accounts: vec![
+                AccountMeta::new_readonly(e.admin.pubkey(), true),
+                AccountMeta::new_readonly(s.pool_pda, false),
+                AccountMeta::new(proposal, false),
+            ],
+            data: vec![S_TAG_CANCEL_FEE_CHANGE],
+        },
+    )
+    .expect("CancelFeeChange");
+    assert_eq!(e.svm.get_account(&proposal).unwrap().data[2], 3);
+    warp_past_timelock(&mut e.svm);
+    // The identical commit bytes were already sent once; use a fresh blockhash.
+    e.svm.expire_blockhash();
+    let err = send(
+        &mut e.svm,
+        &e.payer,
+        &[&e.admin],
+        commit_fee_change_ix(&proxy, proposal),
+    )
+    .expect_err("a cancelled proposal must never commit");
+    assert_custom_err(&err, STAKE_ERR_PROPOSAL_NOT_PENDING, "commit after cancel");
+
+    assert_default_split(&e.svm, &market);
+}
+
+/// The pool's own fee-policy bounds: a committed proxy outside them is refused
+/// by the stake program, the admin can tighten them at once, and loosening
+/// them takes the same timelock as the fee change itself.
+#[test]
+fn fee_policy_bounds_gate_committed_proxies() {
+    let Some(mut e) = env() else { return };
+
+    let (market, mint) = build_live_market_v17(
+        &mut e.svm,
+        e.wrapper_id,
+        e.token_program,
+        &e.admin,
+        &e.payer,
+    );
+    let s = run_init_pool(
+        &mut e.svm,
+        e.wrapper_id,
+        e.stake_id,
+        e.token_program,
+        &e.admin,
+        &e.payer,
+        market,
+        mint,
+    );
+    run_bind_insurance_authority(&mut e.svm, e.wrapper_id, e.stake_id, &e.admin, &e.payer, &s);
+    let trade_fee = group_b_proxy_ix(
+        e.stake_id,
+        e.admin.pubkey(),
+        s.pool_pda,
+        s.vault_auth,
+        market,
+        e.wrapper_id,
+        encode_proxy_trade_fee(42),
+    );
+
+    // Tightening is immediate.
+    send(
+        &mut e.svm,
+        &e.payer,
+        &[&e.admin],
+        set_max_trade_fee_ix(e.stake_id, e.admin.pubkey(), s.pool_pda, 20),
+    )
+    .expect("tightening the bounds needs no timelock");
+    let err = send_timelocked(&mut e.svm, &e.payer, &e.admin, trade_fee.clone())
+        .expect_err("a trade fee above the pool's bound must not commit");
+    assert_custom_err(
+        &err,
+        STAKE_ERR_FEE_POLICY_BOUND_VIOLATED,
+        "trade fee above max_trade_fee_base_bps",
+    );
+    assert_eq!(read_u64_at(&e.svm, &market, OFF_TRADE_FEE_BASE_BPS), 0);
+
+    // Loosening directly is refused; through the timelock it goes in.
+    let loosen = set_max_trade_fee_ix(e.stake_id, e.admin.pubkey(), s.pool_pda, 100);
+    let err = send(&mut e.svm, &e.payer, &[&e.admin], loosen.clone())
+        .expect_err("loosening the bounds directly must fail");
+    assert_custom_err(
+        &err,
+        STAKE_ERR_FEE_CHANGE_REQUIRES_TIMELOCK,
+        "direct loosening",
+    );
+    send_timelocked(&mut e.svm, &e.payer, &e.admin, loosen)
+        .expect("a timelocked SetFeePolicyBounds may loosen");
+    send_timelocked(&mut e.svm, &e.payer, &e.admin, trade_fee)
+        .expect("the same trade fee commits under the loosened bound");
+    assert_eq!(read_u64_at(&e.svm, &market, OFF_TRADE_FEE_BASE_BPS), 42);
+}
+
 // ════════════════════════════════════════════════════════════════════════════
 // WRAPPER TAG 69 — deliberately NOT proxied; this pins the reasoning
 // ════════════════════════════════════════════════════════════════════════════
@@ -1429,9 +1695,10 @@ fn tag69_restart_asset_oracle_is_not_proxyable_after_asset_admin_burn() {
     );
     eprintln!("post-burn asset_admin-gated call rejected with: {err:?}");
 
-    // And pin that this program exposes no tag-69 proxy: 29 is unallocated.
-    // If someone adds one, this assertion is the prompt to revisit the analysis
-    // in instruction.rs's module doc.
+    // And pin that this program exposes no tag-69 proxy: a tag past the end of
+    // the allocated range is rejected. (29 used to be probed here; it is now
+    // DepositFor.) If someone adds a proxy, this assertion is the prompt to
+    // revisit the analysis in instruction.rs's module doc.
     let bogus = Instruction {
         program_id: e.stake_id,
         accounts: vec![
@@ -1440,8 +1707,8 @@ fn tag69_restart_asset_oracle_is_not_proxyable_after_asset_admin_burn() {
             AccountMeta::new(market, false),
             AccountMeta::new_readonly(e.wrapper_id, false),
         ],
-        data: vec![29u8],
+        data: vec![255u8],
     };
     send(&mut e.svm, &e.payer, &[&e.admin], bogus)
-        .expect_err("stake tag 29 must be unallocated — no tag-69 proxy exists");
+        .expect_err("stake tag 255 must be unallocated — no tag-69 proxy exists");
 }
@@ -2,7 +2,9 @@
 
 use bytemuck::Zeroable;
 use percolator_stake::instruction::StakeInstruction;
-use percolator_stake::state::{StakeDeposit, StakePool, STAKE_DEPOSIT_SIZE, STAKE_POOL_SIZE};
+use percolator_stake::state::{
+    FeePolicyBounds, StakeDeposit, StakePool, STAKE_DEPOSIT_SIZE, STAKE_POOL_SIZE,
+};
 
 // ═══════════════════════════════════════════════════════════════
 // Helper: create a zeroed StakePool with basic fields set
@@ -415,12 +417,14 @@ fn test_decode_init_pool() {
     let mut data = vec![0u8]; // tag = 0
     data.extend_from_slice(&100u64.to_le_bytes()); // cooldown_slots
     data.extend_from_slice(&5_000_000u64.to_le_bytes()); // deposit_cap
+    data.extend_from_slice(&FeePolicyBounds::UNBOUNDED.pack());
 
     let ix = StakeInstruction::unpack(&data).unwrap();
     match ix {
         StakeInstruction::InitPool {
             cooldown_slots,
             deposit_cap,
+            ..
         } => {
             assert_eq!(cooldown_slots, 100);
             assert_eq!(deposit_cap, 5_000_000);
@@ -448,7 +452,13 @@ fn test_decode_withdraw() {
 
     let ix = StakeInstruction::unpack(&data).unwrap();
     match ix {
-        StakeInstruction::Withdraw { lp_amount } => assert_eq!(lp_amount, 500_000),
+        StakeInstruction::Withdraw {
+            lp_amount,
+            close_if_empty,
+        } => {
+            assert_eq!(lp_amount, 500_000);
+            assert!(!close_if_empty);
+        }
         _ => panic!("Expected Withdraw"),
     }
 }
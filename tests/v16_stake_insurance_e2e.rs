@@ -240,6 +240,7 @@ impl Env {
         pool.vault = stake_vault.to_bytes();
         pool.total_deposited = FLUSH_AMOUNT; // available for flush = FLUSH_AMOUNT
         pool.percolator_program = wrapper_id.to_bytes();
+        pool.wrapper_abi_version = percolator_stake::cpi::WRAPPER_ABI_V17;
         pool.pool_mode = 0; // insurance LP (flush only valid here)
         pool.set_discriminator(); // writes discriminator + CURRENT_VERSION (2)
         let mut pool_bytes = vec![0u8; STAKE_POOL_SIZE];
@@ -736,6 +737,7 @@ fn add_stake_pool(
     pool.vault = stake_vault.to_bytes();
     pool.total_deposited = amount;
     pool.percolator_program = wrapper_id.to_bytes();
+    pool.wrapper_abi_version = percolator_stake::cpi::WRAPPER_ABI_V17;
     pool.pool_mode = 0;
     pool.set_discriminator();
     let mut bytes = vec![0u8; STAKE_POOL_SIZE];
@@ -29,7 +29,7 @@
 
 use bytemuck::Zeroable;
 use litesvm::LiteSVM;
-use percolator_stake::error::StakeError;
+use percolator_stake::error::{describe_simulation_failure, StakeError};
 use percolator_stake::state::{
     derive_pool_pda, derive_vault_authority, StakePool, STAKE_POOL_SIZE,
 };
@@ -214,13 +214,23 @@ fn send(
         solana_sdk::compute_budget::ComputeBudgetInstruction::request_heap_frame(128 * 1024);
     let cb_cu =
         solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(1_400_000);
+    // Keep the target and tag so a failed stake CPI can be explained below.
+    let (program_id, tag) = (ix.program_id, ix.data.first().copied().unwrap_or(u8::MAX));
     let tx = Transaction::new_signed_with_payer(
         &[cb_heap, cb_cu, ix],
         Some(&payer.pubkey()),
         &all,
         svm.latest_blockhash(),
     );
-    svm.send_transaction(tx).map(|_| ()).map_err(|e| e.err)
+    svm.send_transaction(tx).map(|_| ()).map_err(|e| {
+        let stake_id = Pubkey::from_str(STAKE_ID).unwrap();
+        if program_id == stake_id {
+            if let Some(why) = describe_simulation_failure(&stake_id, tag, &e.meta.logs) {
+                eprintln!("stake tag {tag} failed: {why}");
+            }
+        }
+        e.err
+    })
 }
 
 // ── Market + stake pool setup ─────────────────────────────────────────────────
@@ -315,6 +325,7 @@ fn add_stake_pool(
     pool.vault = stake_vault.to_bytes();
     pool.total_deposited = amount;
     pool.percolator_program = wrapper_id.to_bytes();
+    pool.wrapper_abi_version = percolator_stake::cpi::WRAPPER_ABI_V17;
     pool.pool_mode = 0;
     pool.set_discriminator();
 